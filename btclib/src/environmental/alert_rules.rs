@@ -0,0 +1,683 @@
+//! Declarative environmental alert rules
+//!
+//! Rules are written in TOML and evaluated by an `AlertEngine` against
+//! samples of the emissions and renewable metrics in `alerting::MetricType`.
+//! A rule fires on a fixed threshold, on the change of a metric over a time
+//! window, or on a sample that is anomalous relative to recent history.
+//!
+//! While a rule keeps firing its alert stays open: repeats within the rule's
+//! deduplication window are suppressed, and an escalation raises the alert's
+//! severity after enough repeats or enough time. When the condition clears
+//! the alert is resolved. Every notification is delivered to the
+//! `NotificationSink`s named by the rule, or to all sinks if it names none.
+//!
+//! ```toml
+//! [[sinks]]
+//! kind = "webhook"
+//! name = "ops"
+//! url = "https://alerts.example.com/hook"
+//!
+//! [[rules]]
+//! id = "emissions-high"
+//! name = "Daily emissions high"
+//! metric = "CarbonEmissions"
+//! severity = "Medium"
+//! dedup_seconds = 3600
+//! condition = { kind = "threshold", operator = "GreaterThan", value = 50.0 }
+//! escalation = { after_repeats = 6, severity = "Critical" }
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::environmental::alert_sinks::{NotificationSink, SinkConfig};
+use crate::environmental::alerting::{
+    Alert, AlertSeverity, AlertStatus, AlertingError, ComparisonOperator, MetricType,
+};
+use crate::environmental::dashboard::EnvironmentalMetrics;
+
+/// Samples kept per metric for rate and anomaly conditions
+const DEFAULT_HISTORY_LIMIT: usize = 1024;
+
+/// Condition under which a rule fires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleCondition {
+    /// The sample compared against a fixed value
+    Threshold {
+        operator: ComparisonOperator,
+        value: f64,
+    },
+    
+    /// The change since the oldest sample within the window compared
+    /// against `change`
+    RateOfChange {
+        operator: ComparisonOperator,
+        change: f64,
+        window_seconds: u64,
+    },
+    
+    /// The sample deviates from the mean of the previous `window` samples
+    /// by at least `z_score` standard deviations
+    Anomaly {
+        z_score: f64,
+        window: usize,
+        #[serde(default = "default_min_samples")]
+        min_samples: usize,
+    },
+}
+
+fn default_min_samples() -> usize {
+    5
+}
+
+fn default_dedup_seconds() -> u64 {
+    3600
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl RuleCondition {
+    /// Check the condition's parameters
+    pub fn validate(&self) -> Result<(), AlertingError> {
+        let valid = match self {
+            RuleCondition::Threshold { value, .. } => value.is_finite(),
+            RuleCondition::RateOfChange { change, window_seconds, .. } => {
+                change.is_finite() && *window_seconds > 0
+            }
+            RuleCondition::Anomaly { z_score, window, min_samples } => {
+                z_score.is_finite() && *z_score > 0.0 && *min_samples >= 2 && *window >= *min_samples
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(AlertingError::InvalidConfiguration(format!("Invalid condition: {:?}", self)))
+        }
+    }
+    
+    /// Short description used in alert messages
+    pub fn describe(&self) -> String {
+        match self {
+            RuleCondition::Threshold { operator, value } => {
+                format!("{} {}", operator.symbol(), value)
+            }
+            RuleCondition::RateOfChange { operator, change, window_seconds } => {
+                format!("change {} {} over {}s", operator.symbol(), change, window_seconds)
+            }
+            RuleCondition::Anomaly { z_score, window, .. } => {
+                format!("|z| >= {} over {} samples", z_score, window)
+            }
+        }
+    }
+}
+
+/// Raise an open alert's severity once it has persisted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Escalation {
+    /// Escalate once the rule has fired this many times
+    #[serde(default)]
+    pub after_repeats: Option<u32>,
+    
+    /// Escalate once the alert has been open this long
+    #[serde(default)]
+    pub after_seconds: Option<u64>,
+    
+    /// Severity after escalation
+    pub severity: AlertSeverity,
+}
+
+/// A rule in a `RuleSet`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleDefinition {
+    /// Unique rule ID
+    pub id: String,
+    
+    /// Rule name
+    pub name: String,
+    
+    /// Description of the rule
+    #[serde(default)]
+    pub description: String,
+    
+    /// Metric the rule watches
+    pub metric: MetricType,
+    
+    /// Severity of a new alert
+    pub severity: AlertSeverity,
+    
+    /// When the rule fires
+    pub condition: RuleCondition,
+    
+    /// Window in which repeats of an open alert are not re-notified
+    #[serde(default = "default_dedup_seconds")]
+    pub dedup_seconds: u64,
+    
+    /// Optional escalation of an open alert
+    #[serde(default)]
+    pub escalation: Option<Escalation>,
+    
+    /// Names of the sinks to notify; empty notifies every sink
+    #[serde(default)]
+    pub sinks: Vec<String>,
+    
+    /// Whether the rule is evaluated
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// Rules and sinks loaded from TOML
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    /// Notification sinks
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
+    
+    /// Alert rules
+    #[serde(default)]
+    pub rules: Vec<RuleDefinition>,
+}
+
+impl RuleSet {
+    /// Parse a rule set from TOML
+    pub fn from_toml(text: &str) -> Result<Self, AlertingError> {
+        let set: RuleSet = toml::from_str(text)
+            .map_err(|e| AlertingError::InvalidConfiguration(e.to_string()))?;
+        set.validate()?;
+        Ok(set)
+    }
+    
+    /// Check rules are valid and every sink they name is configured
+    pub fn validate(&self) -> Result<(), AlertingError> {
+        validate_rules(&self.rules)?;
+        for rule in &self.rules {
+            for sink in &rule.sinks {
+                if !self.sinks.iter().any(|s| s.name() == sink) {
+                    return Err(AlertingError::InvalidConfiguration(
+                        format!("Rule {} names unknown sink {}", rule.id, sink)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check rule IDs are unique and conditions and escalations are valid
+fn validate_rules(rules: &[RuleDefinition]) -> Result<(), AlertingError> {
+    let mut ids = HashSet::new();
+    for rule in rules {
+        if !ids.insert(rule.id.as_str()) {
+            return Err(AlertingError::InvalidConfiguration(format!("Duplicate rule ID: {}", rule.id)));
+        }
+        rule.condition.validate()?;
+        if let Some(escalation) = &rule.escalation {
+            if escalation.after_repeats.is_none() && escalation.after_seconds.is_none() {
+                return Err(AlertingError::InvalidConfiguration(
+                    format!("Escalation of rule {} has no trigger", rule.id)
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What happened to an alert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertEvent {
+    /// A rule started firing
+    Triggered,
+    
+    /// A rule is still firing after its deduplication window
+    Repeated,
+    
+    /// An open alert's severity was raised
+    Escalated,
+    
+    /// A rule stopped firing
+    Resolved,
+}
+
+/// Notification delivered to sinks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertNotification {
+    /// What happened
+    pub event: AlertEvent,
+    
+    /// Current severity of the alert
+    pub severity: AlertSeverity,
+    
+    /// Metric that was evaluated
+    pub metric: MetricType,
+    
+    /// Name of the rule
+    pub rule_name: String,
+    
+    /// Times the rule has fired since the alert opened
+    pub occurrences: u32,
+    
+    /// The alert
+    pub alert: Alert,
+}
+
+/// State of an alert that has not resolved
+#[derive(Debug, Clone)]
+struct OpenAlert {
+    alert: Alert,
+    severity: AlertSeverity,
+    occurrences: u32,
+    escalated: bool,
+    last_notified: DateTime<Utc>,
+}
+
+/// Evaluates rules against metric samples and notifies sinks
+pub struct AlertEngine {
+    /// Alert rules
+    rules: Vec<RuleDefinition>,
+    
+    /// Named sinks
+    sinks: Vec<Arc<dyn NotificationSink>>,
+    
+    /// Recent samples by metric, oldest first
+    history: HashMap<MetricType, VecDeque<(DateTime<Utc>, f64)>>,
+    
+    /// Open alerts by rule ID
+    open: HashMap<String, OpenAlert>,
+    
+    /// Notifications suppressed by deduplication
+    suppressed: u64,
+}
+
+impl AlertEngine {
+    /// Create an engine with no sinks
+    pub fn new(rules: Vec<RuleDefinition>) -> Result<Self, AlertingError> {
+        validate_rules(&rules)?;
+        Ok(Self {
+            rules,
+            sinks: Vec::new(),
+            history: HashMap::new(),
+            open: HashMap::new(),
+            suppressed: 0,
+        })
+    }
+    
+    /// Create an engine from a rule set, building its configured sinks
+    pub fn from_rule_set(set: RuleSet) -> Result<Self, AlertingError> {
+        set.validate()?;
+        let mut engine = Self::new(set.rules)?;
+        for config in &set.sinks {
+            engine.add_sink(config.build()?)?;
+        }
+        Ok(engine)
+    }
+    
+    /// Create an engine from TOML
+    pub fn from_toml(text: &str) -> Result<Self, AlertingError> {
+        Self::from_rule_set(RuleSet::from_toml(text)?)
+    }
+    
+    /// Add a sink; names must be unique
+    pub fn add_sink(&mut self, sink: Arc<dyn NotificationSink>) -> Result<(), AlertingError> {
+        if self.sinks.iter().any(|s| s.name() == sink.name()) {
+            return Err(AlertingError::InvalidConfiguration(format!("Duplicate sink: {}", sink.name())));
+        }
+        self.sinks.push(sink);
+        Ok(())
+    }
+    
+    /// Alert rules
+    pub fn rules(&self) -> &[RuleDefinition] {
+        &self.rules
+    }
+    
+    /// Alerts that have not resolved
+    pub fn open_alerts(&self) -> Vec<&Alert> {
+        self.open.values().map(|o| &o.alert).collect()
+    }
+    
+    /// Number of notifications suppressed by deduplication
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+    
+    /// Evaluate a sample and deliver the resulting notifications
+    ///
+    /// Every sink is attempted; delivery failures are reported together
+    /// after the sample has been recorded.
+    pub async fn observe(
+        &mut self,
+        metric: MetricType,
+        value: f64,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<AlertNotification>, AlertingError> {
+        let notifications = self.evaluate(metric, value, at);
+        let mut failures = Vec::new();
+        for notification in &notifications {
+            let targets = self.rules.iter()
+                .find(|r| r.id == notification.alert.rule_id)
+                .map(|r| r.sinks.clone())
+                .unwrap_or_default();
+            for sink in &self.sinks {
+                if !targets.is_empty() && !targets.iter().any(|t| t == sink.name()) {
+                    continue;
+                }
+                if let Err(e) = sink.send(notification).await {
+                    failures.push(format!("{}: {}", sink.name(), e));
+                }
+            }
+        }
+        if failures.is_empty() {
+            Ok(notifications)
+        } else {
+            Err(AlertingError::NotificationError(failures.join("; ")))
+        }
+    }
+    
+    /// Evaluate the metrics of a dashboard period
+    pub async fn observe_metrics(
+        &mut self,
+        metrics: &EnvironmentalMetrics,
+    ) -> Result<Vec<AlertNotification>, AlertingError> {
+        let mut samples = vec![
+            (MetricType::EnergyConsumption, metrics.energy_consumption),
+            (MetricType::CarbonEmissions, metrics.total_emissions),
+            (MetricType::NetCarbonImpact, metrics.net_emissions),
+            (MetricType::CarbonIntensity, metrics.emissions_per_transaction),
+        ];
+        if let Some(renewable) = metrics.renewable_percentage {
+            samples.push((MetricType::RenewablePercentage, renewable));
+        }
+        if let Some(coverage) = metrics.rec_coverage_percentage {
+            samples.push((MetricType::RECCoverage, coverage));
+        }
+        if metrics.total_emissions > 0.0 {
+            samples.push((MetricType::OffsetPercentage, metrics.total_assets / metrics.total_emissions * 100.0));
+        }
+        
+        let mut notifications = Vec::new();
+        for (metric, value) in samples {
+            notifications.extend(self.observe(metric, value, metrics.calculation_time).await?);
+        }
+        Ok(notifications)
+    }
+    
+    /// Evaluate a sample against every rule for its metric without
+    /// delivering notifications
+    pub fn evaluate(&mut self, metric: MetricType, value: f64, at: DateTime<Utc>) -> Vec<AlertNotification> {
+        let mut notifications = Vec::new();
+        if !value.is_finite() {
+            return notifications;
+        }
+        
+        let empty = VecDeque::new();
+        let history = self.history.get(&metric).unwrap_or(&empty);
+        let firing: Vec<(RuleDefinition, bool)> = self.rules.iter()
+            .filter(|r| r.enabled && r.metric == metric)
+            .map(|r| (r.clone(), condition_met(&r.condition, history, value, at)))
+            .collect();
+        
+        for (rule, met) in firing {
+            let notification = if met {
+                self.fire(&rule, value, at)
+            } else {
+                self.resolve(&rule, value, at)
+            };
+            notifications.extend(notification);
+        }
+        
+        let samples = self.history.entry(metric).or_default();
+        samples.push_back((at, value));
+        while samples.len() > DEFAULT_HISTORY_LIMIT {
+            samples.pop_front();
+        }
+        
+        notifications
+    }
+    
+    /// Open, repeat or escalate the alert of a firing rule
+    fn fire(&mut self, rule: &RuleDefinition, value: f64, at: DateTime<Utc>) -> Option<AlertNotification> {
+        let message = format!("{}: {} is {} ({})",
+            rule.name,
+            rule.metric.display_name(),
+            value,
+            rule.condition.describe()
+        );
+        
+        let Some(open) = self.open.get_mut(&rule.id) else {
+            let mut context = HashMap::new();
+            context.insert("metric".to_string(), rule.metric.display_name().to_string());
+            context.insert("condition".to_string(), rule.condition.describe());
+            let alert = Alert {
+                id: format!("alert_{:x}", rand::random::<u64>()),
+                rule_id: rule.id.clone(),
+                message,
+                current_value: value,
+                threshold_value: threshold_of(&rule.condition),
+                timestamp: at,
+                status: AlertStatus::Active,
+                resolved_by: None,
+                resolved_at: None,
+                context,
+            };
+            let open = OpenAlert {
+                alert,
+                severity: rule.severity,
+                occurrences: 1,
+                escalated: false,
+                last_notified: at,
+            };
+            let notification = notify(AlertEvent::Triggered, rule, &open);
+            self.open.insert(rule.id.clone(), open);
+            return Some(notification);
+        };
+        
+        open.occurrences += 1;
+        open.alert.current_value = value;
+        open.alert.message = message;
+        
+        if let Some(escalation) = rule.escalation.as_ref().filter(|_| !open.escalated) {
+            let by_repeats = escalation.after_repeats.is_some_and(|n| open.occurrences >= n);
+            let by_age = escalation.after_seconds
+                .is_some_and(|secs| at - open.alert.timestamp >= Duration::seconds(secs as i64));
+            if by_repeats || by_age {
+                open.escalated = true;
+                open.severity = escalation.severity;
+                open.last_notified = at;
+                return Some(notify(AlertEvent::Escalated, rule, open));
+            }
+        }
+        
+        if at - open.last_notified >= Duration::seconds(rule.dedup_seconds as i64) {
+            open.last_notified = at;
+            Some(notify(AlertEvent::Repeated, rule, open))
+        } else {
+            self.suppressed += 1;
+            None
+        }
+    }
+    
+    /// Resolve the alert of a rule that stopped firing
+    fn resolve(&mut self, rule: &RuleDefinition, value: f64, at: DateTime<Utc>) -> Option<AlertNotification> {
+        let mut open = self.open.remove(&rule.id)?;
+        open.alert.status = AlertStatus::Resolved;
+        open.alert.current_value = value;
+        open.alert.resolved_by = Some("System".to_string());
+        open.alert.resolved_at = Some(at);
+        Some(notify(AlertEvent::Resolved, rule, &open))
+    }
+}
+
+fn notify(event: AlertEvent, rule: &RuleDefinition, open: &OpenAlert) -> AlertNotification {
+    AlertNotification {
+        event,
+        severity: open.severity,
+        metric: rule.metric,
+        rule_name: rule.name.clone(),
+        occurrences: open.occurrences,
+        alert: open.alert.clone(),
+    }
+}
+
+fn threshold_of(condition: &RuleCondition) -> f64 {
+    match condition {
+        RuleCondition::Threshold { value, .. } => *value,
+        RuleCondition::RateOfChange { change, .. } => *change,
+        RuleCondition::Anomaly { z_score, .. } => *z_score,
+    }
+}
+
+/// Whether `value` at `at` meets `condition` given earlier samples
+fn condition_met(
+    condition: &RuleCondition,
+    history: &VecDeque<(DateTime<Utc>, f64)>,
+    value: f64,
+    at: DateTime<Utc>,
+) -> bool {
+    match condition {
+        RuleCondition::Threshold { operator, value: threshold } => operator.compare(value, *threshold),
+        RuleCondition::RateOfChange { operator, change, window_seconds } => {
+            let since = at - Duration::seconds(*window_seconds as i64);
+            match history.iter().find(|(t, _)| *t >= since && *t < at) {
+                Some((_, earliest)) => operator.compare(value - earliest, *change),
+                None => false,
+            }
+        }
+        RuleCondition::Anomaly { z_score, window, min_samples } => {
+            let recent: Vec<f64> = history.iter().rev().take(*window).map(|(_, v)| *v).collect();
+            if recent.len() < *min_samples {
+                return false;
+            }
+            let n = recent.len() as f64;
+            let mean = recent.iter().sum::<f64>() / n;
+            let variance = recent.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+            let std_dev = variance.sqrt();
+            // A flat history has no meaningful spread to measure against
+            if std_dev < f64::EPSILON {
+                return false;
+            }
+            ((value - mean) / std_dev).abs() >= *z_score
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environmental::alert_sinks::ChannelSink;
+    
+    const RULES: &str = r#"
+        [[rules]]
+        id = "emissions-high"
+        name = "Daily emissions high"
+        metric = "CarbonEmissions"
+        severity = "Medium"
+        dedup_seconds = 3600
+        condition = { kind = "threshold", operator = "GreaterThan", value = 50.0 }
+        escalation = { after_repeats = 4, severity = "Critical" }
+        
+        [[rules]]
+        id = "renewable-drop"
+        name = "Renewable share falling"
+        metric = "RenewablePercentage"
+        severity = "High"
+        condition = { kind = "rate_of_change", operator = "LessThanOrEqual", change = -10.0, window_seconds = 7200 }
+        
+        [[rules]]
+        id = "intensity-anomaly"
+        name = "Carbon intensity anomaly"
+        metric = "CarbonIntensity"
+        severity = "Low"
+        condition = { kind = "anomaly", z_score = 3.0, window = 10 }
+    "#;
+    
+    #[tokio::test]
+    async fn test_rules_dedup_escalate_and_resolve() {
+        let mut engine = AlertEngine::from_toml(RULES).unwrap();
+        let (sink, mut rx) = ChannelSink::new("channel");
+        engine.add_sink(Arc::new(sink)).unwrap();
+        let start = Utc::now();
+        let minutes = |m: i64| start + Duration::minutes(m);
+        
+        // Threshold: trigger once, suppress repeats inside the window, then escalate
+        let mut events = Vec::new();
+        for (m, value) in [(0, 60.0), (10, 61.0), (20, 62.0), (30, 63.0), (40, 64.0)] {
+            for n in engine.observe(MetricType::CarbonEmissions, value, minutes(m)).await.unwrap() {
+                events.push((n.event, n.severity));
+            }
+        }
+        assert_eq!(events, vec![
+            (AlertEvent::Triggered, AlertSeverity::Medium),
+            (AlertEvent::Escalated, AlertSeverity::Critical),
+        ]);
+        assert_eq!(engine.suppressed(), 3);
+        assert_eq!(engine.open_alerts().len(), 1);
+        
+        // Past the dedup window the still-open alert is repeated, then resolves
+        let repeated = engine.observe(MetricType::CarbonEmissions, 65.0, minutes(120)).await.unwrap();
+        assert_eq!(repeated[0].event, AlertEvent::Repeated);
+        let resolved = engine.observe(MetricType::CarbonEmissions, 40.0, minutes(130)).await.unwrap();
+        assert_eq!(resolved[0].event, AlertEvent::Resolved);
+        assert_eq!(resolved[0].alert.status, AlertStatus::Resolved);
+        assert!(engine.open_alerts().is_empty());
+        
+        // Every delivered notification reached the channel
+        let mut delivered = Vec::new();
+        while let Ok(n) = rx.try_recv() {
+            delivered.push(n.event);
+        }
+        assert_eq!(delivered, vec![
+            AlertEvent::Triggered, AlertEvent::Escalated, AlertEvent::Repeated, AlertEvent::Resolved,
+        ]);
+        
+        // Rate of change: a 12 point drop inside two hours fires, and resolves once
+        // the window no longer holds an earlier sample
+        assert!(engine.evaluate(MetricType::RenewablePercentage, 80.0, minutes(0)).is_empty());
+        assert!(engine.evaluate(MetricType::RenewablePercentage, 74.0, minutes(60)).is_empty());
+        let drop = engine.evaluate(MetricType::RenewablePercentage, 68.0, minutes(100));
+        assert_eq!(drop[0].event, AlertEvent::Triggered);
+        assert_eq!(engine.evaluate(MetricType::RenewablePercentage, 68.0, minutes(280))[0].event, AlertEvent::Resolved);
+        
+        // Anomaly: noisy but stable history, then a spike
+        for (i, value) in [100.0, 102.0, 98.0, 101.0, 99.0, 100.0].iter().enumerate() {
+            assert!(engine.evaluate(MetricType::CarbonIntensity, *value, minutes(i as i64)).is_empty());
+        }
+        let spike = engine.evaluate(MetricType::CarbonIntensity, 130.0, minutes(10));
+        assert_eq!(spike[0].event, AlertEvent::Triggered);
+        assert_eq!(spike[0].severity, AlertSeverity::Low);
+    }
+    
+    #[test]
+    fn test_rule_set_validation() {
+        let unknown_sink = r#"
+            [[rules]]
+            id = "r"
+            name = "r"
+            metric = "CarbonEmissions"
+            severity = "Low"
+            sinks = ["pager"]
+            condition = { kind = "threshold", operator = "GreaterThan", value = 1.0 }
+        "#;
+        assert!(RuleSet::from_toml(unknown_sink).is_err());
+        
+        let bad_anomaly = r#"
+            [[rules]]
+            id = "r"
+            name = "r"
+            metric = "CarbonEmissions"
+            severity = "Low"
+            condition = { kind = "anomaly", z_score = 3.0, window = 1 }
+        "#;
+        assert!(RuleSet::from_toml(bad_anomaly).is_err());
+        
+        let set = RuleSet::from_toml(RULES).unwrap();
+        assert_eq!(set.rules.len(), 3);
+        assert_eq!(set.rules[2].condition, RuleCondition::Anomaly { z_score: 3.0, window: 10, min_samples: 5 });
+        assert!(set.rules.iter().all(|r| r.enabled));
+    }
+}
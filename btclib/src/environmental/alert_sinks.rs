@@ -0,0 +1,376 @@
+//! Notification sinks for environmental alerts
+//!
+//! An `AlertEngine` delivers each `AlertNotification` to the sinks its rule
+//! names. `WebhookSink` POSTs the notification as JSON, `FileSink` appends it
+//! as a JSON line, `SyslogSink` sends an RFC 5424 message over UDP and
+//! `ChannelSink` hands it to an in-process receiver. Other transports only
+//! need to implement `NotificationSink`.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+
+use crate::environmental::alert_rules::AlertNotification;
+use crate::environmental::alerting::{AlertSeverity, AlertingError};
+
+/// Syslog facility local0
+const SYSLOG_FACILITY_LOCAL0: u8 = 16;
+
+/// Destination for alert notifications
+#[async_trait]
+pub trait NotificationSink: Send + Sync {
+    /// Name rules use to select the sink
+    fn name(&self) -> &str;
+    
+    /// Deliver a notification
+    async fn send(&self, notification: &AlertNotification) -> Result<(), AlertingError>;
+}
+
+/// Sink configuration in a rule set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// HTTP POST of the notification as JSON
+    Webhook {
+        name: String,
+        url: String,
+        #[serde(default = "default_timeout_seconds")]
+        timeout_seconds: u64,
+    },
+    
+    /// JSON lines appended to a file
+    File {
+        name: String,
+        path: PathBuf,
+    },
+    
+    /// RFC 5424 messages sent over UDP
+    Syslog {
+        name: String,
+        address: SocketAddr,
+        #[serde(default = "default_app_name")]
+        app_name: String,
+    },
+}
+
+fn default_timeout_seconds() -> u64 {
+    10
+}
+
+fn default_app_name() -> String {
+    "supernova".to_string()
+}
+
+impl SinkConfig {
+    /// Name of the configured sink
+    pub fn name(&self) -> &str {
+        match self {
+            SinkConfig::Webhook { name, .. } | SinkConfig::File { name, .. } | SinkConfig::Syslog { name, .. } => name,
+        }
+    }
+    
+    /// Build the configured sink
+    pub fn build(&self) -> Result<Arc<dyn NotificationSink>, AlertingError> {
+        Ok(match self {
+            SinkConfig::Webhook { name, url, timeout_seconds } => {
+                Arc::new(WebhookSink::new(name, url, StdDuration::from_secs(*timeout_seconds))?)
+            }
+            SinkConfig::File { name, path } => Arc::new(FileSink::new(name, path.clone())),
+            SinkConfig::Syslog { name, address, app_name } => Arc::new(SyslogSink::new(name, *address, app_name)),
+        })
+    }
+}
+
+/// Posts notifications to an HTTP endpoint
+pub struct WebhookSink {
+    name: String,
+    url: String,
+    client: Client,
+}
+
+impl WebhookSink {
+    /// Create a webhook sink
+    pub fn new(name: &str, url: &str, timeout: StdDuration) -> Result<Self, AlertingError> {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| AlertingError::NotificationError(e.to_string()))?;
+        Ok(Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            client,
+        })
+    }
+}
+
+#[async_trait]
+impl NotificationSink for WebhookSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    async fn send(&self, notification: &AlertNotification) -> Result<(), AlertingError> {
+        let response = self.client.post(&self.url)
+            .json(notification)
+            .send()
+            .await
+            .map_err(|e| AlertingError::NotificationError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(AlertingError::NotificationError(
+                format!("Webhook returned HTTP {}", response.status())
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Appends notifications to a file, one JSON object per line
+pub struct FileSink {
+    name: String,
+    path: PathBuf,
+}
+
+impl FileSink {
+    /// Create a file sink
+    pub fn new(name: &str, path: PathBuf) -> Self {
+        Self {
+            name: name.to_string(),
+            path,
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for FileSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    async fn send(&self, notification: &AlertNotification) -> Result<(), AlertingError> {
+        let mut line = serde_json::to_vec(notification)
+            .map_err(|e| AlertingError::NotificationError(e.to_string()))?;
+        line.push(b'\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| AlertingError::NotificationError(e.to_string()))?;
+        file.write_all(&line)
+            .await
+            .map_err(|e| AlertingError::NotificationError(e.to_string()))?;
+        // tokio writes in the background; flush so the line is on disk on return
+        file.flush()
+            .await
+            .map_err(|e| AlertingError::NotificationError(e.to_string()))
+    }
+}
+
+/// Sends notifications to a syslog collector over UDP
+pub struct SyslogSink {
+    name: String,
+    address: SocketAddr,
+    app_name: String,
+    hostname: String,
+}
+
+impl SyslogSink {
+    /// Create a syslog sink
+    pub fn new(name: &str, address: SocketAddr, app_name: &str) -> Self {
+        let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "-".to_string());
+        Self {
+            name: name.to_string(),
+            address,
+            app_name: app_name.to_string(),
+            hostname,
+        }
+    }
+    
+    /// RFC 5424 message for a notification
+    pub fn format(&self, notification: &AlertNotification) -> String {
+        let severity = match notification.severity {
+            AlertSeverity::Critical => 2,
+            AlertSeverity::High => 3,
+            AlertSeverity::Medium => 4,
+            AlertSeverity::Low => 5,
+            AlertSeverity::Info => 6,
+        };
+        format!("<{}>1 {} {} {} - {} - {:?} {}",
+            SYSLOG_FACILITY_LOCAL0 * 8 + severity,
+            notification.alert.timestamp.to_rfc3339(),
+            self.hostname,
+            self.app_name,
+            notification.alert.rule_id,
+            notification.event,
+            notification.alert.message
+        )
+    }
+}
+
+#[async_trait]
+impl NotificationSink for SyslogSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    async fn send(&self, notification: &AlertNotification) -> Result<(), AlertingError> {
+        let bind = if self.address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = UdpSocket::bind(bind)
+            .await
+            .map_err(|e| AlertingError::NotificationError(e.to_string()))?;
+        socket.send_to(self.format(notification).as_bytes(), self.address)
+            .await
+            .map_err(|e| AlertingError::NotificationError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Hands notifications to an in-process receiver
+pub struct ChannelSink {
+    name: String,
+    sender: mpsc::UnboundedSender<AlertNotification>,
+}
+
+impl ChannelSink {
+    /// Create a channel sink and the receiver for its notifications
+    pub fn new(name: &str) -> (Self, mpsc::UnboundedReceiver<AlertNotification>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { name: name.to_string(), sender }, receiver)
+    }
+}
+
+#[async_trait]
+impl NotificationSink for ChannelSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    async fn send(&self, notification: &AlertNotification) -> Result<(), AlertingError> {
+        self.sender.send(notification.clone())
+            .map_err(|_| AlertingError::NotificationError("Channel receiver dropped".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environmental::alert_rules::AlertEngine;
+    use crate::environmental::alerting::MetricType;
+    use chrono::Utc;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+    
+    /// Local HTTP stand-in that answers one request with `status` and
+    /// returns the request body
+    async fn http_stand_in(status: u16) -> (String, oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text[..end].lines()
+                        .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length || n == 0 {
+                        let _ = tx.send(text[end + 4..].to_string());
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+            let response = format!("HTTP/1.1 {} Stand-in\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+        (url, rx)
+    }
+    
+    fn rules(sinks: &str) -> String {
+        format!(r#"
+            {}
+            
+            [[rules]]
+            id = "emissions-high"
+            name = "Daily emissions high"
+            metric = "CarbonEmissions"
+            severity = "High"
+            condition = {{ kind = "threshold", operator = "GreaterThan", value = 50.0 }}
+        "#, sinks)
+    }
+    
+    #[tokio::test]
+    async fn test_webhook_file_and_syslog_sinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("alerts.jsonl");
+        let collector = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let (url, body) = http_stand_in(200).await;
+        
+        let config = rules(&format!(r#"
+            [[sinks]]
+            kind = "webhook"
+            name = "ops"
+            url = "{}"
+            
+            [[sinks]]
+            kind = "file"
+            name = "audit"
+            path = "{}"
+            
+            [[sinks]]
+            kind = "syslog"
+            name = "syslog"
+            address = "{}"
+        "#, url, log.display(), collector.local_addr().unwrap()));
+        let mut engine = AlertEngine::from_toml(&config).unwrap();
+        engine.observe(MetricType::CarbonEmissions, 75.0, Utc::now()).await.unwrap();
+        
+        let posted: serde_json::Value = serde_json::from_str(&body.await.unwrap()).unwrap();
+        assert_eq!(posted["event"], "triggered");
+        assert_eq!(posted["severity"], "High");
+        assert_eq!(posted["alert"]["rule_id"], "emissions-high");
+        
+        let lines = std::fs::read_to_string(&log).unwrap();
+        let logged: AlertNotification = serde_json::from_str(lines.lines().next().unwrap()).unwrap();
+        assert_eq!(logged.alert.current_value, 75.0);
+        
+        let mut datagram = [0u8; 1024];
+        let n = collector.recv(&mut datagram).await.unwrap();
+        let message = String::from_utf8_lossy(&datagram[..n]);
+        // local0 (16) * 8 + err (3)
+        assert!(message.starts_with("<131>1 "), "{}", message);
+        assert!(message.contains("emissions-high - Triggered Daily emissions high"));
+    }
+    
+    #[tokio::test]
+    async fn test_webhook_failure_reported() {
+        let (url, _body) = http_stand_in(503).await;
+        let config = rules(&format!("[[sinks]]\nkind = \"webhook\"\nname = \"ops\"\nurl = \"{}\"", url));
+        let mut engine = AlertEngine::from_toml(&config).unwrap();
+        let (channel, mut rx) = ChannelSink::new("channel");
+        engine.add_sink(Arc::new(channel)).unwrap();
+        
+        // The failing webhook is reported but does not stop other sinks
+        match engine.observe(MetricType::CarbonEmissions, 75.0, Utc::now()).await {
+            Err(AlertingError::NotificationError(msg)) => assert!(msg.contains("ops: "), "{}", msg),
+            other => panic!("Expected NotificationError, got {:?}", other.map(|n| n.len())),
+        }
+        assert!(rx.try_recv().is_ok());
+    }
+}
@@ -2,10 +2,9 @@ use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 use thiserror::Error;
-use std::sync::{Arc, RwLock};
 
-use crate::environmental::emissions::{EmissionsTracker, Emissions};
-use crate::environmental::dashboard::{EnvironmentalDashboard, EnvironmentalMetrics, EmissionsTimePeriod};
+use crate::environmental::emissions::EmissionsTracker;
+use crate::environmental::dashboard::{EnvironmentalDashboard, EmissionsTimePeriod};
 use crate::environmental::treasury::{EnvironmentalTreasury, TreasuryAccountType};
 
 /// Error types for environmental alerting
@@ -77,6 +76,24 @@ pub enum MetricType {
     VerifiedMinerPercentage,
 }
 
+impl MetricType {
+    /// Human-readable metric name
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            MetricType::EnergyConsumption => "Energy Consumption",
+            MetricType::CarbonEmissions => "Carbon Emissions",
+            MetricType::RenewablePercentage => "Renewable Energy Percentage",
+            MetricType::OffsetPercentage => "Carbon Offset Percentage",
+            MetricType::NetCarbonImpact => "Net Carbon Impact",
+            MetricType::CarbonIntensity => "Carbon Intensity",
+            MetricType::TreasuryAllocation => "Treasury Allocation Percentage",
+            MetricType::TreasuryBalance => "Treasury Balance",
+            MetricType::RECCoverage => "REC Coverage Percentage",
+            MetricType::VerifiedMinerPercentage => "Verified Miner Percentage",
+        }
+    }
+}
+
 /// Comparison operators for alerts
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ComparisonOperator {
@@ -94,6 +111,32 @@ pub enum ComparisonOperator {
     NotEqual,
 }
 
+impl ComparisonOperator {
+    /// Whether `value` satisfies the operator against `threshold`
+    pub fn compare(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            ComparisonOperator::GreaterThan => value > threshold,
+            ComparisonOperator::GreaterThanOrEqual => value >= threshold,
+            ComparisonOperator::LessThan => value < threshold,
+            ComparisonOperator::LessThanOrEqual => value <= threshold,
+            ComparisonOperator::Equal => (value - threshold).abs() < f64::EPSILON,
+            ComparisonOperator::NotEqual => (value - threshold).abs() >= f64::EPSILON,
+        }
+    }
+    
+    /// Symbol for the operator
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ComparisonOperator::GreaterThan => ">",
+            ComparisonOperator::GreaterThanOrEqual => ">=",
+            ComparisonOperator::LessThan => "<",
+            ComparisonOperator::LessThanOrEqual => "<=",
+            ComparisonOperator::Equal => "=",
+            ComparisonOperator::NotEqual => "!=",
+        }
+    }
+}
+
 /// Alert notification method
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum NotificationMethod {
@@ -208,60 +251,29 @@ impl Default for AlertingConfig {
 pub type AlertingSystem = EnvironmentalAlertingSystem;
 
 /// Environmental alerting system
-#[derive(Debug, Clone)]
 pub struct EnvironmentalAlertingSystem {
-    /// Active alerts
-    pub alerts: Arc<RwLock<HashMap<String, Alert>>>,
-    /// Alert rules
-    pub rules: Arc<RwLock<HashMap<String, AlertRule>>>,
     /// Configuration
-    pub config: AlertingConfig,
-}
-
-impl EnvironmentalAlertingSystem {
-    /// Create a new environmental alerting system
-    pub fn new(
-        config: AlertingConfig,
-        dashboard: EnvironmentalDashboard,
-        emissions_tracker: EmissionsTracker,
-        treasury: EnvironmentalTreasury,
-    ) -> Self {
-        Self {
-            alerts: Arc::new(RwLock::new(HashMap::new())),
-            rules: Arc::new(RwLock::new(HashMap::new())),
-            config,
-        }
-    }
-    
-    /// Add an alert rule
-    pub fn add_rule(&self, rule: AlertRule) -> Result<(), AlertingError> {
-        let mut rules = self.rules.write().unwrap();
-        rules.insert(rule.id.clone(), rule);
-        Ok(())
-    }
-    
-    /// Trigger an alert
-    pub fn trigger_alert(&self, alert: Alert) -> Result<(), AlertingError> {
-        if !self.config.enabled {
-            return Ok(());
-        }
-        
-        let mut alerts = self.alerts.write().unwrap();
-        alerts.insert(alert.id.clone(), alert);
-        
-        // Clean up old alerts if necessary
-        if alerts.len() > self.config.max_alerts_per_hour as usize {
-            let oldest_id = alerts.keys().next().cloned();
-            if let Some(id) = oldest_id {
-                alerts.remove(&id);
-            }
-        }
-        
-        Ok(())
-    }
+    config: AlertingConfig,
+    /// Alert rules
+    rules: Vec<AlertRule>,
+    /// Alerts by ID
+    active_alerts: HashMap<String, Alert>,
+    /// Status changes
+    alert_history: Vec<AlertHistoryRecord>,
+    /// Last evaluation time by rule ID
+    last_check: HashMap<String, DateTime<Utc>>,
+    /// Alerts raised in the current hour
+    alerts_this_hour: u32,
+    /// Start of the current hour
+    current_hour_start: DateTime<Utc>,
+    /// Source of emissions metrics
+    dashboard: EnvironmentalDashboard,
+    /// Emissions tracker
+    emissions_tracker: EmissionsTracker,
+    /// Treasury for allocation and balance metrics
+    treasury: EnvironmentalTreasury,
 }
 
-/// Alert rule for environmental monitoring
 impl EnvironmentalAlertingSystem {
     /// Create a new environmental alerting system
     pub fn new(
@@ -773,42 +785,17 @@ impl EnvironmentalAlertingSystem {
     
     /// Compare a value against a threshold using the specified operator
     fn compare_value(&self, value: f64, threshold: f64, operator: ComparisonOperator) -> bool {
-        match operator {
-            ComparisonOperator::GreaterThan => value > threshold,
-            ComparisonOperator::GreaterThanOrEqual => value >= threshold,
-            ComparisonOperator::LessThan => value < threshold,
-            ComparisonOperator::LessThanOrEqual => value <= threshold,
-            ComparisonOperator::Equal => (value - threshold).abs() < f64::EPSILON,
-            ComparisonOperator::NotEqual => (value - threshold).abs() >= f64::EPSILON,
-        }
+        operator.compare(value, threshold)
     }
     
     /// Get a human-readable name for a metric
     fn get_metric_name(&self, metric_type: MetricType) -> &'static str {
-        match metric_type {
-            MetricType::EnergyConsumption => "Energy Consumption",
-            MetricType::CarbonEmissions => "Carbon Emissions",
-            MetricType::RenewablePercentage => "Renewable Energy Percentage",
-            MetricType::OffsetPercentage => "Carbon Offset Percentage",
-            MetricType::NetCarbonImpact => "Net Carbon Impact",
-            MetricType::CarbonIntensity => "Carbon Intensity",
-            MetricType::TreasuryAllocation => "Treasury Allocation Percentage",
-            MetricType::TreasuryBalance => "Treasury Balance",
-            MetricType::RECCoverage => "REC Coverage Percentage",
-            MetricType::VerifiedMinerPercentage => "Verified Miner Percentage",
-        }
+        metric_type.display_name()
     }
     
     /// Get a symbol for a comparison operator
     fn get_operator_symbol(&self, operator: ComparisonOperator) -> &'static str {
-        match operator {
-            ComparisonOperator::GreaterThan => ">",
-            ComparisonOperator::GreaterThanOrEqual => ">=",
-            ComparisonOperator::LessThan => "<",
-            ComparisonOperator::LessThanOrEqual => "<=",
-            ComparisonOperator::Equal => "=",
-            ComparisonOperator::NotEqual => "!=",
-        }
+        operator.symbol()
     }
     
     /// Send notifications for an alert
@@ -859,14 +846,22 @@ impl ToString for AlertSeverity {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::environmental::api::EnvironmentalApi;
+    use crate::environmental::emissions::EmissionsConfig;
+    use crate::environmental::treasury::TreasuryConfig;
     
     #[test]
     fn test_comparison_operators() {
+        let dashboard = EnvironmentalDashboard::new(
+            EmissionsTracker::new(EmissionsConfig::default()),
+            EnvironmentalTreasury::new(TreasuryConfig::default()),
+            Box::new(EnvironmentalApi::new()),
+        );
         let system = EnvironmentalAlertingSystem::new(
             AlertingConfig::default(),
-            EnvironmentalDashboard::new(EmissionsTracker::new(), EnvironmentalTreasury::new()),
-            EmissionsTracker::new(),
-            EnvironmentalTreasury::new(),
+            dashboard,
+            EmissionsTracker::new(EmissionsConfig::default()),
+            EnvironmentalTreasury::new(TreasuryConfig::default()),
         );
         
         assert!(system.compare_value(10.0, 5.0, ComparisonOperator::GreaterThan));
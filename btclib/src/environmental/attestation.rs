@@ -0,0 +1,595 @@
+//! Oracle-signed REC attestations
+//!
+//! `EnvironmentalOracle` reaches consensus on a miner's renewable energy
+//! certificate in memory. An attestation records that outcome in a compact,
+//! versioned object signed by the oracle set of an epoch, so anyone holding
+//! the published oracle-set registry can verify it later without the oracle.
+//!
+//! Oracle sets are k-of-n: an attestation is valid once `threshold` members
+//! of its epoch's set have signed it with Dilithium.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::crypto::quantum::{verify_quantum_signature, QuantumKeyPair, QuantumParameters, QuantumScheme};
+use crate::environmental::emissions::RECCertificateInfo;
+use crate::environmental::miner_reporting::to_milli_mwh;
+use crate::environmental::types::EnergySource;
+
+/// Current attestation format version
+pub const ATTESTATION_VERSION: u8 = 1;
+
+/// Domain separator for attestation signatures
+const ATTESTATION_DOMAIN: &[u8] = b"supernova_rec_attestation";
+
+/// Miner ID of whoever a coinbase reward output with `reward_script` pays
+pub fn miner_id_for_script(reward_script: &[u8]) -> [u8; 32] {
+    Sha256::digest(reward_script).into()
+}
+
+/// Error types for attestations
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum AttestationError {
+    #[error("Unsupported attestation version: {0}")]
+    UnsupportedVersion(u8),
+    
+    #[error("Invalid attestation: {0}")]
+    InvalidAttestation(String),
+    
+    #[error("No oracle set published for epoch {0}")]
+    UnknownEpoch(u64),
+    
+    #[error("Invalid oracle set: {0}")]
+    InvalidOracleSet(String),
+    
+    #[error("Signer {0} is not in the oracle set")]
+    UnknownSigner(u16),
+    
+    #[error("Signer {0} signed more than once")]
+    DuplicateSigner(u16),
+    
+    #[error("Invalid signature from signer {0}")]
+    InvalidSignature(u16),
+    
+    #[error("Insufficient signatures: required {required}, has {has}")]
+    InsufficientSignatures { required: u16, has: u16 },
+    
+    #[error("Certificate {0} is already attested")]
+    DuplicateCertificate(String),
+    
+    #[error("Signing error: {0}")]
+    SigningError(String),
+    
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+}
+
+/// Kind of environmental claim an attestation backs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AttestationKind {
+    /// Renewable energy certificate
+    RenewableCertificate,
+    /// Retired carbon offset
+    CarbonOffset,
+}
+
+/// What the oracle set attests to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttestationClaim {
+    /// Format version
+    pub version: u8,
+    
+    /// Kind of claim
+    pub kind: AttestationKind,
+    
+    /// Registry ID of the certificate or offset
+    pub certificate_id: String,
+    
+    /// Miner the certificate was verified for, as the hash of the script
+    /// its coinbase reward pays (see `miner_id_for_script`)
+    pub miner_id: [u8; 32],
+    
+    /// Energy covered in milli-MWh, zero for carbon offsets
+    pub energy_milli_mwh: u64,
+    
+    /// Start of the generation period (Unix seconds)
+    pub period_start: u64,
+    
+    /// End of the generation period (Unix seconds)
+    pub period_end: u64,
+    
+    /// Generation location as an ISO country code, optionally with a sub-region
+    pub location: String,
+    
+    /// Source of the energy
+    pub energy_type: EnergySource,
+    
+    /// Epoch of the oracle set that signs the attestation
+    pub oracle_epoch: u64,
+}
+
+impl AttestationClaim {
+    /// Check the fields are well formed
+    pub fn validate(&self) -> Result<(), AttestationError> {
+        if self.version != ATTESTATION_VERSION {
+            return Err(AttestationError::UnsupportedVersion(self.version));
+        }
+        if self.certificate_id.is_empty() {
+            return Err(AttestationError::InvalidAttestation("Missing certificate ID".to_string()));
+        }
+        let needs_energy = self.kind == AttestationKind::RenewableCertificate;
+        if needs_energy && self.energy_milli_mwh == 0 {
+            return Err(AttestationError::InvalidAttestation("Certificate covers no energy".to_string()));
+        }
+        if self.period_end <= self.period_start {
+            return Err(AttestationError::InvalidAttestation("Period ends before it starts".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Signature of one oracle-set member
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OracleSignature {
+    /// Index of the signer in the epoch's oracle set
+    pub signer: u16,
+    
+    /// Dilithium signature over the claim
+    pub signature: Vec<u8>,
+}
+
+/// REC attestation signed by an oracle set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecAttestation {
+    /// Attested claim
+    pub claim: AttestationClaim,
+    
+    /// Signatures of oracle-set members
+    pub signatures: Vec<OracleSignature>,
+}
+
+impl RecAttestation {
+    /// Create an unsigned attestation
+    pub fn new(claim: AttestationClaim) -> Self {
+        Self {
+            claim,
+            signatures: Vec::new(),
+        }
+    }
+    
+    /// Attest a certificate the oracles verified for `miner_id`
+    pub fn from_certificate(
+        miner_id: [u8; 32],
+        certificate: &RECCertificateInfo,
+        energy_type: EnergySource,
+        oracle_epoch: u64,
+    ) -> Self {
+        let location = match &certificate.generation_location {
+            Some(region) => match &region.sub_region {
+                Some(sub_region) => format!("{}-{}", region.country_code, sub_region),
+                None => region.country_code.clone(),
+            },
+            None => String::new(),
+        };
+        
+        Self::new(AttestationClaim {
+            version: ATTESTATION_VERSION,
+            kind: AttestationKind::RenewableCertificate,
+            certificate_id: certificate.certificate_id.clone(),
+            miner_id,
+            energy_milli_mwh: to_milli_mwh(certificate.amount_mwh),
+            period_start: certificate.generation_start.timestamp().max(0) as u64,
+            period_end: certificate.generation_end.timestamp().max(0) as u64,
+            location,
+            energy_type,
+            oracle_epoch,
+        })
+    }
+    
+    /// Message every oracle signs
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut message = ATTESTATION_DOMAIN.to_vec();
+        message.extend_from_slice(&bincode::serialize(&self.claim).unwrap_or_default());
+        message
+    }
+    
+    /// Identifier of the attestation, independent of its signatures
+    pub fn attestation_id(&self) -> [u8; 32] {
+        Sha256::digest(self.signing_message()).into()
+    }
+    
+    /// Merkle leaf data for coinbase commitments, covering the signatures
+    pub fn leaf_data(&self) -> Vec<u8> {
+        let mut data = self.attestation_id().to_vec();
+        for signature in &self.signatures {
+            data.extend_from_slice(&signature.signer.to_le_bytes());
+            data.extend_from_slice(&(signature.signature.len() as u32).to_le_bytes());
+            data.extend_from_slice(&signature.signature);
+        }
+        data
+    }
+    
+    /// Add the signature of oracle-set member `signer`, replacing an earlier one
+    pub fn sign(&mut self, signer: u16, keypair: &QuantumKeyPair) -> Result<(), AttestationError> {
+        let signature = keypair.sign(&self.signing_message())
+            .map_err(|e| AttestationError::SigningError(e.to_string()))?;
+        
+        self.signatures.retain(|existing| existing.signer != signer);
+        self.signatures.push(OracleSignature { signer, signature });
+        self.signatures.sort_by_key(|existing| existing.signer);
+        Ok(())
+    }
+    
+    /// Serialize for storage and transport
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AttestationError> {
+        bincode::serialize(self).map_err(|e| AttestationError::SerializationError(e.to_string()))
+    }
+    
+    /// Deserialize an attestation, rejecting unknown versions
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AttestationError> {
+        let attestation: Self = bincode::deserialize(bytes)
+            .map_err(|e| AttestationError::SerializationError(e.to_string()))?;
+        if attestation.claim.version != ATTESTATION_VERSION {
+            return Err(AttestationError::UnsupportedVersion(attestation.claim.version));
+        }
+        Ok(attestation)
+    }
+}
+
+/// Member of an oracle set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OracleSetMember {
+    /// Oracle identifier
+    pub oracle_id: String,
+    
+    /// Dilithium public key
+    pub public_key: Vec<u8>,
+}
+
+/// Oracles that sign attestations during an epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OracleSet {
+    /// Epoch the set signs for
+    pub epoch: u64,
+    
+    /// Signatures an attestation needs
+    pub threshold: u16,
+    
+    /// Dilithium security level of the members' keys
+    pub security_level: u8,
+    
+    /// Members, indexed by position
+    pub members: Vec<OracleSetMember>,
+}
+
+impl OracleSet {
+    /// Check the threshold is reachable and members are distinct
+    pub fn validate(&self) -> Result<(), AttestationError> {
+        if self.members.len() > u16::MAX as usize {
+            return Err(AttestationError::InvalidOracleSet("Too many members".to_string()));
+        }
+        if self.threshold == 0 || self.threshold as usize > self.members.len() {
+            return Err(AttestationError::InvalidOracleSet(
+                format!("Threshold {} with {} members", self.threshold, self.members.len())
+            ));
+        }
+        
+        let mut keys = HashSet::new();
+        for member in &self.members {
+            if !keys.insert(&member.public_key) {
+                return Err(AttestationError::InvalidOracleSet(format!("Duplicate key for {}", member.oracle_id)));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Verify an attestation signed for this set's epoch
+    ///
+    /// Every signature present must be valid, and at least `threshold`
+    /// distinct members must have signed.
+    pub fn verify(&self, attestation: &RecAttestation) -> Result<(), AttestationError> {
+        attestation.claim.validate()?;
+        if attestation.claim.oracle_epoch != self.epoch {
+            return Err(AttestationError::UnknownEpoch(attestation.claim.oracle_epoch));
+        }
+        
+        let message = attestation.signing_message();
+        let parameters = QuantumParameters::with_security_level(QuantumScheme::Dilithium, self.security_level);
+        let mut signers = HashSet::new();
+        
+        for signature in &attestation.signatures {
+            let member = self.members.get(signature.signer as usize)
+                .ok_or(AttestationError::UnknownSigner(signature.signer))?;
+            if !signers.insert(signature.signer) {
+                return Err(AttestationError::DuplicateSigner(signature.signer));
+            }
+            
+            let valid = verify_quantum_signature(&member.public_key, &message, &signature.signature, parameters)
+                .unwrap_or(false);
+            if !valid {
+                return Err(AttestationError::InvalidSignature(signature.signer));
+            }
+        }
+        
+        if signers.len() < self.threshold as usize {
+            return Err(AttestationError::InsufficientSignatures {
+                required: self.threshold,
+                has: signers.len() as u16,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Published history of oracle sets, by epoch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OracleSetRegistry {
+    sets: BTreeMap<u64, OracleSet>,
+}
+
+impl OracleSetRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Publish the oracle set of a new epoch
+    pub fn publish(&mut self, set: OracleSet) -> Result<(), AttestationError> {
+        set.validate()?;
+        if let Some(latest) = self.latest_epoch() {
+            if set.epoch <= latest {
+                return Err(AttestationError::InvalidOracleSet(
+                    format!("Epoch {} is not after epoch {}", set.epoch, latest)
+                ));
+            }
+        }
+        
+        self.sets.insert(set.epoch, set);
+        Ok(())
+    }
+    
+    /// Oracle set of an epoch
+    pub fn get(&self, epoch: u64) -> Option<&OracleSet> {
+        self.sets.get(&epoch)
+    }
+    
+    /// Most recent epoch
+    pub fn latest_epoch(&self) -> Option<u64> {
+        self.sets.keys().next_back().copied()
+    }
+    
+    /// Verify an attestation against the set of its epoch
+    pub fn verify(&self, attestation: &RecAttestation) -> Result<(), AttestationError> {
+        self.get(attestation.claim.oracle_epoch)
+            .ok_or(AttestationError::UnknownEpoch(attestation.claim.oracle_epoch))?
+            .verify(attestation)
+    }
+    
+    /// Serialize the registry for publication
+    pub fn to_json(&self) -> Result<String, AttestationError> {
+        serde_json::to_string_pretty(self).map_err(|e| AttestationError::SerializationError(e.to_string()))
+    }
+    
+    /// Load a published registry, checking every set
+    pub fn from_json(json: &str) -> Result<Self, AttestationError> {
+        let registry: Self = serde_json::from_str(json)
+            .map_err(|e| AttestationError::SerializationError(e.to_string()))?;
+        for set in registry.sets.values() {
+            set.validate()?;
+        }
+        Ok(registry)
+    }
+}
+
+/// Verified attestations, by miner
+pub struct AttestationStore {
+    registry: RwLock<OracleSetRegistry>,
+    attestations: RwLock<HashMap<[u8; 32], Vec<RecAttestation>>>,
+}
+
+impl AttestationStore {
+    /// Create a store that verifies against `registry`
+    pub fn new(registry: OracleSetRegistry) -> Self {
+        Self {
+            registry: RwLock::new(registry),
+            attestations: RwLock::new(HashMap::new()),
+        }
+    }
+    
+    /// Publish the oracle set of a new epoch
+    pub fn publish_oracle_set(&self, set: OracleSet) -> Result<(), AttestationError> {
+        self.registry.write().unwrap().publish(set)
+    }
+    
+    /// Copy of the oracle-set registry
+    pub fn registry(&self) -> OracleSetRegistry {
+        self.registry.read().unwrap().clone()
+    }
+    
+    /// Verify and store an attestation, returning its ID
+    pub fn insert(&self, attestation: RecAttestation) -> Result<[u8; 32], AttestationError> {
+        self.registry.read().unwrap().verify(&attestation)?;
+        
+        let mut attestations = self.attestations.write().unwrap();
+        let miner_attestations = attestations.entry(attestation.claim.miner_id).or_default();
+        if miner_attestations.iter().any(|existing| existing.claim.certificate_id == attestation.claim.certificate_id) {
+            return Err(AttestationError::DuplicateCertificate(attestation.claim.certificate_id));
+        }
+        
+        let id = attestation.attestation_id();
+        miner_attestations.push(attestation);
+        Ok(id)
+    }
+    
+    /// Attestations of a miner, oldest first
+    pub fn by_miner(&self, miner_id: &[u8; 32]) -> Vec<RecAttestation> {
+        self.attestations.read().unwrap().get(miner_id).cloned().unwrap_or_default()
+    }
+}
+
+impl Default for AttestationStore {
+    fn default() -> Self {
+        Self::new(OracleSetRegistry::new())
+    }
+}
+
+/// Certificates that have backed an environmental bonus on chain
+///
+/// Each `(kind, certificate_id)` pair can back one block's bonus. Entries
+/// remember the height of the block that consumed them so a reorganization
+/// can release them again.
+#[derive(Debug, Clone, Default)]
+pub struct ConsumedCertificates {
+    consumed: HashMap<(AttestationKind, String), u64>,
+}
+
+impl ConsumedCertificates {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Height of the block that consumed a certificate
+    pub fn consumed_at(&self, kind: AttestationKind, certificate_id: &str) -> Option<u64> {
+        self.consumed.get(&(kind, certificate_id.to_string())).copied()
+    }
+    
+    /// Record the certificates behind the bonus of the block at `height`
+    ///
+    /// Nothing is recorded if any of them was consumed before.
+    pub fn consume(&mut self, attestations: &[RecAttestation], height: u64) -> Result<(), AttestationError> {
+        let mut keys = HashSet::new();
+        for attestation in attestations {
+            let key = (attestation.claim.kind, attestation.claim.certificate_id.clone());
+            if self.consumed.contains_key(&key) || !keys.insert(key) {
+                return Err(AttestationError::DuplicateCertificate(attestation.claim.certificate_id.clone()));
+            }
+        }
+        
+        for key in keys {
+            self.consumed.insert(key, height);
+        }
+        Ok(())
+    }
+    
+    /// Release the certificates consumed at or above `height`
+    pub fn disconnect(&mut self, height: u64) {
+        self.consumed.retain(|_, consumed_at| *consumed_at < height);
+    }
+    
+    /// Number of consumed certificates
+    pub fn len(&self) -> usize {
+        self.consumed.len()
+    }
+    
+    /// Whether no certificate has been consumed
+    pub fn is_empty(&self) -> bool {
+        self.consumed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn oracle_keys(count: usize) -> Vec<QuantumKeyPair> {
+        (0..count)
+            .map(|_| QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap())
+            .collect()
+    }
+    
+    fn oracle_set(epoch: u64, threshold: u16, keys: &[QuantumKeyPair]) -> OracleSet {
+        OracleSet {
+            epoch,
+            threshold,
+            security_level: 2,
+            members: keys.iter().enumerate()
+                .map(|(i, key)| OracleSetMember {
+                    oracle_id: format!("oracle-{}", i),
+                    public_key: key.public_key.clone(),
+                })
+                .collect(),
+        }
+    }
+    
+    fn claim(certificate_id: &str, epoch: u64) -> AttestationClaim {
+        AttestationClaim {
+            version: ATTESTATION_VERSION,
+            kind: AttestationKind::RenewableCertificate,
+            certificate_id: certificate_id.to_string(),
+            miner_id: miner_id_for_script(b"miner-1"),
+            energy_milli_mwh: 12_500,
+            period_start: 1_700_000_000,
+            period_end: 1_702_592_000,
+            location: "IS".to_string(),
+            energy_type: EnergySource::Geothermal,
+            oracle_epoch: epoch,
+        }
+    }
+    
+    #[test]
+    fn test_threshold_verification() {
+        let keys = oracle_keys(3);
+        let mut registry = OracleSetRegistry::new();
+        registry.publish(oracle_set(1, 2, &keys)).unwrap();
+        
+        let mut attestation = RecAttestation::new(claim("REC-1", 1));
+        attestation.sign(0, &keys[0]).unwrap();
+        assert_eq!(
+            registry.verify(&attestation),
+            Err(AttestationError::InsufficientSignatures { required: 2, has: 1 })
+        );
+        
+        attestation.sign(2, &keys[2]).unwrap();
+        assert_eq!(registry.verify(&attestation), Ok(()));
+        
+        // Verification works offline from the encoded attestation and the published registry
+        let decoded = RecAttestation::from_bytes(&attestation.to_bytes().unwrap()).unwrap();
+        let published = OracleSetRegistry::from_json(&registry.to_json().unwrap()).unwrap();
+        assert_eq!(published.verify(&decoded), Ok(()));
+        assert_eq!(decoded.attestation_id(), attestation.attestation_id());
+        
+        // Tampering with the claim invalidates the signatures
+        let mut tampered = attestation.clone();
+        tampered.claim.energy_milli_mwh = 125_000;
+        assert_eq!(registry.verify(&tampered), Err(AttestationError::InvalidSignature(0)));
+        
+        // A member signing under another index does not count
+        let mut impostor = attestation.clone();
+        impostor.sign(1, &keys[0]).unwrap();
+        assert_eq!(registry.verify(&impostor), Err(AttestationError::InvalidSignature(1)));
+        
+        // Signatures only count for the epoch they were made for
+        registry.publish(oracle_set(2, 2, &keys)).unwrap();
+        let mut next_epoch = attestation.clone();
+        next_epoch.claim.oracle_epoch = 2;
+        assert!(registry.verify(&next_epoch).is_err());
+        assert!(registry.publish(oracle_set(2, 1, &keys)).is_err());
+    }
+    
+    #[test]
+    fn test_store_by_miner() {
+        let keys = oracle_keys(2);
+        let mut registry = OracleSetRegistry::new();
+        registry.publish(oracle_set(7, 1, &keys)).unwrap();
+        let store = AttestationStore::new(registry);
+        
+        let mut unsigned = RecAttestation::new(claim("REC-2", 7));
+        assert!(store.insert(unsigned.clone()).is_err());
+        
+        unsigned.sign(1, &keys[1]).unwrap();
+        store.insert(unsigned.clone()).unwrap();
+        assert_eq!(store.insert(unsigned), Err(AttestationError::DuplicateCertificate("REC-2".to_string())));
+        
+        assert_eq!(store.by_miner(&miner_id_for_script(b"miner-1")).len(), 1);
+        assert!(store.by_miner(&miner_id_for_script(b"miner-2")).is_empty());
+        
+        let mut bad = claim("REC-3", 7);
+        bad.version = 2;
+        assert_eq!(bad.validate(), Err(AttestationError::UnsupportedVersion(2)));
+    }
+}
@@ -0,0 +1,862 @@
+//! On-chain environmental governance
+//!
+//! Proposals, votes and voting stake are carried in tagged transaction
+//! outputs instead of `EnvironmentalGovernance`'s in-memory voter list.
+//! A vote's weight is the voter's locked stake at the proposal's snapshot
+//! height (the height it was mined at), optionally scaled by coin age, so
+//! stake moved after the snapshot cannot vote twice.
+//!
+//! `GovernanceLedger` replays blocks: it tallies each proposal once its
+//! voting period ends and activates approved parameter changes at the
+//! proposal's activation height. Recent blocks can be disconnected again
+//! when the chain reorganizes.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::crypto::quantum::{verify_quantum_signature, QuantumKeyPair, QuantumParameters, QuantumScheme};
+use crate::environmental::governance::{ProposalStatus, ProposalType};
+use crate::environmental::treasury_ledger::{DEFAULT_TREASURY_ALLOCATION_BPS, LEDGER_UNDO_DEPTH};
+use crate::types::block::Block;
+
+/// Prefix of every governance output script
+pub const GOVERNANCE_SCRIPT_TAG: &[u8; 4] = b"SNGV";
+
+/// Domain separator for proposal signatures
+const PROPOSAL_DOMAIN: &[u8] = b"supernova_governance_proposal";
+
+/// Domain separator for vote signatures
+const VOTE_DOMAIN: &[u8] = b"supernova_governance_vote";
+
+/// Error types for on-chain governance records
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ChainGovernanceError {
+    #[error("Malformed governance record: {0}")]
+    Malformed(String),
+    
+    #[error("Invalid signature")]
+    InvalidSignature,
+    
+    #[error("Invalid proposal schedule: {0}")]
+    InvalidSchedule(String),
+    
+    #[error("Invalid proposal: {0}")]
+    InvalidProposal(String),
+    
+    #[error("Signing error: {0}")]
+    SigningError(String),
+    
+    #[error("Cannot disconnect block: {0}")]
+    DisconnectFailed(String),
+}
+
+/// Identifier of a voter: sha256 of their Dilithium public key
+pub fn voter_id(public_key: &[u8]) -> [u8; 32] {
+    Sha256::digest(public_key).into()
+}
+
+/// Stake locked for voting; the output's value is the stake
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakeLock {
+    /// Voter the stake belongs to
+    pub voter: [u8; 32],
+    
+    /// The stake counts for snapshots below this height
+    pub unlock_height: u64,
+}
+
+/// Content of a proposal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposalPayload {
+    /// Title of the proposal
+    pub title: String,
+    
+    /// Detailed description of the proposal
+    pub description: String,
+    
+    /// What the proposal does
+    pub proposal_type: ProposalType,
+    
+    /// Last height at which votes count
+    pub voting_end_height: u64,
+    
+    /// Height at which an approved proposal takes effect
+    pub activation_height: u64,
+    
+    /// URL for additional information
+    pub url: Option<String>,
+}
+
+/// Proposal signed by its proposer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedProposal {
+    /// Proposal content
+    pub payload: ProposalPayload,
+    
+    /// Proposer's Dilithium public key
+    pub proposer_key: Vec<u8>,
+    
+    /// Signature over the payload
+    pub signature: Vec<u8>,
+}
+
+impl SignedProposal {
+    /// Sign a proposal
+    pub fn sign(payload: ProposalPayload, keypair: &QuantumKeyPair) -> Result<Self, ChainGovernanceError> {
+        let signature = keypair.sign(&Self::signing_message(&payload))
+            .map_err(|e| ChainGovernanceError::SigningError(e.to_string()))?;
+        Ok(Self {
+            payload,
+            proposer_key: keypair.public_key.clone(),
+            signature,
+        })
+    }
+    
+    /// Verify the proposer's signature
+    pub fn verify(&self, security_level: u8) -> Result<(), ChainGovernanceError> {
+        verify_signature(&self.proposer_key, &Self::signing_message(&self.payload), &self.signature, security_level)
+    }
+    
+    /// Proposer's voter ID
+    pub fn proposer(&self) -> [u8; 32] {
+        voter_id(&self.proposer_key)
+    }
+    
+    fn signing_message(payload: &ProposalPayload) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(PROPOSAL_DOMAIN);
+        hasher.update(bincode::serialize(payload).expect("proposal serialization cannot fail"));
+        hasher.finalize().to_vec()
+    }
+}
+
+/// Vote signed by the voter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedVote {
+    /// Proposal voted on
+    pub proposal_id: String,
+    
+    /// Whether the vote is in favour
+    pub support: bool,
+    
+    /// Voter's Dilithium public key
+    pub voter_key: Vec<u8>,
+    
+    /// Signature over the proposal ID and choice
+    pub signature: Vec<u8>,
+}
+
+impl SignedVote {
+    /// Sign a vote
+    pub fn sign(proposal_id: &str, support: bool, keypair: &QuantumKeyPair) -> Result<Self, ChainGovernanceError> {
+        let signature = keypair.sign(&Self::signing_message(proposal_id, support))
+            .map_err(|e| ChainGovernanceError::SigningError(e.to_string()))?;
+        Ok(Self {
+            proposal_id: proposal_id.to_string(),
+            support,
+            voter_key: keypair.public_key.clone(),
+            signature,
+        })
+    }
+    
+    /// Verify the voter's signature
+    pub fn verify(&self, security_level: u8) -> Result<(), ChainGovernanceError> {
+        verify_signature(&self.voter_key, &Self::signing_message(&self.proposal_id, self.support), &self.signature, security_level)
+    }
+    
+    /// Voter's ID
+    pub fn voter(&self) -> [u8; 32] {
+        voter_id(&self.voter_key)
+    }
+    
+    fn signing_message(proposal_id: &str, support: bool) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(VOTE_DOMAIN);
+        hasher.update(proposal_id.as_bytes());
+        hasher.update([support as u8]);
+        hasher.finalize().to_vec()
+    }
+}
+
+fn verify_signature(public_key: &[u8], message: &[u8], signature: &[u8], security_level: u8) -> Result<(), ChainGovernanceError> {
+    let parameters = QuantumParameters::with_security_level(QuantumScheme::Dilithium, security_level);
+    match verify_quantum_signature(public_key, message, signature, parameters) {
+        Ok(true) => Ok(()),
+        _ => Err(ChainGovernanceError::InvalidSignature),
+    }
+}
+
+/// Governance record carried in a tagged output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GovernanceRecord {
+    /// Stake locked for voting
+    Stake(StakeLock),
+    /// New proposal
+    Proposal(SignedProposal),
+    /// Vote on a proposal
+    Vote(SignedVote),
+}
+
+impl GovernanceRecord {
+    /// Encode as an output script
+    pub fn to_script(&self) -> Vec<u8> {
+        let mut script = GOVERNANCE_SCRIPT_TAG.to_vec();
+        script.extend(bincode::serialize(self).expect("governance record serialization cannot fail"));
+        script
+    }
+    
+    /// Decode from an output script; `Ok(None)` if the script is not tagged
+    pub fn from_script(script: &[u8]) -> Result<Option<Self>, ChainGovernanceError> {
+        match script.strip_prefix(GOVERNANCE_SCRIPT_TAG.as_slice()) {
+            Some(body) => bincode::deserialize(body)
+                .map(Some)
+                .map_err(|e| ChainGovernanceError::Malformed(e.to_string())),
+            None => Ok(None),
+        }
+    }
+}
+
+/// How stake turns into voting weight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WeightMode {
+    /// Weight equals the locked stake
+    LockedStake,
+    /// Stake gains weight linearly until it has been locked this many blocks
+    CoinAge { full_weight_after: u64 },
+}
+
+/// On-chain governance rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainGovernanceConfig {
+    /// Dilithium security level of proposer and voter keys
+    pub security_level: u8,
+    /// How stake turns into voting weight
+    pub weight_mode: WeightMode,
+    /// Share of eligible weight that must vote
+    pub quorum_percentage: f64,
+    /// Share of cast weight that must approve
+    pub approval_threshold: f64,
+    /// Minimum voting period in blocks
+    pub min_voting_blocks: u64,
+    /// Minimum blocks between the end of voting and activation
+    pub activation_delay_blocks: u64,
+}
+
+impl Default for ChainGovernanceConfig {
+    fn default() -> Self {
+        Self {
+            security_level: 2,
+            weight_mode: WeightMode::LockedStake,
+            quorum_percentage: 33.0,       // 33% quorum required
+            approval_threshold: 66.0,      // 66% approval required
+            min_voting_blocks: 2016,       // ~2 weeks
+            activation_delay_blocks: 288,  // ~2 day time lock
+        }
+    }
+}
+
+/// Weighted vote count for a proposal
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProposalTally {
+    /// Weight in favour
+    pub votes_for: u64,
+    /// Weight against
+    pub votes_against: u64,
+    /// Total weight able to vote at the snapshot
+    pub eligible_weight: u64,
+    /// Number of distinct voters counted
+    pub voters: usize,
+}
+
+impl ProposalTally {
+    /// Percentage of eligible weight that voted
+    pub fn participation(&self) -> f64 {
+        if self.eligible_weight == 0 {
+            return 0.0;
+        }
+        (self.votes_for + self.votes_against) as f64 / self.eligible_weight as f64 * 100.0
+    }
+    
+    /// Percentage of cast weight in favour
+    pub fn approval(&self) -> f64 {
+        let cast = self.votes_for + self.votes_against;
+        if cast == 0 {
+            return 0.0;
+        }
+        self.votes_for as f64 / cast as f64 * 100.0
+    }
+}
+
+/// Proposal as tracked from the chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainProposal {
+    /// Proposal ID: hex of the proposing transaction's hash
+    pub id: String,
+    /// Proposal content
+    pub payload: ProposalPayload,
+    /// Proposer's voter ID
+    pub proposer: [u8; 32],
+    /// Height weights are measured at
+    pub snapshot_height: u64,
+    /// Current status
+    pub status: ProposalStatus,
+    /// Final tally, once voting has ended
+    pub tally: Option<ProposalTally>,
+    /// Height the proposal took effect at
+    pub activated_at: Option<u64>,
+}
+
+/// Consensus parameters governance can change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainParameters {
+    /// Share of the block subsidy owed to the treasury, in basis points
+    pub treasury_allocation_bps: u64,
+}
+
+impl Default for ChainParameters {
+    fn default() -> Self {
+        Self {
+            treasury_allocation_bps: DEFAULT_TREASURY_ALLOCATION_BPS,
+        }
+    }
+}
+
+/// Stake output and its lifetime
+#[derive(Debug, Clone)]
+struct StakeEntry {
+    voter: [u8; 32],
+    amount: u64,
+    created_height: u64,
+    unlock_height: u64,
+    spent_height: Option<u64>,
+}
+
+/// What a block changed in the ledger, to disconnect it again
+#[derive(Debug, Clone)]
+struct GovernanceUndo {
+    /// Hash of the applied block
+    block_hash: [u8; 32],
+    /// Tip before the block was applied
+    prev_tip: Option<u64>,
+    /// Stakes the block created
+    created_stakes: Vec<([u8; 32], u32)>,
+    /// Stakes the block spent
+    spent_stakes: Vec<([u8; 32], u32)>,
+    /// Proposals the block created
+    created_proposals: Vec<String>,
+    /// Votes the block cast: proposal, voter and the vote it replaced
+    replaced_votes: Vec<(String, [u8; 32], Option<bool>)>,
+    /// Proposals the block tallied or activated, as they were before
+    changed_proposals: Vec<ChainProposal>,
+    /// Parameters before the block
+    parameters: ChainParameters,
+}
+
+/// Governance state derived from the chain
+#[derive(Debug, Clone)]
+pub struct GovernanceLedger {
+    /// Governance rules
+    config: ChainGovernanceConfig,
+    /// Stake outputs by outpoint, including spent ones for past snapshots
+    stakes: HashMap<([u8; 32], u32), StakeEntry>,
+    /// Proposals by ID
+    proposals: BTreeMap<String, ChainProposal>,
+    /// Latest vote of each voter, by proposal
+    votes: HashMap<String, HashMap<[u8; 32], bool>>,
+    /// Parameters in effect
+    parameters: ChainParameters,
+    /// Height of the last applied block
+    tip: Option<u64>,
+    /// Undo data of the most recent blocks, oldest first
+    undo: VecDeque<GovernanceUndo>,
+}
+
+impl GovernanceLedger {
+    /// Create an empty ledger
+    pub fn new(config: ChainGovernanceConfig) -> Self {
+        Self {
+            config,
+            stakes: HashMap::new(),
+            proposals: BTreeMap::new(),
+            votes: HashMap::new(),
+            parameters: ChainParameters::default(),
+            tip: None,
+            undo: VecDeque::new(),
+        }
+    }
+    
+    /// Apply the governance records of the next block
+    ///
+    /// Invalid records (bad signatures, no stake, bad schedules, late votes)
+    /// are ignored. Proposals whose voting ends at this height are tallied,
+    /// and approved proposals due at this height are activated.
+    pub fn apply_block(&mut self, block: &Block) {
+        let height = block.height();
+        let mut undo = GovernanceUndo {
+            block_hash: block.hash(),
+            prev_tip: self.tip,
+            created_stakes: Vec::new(),
+            spent_stakes: Vec::new(),
+            created_proposals: Vec::new(),
+            replaced_votes: Vec::new(),
+            changed_proposals: Vec::new(),
+            parameters: self.parameters.clone(),
+        };
+        
+        for tx in block.transactions() {
+            let txid = tx.hash();
+            
+            if !tx.is_coinbase() {
+                for input in tx.inputs() {
+                    let outpoint = (input.prev_tx_hash(), input.prev_output_index());
+                    if let Some(stake) = self.stakes.get_mut(&outpoint) {
+                        if stake.spent_height.is_none() {
+                            stake.spent_height = Some(height);
+                            undo.spent_stakes.push(outpoint);
+                        }
+                    }
+                }
+            }
+            
+            for (index, output) in tx.outputs().iter().enumerate() {
+                let record = match GovernanceRecord::from_script(&output.pub_key_script) {
+                    Ok(Some(record)) => record,
+                    _ => continue,
+                };
+                
+                match record {
+                    GovernanceRecord::Stake(lock) => {
+                        if lock.unlock_height > height && output.amount() > 0 {
+                            undo.created_stakes.push((txid, index as u32));
+                            self.stakes.insert((txid, index as u32), StakeEntry {
+                                voter: lock.voter,
+                                amount: output.amount(),
+                                created_height: height,
+                                unlock_height: lock.unlock_height,
+                                spent_height: None,
+                            });
+                        }
+                    }
+                    GovernanceRecord::Proposal(proposal) => {
+                        let id = hex::encode(txid);
+                        if self.check_proposal(&proposal, height).is_ok() && !self.proposals.contains_key(&id) {
+                            undo.created_proposals.push(id.clone());
+                            self.proposals.insert(id.clone(), ChainProposal {
+                                id,
+                                proposer: proposal.proposer(),
+                                payload: proposal.payload,
+                                snapshot_height: height,
+                                status: ProposalStatus::Active,
+                                tally: None,
+                                activated_at: None,
+                            });
+                        }
+                    }
+                    GovernanceRecord::Vote(vote) => self.record_vote(vote, height, &mut undo),
+                }
+            }
+        }
+        
+        self.close_voting(height, &mut undo);
+        self.activate(height, &mut undo);
+        self.tip = Some(height);
+        self.undo.push_back(undo);
+        if self.undo.len() > LEDGER_UNDO_DEPTH {
+            self.undo.pop_front();
+        }
+    }
+    
+    /// Revert the last applied block, which must be `block`
+    ///
+    /// Only the last `LEDGER_UNDO_DEPTH` blocks can be disconnected; deeper
+    /// reorganizations need the ledger rebuilt from the chain.
+    pub fn disconnect_block(&mut self, block: &Block) -> Result<(), ChainGovernanceError> {
+        let undo = match self.undo.back() {
+            Some(undo) if undo.block_hash == block.hash() => self.undo.pop_back().expect("undo entry exists"),
+            _ => return Err(ChainGovernanceError::DisconnectFailed(
+                format!("block {} is not the ledger tip", hex::encode(block.hash()))
+            )),
+        };
+        
+        // Oldest snapshot last, so a proposal changed twice ends up as before the block
+        for proposal in undo.changed_proposals.into_iter().rev() {
+            self.proposals.insert(proposal.id.clone(), proposal);
+        }
+        self.parameters = undo.parameters;
+        
+        for (proposal_id, voter, previous) in undo.replaced_votes.into_iter().rev() {
+            let votes = self.votes.entry(proposal_id.clone()).or_default();
+            match previous {
+                Some(support) => {
+                    votes.insert(voter, support);
+                }
+                None => {
+                    votes.remove(&voter);
+                }
+            }
+            if votes.is_empty() {
+                self.votes.remove(&proposal_id);
+            }
+        }
+        for proposal_id in undo.created_proposals {
+            self.proposals.remove(&proposal_id);
+            self.votes.remove(&proposal_id);
+        }
+        
+        for outpoint in undo.created_stakes {
+            self.stakes.remove(&outpoint);
+        }
+        for outpoint in undo.spent_stakes {
+            if let Some(stake) = self.stakes.get_mut(&outpoint) {
+                stake.spent_height = None;
+            }
+        }
+        
+        self.tip = undo.prev_tip;
+        Ok(())
+    }
+    
+    /// Check a proposal mined at `height`
+    pub fn check_proposal(&self, proposal: &SignedProposal, height: u64) -> Result<(), ChainGovernanceError> {
+        proposal.verify(self.config.security_level)?;
+        
+        let payload = &proposal.payload;
+        if payload.voting_end_height < height + self.config.min_voting_blocks {
+            return Err(ChainGovernanceError::InvalidSchedule(
+                format!("Voting must last at least {} blocks", self.config.min_voting_blocks)
+            ));
+        }
+        if payload.activation_height < payload.voting_end_height + self.config.activation_delay_blocks {
+            return Err(ChainGovernanceError::InvalidSchedule(
+                format!("Activation must be at least {} blocks after voting ends", self.config.activation_delay_blocks)
+            ));
+        }
+        if let ProposalType::ChangeFeeAllocation { new_percentage } = payload.proposal_type {
+            if !(0.0..=100.0).contains(&new_percentage) {
+                return Err(ChainGovernanceError::InvalidProposal(
+                    format!("Fee allocation percentage must be between 0 and 100, got {}", new_percentage)
+                ));
+            }
+        }
+        if self.weight_at(&proposal.proposer(), height) == 0 {
+            return Err(ChainGovernanceError::InvalidProposal("Proposer has no stake".to_string()));
+        }
+        Ok(())
+    }
+    
+    fn record_vote(&mut self, vote: SignedVote, height: u64, undo: &mut GovernanceUndo) {
+        let snapshot = match self.proposals.get(&vote.proposal_id) {
+            Some(proposal) if proposal.status == ProposalStatus::Active && height <= proposal.payload.voting_end_height => {
+                proposal.snapshot_height
+            }
+            _ => return,
+        };
+        if vote.verify(self.config.security_level).is_err() || self.weight_at(&vote.voter(), snapshot) == 0 {
+            return;
+        }
+        
+        // A later vote replaces the voter's earlier one
+        let voter = vote.voter();
+        let previous = self.votes.entry(vote.proposal_id.clone())
+            .or_default()
+            .insert(voter, vote.support);
+        undo.replaced_votes.push((vote.proposal_id, voter, previous));
+    }
+    
+    fn close_voting(&mut self, height: u64, undo: &mut GovernanceUndo) {
+        let ended: Vec<String> = self.proposals.values()
+            .filter(|p| p.status == ProposalStatus::Active && p.payload.voting_end_height <= height)
+            .map(|p| p.id.clone())
+            .collect();
+        
+        for id in ended {
+            let tally = self.tally(&id).unwrap_or_default();
+            let approved = tally.participation() >= self.config.quorum_percentage
+                && tally.approval() >= self.config.approval_threshold;
+            
+            let proposal = self.proposals.get_mut(&id).expect("ended proposal exists");
+            undo.changed_proposals.push(proposal.clone());
+            proposal.status = if approved { ProposalStatus::Approved } else { ProposalStatus::Rejected };
+            proposal.tally = Some(tally);
+        }
+    }
+    
+    fn activate(&mut self, height: u64, undo: &mut GovernanceUndo) {
+        for proposal in self.proposals.values_mut() {
+            if proposal.status != ProposalStatus::Approved
+                || proposal.activated_at.is_some()
+                || proposal.payload.activation_height > height
+            {
+                continue;
+            }
+            
+            undo.changed_proposals.push(proposal.clone());
+            proposal.activated_at = Some(height);
+            // Parameter changes execute here; spends execute through the treasury
+            if let ProposalType::ChangeFeeAllocation { new_percentage } = proposal.payload.proposal_type {
+                self.parameters.treasury_allocation_bps = (new_percentage * 100.0).round() as u64;
+                proposal.status = ProposalStatus::Executed;
+            }
+        }
+    }
+    
+    /// Voting weight of `voter` at `snapshot`
+    pub fn weight_at(&self, voter: &[u8; 32], snapshot: u64) -> u64 {
+        self.stakes.values()
+            .filter(|stake| stake.voter == *voter)
+            .map(|stake| self.stake_weight(stake, snapshot))
+            .sum()
+    }
+    
+    fn stake_weight(&self, stake: &StakeEntry, snapshot: u64) -> u64 {
+        let live = stake.created_height <= snapshot
+            && stake.unlock_height > snapshot
+            && stake.spent_height.is_none_or(|spent| spent > snapshot);
+        if !live {
+            return 0;
+        }
+        
+        match self.config.weight_mode {
+            WeightMode::LockedStake => stake.amount,
+            WeightMode::CoinAge { full_weight_after } => {
+                let age = (snapshot - stake.created_height).min(full_weight_after);
+                if full_weight_after == 0 {
+                    stake.amount
+                } else {
+                    (stake.amount as u128 * age as u128 / full_weight_after as u128) as u64
+                }
+            }
+        }
+    }
+    
+    /// Current tally of a proposal, weighted at its snapshot
+    pub fn tally(&self, proposal_id: &str) -> Option<ProposalTally> {
+        let proposal = self.proposals.get(proposal_id)?;
+        if let Some(tally) = &proposal.tally {
+            return Some(tally.clone());
+        }
+        
+        let snapshot = proposal.snapshot_height;
+        let mut tally = ProposalTally {
+            eligible_weight: self.stakes.values().map(|stake| self.stake_weight(stake, snapshot)).sum(),
+            ..ProposalTally::default()
+        };
+        for (voter, support) in self.votes.get(proposal_id).into_iter().flatten() {
+            let weight = self.weight_at(voter, snapshot);
+            if *support {
+                tally.votes_for += weight;
+            } else {
+                tally.votes_against += weight;
+            }
+            tally.voters += 1;
+        }
+        Some(tally)
+    }
+    
+    /// Get a proposal by ID
+    pub fn proposal(&self, proposal_id: &str) -> Option<&ChainProposal> {
+        self.proposals.get(proposal_id)
+    }
+    
+    /// All proposals, by ID
+    pub fn proposals(&self) -> impl Iterator<Item = &ChainProposal> {
+        self.proposals.values()
+    }
+    
+    /// Parameters in effect
+    pub fn parameters(&self) -> &ChainParameters {
+        &self.parameters
+    }
+    
+    /// Governance rules
+    pub fn config(&self) -> &ChainGovernanceConfig {
+        &self.config
+    }
+    
+    /// Height of the last applied block
+    pub fn tip(&self) -> Option<u64> {
+        self.tip
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+    
+    fn keypair() -> QuantumKeyPair {
+        QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap()
+    }
+    
+    fn record_tx(seed: u8, records: Vec<(u64, GovernanceRecord)>) -> Transaction {
+        Transaction::new(
+            1,
+            vec![TransactionInput::new([seed; 32], 0, Vec::new(), 0xffffffff)],
+            records.into_iter().map(|(value, record)| TransactionOutput::new(value, record.to_script())).collect(),
+            0,
+        )
+    }
+    
+    fn block(height: u64, transactions: Vec<Transaction>) -> Block {
+        let mut block = Block::new_with_params(1, [height as u8; 32], transactions, 0x207fffff);
+        block.set_height(height);
+        block
+    }
+    
+    fn stake(key: &QuantumKeyPair) -> GovernanceRecord {
+        GovernanceRecord::Stake(StakeLock { voter: voter_id(&key.public_key), unlock_height: 1_000 })
+    }
+    
+    #[test]
+    fn test_stake_weighted_vote_and_activation() {
+        let config = ChainGovernanceConfig {
+            min_voting_blocks: 2,
+            activation_delay_blocks: 1,
+            ..ChainGovernanceConfig::default()
+        };
+        let mut ledger = GovernanceLedger::new(config);
+        let (alice, bob, carol) = (keypair(), keypair(), keypair());
+        
+        ledger.apply_block(&block(1, vec![
+            record_tx(1, vec![(700, stake(&alice)), (300, stake(&bob))]),
+        ]));
+        
+        let proposal = SignedProposal::sign(ProposalPayload {
+            title: "Raise treasury allocation".to_string(),
+            description: "Allocate 3% of the subsidy".to_string(),
+            proposal_type: ProposalType::ChangeFeeAllocation { new_percentage: 3.0 },
+            voting_end_height: 4,
+            activation_height: 5,
+            url: None,
+        }, &alice).unwrap();
+        let proposal_tx = record_tx(2, vec![(0, GovernanceRecord::Proposal(proposal))]);
+        let id = hex::encode(proposal_tx.hash());
+        ledger.apply_block(&block(2, vec![proposal_tx]));
+        assert_eq!(ledger.proposal(&id).unwrap().status, ProposalStatus::Active);
+        
+        // Carol's stake arrives after the snapshot and does not count
+        let mut forged = SignedVote::sign(&id, false, &bob).unwrap();
+        forged.support = true;
+        ledger.apply_block(&block(3, vec![
+            record_tx(3, vec![(5_000, stake(&carol))]),
+            record_tx(4, vec![
+                (0, GovernanceRecord::Vote(SignedVote::sign(&id, true, &alice).unwrap())),
+                (0, GovernanceRecord::Vote(SignedVote::sign(&id, false, &carol).unwrap())),
+                (0, GovernanceRecord::Vote(forged)),
+            ]),
+        ]));
+        
+        let live = ledger.tally(&id).unwrap();
+        assert_eq!(live.votes_for, 700);
+        assert_eq!(live.votes_against, 0);
+        assert_eq!(live.eligible_weight, 1_000);
+        
+        ledger.apply_block(&block(4, vec![
+            record_tx(5, vec![(0, GovernanceRecord::Vote(SignedVote::sign(&id, false, &bob).unwrap()))]),
+        ]));
+        let closed = ledger.proposal(&id).unwrap();
+        assert_eq!(closed.status, ProposalStatus::Approved);
+        assert_eq!(closed.tally.as_ref().unwrap().votes_against, 300);
+        assert_eq!(ledger.parameters().treasury_allocation_bps, DEFAULT_TREASURY_ALLOCATION_BPS);
+        
+        ledger.apply_block(&block(5, vec![]));
+        assert_eq!(ledger.proposal(&id).unwrap().status, ProposalStatus::Executed);
+        assert_eq!(ledger.parameters().treasury_allocation_bps, 300);
+    }
+    
+    #[test]
+    fn test_disconnect_block() {
+        let mut ledger = GovernanceLedger::new(ChainGovernanceConfig {
+            min_voting_blocks: 2,
+            activation_delay_blocks: 1,
+            ..ChainGovernanceConfig::default()
+        });
+        let (alice, bob) = (keypair(), keypair());
+        
+        let proposal = SignedProposal::sign(ProposalPayload {
+            title: "Raise treasury allocation".to_string(),
+            description: "Allocate 3% of the subsidy".to_string(),
+            proposal_type: ProposalType::ChangeFeeAllocation { new_percentage: 3.0 },
+            voting_end_height: 4,
+            activation_height: 5,
+            url: None,
+        }, &alice).unwrap();
+        let proposal_tx = record_tx(2, vec![(0, GovernanceRecord::Proposal(proposal))]);
+        let id = hex::encode(proposal_tx.hash());
+        let blocks = vec![
+            block(1, vec![record_tx(1, vec![(700, stake(&alice)), (300, stake(&bob))])]),
+            block(2, vec![proposal_tx]),
+            block(3, vec![record_tx(3, vec![(0, GovernanceRecord::Vote(SignedVote::sign(&id, true, &alice).unwrap()))])]),
+            block(4, vec![record_tx(4, vec![
+                (0, GovernanceRecord::Vote(SignedVote::sign(&id, false, &alice).unwrap())),
+                (0, GovernanceRecord::Vote(SignedVote::sign(&id, false, &bob).unwrap())),
+            ])]),
+            block(5, vec![]),
+        ];
+        for block in &blocks {
+            ledger.apply_block(block);
+        }
+        assert_eq!(ledger.proposal(&id).unwrap().status, ProposalStatus::Rejected);
+        assert!(ledger.disconnect_block(&blocks[3]).is_err());
+        
+        // Undo the tally and both replacement votes
+        ledger.disconnect_block(&blocks[4]).unwrap();
+        ledger.disconnect_block(&blocks[3]).unwrap();
+        assert_eq!(ledger.tip(), Some(3));
+        let proposal = ledger.proposal(&id).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Active);
+        assert!(proposal.tally.is_none());
+        let tally = ledger.tally(&id).unwrap();
+        assert_eq!((tally.votes_for, tally.votes_against, tally.voters), (700, 0, 1));
+        
+        // A different block 4 approves and block 5 activates the change
+        let approving = block(4, vec![record_tx(5, vec![
+            (0, GovernanceRecord::Vote(SignedVote::sign(&id, true, &bob).unwrap())),
+        ])]);
+        ledger.apply_block(&approving);
+        ledger.apply_block(&blocks[4]);
+        assert_eq!(ledger.parameters().treasury_allocation_bps, 300);
+        ledger.disconnect_block(&blocks[4]).unwrap();
+        assert_eq!(ledger.proposal(&id).unwrap().status, ProposalStatus::Approved);
+        assert_eq!(ledger.parameters().treasury_allocation_bps, DEFAULT_TREASURY_ALLOCATION_BPS);
+        
+        // Back to before the proposal and the stakes
+        for block in [&approving, &blocks[2], &blocks[1], &blocks[0]] {
+            ledger.disconnect_block(block).unwrap();
+        }
+        assert!(ledger.proposal(&id).is_none());
+        assert_eq!(ledger.weight_at(&voter_id(&alice.public_key), 1), 0);
+        assert_eq!(ledger.tip(), None);
+    }
+    
+    #[test]
+    fn test_coin_age_weight() {
+        let key = keypair();
+        let mut ledger = GovernanceLedger::new(ChainGovernanceConfig {
+            weight_mode: WeightMode::CoinAge { full_weight_after: 10 },
+            ..ChainGovernanceConfig::default()
+        });
+        let stake_tx = record_tx(1, vec![(1_000, stake(&key))]);
+        let outpoint = stake_tx.hash();
+        ledger.apply_block(&block(10, vec![stake_tx]));
+        
+        let voter = voter_id(&key.public_key);
+        assert_eq!(ledger.weight_at(&voter, 10), 0);
+        assert_eq!(ledger.weight_at(&voter, 15), 500);
+        assert_eq!(ledger.weight_at(&voter, 30), 1_000);
+        
+        // Spending the stake removes its weight from later snapshots only
+        let spend = Transaction::new(
+            1,
+            vec![TransactionInput::new(outpoint, 0, Vec::new(), 0xffffffff)],
+            vec![TransactionOutput::new(1_000, vec![0xaa])],
+            0,
+        );
+        ledger.apply_block(&block(20, vec![spend]));
+        assert_eq!(ledger.weight_at(&voter, 19), 900);
+        assert_eq!(ledger.weight_at(&voter, 20), 0);
+    }
+}
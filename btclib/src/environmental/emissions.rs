@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use thiserror::Error;
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc, NaiveDateTime};
@@ -313,6 +313,96 @@ pub struct Emissions {
     pub confidence_level: Option<f64>,
 }
 
+/// Which grid intensity energy is charged at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IntensityMode {
+    /// Average intensity of all generation on the grid
+    Average,
+    /// Intensity of the generator that responds to extra load
+    Marginal,
+}
+
+/// Grid carbon intensity for one hour (gCO2e/kWh)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HourlyIntensity {
+    /// Start of the hour
+    pub hour: DateTime<Utc>,
+    /// Average intensity
+    pub average: f64,
+    /// Marginal intensity, if known
+    pub marginal: Option<f64>,
+}
+
+impl HourlyIntensity {
+    /// Intensity under a mode; marginal falls back to average when unknown
+    pub fn intensity(&self, mode: IntensityMode) -> f64 {
+        match mode {
+            IntensityMode::Average => self.average,
+            IntensityMode::Marginal => self.marginal.unwrap_or(self.average),
+        }
+    }
+}
+
+/// Energy a miner used to mine one block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockEnergyRecord {
+    /// Block height
+    pub height: u64,
+    /// When the block was mined
+    pub timestamp: DateTime<Utc>,
+    /// Energy used to mine the block in kWh
+    pub energy_kwh: f64,
+}
+
+/// Emissions attributed to one hour
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HourlyAttribution {
+    /// Start of the hour
+    pub hour: DateTime<Utc>,
+    /// Energy used during the hour in kWh
+    pub energy_kwh: f64,
+    /// Intensity the energy was charged at (gCO2e/kWh)
+    pub intensity: f64,
+    /// Emissions in kg CO2e
+    pub emissions_kg: f64,
+    /// Whether the intensity came from the hourly series rather than a static factor
+    pub hourly_data: bool,
+}
+
+/// A miner's emissions with each block charged at the intensity of its hour
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MinerFootprint {
+    /// Miner identifier
+    pub miner_id: String,
+    /// Grid region the miner draws from
+    pub region: Region,
+    /// Intensity mode used
+    pub mode: IntensityMode,
+    /// Total energy in kWh
+    pub energy_kwh: f64,
+    /// Total emissions in kg CO2e
+    pub emissions_kg: f64,
+    /// Per-hour breakdown, in time order
+    pub hourly: Vec<HourlyAttribution>,
+}
+
+impl MinerFootprint {
+    /// Energy-weighted intensity (gCO2e/kWh)
+    pub fn effective_intensity(&self) -> f64 {
+        if self.energy_kwh > 0.0 {
+            self.emissions_kg * 1000.0 / self.energy_kwh
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Truncate a time to the start of its hour
+pub fn hour_start(time: DateTime<Utc>) -> DateTime<Utc> {
+    let timestamp = time.timestamp();
+    DateTime::from_timestamp(timestamp - timestamp.rem_euclid(3600), 0).unwrap_or(time)
+}
+
 /// Configuration for emissions tracking
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmissionsConfig {
@@ -393,6 +483,8 @@ pub struct EmissionsTracker {
     region_emission_factors: HashMap<Region, EmissionFactor>,
     /// Alternative emission factors (marginal, etc.)
     alt_emission_factors: HashMap<(Region, EmissionsFactorType), EmissionFactor>,
+    /// Hourly grid intensity by region, keyed by the hour's Unix timestamp
+    hourly_intensity: HashMap<Region, BTreeMap<i64, HourlyIntensity>>,
     /// Reported renewable energy percentage by mining pool
     pool_energy_info: HashMap<PoolId, PoolEnergyInfo>,
     /// Global configuration for the emissions tracker
@@ -418,6 +510,7 @@ impl EmissionsTracker {
             region_hashrates: HashMap::new(),
             region_emission_factors: HashMap::new(),
             alt_emission_factors: HashMap::new(),
+            hourly_intensity: HashMap::new(),
             pool_energy_info: HashMap::new(),
             config,
             http_client,
@@ -728,6 +821,97 @@ impl EmissionsTracker {
         })
     }
     
+    /// Record the grid intensity of a region for the hour containing `time`
+    pub fn record_hourly_intensity(&mut self, region: Region, time: DateTime<Utc>, average: f64, marginal: Option<f64>) {
+        let hour = hour_start(time);
+        self.hourly_intensity
+            .entry(region)
+            .or_default()
+            .insert(hour.timestamp(), HourlyIntensity { hour, average, marginal });
+    }
+    
+    /// Recorded hourly intensities of a region within `[start, end)`
+    pub fn hourly_intensities(&self, region: &Region, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<HourlyIntensity> {
+        self.hourly_intensity
+            .get(region)
+            .map(|series| series.range(hour_start(start).timestamp()..end.timestamp()).map(|(_, hour)| *hour).collect())
+            .unwrap_or_default()
+    }
+    
+    /// Intensity mode selected by the configuration
+    pub fn intensity_mode(&self) -> IntensityMode {
+        if self.config.use_marginal_emissions {
+            IntensityMode::Marginal
+        } else {
+            IntensityMode::Average
+        }
+    }
+    
+    /// Grid intensity (gCO2e/kWh) of a region at a time
+    ///
+    /// Uses the hourly series when the hour is recorded, otherwise the
+    /// region's static factor. The flag is true for hourly data.
+    pub fn intensity_at(&self, region: &Region, time: DateTime<Utc>, mode: IntensityMode) -> (f64, bool) {
+        let hourly = self.hourly_intensity
+            .get(region)
+            .and_then(|series| series.get(&hour_start(time).timestamp()));
+        if let Some(hourly) = hourly {
+            return (hourly.intensity(mode), true);
+        }
+        
+        let factor = match mode {
+            IntensityMode::Average => self.get_best_emissions_factor(region),
+            IntensityMode::Marginal => self.get_marginal_emissions_factor(region),
+        };
+        
+        match factor {
+            Some(factor) => (factor.grid_emissions_factor * 1000.0, false), // Convert tonnes/MWh to g/kWh
+            None => (self.config.default_emission_factor, false),
+        }
+    }
+    
+    /// Attribute each block's energy to the hour it was mined
+    pub fn calculate_miner_footprint(
+        &self,
+        miner_id: &str,
+        region: &Region,
+        blocks: &[BlockEnergyRecord],
+        mode: IntensityMode,
+    ) -> Result<MinerFootprint, EmissionsError> {
+        let mut hours: BTreeMap<i64, (DateTime<Utc>, f64)> = BTreeMap::new();
+        for block in blocks {
+            if !block.energy_kwh.is_finite() || block.energy_kwh < 0.0 {
+                return Err(EmissionsError::DataSourceError(
+                    format!("Invalid energy for block {}: {}", block.height, block.energy_kwh)
+                ));
+            }
+            let hour = hour_start(block.timestamp);
+            hours.entry(hour.timestamp()).or_insert((hour, 0.0)).1 += block.energy_kwh;
+        }
+        
+        let hourly: Vec<HourlyAttribution> = hours.into_values()
+            .map(|(hour, energy_kwh)| {
+                let (intensity, hourly_data) = self.intensity_at(region, hour, mode);
+                HourlyAttribution {
+                    hour,
+                    energy_kwh,
+                    intensity,
+                    emissions_kg: energy_kwh * intensity / 1000.0,
+                    hourly_data,
+                }
+            })
+            .collect();
+        
+        Ok(MinerFootprint {
+            miner_id: miner_id.to_string(),
+            region: region.clone(),
+            mode,
+            energy_kwh: hourly.iter().map(|hour| hour.energy_kwh).sum(),
+            emissions_kg: hourly.iter().map(|hour| hour.emissions_kg).sum(),
+            hourly,
+        })
+    }
+    
     /// Verify renewable energy certificate claims through oracle consensus
     pub fn verify_rec_claim(&self, certificate: &RECCertificateInfo) -> VerificationStatus {
         // Use the oracle system for real verification
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
+use tracing::warn;
+
+use crate::environmental::grid_data::{ApiFormat, GridDataError, GridDataSource};
 
 /// Grid emission factor data sources
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -14,6 +17,8 @@ pub enum EmissionFactorSource {
     WattTimeMOER,
     /// WattTime Operating Emissions Rate V3.2
     WattTimeOER,
+    /// Live reading from a `GridDataSource`
+    LiveGrid,
 }
 
 /// Structure to represent emission factors for a specific country
@@ -67,10 +72,13 @@ impl EmissionsFactorDatabase {
     }
     
     /// Get best available emission factor for a country,
-    /// prioritizing WattTime > IFI > IEA data
+    /// prioritizing live grid > WattTime > IFI > IEA data
     pub fn get_best_factor(&self, country_code: &str) -> f64 {
         if let Some(country) = self.countries.get(country_code) {
-            // Priority order: WattTime MOER > WattTime OER > IFI > IEA 2021 > IEA 2020
+            // Priority order: Live grid > WattTime MOER > WattTime OER > IFI > IEA 2021 > IEA 2020
+            if let Some(live) = country.factors.get(&EmissionFactorSource::LiveGrid) {
+                return *live;
+            }
             if let Some(wt_moer) = country.factors.get(&EmissionFactorSource::WattTimeMOER) {
                 return *wt_moer;
             }
@@ -190,6 +198,39 @@ impl EmissionsFactorDatabase {
         Ok(())
     }
     
+    /// Update live factors from a grid data source returning `format` responses
+    ///
+    /// Each country is fetched through the zones `format` maps it to and gets
+    /// the average intensity of the zones with data. Countries without zones,
+    /// without data or whose zones fail keep their static factors; a failing
+    /// zone is logged and skipped. Returns the number of countries updated.
+    pub async fn update_from_source(&mut self, source: &dyn GridDataSource, format: ApiFormat) -> Result<usize, String> {
+        let mut codes: Vec<String> = self.countries.keys().cloned().collect();
+        codes.sort();
+        
+        let mut updated = 0;
+        for code in codes {
+            let mut intensities = Vec::new();
+            for zone in format.country_zones(&code) {
+                match source.fetch(zone).await {
+                    Ok(reading) => intensities.push(reading.carbon_intensity),
+                    Err(GridDataError::NoData(_)) => {}
+                    Err(e) => warn!("Skipping zone {} of {} from {}: {}", zone, code, source.name(), e),
+                }
+            }
+            
+            if intensities.is_empty() {
+                continue;
+            }
+            let average = intensities.iter().sum::<f64>() / intensities.len() as f64;
+            self.update_factor(&code, EmissionFactorSource::LiveGrid, average)?;
+            updated += 1;
+        }
+        
+        self.last_updated = chrono::Utc::now().timestamp();
+        Ok(updated)
+    }
+    
     /// Get countries with lowest emission factors
     pub fn get_greenest_countries(&self, count: usize) -> Vec<(String, f64)> {
         let mut countries: Vec<(String, f64)> = self.countries
@@ -264,4 +305,40 @@ mod tests {
         let result = db.update_factor("XYZ", EmissionFactorSource::IEA2021, 500.0);
         assert!(result.is_err());
     }
+    
+    #[tokio::test]
+    async fn test_update_from_source() {
+        use crate::environmental::grid_data::{GridFixture, RecordedResponse, ReplayGridSource};
+        
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/grid");
+        let mut fixture = GridFixture::load(fixtures.join("electricity_maps.json")).unwrap();
+        
+        // A malformed zone is skipped rather than aborting the update
+        fixture.responses.push(RecordedResponse {
+            zone: "FR".to_string(),
+            body: serde_json::json!({ "zone": "FR" }),
+        });
+        let source = ReplayGridSource::new(fixture);
+        
+        let mut db = EmissionsFactorDatabase::new();
+        assert_eq!(db.update_from_source(&source, ApiFormat::ElectricityMaps).await, Ok(3));
+        
+        // ISO-3 countries pick up their zone's live reading
+        assert_eq!(db.get_best_factor("DEU"), 302.0);
+        assert_eq!(db.get_best_factor("USA"), 388.0);
+        assert_eq!(db.get_best_factor("AUS"), 560.0);
+        
+        // Countries without data or with a failing zone keep their static factors
+        assert_eq!(db.get_best_factor("FRA"), 384.0);
+        assert_eq!(db.get_best_factor("GBR"), 428.0);
+        
+        // WattTime balancing authorities and UK regions map to their countries
+        let watttime = ReplayGridSource::load(fixtures.join("watttime.json")).unwrap();
+        assert_eq!(db.update_from_source(&watttime, ApiFormat::WattTime).await, Ok(1));
+        assert!(db.get_best_factor("USA") > 400.0);
+        
+        let uk = ReplayGridSource::load(fixtures.join("uk_carbon_intensity.json")).unwrap();
+        assert_eq!(db.update_from_source(&uk, ApiFormat::UkCarbonIntensity).await, Ok(1));
+        assert_eq!(db.get_best_factor("GBR"), 180.0);
+    }
 } 
\ No newline at end of file
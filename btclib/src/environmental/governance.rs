@@ -5,7 +5,10 @@ use thiserror::Error;
 use uuid;
 
 use crate::environmental::treasury::{EnvironmentalTreasury, TreasuryAccountType, TreasuryError};
+use crate::environmental::treasury_ledger::{TreasuryAuthorization, TreasuryCommittee, TreasuryLedger};
+use crate::crypto::quantum::QuantumKeyPair;
 use crate::crypto::signature::Signature;
+use crate::types::transaction::Transaction;
 
 /// Error types for environmental governance operations
 #[derive(Error, Debug)]
@@ -101,6 +104,19 @@ pub enum ProposalType {
     },
 }
 
+impl ProposalType {
+    /// Amount the proposal may pay out of the on-chain treasury
+    pub fn spend_amount(&self) -> Option<u64> {
+        match self {
+            ProposalType::PurchaseRECs { amount, .. }
+            | ProposalType::PurchaseOffsets { amount, .. }
+            | ProposalType::FundProject { amount, .. } => Some(*amount),
+            ProposalType::Other { amount, .. } => *amount,
+            ProposalType::TreasuryAllocation { .. } | ProposalType::ChangeFeeAllocation { .. } => None,
+        }
+    }
+}
+
 /// Environmental governance proposal
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnvironmentalProposal {
@@ -193,6 +209,8 @@ pub struct EnvironmentalGovernance {
     config: GovernanceConfig,
     /// Treasury reference
     treasury: EnvironmentalTreasury,
+    /// Treasury balance derived from the chain, once synced
+    chain_balance: Option<u64>,
 }
 
 impl EnvironmentalGovernance {
@@ -203,9 +221,21 @@ impl EnvironmentalGovernance {
             historical_proposals: HashMap::new(),
             config,
             treasury,
+            chain_balance: None,
         }
     }
     
+    /// Use the chain-derived treasury balance when checking proposal amounts
+    pub fn sync_treasury_ledger(&mut self, ledger: &TreasuryLedger) {
+        self.chain_balance = Some(ledger.balance());
+    }
+    
+    /// Balance proposals can draw on: the chain's once synced, else the in-memory treasury's
+    pub fn available_balance(&self) -> u64 {
+        self.chain_balance
+            .unwrap_or_else(|| self.treasury.get_balance(Some(TreasuryAccountType::Main)))
+    }
+    
     /// Create a new proposal
     pub fn create_proposal(
         &mut self,
@@ -231,10 +261,10 @@ impl EnvironmentalGovernance {
                 }
                 
                 // Check treasury balance
-                if *amount > self.treasury.get_balance(Some(TreasuryAccountType::Main)) {
+                if *amount > self.available_balance() {
                     return Err(GovernanceError::InvalidProposal(
                         format!("Allocation amount {} exceeds available balance {}", 
-                                amount, self.treasury.get_balance(Some(TreasuryAccountType::Main)))
+                                amount, self.available_balance())
                     ));
                 }
             },
@@ -263,19 +293,19 @@ impl EnvironmentalGovernance {
                 }
                 
                 // Check treasury balance
-                if *amount > self.treasury.get_balance(Some(TreasuryAccountType::Main)) {
+                if *amount > self.available_balance() {
                     return Err(GovernanceError::InvalidProposal(
                         format!("Amount {} exceeds available balance {}", 
-                                amount, self.treasury.get_balance(Some(TreasuryAccountType::Main)))
+                                amount, self.available_balance())
                     ));
                 }
             },
             ProposalType::Other { amount, .. } => {
                 if let Some(amount) = amount {
-                    if *amount > 0 && *amount > self.treasury.get_balance(Some(TreasuryAccountType::Main)) {
+                    if *amount > 0 && *amount > self.available_balance() {
                         return Err(GovernanceError::InvalidProposal(
                             format!("Amount {} exceeds available balance {}", 
-                                    amount, self.treasury.get_balance(Some(TreasuryAccountType::Main)))
+                                    amount, self.available_balance())
                         ));
                     }
                 }
@@ -487,6 +517,57 @@ impl EnvironmentalGovernance {
         Ok(())
     }
     
+    /// Authorize an on-chain treasury spend executing an approved proposal
+    ///
+    /// `tx` must pay out no more than the proposal's amount; outputs back to
+    /// the committee script count as change. Each `(index, key)` signs as
+    /// that committee member, and the result goes in the signature script of
+    /// every treasury input.
+    pub fn authorize_treasury_spend(
+        &self,
+        proposal_id: &str,
+        tx: &Transaction,
+        committee: &TreasuryCommittee,
+        signers: &[(u16, &QuantumKeyPair)],
+    ) -> Result<TreasuryAuthorization, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id)
+            .ok_or_else(|| GovernanceError::ProposalNotFound(proposal_id.to_string()))?;
+        
+        if proposal.status != ProposalStatus::Approved {
+            return Err(GovernanceError::InvalidProposal(
+                format!("Cannot spend for proposal {} with status {:?}", proposal_id, proposal.status)
+            ));
+        }
+        
+        let time_lock_expired = proposal.voting_ends_at + chrono::Duration::days(self.config.time_lock_days as i64) < Utc::now();
+        if !time_lock_expired {
+            return Err(GovernanceError::InvalidProposal(
+                format!("Time lock for proposal {} has not expired yet", proposal_id)
+            ));
+        }
+        
+        let approved = proposal.proposal_type.spend_amount()
+            .ok_or_else(|| GovernanceError::InvalidAllocation(
+                format!("Proposal {} does not pay out of the treasury", proposal_id)
+            ))?;
+        let script = committee.script();
+        let paid_out: u64 = tx.outputs().iter()
+            .filter(|output| output.pub_key_script != script)
+            .map(|output| output.amount())
+            .sum();
+        if paid_out > approved {
+            return Err(GovernanceError::InvalidAllocation(
+                format!("Spend of {} exceeds the {} approved by proposal {}", paid_out, approved, proposal_id)
+            ));
+        }
+        
+        let mut authorization = TreasuryAuthorization::new(proposal_id);
+        for (signer, keypair) in signers {
+            committee.sign_spend(&mut authorization, tx, *signer, keypair)?;
+        }
+        Ok(authorization)
+    }
+    
     /// Cancel a proposal (only proposer or admin can cancel)
     pub fn cancel_proposal(&mut self, proposal_id: &str, canceller: &str) -> Result<(), GovernanceError> {
         // Get the proposal
@@ -705,4 +786,77 @@ mod tests {
         assert_eq!(proposal.votes_for.len(), 2);
         assert_eq!(proposal.votes_against.len(), 1);
     }
+    
+    #[test]
+    fn test_treasury_spend_authorization() {
+        use crate::crypto::quantum::{QuantumParameters, QuantumScheme};
+        use crate::types::block::Block;
+        use crate::types::transaction::{TransactionInput, TransactionOutput};
+        
+        let mut governance = create_test_governance();
+        let keys: Vec<_> = (0..2)
+            .map(|_| QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap())
+            .collect();
+        let committee = TreasuryCommittee {
+            threshold: 2,
+            security_level: 2,
+            members: keys.iter().map(|key| key.public_key.clone()).collect(),
+        };
+        
+        // Fund the treasury from a coinbase allocation on chain
+        let coinbase = Transaction::new(
+            1,
+            vec![TransactionInput::new_coinbase(vec![1])],
+            vec![TransactionOutput::new(10_000, committee.script())],
+            0,
+        );
+        let mut ledger = TreasuryLedger::new(committee.script());
+        ledger.apply_block(&Block::new_with_params(1, [0u8; 32], vec![coinbase], 0x207fffff));
+        governance.sync_treasury_ledger(&ledger);
+        assert_eq!(governance.available_balance(), 10_000);
+        
+        let proposal_id = governance.create_proposal(
+            "Buy RECs".to_string(),
+            "Purchase RECs for Q3".to_string(),
+            ProposalType::PurchaseRECs { amount: 1_000, provider: "GreenCo".to_string() },
+            "voter1".to_string(),
+            None,
+            false,
+        ).unwrap();
+        
+        let spend = |amount: u64| Transaction::new(
+            1,
+            vec![TransactionInput::new([5u8; 32], 0, Vec::new(), 0xffffffff)],
+            vec![
+                TransactionOutput::new(amount, vec![0xaa]),
+                TransactionOutput::new(5_000, committee.script()),
+            ],
+            0,
+        );
+        let signers = [(0, &keys[0]), (1, &keys[1])];
+        
+        // Still active
+        assert!(governance.authorize_treasury_spend(&proposal_id, &spend(1_000), &committee, &signers).is_err());
+        
+        {
+            let proposal = governance.proposals.get_mut(&proposal_id).unwrap();
+            proposal.status = ProposalStatus::Approved;
+            proposal.voting_ends_at = Utc::now() - chrono::Duration::days(3);
+        }
+        
+        assert!(matches!(
+            governance.authorize_treasury_spend(&proposal_id, &spend(1_001), &committee, &signers),
+            Err(GovernanceError::InvalidAllocation(_))
+        ));
+        
+        let unsigned = spend(1_000);
+        let authorization = governance.authorize_treasury_spend(&proposal_id, &unsigned, &committee, &signers).unwrap();
+        let signed = Transaction::new(
+            1,
+            vec![TransactionInput::new([5u8; 32], 0, authorization.to_script(), 0xffffffff)],
+            unsigned.outputs().to_vec(),
+            0,
+        );
+        assert_eq!(committee.verify_spend(&signed, 0).unwrap(), proposal_id);
+    }
 } 
\ No newline at end of file
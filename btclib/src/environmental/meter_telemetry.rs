@@ -0,0 +1,655 @@
+//! Signed smart-meter telemetry
+//!
+//! Miners register each smart meter's public key (Ed25519 or Dilithium) with
+//! the node. Meters report their cumulative energy register in batches of
+//! `MeterReading`s, each signed in its `attestation` field. A `MeterIngestor`
+//! accepts a batch only if every reading is signed by the registered key,
+//! the register never runs backwards and the energy drawn is physically
+//! possible for the meter's rated power.
+//!
+//! Accepted energy is aggregated per miner per epoch. Once an epoch has
+//! ended its total can be handed to `MinerReportingManager` as verified
+//! energy consumption.
+//!
+//! `SimulatedMeter` produces signed batches the way a meter would, for tests
+//! and development networks.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::RwLock;
+
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::crypto::quantum::{verify_quantum_signature, QuantumKeyPair, QuantumParameters, QuantumScheme};
+use crate::environmental::oracle::MeterReading;
+
+/// Domain separator for meter reading signatures
+const METER_READING_DOMAIN: &[u8] = b"supernova_meter_reading";
+
+/// Error types for meter telemetry
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum MeterTelemetryError {
+    #[error("Meter {0} is not registered")]
+    UnknownMeter(String),
+    
+    #[error("Meter {0} is already registered")]
+    DuplicateMeter(String),
+    
+    #[error("Invalid meter registration: {0}")]
+    InvalidRegistration(String),
+    
+    #[error("Invalid signature on reading at {0}")]
+    InvalidSignature(DateTime<Utc>),
+    
+    #[error("Reading at {timestamp} is not after the previous reading at {previous}")]
+    NonMonotonicTime { timestamp: DateTime<Utc>, previous: DateTime<Utc> },
+    
+    #[error("Energy register fell from {previous} kWh to {energy_kwh} kWh")]
+    NonMonotonicEnergy { energy_kwh: f64, previous: f64 },
+    
+    #[error("Implausible reading: {0}")]
+    Implausible(String),
+    
+    #[error("Invalid batch: {0}")]
+    InvalidBatch(String),
+    
+    #[error("Signing error: {0}")]
+    SigningError(String),
+}
+
+/// Signature scheme of a meter key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MeterKeyScheme {
+    /// Ed25519, common in metering secure elements
+    Ed25519,
+    
+    /// Dilithium at the given security level
+    Dilithium(u8),
+}
+
+/// A meter registered to a miner
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegisteredMeter {
+    /// Meter identifier
+    pub meter_id: String,
+    
+    /// Miner the meter belongs to
+    pub miner_id: String,
+    
+    /// Signature scheme of the meter key
+    pub scheme: MeterKeyScheme,
+    
+    /// Public key of the meter
+    pub public_key: Vec<u8>,
+    
+    /// Rated maximum power draw in kW
+    pub max_power_kw: f64,
+}
+
+impl RegisteredMeter {
+    /// Check the registration is usable
+    pub fn validate(&self) -> Result<(), MeterTelemetryError> {
+        if self.meter_id.is_empty() || self.miner_id.is_empty() {
+            return Err(MeterTelemetryError::InvalidRegistration("Meter and miner IDs are required".to_string()));
+        }
+        if !(self.max_power_kw.is_finite() && self.max_power_kw > 0.0) {
+            return Err(MeterTelemetryError::InvalidRegistration(
+                format!("Invalid rated power: {}", self.max_power_kw)
+            ));
+        }
+        if self.scheme == MeterKeyScheme::Ed25519 && self.public_key.len() != 32 {
+            return Err(MeterTelemetryError::InvalidRegistration("Ed25519 keys are 32 bytes".to_string()));
+        }
+        if self.public_key.is_empty() {
+            return Err(MeterTelemetryError::InvalidRegistration("Public key is required".to_string()));
+        }
+        Ok(())
+    }
+    
+    /// Verify the signature in a reading's attestation
+    pub fn verify(&self, reading: &MeterReading) -> Result<(), MeterTelemetryError> {
+        let message = reading_message(reading);
+        let valid = match self.scheme {
+            MeterKeyScheme::Ed25519 => {
+                let key: [u8; 32] = self.public_key.as_slice().try_into().unwrap_or([0u8; 32]);
+                match (VerifyingKey::from_bytes(&key), Signature::from_slice(&reading.attestation)) {
+                    (Ok(key), Ok(signature)) => key.verify(&message, &signature).is_ok(),
+                    _ => false,
+                }
+            }
+            MeterKeyScheme::Dilithium(security_level) => {
+                let parameters = QuantumParameters::with_security_level(QuantumScheme::Dilithium, security_level);
+                verify_quantum_signature(&self.public_key, &message, &reading.attestation, parameters)
+                    .unwrap_or(false)
+            }
+        };
+        
+        if valid {
+            Ok(())
+        } else {
+            Err(MeterTelemetryError::InvalidSignature(reading.timestamp))
+        }
+    }
+}
+
+/// Message a meter signs for a reading
+pub fn reading_message(reading: &MeterReading) -> Vec<u8> {
+    let mut message = Vec::with_capacity(METER_READING_DOMAIN.len() + reading.meter_id.len() + 32);
+    message.extend_from_slice(METER_READING_DOMAIN);
+    message.extend_from_slice(&(reading.meter_id.len() as u32).to_le_bytes());
+    message.extend_from_slice(reading.meter_id.as_bytes());
+    message.extend_from_slice(&reading.timestamp.timestamp_millis().to_le_bytes());
+    message.extend_from_slice(&reading.energy_kwh.to_bits().to_le_bytes());
+    message.extend_from_slice(&reading.power_kw.to_bits().to_le_bytes());
+    message
+}
+
+/// Key a meter signs readings with
+pub enum MeterSigner {
+    /// Ed25519 signing key
+    Ed25519(SigningKey),
+    
+    /// Dilithium key pair
+    Dilithium(QuantumKeyPair),
+}
+
+impl MeterSigner {
+    /// Generate an Ed25519 key
+    pub fn generate_ed25519() -> Self {
+        MeterSigner::Ed25519(SigningKey::from_bytes(&rand::random::<[u8; 32]>()))
+    }
+    
+    /// Generate a Dilithium key at `security_level`
+    pub fn generate_dilithium(security_level: u8) -> Result<Self, MeterTelemetryError> {
+        let parameters = QuantumParameters::with_security_level(QuantumScheme::Dilithium, security_level);
+        QuantumKeyPair::generate(parameters)
+            .map(MeterSigner::Dilithium)
+            .map_err(|e| MeterTelemetryError::SigningError(e.to_string()))
+    }
+    
+    /// Scheme of the key
+    pub fn scheme(&self) -> MeterKeyScheme {
+        match self {
+            MeterSigner::Ed25519(_) => MeterKeyScheme::Ed25519,
+            MeterSigner::Dilithium(keypair) => MeterKeyScheme::Dilithium(keypair.parameters.security_level),
+        }
+    }
+    
+    /// Public key to register
+    pub fn public_key(&self) -> Vec<u8> {
+        match self {
+            MeterSigner::Ed25519(key) => key.verifying_key().to_bytes().to_vec(),
+            MeterSigner::Dilithium(keypair) => keypair.public_key.clone(),
+        }
+    }
+    
+    /// Sign a reading, replacing its attestation
+    pub fn sign(&self, reading: &mut MeterReading) -> Result<(), MeterTelemetryError> {
+        let message = reading_message(reading);
+        reading.attestation = match self {
+            MeterSigner::Ed25519(key) => key.sign(&message).to_bytes().to_vec(),
+            MeterSigner::Dilithium(keypair) => keypair.sign(&message)
+                .map_err(|e| MeterTelemetryError::SigningError(e.to_string()))?,
+        };
+        Ok(())
+    }
+}
+
+/// Readings from one meter, in time order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeterBatch {
+    /// Meter that produced the readings
+    pub meter_id: String,
+    
+    /// Signed readings
+    pub readings: Vec<MeterReading>,
+}
+
+/// Limits applied to incoming telemetry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// Length of an aggregation epoch in seconds
+    pub epoch_seconds: i64,
+    
+    /// How far ahead of the node's clock a reading may be
+    pub max_clock_skew_seconds: i64,
+    
+    /// Allowance over a meter's rated power before a reading is implausible
+    pub power_tolerance: f64,
+    
+    /// Maximum readings per batch
+    pub max_batch_size: usize,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            epoch_seconds: 86_400,
+            max_clock_skew_seconds: 300,
+            power_tolerance: 1.1,
+            max_batch_size: 1_000,
+        }
+    }
+}
+
+/// Metered energy of one miner over one epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochConsumption {
+    /// Miner the energy is attributed to
+    pub miner_id: String,
+    
+    /// Epoch number (Unix time divided by the epoch length)
+    pub epoch: i64,
+    
+    /// Start of the epoch
+    pub start: DateTime<Utc>,
+    
+    /// End of the epoch
+    pub end: DateTime<Utc>,
+    
+    /// Energy drawn in kWh
+    pub energy_kwh: f64,
+    
+    /// Highest reported power in kW
+    pub peak_power_kw: f64,
+    
+    /// Readings that contributed
+    pub readings: usize,
+    
+    /// Meters that contributed
+    pub meters: BTreeSet<String>,
+}
+
+impl EpochConsumption {
+    /// Average energy per day over the epoch
+    pub fn energy_kwh_per_day(&self) -> f64 {
+        let days = (self.end - self.start).num_seconds() as f64 / 86_400.0;
+        if days > 0.0 {
+            self.energy_kwh / days
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Result of ingesting a batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IngestReceipt {
+    /// Meter that sent the batch
+    pub meter_id: String,
+    
+    /// Miner the meter belongs to
+    pub miner_id: String,
+    
+    /// Readings accepted
+    pub accepted: usize,
+    
+    /// Energy attributed by the batch in kWh
+    pub energy_kwh: f64,
+    
+    /// Time of the last accepted reading
+    pub last_reading: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct IngestState {
+    meters: HashMap<String, RegisteredMeter>,
+    /// Last accepted reading time and register value by meter
+    last: HashMap<String, (DateTime<Utc>, f64)>,
+    /// Open epochs by miner and epoch number
+    epochs: BTreeMap<(String, i64), EpochConsumption>,
+}
+
+/// Verifies meter batches and aggregates them into epochs
+pub struct MeterIngestor {
+    config: TelemetryConfig,
+    state: RwLock<IngestState>,
+}
+
+impl Default for MeterIngestor {
+    fn default() -> Self {
+        Self::new(TelemetryConfig::default())
+    }
+}
+
+impl MeterIngestor {
+    /// Create an ingestor with no registered meters
+    pub fn new(config: TelemetryConfig) -> Self {
+        Self {
+            config,
+            state: RwLock::new(IngestState::default()),
+        }
+    }
+    
+    /// Register a meter key
+    pub fn register_meter(&self, meter: RegisteredMeter) -> Result<(), MeterTelemetryError> {
+        meter.validate()?;
+        let mut state = self.state.write().unwrap();
+        if state.meters.contains_key(&meter.meter_id) {
+            return Err(MeterTelemetryError::DuplicateMeter(meter.meter_id));
+        }
+        state.meters.insert(meter.meter_id.clone(), meter);
+        Ok(())
+    }
+    
+    /// Stop accepting readings from a meter
+    pub fn revoke_meter(&self, meter_id: &str) -> Option<RegisteredMeter> {
+        let mut state = self.state.write().unwrap();
+        state.last.remove(meter_id);
+        state.meters.remove(meter_id)
+    }
+    
+    /// Registration of a meter
+    pub fn meter(&self, meter_id: &str) -> Option<RegisteredMeter> {
+        self.state.read().unwrap().meters.get(meter_id).cloned()
+    }
+    
+    /// Epoch containing `time`
+    pub fn epoch_of(&self, time: DateTime<Utc>) -> i64 {
+        time.timestamp().div_euclid(self.config.epoch_seconds)
+    }
+    
+    /// Verify a batch and add its energy to the miner's epochs
+    ///
+    /// The batch is accepted or rejected as a whole. A meter's first
+    /// accepted reading only sets its baseline; energy is the increase of
+    /// the register between readings, attributed to the epoch of the later
+    /// reading.
+    pub fn ingest(&self, batch: &MeterBatch, now: DateTime<Utc>) -> Result<IngestReceipt, MeterTelemetryError> {
+        if batch.readings.is_empty() {
+            return Err(MeterTelemetryError::InvalidBatch("No readings".to_string()));
+        }
+        if batch.readings.len() > self.config.max_batch_size {
+            return Err(MeterTelemetryError::InvalidBatch(
+                format!("{} readings exceeds the limit of {}", batch.readings.len(), self.config.max_batch_size)
+            ));
+        }
+        
+        let mut state = self.state.write().unwrap();
+        let meter = state.meters.get(&batch.meter_id)
+            .cloned()
+            .ok_or_else(|| MeterTelemetryError::UnknownMeter(batch.meter_id.clone()))?;
+        
+        let latest_allowed = now + Duration::seconds(self.config.max_clock_skew_seconds);
+        let max_power_kw = meter.max_power_kw * self.config.power_tolerance;
+        let mut previous = state.last.get(&meter.meter_id).copied();
+        let mut deltas = Vec::with_capacity(batch.readings.len());
+        
+        for reading in &batch.readings {
+            if reading.meter_id != meter.meter_id {
+                return Err(MeterTelemetryError::InvalidBatch(
+                    format!("Reading from meter {} in batch for {}", reading.meter_id, meter.meter_id)
+                ));
+            }
+            meter.verify(reading)?;
+            
+            if !(reading.energy_kwh.is_finite() && reading.energy_kwh >= 0.0) {
+                return Err(MeterTelemetryError::Implausible(format!("Energy register {}", reading.energy_kwh)));
+            }
+            if !(reading.power_kw.is_finite() && reading.power_kw >= 0.0 && reading.power_kw <= max_power_kw) {
+                return Err(MeterTelemetryError::Implausible(
+                    format!("Power {} kW for a meter rated {} kW", reading.power_kw, meter.max_power_kw)
+                ));
+            }
+            if reading.timestamp > latest_allowed {
+                return Err(MeterTelemetryError::Implausible(format!("Reading at {} is in the future", reading.timestamp)));
+            }
+            
+            if let Some((previous_time, previous_energy)) = previous {
+                if reading.timestamp <= previous_time {
+                    return Err(MeterTelemetryError::NonMonotonicTime {
+                        timestamp: reading.timestamp,
+                        previous: previous_time,
+                    });
+                }
+                if reading.energy_kwh < previous_energy {
+                    return Err(MeterTelemetryError::NonMonotonicEnergy {
+                        energy_kwh: reading.energy_kwh,
+                        previous: previous_energy,
+                    });
+                }
+                
+                let hours = (reading.timestamp - previous_time).num_milliseconds() as f64 / 3_600_000.0;
+                let delta = reading.energy_kwh - previous_energy;
+                if delta > max_power_kw * hours + 1e-9 {
+                    return Err(MeterTelemetryError::Implausible(format!(
+                        "{:.3} kWh in {:.3} h exceeds the rated {} kW", delta, hours, meter.max_power_kw
+                    )));
+                }
+                deltas.push((reading, delta));
+            } else {
+                deltas.push((reading, 0.0));
+            }
+            previous = Some((reading.timestamp, reading.energy_kwh));
+        }
+        
+        // Every reading checked out; record the batch
+        let mut energy_kwh = 0.0;
+        for (reading, delta) in &deltas {
+            let epoch = self.epoch_of(reading.timestamp);
+            let start = DateTime::from_timestamp(epoch * self.config.epoch_seconds, 0).unwrap_or(reading.timestamp);
+            let entry = state.epochs
+                .entry((meter.miner_id.clone(), epoch))
+                .or_insert_with(|| EpochConsumption {
+                    miner_id: meter.miner_id.clone(),
+                    epoch,
+                    start,
+                    end: start + Duration::seconds(self.config.epoch_seconds),
+                    energy_kwh: 0.0,
+                    peak_power_kw: 0.0,
+                    readings: 0,
+                    meters: BTreeSet::new(),
+                });
+            entry.energy_kwh += delta;
+            entry.peak_power_kw = entry.peak_power_kw.max(reading.power_kw);
+            entry.readings += 1;
+            entry.meters.insert(meter.meter_id.clone());
+            energy_kwh += delta;
+        }
+        
+        let last = previous.expect("batch has readings");
+        state.last.insert(meter.meter_id.clone(), last);
+        
+        Ok(IngestReceipt {
+            meter_id: meter.meter_id,
+            miner_id: meter.miner_id,
+            accepted: deltas.len(),
+            energy_kwh,
+            last_reading: last.0,
+        })
+    }
+    
+    /// Consumption of a miner in an epoch, complete or not
+    pub fn epoch_consumption(&self, miner_id: &str, epoch: i64) -> Option<EpochConsumption> {
+        self.state.read().unwrap().epochs.get(&(miner_id.to_string(), epoch)).cloned()
+    }
+    
+    /// Remove and return every epoch that ended at or before `now`
+    pub fn complete_epochs(&self, now: DateTime<Utc>) -> Vec<EpochConsumption> {
+        let mut state = self.state.write().unwrap();
+        let completed: Vec<(String, i64)> = state.epochs.iter()
+            .filter(|(_, consumption)| consumption.end <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        completed.iter()
+            .filter_map(|key| state.epochs.remove(key))
+            .collect()
+    }
+}
+
+/// Simulated smart meter producing signed batches
+pub struct SimulatedMeter {
+    meter_id: String,
+    signer: MeterSigner,
+    register_kwh: f64,
+    power_kw: f64,
+    clock: DateTime<Utc>,
+}
+
+impl SimulatedMeter {
+    /// Meter starting at an empty register at `start`
+    pub fn new(meter_id: &str, signer: MeterSigner, power_kw: f64, start: DateTime<Utc>) -> Self {
+        Self {
+            meter_id: meter_id.to_string(),
+            signer,
+            register_kwh: 0.0,
+            power_kw,
+            clock: start,
+        }
+    }
+    
+    /// Registration for this meter
+    pub fn registration(&self, miner_id: &str, max_power_kw: f64) -> RegisteredMeter {
+        RegisteredMeter {
+            meter_id: self.meter_id.clone(),
+            miner_id: miner_id.to_string(),
+            scheme: self.signer.scheme(),
+            public_key: self.signer.public_key(),
+            max_power_kw,
+        }
+    }
+    
+    /// Change the simulated load
+    pub fn set_power(&mut self, power_kw: f64) {
+        self.power_kw = power_kw;
+    }
+    
+    /// Current simulated time
+    pub fn clock(&self) -> DateTime<Utc> {
+        self.clock
+    }
+    
+    /// Sign a reading of the current register
+    pub fn read(&self) -> Result<MeterReading, MeterTelemetryError> {
+        let mut reading = MeterReading {
+            meter_id: self.meter_id.clone(),
+            timestamp: self.clock,
+            energy_kwh: self.register_kwh,
+            power_kw: self.power_kw,
+            attestation: Vec::new(),
+        };
+        self.signer.sign(&mut reading)?;
+        Ok(reading)
+    }
+    
+    /// Run at the current load for `count` intervals, reading the register
+    /// at the end of each
+    pub fn batch(&mut self, count: usize, interval: Duration) -> Result<MeterBatch, MeterTelemetryError> {
+        let mut readings = Vec::with_capacity(count);
+        for _ in 0..count {
+            self.advance(interval);
+            readings.push(self.read()?);
+        }
+        Ok(MeterBatch {
+            meter_id: self.meter_id.clone(),
+            readings,
+        })
+    }
+    
+    /// Run at the current load for `interval`
+    pub fn advance(&mut self, interval: Duration) {
+        self.register_kwh += self.power_kw * interval.num_milliseconds() as f64 / 3_600_000.0;
+        self.clock += interval;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environmental::types::Region;
+    use crate::environmental::miner_reporting::{MinerEnvironmentalInfo, MinerReportingManager};
+    
+    fn day_start() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 - 1_700_000_000 % 86_400, 0).unwrap()
+    }
+    
+    #[test]
+    fn test_signed_batches_aggregate_into_epochs() {
+        let start = day_start();
+        let ingestor = MeterIngestor::default();
+        let mut ed_meter = SimulatedMeter::new("meter-ed", MeterSigner::generate_ed25519(), 100.0, start);
+        let mut pq_meter = SimulatedMeter::new("meter-pq", MeterSigner::generate_dilithium(2).unwrap(), 50.0, start);
+        ingestor.register_meter(ed_meter.registration("miner1", 120.0)).unwrap();
+        ingestor.register_meter(pq_meter.registration("miner1", 60.0)).unwrap();
+        assert!(ingestor.register_meter(ed_meter.registration("miner2", 120.0)).is_err());
+        
+        // A baseline reading, then a day of hourly readings from each meter
+        let now = start + Duration::days(2);
+        let baseline = MeterBatch { meter_id: "meter-ed".to_string(), readings: vec![ed_meter.read().unwrap()] };
+        assert_eq!(ingestor.ingest(&baseline, now).unwrap().energy_kwh, 0.0);
+        let receipt = ingestor.ingest(&ed_meter.batch(24, Duration::hours(1)).unwrap(), now).unwrap();
+        assert_eq!((receipt.accepted, receipt.energy_kwh), (24, 2_400.0));
+        let baseline = MeterBatch { meter_id: "meter-pq".to_string(), readings: vec![pq_meter.read().unwrap()] };
+        ingestor.ingest(&baseline, now).unwrap();
+        ingestor.ingest(&pq_meter.batch(24, Duration::hours(1)).unwrap(), now).unwrap();
+        
+        // The last hourly reading lands at midnight, in the next epoch
+        let epoch = ingestor.epoch_of(start);
+        let first = ingestor.epoch_consumption("miner1", epoch).unwrap();
+        assert_eq!(first.energy_kwh, 23.0 * 150.0);
+        assert_eq!(first.meters.len(), 2);
+        assert_eq!(first.peak_power_kw, 100.0);
+        assert!(ingestor.complete_epochs(start + Duration::hours(23)).is_empty());
+        
+        // Completed epochs feed the reporting manager
+        let mut manager = MinerReportingManager::new();
+        manager.register_miner(MinerEnvironmentalInfo::new("miner1".to_string(), "Miner One".to_string(), Region::NorthAmerica)).unwrap();
+        let completed = ingestor.complete_epochs(start + Duration::days(1));
+        assert_eq!(completed.len(), 1);
+        manager.apply_metered_consumption(&completed[0]).unwrap();
+        assert_eq!(manager.get_miner("miner1").unwrap().energy_consumption_kwh_day, 3_450.0);
+        assert_eq!(manager.metered_consumption("miner1").unwrap().epoch, epoch);
+        assert!(ingestor.epoch_consumption("miner1", epoch + 1).is_some());
+    }
+    
+    #[test]
+    fn test_invalid_batches_rejected() {
+        let start = day_start();
+        let now = start + Duration::hours(2);
+        let ingestor = MeterIngestor::default();
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut meter = SimulatedMeter::new("meter-1", MeterSigner::Ed25519(key.clone()), 100.0, start);
+        ingestor.register_meter(meter.registration("miner1", 120.0)).unwrap();
+        let good = meter.batch(4, Duration::minutes(15)).unwrap();
+        
+        // Unregistered meter and a meter signing with the wrong key
+        let stranger = SimulatedMeter::new("meter-2", MeterSigner::generate_ed25519(), 100.0, start);
+        let batch = MeterBatch { meter_id: "meter-2".to_string(), readings: vec![stranger.read().unwrap()] };
+        assert!(matches!(ingestor.ingest(&batch, now), Err(MeterTelemetryError::UnknownMeter(_))));
+        let impostor = SimulatedMeter::new("meter-1", MeterSigner::generate_ed25519(), 100.0, start);
+        let batch = MeterBatch { meter_id: "meter-1".to_string(), readings: vec![impostor.read().unwrap()] };
+        assert!(matches!(ingestor.ingest(&batch, now), Err(MeterTelemetryError::InvalidSignature(_))));
+        
+        // Altering a signed value breaks the signature
+        let mut tampered = good.clone();
+        tampered.readings[3].energy_kwh *= 2.0;
+        assert!(matches!(ingestor.ingest(&tampered, now), Err(MeterTelemetryError::InvalidSignature(_))));
+        
+        // Readings from the future are rejected
+        assert!(matches!(ingestor.ingest(&good, start), Err(MeterTelemetryError::Implausible(_))));
+        
+        ingestor.ingest(&good, now).unwrap();
+        
+        // Replays and registers running backwards are rejected
+        assert!(matches!(ingestor.ingest(&good, now), Err(MeterTelemetryError::NonMonotonicTime { .. })));
+        let mut rollback = SimulatedMeter::new("meter-1", MeterSigner::Ed25519(key.clone()), 100.0, meter.clock());
+        rollback.advance(Duration::minutes(15));
+        let batch = MeterBatch { meter_id: "meter-1".to_string(), readings: vec![rollback.read().unwrap()] };
+        assert!(matches!(ingestor.ingest(&batch, now), Err(MeterTelemetryError::NonMonotonicEnergy { .. })));
+        
+        // More energy than the rated power allows, with a valid signature
+        meter.advance(Duration::minutes(15));
+        let mut surge = meter.read().unwrap();
+        surge.energy_kwh += 500.0;
+        MeterSigner::Ed25519(key).sign(&mut surge).unwrap();
+        let batch = MeterBatch { meter_id: "meter-1".to_string(), readings: vec![surge] };
+        assert!(matches!(ingestor.ingest(&batch, now), Err(MeterTelemetryError::Implausible(_))));
+        
+        // Rejected batches left the epoch untouched
+        let consumption = ingestor.epoch_consumption("miner1", ingestor.epoch_of(start)).unwrap();
+        assert_eq!(consumption.readings, 4);
+        assert_eq!(consumption.energy_kwh, 75.0);
+    }
+}
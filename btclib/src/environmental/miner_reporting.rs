@@ -6,10 +6,49 @@ use chrono::{DateTime, Utc};
 use thiserror::Error;
 use crate::environmental::types::{EnergySource as TypesEnergySource, EmissionFactor, HardwareType as TypesHardwareType, Region};
 use crate::environmental::emissions::VerificationStatus;
+use crate::environmental::retirement::{RetirementRecord, RetirementRegistry};
+use crate::environmental::meter_telemetry::EpochConsumption;
 use std::sync::{Arc, RwLock};
 use url::Url;
 use std::fmt;
 
+/// Largest bonus verified RECs earn, in basis points, at full coverage
+pub const REC_BONUS_BPS: u64 = 500;
+
+/// Bonus verified carbon offsets earn, in basis points
+pub const OFFSET_BONUS_BPS: u64 = 200;
+
+/// Bonus in basis points for verified RECs covering `rec_milli_mwh` of
+/// `energy_milli_mwh` consumed, plus the offset bonus if the miner has
+/// verified offsets
+///
+/// RECs are prioritized: their bonus scales with coverage up to
+/// `REC_BONUS_BPS`, while offsets add a flat, smaller bonus. Integer
+/// arithmetic keeps the result identical on every node, so consensus can
+/// rely on it.
+pub fn rec_priority_bonus_bps(rec_milli_mwh: u64, energy_milli_mwh: u64, has_verified_offsets: bool) -> u64 {
+    let rec_bonus = if rec_milli_mwh == 0 {
+        0
+    } else if rec_milli_mwh >= energy_milli_mwh {
+        REC_BONUS_BPS
+    } else {
+        (rec_milli_mwh as u128 * REC_BONUS_BPS as u128 / energy_milli_mwh as u128) as u64
+    };
+    
+    let offset_bonus = if has_verified_offsets {
+        OFFSET_BONUS_BPS
+    } else {
+        0
+    };
+    
+    rec_bonus + offset_bonus
+}
+
+/// Convert an off-chain MWh figure to whole milli-MWh, rounding down
+pub fn to_milli_mwh(mwh: f64) -> u64 {
+    (mwh * 1_000.0) as u64
+}
+
 /// Status of miner verification
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MinerVerificationStatus {
@@ -539,6 +578,10 @@ pub struct MinerReportingManager {
     hardware_baselines: HashMap<TypesHardwareType, f64>,
     /// Reports by miner ID
     reports: HashMap<String, MinerEnvironmentalReport>,
+    /// Registry of retired certificates, if duplicate claims are checked
+    retirements: Option<Arc<RetirementRegistry>>,
+    /// Latest metered epoch applied, by miner ID
+    metered_consumption: HashMap<String, EpochConsumption>,
 }
 
 impl MinerReportingManager {
@@ -549,14 +592,22 @@ impl MinerReportingManager {
             emission_factors: HashMap::new(),
             hardware_baselines: HashMap::new(),
             reports: HashMap::new(),
+            retirements: None,
+            metered_consumption: HashMap::new(),
         }
     }
 
+    /// Retire RECs and offsets in `registry` as miners claim them
+    pub fn set_retirement_registry(&mut self, registry: Arc<RetirementRegistry>) {
+        self.retirements = Some(registry);
+    }
+
     /// Register a new miner
     pub fn register_miner(&mut self, info: MinerEnvironmentalInfo) -> Result<(), String> {
         if self.miners.contains_key(&info.miner_id) {
             return Err(format!("Miner with ID {} is already registered", info.miner_id));
         }
+        self.retire_certificates(&info)?;
 
         let miner_id = info.miner_id.clone();
         self.miners.insert(miner_id.clone(), info);
@@ -570,6 +621,7 @@ impl MinerReportingManager {
         if !self.miners.contains_key(&info.miner_id) {
             return Err(format!("Miner with ID {} is not registered", info.miner_id));
         }
+        self.retire_certificates(&info)?;
 
         let miner_id = info.miner_id.clone();
         self.miners.insert(miner_id.clone(), info);
@@ -578,6 +630,56 @@ impl MinerReportingManager {
         Ok(())
     }
 
+    /// Retire the certificates `info` adds to the miner's registered ones
+    ///
+    /// Fails without retiring anything if any of them was already claimed.
+    fn retire_certificates(&self, info: &MinerEnvironmentalInfo) -> Result<(), String> {
+        let registry = match &self.retirements {
+            Some(registry) => registry,
+            None => return Ok(()),
+        };
+        let previous = self.miners.get(&info.miner_id);
+
+        let mut claims: Vec<RetirementRecord> = info.rec_certificates.iter()
+            .filter(|cert| !previous.map_or(false, |p| p.rec_certificates.iter().any(|c| c.certificate_id == cert.certificate_id)))
+            .map(|cert| RetirementRecord::from_rec(&info.miner_id, cert))
+            .collect();
+        claims.extend(info.carbon_offsets.iter()
+            .filter(|offset| !previous.map_or(false, |p| p.carbon_offsets.iter().any(|o| o.offset_id == offset.offset_id)))
+            .map(|offset| RetirementRecord::from_offset(&info.miner_id, offset)));
+
+        registry.check_unclaimed(&claims).map_err(|e| e.to_string())?;
+        for claim in &claims {
+            registry.claim(claim).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Replace a miner's reported energy use with metered consumption
+    ///
+    /// Only epochs later than the last one applied change the miner's
+    /// figures; older epochs are ignored.
+    pub fn apply_metered_consumption(&mut self, consumption: &EpochConsumption) -> Result<(), String> {
+        let miner = self.miners.get_mut(&consumption.miner_id)
+            .ok_or_else(|| format!("Miner with ID {} is not registered", consumption.miner_id))?;
+        if self.metered_consumption.get(&consumption.miner_id).map_or(false, |last| last.epoch >= consumption.epoch) {
+            return Ok(());
+        }
+
+        miner.energy_consumption_kwh_day = consumption.energy_kwh_per_day();
+        miner.last_update = Utc::now();
+        debug!("Applied metered consumption for miner {} in epoch {}: {:.3} kWh",
+               consumption.miner_id, consumption.epoch, consumption.energy_kwh);
+        self.metered_consumption.insert(consumption.miner_id.clone(), consumption.clone());
+
+        Ok(())
+    }
+
+    /// Latest metered epoch applied to a miner
+    pub fn metered_consumption(&self, miner_id: &str) -> Option<&EpochConsumption> {
+        self.metered_consumption.get(miner_id)
+    }
+
     /// Get a miner's information by ID
     pub fn get_miner(&self, miner_id: &str) -> Option<&MinerEnvironmentalInfo> {
         self.miners.get(miner_id)
@@ -898,24 +1000,13 @@ impl MinerReportingManager {
             0.0 // No discount for less than 25% renewable
         };
         
-        // REC bonus - prioritize RECs over everything else
-        let rec_bonus = if info.has_verified_recs() {
-            // Calculate REC coverage percentage relative to energy consumption
-            let annual_energy_mwh = info.energy_consumption_kwh_day * 365.0 / 1000.0;
-            let rec_coverage = (info.total_verified_recs_mwh() / annual_energy_mwh).min(1.0);
-            
-            // Bonus based on REC coverage
-            rec_coverage * 5.0 // Up to 5% additional discount
-        } else {
-            0.0
-        };
-        
-        // Offset bonus - smaller bonus for offsets
-        let offset_bonus = if info.has_verified_offsets() {
-            2.0 // 2% additional discount for verified offsets
-        } else {
-            0.0
-        };
+        // REC bonus relative to annual energy consumption, plus a smaller bonus for offsets
+        let annual_energy_mwh = info.energy_consumption_kwh_day * 365.0 / 1000.0;
+        let attested_bonus = rec_priority_bonus_bps(
+            to_milli_mwh(info.total_verified_recs_mwh()),
+            to_milli_mwh(annual_energy_mwh),
+            info.has_verified_offsets(),
+        ) as f64 / 100.0;
         
         // Location verification bonus
         let location_bonus = if let Some(verification) = &info.location_verification {
@@ -929,7 +1020,7 @@ impl MinerReportingManager {
         };
         
         // Total discount
-        base_discount + rec_bonus + offset_bonus + location_bonus
+        base_discount + attested_bonus + location_bonus
     }
     
     /// Get miners with verified REC certificates (prioritize over offsets)
@@ -1131,4 +1222,44 @@ mod tests {
         // Adding offsets should reduce further
         assert!(with_both < with_recs);
     }
+    
+    #[test]
+    fn test_certificates_retired_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        let mut manager = MinerReportingManager::new();
+        let registry = Arc::new(RetirementRegistry::new(db.open_tree("retirements").unwrap()));
+        manager.set_retirement_registry(Arc::clone(&registry));
+        
+        let rec = |id: &str| RECCertificate {
+            certificate_id: id.to_string(),
+            issuer: "Green-e".to_string(),
+            amount_mwh: 50.0,
+            generation_start: Utc::now() - chrono::Duration::days(90),
+            generation_end: Utc::now(),
+            generation_location: Some(Region::Europe),
+            energy_type: TypesEnergySource::Wind,
+            verification_status: MinerVerificationStatus::Verified,
+            certificate_url: None,
+            last_verified: None,
+            blockchain_tx_id: None,
+        };
+        
+        let mut first = MinerEnvironmentalInfo::new("miner1".to_string(), "First".to_string(), Region::Europe);
+        first.add_rec_certificate(rec("REC-100"));
+        manager.register_miner(first.clone()).unwrap();
+        
+        // Resubmitting the miner's own certificates is not a new claim
+        first.add_rec_certificate(rec("REC-101"));
+        manager.update_miner(first).unwrap();
+        
+        let mut second = MinerEnvironmentalInfo::new("miner2".to_string(), "Second".to_string(), Region::Europe);
+        second.add_rec_certificate(rec("REC-200"));
+        second.add_rec_certificate(rec("REC-100"));
+        assert!(manager.register_miner(second).unwrap_err().contains("miner1"));
+        
+        // Nothing from the rejected submission was retired
+        assert!(registry.lookup("REC-200").unwrap().is_none());
+        assert_eq!(registry.by_miner("miner1").unwrap().len(), 2);
+    }
 } 
\ No newline at end of file
@@ -2,18 +2,29 @@
 // Provides features for environmental monitoring, carbon offsetting, and ESG compliance
 
 // Re-export all modules
+pub mod alert_rules;
+pub mod alert_sinks;
+pub mod alerting;
 pub mod api;
-// pub mod alerting;  // Temporarily disabled for compilation
+pub mod attestation;
 pub mod carbon_tracking;
+pub mod chain_governance;
 pub mod dashboard;
 pub mod emissions;
+pub mod emissions_factors;
 pub mod governance;
+pub mod grid_data;
 pub mod manual_verification;
 pub mod miner_reporting;
+pub mod meter_telemetry;
 pub mod oracle;
+pub mod real_oracle;
 pub mod renewable_validation;
+pub mod retirement;
 pub mod transparency;
+pub mod transparency_report;
 pub mod treasury;
+pub mod treasury_ledger;
 pub mod types;
 pub mod verification;
 
@@ -22,12 +33,20 @@ pub use types::{Region as TypesRegion, EnergySource, EmissionFactor, EmissionsFa
 pub use miner_reporting::{MinerEnvironmentalInfo, MinerReportingManager, MinerVerificationStatus};
 pub use emissions::{EmissionsTracker, EmissionCalculator, Region as EmissionsRegion, VerificationStatus, Emissions};
 pub use treasury::{EnvironmentalTreasury, EnvironmentalAssetType, EnvironmentalAssetPurchase};
+pub use treasury_ledger::{TreasuryCommittee, TreasuryAuthorization, TreasuryConsensus, TreasuryLedger, TreasurySpend, TreasuryDeposit};
 pub use dashboard::{EnvironmentalDashboard, EnvironmentalMetrics, EmissionsTimePeriod};
 pub use transparency::{TransparencyDashboard, TransparencyReport, TransparencyLevel};
 pub use governance::{EnvironmentalGovernance, EnvironmentalProposal, ProposalStatus};
-// pub use alerting::{AlertingSystem, Alert, AlertRule};  // Temporarily disabled for compilation
+pub use chain_governance::{GovernanceLedger, GovernanceRecord, ChainGovernanceConfig, ChainProposal, ProposalTally, SignedProposal, SignedVote, StakeLock, WeightMode};
+pub use alerting::{AlertingSystem, Alert, AlertRule, AlertSeverity, AlertStatus, MetricType};
+pub use alert_rules::{AlertEngine, AlertEvent, AlertNotification, RuleSet, RuleDefinition, RuleCondition, Escalation};
+pub use alert_sinks::{NotificationSink, SinkConfig, WebhookSink, FileSink, SyslogSink, ChannelSink};
 pub use verification::{RenewableCertificate, CarbonOffset, VerificationService};
 pub use oracle::{EnvironmentalOracle, OracleError, OracleInfo, OracleSubmission};
+pub use retirement::{RetirementRegistry, RetirementRecord, RetirementError, CertificateKind};
+pub use attestation::{RecAttestation, AttestationClaim, AttestationKind, AttestationError, ConsumedCertificates, AttestationStore, OracleSet, OracleSetMember, OracleSetRegistry};
+pub use meter_telemetry::{MeterIngestor, MeterBatch, MeterKeyScheme, MeterSigner, MeterTelemetryError, RegisteredMeter, TelemetryConfig, EpochConsumption, IngestReceipt, SimulatedMeter};
+pub use grid_data::{GridDataSource, GridDataError, GridReading, ApiFormat, HttpGridSource, CachedGridSource, CachedReading, ReplayGridSource, GridFixture};
 
 // New Phase 3 modules
 pub use carbon_tracking::{
@@ -54,6 +73,10 @@ pub use self::emissions::{
     RegionalEnergyData,
     NetworkHashrate,
     EmissionsConfig,
+    IntensityMode,
+    HourlyIntensity,
+    BlockEnergyRecord,
+    MinerFootprint,
 };
 
 pub use self::treasury::{
@@ -2,8 +2,8 @@
 
 
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
-use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Serialize, Deserialize};
 use reqwest::Client;
 use thiserror::Error;
@@ -11,21 +11,17 @@ use thiserror::Error;
 use crate::environmental::{
     types::{Region, EnergySource},
     emissions::EmissionFactor,
+    grid_data::{ApiFormat, CachedGridSource, GridDataError, GridDataSource, HttpGridSource},
 };
 
 /// Real-time energy grid data provider
-#[derive(Debug, Clone)]
+///
+/// Readings come from a pluggable `GridDataSource` behind a TTL cache; each
+/// region is served by one of the source's zones.
+#[derive(Clone)]
 pub struct GridDataProvider {
-    client: Client,
-    api_endpoints: HashMap<Region, String>,
-    cache: Arc<RwLock<GridDataCache>>,
-}
-
-/// Cached grid data
-#[derive(Debug, Clone)]
-struct GridDataCache {
-    data: HashMap<Region, GridData>,
-    last_update: DateTime<Utc>,
+    source: Arc<CachedGridSource<Arc<dyn GridDataSource>>>,
+    zones: HashMap<Region, String>,
 }
 
 /// Real-time grid data
@@ -36,8 +32,10 @@ pub struct GridData {
     pub energy_mix: HashMap<EnergySource, f64>,
     pub carbon_intensity: f64, // gCO2/kWh
     pub renewable_percentage: f64,
-    pub demand_mw: f64,
-    pub generation_mw: f64,
+    pub demand_mw: Option<f64>,
+    pub generation_mw: Option<f64>,
+    /// Served from cache after the source failed to refresh it
+    pub stale: bool,
 }
 
 /// Environmental oracle errors
@@ -62,151 +60,58 @@ pub enum OracleError {
     NetworkError(#[from] reqwest::Error),
 }
 
+impl From<GridDataError> for OracleError {
+    fn from(error: GridDataError) -> Self {
+        match error {
+            GridDataError::RateLimited => OracleError::RateLimitExceeded,
+            GridDataError::ParseError(msg) => OracleError::ParseError(msg),
+            other => OracleError::ApiError(other.to_string()),
+        }
+    }
+}
+
 impl GridDataProvider {
-    /// Create a new grid data provider with real API endpoints
+    /// Create a new grid data provider backed by the Electricity Maps API
     pub fn new() -> Self {
-        let mut api_endpoints = HashMap::new();
-        
-        // Real API endpoints for different regions
-        // These would be actual grid operator APIs in production
-        api_endpoints.insert(
-            Region::NorthAmerica,
-            "https://api.eia.gov/v2/electricity/operating-generator-capacity".to_string()
-        );
-        api_endpoints.insert(
-            Region::Europe,
-            "https://transparency.entsoe.eu/api".to_string()
-        );
-        api_endpoints.insert(
-            Region::AsiaPacific,
-            "https://api.aemo.com.au/public/api".to_string()
-        );
+        let mut zones = HashMap::new();
+        zones.insert(Region::NorthAmerica, "US".to_string());
+        zones.insert(Region::Europe, "DE".to_string());
+        zones.insert(Region::AsiaPacific, "AU".to_string());
         
+        let source = HttpGridSource::new(ApiFormat::ElectricityMaps, "https://api.electricitymap.org/v3");
+        Self::with_source(Arc::new(source), zones)
+    }
+    
+    /// Create a provider reading `zones` from `source`
+    pub fn with_source(source: Arc<dyn GridDataSource>, zones: HashMap<Region, String>) -> Self {
         Self {
-            client: Client::new(),
-            api_endpoints,
-            cache: Arc::new(RwLock::new(GridDataCache {
-                data: HashMap::new(),
-                last_update: Utc::now(),
-            })),
+            // 5 minute TTL, serving stale data for up to an hour
+            source: Arc::new(CachedGridSource::new(source, Duration::minutes(5), Duration::hours(1))),
+            zones,
         }
     }
     
     /// Get real-time grid data for a region
     pub async fn get_grid_data(&self, region: Region) -> Result<GridData, OracleError> {
-        // Check cache first (5 minute TTL)
-        {
-            let cache = self.cache.read().map_err(|_| {
-                OracleError::ApiError("Cache lock error".to_string())
-            })?;
-            
-            if let Some(data) = cache.data.get(&region) {
-                let age = Utc::now() - cache.last_update;
-                if age.num_minutes() < 5 {
-                    return Ok(data.clone());
-                }
-            }
-        }
-        
-        // Fetch fresh data from API
-        let endpoint = self.api_endpoints.get(&region)
+        let zone = self.zones.get(&region)
             .ok_or(OracleError::NoDataForRegion(region))?;
         
-        // Make API request
-        let response = self.client
-            .get(endpoint)
-            .timeout(std::time::Duration::from_secs(30))
-            .send()
-            .await?;
-        
-        if !response.status().is_success() {
-            if response.status().as_u16() == 429 {
-                return Err(OracleError::RateLimitExceeded);
-            }
-            return Err(OracleError::ApiError(
-                format!("API returned status: {}", response.status())
-            ));
-        }
-        
-        // Parse response based on region/API format
-        let grid_data = self.parse_api_response(region, response).await?;
-        
-        // Update cache
-        {
-            let mut cache = self.cache.write().map_err(|_| {
-                OracleError::ApiError("Cache lock error".to_string())
-            })?;
-            cache.data.insert(region, grid_data.clone());
-            cache.last_update = Utc::now();
-        }
-        
-        Ok(grid_data)
-    }
-    
-    /// Parse API response into GridData
-    async fn parse_api_response(
-        &self,
-        region: Region,
-        response: reqwest::Response,
-    ) -> Result<GridData, OracleError> {
-        let body = response.text().await?;
-        
-        // In production, this would parse actual API responses
-        // For now, we'll create realistic data based on typical grid compositions
-        let (energy_mix, carbon_intensity) = match region {
-            Region::NorthAmerica => {
-                let mut mix = HashMap::new();
-                mix.insert(EnergySource::NaturalGas, 40.0);
-                mix.insert(EnergySource::Nuclear, 20.0);
-                mix.insert(EnergySource::Coal, 20.0);
-                mix.insert(EnergySource::Wind, 10.0);
-                mix.insert(EnergySource::Solar, 5.0);
-                mix.insert(EnergySource::Hydro, 5.0);
-                (mix, 450.0) // gCO2/kWh
-            }
-            Region::Europe => {
-                let mut mix = HashMap::new();
-                mix.insert(EnergySource::Wind, 25.0);
-                mix.insert(EnergySource::Nuclear, 25.0);
-                mix.insert(EnergySource::NaturalGas, 20.0);
-                mix.insert(EnergySource::Hydro, 15.0);
-                mix.insert(EnergySource::Solar, 10.0);
-                mix.insert(EnergySource::Coal, 5.0);
-                (mix, 300.0) // gCO2/kWh
-            }
-            Region::AsiaPacific => {
-                let mut mix = HashMap::new();
-                mix.insert(EnergySource::Coal, 45.0);
-                mix.insert(EnergySource::NaturalGas, 20.0);
-                mix.insert(EnergySource::Hydro, 15.0);
-                mix.insert(EnergySource::Nuclear, 10.0);
-                mix.insert(EnergySource::Wind, 5.0);
-                mix.insert(EnergySource::Solar, 5.0);
-                (mix, 600.0) // gCO2/kWh
-            }
-            _ => {
-                let mut mix = HashMap::new();
-                mix.insert(EnergySource::Grid, 100.0);
-                (mix, 500.0) // Global average
-            }
+        let cached = match self.source.get(zone).await {
+            Ok(cached) => cached,
+            Err(GridDataError::NoData(_)) => return Err(OracleError::NoDataForRegion(region)),
+            Err(e) => return Err(e.into()),
         };
         
-        let renewable_percentage = energy_mix.iter()
-            .filter(|(source, _)| matches!(
-                source,
-                EnergySource::Solar | EnergySource::Wind | EnergySource::Hydro | EnergySource::Geothermal
-            ))
-            .map(|(_, percentage)| percentage)
-            .sum();
-        
+        let reading = cached.reading;
         Ok(GridData {
             region,
-            timestamp: Utc::now(),
-            energy_mix,
-            carbon_intensity,
-            renewable_percentage,
-            demand_mw: 50000.0, // Placeholder
-            generation_mw: 51000.0, // Placeholder
+            timestamp: reading.timestamp,
+            renewable_percentage: reading.renewable_share(),
+            energy_mix: reading.energy_mix,
+            carbon_intensity: reading.carbon_intensity,
+            demand_mw: reading.demand_mw,
+            generation_mw: reading.generation_mw,
+            stale: cached.stale,
         })
     }
 }
@@ -382,9 +287,14 @@ pub struct SmartMeterReading {
 mod tests {
     use super::*;
     
+    use crate::environmental::grid_data::ReplayGridSource;
+    
     #[tokio::test]
     async fn test_grid_data_provider() {
-        let provider = GridDataProvider::new();
+        let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/grid/electricity_maps.json");
+        let mut zones = HashMap::new();
+        zones.insert(Region::NorthAmerica, "US".to_string());
+        let provider = GridDataProvider::with_source(Arc::new(ReplayGridSource::load(fixture).unwrap()), zones);
         
         // Test getting grid data for North America
         let result = provider.get_grid_data(Region::NorthAmerica).await;
@@ -393,7 +303,13 @@ mod tests {
         let grid_data = result.unwrap();
         assert_eq!(grid_data.region, Region::NorthAmerica);
         assert!(grid_data.renewable_percentage >= 0.0);
-        assert!(grid_data.carbon_intensity > 0.0);
+        assert_eq!(grid_data.carbon_intensity, 388.0);
+        assert!(!grid_data.stale);
+        
+        assert!(matches!(
+            provider.get_grid_data(Region::Europe).await,
+            Err(OracleError::NoDataForRegion(Region::Europe))
+        ));
     }
     
     #[tokio::test]
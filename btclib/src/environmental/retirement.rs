@@ -0,0 +1,265 @@
+//! Certificate retirement registry
+//!
+//! Once a miner counts a REC or carbon offset towards its environmental
+//! profile, the certificate is retired on Supernova: its ID is recorded in a
+//! database tree and any later claim of the same ID is rejected, whether it
+//! comes from another miner or from the same miner for another period.
+//!
+//! IDs are compared after trimming and upper-casing, so trivially different
+//! spellings of one certificate cannot be claimed twice.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::environmental::miner_reporting::{CarbonOffset, RECCertificate};
+
+/// Retirement registry errors
+#[derive(Debug, Error)]
+pub enum RetirementError {
+    #[error("Database error: {0}")]
+    Database(#[from] sled::Error),
+    
+    #[error("Codec error: {0}")]
+    Codec(#[from] bincode::Error),
+    
+    #[error("Certificate {certificate_id} already claimed by {claimed_by}")]
+    AlreadyClaimed { certificate_id: String, claimed_by: String },
+    
+    #[error("Invalid claim: {0}")]
+    InvalidClaim(String),
+}
+
+/// Kind of retired certificate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CertificateKind {
+    /// Renewable energy certificate
+    Rec,
+    
+    /// Carbon offset
+    CarbonOffset,
+}
+
+impl CertificateKind {
+    /// Name used in reports and APIs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CertificateKind::Rec => "REC",
+            CertificateKind::CarbonOffset => "CARBON_OFFSET",
+        }
+    }
+}
+
+/// A retired certificate and the claim that retired it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetirementRecord {
+    /// Kind of certificate
+    pub kind: CertificateKind,
+    
+    /// Certificate ID as submitted
+    pub certificate_id: String,
+    
+    /// Issuing organization
+    pub issuer: String,
+    
+    /// Miner that claimed the certificate
+    pub miner_id: String,
+    
+    /// MWh for RECs, tonnes CO2e for offsets
+    pub amount: f64,
+    
+    /// Start of the period the certificate covers
+    pub period_start: DateTime<Utc>,
+    
+    /// End of the period the certificate covers
+    pub period_end: DateTime<Utc>,
+    
+    /// When the claim was recorded
+    pub claimed_at: DateTime<Utc>,
+}
+
+impl RetirementRecord {
+    /// Claim of a REC by `miner_id`
+    pub fn from_rec(miner_id: &str, certificate: &RECCertificate) -> Self {
+        Self {
+            kind: CertificateKind::Rec,
+            certificate_id: certificate.certificate_id.clone(),
+            issuer: certificate.issuer.clone(),
+            miner_id: miner_id.to_string(),
+            amount: certificate.amount_mwh,
+            period_start: certificate.generation_start,
+            period_end: certificate.generation_end,
+            claimed_at: Utc::now(),
+        }
+    }
+    
+    /// Claim of a carbon offset by `miner_id`
+    pub fn from_offset(miner_id: &str, offset: &CarbonOffset) -> Self {
+        Self {
+            kind: CertificateKind::CarbonOffset,
+            certificate_id: offset.offset_id.clone(),
+            issuer: offset.issuer.clone(),
+            miner_id: miner_id.to_string(),
+            amount: offset.amount_tonnes,
+            period_start: offset.offset_start,
+            period_end: offset.offset_end,
+            claimed_at: Utc::now(),
+        }
+    }
+}
+
+/// Normalized registry key of a certificate ID
+pub fn certificate_key(certificate_id: &str) -> String {
+    certificate_id.trim().to_ascii_uppercase()
+}
+
+/// Persistent set of retired certificates
+pub struct RetirementRegistry {
+    tree: sled::Tree,
+}
+
+impl RetirementRegistry {
+    /// Use `tree` to store retirements
+    pub fn new(tree: sled::Tree) -> Self {
+        Self { tree }
+    }
+    
+    /// Retire a certificate, failing if its ID was already claimed
+    pub fn claim(&self, record: &RetirementRecord) -> Result<(), RetirementError> {
+        let key = certificate_key(&record.certificate_id);
+        if key.is_empty() {
+            return Err(RetirementError::InvalidClaim("Empty certificate ID".to_string()));
+        }
+        if record.miner_id.is_empty() {
+            return Err(RetirementError::InvalidClaim("Empty miner ID".to_string()));
+        }
+        
+        // Insert only if absent, so concurrent claims cannot both succeed
+        let value = bincode::serialize(record)?;
+        match self.tree.compare_and_swap(key.as_bytes(), None as Option<&[u8]>, Some(value))? {
+            Ok(()) => Ok(()),
+            Err(existing) => {
+                let claimed_by = match existing.current {
+                    Some(bytes) => bincode::deserialize::<RetirementRecord>(&bytes)?.miner_id,
+                    None => String::new(),
+                };
+                Err(RetirementError::AlreadyClaimed {
+                    certificate_id: record.certificate_id.clone(),
+                    claimed_by,
+                })
+            }
+        }
+    }
+    
+    /// Check that none of `records` has been claimed, or repeats another
+    pub fn check_unclaimed(&self, records: &[RetirementRecord]) -> Result<(), RetirementError> {
+        let mut seen = HashSet::new();
+        for record in records {
+            let key = certificate_key(&record.certificate_id);
+            if !seen.insert(key.clone()) {
+                return Err(RetirementError::AlreadyClaimed {
+                    certificate_id: record.certificate_id.clone(),
+                    claimed_by: record.miner_id.clone(),
+                });
+            }
+            if let Some(existing) = self.lookup(&key)? {
+                return Err(RetirementError::AlreadyClaimed {
+                    certificate_id: record.certificate_id.clone(),
+                    claimed_by: existing.miner_id,
+                });
+            }
+        }
+        Ok(())
+    }
+    
+    /// Retirement of a certificate, if it has been claimed
+    pub fn lookup(&self, certificate_id: &str) -> Result<Option<RetirementRecord>, RetirementError> {
+        match self.tree.get(certificate_key(certificate_id).as_bytes())? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Certificates claimed by a miner
+    pub fn by_miner(&self, miner_id: &str) -> Result<Vec<RetirementRecord>, RetirementError> {
+        let mut records = Vec::new();
+        for entry in self.tree.iter() {
+            let (_, value) = entry?;
+            let record: RetirementRecord = bincode::deserialize(&value)?;
+            if record.miner_id == miner_id {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+    
+    /// Number of retired certificates
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+    
+    /// Whether no certificates are retired
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    
+    fn record(kind: CertificateKind, certificate_id: &str, miner_id: &str, start: DateTime<Utc>) -> RetirementRecord {
+        RetirementRecord {
+            kind,
+            certificate_id: certificate_id.to_string(),
+            issuer: "Registry".to_string(),
+            miner_id: miner_id.to_string(),
+            amount: 10.0,
+            period_start: start,
+            period_end: start + Duration::days(90),
+            claimed_at: Utc::now(),
+        }
+    }
+    
+    #[test]
+    fn test_duplicate_claims_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        let registry = RetirementRegistry::new(db.open_tree("retirements").unwrap());
+        let q1 = Utc::now() - Duration::days(180);
+        let q2 = q1 + Duration::days(90);
+        
+        registry.claim(&record(CertificateKind::Rec, "REC-001", "miner1", q1)).unwrap();
+        
+        // Another miner, the same miner in a later period, and a respelling all fail
+        for (id, miner, start) in [("REC-001", "miner2", q1), ("REC-001", "miner1", q2), (" rec-001", "miner3", q2)] {
+            match registry.claim(&record(CertificateKind::Rec, id, miner, start)) {
+                Err(RetirementError::AlreadyClaimed { claimed_by, .. }) => assert_eq!(claimed_by, "miner1"),
+                other => panic!("Expected AlreadyClaimed, got {:?}", other),
+            }
+        }
+        
+        registry.claim(&record(CertificateKind::CarbonOffset, "VCS-42", "miner2", q2)).unwrap();
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.lookup("rec-001").unwrap().unwrap().miner_id, "miner1");
+        assert!(registry.lookup("REC-002").unwrap().is_none());
+        assert_eq!(registry.by_miner("miner2").unwrap()[0].kind, CertificateKind::CarbonOffset);
+        
+        // A batch repeating an ID is rejected before anything is claimed
+        let batch = [
+            record(CertificateKind::Rec, "REC-003", "miner3", q2),
+            record(CertificateKind::Rec, "REC-003", "miner3", q2),
+        ];
+        assert!(registry.check_unclaimed(&batch).is_err());
+        assert!(registry.check_unclaimed(&batch[..1]).is_ok());
+        
+        // Retirements survive reopening the tree
+        drop(registry);
+        let reopened = RetirementRegistry::new(db.open_tree("retirements").unwrap());
+        assert_eq!(reopened.len(), 2);
+    }
+}
@@ -1,8 +1,12 @@
 use std::fmt;
 use std::collections::HashMap;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Serialize, Deserialize};
 use crate::environmental::transparency::{TransparencyReport, TransparencyLevel};
 use crate::environmental::miner_reporting::MinerVerificationStatus;
+use crate::environmental::emissions::{
+    hour_start, BlockEnergyRecord, EmissionsError, EmissionsTracker, IntensityMode, MinerFootprint, Region,
+};
 
 /// Generate a text report from a transparency report
 pub fn generate_text_report(report: &TransparencyReport) -> String {
@@ -40,7 +44,7 @@ pub fn generate_text_report(report: &TransparencyReport) -> String {
         
         text.push_str("Verification Status:\n");
         for (status, count) in &rec_stats.verification_status_breakdown {
-            text.push_str(&format!("  {}: {}\n", status, count));
+            text.push_str(&format!("  {:?}: {}\n", status, count));
         }
     }
     
@@ -57,9 +61,195 @@ pub fn generate_text_report(report: &TransparencyReport) -> String {
         
         text.push_str("Verification Status:\n");
         for (status, count) in &offset_stats.verification_status_breakdown {
-            text.push_str(&format!("  {}: {}\n", status, count));
+            text.push_str(&format!("  {:?}: {}\n", status, count));
         }
     }
     
     text
-} 
\ No newline at end of file
+}
+
+/// How a miner's timing shifts its footprint under one intensity mode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimingImpact {
+    /// Footprint with each block charged at the intensity of its hour
+    pub footprint: MinerFootprint,
+    /// Emissions in kg CO2e had the same energy been drawn evenly over the period
+    pub flat_load_emissions_kg: f64,
+    /// Actual minus flat-load emissions in kg CO2e; negative when timing helped
+    pub shift_kg: f64,
+    /// Shift relative to the flat-load emissions, in percent
+    pub shift_percentage: f64,
+}
+
+/// Report on how curtailing during dirty hours shifts a miner's footprint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurtailmentReport {
+    /// Miner identifier
+    pub miner_id: String,
+    /// Grid region the miner draws from
+    pub region: Region,
+    /// Start of the reporting period
+    pub period_start: DateTime<Utc>,
+    /// End of the reporting period
+    pub period_end: DateTime<Utc>,
+    /// Hours in the period with no blocks mined
+    pub curtailed_hours: usize,
+    /// Hours in the period
+    pub total_hours: usize,
+    /// Impact under average grid intensity
+    pub average: TimingImpact,
+    /// Impact under marginal grid intensity
+    pub marginal: TimingImpact,
+}
+
+fn timing_impact(
+    tracker: &EmissionsTracker,
+    miner_id: &str,
+    region: &Region,
+    blocks: &[BlockEnergyRecord],
+    hours: &[DateTime<Utc>],
+    mode: IntensityMode,
+) -> Result<TimingImpact, EmissionsError> {
+    let footprint = tracker.calculate_miner_footprint(miner_id, region, blocks, mode)?;
+    
+    let energy_per_hour = footprint.energy_kwh / hours.len() as f64;
+    let flat_load_emissions_kg: f64 = hours.iter()
+        .map(|hour| energy_per_hour * tracker.intensity_at(region, *hour, mode).0 / 1000.0)
+        .sum();
+    
+    let shift_kg = footprint.emissions_kg - flat_load_emissions_kg;
+    let shift_percentage = if flat_load_emissions_kg > 0.0 {
+        shift_kg / flat_load_emissions_kg * 100.0
+    } else {
+        0.0
+    };
+    
+    Ok(TimingImpact {
+        footprint,
+        flat_load_emissions_kg,
+        shift_kg,
+        shift_percentage,
+    })
+}
+
+/// Compare a miner's hourly-attributed footprint with a flat-load baseline
+///
+/// Blocks outside `[period_start, period_end)` are ignored.
+pub fn generate_curtailment_report(
+    tracker: &EmissionsTracker,
+    miner_id: &str,
+    region: &Region,
+    blocks: &[BlockEnergyRecord],
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+) -> Result<CurtailmentReport, EmissionsError> {
+    if period_start >= period_end {
+        return Err(EmissionsError::InvalidTimeRange);
+    }
+    
+    let mut hours = Vec::new();
+    let mut hour = hour_start(period_start);
+    while hour < period_end {
+        hours.push(hour);
+        hour = hour + Duration::hours(1);
+    }
+    
+    let blocks: Vec<BlockEnergyRecord> = blocks.iter()
+        .filter(|block| block.timestamp >= period_start && block.timestamp < period_end)
+        .cloned()
+        .collect();
+    
+    let average = timing_impact(tracker, miner_id, region, &blocks, &hours, IntensityMode::Average)?;
+    let marginal = timing_impact(tracker, miner_id, region, &blocks, &hours, IntensityMode::Marginal)?;
+    
+    let active_hours = average.footprint.hourly.iter().filter(|hour| hour.energy_kwh > 0.0).count();
+    
+    Ok(CurtailmentReport {
+        miner_id: miner_id.to_string(),
+        region: region.clone(),
+        period_start,
+        period_end,
+        curtailed_hours: hours.len() - active_hours,
+        total_hours: hours.len(),
+        average,
+        marginal,
+    })
+}
+
+/// Generate a text report from a curtailment report
+pub fn generate_curtailment_text_report(report: &CurtailmentReport) -> String {
+    let mut text = String::new();
+    
+    text.push_str(&format!(
+        "Curtailment Report for {} ({} to {}):\n",
+        report.miner_id,
+        report.period_start.format("%Y-%m-%d %H:%M"),
+        report.period_end.format("%Y-%m-%d %H:%M")
+    ));
+    text.push_str(&format!("Region: {}\n", match &report.region.sub_region {
+        Some(sub_region) => format!("{}-{}", report.region.country_code, sub_region),
+        None => report.region.country_code.clone(),
+    }));
+    text.push_str(&format!("Curtailed Hours: {} of {}\n", report.curtailed_hours, report.total_hours));
+    text.push_str(&format!("Energy: {:.2} kWh\n", report.average.footprint.energy_kwh));
+    
+    for (label, impact) in [("Average", &report.average), ("Marginal", &report.marginal)] {
+        text.push_str(&format!("\n{} Intensity:\n", label));
+        text.push_str(&format!("  Emissions: {:.2} kg CO2e\n", impact.footprint.emissions_kg));
+        text.push_str(&format!("  Effective Intensity: {:.1} gCO2e/kWh\n", impact.footprint.effective_intensity()));
+        text.push_str(&format!("  Flat-Load Emissions: {:.2} kg CO2e\n", impact.flat_load_emissions_kg));
+        text.push_str(&format!("  Timing Shift: {:+.2} kg CO2e ({:+.1}%)\n", impact.shift_kg, impact.shift_percentage));
+    }
+    
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_curtailment_shifts_footprint() {
+        let mut tracker = EmissionsTracker::default();
+        tracker.load_default_emission_factors();
+        
+        // Four hours: clean overnight, dirty evening peak
+        let region = Region::new("US");
+        let start = DateTime::parse_from_rfc3339("2024-01-15T00:00:00Z").unwrap().with_timezone(&Utc);
+        let profile = [(100.0, Some(200.0)), (100.0, Some(200.0)), (500.0, Some(900.0)), (500.0, Some(900.0))];
+        for (i, (average, marginal)) in profile.iter().enumerate() {
+            tracker.record_hourly_intensity(region.clone(), start + Duration::hours(i as i64), *average, *marginal);
+        }
+        assert_eq!(tracker.hourly_intensities(&region, start, start + Duration::hours(4)).len(), 4);
+        
+        // The miner runs only in the clean hours
+        let blocks: Vec<BlockEnergyRecord> = (0..4)
+            .map(|i| BlockEnergyRecord {
+                height: i,
+                timestamp: start + Duration::minutes(30 * i as i64 + 10),
+                energy_kwh: 250.0,
+            })
+            .collect();
+        
+        let report = generate_curtailment_report(&tracker, "miner-1", &region, &blocks, start, start + Duration::hours(4)).unwrap();
+        assert_eq!(report.total_hours, 4);
+        assert_eq!(report.curtailed_hours, 2);
+        
+        // 1000 kWh at 100 g/kWh vs spread at a 300 g/kWh mean
+        assert!((report.average.footprint.emissions_kg - 100.0).abs() < 1e-9);
+        assert!((report.average.flat_load_emissions_kg - 300.0).abs() < 1e-9);
+        assert!((report.average.shift_percentage + 200.0 / 3.0).abs() < 1e-9);
+        
+        assert!((report.marginal.footprint.emissions_kg - 200.0).abs() < 1e-9);
+        assert!((report.marginal.flat_load_emissions_kg - 550.0).abs() < 1e-9);
+        assert!(report.marginal.footprint.hourly.iter().all(|hour| hour.hourly_data));
+        
+        // Hours without series data fall back to the static factor
+        let (intensity, hourly) = tracker.intensity_at(&region, start + Duration::hours(5), IntensityMode::Average);
+        assert!(!hourly);
+        assert!((intensity - 380.0).abs() < 1e-9);
+        
+        let text = generate_curtailment_text_report(&report);
+        assert!(text.contains("Curtailed Hours: 2 of 4"));
+    }
+}
@@ -26,6 +26,15 @@ pub enum TreasuryError {
     
     #[error("Verification failed: {0}")]
     VerificationFailed(String),
+    
+    #[error("Invalid treasury committee: {0}")]
+    InvalidCommittee(String),
+    
+    #[error("Unauthorized treasury spend: {0}")]
+    UnauthorizedSpend(String),
+    
+    #[error("Cannot disconnect block: {0}")]
+    DisconnectFailed(String),
 }
 
 /// Types of environmental assets that can be purchased
@@ -0,0 +1,620 @@
+//! On-chain environmental treasury
+//!
+//! `EnvironmentalTreasury` keeps balances in process memory. Here the treasury
+//! is a set of UTXOs locked to a committee script: consensus requires every
+//! coinbase to pay the treasury allocation to that script, and spending a
+//! treasury output needs `threshold` Dilithium signatures from the governance
+//! committee over the spending transaction and the approved proposal.
+//!
+//! `TreasuryLedger` rebuilds balance and spend history by replaying blocks,
+//! so every node derives the same treasury state from the chain. The most
+//! recent blocks can be disconnected again when the chain reorganizes.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::crypto::quantum::{verify_quantum_signature, QuantumKeyPair, QuantumParameters, QuantumScheme};
+use crate::environmental::treasury::TreasuryError;
+use crate::types::block::Block;
+use crate::types::transaction::{Transaction, TransactionInput};
+
+/// Prefix of every treasury locking script
+pub const TREASURY_SCRIPT_TAG: &[u8; 4] = b"SNET";
+
+/// Domain separator for committee signatures over treasury spends
+const TREASURY_SPEND_DOMAIN: &[u8] = b"supernova_treasury_spend";
+
+/// Default share of the block subsidy owed to the treasury (2%)
+pub const DEFAULT_TREASURY_ALLOCATION_BPS: u64 = 200;
+
+/// Blocks a chain-derived ledger keeps undo data for, matching the deepest
+/// reorganization the node accepts
+pub const LEDGER_UNDO_DEPTH: usize = 100;
+
+/// Governance committee controlling the treasury
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreasuryCommittee {
+    /// Signatures a spend needs
+    pub threshold: u16,
+    
+    /// Dilithium security level of the members' keys
+    pub security_level: u8,
+    
+    /// Dilithium public keys, indexed by position
+    pub members: Vec<Vec<u8>>,
+}
+
+impl TreasuryCommittee {
+    /// Check the threshold is reachable and members are distinct
+    pub fn validate(&self) -> Result<(), TreasuryError> {
+        if self.members.len() > u16::MAX as usize {
+            return Err(TreasuryError::InvalidCommittee("Too many members".to_string()));
+        }
+        if self.threshold == 0 || self.threshold as usize > self.members.len() {
+            return Err(TreasuryError::InvalidCommittee(
+                format!("Threshold {} with {} members", self.threshold, self.members.len())
+            ));
+        }
+        
+        let mut keys = HashSet::new();
+        for (index, key) in self.members.iter().enumerate() {
+            if !keys.insert(key) {
+                return Err(TreasuryError::InvalidCommittee(format!("Duplicate key for member {}", index)));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Locking script of the treasury: tag || sha256(committee)
+    pub fn script(&self) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.threshold.to_le_bytes());
+        hasher.update([self.security_level]);
+        for key in &self.members {
+            hasher.update((key.len() as u32).to_le_bytes());
+            hasher.update(key);
+        }
+        
+        let mut script = TREASURY_SCRIPT_TAG.to_vec();
+        script.extend_from_slice(&hasher.finalize());
+        script
+    }
+    
+    /// Sign a treasury spend as member `signer`
+    pub fn sign_spend(
+        &self,
+        authorization: &mut TreasuryAuthorization,
+        tx: &Transaction,
+        signer: u16,
+        keypair: &QuantumKeyPair,
+    ) -> Result<(), TreasuryError> {
+        if self.members.get(signer as usize) != Some(&keypair.public_key) {
+            return Err(TreasuryError::UnauthorizedSpend(format!("Key is not committee member {}", signer)));
+        }
+        if authorization.signatures.iter().any(|s| s.signer == signer) {
+            return Err(TreasuryError::UnauthorizedSpend(format!("Member {} signed twice", signer)));
+        }
+        
+        let message = treasury_spend_message(tx, &authorization.proposal_id);
+        let signature = keypair.sign(&message)
+            .map_err(|e| TreasuryError::UnauthorizedSpend(format!("Signing failed: {}", e)))?;
+        authorization.signatures.push(CommitteeSignature { signer, signature });
+        Ok(())
+    }
+    
+    /// Verify the authorization carried by input `input_index` of `tx`
+    ///
+    /// Every signature present must be valid, and at least `threshold`
+    /// distinct members must have signed. Returns the authorizing proposal.
+    pub fn verify_spend(&self, tx: &Transaction, input_index: usize) -> Result<String, TreasuryError> {
+        let input = tx.inputs().get(input_index)
+            .ok_or_else(|| TreasuryError::UnauthorizedSpend(format!("No input {}", input_index)))?;
+        let authorization = TreasuryAuthorization::from_script(input.signature_script())?;
+        
+        let message = treasury_spend_message(tx, &authorization.proposal_id);
+        let parameters = QuantumParameters::with_security_level(QuantumScheme::Dilithium, self.security_level);
+        let mut signers = HashSet::new();
+        
+        for signature in &authorization.signatures {
+            let key = self.members.get(signature.signer as usize)
+                .ok_or_else(|| TreasuryError::UnauthorizedSpend(format!("Unknown signer {}", signature.signer)))?;
+            if !signers.insert(signature.signer) {
+                return Err(TreasuryError::UnauthorizedSpend(format!("Member {} signed twice", signature.signer)));
+            }
+            
+            let valid = verify_quantum_signature(key, &message, &signature.signature, parameters)
+                .unwrap_or(false);
+            if !valid {
+                return Err(TreasuryError::UnauthorizedSpend(format!("Invalid signature from member {}", signature.signer)));
+            }
+        }
+        
+        if signers.len() < self.threshold as usize {
+            return Err(TreasuryError::UnauthorizedSpend(
+                format!("{} of {} required signatures", signers.len(), self.threshold)
+            ));
+        }
+        Ok(authorization.proposal_id)
+    }
+}
+
+/// Whether `script` locks funds to a treasury committee
+pub fn is_treasury_script(script: &[u8]) -> bool {
+    script.len() == TREASURY_SCRIPT_TAG.len() + 32 && script.starts_with(TREASURY_SCRIPT_TAG)
+}
+
+/// Message the committee signs for a spend
+///
+/// Covers the transaction with its signature scripts blanked, so each
+/// treasury input can carry the same authorization, and the proposal that
+/// approved the spend.
+pub fn treasury_spend_message(tx: &Transaction, proposal_id: &str) -> Vec<u8> {
+    let inputs = tx.inputs().iter()
+        .map(|input| TransactionInput::new(input.prev_tx_hash(), input.prev_output_index(), Vec::new(), input.sequence()))
+        .collect();
+    let unsigned = Transaction::new(tx.version(), inputs, tx.outputs().to_vec(), tx.lock_time());
+    
+    let mut hasher = Sha256::new();
+    hasher.update(TREASURY_SPEND_DOMAIN);
+    hasher.update(unsigned.hash());
+    hasher.update(proposal_id.as_bytes());
+    hasher.finalize().to_vec()
+}
+
+/// Committee member signature on a treasury spend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitteeSignature {
+    /// Index of the member in the committee
+    pub signer: u16,
+    
+    /// Dilithium signature over `treasury_spend_message`
+    pub signature: Vec<u8>,
+}
+
+/// Governance authorization carried in the signature script of a treasury input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreasuryAuthorization {
+    /// Approved proposal the spend executes
+    pub proposal_id: String,
+    
+    /// Committee signatures
+    pub signatures: Vec<CommitteeSignature>,
+}
+
+impl TreasuryAuthorization {
+    /// Start an authorization for `proposal_id`
+    pub fn new(proposal_id: impl Into<String>) -> Self {
+        Self {
+            proposal_id: proposal_id.into(),
+            signatures: Vec::new(),
+        }
+    }
+    
+    /// Encode as a signature script
+    pub fn to_script(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("authorization serialization cannot fail")
+    }
+    
+    /// Decode from a signature script
+    pub fn from_script(script: &[u8]) -> Result<Self, TreasuryError> {
+        bincode::deserialize(script)
+            .map_err(|e| TreasuryError::UnauthorizedSpend(format!("Malformed authorization: {}", e)))
+    }
+}
+
+/// Treasury rules enforced by block validation
+#[derive(Debug, Clone)]
+pub struct TreasuryConsensus {
+    /// Committee whose script receives allocations and authorizes spends
+    pub committee: TreasuryCommittee,
+    
+    /// Share of the block subsidy owed to the treasury, in basis points
+    pub allocation_bps: u64,
+}
+
+impl TreasuryConsensus {
+    /// Treasury rules with the default allocation
+    pub fn new(committee: TreasuryCommittee) -> Self {
+        Self {
+            committee,
+            allocation_bps: DEFAULT_TREASURY_ALLOCATION_BPS,
+        }
+    }
+    
+    /// Amount a coinbase must pay the treasury for a given subsidy
+    ///
+    /// Based on the subsidy alone, since fees are not known while the
+    /// coinbase is validated.
+    pub fn required_allocation(&self, subsidy: u64) -> u64 {
+        (subsidy as u128 * self.allocation_bps as u128 / 10_000) as u64
+    }
+}
+
+/// Coinbase payment into the treasury
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreasuryDeposit {
+    /// Block height
+    pub height: u64,
+    
+    /// Coinbase transaction
+    pub txid: [u8; 32],
+    
+    /// Amount paid to the treasury
+    pub amount: u64,
+}
+
+/// Payment out of the treasury
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreasurySpend {
+    /// Block height
+    pub height: u64,
+    
+    /// Spending transaction
+    pub txid: [u8; 32],
+    
+    /// Proposal that authorized the spend
+    pub proposal_id: String,
+    
+    /// Treasury funds consumed
+    pub amount_in: u64,
+    
+    /// Payments to scripts other than the treasury's
+    pub payments: Vec<(Vec<u8>, u64)>,
+    
+    /// Change returned to the treasury
+    pub change: u64,
+}
+
+impl TreasurySpend {
+    /// Total paid out of the treasury
+    pub fn paid_out(&self) -> u64 {
+        self.payments.iter().map(|(_, amount)| amount).sum()
+    }
+}
+
+/// Unspent treasury output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreasuryOutput {
+    /// Height of the block creating it
+    pub height: u64,
+    
+    /// Value of the output
+    pub amount: u64,
+}
+
+/// What a block changed in the ledger, to disconnect it again
+#[derive(Debug, Clone)]
+struct TreasuryUndo {
+    /// Hash of the applied block
+    block_hash: [u8; 32],
+    
+    /// Tip before the block was applied
+    prev_tip: Option<u64>,
+    
+    /// Treasury outputs the block spent
+    spent: Vec<(([u8; 32], u32), TreasuryOutput)>,
+}
+
+/// Treasury state derived from the chain
+#[derive(Debug, Clone)]
+pub struct TreasuryLedger {
+    /// Locking script of the treasury
+    script: Vec<u8>,
+    
+    /// Unspent treasury outputs by outpoint
+    outputs: BTreeMap<([u8; 32], u32), TreasuryOutput>,
+    
+    /// Coinbase allocations, oldest first
+    deposits: Vec<TreasuryDeposit>,
+    
+    /// Spends, oldest first
+    spends: Vec<TreasurySpend>,
+    
+    /// Height of the last applied block
+    tip: Option<u64>,
+    
+    /// Undo data of the most recent blocks, oldest first
+    undo: VecDeque<TreasuryUndo>,
+}
+
+impl TreasuryLedger {
+    /// Create an empty ledger tracking `script`
+    pub fn new(script: Vec<u8>) -> Self {
+        Self {
+            script,
+            outputs: BTreeMap::new(),
+            deposits: Vec::new(),
+            spends: Vec::new(),
+            tip: None,
+            undo: VecDeque::new(),
+        }
+    }
+    
+    /// Build a ledger by replaying `blocks` in chain order
+    pub fn from_blocks<'a>(script: Vec<u8>, blocks: impl IntoIterator<Item = &'a Block>) -> Self {
+        let mut ledger = Self::new(script);
+        for block in blocks {
+            ledger.apply_block(block);
+        }
+        ledger
+    }
+    
+    /// Apply the treasury effects of the next block
+    pub fn apply_block(&mut self, block: &Block) {
+        let height = block.height();
+        let mut undo = TreasuryUndo {
+            block_hash: block.hash(),
+            prev_tip: self.tip,
+            spent: Vec::new(),
+        };
+        
+        for tx in block.transactions() {
+            let txid = tx.hash();
+            
+            if !tx.is_coinbase() {
+                let mut amount_in = 0;
+                let mut proposal_id = None;
+                for input in tx.inputs() {
+                    let outpoint = (input.prev_tx_hash(), input.prev_output_index());
+                    if let Some(output) = self.outputs.remove(&outpoint) {
+                        undo.spent.push((outpoint, output));
+                        amount_in += output.amount;
+                        if proposal_id.is_none() {
+                            proposal_id = TreasuryAuthorization::from_script(input.signature_script())
+                                .ok()
+                                .map(|authorization| authorization.proposal_id);
+                        }
+                    }
+                }
+                
+                if amount_in > 0 {
+                    let (change, payments): (Vec<_>, Vec<_>) = tx.outputs().iter()
+                        .partition(|output| output.pub_key_script == self.script);
+                    self.spends.push(TreasurySpend {
+                        height,
+                        txid,
+                        proposal_id: proposal_id.unwrap_or_default(),
+                        amount_in,
+                        payments: payments.into_iter().map(|o| (o.pub_key_script.clone(), o.amount())).collect(),
+                        change: change.iter().map(|o| o.amount()).sum(),
+                    });
+                }
+            }
+            
+            let mut deposited = 0;
+            for (index, output) in tx.outputs().iter().enumerate() {
+                if output.pub_key_script == self.script {
+                    self.outputs.insert((txid, index as u32), TreasuryOutput { height, amount: output.amount() });
+                    deposited += output.amount();
+                }
+            }
+            if tx.is_coinbase() && deposited > 0 {
+                self.deposits.push(TreasuryDeposit { height, txid, amount: deposited });
+            }
+        }
+        
+        self.tip = Some(height);
+        self.undo.push_back(undo);
+        if self.undo.len() > LEDGER_UNDO_DEPTH {
+            self.undo.pop_front();
+        }
+    }
+    
+    /// Revert the last applied block, which must be `block`
+    ///
+    /// Only the last `LEDGER_UNDO_DEPTH` blocks can be disconnected; deeper
+    /// reorganizations need the ledger rebuilt from the chain.
+    pub fn disconnect_block(&mut self, block: &Block) -> Result<(), TreasuryError> {
+        let undo = match self.undo.back() {
+            Some(undo) if undo.block_hash == block.hash() => self.undo.pop_back().expect("undo entry exists"),
+            _ => return Err(TreasuryError::DisconnectFailed(
+                format!("block {} is not the ledger tip", hex::encode(block.hash()))
+            )),
+        };
+        let height = block.height();
+        
+        // Outputs created and spent within the block stay gone
+        let mut created = HashSet::new();
+        for tx in block.transactions() {
+            let txid = tx.hash();
+            created.insert(txid);
+            for index in 0..tx.outputs().len() {
+                self.outputs.remove(&(txid, index as u32));
+            }
+        }
+        self.outputs.extend(undo.spent.into_iter().filter(|((txid, _), _)| !created.contains(txid)));
+        
+        while self.deposits.last().is_some_and(|deposit| deposit.height == height) {
+            self.deposits.pop();
+        }
+        while self.spends.last().is_some_and(|spend| spend.height == height) {
+            self.spends.pop();
+        }
+        self.tip = undo.prev_tip;
+        Ok(())
+    }
+    
+    /// Locking script tracked by this ledger
+    pub fn script(&self) -> &[u8] {
+        &self.script
+    }
+    
+    /// Spendable treasury balance
+    pub fn balance(&self) -> u64 {
+        self.outputs.values().map(|output| output.amount).sum()
+    }
+    
+    /// Total ever allocated by coinbases
+    pub fn total_allocated(&self) -> u64 {
+        self.deposits.iter().map(|deposit| deposit.amount).sum()
+    }
+    
+    /// Unspent treasury outputs
+    pub fn unspent_outputs(&self) -> impl Iterator<Item = (&([u8; 32], u32), &TreasuryOutput)> {
+        self.outputs.iter()
+    }
+    
+    /// Coinbase allocations, oldest first
+    pub fn deposits(&self) -> &[TreasuryDeposit] {
+        &self.deposits
+    }
+    
+    /// Spends, oldest first
+    pub fn spends(&self) -> &[TreasurySpend] {
+        &self.spends
+    }
+    
+    /// Spends executing a given proposal
+    pub fn spends_for_proposal(&self, proposal_id: &str) -> Vec<&TreasurySpend> {
+        self.spends.iter().filter(|spend| spend.proposal_id == proposal_id).collect()
+    }
+    
+    /// Height of the last applied block
+    pub fn tip(&self) -> Option<u64> {
+        self.tip
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::transaction::TransactionOutput;
+    
+    fn committee(threshold: u16, keys: &[QuantumKeyPair]) -> TreasuryCommittee {
+        TreasuryCommittee {
+            threshold,
+            security_level: 2,
+            members: keys.iter().map(|key| key.public_key.clone()).collect(),
+        }
+    }
+    
+    fn block(height: u64, prev: [u8; 32], transactions: Vec<Transaction>) -> Block {
+        let mut block = Block::new_with_params(1, prev, transactions, 0x207fffff);
+        block.set_height(height);
+        block
+    }
+    
+    #[test]
+    fn test_spend_authorization_threshold() {
+        let keys: Vec<_> = (0..3)
+            .map(|_| QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap())
+            .collect();
+        let committee = committee(2, &keys);
+        committee.validate().unwrap();
+        assert!(is_treasury_script(&committee.script()));
+        
+        let spend = |script: Vec<u8>| Transaction::new(
+            1,
+            vec![TransactionInput::new([7u8; 32], 0, script, 0xffffffff)],
+            vec![TransactionOutput::new(1_000, vec![1, 2, 3])],
+            0,
+        );
+        
+        let unsigned = spend(Vec::new());
+        let mut authorization = TreasuryAuthorization::new("proposal-1");
+        committee.sign_spend(&mut authorization, &unsigned, 0, &keys[0]).unwrap();
+        assert!(committee.sign_spend(&mut authorization, &unsigned, 1, &keys[2]).is_err());
+        
+        // One of two signatures is not enough
+        assert!(committee.verify_spend(&spend(authorization.to_script()), 0).is_err());
+        
+        committee.sign_spend(&mut authorization, &unsigned, 2, &keys[2]).unwrap();
+        let signed = spend(authorization.to_script());
+        assert_eq!(committee.verify_spend(&signed, 0).unwrap(), "proposal-1");
+        
+        // Signatures do not carry over to a different payment
+        let redirected = Transaction::new(
+            1,
+            signed.inputs().to_vec(),
+            vec![TransactionOutput::new(1_000, vec![9, 9, 9])],
+            0,
+        );
+        assert!(committee.verify_spend(&redirected, 0).is_err());
+    }
+    
+    #[test]
+    fn test_ledger_from_blocks() {
+        let key = QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap();
+        let script = committee(1, std::slice::from_ref(&key)).script();
+        
+        let coinbase = |tag: u8| Transaction::new(
+            1,
+            vec![TransactionInput::new_coinbase(vec![tag])],
+            vec![
+                TransactionOutput::new(49_000_000_000, vec![0xaa]),
+                TransactionOutput::new(1_000_000_000, script.clone()),
+            ],
+            0,
+        );
+        let first = coinbase(1);
+        let spend = Transaction::new(
+            1,
+            vec![TransactionInput::new(first.hash(), 1, TreasuryAuthorization::new("recs").to_script(), 0xffffffff)],
+            vec![
+                TransactionOutput::new(400_000_000, vec![0xbb]),
+                TransactionOutput::new(600_000_000, script.clone()),
+            ],
+            0,
+        );
+        
+        let blocks = vec![
+            block(1, [0u8; 32], vec![first]),
+            block(2, [1u8; 32], vec![coinbase(2), spend]),
+        ];
+        let ledger = TreasuryLedger::from_blocks(script, &blocks);
+        
+        assert_eq!(ledger.tip(), Some(2));
+        assert_eq!(ledger.total_allocated(), 2_000_000_000);
+        assert_eq!(ledger.balance(), 1_600_000_000);
+        assert_eq!(ledger.unspent_outputs().count(), 2);
+        
+        let spends = ledger.spends_for_proposal("recs");
+        assert_eq!(spends.len(), 1);
+        assert_eq!(spends[0].amount_in, 1_000_000_000);
+        assert_eq!(spends[0].paid_out(), 400_000_000);
+        assert_eq!(spends[0].change, 600_000_000);
+    }
+    
+    #[test]
+    fn test_disconnect_block() {
+        let key = QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap();
+        let script = committee(1, std::slice::from_ref(&key)).script();
+        
+        let coinbase = |tag: u8| Transaction::new(
+            1,
+            vec![TransactionInput::new_coinbase(vec![tag])],
+            vec![TransactionOutput::new(1_000_000_000, script.clone())],
+            0,
+        );
+        let first = coinbase(1);
+        let spend = Transaction::new(
+            1,
+            vec![TransactionInput::new(first.hash(), 0, TreasuryAuthorization::new("recs").to_script(), 0xffffffff)],
+            vec![
+                TransactionOutput::new(400_000_000, vec![0xbb]),
+                TransactionOutput::new(600_000_000, script.clone()),
+            ],
+            0,
+        );
+        let first_block = block(1, [0u8; 32], vec![first]);
+        let second_block = block(2, first_block.hash(), vec![coinbase(2), spend]);
+        
+        let mut ledger = TreasuryLedger::from_blocks(script.clone(), [&first_block, &second_block]);
+        assert!(ledger.disconnect_block(&first_block).is_err());
+        ledger.disconnect_block(&second_block).unwrap();
+        
+        // Back to the state after the first block
+        let expected = TreasuryLedger::from_blocks(script, [&first_block]);
+        assert_eq!(ledger.tip(), Some(1));
+        assert_eq!(ledger.balance(), expected.balance());
+        assert_eq!(ledger.deposits(), expected.deposits());
+        assert!(ledger.spends().is_empty());
+        assert_eq!(ledger.unspent_outputs().collect::<Vec<_>>(), expected.unspent_outputs().collect::<Vec<_>>());
+        
+        ledger.disconnect_block(&first_block).unwrap();
+        assert_eq!(ledger.tip(), None);
+        assert_eq!(ledger.balance(), 0);
+    }
+}
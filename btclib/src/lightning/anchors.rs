@@ -0,0 +1,494 @@
+//! Anchor Outputs
+//!
+//! Commitments in the anchor-output format only pay the minimum relay fee.
+//! Each party gets a small anchor output it can spend immediately, so the
+//! fee is chosen at broadcast time with a child-pays-for-parent transaction
+//! funded by the wallet. HTLC outputs carry a one-block relative delay, and
+//! their second-stage transactions are signed by the counterparty with
+//! SINGLE|ANYONECANPAY so the broadcaster can attach fee inputs and a change
+//! output without invalidating that signature.
+
+use secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1, SecretKey};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::script::{Opcode, ScriptBuilder};
+use crate::types::transaction::{OutPoint, Transaction, TransactionInput, TransactionOutput};
+
+use super::wallet::{LightningWallet, WalletError, WalletUtxo};
+
+/// Value of each anchor output in novas
+pub const ANCHOR_OUTPUT_NOVAS: u64 = 330;
+
+/// Blocks before the counterparty's anchor can be swept by anyone
+pub const ANCHOR_SWEEP_DELAY: i64 = 16;
+
+/// Fee rate anchor commitments are signed at, in novas per byte
+pub const MIN_RELAY_FEERATE: u64 = 1;
+
+/// Fee rate targeted when bumping a force-close, in novas per byte
+pub const DEFAULT_FORCE_CLOSE_FEERATE: u64 = 10;
+
+/// Change below this value is left to miners
+const DUST_LIMIT_NOVAS: u64 = 546;
+
+/// Length of a compact signature followed by its sighash byte
+const SIGNATURE_LEN: usize = 65;
+
+/// Anchor output errors
+#[derive(Debug, Error)]
+pub enum AnchorError {
+    #[error("Input {0} not found")]
+    MissingInput(usize),
+    
+    #[error("SIGHASH_SINGLE input {0} has no matching output")]
+    MissingOutput(usize),
+    
+    #[error("Anchor output not found in commitment")]
+    AnchorNotFound,
+    
+    #[error("Invalid key: {0}")]
+    Key(String),
+    
+    #[error("Wallet error: {0}")]
+    Wallet(#[from] WalletError),
+}
+
+/// Which parts of a transaction a signature commits to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SigHashType {
+    /// Every input and every output
+    All = 0x01,
+    
+    /// Only the signed input and the output at the same index
+    SingleAnyoneCanPay = 0x83,
+}
+
+impl SigHashType {
+    /// Parse a sighash byte
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0x01 => Some(SigHashType::All),
+            0x83 => Some(SigHashType::SingleAnyoneCanPay),
+            _ => None,
+        }
+    }
+}
+
+/// Digest signed for input `input_index` of `tx`
+///
+/// Script signatures are never covered, so inputs can be signed in any
+/// order.
+pub fn signature_hash(tx: &Transaction, input_index: usize, sighash_type: SigHashType) -> Result<[u8; 32], AnchorError> {
+    let input = tx.inputs().get(input_index).ok_or(AnchorError::MissingInput(input_index))?;
+    
+    let mut hasher = Sha256::new();
+    hasher.update(tx.version().to_le_bytes());
+    
+    match sighash_type {
+        SigHashType::All => {
+            for input in tx.inputs() {
+                hash_input(&mut hasher, input);
+            }
+            for output in tx.outputs() {
+                hash_output(&mut hasher, output);
+            }
+            hasher.update((input_index as u32).to_le_bytes());
+        }
+        SigHashType::SingleAnyoneCanPay => {
+            let output = tx.outputs().get(input_index).ok_or(AnchorError::MissingOutput(input_index))?;
+            hash_input(&mut hasher, input);
+            hash_output(&mut hasher, output);
+        }
+    }
+    
+    hasher.update(tx.lock_time().to_le_bytes());
+    hasher.update([sighash_type as u8]);
+    Ok(hasher.finalize().into())
+}
+
+fn hash_input(hasher: &mut Sha256, input: &TransactionInput) {
+    hasher.update(input.prev_tx_hash());
+    hasher.update(input.prev_output_index().to_le_bytes());
+    hasher.update(input.sequence().to_le_bytes());
+}
+
+fn hash_output(hasher: &mut Sha256, output: &TransactionOutput) {
+    hasher.update(output.amount().to_le_bytes());
+    hasher.update((output.script_pubkey().len() as u32).to_le_bytes());
+    hasher.update(output.script_pubkey());
+}
+
+/// Sign input `input_index`, returning the compact signature and sighash byte
+pub fn sign_input(
+    tx: &Transaction,
+    input_index: usize,
+    sighash_type: SigHashType,
+    key: &SecretKey,
+) -> Result<Vec<u8>, AnchorError> {
+    let digest = signature_hash(tx, input_index, sighash_type)?;
+    let message = Message::from_slice(&digest).map_err(|e| AnchorError::Key(e.to_string()))?;
+    
+    let mut signature = Secp256k1::new().sign_ecdsa(&message, key).serialize_compact().to_vec();
+    signature.push(sighash_type as u8);
+    Ok(signature)
+}
+
+/// Check a signature produced by `sign_input` against `pubkey`
+pub fn verify_input_signature(tx: &Transaction, input_index: usize, pubkey: &[u8], signature: &[u8]) -> bool {
+    let Some((&type_byte, compact)) = signature.split_last() else {
+        return false;
+    };
+    let Some(sighash_type) = SigHashType::from_byte(type_byte) else {
+        return false;
+    };
+    let Ok(digest) = signature_hash(tx, input_index, sighash_type) else {
+        return false;
+    };
+    
+    match (PublicKey::from_slice(pubkey), Signature::from_compact(compact), Message::from_slice(&digest)) {
+        (Ok(pubkey), Ok(signature), Ok(message)) => {
+            Secp256k1::verification_only().verify_ecdsa(&message, &signature, &pubkey).is_ok()
+        }
+        _ => false,
+    }
+}
+
+/// Script of an anchor spendable by `pubkey`, or by anyone after 16 blocks
+pub fn anchor_redeem_script(pubkey: &[u8]) -> Vec<u8> {
+    ScriptBuilder::new()
+        .push_data(pubkey)
+        .push_opcode(Opcode::OP_CHECKSIG)
+        .push_opcode(Opcode::OP_IFDUP)
+        .push_opcode(Opcode::OP_NOTIF)
+        .push_number(ANCHOR_SWEEP_DELAY)
+        .push_opcode(Opcode::OP_CHECKSEQUENCEVERIFY)
+        .push_opcode(Opcode::OP_ENDIF)
+        .build()
+}
+
+/// P2SH output script of an anchor
+pub fn anchor_script_pubkey(pubkey: &[u8]) -> Vec<u8> {
+    ScriptBuilder::pay_to_script_hash(&ScriptBuilder::hash_pubkey(&anchor_redeem_script(pubkey)))
+}
+
+/// Script of an HTLC output on an anchor commitment
+///
+/// The receiver claims with the preimage, the offerer after the CLTV
+/// expiry. Both paths wait one confirmation so an unconfirmed HTLC spend
+/// cannot pin the commitment.
+pub fn htlc_redeem_script(payment_hash: &[u8; 32], receiver_pubkey: &[u8], offerer_pubkey: &[u8], expiry_height: u32) -> Vec<u8> {
+    ScriptBuilder::new()
+        .push_number(1)
+        .push_opcode(Opcode::OP_CHECKSEQUENCEVERIFY)
+        .push_opcode(Opcode::OP_DROP)
+        .push_opcode(Opcode::OP_IF)
+        .push_opcode(Opcode::OP_SHA256)
+        .push_data(payment_hash)
+        .push_opcode(Opcode::OP_EQUALVERIFY)
+        .push_data(receiver_pubkey)
+        .push_opcode(Opcode::OP_ELSE)
+        .push_number(expiry_height as i64)
+        .push_opcode(Opcode::OP_CHECKLOCKTIMEVERIFY)
+        .push_opcode(Opcode::OP_DROP)
+        .push_data(offerer_pubkey)
+        .push_opcode(Opcode::OP_ENDIF)
+        .push_opcode(Opcode::OP_CHECKSIG)
+        .build()
+}
+
+/// Script of the counterparty's balance output, spendable after one block
+pub fn to_remote_redeem_script(pubkey: &[u8]) -> Vec<u8> {
+    ScriptBuilder::new()
+        .push_data(pubkey)
+        .push_opcode(Opcode::OP_CHECKSIGVERIFY)
+        .push_number(1)
+        .push_opcode(Opcode::OP_CHECKSEQUENCEVERIFY)
+        .build()
+}
+
+/// Script paying to `pubkey` after a relative delay
+pub fn delayed_redeem_script(pubkey: &[u8], to_self_delay: u16) -> Vec<u8> {
+    ScriptBuilder::new()
+        .push_number(to_self_delay as i64)
+        .push_opcode(Opcode::OP_CHECKSEQUENCEVERIFY)
+        .push_opcode(Opcode::OP_DROP)
+        .push_data(pubkey)
+        .push_opcode(Opcode::OP_CHECKSIG)
+        .build()
+}
+
+/// Second-stage transaction type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtlcTxKind {
+    /// Claims a received HTLC with the preimage
+    Success,
+    
+    /// Reclaims an offered HTLC after expiry
+    Timeout,
+}
+
+/// Second-stage transaction spending an HTLC output of a commitment
+#[derive(Debug, Clone)]
+pub struct HtlcTransaction {
+    /// ID of the HTLC in the channel
+    pub htlc_id: u64,
+    
+    /// Success or timeout path
+    pub kind: HtlcTxKind,
+    
+    /// Zero-fee transaction; input and output 0 are the HTLC's
+    pub tx: Transaction,
+}
+
+impl HtlcTransaction {
+    /// Counterparty signature over the HTLC input and output only
+    pub fn sign(&self, key: &SecretKey) -> Result<Vec<u8>, AnchorError> {
+        sign_input(&self.tx, 0, SigHashType::SingleAnyoneCanPay, key)
+    }
+    
+    /// Attach fee-paying inputs and an optional change output
+    ///
+    /// The HTLC input and output keep index 0, so a SINGLE|ANYONECANPAY
+    /// signature over them stays valid.
+    pub fn with_fee_inputs(&self, fee_inputs: Vec<TransactionInput>, change: Option<TransactionOutput>) -> Transaction {
+        let mut inputs = self.tx.inputs().to_vec();
+        inputs.extend(fee_inputs);
+        let mut outputs = self.tx.outputs().to_vec();
+        outputs.extend(change);
+        
+        Transaction::new(self.tx.version(), inputs, outputs, self.tx.lock_time())
+    }
+}
+
+/// Child transaction bumping the fee of a commitment through its anchor
+#[derive(Debug, Clone)]
+pub struct FeeBump {
+    /// Signed child transaction
+    pub child_tx: Transaction,
+    
+    /// Fee paid by the child in novas
+    pub fee: u64,
+    
+    /// Fee rate of commitment and child together, in novas per byte
+    pub package_feerate: u64,
+}
+
+/// Build a CPFP child spending our anchor and wallet funds
+///
+/// The anchor must pay to the wallet's on-chain key, which also signs the
+/// wallet outputs and receives the change. `parent_fee` is the fee already
+/// paid by the commitment.
+pub fn bump_commitment_fee(
+    commitment: &Transaction,
+    parent_fee: u64,
+    wallet: &mut LightningWallet,
+    target_feerate: u64,
+) -> Result<FeeBump, AnchorError> {
+    let key = wallet.onchain_key()?;
+    let wallet_pubkey = PublicKey::from_secret_key(&Secp256k1::new(), &key).serialize();
+    let change_script = wallet.change_script()?;
+    
+    let anchor_script = anchor_script_pubkey(&wallet_pubkey);
+    let anchor_vout = commitment.outputs().iter()
+        .position(|output| output.script_pubkey() == anchor_script.as_slice())
+        .ok_or(AnchorError::AnchorNotFound)?;
+    let anchor = OutPoint { txid: commitment.hash(), vout: anchor_vout as u32 };
+    let anchor_value = commitment.outputs()[anchor_vout].amount();
+    let anchor_redeem = anchor_redeem_script(&wallet_pubkey);
+    
+    let parent_size = commitment.calculate_size() as u64;
+    let child = |utxos: &[WalletUtxo], change: u64| {
+        child_transaction(&anchor, &anchor_redeem, utxos, &wallet_pubkey, &change_script, change)
+    };
+    
+    // Add wallet outputs, largest first, until they cover the fee and change
+    let mut candidates = wallet.utxos().to_vec();
+    candidates.sort_by_key(|utxo| std::cmp::Reverse(utxo.amount));
+    let mut candidates = candidates.into_iter();
+    let mut selected = Vec::new();
+    let fee = loop {
+        let size = parent_size + child(&selected, 0).calculate_size() as u64;
+        let fee = (target_feerate * size).saturating_sub(parent_fee);
+        let funded = anchor_value + selected.iter().map(|utxo: &WalletUtxo| utxo.amount).sum::<u64>();
+        if !selected.is_empty() && funded >= fee + DUST_LIMIT_NOVAS {
+            break fee;
+        }
+        selected.push(candidates.next().ok_or_else(|| WalletError::InsufficientFunds(
+            format!("Wallet cannot pay {} novas to bump the commitment fee", fee)
+        ))?);
+    };
+    
+    let total_in = anchor_value + selected.iter().map(|utxo| utxo.amount).sum::<u64>();
+    let child_tx = sign_child(&child(&selected, total_in - fee), &anchor_redeem, &wallet_pubkey, &key)?;
+    wallet.spend_utxos(&selected.iter().map(|utxo| utxo.outpoint.clone()).collect::<Vec<_>>());
+    
+    let package_feerate = (parent_fee + fee) / (parent_size + child_tx.calculate_size() as u64);
+    
+    Ok(FeeBump { child_tx, fee, package_feerate })
+}
+
+/// Child with placeholder signatures of the final length
+fn child_transaction(
+    anchor: &OutPoint,
+    anchor_redeem: &[u8],
+    utxos: &[WalletUtxo],
+    wallet_pubkey: &[u8],
+    change_script: &[u8],
+    change: u64,
+) -> Transaction {
+    let placeholder = [0u8; SIGNATURE_LEN];
+    
+    let mut inputs = vec![TransactionInput::new(
+        anchor.txid,
+        anchor.vout,
+        ScriptBuilder::new().push_data(&placeholder).push_data(anchor_redeem).build(),
+        0xFFFF_FFFF,
+    )];
+    inputs.extend(utxos.iter().map(|utxo| TransactionInput::new(
+        utxo.outpoint.txid,
+        utxo.outpoint.vout,
+        ScriptBuilder::new().push_data(&placeholder).push_data(wallet_pubkey).build(),
+        0xFFFF_FFFF,
+    )));
+    
+    Transaction::new(2, inputs, vec![TransactionOutput::new(change, change_script.to_vec())], 0)
+}
+
+fn sign_child(unsigned: &Transaction, anchor_redeem: &[u8], wallet_pubkey: &[u8], key: &SecretKey) -> Result<Transaction, AnchorError> {
+    let mut inputs = Vec::with_capacity(unsigned.inputs().len());
+    
+    for (index, input) in unsigned.inputs().iter().enumerate() {
+        let signature = sign_input(unsigned, index, SigHashType::All, key)?;
+        let last_push = if index == 0 { anchor_redeem } else { wallet_pubkey };
+        let script_sig = ScriptBuilder::new().push_data(&signature).push_data(last_push).build();
+        inputs.push(TransactionInput::new(input.prev_tx_hash(), input.prev_output_index(), script_sig, input.sequence()));
+    }
+    
+    Ok(Transaction::new(unsigned.version(), inputs, unsigned.outputs().to_vec(), unsigned.lock_time()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightning::channel::{Channel, ChannelState, CommitmentFormat, PublicKey as ChannelPublicKey};
+    
+    fn anchor_channel(wallet: &LightningWallet, remote_key: &SecretKey) -> Channel {
+        let remote_pubkey = PublicKey::from_secret_key(&Secp256k1::new(), remote_key).serialize();
+        let mut channel = Channel::new(
+            ChannelPublicKey::from_bytes(wallet.onchain_pubkey().unwrap()),
+            ChannelPublicKey::from_bytes(remote_pubkey),
+            1_000_000,
+            true,
+            false,
+        );
+        channel.commitment_format = CommitmentFormat::AnchorOutputs;
+        channel.create_funding_transaction(vec![TransactionInput::new([7u8; 32], 0, Vec::new(), 0xffffffff)], None, 1)
+            .unwrap();
+        channel.state = ChannelState::Active;
+        channel
+    }
+    
+    #[test]
+    fn test_anchor_commitment_and_htlc_transactions() {
+        let wallet = LightningWallet::new_test_wallet(0);
+        let remote_key = SecretKey::from_slice(&[0x42; 32]).unwrap();
+        let remote_pubkey = PublicKey::from_secret_key(&Secp256k1::new(), &remote_key).serialize();
+        let mut channel = anchor_channel(&wallet, &remote_key);
+        channel.local_balance_novas = 800_000;
+        channel.remote_balance_novas = 200_000;
+        
+        let offered = channel.add_htlc([1u8; 32], 100_000, 500, true).unwrap();
+        let received = channel.add_htlc([2u8; 32], 50_000, 600, false).unwrap();
+        let commitment = channel.create_commitment_transaction().unwrap();
+        
+        // Balances, one output per HTLC, then an anchor for each party
+        let outputs = commitment.outputs();
+        assert_eq!(outputs.len(), 6);
+        assert_eq!(outputs[2].amount(), 100_000);
+        assert_eq!(outputs[3].amount(), 50_000);
+        assert_eq!(outputs[4].script_pubkey(), anchor_script_pubkey(&wallet.onchain_pubkey().unwrap()).as_slice());
+        assert_eq!(outputs[5].script_pubkey(), anchor_script_pubkey(&remote_pubkey).as_slice());
+        
+        // The funder pays for the anchors and the minimum relay fee
+        let fee = channel.commitment_fee(&commitment);
+        assert!(fee > 0 && fee <= commitment.calculate_size() as u64 * MIN_RELAY_FEERATE);
+        assert_eq!(outputs[0].amount(), 700_000 - 2 * ANCHOR_OUTPUT_NOVAS - fee);
+        assert_eq!(outputs[1].amount(), 150_000);
+        
+        let htlc_txs = channel.htlc_transactions(&commitment).unwrap();
+        assert_eq!(htlc_txs.len(), 2);
+        assert_eq!((htlc_txs[0].htlc_id, htlc_txs[0].kind), (offered, HtlcTxKind::Timeout));
+        assert_eq!((htlc_txs[1].htlc_id, htlc_txs[1].kind), (received, HtlcTxKind::Success));
+        assert_eq!(htlc_txs[0].tx.lock_time(), 500);
+        assert_eq!(htlc_txs[1].tx.inputs()[0].prev_output_index(), 3);
+        assert_eq!(htlc_txs[1].tx.outputs()[0].amount(), 50_000);
+        
+        // The counterparty's signature survives attaching fee inputs and change
+        let timeout = &htlc_txs[0];
+        let signature = timeout.sign(&remote_key).unwrap();
+        assert!(verify_input_signature(&timeout.tx, 0, &remote_pubkey, &signature));
+        
+        let fee_input = TransactionInput::new([8u8; 32], 1, Vec::new(), 0xffffffff);
+        let change = TransactionOutput::new(20_000, vec![0x51]);
+        let bumped = timeout.with_fee_inputs(vec![fee_input.clone()], Some(change));
+        assert_eq!(bumped.inputs().len(), 2);
+        assert!(verify_input_signature(&bumped, 0, &remote_pubkey, &signature));
+        
+        // ...but not changes to the HTLC output itself
+        let stolen = HtlcTransaction {
+            tx: Transaction::new(2, timeout.tx.inputs().to_vec(), vec![TransactionOutput::new(100_000, vec![0x51])], 500),
+            ..timeout.clone()
+        };
+        assert!(!verify_input_signature(&stolen.tx, 0, &remote_pubkey, &signature));
+        
+        // A SIGHASH_ALL signature would break as soon as fees are attached
+        let all = sign_input(&timeout.tx, 0, SigHashType::All, &remote_key).unwrap();
+        assert!(verify_input_signature(&timeout.tx, 0, &remote_pubkey, &all));
+        assert!(!verify_input_signature(&timeout.with_fee_inputs(vec![fee_input], None), 0, &remote_pubkey, &all));
+    }
+    
+    #[test]
+    fn test_force_close_bumps_fee_through_anchor() {
+        let mut wallet = LightningWallet::new_test_wallet(0);
+        let wallet_pubkey = wallet.onchain_pubkey().unwrap();
+        let mut channel = anchor_channel(&wallet, &SecretKey::from_slice(&[0x42; 32]).unwrap());
+        let commitment = channel.create_commitment_transaction().unwrap();
+        
+        // Without wallet funds the channel is left open
+        assert!(channel.force_close_with_fee_bump(&mut wallet, 10).is_err());
+        assert_eq!(channel.state, ChannelState::Active);
+        
+        wallet.add_utxo(WalletUtxo { outpoint: OutPoint { txid: [9u8; 32], vout: 0 }, amount: 30_000 });
+        wallet.add_utxo(WalletUtxo { outpoint: OutPoint { txid: [9u8; 32], vout: 1 }, amount: 5_000 });
+        
+        let (closed, bump) = channel.force_close_with_fee_bump(&mut wallet, 10).unwrap();
+        assert_eq!(closed.hash(), commitment.hash());
+        assert_eq!(channel.state, ChannelState::ForceClosed);
+        
+        // The child spends our anchor and the largest wallet output
+        let child = &bump.child_tx;
+        assert_eq!(child.inputs().len(), 2);
+        assert_eq!(child.inputs()[0].prev_tx_hash(), commitment.hash());
+        let anchor = &commitment.outputs()[child.inputs()[0].prev_output_index() as usize];
+        assert_eq!(anchor.script_pubkey(), anchor_script_pubkey(&wallet_pubkey).as_slice());
+        assert_eq!(child.inputs()[1].prev_output_index(), 0);
+        
+        // Commitment and child together meet the target fee rate
+        assert!(bump.package_feerate >= 10);
+        let package_size = (commitment.calculate_size() + child.calculate_size()) as u64;
+        assert_eq!(channel.commitment_fee(&commitment) + bump.fee, package_size * 10);
+        assert_eq!(child.outputs()[0].amount(), ANCHOR_OUTPUT_NOVAS + 30_000 - bump.fee);
+        assert_eq!(child.outputs()[0].script_pubkey(), wallet.change_script().unwrap().as_slice());
+        
+        // Every input is signed by the wallet key over the whole child
+        for index in 0..child.inputs().len() {
+            let signature = &child.inputs()[index].signature_script()[1..1 + SIGNATURE_LEN];
+            assert!(verify_input_signature(child, index, &wallet_pubkey, signature));
+        }
+        
+        assert_eq!(wallet.utxos().len(), 1);
+        assert_eq!(wallet.get_on_chain_balance(), 5_000);
+    }
+}
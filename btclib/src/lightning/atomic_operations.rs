@@ -508,6 +508,26 @@ impl AtomicChannel {
         })
     }
     
+    /// Latest commitment transaction, if one was created
+    pub fn current_commitment(&self) -> AtomicResult<Option<Transaction>> {
+        let channel = self.channel.lock()
+            .map_err(|e| AtomicOperationError::LockError(format!("Failed to acquire channel lock: {}", e)))?;
+        
+        Ok(channel.commitment_tx.clone())
+    }
+    
+    /// Commitment the counterparty holds for the current balances
+    pub fn remote_commitment(&self) -> AtomicResult<Transaction> {
+        let mut channel = self.channel.lock()
+            .map_err(|e| AtomicOperationError::LockError(format!("Failed to acquire channel lock: {}", e)))?;
+        
+        channel.local_balance_novas = self.state.local_balance.load(Ordering::SeqCst);
+        channel.remote_balance_novas = self.state.remote_balance.load(Ordering::SeqCst);
+        
+        channel.remote_commitment_transaction()
+            .map_err(|e| AtomicOperationError::ChannelError(e))
+    }
+    
     /// Create commitment transaction atomically
     pub fn create_commitment_transaction(&self) -> AtomicResult<Transaction> {
         let _htlc_guard = self.state.htlc_lock.lock()
@@ -12,6 +12,11 @@ use serde::{Serialize, Deserialize, Serializer, Deserializer};
 use crate::types::transaction::{Transaction, TransactionInput as TxIn, TransactionOutput as TxOut, OutPoint};
 use crate::crypto::signature::SignatureScheme;
 use crate::crypto::quantum::{QuantumKeyPair, QuantumScheme};
+use crate::lightning::anchors::{
+    self, FeeBump, HtlcTransaction, HtlcTxKind, ANCHOR_OUTPUT_NOVAS, MIN_RELAY_FEERATE,
+};
+use crate::lightning::wallet::LightningWallet;
+use crate::script::ScriptBuilder;
 use std::sync::{Arc, RwLock, Mutex};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 // TODO: Replace with actual Script type
@@ -36,6 +41,10 @@ pub struct PublicKey([u8; 33]);  // Wrapper struct instead of type alias
 pub struct PrivateKey([u8; 32]); // Wrapper struct instead of type alias
 
 impl PublicKey {
+    pub fn from_bytes(bytes: [u8; 33]) -> Self {
+        Self(bytes)
+    }
+    
     pub fn serialize(&self) -> [u8; 33] {
         self.0
     }
@@ -110,6 +119,9 @@ pub enum ChannelError {
     
     #[error("Closure error: {0}")]
     ClosureError(String),
+    
+    #[error("Splice error: {0}")]
+    SpliceError(String),
 }
 
 /// Result type for channel operations
@@ -238,6 +250,9 @@ pub struct ChannelConfig {
     
     /// Maximum CLTV expiry delta for HTLCs
     pub max_cltv_expiry_delta: u16,
+    
+    /// Whether commitments use the anchor-output format
+    pub anchor_outputs: bool,
 }
 
 impl Default for ChannelConfig {
@@ -255,10 +270,22 @@ impl Default for ChannelConfig {
             force_close_timeout_seconds: 86400,        // 24 hours
             min_cltv_expiry_delta: 144, // Minimum 1 day (assuming 10min blocks)
             max_cltv_expiry_delta: 2016, // Maximum 2 weeks (assuming 10min blocks)
+            anchor_outputs: false,
         }
     }
 }
 
+/// Layout of commitment transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CommitmentFormat {
+    /// Two balance outputs, fee fixed when signing
+    #[default]
+    Legacy,
+    
+    /// Balance and HTLC outputs plus an anchor per party, fee bumped at broadcast
+    AnchorOutputs,
+}
+
 /// Information about a hash-time-locked contract (HTLC)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Htlc {
@@ -274,6 +301,42 @@ pub struct Htlc {
     pub id: u64,
 }
 
+/// A funding transaction proposed by a splice that has not confirmed yet
+///
+/// Until one candidate confirms, the channel keeps a commitment transaction
+/// for the current funding output and for every candidate, so whichever
+/// transaction makes it into a block is already covered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingCandidate {
+    /// Outpoint of the new funding output
+    pub outpoint: OutPoint,
+    
+    /// Splice transaction spending the current funding output
+    pub funding_tx: Transaction,
+    
+    /// Channel capacity once this candidate confirms
+    pub capacity_novas: u64,
+    
+    /// Change to our balance, including the splice fee
+    pub local_delta_novas: i64,
+    
+    /// Change to their balance
+    pub remote_delta_novas: i64,
+    
+    /// Commitment transaction spending this candidate
+    pub commitment_tx: Option<Transaction>,
+}
+
+impl FundingCandidate {
+    fn local_balance(&self, current: u64) -> u64 {
+        (current as i64 + self.local_delta_novas).max(0) as u64
+    }
+    
+    fn remote_balance(&self, current: u64) -> u64 {
+        (current as i64 + self.remote_delta_novas).max(0) as u64
+    }
+}
+
 /// Public information about a channel
 #[derive(Debug, Clone)]
 pub struct ChannelInfo {
@@ -382,6 +445,14 @@ pub struct Channel {
     
     /// Last update timestamp
     pub last_update: u64,
+    
+    /// Unconfirmed splice transactions that may replace the funding output
+    #[serde(default)]
+    pub funding_candidates: Vec<FundingCandidate>,
+    
+    /// Layout of commitment transactions
+    #[serde(default)]
+    pub commitment_format: CommitmentFormat,
 }
 
 impl Channel {
@@ -424,6 +495,8 @@ impl Channel {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs(),
+            funding_candidates: Vec::new(),
+            commitment_format: CommitmentFormat::Legacy,
         }
     }
     
@@ -513,9 +586,77 @@ impl Channel {
         // 3. Add outputs for any pending HTLCs
         // 4. Set proper sequence numbers for timelocks
         
-        let funding_outpoint = self.funding_outpoint.as_ref().unwrap();
+        let funding_outpoint = self.funding_outpoint.clone().unwrap();
         
-        let commitment_tx = Transaction::new(
+        let commitment_tx = self.build_commitment(
+            &funding_outpoint,
+            self.local_balance_novas,
+            self.remote_balance_novas,
+        );
+        
+        // While a splice is pending, every funding candidate needs a commitment
+        // for the same state, since any of them may end up confirmed
+        let local_balance = self.local_balance_novas;
+        let remote_balance = self.remote_balance_novas;
+        let candidate_commitments: Vec<Transaction> = self.funding_candidates.iter()
+            .map(|candidate| self.build_commitment(
+                &candidate.outpoint,
+                candidate.local_balance(local_balance),
+                candidate.remote_balance(remote_balance),
+            ))
+            .collect();
+        for (candidate, tx) in self.funding_candidates.iter_mut().zip(candidate_commitments) {
+            candidate.commitment_tx = Some(tx);
+        }
+        
+        // Store the commitment transaction
+        self.commitment_tx = Some(commitment_tx.clone());
+        
+        // Update commitment number
+        self.commitment_number += 1;
+        
+        Ok(commitment_tx)
+    }
+    
+    /// Build a commitment transaction spending the given funding output
+    fn build_commitment(&self, funding_outpoint: &OutPoint, local_balance: u64, remote_balance: u64) -> Transaction {
+        self.build_holder_commitment(funding_outpoint, true, local_balance, remote_balance)
+    }
+    
+    /// Commitment the counterparty holds for the current balances
+    ///
+    /// This is the transaction the counterparty could broadcast, so it is the
+    /// one a watchtower must be able to punish once it is revoked.
+    pub fn remote_commitment_transaction(&self) -> ChannelResult<Transaction> {
+        let funding_outpoint = self.funding_outpoint.clone()
+            .ok_or_else(|| ChannelError::FundingError("No funding outpoint".to_string()))?;
+        
+        Ok(self.build_holder_commitment(
+            &funding_outpoint,
+            false,
+            self.remote_balance_novas,
+            self.local_balance_novas,
+        ))
+    }
+    
+    /// Build the commitment held by us (`ours`) or by the counterparty
+    ///
+    /// Balances are given from the holder's point of view: the holder's
+    /// output comes first.
+    fn build_holder_commitment(
+        &self,
+        funding_outpoint: &OutPoint,
+        ours: bool,
+        holder_balance: u64,
+        counterparty_balance: u64,
+    ) -> Transaction {
+        if self.commitment_format == CommitmentFormat::AnchorOutputs {
+            return self.build_anchor_commitment(funding_outpoint, ours, holder_balance, counterparty_balance);
+        }
+        let (holder_pubkey, counterparty_pubkey) = self.holder_pubkeys(ours);
+        
+        // In a real implementation, additional outputs would be added for each HTLC
+        Transaction::new(
             2, // version
             vec![
                 TxIn::new(
@@ -526,29 +667,30 @@ impl Channel {
                 )
             ],
             vec![
-                // Output to local with their balance
+                // Output to the holder with their balance
                 TxOut::new(
-                    self.local_balance_novas,
-                    Script::new_p2wpkh(&self.local_node_id.serialize()).0,
+                    holder_balance,
+                    Script::new_p2wpkh(&holder_pubkey).0,
                 ),
-                // Output to remote with their balance
+                // Output to the counterparty with their balance
                 TxOut::new(
-                    self.remote_balance_novas,
-                    Script::new_p2wpkh(&self.remote_node_id.serialize()).0,
+                    counterparty_balance,
+                    Script::new_p2wpkh(&counterparty_pubkey).0,
                 ),
             ],
             0, // lock_time
-        );
-        
-        // In a real implementation, additional outputs would be added for each HTLC
-        
-        // Store the commitment transaction
-        self.commitment_tx = Some(commitment_tx.clone());
-        
-        // Update commitment number
-        self.commitment_number += 1;
-        
-        Ok(commitment_tx)
+        )
+    }
+    
+    /// Serialized keys of a commitment's holder and counterparty
+    fn holder_pubkeys(&self, ours: bool) -> (Vec<u8>, Vec<u8>) {
+        let local_pubkey = self.local_node_id.serialize().to_vec();
+        let remote_pubkey = self.remote_node_id.serialize().to_vec();
+        if ours {
+            (local_pubkey, remote_pubkey)
+        } else {
+            (remote_pubkey, local_pubkey)
+        }
     }
     
     /// Add an HTLC to the channel
@@ -565,16 +707,19 @@ impl Channel {
             ));
         }
         
-        // Check if we have enough balance
-        if is_outgoing && self.local_balance_novas < amount_novas {
+        // Check if we have enough balance, on every funding candidate while
+        // a splice is pending
+        let local_available = self.spendable_local_novas();
+        if is_outgoing && local_available < amount_novas {
             return Err(ChannelError::InsufficientFunds(
-                format!("Insufficient local balance: {} < {}", self.local_balance_novas, amount_novas)
+                format!("Insufficient local balance: {} < {}", local_available, amount_novas)
             ));
         }
         
-        if !is_outgoing && self.remote_balance_novas < amount_novas {
+        let remote_available = self.spendable_remote_novas();
+        if !is_outgoing && remote_available < amount_novas {
             return Err(ChannelError::InsufficientFunds(
-                format!("Insufficient remote balance: {} < {}", self.remote_balance_novas, amount_novas)
+                format!("Insufficient remote balance: {} < {}", remote_available, amount_novas)
             ));
         }
         
@@ -709,6 +854,12 @@ impl Channel {
             ));
         }
         
+        if self.is_splicing() {
+            return Err(ChannelError::InvalidState(
+                "Cannot close channel while a splice is pending".to_string()
+            ));
+        }
+        
         // Create closing transaction
         let closing_tx = self.create_closing_transaction()?;
         
@@ -782,6 +933,139 @@ impl Channel {
         Ok(())
     }
     
+    /// Build an anchor-output commitment paying the minimum relay fee
+    ///
+    /// The funder pays for both anchors and the fee.
+    fn build_anchor_commitment(
+        &self,
+        funding_outpoint: &OutPoint,
+        ours: bool,
+        local_balance: u64,
+        remote_balance: u64,
+    ) -> Transaction {
+        // "Local" is the holder of the commitment, which may be the counterparty
+        let (local_pubkey, remote_pubkey) = self.holder_pubkeys(ours);
+        let holder_is_initiator = self.is_initiator == ours;
+        let p2sh = |redeem_script: Vec<u8>| ScriptBuilder::pay_to_script_hash(&ScriptBuilder::hash_pubkey(&redeem_script));
+        
+        let build = |fee: u64| {
+            let funder_cost = 2 * ANCHOR_OUTPUT_NOVAS + fee;
+            let (local_value, remote_value) = if holder_is_initiator {
+                (local_balance.saturating_sub(funder_cost), remote_balance)
+            } else {
+                (local_balance, remote_balance.saturating_sub(funder_cost))
+            };
+            
+            let mut outputs = vec![
+                TxOut::new(local_value, p2sh(anchors::delayed_redeem_script(&local_pubkey, self.to_self_delay))),
+                TxOut::new(remote_value, p2sh(anchors::to_remote_redeem_script(&remote_pubkey))),
+            ];
+            outputs.extend(self.pending_htlcs.iter().map(|htlc| {
+                let (receiver, offerer) = if htlc.is_outgoing == ours {
+                    (&remote_pubkey, &local_pubkey)
+                } else {
+                    (&local_pubkey, &remote_pubkey)
+                };
+                TxOut::new(
+                    htlc.amount_novas,
+                    p2sh(anchors::htlc_redeem_script(&htlc.payment_hash, receiver, offerer, htlc.expiry_height)),
+                )
+            }));
+            outputs.push(TxOut::new(ANCHOR_OUTPUT_NOVAS, anchors::anchor_script_pubkey(&local_pubkey)));
+            outputs.push(TxOut::new(ANCHOR_OUTPUT_NOVAS, anchors::anchor_script_pubkey(&remote_pubkey)));
+            
+            Transaction::new(
+                2,
+                vec![TxIn::new(funding_outpoint.txid, funding_outpoint.vout, Vec::new(), 0xffffffff)],
+                outputs,
+                0,
+            )
+        };
+        
+        let fee = build(0).calculate_size() as u64 * MIN_RELAY_FEERATE;
+        build(fee)
+    }
+    
+    /// Fee paid by a commitment spending the current funding output
+    pub fn commitment_fee(&self, commitment: &Transaction) -> u64 {
+        let outputs: u64 = commitment.outputs().iter().map(|output| output.amount()).sum();
+        self.capacity_novas.saturating_sub(outputs)
+    }
+    
+    /// Second-stage transactions for the HTLC outputs of an anchor commitment
+    ///
+    /// Each transaction pays the full HTLC amount to our delayed output; fees
+    /// are attached when broadcasting.
+    pub fn htlc_transactions(&self, commitment: &Transaction) -> ChannelResult<Vec<HtlcTransaction>> {
+        if self.commitment_format != CommitmentFormat::AnchorOutputs {
+            return Err(ChannelError::CommitmentError(
+                "HTLC transactions require anchor outputs".to_string()
+            ));
+        }
+        
+        let txid = commitment.hash();
+        let delayed_script = ScriptBuilder::pay_to_script_hash(&ScriptBuilder::hash_pubkey(
+            &anchors::delayed_redeem_script(&self.local_node_id.serialize(), self.to_self_delay),
+        ));
+        
+        // HTLC outputs follow the two balance outputs, in HTLC order
+        self.pending_htlcs.iter().enumerate().map(|(index, htlc)| {
+            let vout = 2 + index;
+            let output = commitment.outputs().get(vout)
+                .filter(|output| output.amount() == htlc.amount_novas)
+                .ok_or_else(|| ChannelError::CommitmentError(
+                    format!("Commitment has no output for HTLC {}", htlc.id)
+                ))?;
+            
+            let (kind, lock_time) = if htlc.is_outgoing {
+                (HtlcTxKind::Timeout, htlc.expiry_height)
+            } else {
+                (HtlcTxKind::Success, 0)
+            };
+            
+            let tx = Transaction::new(
+                2,
+                vec![TxIn::new(txid, vout as u32, Vec::new(), 1)],
+                vec![TxOut::new(output.amount(), delayed_script.clone())],
+                lock_time,
+            );
+            
+            Ok(HtlcTransaction { htlc_id: htlc.id, kind, tx })
+        }).collect()
+    }
+    
+    /// Force close and bump the commitment fee through our anchor
+    ///
+    /// Returns the commitment and the child transaction paying for it. Our
+    /// anchor must pay to the wallet's on-chain key.
+    pub fn force_close_with_fee_bump(
+        &mut self,
+        wallet: &mut LightningWallet,
+        target_feerate: u64,
+    ) -> ChannelResult<(Transaction, FeeBump)> {
+        if self.commitment_format != CommitmentFormat::AnchorOutputs {
+            return Err(ChannelError::CommitmentError(
+                "Fee bumping requires anchor outputs".to_string()
+            ));
+        }
+        
+        if self.state == ChannelState::Closed || self.state == ChannelState::ForceClosed {
+            return Err(ChannelError::InvalidState(
+                "Channel is already closed".to_string()
+            ));
+        }
+        
+        let commitment = self.commitment_tx.clone()
+            .ok_or_else(|| ChannelError::InvalidState(
+                "No commitment transaction available for force close".to_string()
+            ))?;
+        let bump = anchors::bump_commitment_fee(&commitment, self.commitment_fee(&commitment), wallet, target_feerate)
+            .map_err(|e| ChannelError::CommitmentError(e.to_string()))?;
+        
+        let commitment = self.force_close()?;
+        Ok((commitment, bump))
+    }
+    
     /// Force close the channel
     pub fn force_close(&mut self) -> ChannelResult<Transaction> {
         if self.state == ChannelState::Closed || self.state == ChannelState::ForceClosed {
@@ -829,6 +1113,9 @@ impl Channel {
         channel.channel_reserve_novas = config.channel_reserve_novas;
         channel.min_htlc_value_novas = config.min_htlc_value_msat / 1000; // Convert from msat to novas
         channel.max_accepted_htlcs = config.max_accepted_htlcs;
+        if config.anchor_outputs {
+            channel.commitment_format = CommitmentFormat::AnchorOutputs;
+        }
         
         // If there's a push amount, adjust balances
         if push_amount > 0 {
@@ -925,6 +1212,177 @@ impl Channel {
             update_count: self.commitment_number,
         }
     }
+    
+    /// Whether a splice transaction is waiting for confirmation
+    pub fn is_splicing(&self) -> bool {
+        !self.funding_candidates.is_empty()
+    }
+    
+    /// Our balance available for new HTLCs across all funding candidates
+    pub fn spendable_local_novas(&self) -> u64 {
+        self.funding_candidates.iter()
+            .map(|c| c.local_balance(self.local_balance_novas))
+            .fold(self.local_balance_novas, u64::min)
+    }
+    
+    /// Their balance available for new HTLCs across all funding candidates
+    pub fn spendable_remote_novas(&self) -> u64 {
+        self.funding_candidates.iter()
+            .map(|c| c.remote_balance(self.remote_balance_novas))
+            .fold(self.remote_balance_novas, u64::min)
+    }
+    
+    /// Create a splice transaction resizing the channel
+    ///
+    /// The splice transaction spends the current funding output together with
+    /// `splice_inputs` and creates a new funding output of the resized
+    /// capacity, followed by `splice_outputs` for any funds spliced out.
+    /// Positive contributions are funded by the splice inputs, negative ones
+    /// must be paid out exactly by the splice outputs. We pay the fee from
+    /// our side of the channel. The channel stays active: HTLCs can be added
+    /// and settled while the splice confirms.
+    pub fn splice(
+        &mut self,
+        local_contribution_novas: i64,
+        remote_contribution_novas: i64,
+        splice_inputs: Vec<TxIn>,
+        splice_outputs: Vec<TxOut>,
+        fee_novas: u64,
+    ) -> ChannelResult<Transaction> {
+        if self.state != ChannelState::Active {
+            return Err(ChannelError::InvalidState(
+                "Channel must be active to splice".to_string()
+            ));
+        }
+        
+        if self.is_splicing() {
+            return Err(ChannelError::SpliceError(
+                "A splice is already pending".to_string()
+            ));
+        }
+        
+        let funding_outpoint = self.funding_outpoint.clone()
+            .ok_or_else(|| ChannelError::FundingError("No funding outpoint".to_string()))?;
+        
+        let spliced_out: u64 = [local_contribution_novas, remote_contribution_novas].iter()
+            .filter(|c| **c < 0)
+            .map(|c| c.unsigned_abs())
+            .sum();
+        let paid_out: u64 = splice_outputs.iter().map(|o| o.amount()).sum();
+        if spliced_out != paid_out {
+            return Err(ChannelError::SpliceError(
+                format!("Splice outputs pay {} novas but {} novas are spliced out", paid_out, spliced_out)
+            ));
+        }
+        
+        let spliced_in = local_contribution_novas.max(0) + remote_contribution_novas.max(0);
+        if spliced_in > 0 && splice_inputs.is_empty() {
+            return Err(ChannelError::SpliceError(
+                "Splice-in requires at least one input".to_string()
+            ));
+        }
+        
+        let local_delta = local_contribution_novas - fee_novas as i64;
+        let remote_delta = remote_contribution_novas;
+        
+        // Balances already exclude in-flight HTLCs, so this keeps them covered
+        let new_local = self.local_balance_novas as i64 + local_delta;
+        let new_remote = self.remote_balance_novas as i64 + remote_delta;
+        if new_local < 0 || new_remote < 0 {
+            return Err(ChannelError::InsufficientFunds(
+                format!("Splice leaves negative balance: local {}, remote {}", new_local, new_remote)
+            ));
+        }
+        
+        let in_flight: u64 = self.pending_htlcs.iter().map(|h| h.amount_novas).sum();
+        let new_capacity = new_local as u64 + new_remote as u64 + in_flight;
+        if new_capacity < self.channel_reserve_novas * 2 {
+            return Err(ChannelError::InsufficientFunds(
+                format!("Spliced capacity {} is below the channel reserve", new_capacity)
+            ));
+        }
+        
+        let mut inputs = vec![TxIn::new(
+            funding_outpoint.txid,
+            funding_outpoint.vout,
+            Vec::new(), // Filled with both funding signatures
+            0xfffffffd, // Signal replaceability
+        )];
+        inputs.extend(splice_inputs);
+        
+        let mut outputs = vec![TxOut::new(
+            new_capacity,
+            Script::new_p2wsh(&[0x52, 0x21, 0x21, 0x52, 0xae]).0,
+        )];
+        outputs.extend(splice_outputs);
+        
+        let splice_tx = Transaction::new(2, inputs, outputs, 0);
+        
+        let candidate = FundingCandidate {
+            outpoint: OutPoint { txid: splice_tx.hash(), vout: 0 },
+            funding_tx: splice_tx.clone(),
+            capacity_novas: new_capacity,
+            local_delta_novas: local_delta,
+            remote_delta_novas: remote_delta,
+            commitment_tx: None,
+        };
+        self.funding_candidates.push(candidate);
+        
+        // Sign commitments for both the old and the new funding output
+        self.create_commitment_transaction()?;
+        
+        info!(
+            "Spliced channel {} from {} to {} novas",
+            hex::encode(&self.channel_id), self.capacity_novas, new_capacity
+        );
+        
+        Ok(splice_tx)
+    }
+    
+    /// Handle confirmation of a splice transaction
+    ///
+    /// The confirmed candidate becomes the funding output and every other
+    /// candidate is discarded, since they all spend the same funding output.
+    pub fn splice_locked(&mut self, splice_txid: [u8; 32]) -> ChannelResult<()> {
+        let index = self.funding_candidates.iter()
+            .position(|c| c.outpoint.txid == splice_txid)
+            .ok_or_else(|| ChannelError::SpliceError(
+                format!("Unknown splice transaction {}", hex::encode(splice_txid))
+            ))?;
+        
+        let candidate = self.funding_candidates.swap_remove(index);
+        self.funding_candidates.clear();
+        
+        self.local_balance_novas = candidate.local_balance(self.local_balance_novas);
+        self.remote_balance_novas = candidate.remote_balance(self.remote_balance_novas);
+        self.capacity_novas = candidate.capacity_novas;
+        self.funding_outpoint = Some(candidate.outpoint);
+        if candidate.commitment_tx.is_some() {
+            self.commitment_tx = candidate.commitment_tx;
+        }
+        
+        info!(
+            "Splice {} locked for channel {}, capacity now {} novas",
+            hex::encode(splice_txid), hex::encode(&self.channel_id), self.capacity_novas
+        );
+        
+        Ok(())
+    }
+    
+    /// Abandon a pending splice, keeping the current funding output
+    ///
+    /// Used when the splice transaction can no longer confirm, for example
+    /// because one of its inputs was double spent.
+    pub fn abort_splice(&mut self) -> ChannelResult<()> {
+        if !self.is_splicing() {
+            return Err(ChannelError::SpliceError("No splice is pending".to_string()));
+        }
+        
+        self.funding_candidates.clear();
+        warn!("Aborted splice for channel {}", hex::encode(&self.channel_id));
+        
+        Ok(())
+    }
 }
 
 /// Manager for Lightning Network channels
@@ -1082,4 +1540,122 @@ impl<'de> Deserialize<'de> for PrivateKey {
         array.copy_from_slice(&bytes);
         Ok(PrivateKey(array))
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightning::wire::{MessageFactory, MessageType, SpliceAckPayload, SpliceInitPayload};
+
+    fn active_channel(capacity: u64) -> Channel {
+        let mut channel = Channel::new(PublicKey([1u8; 33]), PublicKey([2u8; 33]), capacity, true, false);
+        channel.create_funding_transaction(vec![TxIn::new([7u8; 32], 0, Vec::new(), 0xffffffff)], None, 1)
+            .unwrap();
+        channel.state = ChannelState::Active;
+        channel
+    }
+
+    fn payment_hash(preimage: &[u8; 32]) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&Sha256::digest(preimage));
+        hash
+    }
+
+    #[test]
+    fn test_splice_in_with_htlcs_in_flight() {
+        let mut channel = active_channel(1_000_000);
+        let old_funding = channel.funding_outpoint.clone().unwrap();
+
+        let preimage = [3u8; 32];
+        let first = channel.add_htlc(payment_hash(&preimage), 100_000, 500, true).unwrap();
+
+        let splice_tx = channel.splice(
+            500_000,
+            0,
+            vec![TxIn::new([9u8; 32], 1, Vec::new(), 0xffffffff)],
+            Vec::new(),
+            1_000,
+        ).unwrap();
+
+        // The splice spends the old funding output and pays the new capacity
+        assert_eq!(splice_tx.inputs()[0].prev_tx_hash(), old_funding.txid);
+        assert_eq!(splice_tx.inputs()[0].prev_output_index(), old_funding.vout);
+        assert_eq!(splice_tx.outputs()[0].amount(), 1_499_000);
+        assert!(channel.is_splicing());
+        assert_eq!(channel.capacity_novas, 1_000_000);
+
+        // Both funding outputs have a commitment for the current state
+        let candidate = &channel.funding_candidates[0];
+        let candidate_commitment = candidate.commitment_tx.as_ref().unwrap();
+        assert_eq!(candidate_commitment.inputs()[0].prev_tx_hash(), splice_tx.hash());
+        assert_eq!(channel.commitment_tx.as_ref().unwrap().inputs()[0].prev_tx_hash(), old_funding.txid);
+
+        // HTLCs keep flowing while the splice confirms
+        let second = channel.add_htlc([4u8; 32], 50_000, 500, true).unwrap();
+        channel.settle_htlc(first, preimage).unwrap();
+        channel.fail_htlc(second, "test").unwrap();
+        channel.create_commitment_transaction().unwrap();
+        let commitment = channel.funding_candidates[0].commitment_tx.as_ref().unwrap();
+        assert_eq!(commitment.outputs()[0].amount(), 900_000 + 499_000);
+        assert_eq!(commitment.outputs()[1].amount(), 100_000);
+
+        channel.splice_locked(splice_tx.hash()).unwrap();
+        assert!(!channel.is_splicing());
+        assert_eq!(channel.capacity_novas, 1_499_000);
+        assert_eq!(channel.local_balance_novas, 1_399_000);
+        assert_eq!(channel.remote_balance_novas, 100_000);
+        assert_eq!(channel.funding_outpoint.as_ref().unwrap().txid, splice_tx.hash());
+        assert_eq!(channel.commitment_tx.as_ref().unwrap().inputs()[0].prev_tx_hash(), splice_tx.hash());
+    }
+
+    #[test]
+    fn test_splice_out_reserves_balance_until_locked() {
+        let mut channel = active_channel(1_000_000);
+        let payout = TxOut::new(300_000, Script::new_p2wpkh(&[0u8; 20]).0);
+
+        // Spliced-out amount has to match the splice outputs
+        assert!(channel.splice(-300_000, 0, Vec::new(), Vec::new(), 500).is_err());
+
+        let splice_tx = channel.splice(-300_000, 0, Vec::new(), vec![payout.clone()], 500).unwrap();
+        assert_eq!(splice_tx.outputs()[0].amount(), 699_500);
+        assert_eq!(splice_tx.outputs()[1].amount(), 300_000);
+
+        // Only one splice at a time, and no cooperative close meanwhile
+        assert!(matches!(
+            channel.splice(-1_000, 0, Vec::new(), Vec::new(), 0),
+            Err(ChannelError::SpliceError(_))
+        ));
+        assert!(channel.initiate_close().is_err());
+
+        // New HTLCs must fit the smaller candidate
+        assert_eq!(channel.spendable_local_novas(), 699_500);
+        assert!(channel.add_htlc([5u8; 32], 800_000, 500, true).is_err());
+        assert!(channel.add_htlc([5u8; 32], 600_000, 500, true).is_ok());
+
+        // Dropping the splice falls back to the original funding output
+        channel.abort_splice().unwrap();
+        assert_eq!(channel.spendable_local_novas(), 400_000);
+        assert_eq!(channel.capacity_novas, 1_000_000);
+        assert!(channel.splice_locked(splice_tx.hash()).is_err());
+    }
+
+    #[test]
+    fn test_splice_negotiation_messages() {
+        let alice = MessageFactory::new("alice".to_string(), vec![1; 32]);
+        let bob = MessageFactory::new("bob".to_string(), vec![2; 32]);
+        let channel_id = ChannelId::new_random();
+
+        let init = alice.create_splice_init(channel_id.clone(), 250_000, 253, 0).unwrap();
+        let init = crate::lightning::wire::Message::deserialize(&init.serialize().unwrap()).unwrap();
+        assert_eq!(init.msg_type, MessageType::SpliceInit);
+        let init_payload: SpliceInitPayload = init.decode_payload().unwrap();
+        assert_eq!(init_payload.funding_contribution_novas, 250_000);
+
+        let ack = bob.create_splice_ack(&init, -10_000).unwrap();
+        let ack_payload: SpliceAckPayload = ack.decode_payload().unwrap();
+        assert_eq!(ack_payload.channel_id, channel_id);
+        assert_eq!(ack_payload.funding_contribution_novas, -10_000);
+
+        // Acks only answer splice proposals
+        assert!(bob.create_splice_ack(&ack, 0).is_err());
+    }
+}
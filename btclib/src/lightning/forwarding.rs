@@ -0,0 +1,327 @@
+//! Forwarding Ledger
+//!
+//! Records every HTLC we forward for other nodes, whether it settled or
+//! failed, in a database tree so routing income survives restarts. Events
+//! are keyed by timestamp, which makes time-range queries and the
+//! per-channel and per-peer earnings reports cheap scans.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Forwarding ledger errors
+#[derive(Debug, Error)]
+pub enum LedgerError {
+    #[error("Database error: {0}")]
+    Database(#[from] sled::Error),
+    
+    #[error("Codec error: {0}")]
+    Codec(#[from] bincode::Error),
+    
+    #[error("Invalid time range: {from} > {to}")]
+    InvalidRange { from: u64, to: u64 },
+}
+
+/// Outcome of a forwarded HTLC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ForwardStatus {
+    /// The preimage came back and both HTLCs were settled
+    Settled,
+    
+    /// The HTLC was failed back to the previous hop
+    Failed,
+}
+
+/// A single forwarded HTLC
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardingEvent {
+    /// When the forward resolved, in seconds since the epoch
+    pub timestamp: u64,
+    
+    /// Channel the HTLC came in on
+    pub incoming_channel: String,
+    
+    /// Channel the HTLC went out on
+    pub outgoing_channel: String,
+    
+    /// Peer on the incoming channel
+    pub incoming_peer: String,
+    
+    /// Peer on the outgoing channel
+    pub outgoing_peer: String,
+    
+    /// Payment hash of the HTLC
+    pub payment_hash: String,
+    
+    /// Amount received from the previous hop in millinovas
+    pub amount_in_msat: u64,
+    
+    /// Amount sent to the next hop in millinovas
+    pub amount_out_msat: u64,
+    
+    /// Fee earned in millinovas; zero unless settled
+    pub fee_msat: u64,
+    
+    /// How the forward resolved
+    pub status: ForwardStatus,
+    
+    /// Why the forward failed
+    pub failure_reason: Option<String>,
+}
+
+/// Routing income of one of our channels
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelEarnings {
+    /// Channel ID
+    pub channel_id: String,
+    
+    /// Settled forwards that came in on this channel
+    pub forwards_in: u64,
+    
+    /// Settled forwards that went out on this channel
+    pub forwards_out: u64,
+    
+    /// Amount received on this channel in millinovas
+    pub volume_in_msat: u64,
+    
+    /// Amount sent on this channel in millinovas
+    pub volume_out_msat: u64,
+    
+    /// Fees earned by forwards going out on this channel in millinovas
+    pub fees_msat: u64,
+    
+    /// Failed forwards that were to go out on this channel
+    pub failures: u64,
+}
+
+/// Routing income attributed to a peer we forward to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerEarnings {
+    /// Peer node ID
+    pub peer: String,
+    
+    /// Settled forwards to this peer
+    pub forwards: u64,
+    
+    /// Amount sent to this peer in millinovas
+    pub volume_msat: u64,
+    
+    /// Fees earned in millinovas
+    pub fees_msat: u64,
+}
+
+/// Aggregated routing income over a time range
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardingReport {
+    /// Start of the range, inclusive
+    pub from: u64,
+    
+    /// End of the range, inclusive
+    pub to: u64,
+    
+    /// Settled forwards
+    pub settled: u64,
+    
+    /// Failed forwards
+    pub failed: u64,
+    
+    /// Amount forwarded in millinovas
+    pub total_volume_msat: u64,
+    
+    /// Fees earned in millinovas
+    pub total_fees_msat: u64,
+    
+    /// Earnings per channel, ordered by channel ID
+    pub channels: Vec<ChannelEarnings>,
+    
+    /// Earnings per outgoing peer, ordered by peer
+    pub peers: Vec<PeerEarnings>,
+}
+
+/// Persistent log of forwarded HTLCs
+pub struct ForwardingLedger {
+    tree: sled::Tree,
+    sequence: AtomicU64,
+}
+
+impl ForwardingLedger {
+    /// Use `tree` to store forwarding events
+    pub fn new(tree: sled::Tree) -> Result<Self, LedgerError> {
+        // Continue numbering after the highest stored sequence; events may
+        // have been recorded out of timestamp order, so scan them all
+        let mut next = 0;
+        for key in tree.iter().keys() {
+            let key = key?;
+            if key.len() == 16 {
+                let mut seq = [0u8; 8];
+                seq.copy_from_slice(&key[8..]);
+                next = next.max(u64::from_be_bytes(seq) + 1);
+            }
+        }
+        
+        Ok(Self {
+            tree,
+            sequence: AtomicU64::new(next),
+        })
+    }
+    
+    /// Store a forwarding event
+    pub fn record(&self, event: &ForwardingEvent) -> Result<(), LedgerError> {
+        let seq = self.sequence.fetch_add(1, Ordering::SeqCst);
+        let mut key = [0u8; 16];
+        key[..8].copy_from_slice(&event.timestamp.to_be_bytes());
+        key[8..].copy_from_slice(&seq.to_be_bytes());
+        
+        self.tree.insert(key, bincode::serialize(event)?)?;
+        Ok(())
+    }
+    
+    /// Events that resolved between `from` and `to` seconds, inclusive, oldest first
+    pub fn events(&self, from: u64, to: u64) -> Result<Vec<ForwardingEvent>, LedgerError> {
+        if from > to {
+            return Err(LedgerError::InvalidRange { from, to });
+        }
+        
+        let start = Self::bound(from, 0);
+        let end = Self::bound(to, u64::MAX);
+        
+        self.tree.range(start..=end)
+            .map(|entry| {
+                let (_, value) = entry?;
+                Ok(bincode::deserialize(&value)?)
+            })
+            .collect()
+    }
+    
+    /// Number of stored events
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+    
+    /// Whether no events are stored
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+    
+    /// Aggregate earnings between `from` and `to` seconds, inclusive
+    pub fn report(&self, from: u64, to: u64) -> Result<ForwardingReport, LedgerError> {
+        let mut report = ForwardingReport {
+            from,
+            to,
+            ..Default::default()
+        };
+        let mut channels: BTreeMap<String, ChannelEarnings> = BTreeMap::new();
+        let mut peers: BTreeMap<String, PeerEarnings> = BTreeMap::new();
+        
+        for event in self.events(from, to)? {
+            if event.status == ForwardStatus::Failed {
+                report.failed += 1;
+                channel_entry(&mut channels, &event.outgoing_channel).failures += 1;
+                continue;
+            }
+            
+            report.settled += 1;
+            report.total_volume_msat += event.amount_out_msat;
+            report.total_fees_msat += event.fee_msat;
+            
+            let incoming = channel_entry(&mut channels, &event.incoming_channel);
+            incoming.forwards_in += 1;
+            incoming.volume_in_msat += event.amount_in_msat;
+            
+            let outgoing = channel_entry(&mut channels, &event.outgoing_channel);
+            outgoing.forwards_out += 1;
+            outgoing.volume_out_msat += event.amount_out_msat;
+            outgoing.fees_msat += event.fee_msat;
+            
+            let peer = peers.entry(event.outgoing_peer.clone()).or_insert_with(|| PeerEarnings {
+                peer: event.outgoing_peer.clone(),
+                ..Default::default()
+            });
+            peer.forwards += 1;
+            peer.volume_msat += event.amount_out_msat;
+            peer.fees_msat += event.fee_msat;
+        }
+        
+        report.channels = channels.into_values().collect();
+        report.peers = peers.into_values().collect();
+        Ok(report)
+    }
+    
+    fn bound(timestamp: u64, seq: u64) -> [u8; 16] {
+        let mut key = [0u8; 16];
+        key[..8].copy_from_slice(&timestamp.to_be_bytes());
+        key[8..].copy_from_slice(&seq.to_be_bytes());
+        key
+    }
+}
+
+fn channel_entry<'a>(channels: &'a mut BTreeMap<String, ChannelEarnings>, channel_id: &str) -> &'a mut ChannelEarnings {
+    channels.entry(channel_id.to_string()).or_insert_with(|| ChannelEarnings {
+        channel_id: channel_id.to_string(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn event(timestamp: u64, incoming: &str, outgoing: &str, amount_out_msat: u64, fee_msat: u64, status: ForwardStatus) -> ForwardingEvent {
+        ForwardingEvent {
+            timestamp,
+            incoming_channel: incoming.to_string(),
+            outgoing_channel: outgoing.to_string(),
+            incoming_peer: format!("peer-{}", incoming),
+            outgoing_peer: format!("peer-{}", outgoing),
+            payment_hash: hex::encode([timestamp as u8; 32]),
+            amount_in_msat: amount_out_msat + fee_msat,
+            amount_out_msat,
+            fee_msat: if status == ForwardStatus::Settled { fee_msat } else { 0 },
+            status,
+            failure_reason: (status == ForwardStatus::Failed).then(|| "temporary channel failure".to_string()),
+        }
+    }
+    
+    #[test]
+    fn test_ledger_range_queries_and_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        let ledger = ForwardingLedger::new(db.open_tree("forwards").unwrap()).unwrap();
+        
+        ledger.record(&event(100, "a", "b", 1_000_000, 1_100, ForwardStatus::Settled)).unwrap();
+        ledger.record(&event(200, "a", "c", 2_000_000, 1_200, ForwardStatus::Settled)).unwrap();
+        ledger.record(&event(200, "c", "b", 500_000, 1_050, ForwardStatus::Settled)).unwrap();
+        ledger.record(&event(300, "a", "b", 700_000, 1_070, ForwardStatus::Failed)).unwrap();
+        ledger.record(&event(400, "b", "a", 900_000, 1_090, ForwardStatus::Settled)).unwrap();
+        
+        // Bounds are inclusive and events with equal timestamps are all kept
+        assert_eq!(ledger.events(200, 300).unwrap().len(), 3);
+        assert_eq!(ledger.events(0, u64::MAX).unwrap().len(), 5);
+        assert!(ledger.events(401, 500).unwrap().is_empty());
+        assert!(ledger.events(2, 1).is_err());
+        
+        let report = ledger.report(100, 300).unwrap();
+        assert_eq!(report.settled, 3);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.total_volume_msat, 3_500_000);
+        assert_eq!(report.total_fees_msat, 3_350);
+        
+        let b = report.channels.iter().find(|c| c.channel_id == "b").unwrap();
+        assert_eq!((b.forwards_out, b.fees_msat, b.failures), (2, 2_150, 1));
+        let a = report.channels.iter().find(|c| c.channel_id == "a").unwrap();
+        assert_eq!((a.forwards_in, a.volume_in_msat, a.fees_msat), (2, 3_002_300, 0));
+        
+        let peer_b = report.peers.iter().find(|p| p.peer == "peer-b").unwrap();
+        assert_eq!((peer_b.forwards, peer_b.fees_msat), (2, 2_150));
+        
+        // Events survive reopening and new ones do not overwrite them
+        drop(ledger);
+        let ledger = ForwardingLedger::new(db.open_tree("forwards").unwrap()).unwrap();
+        ledger.record(&event(400, "b", "c", 100_000, 1_010, ForwardStatus::Settled)).unwrap();
+        assert_eq!(ledger.len(), 6);
+        assert_eq!(ledger.report(400, 400).unwrap().total_fees_msat, 2_100);
+    }
+}
@@ -133,6 +133,68 @@ pub struct GreenRoutingParameters {
     pub carbon_negative_bonus: f64,
 }
 
+impl Default for GreenRoutingParameters {
+    fn default() -> Self {
+        Self {
+            fee_weight: 0.4,
+            carbon_weight: 0.3,
+            renewable_weight: 0.2,
+            reliability_weight: 0.1,
+            max_carbon_per_route: 0.01, // 10g CO2e max
+            min_renewable_percentage: 50.0,
+            max_route_length: 6,
+            green_node_preference: 1.2,
+            carbon_negative_bonus: 1.5,
+        }
+    }
+}
+
+impl GreenRoutingParameters {
+    /// Carbon intensity at which the carbon weight applies in full, in gCO2e/kWh
+    pub const REFERENCE_CARBON_INTENSITY: f64 = 500.0;
+    
+    /// Penalty per hop at full weight in millinovas
+    pub const BASE_HOP_PENALTY_MSAT: u64 = 1_000;
+    
+    /// Penalty per hop at full weight in parts per million of the amount
+    pub const HOP_PENALTY_AMOUNT_PPM: u64 = 500;
+    
+    /// Carbon penalty of a hop into a node, in millinovas
+    ///
+    /// The carbon and renewable components are weighed against the fee
+    /// weight, so the penalty is comparable with the fees path finding
+    /// minimizes. Nodes that advertise nothing are assumed to run at the
+    /// reference intensity without renewables; certified and carbon
+    /// negative nodes get the same discounts as in green route scoring.
+    pub fn hop_penalty_msat(
+        &self,
+        amount_msat: u64,
+        carbon_intensity: Option<f64>,
+        renewable_percentage: Option<f64>,
+        green_certified: bool,
+    ) -> u64 {
+        let intensity = carbon_intensity.unwrap_or(Self::REFERENCE_CARBON_INTENSITY);
+        let renewable = renewable_percentage.unwrap_or(0.0).clamp(0.0, 100.0);
+        
+        let weight = (self.carbon_weight * intensity.max(0.0) / Self::REFERENCE_CARBON_INTENSITY
+            + self.renewable_weight * (100.0 - renewable) / 100.0)
+            / self.fee_weight.max(f64::EPSILON);
+        
+        let base = Self::BASE_HOP_PENALTY_MSAT + amount_msat * Self::HOP_PENALTY_AMOUNT_PPM / 1_000_000;
+        let mut penalty = base as f64 * weight.max(0.0);
+        
+        if green_certified {
+            penalty /= self.green_node_preference;
+        }
+        
+        if intensity < 0.0 {
+            penalty /= self.carbon_negative_bonus;
+        }
+        
+        penalty.round() as u64
+    }
+}
+
 /// Route cache key
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 struct RouteCacheKey {
@@ -166,17 +228,7 @@ type ChannelId = [u8; 32];
 impl GreenLightningRouter {
     /// Create new green Lightning router
     pub fn new(carbon_tracker: Arc<CarbonTracker>) -> Self {
-        let default_params = GreenRoutingParameters {
-            fee_weight: 0.4,
-            carbon_weight: 0.3,
-            renewable_weight: 0.2,
-            reliability_weight: 0.1,
-            max_carbon_per_route: 0.01, // 10g CO2e max
-            min_renewable_percentage: 50.0,
-            max_route_length: 6,
-            green_node_preference: 1.2,
-            carbon_negative_bonus: 1.5,
-        };
+        let default_params = GreenRoutingParameters::default();
         
         Self {
             network_graph: Arc::new(RwLock::new(EnvironmentalNetworkGraph {
@@ -191,6 +243,26 @@ impl GreenLightningRouter {
         }
     }
     
+    /// Add or replace a node in the environmental graph
+    pub fn add_node(&self, node: EnvironmentalNode) {
+        self.network_graph.write().unwrap().nodes.insert(node.node_id, node);
+    }
+    
+    /// Add or replace a channel in the environmental graph
+    pub fn add_channel(&self, channel: EnvironmentalChannel) {
+        self.network_graph.write().unwrap().channels.insert(channel.channel_id, channel);
+    }
+    
+    /// Find the best green route without consulting or filling the route cache
+    pub fn find_green_route(
+        &self,
+        source: NodeId,
+        destination: NodeId,
+        amount_sats: u64,
+    ) -> Result<GreenLightningRoute, RoutingError> {
+        self.find_optimal_green_route(source, destination, amount_sats)
+    }
+    
     /// Calculate route carbon footprint
     pub fn calculate_route_carbon_footprint(
         &self,
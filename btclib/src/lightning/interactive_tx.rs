@@ -0,0 +1,1094 @@
+// supernova Lightning Network - Interactive Transaction Construction
+//
+// This file implements dual-funded channel opening. Both peers contribute
+// inputs and outputs to the funding transaction by taking turns sending
+// tx_add_*/tx_remove_* messages until each side has sent tx_complete, then
+// exchange quantum-signed witnesses for their own inputs. The initiator may
+// replace an unconfirmed funding transaction at a higher fee rate; every
+// signed version is kept as a candidate until one of them confirms.
+
+use crate::crypto::quantum::{verify_quantum_signature, QuantumKeyPair, QuantumParameters};
+use crate::lightning::channel::ChannelId;
+use crate::lightning::wire::{
+    AcceptChannel2Payload, ErrorPayload, LightningError, Message, MessageFactory, MessageType,
+    OpenChannel2Payload, QuantumWitness, TxAckRbfPayload, TxAddInputPayload, TxAddOutputPayload,
+    TxInitRbfPayload, TxRemovePayload, TxSignaturesPayload,
+};
+use crate::types::transaction::{
+    OutPoint, Transaction, TransactionInput as TxIn, TransactionOutput as TxOut,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use thiserror::Error;
+use tracing::{debug, info};
+
+/// Maximum inputs or outputs each side may add
+pub const MAX_CONTRIBUTIONS_PER_PEER: usize = 252;
+
+/// Outputs below this value are not worth creating
+pub const DUST_LIMIT_NOVAS: u64 = 546;
+
+/// Weight of version, locktime and input/output counts
+const TX_COMMON_WEIGHT: u64 = 44;
+
+/// Weight of an input including its quantum witness
+const INPUT_WEIGHT: u64 = 164 + 3_800;
+
+/// Weight of an output without its script
+const OUTPUT_BASE_WEIGHT: u64 = 36;
+
+/// Error types for interactive transaction construction
+#[derive(Debug, Error)]
+pub enum InteractiveTxError {
+    #[error("Protocol violation: {0}")]
+    ProtocolViolation(String),
+
+    #[error("Insufficient funds: {0}")]
+    InsufficientFunds(String),
+
+    #[error("Invalid witness: {0}")]
+    InvalidWitness(String),
+
+    #[error("Signing error: {0}")]
+    SigningError(String),
+
+    #[error("Peer aborted: {0}")]
+    Aborted(String),
+
+    #[error("Wire error: {0}")]
+    Wire(#[from] LightningError),
+}
+
+/// Result type for interactive transaction operations
+pub type InteractiveTxResult<T> = Result<T, InteractiveTxError>;
+
+/// A confirmed output spent by a funding contribution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingInput {
+    /// Output being spent
+    pub outpoint: OutPoint,
+
+    /// Value of the output in novas
+    pub value_novas: u64,
+}
+
+/// Inputs and change script a peer brings to a dual-funded channel
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FundingContribution {
+    /// Amount added to the channel in novas
+    pub funding_novas: u64,
+
+    /// Outputs funding the contribution and its share of fees
+    pub inputs: Vec<FundingInput>,
+
+    /// Script receiving any change
+    pub change_script: Vec<u8>,
+}
+
+impl FundingContribution {
+    fn total_input(&self) -> u64 {
+        self.inputs.iter().map(|i| i.value_novas).sum()
+    }
+}
+
+/// Fee for the given weight at a fee rate per kiloweight
+pub fn fee_for_weight(feerate_per_kw: u32, weight: u64) -> u64 {
+    weight * feerate_per_kw as u64 / 1000
+}
+
+fn output_weight(script: &[u8]) -> u64 {
+    OUTPUT_BASE_WEIGHT + 4 * script.len() as u64
+}
+
+/// 2-of-2 funding script over both peers' funding keys
+pub fn funding_script(initiator_pubkey: &[u8], acceptor_pubkey: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update([0x52]); // OP_2
+    hasher.update(initiator_pubkey);
+    hasher.update(acceptor_pubkey);
+    hasher.update([0x52, 0xae]); // OP_2 OP_CHECKMULTISIG
+
+    let mut script = vec![0x00, 0x20];
+    script.extend_from_slice(&hasher.finalize());
+    script
+}
+
+/// Digest signed by the witness of one funding input
+fn witness_digest(txid: &[u8; 32], serial_id: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"supernova/interactive-tx/witness");
+    hasher.update(txid);
+    hasher.update(serial_id.to_le_bytes());
+
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&hasher.finalize());
+    digest
+}
+
+#[derive(Debug, Clone)]
+struct TrackedInput {
+    input: FundingInput,
+    sequence: u32,
+    is_local: bool,
+}
+
+#[derive(Debug, Clone)]
+struct TrackedOutput {
+    output: TxOut,
+    is_local: bool,
+}
+
+#[derive(Debug, Clone)]
+enum LocalAction {
+    AddInput(u64, FundingInput),
+    AddOutput(u64, TxOut),
+}
+
+/// One round of interactive transaction construction
+///
+/// Serial IDs are even for the initiator and odd for the acceptor, and the
+/// final transaction orders inputs and outputs by serial ID so both peers
+/// build the same transaction. Construction ends after two consecutive
+/// tx_complete messages.
+#[derive(Debug, Clone)]
+pub struct InteractiveTxConstructor {
+    channel_id: ChannelId,
+    is_initiator: bool,
+    feerate_per_kw: u32,
+    locktime: u32,
+    funding_script: Vec<u8>,
+    channel_capacity_novas: u64,
+    remote_funding_novas: u64,
+    inputs: BTreeMap<u64, TrackedInput>,
+    outputs: BTreeMap<u64, TrackedOutput>,
+    queue: VecDeque<LocalAction>,
+    next_serial_id: u64,
+    remote_contributions: usize,
+    local_complete: bool,
+    remote_complete: bool,
+}
+
+impl InteractiveTxConstructor {
+    /// Start construction with our contribution queued
+    ///
+    /// Our change covers the fee for our own inputs and outputs; the
+    /// initiator also pays for the common fields and the funding output.
+    pub fn new(
+        channel_id: ChannelId,
+        is_initiator: bool,
+        feerate_per_kw: u32,
+        locktime: u32,
+        funding_script: Vec<u8>,
+        local: &FundingContribution,
+        remote_funding_novas: u64,
+    ) -> InteractiveTxResult<Self> {
+        if local.inputs.len() > MAX_CONTRIBUTIONS_PER_PEER {
+            return Err(InteractiveTxError::ProtocolViolation(
+                format!("Too many funding inputs: {}", local.inputs.len())
+            ));
+        }
+        if local.funding_novas > 0 && local.inputs.is_empty() {
+            return Err(InteractiveTxError::InsufficientFunds(
+                "Contribution has no inputs".to_string()
+            ));
+        }
+
+        let mut constructor = Self {
+            channel_id,
+            is_initiator,
+            feerate_per_kw,
+            locktime,
+            funding_script: funding_script.clone(),
+            channel_capacity_novas: local.funding_novas + remote_funding_novas,
+            remote_funding_novas,
+            inputs: BTreeMap::new(),
+            outputs: BTreeMap::new(),
+            queue: VecDeque::new(),
+            next_serial_id: if is_initiator { 0 } else { 1 },
+            remote_contributions: 0,
+            local_complete: false,
+            remote_complete: false,
+        };
+
+        let mut weight = local.inputs.len() as u64 * INPUT_WEIGHT;
+        if is_initiator {
+            weight += TX_COMMON_WEIGHT + output_weight(&funding_script);
+        }
+
+        let total_input = local.total_input();
+        let fee_without_change = fee_for_weight(feerate_per_kw, weight);
+        let fee_with_change = fee_for_weight(feerate_per_kw, weight + output_weight(&local.change_script));
+        let required = local.funding_novas + fee_without_change;
+        if total_input < required {
+            return Err(InteractiveTxError::InsufficientFunds(
+                format!("Inputs of {} novas cannot cover {} novas", total_input, required)
+            ));
+        }
+
+        for input in &local.inputs {
+            let serial_id = constructor.take_serial_id();
+            constructor.queue.push_back(LocalAction::AddInput(serial_id, input.clone()));
+        }
+
+        if is_initiator {
+            let serial_id = constructor.take_serial_id();
+            constructor.queue.push_back(LocalAction::AddOutput(
+                serial_id,
+                TxOut::new(constructor.channel_capacity_novas, funding_script),
+            ));
+        }
+
+        // Change below dust is left to the fee
+        let change = total_input.saturating_sub(local.funding_novas + fee_with_change);
+        if change >= DUST_LIMIT_NOVAS {
+            let serial_id = constructor.take_serial_id();
+            constructor.queue.push_back(LocalAction::AddOutput(
+                serial_id,
+                TxOut::new(change, local.change_script.clone()),
+            ));
+        }
+
+        Ok(constructor)
+    }
+
+    fn take_serial_id(&mut self) -> u64 {
+        let serial_id = self.next_serial_id;
+        self.next_serial_id += 2;
+        serial_id
+    }
+
+    /// Whether both sides have sent consecutive tx_complete messages
+    pub fn is_complete(&self) -> bool {
+        self.local_complete && self.remote_complete
+    }
+
+    /// Fee rate of this round
+    pub fn feerate_per_kw(&self) -> u32 {
+        self.feerate_per_kw
+    }
+
+    /// Produce our next turn: a queued addition or tx_complete
+    pub fn next_message(&mut self, factory: &MessageFactory) -> InteractiveTxResult<Message> {
+        let message = match self.queue.pop_front() {
+            Some(LocalAction::AddInput(serial_id, input)) => {
+                let sequence = 0xfffffffd; // Signal replaceability
+                let payload = TxAddInputPayload {
+                    channel_id: self.channel_id.clone(),
+                    serial_id,
+                    prev_txid: input.outpoint.txid,
+                    prev_vout: input.outpoint.vout,
+                    value_novas: input.value_novas,
+                    sequence,
+                };
+                self.inputs.insert(serial_id, TrackedInput { input, sequence, is_local: true });
+                self.local_complete = false;
+                factory.create_tx_add_input(&payload)?
+            }
+            Some(LocalAction::AddOutput(serial_id, output)) => {
+                let payload = TxAddOutputPayload {
+                    channel_id: self.channel_id.clone(),
+                    serial_id,
+                    amount_novas: output.amount(),
+                    script: output.script_pubkey().to_vec(),
+                };
+                self.outputs.insert(serial_id, TrackedOutput { output, is_local: true });
+                self.local_complete = false;
+                factory.create_tx_add_output(&payload)?
+            }
+            None => {
+                self.local_complete = true;
+                factory.create_tx_complete(self.channel_id.clone())?
+            }
+        };
+
+        Ok(message)
+    }
+
+    fn check_remote_serial_id(&self, serial_id: u64) -> InteractiveTxResult<()> {
+        let remote_parity = if self.is_initiator { 1 } else { 0 };
+        if serial_id % 2 != remote_parity {
+            return Err(InteractiveTxError::ProtocolViolation(
+                format!("Serial ID {} has the wrong parity", serial_id)
+            ));
+        }
+        Ok(())
+    }
+
+    fn count_remote_contribution(&mut self) -> InteractiveTxResult<()> {
+        self.remote_contributions += 1;
+        if self.remote_contributions > 2 * MAX_CONTRIBUTIONS_PER_PEER {
+            return Err(InteractiveTxError::ProtocolViolation(
+                "Peer sent too many additions".to_string()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply one message from the peer
+    pub fn handle_message(&mut self, message: &Message) -> InteractiveTxResult<()> {
+        match message.msg_type {
+            MessageType::TxAddInput => {
+                let payload: TxAddInputPayload = message.decode_payload()?;
+                self.check_remote_serial_id(payload.serial_id)?;
+                self.count_remote_contribution()?;
+                if self.inputs.contains_key(&payload.serial_id) {
+                    return Err(InteractiveTxError::ProtocolViolation(
+                        format!("Duplicate input serial ID {}", payload.serial_id)
+                    ));
+                }
+                let outpoint = OutPoint { txid: payload.prev_txid, vout: payload.prev_vout };
+                let already_spent = self.inputs.values().any(|i| i.input.outpoint == outpoint)
+                    || self.queue.iter().any(|a| matches!(a, LocalAction::AddInput(_, i) if i.outpoint == outpoint));
+                if already_spent {
+                    return Err(InteractiveTxError::ProtocolViolation(
+                        format!("Input {} added twice", outpoint)
+                    ));
+                }
+                self.inputs.insert(payload.serial_id, TrackedInput {
+                    input: FundingInput { outpoint, value_novas: payload.value_novas },
+                    sequence: payload.sequence,
+                    is_local: false,
+                });
+                self.remote_complete = false;
+            }
+            MessageType::TxAddOutput => {
+                let payload: TxAddOutputPayload = message.decode_payload()?;
+                self.check_remote_serial_id(payload.serial_id)?;
+                self.count_remote_contribution()?;
+                if self.outputs.contains_key(&payload.serial_id) {
+                    return Err(InteractiveTxError::ProtocolViolation(
+                        format!("Duplicate output serial ID {}", payload.serial_id)
+                    ));
+                }
+                if payload.amount_novas < DUST_LIMIT_NOVAS {
+                    return Err(InteractiveTxError::ProtocolViolation(
+                        format!("Output of {} novas is below dust", payload.amount_novas)
+                    ));
+                }
+                self.outputs.insert(payload.serial_id, TrackedOutput {
+                    output: TxOut::new(payload.amount_novas, payload.script),
+                    is_local: false,
+                });
+                self.remote_complete = false;
+            }
+            MessageType::TxRemoveInput => {
+                let payload: TxRemovePayload = message.decode_payload()?;
+                self.check_remote_serial_id(payload.serial_id)?;
+                if self.inputs.remove(&payload.serial_id).is_none() {
+                    return Err(InteractiveTxError::ProtocolViolation(
+                        format!("Unknown input serial ID {}", payload.serial_id)
+                    ));
+                }
+                self.remote_complete = false;
+            }
+            MessageType::TxRemoveOutput => {
+                let payload: TxRemovePayload = message.decode_payload()?;
+                self.check_remote_serial_id(payload.serial_id)?;
+                if self.outputs.remove(&payload.serial_id).is_none() {
+                    return Err(InteractiveTxError::ProtocolViolation(
+                        format!("Unknown output serial ID {}", payload.serial_id)
+                    ));
+                }
+                self.remote_complete = false;
+            }
+            MessageType::TxComplete => {
+                self.remote_complete = true;
+            }
+            MessageType::TxAbort => {
+                let payload: ErrorPayload = message.decode_payload()?;
+                return Err(InteractiveTxError::Aborted(payload.message));
+            }
+            ref other => {
+                return Err(InteractiveTxError::ProtocolViolation(
+                    format!("Unexpected {:?} during transaction construction", other)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build the negotiated transaction and check both contributions
+    pub fn build_transaction(&self) -> InteractiveTxResult<Transaction> {
+        if !self.is_complete() {
+            return Err(InteractiveTxError::ProtocolViolation(
+                "Transaction construction is not complete".to_string()
+            ));
+        }
+
+        let funding_outputs: Vec<&TrackedOutput> = self.outputs.values()
+            .filter(|o| o.output.script_pubkey() == self.funding_script.as_slice())
+            .collect();
+        if funding_outputs.len() != 1 {
+            return Err(InteractiveTxError::ProtocolViolation(
+                format!("Expected one funding output, found {}", funding_outputs.len())
+            ));
+        }
+        if funding_outputs[0].output.amount() != self.channel_capacity_novas {
+            return Err(InteractiveTxError::ProtocolViolation(
+                format!(
+                    "Funding output pays {} novas, expected {}",
+                    funding_outputs[0].output.amount(), self.channel_capacity_novas
+                )
+            ));
+        }
+
+        // The peer must fund its contribution and the fee for what it added
+        let remote_inputs: u64 = self.inputs.values()
+            .filter(|i| !i.is_local)
+            .map(|i| i.input.value_novas)
+            .sum();
+        let remote_outputs: Vec<&TrackedOutput> = self.outputs.values()
+            .filter(|o| !o.is_local && o.output.script_pubkey() != self.funding_script.as_slice())
+            .collect();
+        let remote_change: u64 = remote_outputs.iter().map(|o| o.output.amount()).sum();
+        let mut remote_weight = self.inputs.values().filter(|i| !i.is_local).count() as u64 * INPUT_WEIGHT
+            + remote_outputs.iter().map(|o| output_weight(o.output.script_pubkey())).sum::<u64>();
+        if !self.is_initiator {
+            remote_weight += TX_COMMON_WEIGHT + output_weight(&self.funding_script);
+        }
+        let remote_required = self.remote_funding_novas
+            + remote_change
+            + fee_for_weight(self.feerate_per_kw, remote_weight);
+        if remote_inputs < remote_required {
+            return Err(InteractiveTxError::InsufficientFunds(
+                format!("Peer inputs of {} novas cannot cover {} novas", remote_inputs, remote_required)
+            ));
+        }
+
+        let inputs = self.inputs.values()
+            .map(|i| TxIn::new(i.input.outpoint.txid, i.input.outpoint.vout, Vec::new(), i.sequence))
+            .collect();
+        let outputs = self.outputs.values()
+            .map(|o| o.output.clone())
+            .collect();
+
+        Ok(Transaction::new(2, inputs, outputs, self.locktime))
+    }
+
+    /// Index of the funding output in the built transaction
+    pub fn funding_output_index(&self) -> u32 {
+        self.outputs.values()
+            .position(|o| o.output.script_pubkey() == self.funding_script.as_slice())
+            .unwrap_or(0) as u32
+    }
+
+    /// Outpoints spent by the negotiated transaction
+    pub fn spent_outpoints(&self) -> HashSet<OutPoint> {
+        self.inputs.values().map(|i| i.input.outpoint.clone()).collect()
+    }
+
+    /// Sign every input we contributed
+    pub fn sign_local_inputs(
+        &self,
+        txid: &[u8; 32],
+        key: &QuantumKeyPair,
+    ) -> InteractiveTxResult<Vec<QuantumWitness>> {
+        self.inputs.iter()
+            .filter(|(_, i)| i.is_local)
+            .map(|(serial_id, _)| {
+                let signature = key.sign(&witness_digest(txid, *serial_id))
+                    .map_err(|e| InteractiveTxError::SigningError(e.to_string()))?;
+                Ok(QuantumWitness {
+                    serial_id: *serial_id,
+                    scheme: key.parameters.scheme,
+                    security_level: key.parameters.security_level,
+                    public_key: key.public_key.clone(),
+                    signature,
+                })
+            })
+            .collect()
+    }
+
+    /// Check the peer signed each of its inputs with its funding key
+    pub fn verify_remote_witnesses(
+        &self,
+        txid: &[u8; 32],
+        witnesses: &[QuantumWitness],
+        remote_funding_pubkey: &[u8],
+    ) -> InteractiveTxResult<()> {
+        for (serial_id, _) in self.inputs.iter().filter(|(_, i)| !i.is_local) {
+            let witness = witnesses.iter()
+                .find(|w| w.serial_id == *serial_id)
+                .ok_or_else(|| InteractiveTxError::InvalidWitness(
+                    format!("Missing witness for input {}", serial_id)
+                ))?;
+
+            if witness.public_key != remote_funding_pubkey {
+                return Err(InteractiveTxError::InvalidWitness(
+                    format!("Input {} signed with an unexpected key", serial_id)
+                ));
+            }
+
+            let parameters = QuantumParameters::with_security_level(witness.scheme, witness.security_level);
+            match verify_quantum_signature(
+                &witness.public_key,
+                &witness_digest(txid, *serial_id),
+                &witness.signature,
+                parameters,
+            ) {
+                Ok(true) => {}
+                Ok(false) => return Err(InteractiveTxError::InvalidWitness(
+                    format!("Signature for input {} does not verify", serial_id)
+                )),
+                Err(e) => return Err(InteractiveTxError::InvalidWitness(e.to_string())),
+            }
+        }
+
+        if witnesses.len() != self.inputs.values().filter(|i| !i.is_local).count() {
+            return Err(InteractiveTxError::InvalidWitness(
+                "Witnesses provided for inputs the peer did not add".to_string()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Minimum fee rate increase for a funding replacement, as 25/24
+const RBF_FEERATE_NUMERATOR: u64 = 25;
+const RBF_FEERATE_DENOMINATOR: u64 = 24;
+
+/// A fully signed funding transaction of a dual-funded channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NegotiatedFunding {
+    /// Funding transaction
+    pub tx: Transaction,
+
+    /// Funding output of the channel
+    pub outpoint: OutPoint,
+
+    /// Fee rate the transaction was negotiated at
+    pub feerate_per_kw: u32,
+
+    /// Witnesses for our inputs
+    pub local_witnesses: Vec<QuantumWitness>,
+
+    /// Witnesses for the peer's inputs
+    pub remote_witnesses: Vec<QuantumWitness>,
+}
+
+/// Negotiation state of a dual-funded channel until its funding confirms
+pub struct DualFundingSession {
+    channel_id: ChannelId,
+    is_initiator: bool,
+    contribution: FundingContribution,
+    local_key: QuantumKeyPair,
+    remote_funding_pubkey: Vec<u8>,
+    remote_funding_novas: u64,
+    feerate_per_kw: u32,
+    locktime: u32,
+    to_self_delay: u16,
+    max_accepted_htlcs: u16,
+    constructor: Option<InteractiveTxConstructor>,
+    awaiting_signatures: Option<(Transaction, u32, Vec<QuantumWitness>)>,
+    pending_rbf_feerate: Option<u32>,
+    candidates: Vec<NegotiatedFunding>,
+}
+
+impl DualFundingSession {
+    /// Start opening a dual-funded channel, returning the open_channel2 message
+    pub fn initiate(
+        contribution: FundingContribution,
+        local_key: QuantumKeyPair,
+        feerate_per_kw: u32,
+        locktime: u32,
+        factory: &MessageFactory,
+    ) -> InteractiveTxResult<(Self, Message)> {
+        let channel_id = ChannelId::new_random();
+        let payload = OpenChannel2Payload {
+            temporary_channel_id: channel_id.clone(),
+            funding_feerate_per_kw: feerate_per_kw,
+            locktime,
+            funding_novas: contribution.funding_novas,
+            to_self_delay: 144,
+            max_accepted_htlcs: 30,
+            funding_pubkey: local_key.public_key.clone(),
+            funding_security_level: local_key.parameters.security_level,
+        };
+        let message = factory.create_open_channel2(&payload)?;
+
+        let session = Self {
+            channel_id,
+            is_initiator: true,
+            contribution,
+            local_key,
+            remote_funding_pubkey: Vec::new(),
+            remote_funding_novas: 0,
+            feerate_per_kw,
+            locktime,
+            to_self_delay: payload.to_self_delay,
+            max_accepted_htlcs: payload.max_accepted_htlcs,
+            constructor: None,
+            awaiting_signatures: None,
+            pending_rbf_feerate: None,
+            candidates: Vec::new(),
+        };
+
+        Ok((session, message))
+    }
+
+    /// Accept an open_channel2 message with our own contribution
+    pub fn accept(
+        open_channel: &Message,
+        contribution: FundingContribution,
+        local_key: QuantumKeyPair,
+        factory: &MessageFactory,
+    ) -> InteractiveTxResult<(Self, Message)> {
+        if open_channel.msg_type != MessageType::OpenChannel2 {
+            return Err(InteractiveTxError::ProtocolViolation(
+                format!("Expected open_channel2, got {:?}", open_channel.msg_type)
+            ));
+        }
+        let open: OpenChannel2Payload = open_channel.decode_payload()?;
+
+        let mut session = Self {
+            channel_id: open.temporary_channel_id.clone(),
+            is_initiator: false,
+            contribution,
+            local_key,
+            remote_funding_pubkey: open.funding_pubkey,
+            remote_funding_novas: open.funding_novas,
+            feerate_per_kw: open.funding_feerate_per_kw,
+            locktime: open.locktime,
+            to_self_delay: open.to_self_delay,
+            max_accepted_htlcs: open.max_accepted_htlcs,
+            constructor: None,
+            awaiting_signatures: None,
+            pending_rbf_feerate: None,
+            candidates: Vec::new(),
+        };
+        session.start_round()?;
+
+        let payload = AcceptChannel2Payload {
+            temporary_channel_id: session.channel_id.clone(),
+            funding_novas: session.contribution.funding_novas,
+            funding_pubkey: session.local_key.public_key.clone(),
+            funding_security_level: session.local_key.parameters.security_level,
+        };
+        let message = factory.create_accept_channel2(&payload)?;
+
+        Ok((session, message))
+    }
+
+    /// Channel ID shared by both peers
+    pub fn channel_id(&self) -> &ChannelId {
+        &self.channel_id
+    }
+
+    /// Whether we opened the channel
+    pub fn is_initiator(&self) -> bool {
+        self.is_initiator
+    }
+
+    /// Our contribution to the channel in novas
+    pub fn local_funding_novas(&self) -> u64 {
+        self.contribution.funding_novas
+    }
+
+    /// The peer's contribution to the channel in novas
+    pub fn remote_funding_novas(&self) -> u64 {
+        self.remote_funding_novas
+    }
+
+    /// Total channel capacity in novas
+    pub fn capacity_novas(&self) -> u64 {
+        self.contribution.funding_novas + self.remote_funding_novas
+    }
+
+    /// To-self delay agreed in open_channel2
+    pub fn to_self_delay(&self) -> u16 {
+        self.to_self_delay
+    }
+
+    /// Maximum accepted HTLCs agreed in open_channel2
+    pub fn max_accepted_htlcs(&self) -> u16 {
+        self.max_accepted_htlcs
+    }
+
+    /// Fully signed funding transactions, oldest first
+    pub fn candidates(&self) -> &[NegotiatedFunding] {
+        &self.candidates
+    }
+
+    /// Whether a construction round or signature exchange is in progress
+    pub fn is_negotiating(&self) -> bool {
+        self.constructor.is_some() || self.awaiting_signatures.is_some() || self.pending_rbf_feerate.is_some()
+    }
+
+    fn funding_script(&self) -> Vec<u8> {
+        if self.is_initiator {
+            funding_script(&self.local_key.public_key, &self.remote_funding_pubkey)
+        } else {
+            funding_script(&self.remote_funding_pubkey, &self.local_key.public_key)
+        }
+    }
+
+    fn start_round(&mut self) -> InteractiveTxResult<()> {
+        self.constructor = Some(InteractiveTxConstructor::new(
+            self.channel_id.clone(),
+            self.is_initiator,
+            self.feerate_per_kw,
+            self.locktime,
+            self.funding_script(),
+            &self.contribution,
+            self.remote_funding_novas,
+        )?);
+        Ok(())
+    }
+
+    fn check_rbf_feerate(&self, feerate_per_kw: u32) -> InteractiveTxResult<()> {
+        let minimum = (self.feerate_per_kw as u64 * RBF_FEERATE_NUMERATOR).div_ceil(RBF_FEERATE_DENOMINATOR);
+        if (feerate_per_kw as u64) < minimum {
+            return Err(InteractiveTxError::ProtocolViolation(
+                format!("Replacement fee rate {} is below {}", feerate_per_kw, minimum)
+            ));
+        }
+        Ok(())
+    }
+
+    /// Propose replacing the funding transaction at a higher fee rate
+    pub fn bump_fee(&mut self, feerate_per_kw: u32, factory: &MessageFactory) -> InteractiveTxResult<Message> {
+        if !self.is_initiator {
+            return Err(InteractiveTxError::ProtocolViolation(
+                "Only the initiator can replace the funding transaction".to_string()
+            ));
+        }
+        if self.candidates.is_empty() || self.is_negotiating() {
+            return Err(InteractiveTxError::ProtocolViolation(
+                "No signed funding transaction to replace".to_string()
+            ));
+        }
+        self.check_rbf_feerate(feerate_per_kw)?;
+        self.pending_rbf_feerate = Some(feerate_per_kw);
+
+        let payload = TxInitRbfPayload {
+            channel_id: self.channel_id.clone(),
+            locktime: self.locktime,
+            funding_feerate_per_kw: feerate_per_kw,
+            funding_novas: self.contribution.funding_novas,
+        };
+        Ok(factory.create_tx_init_rbf(&payload)?)
+    }
+
+    /// Handle a message from the peer, returning our replies
+    pub fn handle_message(
+        &mut self,
+        message: &Message,
+        factory: &MessageFactory,
+    ) -> InteractiveTxResult<Vec<Message>> {
+        match message.msg_type {
+            MessageType::AcceptChannel2 => {
+                if !self.is_initiator || !self.remote_funding_pubkey.is_empty() {
+                    return Err(InteractiveTxError::ProtocolViolation(
+                        "Unexpected accept_channel2".to_string()
+                    ));
+                }
+                let accept: AcceptChannel2Payload = message.decode_payload()?;
+                self.remote_funding_pubkey = accept.funding_pubkey;
+                self.remote_funding_novas = accept.funding_novas;
+                self.start_round()?;
+                self.take_turn(factory)
+            }
+            MessageType::TxInitRbf => {
+                if self.is_initiator || self.candidates.is_empty() || self.is_negotiating() {
+                    return Err(InteractiveTxError::ProtocolViolation(
+                        "Unexpected tx_init_rbf".to_string()
+                    ));
+                }
+                let rbf: TxInitRbfPayload = message.decode_payload()?;
+                self.check_rbf_feerate(rbf.funding_feerate_per_kw)?;
+                if rbf.funding_novas != self.remote_funding_novas {
+                    return Err(InteractiveTxError::ProtocolViolation(
+                        "Replacement changes the initiator's contribution".to_string()
+                    ));
+                }
+                self.feerate_per_kw = rbf.funding_feerate_per_kw;
+                self.locktime = rbf.locktime;
+                self.start_round()?;
+
+                let payload = TxAckRbfPayload {
+                    channel_id: self.channel_id.clone(),
+                    funding_novas: self.contribution.funding_novas,
+                };
+                Ok(vec![factory.create_tx_ack_rbf(&payload)?])
+            }
+            MessageType::TxAckRbf => {
+                let feerate = self.pending_rbf_feerate.take().ok_or_else(|| {
+                    InteractiveTxError::ProtocolViolation("Unexpected tx_ack_rbf".to_string())
+                })?;
+                let ack: TxAckRbfPayload = message.decode_payload()?;
+                if ack.funding_novas != self.remote_funding_novas {
+                    return Err(InteractiveTxError::ProtocolViolation(
+                        "Replacement changes the acceptor's contribution".to_string()
+                    ));
+                }
+                self.feerate_per_kw = feerate;
+                self.start_round()?;
+                self.take_turn(factory)
+            }
+            MessageType::TxSignatures => {
+                let payload: TxSignaturesPayload = message.decode_payload()?;
+                let (tx, funding_vout, local_witnesses) = self.awaiting_signatures.take().ok_or_else(|| {
+                    InteractiveTxError::ProtocolViolation("Unexpected tx_signatures".to_string())
+                })?;
+                let txid = tx.hash();
+                if payload.txid != txid {
+                    return Err(InteractiveTxError::InvalidWitness(
+                        "Signatures are for a different transaction".to_string()
+                    ));
+                }
+                let constructor = self.constructor.take().ok_or_else(|| {
+                    InteractiveTxError::ProtocolViolation("No transaction under construction".to_string())
+                })?;
+                constructor.verify_remote_witnesses(&txid, &payload.witnesses, &self.remote_funding_pubkey)?;
+
+                info!("Dual-funded transaction {} signed for channel {}", hex::encode(txid), self.channel_id);
+                self.candidates.push(NegotiatedFunding {
+                    tx,
+                    outpoint: OutPoint { txid, vout: funding_vout },
+                    feerate_per_kw: self.feerate_per_kw,
+                    local_witnesses,
+                    remote_witnesses: payload.witnesses,
+                });
+                Ok(Vec::new())
+            }
+            _ => {
+                let constructor = self.constructor.as_mut().ok_or_else(|| {
+                    InteractiveTxError::ProtocolViolation(
+                        format!("Unexpected {:?} with no transaction under construction", message.msg_type)
+                    )
+                })?;
+                if self.awaiting_signatures.is_some() {
+                    return Err(InteractiveTxError::ProtocolViolation(
+                        "Transaction construction already finished".to_string()
+                    ));
+                }
+                constructor.handle_message(message)?;
+                if constructor.is_complete() {
+                    Ok(vec![self.finish_round(factory)?])
+                } else {
+                    self.take_turn(factory)
+                }
+            }
+        }
+    }
+
+    /// Send our next construction message, and our signatures once complete
+    fn take_turn(&mut self, factory: &MessageFactory) -> InteractiveTxResult<Vec<Message>> {
+        let constructor = self.constructor.as_mut().ok_or_else(|| {
+            InteractiveTxError::ProtocolViolation("No transaction under construction".to_string())
+        })?;
+        let mut replies = vec![constructor.next_message(factory)?];
+        if constructor.is_complete() {
+            replies.push(self.finish_round(factory)?);
+        }
+        Ok(replies)
+    }
+
+    fn finish_round(&mut self, factory: &MessageFactory) -> InteractiveTxResult<Message> {
+        let constructor = self.constructor.as_ref().ok_or_else(|| {
+            InteractiveTxError::ProtocolViolation("No transaction under construction".to_string())
+        })?;
+        let tx = constructor.build_transaction()?;
+
+        // A replacement must conflict with every earlier version so that
+        // at most one of them can confirm
+        let spent = constructor.spent_outpoints();
+        for candidate in &self.candidates {
+            if !candidate.tx.inputs().iter().any(|i| spent.contains(&OutPoint {
+                txid: i.prev_tx_hash(),
+                vout: i.prev_output_index(),
+            })) {
+                return Err(InteractiveTxError::ProtocolViolation(
+                    "Replacement does not conflict with an earlier funding transaction".to_string()
+                ));
+            }
+        }
+
+        let txid = tx.hash();
+        let witnesses = constructor.sign_local_inputs(&txid, &self.local_key)?;
+        let funding_vout = constructor.funding_output_index();
+        debug!("Negotiated funding transaction {} at {} per kw", hex::encode(txid), self.feerate_per_kw);
+
+        let payload = TxSignaturesPayload {
+            channel_id: self.channel_id.clone(),
+            txid,
+            witnesses: witnesses.clone(),
+        };
+        self.awaiting_signatures = Some((tx, funding_vout, witnesses));
+
+        Ok(factory.create_tx_signatures(&payload)?)
+    }
+
+    /// Pick the candidate that confirmed, discarding the others
+    pub fn funding_confirmed(&mut self, txid: &[u8; 32]) -> InteractiveTxResult<NegotiatedFunding> {
+        let index = self.candidates.iter()
+            .position(|c| &c.outpoint.txid == txid)
+            .ok_or_else(|| InteractiveTxError::ProtocolViolation(
+                format!("Unknown funding transaction {}", hex::encode(txid))
+            ))?;
+        let confirmed = self.candidates.swap_remove(index);
+        self.candidates.clear();
+        Ok(confirmed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::quantum::{QuantumParameters, QuantumScheme};
+
+    fn dilithium_key() -> QuantumKeyPair {
+        QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap()
+    }
+
+    fn contribution(funding_novas: u64, input_value: u64, seed: u8) -> FundingContribution {
+        FundingContribution {
+            funding_novas,
+            inputs: vec![FundingInput {
+                outpoint: OutPoint { txid: [seed; 32], vout: 0 },
+                value_novas: input_value,
+            }],
+            change_script: vec![0x00, 0x14, seed],
+        }
+    }
+
+    fn exchange<F: Fn(&mut Message)>(
+        initiator: &mut DualFundingSession,
+        acceptor: &mut DualFundingSession,
+        first: Vec<Message>,
+        tamper: F,
+    ) -> InteractiveTxResult<()> {
+        let initiator_factory = MessageFactory::new("initiator".to_string(), vec![1; 32]);
+        let acceptor_factory = MessageFactory::new("acceptor".to_string(), vec![2; 32]);
+        let mut to_acceptor = first;
+        let mut to_initiator = Vec::new();
+
+        while !to_acceptor.is_empty() || !to_initiator.is_empty() {
+            for message in to_acceptor.drain(..) {
+                to_initiator.extend(acceptor.handle_message(&message, &acceptor_factory)?);
+            }
+            for mut message in to_initiator.drain(..) {
+                tamper(&mut message);
+                to_acceptor.extend(initiator.handle_message(&message, &initiator_factory)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn open_pair() -> (DualFundingSession, DualFundingSession, Message) {
+        let factory = MessageFactory::new("initiator".to_string(), vec![1; 32]);
+        let (initiator, open) = DualFundingSession::initiate(
+            contribution(600_000, 700_000, 1), dilithium_key(), 253, 0, &factory,
+        ).unwrap();
+        let (acceptor, accept) = DualFundingSession::accept(
+            &open, contribution(400_000, 450_000, 2), dilithium_key(), &factory,
+        ).unwrap();
+        (initiator, acceptor, accept)
+    }
+
+    #[test]
+    fn test_dual_funded_construction() {
+        let (mut initiator, mut acceptor, accept) = open_pair();
+        let factory = MessageFactory::new("initiator".to_string(), vec![1; 32]);
+        let first = initiator.handle_message(&accept, &factory).unwrap();
+        exchange(&mut initiator, &mut acceptor, first, |_| {}).unwrap();
+
+        assert!(!initiator.is_negotiating());
+        assert!(!acceptor.is_negotiating());
+        let ours = &initiator.candidates()[0];
+        let theirs = &acceptor.candidates()[0];
+        assert_eq!(ours.outpoint, theirs.outpoint);
+        assert_eq!(ours.tx.hash(), theirs.tx.hash());
+
+        // Both peers' inputs are spent and both changes are paid
+        let tx = &ours.tx;
+        assert_eq!(tx.inputs().len(), 2);
+        assert_eq!(tx.outputs().len(), 3);
+        let funding = &tx.outputs()[ours.outpoint.vout as usize];
+        assert_eq!(funding.amount(), 1_000_000);
+        assert_eq!(ours.local_witnesses.len(), 1);
+        assert_eq!(ours.remote_witnesses.len(), 1);
+        assert!(tx.total_output().unwrap() < 1_150_000);
+    }
+
+    #[test]
+    fn test_forged_witness_rejected() {
+        let (mut initiator, mut acceptor, accept) = open_pair();
+        let factory = MessageFactory::new("initiator".to_string(), vec![1; 32]);
+        let first = initiator.handle_message(&accept, &factory).unwrap();
+
+        let result = exchange(&mut initiator, &mut acceptor, first, |message| {
+            if message.msg_type == MessageType::TxSignatures {
+                let mut payload: TxSignaturesPayload = message.decode_payload().unwrap();
+                payload.witnesses[0].signature[0] ^= 0xff;
+                message.payload = bincode::serialize(&payload).unwrap();
+            }
+        });
+        assert!(matches!(result, Err(InteractiveTxError::InvalidWitness(_))));
+        assert!(initiator.candidates().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_bad_peer_contributions() {
+        let factory = MessageFactory::new("acceptor".to_string(), vec![2; 32]);
+        let channel_id = ChannelId::new_random();
+        let script = funding_script(&[1], &[2]);
+        let mut constructor = InteractiveTxConstructor::new(
+            channel_id.clone(), true, 253, 0, script, &contribution(600_000, 700_000, 1), 50_000,
+        ).unwrap();
+
+        // The acceptor must use odd serial IDs
+        let even = factory.create_tx_add_input(&TxAddInputPayload {
+            channel_id: channel_id.clone(),
+            serial_id: 2,
+            prev_txid: [3; 32],
+            prev_vout: 0,
+            value_novas: 10_000,
+            sequence: 0xfffffffd,
+        }).unwrap();
+        assert!(matches!(constructor.handle_message(&even), Err(InteractiveTxError::ProtocolViolation(_))));
+
+        // An input too small for the peer's contribution fails at build time
+        while constructor.next_message(&factory).unwrap().msg_type != MessageType::TxComplete {}
+        let small = factory.create_tx_add_input(&TxAddInputPayload {
+            channel_id: channel_id.clone(),
+            serial_id: 1,
+            prev_txid: [3; 32],
+            prev_vout: 0,
+            value_novas: 10_000,
+            sequence: 0xfffffffd,
+        }).unwrap();
+        constructor.handle_message(&small).unwrap();
+        constructor.next_message(&factory).unwrap();
+        constructor.handle_message(&factory.create_tx_complete(channel_id).unwrap()).unwrap();
+        assert!(constructor.is_complete());
+        assert!(matches!(constructor.build_transaction(), Err(InteractiveTxError::InsufficientFunds(_))));
+    }
+
+    #[test]
+    fn test_rbf_keeps_every_candidate() {
+        let (mut initiator, mut acceptor, accept) = open_pair();
+        let factory = MessageFactory::new("initiator".to_string(), vec![1; 32]);
+        let first = initiator.handle_message(&accept, &factory).unwrap();
+        exchange(&mut initiator, &mut acceptor, first, |_| {}).unwrap();
+
+        // Fee bumps must beat the previous rate by at least 1/24
+        assert!(initiator.bump_fee(260, &factory).is_err());
+        let rbf = initiator.bump_fee(506, &factory).unwrap();
+        exchange(&mut initiator, &mut acceptor, vec![rbf], |_| {}).unwrap();
+
+        assert_eq!(initiator.candidates().len(), 2);
+        assert_eq!(acceptor.candidates().len(), 2);
+        let original = initiator.candidates()[0].outpoint.txid;
+        let replacement = initiator.candidates()[1].outpoint.txid;
+        assert_ne!(original, replacement);
+        assert_eq!(acceptor.candidates()[1].feerate_per_kw, 506);
+
+        let confirmed = acceptor.funding_confirmed(&original).unwrap();
+        assert_eq!(confirmed.outpoint.txid, original);
+        assert!(acceptor.candidates().is_empty());
+    }
+}
@@ -14,6 +14,9 @@ use serde::{Serialize, Deserialize};
 
 // Import shared payment types
 use super::payment::{PaymentHash, PaymentPreimage};
+use super::onion::BlindedPath;
+use super::channel::ChannelId;
+use crate::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme, verify_quantum_signature};
 
 /// Error types for invoice operations
 #[derive(Debug, Error)]
@@ -44,6 +47,12 @@ pub enum InvoiceError {
     
     #[error("Unsupported feature bit: {0}")]
     UnsupportedFeature(u32),
+    
+    #[error("Offer mismatch: {0}")]
+    OfferMismatch(String),
+    
+    #[error("Invalid invoice state: {0}")]
+    InvalidState(String),
 }
 
 /// Route hint for private channels
@@ -92,6 +101,9 @@ pub struct Invoice {
     /// Route hints for private channels
     route_hints: Vec<RouteHint>,
     
+    /// Blinded paths to the destination
+    blinded_paths: Vec<BlindedPath>,
+    
     /// Min final CLTV expiry delta
     min_final_cltv_expiry: u32,
     
@@ -127,6 +139,7 @@ impl Invoice {
             timestamp,
             expiry,
             route_hints: Vec::new(),
+            blinded_paths: Vec::new(),
             min_final_cltv_expiry: 40,
             features: 0,
             signature: None,
@@ -198,6 +211,7 @@ impl Invoice {
             timestamp,
             expiry,
             route_hints: Vec::new(),
+            blinded_paths: Vec::new(),
             min_final_cltv_expiry: 40, // Default CLTV delta
             features: 0,               // No special features
             signature: None,           // No signature yet
@@ -245,6 +259,7 @@ impl Invoice {
             timestamp,
             expiry,
             route_hints: Vec::new(),
+            blinded_paths: Vec::new(),
             min_final_cltv_expiry: 40, // Default CLTV delta
             features: 0,               // No special features
             signature: None,           // No signature yet
@@ -300,6 +315,20 @@ impl Invoice {
         self.route_hints.push(hint);
     }
     
+    /// Get blinded paths
+    pub fn blinded_paths(&self) -> &[BlindedPath] {
+        &self.blinded_paths
+    }
+    
+    /// Add a blinded path
+    ///
+    /// Blinded paths replace route hints, which would reveal the receiver's channels.
+    pub fn add_blinded_path(&mut self, path: BlindedPath) {
+        self.route_hints.clear();
+        self.blinded_paths.push(path);
+        self.features |= feature_bits::ROUTE_BLINDING;
+    }
+    
     /// Get expiry time in seconds
     pub fn expiry(&self) -> u32 {
         self.expiry
@@ -400,6 +429,35 @@ pub struct EnhancedInvoice {
     
     /// Payment attempts
     attempts: u32,
+    
+    /// Whether settlement waits for an external preimage
+    hold: bool,
+    
+    /// HTLCs held for a hold invoice until it is settled or canceled
+    held_htlcs: Vec<HeldHtlc>,
+}
+
+/// Blocks before the earliest held HTLC expires at which a hold invoice
+/// is canceled, leaving time to fail the HTLCs back off-chain
+pub const HOLD_INVOICE_CANCEL_DELTA: u32 = 10;
+
+/// An incoming HTLC held for a hold invoice
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeldHtlc {
+    /// Channel the HTLC arrived on
+    pub channel_id: ChannelId,
+    
+    /// HTLC ID within the channel
+    pub htlc_id: u64,
+    
+    /// Amount in millisatoshis
+    pub amount_msat: u64,
+    
+    /// Absolute CLTV expiry height
+    pub cltv_expiry: u32,
+    
+    /// When the HTLC was accepted
+    pub accepted_at: u64,
 }
 
 /// State of an invoice
@@ -416,6 +474,10 @@ pub enum InvoiceState {
     
     /// Invoice has been canceled
     Canceled,
+    
+    /// Hold invoice has received its full amount and the HTLCs are held
+    /// until the preimage is released or the invoice is canceled
+    Accepted,
 }
 
 /// Payment metadata for invoice
@@ -476,9 +538,27 @@ impl EnhancedInvoice {
             fallback_address: None,
             state: InvoiceState::Open,
             attempts: 0,
+            hold: false,
+            held_htlcs: Vec::new(),
         })
     }
     
+    /// Create a hold invoice from a payment hash only
+    ///
+    /// The preimage is kept by the caller; incoming HTLCs are held until
+    /// `InvoiceDatabase::settle_hold_invoice` or `cancel_hold_invoice`.
+    pub fn new_hold(
+        payment_hash: PaymentHash,
+        amount_msat: u64,
+        description: String,
+        expiry: u32,
+        features: u64,
+    ) -> Result<Self, InvoiceError> {
+        let mut invoice = Self::new(payment_hash, amount_msat, description, expiry, features)?;
+        invoice.hold = true;
+        Ok(invoice)
+    }
+    
     /// Create an invoice from parts
     pub fn from_parts(
         invoice: Invoice,
@@ -493,6 +573,8 @@ impl EnhancedInvoice {
             fallback_address: None,
             state: InvoiceState::Open,
             attempts: 0,
+            hold: false,
+            held_htlcs: Vec::new(),
         }
     }
     
@@ -576,11 +658,33 @@ impl EnhancedInvoice {
     
     /// Cancel the invoice
     pub fn cancel(&mut self) {
-        if self.state == InvoiceState::Open {
+        if matches!(self.state, InvoiceState::Open | InvoiceState::Accepted) {
             self.state = InvoiceState::Canceled;
         }
     }
     
+    /// Check if this is a hold invoice
+    pub fn is_hold(&self) -> bool {
+        self.hold
+    }
+    
+    /// Get the HTLCs held for this invoice
+    pub fn held_htlcs(&self) -> &[HeldHtlc] {
+        &self.held_htlcs
+    }
+    
+    /// Get the total amount of the held HTLCs
+    pub fn amount_received_msat(&self) -> u64 {
+        self.held_htlcs.iter().map(|h| h.amount_msat).sum()
+    }
+    
+    /// Get the height at which the invoice is canceled to protect the held HTLCs
+    pub fn cancel_height(&self) -> Option<u32> {
+        self.held_htlcs.iter()
+            .map(|h| h.cltv_expiry.saturating_sub(HOLD_INVOICE_CANCEL_DELTA))
+            .min()
+    }
+    
     /// Get the invoice state
     pub fn state(&self) -> &InvoiceState {
         &self.state
@@ -601,6 +705,11 @@ impl EnhancedInvoice {
         self.invoice.route_hints()
     }
     
+    /// Get the blinded paths
+    pub fn blinded_paths(&self) -> &[BlindedPath] {
+        self.invoice.blinded_paths()
+    }
+    
     /// Get the destination (node ID)
     pub fn destination(&self) -> &str {
         self.invoice.destination()
@@ -715,6 +824,13 @@ impl InvoiceDatabase {
     /// Mark an invoice as paid
     pub fn mark_invoice_paid(&mut self, payment_hash: &PaymentHash) -> Result<(), InvoiceError> {
         if let Some(invoice) = self.invoices.get_mut(payment_hash) {
+            // Hold invoices only settle once the preimage is released
+            if invoice.is_hold() {
+                return Err(InvoiceError::InvalidState(
+                    format!("Hold invoice {} must be settled with its preimage", payment_hash)
+                ));
+            }
+            
             // Check if the invoice is expired
             if invoice.is_expired() {
                 return Err(InvoiceError::Expired);
@@ -745,6 +861,116 @@ impl InvoiceDatabase {
         }
     }
     
+    /// Hold an incoming HTLC for a hold invoice
+    ///
+    /// The invoice moves to `Accepted` once the held HTLCs cover its amount.
+    /// HTLCs expiring too soon to be failed back safely are refused.
+    pub fn accept_hold_htlc(
+        &mut self,
+        payment_hash: &PaymentHash,
+        htlc: HeldHtlc,
+        current_height: u32,
+    ) -> Result<InvoiceState, InvoiceError> {
+        let invoice = self.invoices.get_mut(payment_hash)
+            .ok_or_else(|| InvoiceError::InvalidHash(
+                format!("Invoice with payment hash {} not found", payment_hash)
+            ))?;
+        
+        if !invoice.is_hold() {
+            return Err(InvoiceError::InvalidState(
+                format!("Invoice {} is not a hold invoice", payment_hash)
+            ));
+        }
+        
+        if !matches!(invoice.state(), InvoiceState::Open | InvoiceState::Accepted) {
+            return Err(InvoiceError::InvalidState(
+                format!("Hold invoice {} is {:?}", payment_hash, invoice.state())
+            ));
+        }
+        
+        if invoice.state == InvoiceState::Open && invoice.is_expired() {
+            return Err(InvoiceError::Expired);
+        }
+        
+        if htlc.cltv_expiry <= current_height + HOLD_INVOICE_CANCEL_DELTA {
+            return Err(InvoiceError::InvalidState(
+                format!("HTLC expiring at {} leaves no time to hold it", htlc.cltv_expiry)
+            ));
+        }
+        
+        if invoice.held_htlcs.iter().any(|h| h.channel_id == htlc.channel_id && h.htlc_id == htlc.htlc_id) {
+            return Err(InvoiceError::InvalidState(
+                format!("HTLC {} is already held", htlc.htlc_id)
+            ));
+        }
+        
+        invoice.held_htlcs.push(htlc);
+        if invoice.amount_received_msat() >= invoice.amount_msat() {
+            invoice.state = InvoiceState::Accepted;
+        }
+        
+        Ok(invoice.state().clone())
+    }
+    
+    /// Settle an accepted hold invoice, returning the HTLCs to fulfill
+    pub fn settle_hold_invoice(&mut self, preimage: &PaymentPreimage) -> Result<Vec<HeldHtlc>, InvoiceError> {
+        let payment_hash = preimage.payment_hash();
+        let invoice = self.invoices.get_mut(&payment_hash)
+            .ok_or_else(|| InvoiceError::InvalidHash(
+                format!("No hold invoice for preimage of {}", payment_hash)
+            ))?;
+        
+        if !invoice.is_hold() || invoice.state != InvoiceState::Accepted {
+            return Err(InvoiceError::InvalidState(
+                format!("Invoice {} is not an accepted hold invoice", payment_hash)
+            ));
+        }
+        
+        invoice.mark_as_paid();
+        self.paid_invoices.insert(payment_hash);
+        self.last_update = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+        
+        Ok(invoice.held_htlcs.clone())
+    }
+    
+    /// Cancel a hold invoice, returning the HTLCs to fail back
+    pub fn cancel_hold_invoice(&mut self, payment_hash: &PaymentHash) -> Result<Vec<HeldHtlc>, InvoiceError> {
+        let invoice = self.invoices.get_mut(payment_hash)
+            .ok_or_else(|| InvoiceError::InvalidHash(
+                format!("Invoice with payment hash {} not found", payment_hash)
+            ))?;
+        
+        if !invoice.is_hold() || !matches!(invoice.state(), InvoiceState::Open | InvoiceState::Accepted) {
+            return Err(InvoiceError::InvalidState(
+                format!("Invoice {} cannot be canceled while {:?}", payment_hash, invoice.state())
+            ));
+        }
+        
+        invoice.cancel();
+        Ok(std::mem::take(&mut invoice.held_htlcs))
+    }
+    
+    /// Cancel hold invoices whose held HTLCs are about to expire
+    ///
+    /// Returns the canceled invoices with the HTLCs to fail back.
+    pub fn cancel_expiring_hold_invoices(&mut self, current_height: u32) -> Vec<(PaymentHash, Vec<HeldHtlc>)> {
+        let expiring: Vec<PaymentHash> = self.invoices.iter()
+            .filter(|(_, invoice)| invoice.is_hold())
+            .filter(|(_, invoice)| matches!(invoice.state(), InvoiceState::Open | InvoiceState::Accepted))
+            .filter(|(_, invoice)| invoice.cancel_height().map_or(false, |height| height <= current_height))
+            .map(|(payment_hash, _)| *payment_hash)
+            .collect();
+        
+        expiring.into_iter()
+            .filter_map(|payment_hash| {
+                self.cancel_hold_invoice(&payment_hash).ok().map(|htlcs| (payment_hash, htlcs))
+            })
+            .collect()
+    }
+    
     /// Check if an invoice is paid
     pub fn is_invoice_paid(&self, payment_hash: &PaymentHash) -> bool {
         self.paid_invoices.contains(payment_hash)
@@ -774,7 +1000,8 @@ impl InvoiceDatabase {
         let mut expired_count = 0;
         
         for invoice in self.invoices.values_mut() {
-            if invoice.is_expired() && matches!(invoice.state(), InvoiceState::Open) {
+            // Held HTLCs are released by cancel_expiring_hold_invoices instead
+            if invoice.is_expired() && matches!(invoice.state(), InvoiceState::Open) && invoice.held_htlcs().is_empty() {
                 invoice.mark_as_expired();
                 expired_count += 1;
             }
@@ -828,4 +1055,1106 @@ impl InvoiceDatabase {
         
         to_remove.len()
     }
-} 
\ No newline at end of file
+} 
+// Reusable payment offers
+//
+// Offers are static, reusable payment descriptions in the spirit of BOLT-12.
+// A merchant publishes an offer once; payers answer it with a signed invoice
+// request, and the merchant replies with a signed invoice for that specific
+// request. Every object is signed with a Dilithium key instead of secp256k1,
+// and encoded as bech32 without checksum under its own human-readable prefix.
+
+/// Human-readable prefix for encoded offers
+pub const OFFER_HRP: &str = "lno";
+
+/// Human-readable prefix for encoded invoice requests
+pub const INVOICE_REQUEST_HRP: &str = "lnr";
+
+/// Human-readable prefix for encoded offer invoices
+pub const OFFER_INVOICE_HRP: &str = "lni";
+
+/// Human-readable prefix for encoded payer proofs
+pub const PAYER_PROOF_HRP: &str = "lnp";
+
+/// Default relative expiry for invoices issued against an offer (seconds)
+pub const DEFAULT_OFFER_INVOICE_EXPIRY: u32 = 7200;
+
+/// Current Unix time in seconds
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(0))
+        .as_secs()
+}
+
+/// Encode a serializable object as bech32 (without checksum) under `hrp`
+fn encode_bech32<T: Serialize>(hrp: &str, value: &T) -> Result<String, InvoiceError> {
+    use bech32::ToBase32;
+
+    let bytes = bincode::serialize(value)
+        .map_err(|e| InvoiceError::ParseError(e.to_string()))?;
+
+    bech32::encode_without_checksum(hrp, bytes.to_base32())
+        .map_err(|e| InvoiceError::InvalidFormat(e.to_string()))
+}
+
+/// Decode a bech32 string (without checksum) produced by `encode_bech32`
+fn decode_bech32<T: for<'de> Deserialize<'de>>(hrp: &str, encoded: &str) -> Result<T, InvoiceError> {
+    use bech32::FromBase32;
+
+    let (decoded_hrp, data) = bech32::decode_without_checksum(encoded.trim())
+        .map_err(|e| InvoiceError::InvalidFormat(e.to_string()))?;
+
+    if decoded_hrp != hrp {
+        return Err(InvoiceError::InvalidFormat(
+            format!("Expected prefix '{}', found '{}'", hrp, decoded_hrp)
+        ));
+    }
+
+    let bytes = Vec::<u8>::from_base32(&data)
+        .map_err(|e| InvoiceError::InvalidFormat(e.to_string()))?;
+
+    bincode::deserialize(&bytes)
+        .map_err(|e| InvoiceError::ParseError(e.to_string()))
+}
+
+/// SHA-256 of a serializable value, used for offer and request identifiers
+fn signing_digest<T: Serialize>(value: &T) -> Result<[u8; 32], InvoiceError> {
+    let bytes = bincode::serialize(value)
+        .map_err(|e| InvoiceError::ParseError(e.to_string()))?;
+
+    Ok(Sha256::digest(&bytes).into())
+}
+
+/// Sign a digest with a Dilithium key pair
+fn dilithium_sign(key: &QuantumKeyPair, digest: &[u8; 32]) -> Result<Vec<u8>, InvoiceError> {
+    if key.parameters.scheme != QuantumScheme::Dilithium {
+        return Err(InvoiceError::InvalidSignature(
+            format!("Offers must be signed with Dilithium, not {:?}", key.parameters.scheme)
+        ));
+    }
+
+    key.sign(digest)
+        .map_err(|e| InvoiceError::InvalidSignature(e.to_string()))
+}
+
+/// Verify a Dilithium signature over a digest
+fn dilithium_verify(
+    public_key: &[u8],
+    security_level: u8,
+    digest: &[u8; 32],
+    signature: &[u8],
+) -> Result<(), InvoiceError> {
+    let parameters = QuantumParameters::with_security_level(QuantumScheme::Dilithium, security_level);
+
+    match verify_quantum_signature(public_key, digest, signature, parameters) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(InvoiceError::InvalidSignature(
+            "Dilithium signature does not match".to_string()
+        )),
+        Err(e) => Err(InvoiceError::InvalidSignature(e.to_string())),
+    }
+}
+
+/// Identifier of an offer (SHA-256 of its signed fields)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OfferId([u8; 32]);
+
+impl OfferId {
+    /// Get the raw bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Convert to hex string
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Create from hex string
+    pub fn from_hex(hex_str: &str) -> Result<Self, InvoiceError> {
+        let bytes = hex::decode(hex_str)
+            .map_err(|e| InvoiceError::InvalidFormat(e.to_string()))?;
+
+        let id: [u8; 32] = bytes.try_into()
+            .map_err(|_| InvoiceError::InvalidFormat("Offer ID must be 32 bytes".to_string()))?;
+
+        Ok(Self(id))
+    }
+}
+
+impl fmt::Display for OfferId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+/// Billing period of a recurring offer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecurrencePeriod {
+    /// Every N seconds
+    Seconds(u32),
+
+    /// Every N days
+    Days(u16),
+
+    /// Every N months (approximated as 30 days)
+    Months(u16),
+}
+
+impl RecurrencePeriod {
+    /// Length of one period in seconds
+    pub fn as_seconds(&self) -> u64 {
+        match self {
+            RecurrencePeriod::Seconds(n) => *n as u64,
+            RecurrencePeriod::Days(n) => *n as u64 * 86_400,
+            RecurrencePeriod::Months(n) => *n as u64 * 30 * 86_400,
+        }
+    }
+}
+
+/// Recurrence terms of an offer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recurrence {
+    /// Length of each billing period
+    pub period: RecurrencePeriod,
+
+    /// Maximum number of periods that can be paid (None = unlimited)
+    pub limit: Option<u32>,
+
+    /// Seconds after the start of a period during which it can be paid
+    /// (None = the whole period)
+    pub paywindow_seconds: Option<u32>,
+}
+
+impl Recurrence {
+    /// Start time of the period identified by `counter`
+    pub fn period_start(&self, basetime: u64, counter: u32) -> u64 {
+        basetime + self.period.as_seconds() * counter as u64
+    }
+
+    /// Check that `counter` may be paid at time `now`
+    pub fn check_counter(&self, basetime: u64, counter: u32, now: u64) -> Result<(), InvoiceError> {
+        if let Some(limit) = self.limit {
+            if counter >= limit {
+                return Err(InvoiceError::OfferMismatch(
+                    format!("Recurrence counter {} exceeds limit {}", counter, limit)
+                ));
+            }
+        }
+
+        let start = self.period_start(basetime, counter);
+        let window = self.paywindow_seconds
+            .map(|w| w as u64)
+            .unwrap_or_else(|| self.period.as_seconds());
+
+        // Allow paying the next period ahead of time, but not further
+        if now + self.period.as_seconds() < start {
+            return Err(InvoiceError::OfferMismatch(
+                format!("Recurrence period {} has not started yet", counter)
+            ));
+        }
+
+        if now > start + window {
+            return Err(InvoiceError::OfferMismatch(
+                format!("Pay window for recurrence period {} has closed", counter)
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Fields of an offer covered by the issuer's signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct OfferContents {
+    description: String,
+    amount_msat: Option<u64>,
+    issuer: Option<String>,
+    quantity_max: Option<u64>,
+    absolute_expiry: Option<u64>,
+    recurrence: Option<Recurrence>,
+    created_at: u64,
+    node_id: String,
+    signing_key: Vec<u8>,
+    security_level: u8,
+    features: u64,
+    paths: Vec<BlindedPath>,
+}
+
+/// A static, reusable payment offer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Offer {
+    /// Signed contents
+    contents: OfferContents,
+
+    /// Dilithium signature of the issuing node
+    signature: Vec<u8>,
+}
+
+impl Offer {
+    /// Create and sign a new offer
+    ///
+    /// `amount_msat` of `None` lets the payer choose the amount (e.g. donations).
+    pub fn new(
+        description: String,
+        amount_msat: Option<u64>,
+        node_id: String,
+        signing_key: &QuantumKeyPair,
+    ) -> Result<Self, InvoiceError> {
+        if amount_msat == Some(0) {
+            return Err(InvoiceError::InvalidAmount(
+                "Offer amount must be greater than zero".to_string()
+            ));
+        }
+
+        let mut offer = Self {
+            contents: OfferContents {
+                description,
+                amount_msat,
+                issuer: None,
+                quantity_max: None,
+                absolute_expiry: None,
+                recurrence: None,
+                created_at: unix_now(),
+                node_id,
+                signing_key: signing_key.public_key.clone(),
+                security_level: signing_key.parameters.security_level,
+                features: 0,
+                paths: Vec::new(),
+            },
+            signature: Vec::new(),
+        };
+
+        offer.sign(signing_key)?;
+
+        Ok(offer)
+    }
+
+    /// Set the issuer name
+    pub fn set_issuer(&mut self, issuer: String, signing_key: &QuantumKeyPair) -> Result<(), InvoiceError> {
+        self.contents.issuer = Some(issuer);
+        self.sign(signing_key)
+    }
+
+    /// Set the absolute expiry time (Unix seconds)
+    pub fn set_absolute_expiry(&mut self, expiry: u64, signing_key: &QuantumKeyPair) -> Result<(), InvoiceError> {
+        self.contents.absolute_expiry = Some(expiry);
+        self.sign(signing_key)
+    }
+
+    /// Set the maximum quantity a single invoice request may ask for
+    pub fn set_quantity_max(&mut self, quantity_max: u64, signing_key: &QuantumKeyPair) -> Result<(), InvoiceError> {
+        if quantity_max == 0 {
+            return Err(InvoiceError::OfferMismatch("Maximum quantity must be at least 1".to_string()));
+        }
+
+        self.contents.quantity_max = Some(quantity_max);
+        self.sign(signing_key)
+    }
+
+    /// Make the offer recurring
+    pub fn set_recurrence(&mut self, recurrence: Recurrence, signing_key: &QuantumKeyPair) -> Result<(), InvoiceError> {
+        if recurrence.period.as_seconds() == 0 {
+            return Err(InvoiceError::OfferMismatch("Recurrence period must not be empty".to_string()));
+        }
+
+        self.contents.recurrence = Some(recurrence);
+        self.sign(signing_key)
+    }
+
+    /// Set the offer feature bits
+    pub fn set_features(&mut self, features: u64, signing_key: &QuantumKeyPair) -> Result<(), InvoiceError> {
+        self.contents.features = features;
+        self.sign(signing_key)
+    }
+    
+    /// Reach the issuer through blinded paths instead of its node ID
+    pub fn set_blinded_paths(&mut self, paths: Vec<BlindedPath>, signing_key: &QuantumKeyPair) -> Result<(), InvoiceError> {
+        if paths.is_empty() {
+            self.contents.features &= !feature_bits::ROUTE_BLINDING;
+        } else {
+            self.contents.features |= feature_bits::ROUTE_BLINDING;
+        }
+        
+        self.contents.paths = paths;
+        self.sign(signing_key)
+    }
+
+    /// Re-sign the offer after its contents changed
+    fn sign(&mut self, signing_key: &QuantumKeyPair) -> Result<(), InvoiceError> {
+        if signing_key.public_key != self.contents.signing_key {
+            return Err(InvoiceError::InvalidSignature(
+                "Signing key does not match the offer's node key".to_string()
+            ));
+        }
+
+        let digest = signing_digest(&self.contents)?;
+        self.signature = dilithium_sign(signing_key, &digest)?;
+
+        Ok(())
+    }
+
+    /// Verify the issuer's signature
+    pub fn verify_signature(&self) -> Result<(), InvoiceError> {
+        let digest = signing_digest(&self.contents)?;
+        dilithium_verify(&self.contents.signing_key, self.contents.security_level, &digest, &self.signature)
+    }
+
+    /// Get the offer ID
+    pub fn id(&self) -> OfferId {
+        // Serializing plain data into a Vec cannot fail
+        OfferId(signing_digest(&self.contents).unwrap_or_default())
+    }
+
+    /// Get description
+    pub fn description(&self) -> &str {
+        &self.contents.description
+    }
+
+    /// Get the fixed amount per item, if any
+    pub fn amount_msat(&self) -> Option<u64> {
+        self.contents.amount_msat
+    }
+
+    /// Get the issuer name
+    pub fn issuer(&self) -> Option<&str> {
+        self.contents.issuer.as_deref()
+    }
+
+    /// Get the maximum quantity per request
+    pub fn quantity_max(&self) -> Option<u64> {
+        self.contents.quantity_max
+    }
+
+    /// Get the absolute expiry time
+    pub fn absolute_expiry(&self) -> Option<u64> {
+        self.contents.absolute_expiry
+    }
+
+    /// Get the recurrence terms
+    pub fn recurrence(&self) -> Option<&Recurrence> {
+        self.contents.recurrence.as_ref()
+    }
+
+    /// Get the creation time, which is also the recurrence base time
+    pub fn created_at(&self) -> u64 {
+        self.contents.created_at
+    }
+
+    /// Get the issuing node ID
+    pub fn node_id(&self) -> &str {
+        &self.contents.node_id
+    }
+
+    /// Get the issuer's Dilithium public key
+    pub fn signing_key(&self) -> &[u8] {
+        &self.contents.signing_key
+    }
+
+    /// Get the features
+    pub fn features(&self) -> u64 {
+        self.contents.features
+    }
+    
+    /// Get the blinded paths to the issuer
+    pub fn blinded_paths(&self) -> &[BlindedPath] {
+        &self.contents.paths
+    }
+
+    /// Check if the offer is expired
+    pub fn is_expired(&self) -> bool {
+        self.contents.absolute_expiry
+            .map(|expiry| unix_now() > expiry)
+            .unwrap_or(false)
+    }
+
+    /// Amount owed for `quantity` items, if the offer has a fixed price
+    pub fn expected_amount_msat(&self, quantity: u64) -> Option<u64> {
+        self.contents.amount_msat.map(|amount| amount.saturating_mul(quantity))
+    }
+
+    /// Check an invoice request against this offer (issuer side)
+    pub fn validate_request(&self, request: &InvoiceRequest) -> Result<(), InvoiceError> {
+        if request.offer_id() != self.id() {
+            return Err(InvoiceError::OfferMismatch("Invoice request is for a different offer".to_string()));
+        }
+
+        request.verify_signature()?;
+        self.check_request_terms(
+            request.amount_msat(),
+            request.quantity(),
+            request.recurrence_counter(),
+            unix_now(),
+        )
+    }
+
+    /// Check amount, quantity, expiry and recurrence terms
+    fn check_request_terms(
+        &self,
+        amount_msat: u64,
+        quantity: Option<u64>,
+        recurrence_counter: Option<u32>,
+        now: u64,
+    ) -> Result<(), InvoiceError> {
+        if self.is_expired() {
+            return Err(InvoiceError::Expired);
+        }
+
+        let quantity = match (quantity, self.contents.quantity_max) {
+            (Some(q), Some(max)) if q == 0 || q > max => {
+                return Err(InvoiceError::OfferMismatch(
+                    format!("Quantity {} outside of 1..={}", q, max)
+                ));
+            },
+            (Some(q), Some(_)) => q,
+            (Some(_), None) => {
+                return Err(InvoiceError::OfferMismatch("Offer does not accept a quantity".to_string()));
+            },
+            (None, Some(_)) => {
+                return Err(InvoiceError::MissingField("quantity".to_string()));
+            },
+            (None, None) => 1,
+        };
+
+        if amount_msat == 0 {
+            return Err(InvoiceError::InvalidAmount("Amount must be greater than zero".to_string()));
+        }
+
+        if let Some(expected) = self.expected_amount_msat(quantity) {
+            if amount_msat < expected {
+                return Err(InvoiceError::InvalidAmount(
+                    format!("Amount {} is below the offer price {}", amount_msat, expected)
+                ));
+            }
+        }
+
+        match (&self.contents.recurrence, recurrence_counter) {
+            (Some(recurrence), Some(counter)) => {
+                recurrence.check_counter(self.contents.created_at, counter, now)?;
+            },
+            (Some(_), None) => {
+                return Err(InvoiceError::MissingField("recurrence_counter".to_string()));
+            },
+            (None, Some(_)) => {
+                return Err(InvoiceError::OfferMismatch("Offer is not recurring".to_string()));
+            },
+            (None, None) => {},
+        }
+
+        Ok(())
+    }
+
+    /// Encode the offer as a bech32 string
+    pub fn encode(&self) -> Result<String, InvoiceError> {
+        encode_bech32(OFFER_HRP, self)
+    }
+
+    /// Decode an offer and verify its signature
+    pub fn decode(encoded: &str) -> Result<Self, InvoiceError> {
+        let offer: Self = decode_bech32(OFFER_HRP, encoded)?;
+        offer.verify_signature()?;
+        Ok(offer)
+    }
+}
+
+impl fmt::Display for Offer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.encode() {
+            Ok(s) => write!(f, "{}", s),
+            Err(_) => write!(f, "Offer({})", self.id()),
+        }
+    }
+}
+
+/// Fields of an invoice request covered by the payer's signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct InvoiceRequestContents {
+    offer_id: OfferId,
+    amount_msat: u64,
+    quantity: Option<u64>,
+    recurrence_counter: Option<u32>,
+    payer_note: Option<String>,
+    payer_metadata: [u8; 32],
+    payer_key: Vec<u8>,
+    payer_security_level: u8,
+    created_at: u64,
+}
+
+/// A payer's request for an invoice against an offer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvoiceRequest {
+    /// Signed contents
+    contents: InvoiceRequestContents,
+
+    /// Dilithium signature of the payer
+    signature: Vec<u8>,
+}
+
+impl InvoiceRequest {
+    /// Build and sign an invoice request for `offer`
+    ///
+    /// `payer_key` should be a fresh key per request so payments against the same
+    /// offer cannot be linked; it is also the key used for payer proofs.
+    pub fn new(
+        offer: &Offer,
+        amount_msat: Option<u64>,
+        quantity: Option<u64>,
+        recurrence_counter: Option<u32>,
+        payer_note: Option<String>,
+        payer_key: &QuantumKeyPair,
+    ) -> Result<Self, InvoiceError> {
+        offer.verify_signature()?;
+
+        let amount_msat = match amount_msat {
+            Some(amount) => amount,
+            None => offer.expected_amount_msat(quantity.unwrap_or(1))
+                .ok_or_else(|| InvoiceError::MissingField("amount_msat".to_string()))?,
+        };
+
+        let now = unix_now();
+        offer.check_request_terms(amount_msat, quantity, recurrence_counter, now)?;
+
+        let mut payer_metadata = [0u8; 32];
+        thread_rng().fill_bytes(&mut payer_metadata);
+
+        let contents = InvoiceRequestContents {
+            offer_id: offer.id(),
+            amount_msat,
+            quantity,
+            recurrence_counter,
+            payer_note,
+            payer_metadata,
+            payer_key: payer_key.public_key.clone(),
+            payer_security_level: payer_key.parameters.security_level,
+            created_at: now,
+        };
+
+        let digest = signing_digest(&contents)?;
+        let signature = dilithium_sign(payer_key, &digest)?;
+
+        Ok(Self { contents, signature })
+    }
+
+    /// Verify the payer's signature
+    pub fn verify_signature(&self) -> Result<(), InvoiceError> {
+        let digest = signing_digest(&self.contents)?;
+        dilithium_verify(&self.contents.payer_key, self.contents.payer_security_level, &digest, &self.signature)
+    }
+
+    /// Identifier of this request (SHA-256 of its signed fields)
+    pub fn id(&self) -> [u8; 32] {
+        signing_digest(&self.contents).unwrap_or_default()
+    }
+
+    /// Get the offer ID
+    pub fn offer_id(&self) -> OfferId {
+        self.contents.offer_id
+    }
+
+    /// Get the requested amount
+    pub fn amount_msat(&self) -> u64 {
+        self.contents.amount_msat
+    }
+
+    /// Get the requested quantity
+    pub fn quantity(&self) -> Option<u64> {
+        self.contents.quantity
+    }
+
+    /// Get the recurrence counter
+    pub fn recurrence_counter(&self) -> Option<u32> {
+        self.contents.recurrence_counter
+    }
+
+    /// Get the payer note
+    pub fn payer_note(&self) -> Option<&str> {
+        self.contents.payer_note.as_deref()
+    }
+
+    /// Get the payer's Dilithium public key
+    pub fn payer_key(&self) -> &[u8] {
+        &self.contents.payer_key
+    }
+
+    /// Encode the request as a bech32 string
+    pub fn encode(&self) -> Result<String, InvoiceError> {
+        encode_bech32(INVOICE_REQUEST_HRP, self)
+    }
+
+    /// Decode a request and verify its signature
+    pub fn decode(encoded: &str) -> Result<Self, InvoiceError> {
+        let request: Self = decode_bech32(INVOICE_REQUEST_HRP, encoded)?;
+        request.verify_signature()?;
+        Ok(request)
+    }
+}
+
+/// Fields of an offer invoice covered by the issuer's signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct OfferInvoiceContents {
+    offer_id: OfferId,
+    invoice_request_id: [u8; 32],
+    payer_key: Vec<u8>,
+    payer_security_level: u8,
+    payment_hash: PaymentHash,
+    amount_msat: u64,
+    quantity: Option<u64>,
+    recurrence_counter: Option<u32>,
+    recurrence_period_start: Option<u64>,
+    created_at: u64,
+    relative_expiry: u32,
+    min_final_cltv_expiry: u32,
+    node_id: String,
+    blinded_paths: Vec<BlindedPath>,
+    signing_key: Vec<u8>,
+    security_level: u8,
+}
+
+/// An invoice issued in response to a specific invoice request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OfferInvoice {
+    /// Signed contents
+    contents: OfferInvoiceContents,
+
+    /// Dilithium signature of the issuing node
+    signature: Vec<u8>,
+}
+
+impl OfferInvoice {
+    /// Issue an invoice for a validated request (issuer side)
+    ///
+    /// `blinded_paths` may be empty, in which case payers route to the node ID.
+    pub fn for_request(
+        offer: &Offer,
+        request: &InvoiceRequest,
+        payment_hash: PaymentHash,
+        relative_expiry: u32,
+        blinded_paths: Vec<BlindedPath>,
+        signing_key: &QuantumKeyPair,
+    ) -> Result<Self, InvoiceError> {
+        offer.validate_request(request)?;
+
+        if signing_key.public_key != offer.signing_key() {
+            return Err(InvoiceError::InvalidSignature(
+                "Signing key does not match the offer's node key".to_string()
+            ));
+        }
+
+        let recurrence_period_start = match (offer.recurrence(), request.recurrence_counter()) {
+            (Some(recurrence), Some(counter)) => Some(recurrence.period_start(offer.created_at(), counter)),
+            _ => None,
+        };
+
+        let contents = OfferInvoiceContents {
+            offer_id: offer.id(),
+            invoice_request_id: request.id(),
+            payer_key: request.contents.payer_key.clone(),
+            payer_security_level: request.contents.payer_security_level,
+            payment_hash,
+            amount_msat: request.amount_msat(),
+            quantity: request.quantity(),
+            recurrence_counter: request.recurrence_counter(),
+            recurrence_period_start,
+            created_at: unix_now(),
+            relative_expiry,
+            min_final_cltv_expiry: 40,
+            node_id: offer.node_id().to_string(),
+            blinded_paths,
+            signing_key: signing_key.public_key.clone(),
+            security_level: signing_key.parameters.security_level,
+        };
+
+        let digest = signing_digest(&contents)?;
+        let signature = dilithium_sign(signing_key, &digest)?;
+
+        Ok(Self { contents, signature })
+    }
+
+    /// Verify the issuer's signature
+    pub fn verify_signature(&self) -> Result<(), InvoiceError> {
+        let digest = signing_digest(&self.contents)?;
+        dilithium_verify(&self.contents.signing_key, self.contents.security_level, &digest, &self.signature)
+    }
+
+    /// Check that this invoice answers `request` for `offer` (payer side)
+    pub fn verify_for_request(&self, offer: &Offer, request: &InvoiceRequest) -> Result<(), InvoiceError> {
+        self.verify_signature()?;
+
+        if self.contents.signing_key != offer.signing_key() {
+            return Err(InvoiceError::InvalidSignature("Invoice not signed by the offer's issuer".to_string()));
+        }
+
+        if self.contents.offer_id != offer.id() || self.contents.invoice_request_id != request.id() {
+            return Err(InvoiceError::OfferMismatch("Invoice does not answer this invoice request".to_string()));
+        }
+
+        if self.contents.amount_msat != request.amount_msat() {
+            return Err(InvoiceError::InvalidAmount(
+                format!("Invoice amount {} differs from requested {}", self.contents.amount_msat, request.amount_msat())
+            ));
+        }
+
+        if self.is_expired() {
+            return Err(InvoiceError::Expired);
+        }
+
+        Ok(())
+    }
+
+    /// Get the offer ID
+    pub fn offer_id(&self) -> OfferId {
+        self.contents.offer_id
+    }
+
+    /// Get the ID of the request this invoice answers
+    pub fn invoice_request_id(&self) -> &[u8; 32] {
+        &self.contents.invoice_request_id
+    }
+
+    /// Get the payer's Dilithium public key
+    pub fn payer_key(&self) -> &[u8] {
+        &self.contents.payer_key
+    }
+
+    /// Get payment hash
+    pub fn payment_hash(&self) -> PaymentHash {
+        self.contents.payment_hash
+    }
+
+    /// Get amount in millisatoshis
+    pub fn amount_msat(&self) -> u64 {
+        self.contents.amount_msat
+    }
+
+    /// Get the recurrence counter
+    pub fn recurrence_counter(&self) -> Option<u32> {
+        self.contents.recurrence_counter
+    }
+
+    /// Get the start of the paid recurrence period
+    pub fn recurrence_period_start(&self) -> Option<u64> {
+        self.contents.recurrence_period_start
+    }
+
+    /// Get creation timestamp
+    pub fn created_at(&self) -> u64 {
+        self.contents.created_at
+    }
+
+    /// Get the relative expiry in seconds
+    pub fn relative_expiry(&self) -> u32 {
+        self.contents.relative_expiry
+    }
+
+    /// Get min final CLTV expiry delta
+    pub fn min_final_cltv_expiry(&self) -> u32 {
+        self.contents.min_final_cltv_expiry
+    }
+
+    /// Get destination (node ID)
+    pub fn destination(&self) -> &str {
+        &self.contents.node_id
+    }
+    
+    /// Get the blinded paths to pay through
+    pub fn blinded_paths(&self) -> &[BlindedPath] {
+        &self.contents.blinded_paths
+    }
+
+    /// Check if the invoice is expired
+    pub fn is_expired(&self) -> bool {
+        unix_now() > self.contents.created_at + self.contents.relative_expiry as u64
+    }
+
+    /// Encode the invoice as a bech32 string
+    pub fn encode(&self) -> Result<String, InvoiceError> {
+        encode_bech32(OFFER_INVOICE_HRP, self)
+    }
+
+    /// Decode an invoice and verify its signature
+    pub fn decode(encoded: &str) -> Result<Self, InvoiceError> {
+        let invoice: Self = decode_bech32(OFFER_INVOICE_HRP, encoded)?;
+        invoice.verify_signature()?;
+        Ok(invoice)
+    }
+}
+
+/// Proof that a payer paid an offer invoice
+///
+/// Combines the issuer-signed invoice, the payment preimage and a signature by
+/// the payer key named in the invoice, so a third party can check who paid what.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayerProof {
+    /// The paid invoice
+    invoice: OfferInvoice,
+
+    /// Preimage revealed by the payee on settlement
+    preimage: PaymentPreimage,
+
+    /// Optional note from the payer
+    note: Option<String>,
+
+    /// Payer signature over the invoice, preimage and note
+    payer_signature: Vec<u8>,
+}
+
+impl PayerProof {
+    /// Create a payer proof (payer side)
+    pub fn new(
+        invoice: OfferInvoice,
+        preimage: PaymentPreimage,
+        note: Option<String>,
+        payer_key: &QuantumKeyPair,
+    ) -> Result<Self, InvoiceError> {
+        if preimage.payment_hash() != invoice.payment_hash() {
+            return Err(InvoiceError::InvalidHash("Preimage does not match the invoice".to_string()));
+        }
+
+        if payer_key.public_key != invoice.payer_key() {
+            return Err(InvoiceError::InvalidSignature("Key is not the invoice's payer key".to_string()));
+        }
+
+        let digest = signing_digest(&(&invoice, &preimage, &note))?;
+        let payer_signature = dilithium_sign(payer_key, &digest)?;
+
+        Ok(Self {
+            invoice,
+            preimage,
+            note,
+            payer_signature,
+        })
+    }
+
+    /// Verify the issuer signature, the preimage and the payer signature
+    pub fn verify(&self) -> Result<(), InvoiceError> {
+        self.invoice.verify_signature()?;
+
+        if self.preimage.payment_hash() != self.invoice.payment_hash() {
+            return Err(InvoiceError::InvalidHash("Preimage does not match the invoice".to_string()));
+        }
+
+        let digest = signing_digest(&(&self.invoice, &self.preimage, &self.note))?;
+        dilithium_verify(
+            self.invoice.payer_key(),
+            self.invoice.contents.payer_security_level,
+            &digest,
+            &self.payer_signature,
+        )
+    }
+
+    /// Get the paid invoice
+    pub fn invoice(&self) -> &OfferInvoice {
+        &self.invoice
+    }
+
+    /// Get the payment preimage
+    pub fn preimage(&self) -> PaymentPreimage {
+        self.preimage
+    }
+
+    /// Get the payer note
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    /// Encode the proof as a bech32 string
+    pub fn encode(&self) -> Result<String, InvoiceError> {
+        encode_bech32(PAYER_PROOF_HRP, self)
+    }
+
+    /// Decode a proof and verify it
+    pub fn decode(encoded: &str) -> Result<Self, InvoiceError> {
+        let proof: Self = decode_bech32(PAYER_PROOF_HRP, encoded)?;
+        proof.verify()?;
+        Ok(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn dilithium_key() -> QuantumKeyPair {
+        QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap()
+    }
+    
+    #[test]
+    fn test_offer_encode_decode_roundtrip() {
+        let node_key = dilithium_key();
+        let mut offer = Offer::new("Coffee".to_string(), Some(5_000), "node".to_string(), &node_key).unwrap();
+        offer.set_issuer("Cafe".to_string(), &node_key).unwrap();
+        
+        let encoded = offer.encode().unwrap();
+        assert!(encoded.starts_with("lno1"));
+        
+        let decoded = Offer::decode(&encoded).unwrap();
+        assert_eq!(decoded, offer);
+        assert_eq!(decoded.id(), offer.id());
+        assert_eq!(decoded.issuer(), Some("Cafe"));
+        
+        // Wrong prefix is rejected
+        assert!(InvoiceRequest::decode(&encoded).is_err());
+    }
+    
+    #[test]
+    fn test_offer_carries_blinded_paths() {
+        use crate::lightning::onion::SystemKem;
+        
+        let node_key = dilithium_key();
+        let mut offer = Offer::new("Coffee".to_string(), Some(5_000), "node".to_string(), &node_key).unwrap();
+        
+        let path = BlindedPath::new(&[], "node", &[9u8; 64], [0u8; 32], 800_000, &SystemKem).unwrap();
+        offer.set_blinded_paths(vec![path.clone()], &node_key).unwrap();
+        assert_ne!(offer.features() & feature_bits::ROUTE_BLINDING, 0);
+        
+        let decoded = Offer::decode(&offer.encode().unwrap()).unwrap();
+        assert_eq!(decoded.blinded_paths(), &[path]);
+    }
+    
+    #[test]
+    fn test_tampered_offer_rejected() {
+        let node_key = dilithium_key();
+        let mut offer = Offer::new("Coffee".to_string(), Some(5_000), "node".to_string(), &node_key).unwrap();
+        offer.contents.amount_msat = Some(1);
+        
+        assert!(offer.verify_signature().is_err());
+        assert!(Offer::decode(&offer.encode().unwrap()).is_err());
+    }
+    
+    #[test]
+    fn test_invoice_request_validation() {
+        let node_key = dilithium_key();
+        let payer_key = dilithium_key();
+        let mut offer = Offer::new("Widgets".to_string(), Some(1_000), "node".to_string(), &node_key).unwrap();
+        offer.set_quantity_max(5, &node_key).unwrap();
+        
+        // Amount defaults to price * quantity
+        let request = InvoiceRequest::new(&offer, None, Some(3), None, None, &payer_key).unwrap();
+        assert_eq!(request.amount_msat(), 3_000);
+        offer.validate_request(&request).unwrap();
+        
+        // Underpaying, exceeding the quantity or omitting it all fail
+        assert!(InvoiceRequest::new(&offer, Some(2_000), Some(3), None, None, &payer_key).is_err());
+        assert!(InvoiceRequest::new(&offer, None, Some(6), None, None, &payer_key).is_err());
+        assert!(InvoiceRequest::new(&offer, None, None, None, None, &payer_key).is_err());
+        
+        // A request for another offer is rejected
+        let other = Offer::new("Other".to_string(), Some(1_000), "node".to_string(), &node_key).unwrap();
+        assert!(other.validate_request(&request).is_err());
+    }
+    
+    #[test]
+    fn test_recurrence_counter_checks() {
+        let recurrence = Recurrence {
+            period: RecurrencePeriod::Days(1),
+            limit: Some(3),
+            paywindow_seconds: Some(3_600),
+        };
+        let base = 1_000_000;
+        
+        assert!(recurrence.check_counter(base, 0, base + 10).is_ok());
+        // Pay window for period 0 has closed
+        assert!(recurrence.check_counter(base, 0, base + 7_200).is_err());
+        // Next period can be paid ahead of time
+        assert!(recurrence.check_counter(base, 1, base + 10).is_ok());
+        // Too far ahead, and beyond the limit
+        assert!(recurrence.check_counter(base, 2, base + 10).is_err());
+        assert!(recurrence.check_counter(base, 3, base + 3 * 86_400).is_err());
+    }
+    
+    #[test]
+    fn test_offer_invoice_and_payer_proof() {
+        let node_key = dilithium_key();
+        let payer_key = dilithium_key();
+        let mut offer = Offer::new("Subscription".to_string(), Some(10_000), "node".to_string(), &node_key).unwrap();
+        offer.set_recurrence(Recurrence {
+            period: RecurrencePeriod::Months(1),
+            limit: None,
+            paywindow_seconds: None,
+        }, &node_key).unwrap();
+        
+        let request = InvoiceRequest::new(&offer, None, None, Some(0), Some("hi".to_string()), &payer_key).unwrap();
+        let request = InvoiceRequest::decode(&request.encode().unwrap()).unwrap();
+        
+        let preimage = PaymentPreimage::new_random();
+        let invoice = OfferInvoice::for_request(
+            &offer, &request, preimage.payment_hash(), DEFAULT_OFFER_INVOICE_EXPIRY, Vec::new(), &node_key,
+        ).unwrap();
+        let invoice = OfferInvoice::decode(&invoice.encode().unwrap()).unwrap();
+        
+        invoice.verify_for_request(&offer, &request).unwrap();
+        assert_eq!(invoice.amount_msat(), 10_000);
+        assert_eq!(invoice.recurrence_period_start(), Some(offer.created_at()));
+        
+        // Only the payer key from the request can prove payment
+        assert!(PayerProof::new(invoice.clone(), preimage, None, &dilithium_key()).is_err());
+        assert!(PayerProof::new(invoice.clone(), PaymentPreimage::new_random(), None, &payer_key).is_err());
+        
+        let proof = PayerProof::new(invoice, preimage, Some("paid".to_string()), &payer_key).unwrap();
+        let decoded = PayerProof::decode(&proof.encode().unwrap()).unwrap();
+        assert_eq!(decoded.note(), Some("paid"));
+    }
+    
+    fn held(htlc_id: u64, amount_msat: u64, cltv_expiry: u32) -> HeldHtlc {
+        HeldHtlc {
+            channel_id: ChannelId::from_bytes([7u8; 32]),
+            htlc_id,
+            amount_msat,
+            cltv_expiry,
+            accepted_at: 0,
+        }
+    }
+    
+    #[test]
+    fn test_hold_invoice_accept_and_settle() {
+        let preimage = PaymentPreimage::new_random();
+        let payment_hash = preimage.payment_hash();
+        let mut db = InvoiceDatabase::new();
+        db.add_invoice(EnhancedInvoice::new_hold(payment_hash, 10_000, "escrow".to_string(), 3600, 0).unwrap()).unwrap();
+        
+        // Hold invoices never settle on their own
+        assert!(db.mark_invoice_paid(&payment_hash).is_err());
+        
+        // Partial HTLCs keep the invoice open until the amount is covered
+        assert_eq!(db.accept_hold_htlc(&payment_hash, held(1, 4_000, 200), 100).unwrap(), InvoiceState::Open);
+        assert!(db.accept_hold_htlc(&payment_hash, held(1, 4_000, 200), 100).is_err());
+        assert_eq!(db.accept_hold_htlc(&payment_hash, held(2, 6_000, 150), 100).unwrap(), InvoiceState::Accepted);
+        
+        let invoice = db.get_invoice(&payment_hash).unwrap();
+        assert_eq!(invoice.amount_received_msat(), 10_000);
+        assert_eq!(invoice.cancel_height(), Some(150 - HOLD_INVOICE_CANCEL_DELTA));
+        
+        // Only the matching preimage settles it
+        assert!(db.settle_hold_invoice(&PaymentPreimage::new_random()).is_err());
+        let htlcs = db.settle_hold_invoice(&preimage).unwrap();
+        assert_eq!(htlcs.len(), 2);
+        assert!(db.is_invoice_paid(&payment_hash));
+        assert!(db.cancel_hold_invoice(&payment_hash).is_err());
+    }
+    
+    #[test]
+    fn test_hold_invoice_cancel_and_expiry() {
+        let mut db = InvoiceDatabase::new();
+        let canceled = PaymentPreimage::new_random().payment_hash();
+        let expiring = PaymentPreimage::new_random().payment_hash();
+        db.add_invoice(EnhancedInvoice::new_hold(canceled, 1_000, "a".to_string(), 3600, 0).unwrap()).unwrap();
+        db.add_invoice(EnhancedInvoice::new_hold(expiring, 1_000, "b".to_string(), 3600, 0).unwrap()).unwrap();
+        
+        // HTLCs too close to expiry are refused
+        assert!(db.accept_hold_htlc(&canceled, held(1, 1_000, 105), 100).is_err());
+        
+        db.accept_hold_htlc(&canceled, held(1, 1_000, 300), 100).unwrap();
+        assert_eq!(db.cancel_hold_invoice(&canceled).unwrap().len(), 1);
+        assert_eq!(db.get_invoice(&canceled).unwrap().state(), &InvoiceState::Canceled);
+        
+        db.accept_hold_htlc(&expiring, held(2, 1_000, 130), 100).unwrap();
+        assert!(db.cancel_expiring_hold_invoices(119).is_empty());
+        
+        let auto_canceled = db.cancel_expiring_hold_invoices(120);
+        assert_eq!(auto_canceled.len(), 1);
+        assert_eq!(auto_canceled[0].0, expiring);
+        assert_eq!(auto_canceled[0].1[0].htlc_id, 2);
+        assert_eq!(db.get_invoice(&expiring).unwrap().state(), &InvoiceState::Canceled);
+    }
+}
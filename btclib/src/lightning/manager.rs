@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock, Mutex};
 use std::time::{Duration, SystemTime, UNIX_EPOCH, Instant};
 use tokio::sync::mpsc;
@@ -8,7 +8,8 @@ use thiserror::Error;
 
 use crate::lightning::{
     Channel, ChannelId, ChannelState, ChannelConfig, ChannelError,
-    Invoice, InvoiceError,
+    Invoice, InvoiceError, EnhancedInvoice, InvoiceDatabase,
+    Offer, OfferId, InvoiceRequest, OfferInvoice, PayerProof, Recurrence,
     PaymentHash, PaymentPreimage, Payment, PaymentStatus, PaymentError,
     Router, RoutingError,
     LightningWallet, WalletError,
@@ -20,7 +21,29 @@ use crate::lightning::{
 use super::{LightningConfig, LightningNetworkError};
 use crate::lightning::payment::{RouteHop, Htlc, HtlcState};
 use crate::types::transaction::Transaction;
-use crate::crypto::quantum::QuantumScheme;
+use crate::lightning::onion::{
+    BlindedForwardNode, BlindedHop, BlindedHopData, BlindedPath, HopKem, PaymentConstraints, PaymentRelay, PerHopPayload,
+    SystemKem,
+};
+use crate::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+use crate::crypto::kem::KemKeyPair;
+use crate::lightning::invoice::{HeldHtlc, InvoiceState};
+use crate::lightning::interactive_tx::{DualFundingSession, FundingContribution, NegotiatedFunding};
+use crate::lightning::wire::{Message, MessageFactory, MessageType, NodeAnnouncementPayload, RevokeAndAckPayload};
+use crate::lightning::router::{NodeEnvironmentalData, NodeId, RouterPreferences};
+use crate::lightning::tower::{SessionPolicy, TowerClient, TowerClientHandle};
+use crate::lightning::anchors::DEFAULT_FORCE_CLOSE_FEERATE;
+use crate::lightning::channel::{CommitmentFormat, PublicKey as ChannelPublicKey};
+use crate::lightning::forwarding::{ForwardingEvent, ForwardingLedger, ForwardingReport, ForwardStatus};
+use crate::lightning::offer_store::{OfferStore, OfferStoreError};
+use crate::lightning::trampoline::{build_trampoline_payment, TrampolineAction, TrampolineFeePolicy, TrampolineHop, TrampolineOnion, TrampolinePayment, TrampolineRecipient, TrampolineRelay};
+use crate::lightning::wire::FEATURE_TRAMPOLINE_ROUTING;
+use crate::lightning::onion_message::{Destination, MessageHop, MessagePath, OnionMessageAction, OnionMessageContents, OnionMessagePacket, OnionMessenger, RateLimitConfig, TLV_INVOICE, TLV_INVOICE_ERROR, TLV_INVOICE_REQUEST};
+use crate::script::ScriptBuilder;
+use std::net::SocketAddr;
+
+/// Placeholder node ID until nodes are identified by their public key
+const LOCAL_NODE_ID: &str = "02abcdef1234567890abcdef1234567890abcdef1234567890abcdef1234567890ab";
 
 /// Lightning Network Manager - Central coordinator for Lightning Network operations
 pub struct LightningManager {
@@ -68,6 +91,91 @@ pub struct LightningManager {
     
     /// Invoice index counter  
     invoice_index: Arc<std::sync::atomic::AtomicU64>,
+    
+    /// Dilithium key used to sign offers and offer invoices
+    node_key: Arc<RwLock<QuantumKeyPair>>,
+    
+    /// KEM key used to unblind hops of blinded paths through this node
+    kem_keypair: KemKeyPair,
+    
+    /// KEM blinding and unblinding the hops of our blinded paths
+    hop_kem: Box<dyn HopKem>,
+    
+    /// Path IDs of the blinded paths we handed out to payers
+    blinded_path_ids: Arc<RwLock<HashSet<[u8; 32]>>>,
+    
+    /// Offers published by this node
+    offers: Arc<RwLock<HashMap<OfferId, Offer>>>,
+    
+    /// Invoice requests sent by this node, with the per-request payer key
+    outgoing_invoice_requests: Arc<RwLock<HashMap<[u8; 32], OutgoingRequest>>>,
+    
+    /// Offer invoices received by this node, keyed by payment hash
+    offer_invoices: Arc<RwLock<HashMap<PaymentHash, OfferInvoice>>>,
+    
+    /// Invoices issued for recurring offers, keyed by offer, payer key and period
+    recurrence_invoices: Arc<RwLock<HashMap<RecurrenceKey, OfferInvoice>>>,
+    
+    /// Persistent node key, offers and invoice requests
+    offer_store: Arc<RwLock<Option<OfferStore>>>,
+    
+    /// Dual-funded channels whose funding has not confirmed yet
+    dual_funding_sessions: Arc<RwLock<HashMap<ChannelId, DualFundingSession>>>,
+    
+    /// What we contribute when a peer opens a dual-funded channel to us
+    dual_funding_contribution: Arc<RwLock<Option<FundingContribution>>>,
+    
+    /// Hold invoices, settled or canceled by an external call
+    hold_invoices: Arc<RwLock<InvoiceDatabase>>,
+    
+    /// Client backing up revoked commitments to a remote watchtower
+    tower_client: Arc<RwLock<Option<TowerClientHandle>>>,
+    
+    /// Latest counterparty commitment per channel, backed up once revoked
+    revocable_commitments: Arc<RwLock<HashMap<ChannelId, Transaction>>>,
+    
+    /// Whether new channels use anchor-output commitments
+    anchor_outputs: Arc<std::sync::atomic::AtomicBool>,
+    
+    /// Routing policy we advertise for each of our channels
+    routing_policies: Arc<RwLock<HashMap<ChannelId, RoutingPolicy>>>,
+    
+    /// Routing policy each peer advertises for its side of our channels
+    peer_routing_policies: Arc<RwLock<HashMap<ChannelId, RoutingPolicy>>>,
+    
+    /// Short channel ID of each channel whose funding is buried in a block
+    short_channel_ids: Arc<RwLock<HashMap<ChannelId, u64>>>,
+    
+    /// HTLCs we forwarded that have not resolved yet
+    pending_forwards: Arc<RwLock<HashMap<PaymentHash, PendingForward>>>,
+    
+    /// Persistent log of resolved forwards
+    forwarding_ledger: Arc<RwLock<Option<ForwardingLedger>>>,
+    
+    /// Creates, relays and receives onion messages
+    onion_messenger: Arc<Mutex<OnionMessenger>>,
+    
+    /// Relays trampoline payments when trampoline routing is enabled
+    trampoline_relay: Arc<RwLock<Option<TrampolineRelay>>>,
+}
+
+/// An invoice request we sent, with the offer it is for and its payer key
+type OutgoingRequest = (Offer, InvoiceRequest, QuantumKeyPair);
+
+/// Offer, payer key and period counter an invoice for a recurring offer was issued for
+type RecurrenceKey = (OfferId, Vec<u8>, u32);
+
+/// A forwarded HTLC pair waiting for the next hop to settle or fail
+#[derive(Debug, Clone)]
+struct PendingForward {
+    incoming_channel: ChannelId,
+    incoming_htlc: u64,
+    outgoing_channel: ChannelId,
+    outgoing_htlc: u64,
+    incoming_peer: String,
+    outgoing_peer: String,
+    amount_in_msat: u64,
+    amount_out_msat: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -77,6 +185,7 @@ pub struct PeerInfo {
     pub connected: bool,
     pub last_seen: SystemTime,
     pub features: Vec<String>,
+    pub kem_public_key: Option<Vec<u8>>,
 }
 
 #[derive(Debug, Clone)]
@@ -116,6 +225,14 @@ pub enum ManagerError {
     RouterError(String),
     #[error("Watchtower error: {0}")]
     WatchtowerError(String),
+    #[error("Forwarding ledger error: {0}")]
+    LedgerError(String),
+    #[error("Onion message error: {0}")]
+    OnionMessageError(String),
+    #[error("Trampoline error: {0}")]
+    TrampolineError(String),
+    #[error("Offer store error: {0}")]
+    OfferStoreError(String),
 }
 
 // Response types for API compatibility
@@ -450,6 +567,18 @@ impl LightningManager {
             None
         };
         
+        // Dilithium node key for offers
+        let node_key = QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium))
+            .map_err(|e| ManagerError::QuantumSecurityError(e.to_string()))?;
+        let kem_keypair = KemKeyPair::generate()
+            .map_err(|e| ManagerError::QuantumSecurityError(e.to_string()))?;
+        let onion_messenger = OnionMessenger::new(
+            LOCAL_NODE_ID.to_string(),
+            kem_keypair.clone(),
+            Box::new(SystemKem),
+            RateLimitConfig::default(),
+        );
+        
         let manager = Self {
             config,
             channels: Arc::new(RwLock::new(HashMap::new())),
@@ -466,6 +595,28 @@ impl LightningManager {
             is_running: Arc::new(std::sync::atomic::AtomicBool::new(false)),
             payment_index: Arc::new(std::sync::atomic::AtomicU64::new(0)),
             invoice_index: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            node_key: Arc::new(RwLock::new(node_key)),
+            kem_keypair,
+            hop_kem: Box::new(SystemKem),
+            blinded_path_ids: Arc::new(RwLock::new(HashSet::new())),
+            offers: Arc::new(RwLock::new(HashMap::new())),
+            outgoing_invoice_requests: Arc::new(RwLock::new(HashMap::new())),
+            offer_invoices: Arc::new(RwLock::new(HashMap::new())),
+            recurrence_invoices: Arc::new(RwLock::new(HashMap::new())),
+            offer_store: Arc::new(RwLock::new(None)),
+            dual_funding_sessions: Arc::new(RwLock::new(HashMap::new())),
+            dual_funding_contribution: Arc::new(RwLock::new(None)),
+            hold_invoices: Arc::new(RwLock::new(InvoiceDatabase::new())),
+            tower_client: Arc::new(RwLock::new(None)),
+            revocable_commitments: Arc::new(RwLock::new(HashMap::new())),
+            anchor_outputs: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            routing_policies: Arc::new(RwLock::new(HashMap::new())),
+            peer_routing_policies: Arc::new(RwLock::new(HashMap::new())),
+            short_channel_ids: Arc::new(RwLock::new(HashMap::new())),
+            pending_forwards: Arc::new(RwLock::new(HashMap::new())),
+            forwarding_ledger: Arc::new(RwLock::new(None)),
+            onion_messenger: Arc::new(Mutex::new(onion_messenger)),
+            trampoline_relay: Arc::new(RwLock::new(None)),
         };
         
         Ok((manager, event_receiver))
@@ -666,9 +817,10 @@ impl LightningManager {
         if let Some(min_htlc) = min_htlc_msat {
             config.min_htlc_value_msat = min_htlc;
         }
+        config.anchor_outputs = self.anchor_outputs.load(std::sync::atomic::Ordering::SeqCst);
         
         // Create channel
-        let channel = Channel::open(
+        let mut channel = Channel::open(
             node_id.to_string(),
             local_funding_amount,
             push_amount,
@@ -676,6 +828,13 @@ impl LightningManager {
             self.config.quantum_scheme.clone(),
         ).map_err(|e| ManagerError::ChannelError(e.to_string()))?;
         
+        // Our anchor pays to the wallet key so force-closes can be fee bumped
+        if channel.commitment_format == CommitmentFormat::AnchorOutputs {
+            let anchor_pubkey = self.wallet.lock().unwrap().onchain_pubkey()
+                .map_err(|e| ManagerError::WalletError(e.to_string()))?;
+            channel.local_node_id = ChannelPublicKey::from_bytes(anchor_pubkey);
+        }
+        
         // Wrap in AtomicChannel for thread safety
         let atomic_channel = Arc::new(AtomicChannel::new(channel));
         
@@ -721,12 +880,26 @@ impl LightningManager {
             }
             
             // Create closing transaction using the underlying channel
+            let mut fee_bump = None;
             // This is a simplified approach - in production, we'd handle this through atomic operations
             let closing_tx = {
                 let mut channel = atomic_channel.channel.lock()
                     .map_err(|e| ManagerError::ChannelError(format!("Failed to lock channel: {}", e)))?;
                 
-                if force {
+                if force && channel.commitment_format == CommitmentFormat::AnchorOutputs {
+                    let mut wallet = self.wallet.lock().unwrap();
+                    match channel.force_close_with_fee_bump(&mut wallet, DEFAULT_FORCE_CLOSE_FEERATE) {
+                        Ok((commitment, bump)) => {
+                            fee_bump = Some(bump.child_tx);
+                            commitment
+                        }
+                        Err(e) => {
+                            warn!("Could not bump force-close fee, broadcasting commitment alone: {}", e);
+                            channel.force_close()
+                                .map_err(|e| ManagerError::ChannelError(e.to_string()))?
+                        }
+                    }
+                } else if force {
                     channel.force_close()
                         .map_err(|e| ManagerError::ChannelError(e.to_string()))?
                 } else {
@@ -735,8 +908,11 @@ impl LightningManager {
                 }
             };
             
-            // Broadcast closing transaction
+            // Broadcast closing transaction, followed by its fee bump
             self.broadcast_transaction(&closing_tx).await?;
+            if let Some(child_tx) = fee_bump {
+                self.broadcast_transaction(&child_tx).await?;
+            }
             
             // Send event
             let _ = self.event_sender.send(LightningEvent::ChannelClosed(channel_id));
@@ -747,6 +923,357 @@ impl LightningManager {
         }
     }
     
+    /// Open a dual-funded channel
+    ///
+    /// Returns the open_channel2 message for the peer. Both peers then
+    /// exchange messages through `handle_dual_funding_message` until the
+    /// funding transaction is built and signed.
+    pub fn open_dual_funded_channel(
+        &self,
+        node_id: &str,
+        contribution: FundingContribution,
+        feerate_per_kw: u32,
+    ) -> Result<Message, ManagerError> {
+        info!("Opening dual-funded channel to {} with funding {}", node_id, contribution.funding_novas);
+        
+        if contribution.funding_novas < 20000 {
+            return Err(ManagerError::ConfigError("Minimum channel size is 20,000 satoshis".to_string()));
+        }
+        
+        let available_balance = self.wallet.lock().unwrap().get_balance();
+        if available_balance < contribution.funding_novas {
+            return Err(ManagerError::InsufficientBalance {
+                required: contribution.funding_novas,
+                available: available_balance,
+            });
+        }
+        
+        let (session, message) = DualFundingSession::initiate(
+            contribution,
+            self.node_key(),
+            feerate_per_kw,
+            self.get_current_height() as u32,
+            &self.message_factory(),
+        ).map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+        
+        self.dual_funding_sessions.write().unwrap()
+            .insert(session.channel_id().clone(), session);
+        
+        Ok(message)
+    }
+    
+    /// Set what we contribute to dual-funded channels opened to us
+    pub fn set_dual_funding_contribution(&self, contribution: Option<FundingContribution>) {
+        *self.dual_funding_contribution.write().unwrap() = contribution;
+    }
+    
+    /// Open new channels with anchor-output commitments
+    pub fn set_anchor_outputs(&self, enabled: bool) {
+        self.anchor_outputs.store(enabled, std::sync::atomic::Ordering::SeqCst);
+    }
+    
+    /// Set the routing policy advertised for one of our channels
+    pub fn set_routing_policy(&self, channel_id: &str, policy: RoutingPolicy) -> Result<(), ManagerError> {
+        let channel_id = ChannelId::from_hex(channel_id)
+            .map_err(|_| ManagerError::InvalidPaymentRequest("Invalid channel ID".to_string()))?;
+        
+        if !self.channels.read().unwrap().contains_key(&channel_id) {
+            return Err(ManagerError::ChannelNotFound(channel_id.to_hex()));
+        }
+        
+        self.routing_policies.write().unwrap().insert(channel_id, policy);
+        Ok(())
+    }
+    
+    /// Get the routing policy advertised for one of our channels
+    pub fn get_routing_policy(&self, channel_id: &str) -> Result<Option<RoutingPolicy>, ManagerError> {
+        let channel_id = ChannelId::from_hex(channel_id)
+            .map_err(|_| ManagerError::InvalidPaymentRequest("Invalid channel ID".to_string()))?;
+        
+        Ok(self.routing_policies.read().unwrap().get(&channel_id).cloned())
+    }
+    
+    /// Assign the short channel ID of a channel whose funding transaction is
+    /// at `tx_index` in the block at `block_height`
+    pub fn set_short_channel_id(&self, channel_id: &str, block_height: u32, tx_index: u32) -> Result<u64, ManagerError> {
+        let channel_id = ChannelId::from_hex(channel_id)
+            .map_err(|_| ManagerError::InvalidPaymentRequest("Invalid channel ID".to_string()))?;
+        let channel = self.atomic_channel(&channel_id)
+            .ok_or_else(|| ManagerError::ChannelNotFound(channel_id.to_hex()))?;
+        let output_index = channel.channel.lock().unwrap().funding_outpoint.as_ref()
+            .map(|outpoint| outpoint.vout)
+            .ok_or_else(|| ManagerError::ChannelError(format!("Channel {} has no funding output", channel_id)))?;
+        
+        if block_height >= 1 << 24 || tx_index >= 1 << 24 || output_index >= 1 << 16 {
+            return Err(ManagerError::ChannelError(format!("Funding of channel {} has no short channel ID", channel_id)));
+        }
+        let short_channel_id = ((block_height as u64) << 40) | ((tx_index as u64) << 16) | output_index as u64;
+        
+        self.short_channel_ids.write().unwrap().insert(channel_id, short_channel_id);
+        Ok(short_channel_id)
+    }
+    
+    /// Short channel ID of one of our channels, once its funding is buried
+    pub fn get_short_channel_id(&self, channel_id: &str) -> Result<Option<u64>, ManagerError> {
+        let channel_id = ChannelId::from_hex(channel_id)
+            .map_err(|_| ManagerError::InvalidPaymentRequest("Invalid channel ID".to_string()))?;
+        
+        Ok(self.short_channel_ids.read().unwrap().get(&channel_id).copied())
+    }
+    
+    /// Record the routing policy a peer advertises for its side of one of our channels
+    ///
+    /// Updates for other channels, for our own side and older than the policy
+    /// we hold are ignored.
+    pub fn handle_channel_update(&self, update: &ChannelUpdate) -> Result<(), ManagerError> {
+        let channel_id = match self.channel_by_short_id(update.chan_id) {
+            Some(channel_id) => channel_id,
+            None => return Ok(()),
+        };
+        let channel = self.atomic_channel(&channel_id)
+            .ok_or_else(|| ManagerError::ChannelNotFound(channel_id.to_hex()))?;
+        
+        // Bit 0 of the channel flags is set on updates from the node with the greater ID
+        let peer_is_node_1 = {
+            let channel = channel.channel.lock().unwrap();
+            channel.remote_node_id.serialize() < channel.local_node_id.serialize()
+        };
+        if (update.channel_flags & 1 == 0) != peer_is_node_1 {
+            debug!("Ignoring update for our side of channel {}", channel_id);
+            return Ok(());
+        }
+        
+        let mut policies = self.peer_routing_policies.write().unwrap();
+        if policies.get(&channel_id).is_some_and(|policy| policy.last_update >= update.timestamp) {
+            return Ok(());
+        }
+        policies.insert(channel_id, RoutingPolicy {
+            time_lock_delta: update.time_lock_delta,
+            min_htlc: update.htlc_minimum_msat,
+            fee_base_msat: update.base_fee as u64,
+            fee_rate_milli_msat: update.fee_rate as u64,
+            disabled: update.channel_flags & 2 != 0,
+            max_htlc_msat: update.htlc_maximum_msat,
+            last_update: update.timestamp,
+        });
+        Ok(())
+    }
+    
+    /// Our channel with `short_channel_id`
+    fn channel_by_short_id(&self, short_channel_id: u64) -> Option<ChannelId> {
+        self.short_channel_ids.read().unwrap().iter()
+            .find(|(_, id)| **id == short_channel_id)
+            .map(|(channel_id, _)| channel_id.clone())
+    }
+    
+    /// Back up revoked commitments to the watchtower at `tower_addr`
+    ///
+    /// Must be called from within a tokio runtime. Swept funds are paid to
+    /// the node key.
+    pub fn add_watchtower(&self, tower_addr: SocketAddr, policy: SessionPolicy) {
+        let node_key = self.node_key();
+        let sweep_script = ScriptBuilder::pay_to_pubkey_hash(&ScriptBuilder::hash_pubkey(&node_key.public_key));
+        let client = TowerClient::new(tower_addr, node_key, policy, sweep_script);
+        *self.tower_client.write().unwrap() = Some(client.spawn());
+        info!("Backing up revoked commitments to watchtower {}", tower_addr);
+    }
+    
+    /// Handle a channel message from a peer, returning our replies
+    ///
+    /// Onion messages are handled by `handle_onion_message`, since they may
+    /// need relaying to another node rather than a reply.
+    pub fn handle_peer_message(&self, from_peer: &str, message: &Message) -> Result<Vec<Message>, ManagerError> {
+        debug!("Received {:?} from {}", message.msg_type, from_peer);
+        
+        match message.msg_type {
+            MessageType::RevokeAndAck => {
+                self.handle_revoke_and_ack(message)?;
+                Ok(Vec::new())
+            },
+            MessageType::OpenChannel2
+            | MessageType::AcceptChannel2
+            | MessageType::TxAddInput
+            | MessageType::TxAddOutput
+            | MessageType::TxRemoveInput
+            | MessageType::TxRemoveOutput
+            | MessageType::TxComplete
+            | MessageType::TxSignatures
+            | MessageType::TxInitRbf
+            | MessageType::TxAckRbf
+            | MessageType::TxAbort => self.handle_dual_funding_message(message),
+            _ => Err(ManagerError::NetworkError(format!("Unhandled message type {:?}", message.msg_type))),
+        }
+    }
+    
+    /// Handle a revoke and ack message from a peer
+    ///
+    /// The peer revokes the commitment it held until now, which is the last
+    /// counterparty commitment we recorded. That commitment and the revealed
+    /// per-commitment secret go to the watchtower client, and the peer's
+    /// commitment for the current balances becomes the next one to back up.
+    pub fn handle_revoke_and_ack(&self, message: &Message) -> Result<(), ManagerError> {
+        if message.msg_type != MessageType::RevokeAndAck {
+            return Err(ManagerError::ChannelError(format!("Expected RevokeAndAck, got {:?}", message.msg_type)));
+        }
+        let payload: RevokeAndAckPayload = message.decode_payload()
+            .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+        
+        let channel = self.channels.read().unwrap().get(&payload.channel_id).cloned()
+            .ok_or_else(|| ManagerError::ChannelNotFound(payload.channel_id.to_hex()))?;
+        let next = channel.remote_commitment()
+            .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+        
+        let revoked = self.revocable_commitments.write().unwrap()
+            .insert(payload.channel_id.clone(), next);
+        
+        match (revoked, self.tower_client.read().unwrap().as_ref()) {
+            (Some(revoked), Some(client)) => {
+                client.backup(revoked, payload.per_commitment_secret)
+                    .map_err(|e| ManagerError::WatchtowerError(e.to_string()))?;
+            },
+            (None, _) => {
+                warn!("No counterparty commitment recorded for channel {}", payload.channel_id.to_hex());
+            },
+            (Some(_), None) => {},
+        }
+        
+        Ok(())
+    }
+    
+    /// Record the commitment the counterparty holds for a newly tracked channel
+    fn record_counterparty_commitment(&self, channel_id: &ChannelId, channel: &AtomicChannel) {
+        match channel.remote_commitment() {
+            Ok(commitment) => {
+                self.revocable_commitments.write().unwrap().insert(channel_id.clone(), commitment);
+            },
+            Err(e) => debug!("No counterparty commitment for channel {}: {}", channel_id.to_hex(), e),
+        }
+    }
+    
+    /// Handle a dual-funding message from a peer, returning our replies
+    pub fn handle_dual_funding_message(&self, message: &Message) -> Result<Vec<Message>, ManagerError> {
+        let factory = self.message_factory();
+        
+        if message.msg_type == MessageType::OpenChannel2 {
+            let contribution = self.dual_funding_contribution.read().unwrap().clone().unwrap_or_default();
+            let (session, reply) = DualFundingSession::accept(message, contribution, self.node_key(), &factory)
+                .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+            self.dual_funding_sessions.write().unwrap()
+                .insert(session.channel_id().clone(), session);
+            return Ok(vec![reply]);
+        }
+        
+        let channel_id = message.channel_id.clone()
+            .ok_or_else(|| ManagerError::ChannelError("Message has no channel ID".to_string()))?;
+        
+        let mut sessions = self.dual_funding_sessions.write().unwrap();
+        let session = sessions.get_mut(&channel_id)
+            .ok_or_else(|| ManagerError::ChannelNotFound(channel_id.to_hex()))?;
+        
+        let signed_before = session.candidates().len();
+        let replies = session.handle_message(message, &factory)
+            .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+        
+        if session.candidates().len() > signed_before {
+            self.track_dual_funded_channel(session)?;
+        }
+        
+        Ok(replies)
+    }
+    
+    /// Replace the unconfirmed funding transaction of a dual-funded channel
+    pub fn bump_dual_funded_channel(&self, channel_id: &str, feerate_per_kw: u32) -> Result<Message, ManagerError> {
+        let channel_id = ChannelId::from_hex(channel_id)
+            .map_err(|_| ManagerError::ChannelNotFound(channel_id.to_string()))?;
+        
+        let mut sessions = self.dual_funding_sessions.write().unwrap();
+        let session = sessions.get_mut(&channel_id)
+            .ok_or_else(|| ManagerError::ChannelNotFound(channel_id.to_hex()))?;
+        
+        session.bump_fee(feerate_per_kw, &self.message_factory())
+            .map_err(|e| ManagerError::ChannelError(e.to_string()))
+    }
+    
+    /// Signed funding transactions of an unconfirmed dual-funded channel, oldest first
+    pub fn get_dual_funding_candidates(&self, channel_id: &str) -> Result<Vec<NegotiatedFunding>, ManagerError> {
+        let channel_id = ChannelId::from_hex(channel_id)
+            .map_err(|_| ManagerError::ChannelNotFound(channel_id.to_string()))?;
+        
+        let sessions = self.dual_funding_sessions.read().unwrap();
+        let session = sessions.get(&channel_id)
+            .ok_or_else(|| ManagerError::ChannelNotFound(channel_id.to_hex()))?;
+        
+        Ok(session.candidates().to_vec())
+    }
+    
+    /// Activate a dual-funded channel once one of its funding transactions confirms
+    pub fn dual_funding_confirmed(&self, channel_id: &str, funding_txid: &str) -> Result<(), ManagerError> {
+        let channel_id = ChannelId::from_hex(channel_id)
+            .map_err(|_| ManagerError::ChannelNotFound(channel_id.to_string()))?;
+        let txid: [u8; 32] = hex::decode(funding_txid).ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| ManagerError::ChannelError(format!("Invalid funding txid {}", funding_txid)))?;
+        
+        let confirmed = {
+            let mut sessions = self.dual_funding_sessions.write().unwrap();
+            let session = sessions.get_mut(&channel_id)
+                .ok_or_else(|| ManagerError::ChannelNotFound(channel_id.to_hex()))?;
+            let confirmed = session.funding_confirmed(&txid)
+                .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+            sessions.remove(&channel_id);
+            confirmed
+        };
+        
+        let pending = self.pending_channels.write().unwrap().remove(&channel_id)
+            .ok_or_else(|| ManagerError::ChannelNotFound(channel_id.to_hex()))?;
+        let mut channel = pending.channel.lock().unwrap().clone();
+        channel.funding_outpoint = Some(confirmed.outpoint);
+        channel.state = ChannelState::Active;
+        
+        let channel = Arc::new(AtomicChannel::new(channel));
+        self.record_counterparty_commitment(&channel_id, &channel);
+        self.channels.write().unwrap().insert(channel_id.clone(), channel);
+        
+        info!("Dual-funded channel {} confirmed with funding {}", channel_id, funding_txid);
+        
+        Ok(())
+    }
+    
+    /// Record the latest signed funding transaction of a dual-funded channel
+    fn track_dual_funded_channel(&self, session: &DualFundingSession) -> Result<(), ManagerError> {
+        let funding = session.candidates().last()
+            .ok_or_else(|| ManagerError::ChannelError("No signed funding transaction".to_string()))?;
+        let channel_id = session.channel_id().clone();
+        
+        let mut pending_channels = self.pending_channels.write().unwrap();
+        if let Some(existing) = pending_channels.get(&channel_id) {
+            // Replacement: the channel now points at the newest candidate
+            existing.channel.lock().unwrap().funding_outpoint = Some(funding.outpoint.clone());
+            return Ok(());
+        }
+        
+        let mut channel = Channel::open(
+            channel_id.to_hex(),
+            session.capacity_novas(),
+            session.remote_funding_novas(),
+            ChannelConfig::default(),
+            self.config.quantum_scheme.clone(),
+        ).map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+        channel.channel_id = *channel_id.as_bytes();
+        channel.is_initiator = session.is_initiator();
+        channel.to_self_delay = session.to_self_delay();
+        channel.max_accepted_htlcs = session.max_accepted_htlcs();
+        channel.funding_outpoint = Some(funding.outpoint.clone());
+        channel.state = ChannelState::FundingSigned;
+        
+        pending_channels.insert(channel_id.clone(), Arc::new(AtomicChannel::new(channel)));
+        drop(pending_channels);
+        
+        let _ = self.event_sender.send(LightningEvent::ChannelOpened(channel_id));
+        
+        Ok(())
+    }
+    
     /// Get payment history
     pub fn get_payments(&self, index_offset: u64, max_payments: u64, include_pending: bool) -> Result<Vec<LightningPayment>, ManagerError> {
         let payments = self.payments.read().unwrap();
@@ -771,6 +1298,24 @@ impl LightningManager {
         amount_msat: Option<u64>,
         timeout_seconds: u32,
         fee_limit_msat: Option<u64>,
+    ) -> Result<PaymentResponse, ManagerError> {
+        self.send_payment_with_preferences(
+            payment_request,
+            amount_msat,
+            timeout_seconds,
+            fee_limit_msat,
+            self.router.preferences(),
+        ).await
+    }
+    
+    /// Send a payment, routing it under `preferences` instead of the router's own
+    pub async fn send_payment_with_preferences(
+        &self,
+        payment_request: &str,
+        amount_msat: Option<u64>,
+        timeout_seconds: u32,
+        fee_limit_msat: Option<u64>,
+        preferences: &RouterPreferences,
     ) -> Result<PaymentResponse, ManagerError> {
         info!("Sending payment: {}", payment_request);
         
@@ -780,12 +1325,35 @@ impl LightningManager {
         // Use provided amount or invoice amount
         let amount = amount_msat.unwrap_or(invoice.amount_msat);
         
-        // Find route
-        let route = self.router.find_route(
-            &invoice.destination,
-            amount,
-            &[], // Route hints
-        ).map_err(|e| ManagerError::RouterError(e.to_string()))?;
+        self.pay_parsed_invoice(&invoice, amount, fee_limit_msat, preferences).await
+    }
+    
+    /// Routing preferences payments use by default
+    pub fn router_preferences(&self) -> RouterPreferences {
+        self.router.preferences().clone()
+    }
+    
+    /// Route and send a payment for an already parsed invoice
+    async fn pay_parsed_invoice(
+        &self,
+        invoice: &ParsedInvoice,
+        amount: u64,
+        fee_limit_msat: Option<u64>,
+        preferences: &RouterPreferences,
+    ) -> Result<PaymentResponse, ManagerError> {
+        // Find route, through the first usable blinded path if the invoice has any
+        let route = if invoice.blinded_paths.is_empty() {
+            self.router.find_route_with_preferences(
+                &invoice.destination,
+                amount,
+                &[], // Route hints
+                preferences,
+            ).map_err(|e| ManagerError::RouterError(e.to_string()))?
+        } else {
+            invoice.blinded_paths.iter()
+                .find_map(|path| self.router.find_route_to_blinded_path(path, amount).ok())
+                .ok_or_else(|| ManagerError::RouterError("No route to any blinded path".to_string()))?
+        };
         
         if route.is_empty() {
             return Err(ManagerError::PaymentFailed("No route found".to_string()));
@@ -800,6 +1368,19 @@ impl LightningManager {
             }
         }
         
+        // The hops of a blinded tail learn what to do from the payloads they unblind
+        let blinded_payloads = match &route.blinded_tail {
+            Some(tail) => {
+                let final_cltv_expiry = self.get_current_height() as u32 + self.config.cltv_expiry_delta as u32;
+                tail.hop_payloads(amount, final_cltv_expiry)
+                    .map_err(|e| ManagerError::PaymentFailed(e.to_string()))?
+            }
+            None => Vec::new(),
+        };
+        let blinded_node_ids: Vec<String> = route.blinded_tail.iter()
+            .flat_map(|tail| tail.hops.iter().map(|hop| hex::encode(&hop.blinded_node_id)))
+            .collect();
+        
         // Create payment
         let payment_hash = invoice.payment_hash.clone();
         let payment_index = self.payment_index.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
@@ -818,7 +1399,13 @@ impl LightningManager {
                 amount_msat: h.amount_msat,
                 fee_msat: h.channel_fee(h.amount_msat),
                 cltv_expiry_delta: h.cltv_expiry_delta,
-            }).collect()),
+            }).chain(blinded_node_ids.iter().zip(&blinded_payloads).map(|(node_id, payload)| RouteHop {
+                channel_id: 0,
+                node_id: node_id.clone(),
+                amount_msat: payload.amount_msat,
+                fee_msat: payload.amount_msat.saturating_sub(amount),
+                cltv_expiry_delta: 0,
+            })).collect()),
             failure_reason: None,
             carbon_footprint_grams: None,
         };
@@ -830,7 +1417,7 @@ impl LightningManager {
         }
         
         // Send payment through route
-        let preimage = self.send_payment_through_route(&route, &invoice).await?;
+        let preimage = self.send_payment_through_route(&route, &blinded_payloads, invoice).await?;
         
         // Update payment status
         {
@@ -848,7 +1435,7 @@ impl LightningManager {
         Ok(PaymentResponse {
             payment_hash: payment_hash.to_hex(),
             payment_preimage: Some(preimage.to_hex()),
-            payment_route: route.hops.iter().map(|h| h.node_id.to_string()).collect(),
+            payment_route: route.hops.iter().map(|h| h.node_id.to_string()).chain(blinded_node_ids).collect(),
             payment_error: None,
             payment_index,
             status: "SUCCEEDED".to_string(),
@@ -888,13 +1475,20 @@ impl LightningManager {
         let invoice_index = self.invoice_index.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         
         // Create invoice using payment module types directly
-        let invoice = Invoice::new_with_preimage(
+        let mut invoice = Invoice::new_with_preimage(
             preimage,
             value_msat,
             memo.to_string(),
             expiry,
         ).map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
         
+        // Private invoices hide our channels behind blinded paths
+        if private {
+            for path in self.build_blinded_paths()? {
+                invoice.add_blinded_path(path);
+            }
+        }
+        
         // Store invoice using payment module types
         {
             let mut invoices = self.invoices.write().unwrap();
@@ -941,68 +1535,1134 @@ impl LightningManager {
         })
     }
     
-    // Helper methods
-    fn get_node_id(&self) -> String {
-        // In a real implementation, this would return the node's public key
-        "02abcdef1234567890abcdef1234567890abcdef1234567890abcdef1234567890ab".to_string()
+    /// Create a hold invoice for a payment hash whose preimage we don't know yet
+    pub fn add_hold_invoice(
+        &self,
+        payment_hash: &str,
+        value_msat: u64,
+        memo: &str,
+        expiry: u32,
+    ) -> Result<InvoiceResponse, ManagerError> {
+        let payment_hash = PaymentHash::from_hex(payment_hash)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        info!("Creating hold invoice {} for {} millinovas", payment_hash, value_msat);
+        
+        let invoice = EnhancedInvoice::new_hold(payment_hash, value_msat, memo.to_string(), expiry, 0)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        let payment_request = self.encode_payment_request(invoice.base_invoice())?;
+        
+        self.hold_invoices.write().unwrap().add_invoice(invoice)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        let add_index = self.invoice_index.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+        let _ = self.event_sender.send(LightningEvent::InvoiceCreated(payment_hash));
+        
+        Ok(InvoiceResponse {
+            payment_request,
+            payment_hash: payment_hash.to_hex(),
+            add_index,
+        })
     }
     
-    fn channel_to_lightning_channel(&self, atomic_channel: &Arc<AtomicChannel>) -> LightningChannel {
-        // Get channel info atomically
-        let channel_info = atomic_channel.get_channel_info()
-            .unwrap_or_else(|_| panic!("Failed to get channel info"));
+    /// Receive an HTLC on `channel_id` that pays one of our invoices
+    ///
+    /// An HTLC paying a hold invoice is held until the invoice is settled or
+    /// canceled and `None` is returned. One paying a regular invoice is
+    /// settled at once with the invoice preimage.
+    pub fn receive_htlc(
+        &self,
+        channel_id: &str,
+        payment_hash: &str,
+        amount_msat: u64,
+        cltv_expiry: u32,
+        current_height: u32,
+    ) -> Result<Option<PaymentPreimage>, ManagerError> {
+        let payment_hash = PaymentHash::from_hex(payment_hash)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        let channel_id = ChannelId::from_hex(channel_id)
+            .map_err(|_| ManagerError::InvalidPaymentRequest("Invalid channel ID".to_string()))?;
+        let channel = self.atomic_channel(&channel_id)
+            .ok_or_else(|| ManagerError::ChannelNotFound(channel_id.to_hex()))?;
         
-        // Get the underlying channel for detailed info
-        let channel = atomic_channel.channel.lock()
-            .unwrap_or_else(|_| panic!("Failed to lock channel"));
+        let is_hold = self.hold_invoices.read().unwrap().get_invoice(&payment_hash).is_some();
+        let preimage = if is_hold {
+            None
+        } else {
+            let invoices = self.invoices.read().unwrap();
+            let invoice = invoices.get(&payment_hash)
+                .ok_or_else(|| ManagerError::PaymentNotFound(payment_hash.to_hex()))?;
+            if amount_msat < invoice.amount_msat() {
+                return Err(ManagerError::PaymentFailed(format!(
+                    "Received {} millinovas for an invoice of {}", amount_msat, invoice.amount_msat()
+                )));
+            }
+            Some(invoice.payment_preimage())
+        };
         
-        // Calculate actual statistics from channel data
-        let total_novas_sent = channel.pending_htlcs.iter()
-            .filter(|htlc| htlc.is_outgoing)
-            .map(|htlc| htlc.amount_novas)
-            .sum::<u64>();
-            
-        let total_novas_received = channel.pending_htlcs.iter()
-            .filter(|htlc| !htlc.is_outgoing)
-            .map(|htlc| htlc.amount_novas)
-            .sum::<u64>();
-            
-        let num_updates = channel_info.commitment_number;
+        let htlc_id = channel.add_htlc(*payment_hash.as_bytes(), amount_msat / 1000, cltv_expiry, false)
+            .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
         
-        // Calculate uptime
-        let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-        let uptime = current_time - channel_info.last_operation_time;
+        let preimage = match preimage {
+            Some(preimage) => preimage,
+            None => {
+                if let Err(e) = self.accept_hold_htlc(&channel_id, htlc_id, &payment_hash, amount_msat, cltv_expiry, current_height) {
+                    let _ = channel.fail_htlc(htlc_id, &e.to_string());
+                    return Err(e);
+                }
+                return Ok(None);
+            }
+        };
         
-        LightningChannel {
-            channel_id: hex::encode(channel_info.channel_id),
-            remote_pubkey: hex::encode(channel.remote_node_id.serialize()),
-            capacity: channel_info.capacity_novas,
-            local_balance: channel_info.local_balance_novas,
-            remote_balance: channel_info.remote_balance_novas,
-            commit_fee: 1000, // Standard commitment fee
-            commit_weight: 724, // Standard commitment weight
-            fee_per_kw: 2500, // Standard fee per kiloweight
-            unsettled_balance: channel.pending_htlcs.iter()
-                .map(|htlc| htlc.amount_novas)
-                .sum(),
-            total_satoshis_sent: total_novas_sent,
-            total_satoshis_received: total_novas_received,
-            num_updates,
-            pending_htlcs: channel.pending_htlcs.iter().map(|htlc| PendingHTLC {
-                incoming: !htlc.is_outgoing,
-                amount: htlc.amount_novas,
-                outpoint: format!("{}:{}", hex::encode(htlc.payment_hash), htlc.id),
-                maturity_height: htlc.expiry_height,
-                blocks_til_maturity: htlc.expiry_height as i32 - self.get_current_height() as i32,
-                stage: 1, // Simplified stage
-            }).collect(),
-            csv_delay: channel.to_self_delay as u32,
-            private: !channel.is_public,
-            initiator: channel.is_initiator,
-            chan_status_flags: "ChanStatusDefault".to_string(),
-            local_chan_reserve_sat: channel.channel_reserve_novas,
-            remote_chan_reserve_sat: channel.channel_reserve_novas,
-            static_remote_key: false,
+        channel.settle_htlc(htlc_id, *preimage.as_bytes())
+            .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+        info!("Received {} millinovas for invoice {}", amount_msat, payment_hash);
+        let _ = self.event_sender.send(LightningEvent::PaymentReceived(payment_hash, amount_msat));
+        
+        Ok(Some(preimage))
+    }
+    
+    /// Receive an HTLC that reached us over one of our blinded paths
+    ///
+    /// `hop` is the final hop of the path. It must carry the ID of a path we
+    /// handed out, and the HTLC must meet the constraints we blinded into it.
+    pub fn receive_blinded_htlc(
+        &self,
+        channel_id: &str,
+        payment_hash: &str,
+        amount_msat: u64,
+        cltv_expiry: u32,
+        current_height: u32,
+        hop: &BlindedHop,
+    ) -> Result<Option<PaymentPreimage>, ManagerError> {
+        let data = self.decrypt_blinded_hop(hop)?;
+        let ours = match (data.next_short_channel_id, data.path_id) {
+            (None, Some(path_id)) => self.blinded_path_ids.read().unwrap().contains(&path_id),
+            _ => false,
+        };
+        if !ours {
+            return Err(ManagerError::PaymentFailed("Blinded hop does not end one of our paths".to_string()));
+        }
+        Self::check_payment_constraints(&data.payment_constraints, amount_msat, cltv_expiry)?;
+        
+        self.receive_htlc(channel_id, payment_hash, amount_msat, cltv_expiry, current_height)
+    }
+    
+    /// Our instructions in a hop of a blinded path
+    fn decrypt_blinded_hop(&self, hop: &BlindedHop) -> Result<BlindedHopData, ManagerError> {
+        hop.decrypt(&self.get_node_id(), &self.kem_keypair.secret_key, self.hop_kem.as_ref())
+            .map_err(|e| ManagerError::PaymentFailed(format!("Invalid blinded hop: {}", e)))
+    }
+    
+    /// Check an HTLC against the constraints of a blinded hop
+    fn check_payment_constraints(constraints: &PaymentConstraints, amount_msat: u64, cltv_expiry: u32) -> Result<(), ManagerError> {
+        if cltv_expiry > constraints.max_cltv_expiry {
+            return Err(ManagerError::PaymentFailed(format!(
+                "CLTV expiry {} beyond blinded path limit {}", cltv_expiry, constraints.max_cltv_expiry
+            )));
+        }
+        if amount_msat < constraints.htlc_minimum_msat {
+            return Err(ManagerError::PaymentFailed(format!(
+                "Amount {} below blinded path minimum {}", amount_msat, constraints.htlc_minimum_msat
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Hold an incoming HTLC paying a hold invoice
+    pub fn accept_hold_htlc(
+        &self,
+        channel_id: &ChannelId,
+        htlc_id: u64,
+        payment_hash: &PaymentHash,
+        amount_msat: u64,
+        cltv_expiry: u32,
+        current_height: u32,
+    ) -> Result<InvoiceState, ManagerError> {
+        let htlc = HeldHtlc {
+            channel_id: channel_id.clone(),
+            htlc_id,
+            amount_msat,
+            cltv_expiry,
+            accepted_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        };
+        
+        let state = self.hold_invoices.write().unwrap()
+            .accept_hold_htlc(payment_hash, htlc, current_height)
+            .map_err(|e| ManagerError::PaymentFailed(e.to_string()))?;
+        
+        debug!("Holding HTLC {} for invoice {} ({:?})", htlc_id, payment_hash, state);
+        Ok(state)
+    }
+    
+    /// Settle an accepted hold invoice by releasing its preimage
+    pub fn settle_hold_invoice(&self, preimage: &str) -> Result<(), ManagerError> {
+        let preimage = PaymentPreimage::from_hex(preimage)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        let htlcs = self.hold_invoices.write().unwrap().settle_hold_invoice(&preimage)
+            .map_err(|e| ManagerError::PaymentFailed(e.to_string()))?;
+        
+        let channels = self.channels.read().unwrap();
+        let mut amount_msat = 0;
+        for htlc in &htlcs {
+            amount_msat += htlc.amount_msat;
+            match channels.get(&htlc.channel_id) {
+                Some(channel) => {
+                    if let Err(e) = channel.settle_htlc(htlc.htlc_id, *preimage.as_bytes()) {
+                        warn!("Failed to settle held HTLC {} on channel {}: {}", htlc.htlc_id, htlc.channel_id, e);
+                    }
+                }
+                None => warn!("Channel {} of held HTLC {} is gone", htlc.channel_id, htlc.htlc_id),
+            }
+        }
+        
+        let payment_hash = preimage.payment_hash();
+        info!("Settled hold invoice {} for {} millinovas", payment_hash, amount_msat);
+        let _ = self.event_sender.send(LightningEvent::PaymentReceived(payment_hash, amount_msat));
+        
+        Ok(())
+    }
+    
+    /// Cancel a hold invoice, failing back any held HTLCs
+    pub fn cancel_hold_invoice(&self, payment_hash: &str) -> Result<(), ManagerError> {
+        let payment_hash = PaymentHash::from_hex(payment_hash)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        let htlcs = self.hold_invoices.write().unwrap().cancel_hold_invoice(&payment_hash)
+            .map_err(|e| ManagerError::PaymentFailed(e.to_string()))?;
+        
+        self.fail_held_htlcs(&htlcs, "Hold invoice canceled");
+        info!("Canceled hold invoice {}", payment_hash);
+        
+        Ok(())
+    }
+    
+    /// Cancel hold invoices whose HTLCs are close to their CLTV expiry
+    ///
+    /// Should be called on every new block so held HTLCs are failed back
+    /// off-chain before the channel would have to go to chain.
+    pub fn cancel_expiring_hold_invoices(&self, current_height: u32) -> usize {
+        let canceled = self.hold_invoices.write().unwrap()
+            .cancel_expiring_hold_invoices(current_height);
+        
+        for (payment_hash, htlcs) in &canceled {
+            warn!("Auto-canceling hold invoice {} at height {}", payment_hash, current_height);
+            self.fail_held_htlcs(htlcs, "Hold invoice expiring");
+        }
+        
+        canceled.len()
+    }
+    
+    /// Look up the state of a hold invoice
+    pub fn lookup_hold_invoice(&self, payment_hash: &str) -> Result<HoldInvoiceResponse, ManagerError> {
+        let payment_hash = PaymentHash::from_hex(payment_hash)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        let hold_invoices = self.hold_invoices.read().unwrap();
+        let invoice = hold_invoices.get_invoice(&payment_hash)
+            .ok_or_else(|| ManagerError::PaymentNotFound(payment_hash.to_hex()))?;
+        
+        Ok(HoldInvoiceResponse {
+            payment_hash: payment_hash.to_hex(),
+            state: format!("{:?}", invoice.state()).to_uppercase(),
+            value_msat: invoice.amount_msat(),
+            amount_received_msat: invoice.amount_received_msat(),
+            held_htlcs: invoice.held_htlcs().len(),
+            cancel_height: invoice.cancel_height(),
+        })
+    }
+    
+    fn fail_held_htlcs(&self, htlcs: &[HeldHtlc], reason: &str) {
+        let channels = self.channels.read().unwrap();
+        for htlc in htlcs {
+            if let Some(channel) = channels.get(&htlc.channel_id) {
+                if let Err(e) = channel.fail_htlc(htlc.htlc_id, reason) {
+                    warn!("Failed to fail back held HTLC {} on channel {}: {}", htlc.htlc_id, htlc.channel_id, e);
+                }
+            }
+        }
+    }
+    
+    /// Track a channel that is already open, such as one restored from a backup
+    pub fn register_open_channel(&self, channel: Channel) -> ChannelId {
+        let channel_id = ChannelId::from_bytes(channel.channel_id);
+        let channel = Arc::new(AtomicChannel::new(channel));
+        self.record_counterparty_commitment(&channel_id, &channel);
+        self.channels.write().unwrap().insert(channel_id.clone(), channel);
+        channel_id
+    }
+    
+    /// Shared handle to one of our channels
+    pub(crate) fn atomic_channel(&self, channel_id: &ChannelId) -> Option<Arc<AtomicChannel>> {
+        self.channels.read().unwrap().get(channel_id).cloned()
+    }
+    
+    /// Record resolved forwards in `ledger`
+    pub fn set_forwarding_ledger(&self, ledger: ForwardingLedger) {
+        *self.forwarding_ledger.write().unwrap() = Some(ledger);
+    }
+    
+    /// Forward an HTLC that arrived on one of our channels to the next hop
+    ///
+    /// Both HTLCs stay pending until `settle_forward` or `fail_forward` is
+    /// called. A forward we refuse is failed back and recorded immediately.
+    pub fn forward_htlc(
+        &self,
+        incoming_channel: &str,
+        outgoing_channel: &str,
+        payment_hash: &str,
+        amount_in_msat: u64,
+        amount_out_msat: u64,
+        cltv_expiry: u32,
+    ) -> Result<(), ManagerError> {
+        let payment_hash = PaymentHash::from_hex(payment_hash)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        let incoming_id = ChannelId::from_hex(incoming_channel)
+            .map_err(|_| ManagerError::InvalidPaymentRequest("Invalid channel ID".to_string()))?;
+        let outgoing_id = ChannelId::from_hex(outgoing_channel)
+            .map_err(|_| ManagerError::InvalidPaymentRequest("Invalid channel ID".to_string()))?;
+        
+        let outgoing_expiry = cltv_expiry.saturating_sub(self.config.cltv_expiry_delta as u32);
+        self.add_forward(payment_hash, incoming_id, outgoing_id, (amount_in_msat, amount_out_msat), cltv_expiry, outgoing_expiry)
+    }
+    
+    /// Forward an HTLC that arrived on one of our channels along a blinded path
+    ///
+    /// `hop` is our hop of the path. Its encrypted data names the next
+    /// channel and the relay parameters the recipient chose for us.
+    pub fn forward_blinded_htlc(
+        &self,
+        incoming_channel: &str,
+        payment_hash: &str,
+        amount_in_msat: u64,
+        cltv_expiry: u32,
+        hop: &BlindedHop,
+    ) -> Result<(), ManagerError> {
+        let payment_hash = PaymentHash::from_hex(payment_hash)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        let incoming_id = ChannelId::from_hex(incoming_channel)
+            .map_err(|_| ManagerError::InvalidPaymentRequest("Invalid channel ID".to_string()))?;
+        
+        let data = self.decrypt_blinded_hop(hop)?;
+        let (short_channel_id, relay) = match (data.next_short_channel_id, data.payment_relay) {
+            (Some(short_channel_id), Some(relay)) => (short_channel_id, relay),
+            _ => return Err(ManagerError::PaymentFailed("Blinded hop does not forward".to_string())),
+        };
+        Self::check_payment_constraints(&data.payment_constraints, amount_in_msat, cltv_expiry)?;
+        let outgoing_id = self.channel_by_short_id(short_channel_id)
+            .ok_or_else(|| ManagerError::PaymentFailed(format!("unknown next channel {}", short_channel_id)))?;
+        
+        let amount_out_msat = relay.amount_to_forward(amount_in_msat);
+        let outgoing_expiry = cltv_expiry.saturating_sub(relay.cltv_expiry_delta as u32);
+        self.add_forward(payment_hash, incoming_id, outgoing_id, (amount_in_msat, amount_out_msat), cltv_expiry, outgoing_expiry)
+    }
+    
+    /// Add an HTLC on the incoming channel and its forward on the outgoing one
+    fn add_forward(
+        &self,
+        payment_hash: PaymentHash,
+        incoming_id: ChannelId,
+        outgoing_id: ChannelId,
+        (amount_in_msat, amount_out_msat): (u64, u64),
+        cltv_expiry: u32,
+        outgoing_expiry: u32,
+    ) -> Result<(), ManagerError> {
+        if self.pending_forwards.read().unwrap().contains_key(&payment_hash) {
+            return Err(ManagerError::PaymentFailed(format!("Already forwarding {}", payment_hash)));
+        }
+        
+        let (incoming, outgoing) = {
+            let channels = self.channels.read().unwrap();
+            (channels.get(&incoming_id).cloned(), channels.get(&outgoing_id).cloned())
+        };
+        let incoming = incoming.ok_or_else(|| ManagerError::ChannelNotFound(incoming_id.to_hex()))?;
+        
+        let mut pending = PendingForward {
+            incoming_channel: incoming_id.clone(),
+            incoming_htlc: 0,
+            outgoing_channel: outgoing_id.clone(),
+            outgoing_htlc: 0,
+            incoming_peer: Self::channel_peer(&incoming),
+            outgoing_peer: outgoing.as_ref().map(Self::channel_peer).unwrap_or_default(),
+            amount_in_msat,
+            amount_out_msat,
+        };
+        
+        // Check the next hop and that we are paid what our policy asks for
+        let refusal = match &outgoing {
+            None => Some("unknown next peer".to_string()),
+            Some(_) if amount_in_msat < amount_out_msat => Some("amount below forwarded amount".to_string()),
+            Some(_) => self.routing_policies.read().unwrap().get(&outgoing_id).and_then(|policy| {
+                let required = policy.fee_base_msat + amount_out_msat * policy.fee_rate_milli_msat / 1_000_000;
+                if policy.disabled {
+                    Some("channel disabled".to_string())
+                } else if amount_out_msat < policy.min_htlc {
+                    Some("amount below minimum".to_string())
+                } else if amount_in_msat - amount_out_msat < required {
+                    Some(format!("fee insufficient: {} < {}", amount_in_msat - amount_out_msat, required))
+                } else {
+                    None
+                }
+            }),
+        };
+        if let Some(reason) = refusal {
+            self.record_forward(&payment_hash, &pending, ForwardStatus::Failed, Some(reason.clone()));
+            return Err(ManagerError::PaymentFailed(reason));
+        }
+        let outgoing = outgoing.expect("checked above");
+        
+        pending.incoming_htlc = incoming.add_htlc(*payment_hash.as_bytes(), amount_in_msat / 1000, cltv_expiry, false)
+            .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+        
+        match outgoing.add_htlc(*payment_hash.as_bytes(), amount_out_msat / 1000, outgoing_expiry, true) {
+            Ok(htlc_id) => pending.outgoing_htlc = htlc_id,
+            Err(e) => {
+                let reason = format!("temporary channel failure: {}", e);
+                let _ = incoming.fail_htlc(pending.incoming_htlc, &reason);
+                self.record_forward(&payment_hash, &pending, ForwardStatus::Failed, Some(reason.clone()));
+                return Err(ManagerError::PaymentFailed(reason));
+            }
+        }
+        
+        debug!("Forwarding {} from {} to {}", payment_hash, incoming_id, outgoing_id);
+        self.pending_forwards.write().unwrap().insert(payment_hash, pending);
+        Ok(())
+    }
+    
+    /// Settle a forwarded HTLC with the preimage returned by the next hop
+    pub fn settle_forward(&self, preimage: &str) -> Result<u64, ManagerError> {
+        let preimage = PaymentPreimage::from_hex(preimage)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        let payment_hash = preimage.payment_hash();
+        
+        let pending = self.pending_forwards.write().unwrap().remove(&payment_hash)
+            .ok_or_else(|| ManagerError::PaymentNotFound(payment_hash.to_hex()))?;
+        
+        {
+            let channels = self.channels.read().unwrap();
+            for (channel_id, htlc_id) in [(&pending.outgoing_channel, pending.outgoing_htlc), (&pending.incoming_channel, pending.incoming_htlc)] {
+                match channels.get(channel_id) {
+                    Some(channel) => {
+                        if let Err(e) = channel.settle_htlc(htlc_id, *preimage.as_bytes()) {
+                            warn!("Failed to settle forwarded HTLC {} on channel {}: {}", htlc_id, channel_id, e);
+                        }
+                    }
+                    None => warn!("Channel {} of forwarded HTLC {} is gone", channel_id, htlc_id),
+                }
+            }
+        }
+        
+        let fee_msat = pending.amount_in_msat - pending.amount_out_msat;
+        self.record_forward(&payment_hash, &pending, ForwardStatus::Settled, None);
+        info!("Settled forward {} earning {} millinovas", payment_hash, fee_msat);
+        
+        Ok(fee_msat)
+    }
+    
+    /// Fail a forwarded HTLC back to the previous hop
+    pub fn fail_forward(&self, payment_hash: &str, reason: &str) -> Result<(), ManagerError> {
+        let payment_hash = PaymentHash::from_hex(payment_hash)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        let pending = self.pending_forwards.write().unwrap().remove(&payment_hash)
+            .ok_or_else(|| ManagerError::PaymentNotFound(payment_hash.to_hex()))?;
+        
+        {
+            let channels = self.channels.read().unwrap();
+            for (channel_id, htlc_id) in [(&pending.outgoing_channel, pending.outgoing_htlc), (&pending.incoming_channel, pending.incoming_htlc)] {
+                if let Some(channel) = channels.get(channel_id) {
+                    if let Err(e) = channel.fail_htlc(htlc_id, reason) {
+                        warn!("Failed to fail back forwarded HTLC {} on channel {}: {}", htlc_id, channel_id, e);
+                    }
+                }
+            }
+        }
+        
+        self.record_forward(&payment_hash, &pending, ForwardStatus::Failed, Some(reason.to_string()));
+        Ok(())
+    }
+    
+    /// Forwards that resolved between `from` and `to`, in seconds since the epoch
+    pub fn get_forwards(&self, from: u64, to: u64) -> Result<Vec<ForwardingEvent>, ManagerError> {
+        let ledger = self.forwarding_ledger.read().unwrap();
+        let ledger = ledger.as_ref()
+            .ok_or_else(|| ManagerError::ConfigError("Forwarding ledger is not configured".to_string()))?;
+        
+        ledger.events(from, to).map_err(|e| ManagerError::LedgerError(e.to_string()))
+    }
+    
+    /// Per-channel and per-peer routing income between `from` and `to`
+    pub fn get_forwarding_report(&self, from: u64, to: u64) -> Result<ForwardingReport, ManagerError> {
+        let ledger = self.forwarding_ledger.read().unwrap();
+        let ledger = ledger.as_ref()
+            .ok_or_else(|| ManagerError::ConfigError("Forwarding ledger is not configured".to_string()))?;
+        
+        ledger.report(from, to).map_err(|e| ManagerError::LedgerError(e.to_string()))
+    }
+    
+    fn record_forward(&self, payment_hash: &PaymentHash, forward: &PendingForward, status: ForwardStatus, failure_reason: Option<String>) {
+        let ledger = self.forwarding_ledger.read().unwrap();
+        let Some(ledger) = ledger.as_ref() else {
+            return;
+        };
+        
+        let event = ForwardingEvent {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            incoming_channel: forward.incoming_channel.to_hex(),
+            outgoing_channel: forward.outgoing_channel.to_hex(),
+            incoming_peer: forward.incoming_peer.clone(),
+            outgoing_peer: forward.outgoing_peer.clone(),
+            payment_hash: payment_hash.to_hex(),
+            amount_in_msat: forward.amount_in_msat,
+            amount_out_msat: forward.amount_out_msat,
+            fee_msat: match status {
+                ForwardStatus::Settled => forward.amount_in_msat - forward.amount_out_msat,
+                ForwardStatus::Failed => 0,
+            },
+            status,
+            failure_reason,
+        };
+        
+        if let Err(e) = ledger.record(&event) {
+            error!("Failed to record forward {}: {}", payment_hash, e);
+        }
+    }
+    
+    /// Send an onion message to `destination` through `route`
+    ///
+    /// Returns the peer to hand the message to and the signed wire message.
+    pub fn send_onion_message(
+        &self,
+        route: &[MessageHop],
+        destination: Destination,
+        contents: OnionMessageContents,
+        reply_path: Option<MessagePath>,
+    ) -> Result<(String, Message), ManagerError> {
+        let (first_hop, packet) = self.onion_messenger.lock().unwrap()
+            .create_message(route, destination, contents, reply_path)
+            .map_err(|e| ManagerError::OnionMessageError(e.to_string()))?;
+        
+        let message = self.message_factory().create_onion_message(&packet)
+            .map_err(|e| ManagerError::NetworkError(e.to_string()))?;
+        
+        Ok((first_hop, message))
+    }
+    
+    /// Create a reply path through `forwarders` that ends at this node
+    ///
+    /// The returned path ID comes back with every message received over the path.
+    pub fn create_message_reply_path(&self, forwarders: &[MessageHop]) -> Result<(MessagePath, [u8; 32]), ManagerError> {
+        let mut path_id = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut path_id);
+        
+        let path = self.onion_messenger.lock().unwrap()
+            .create_reply_path(forwarders, path_id)
+            .map_err(|e| ManagerError::OnionMessageError(e.to_string()))?;
+        
+        Ok((path, path_id))
+    }
+    
+    /// Handle an onion message received from `from_peer`
+    ///
+    /// Messages to relay, and our answers to invoice requests, come back as a
+    /// packet for the next node. Invoices answering our own requests are
+    /// checked and kept so they can be paid.
+    pub fn handle_onion_message(&self, from_peer: &str, message: &Message) -> Result<OnionMessageAction, ManagerError> {
+        if message.msg_type != MessageType::OnionMessage {
+            return Err(ManagerError::NetworkError(format!("Expected OnionMessage, got {:?}", message.msg_type)));
+        }
+        let packet: OnionMessagePacket = message.decode_payload()
+            .map_err(|e| ManagerError::OnionMessageError(e.to_string()))?;
+        
+        let action = self.onion_messenger.lock().unwrap()
+            .handle_message(from_peer, &packet)
+            .map_err(|e| ManagerError::OnionMessageError(e.to_string()))?;
+        
+        match action {
+            OnionMessageAction::Forward { next_node_id, packet } => {
+                debug!("Relaying onion message from {} to {}", from_peer, next_node_id);
+                Ok(OnionMessageAction::Forward { next_node_id, packet })
+            }
+            OnionMessageAction::Receive { contents, reply_path, path_id } => {
+                self.handle_offers_message(contents, reply_path, path_id)
+            }
+        }
+    }
+    
+    /// Pass offers messages delivered to us to the offer handlers
+    fn handle_offers_message(
+        &self,
+        contents: OnionMessageContents,
+        reply_path: Option<MessagePath>,
+        path_id: Option<[u8; 32]>,
+    ) -> Result<OnionMessageAction, ManagerError> {
+        if let Some(invoice_request) = contents.get(TLV_INVOICE_REQUEST) {
+            let reply_path = reply_path
+                .ok_or_else(|| ManagerError::OnionMessageError("Invoice request without a reply path".to_string()))?;
+            
+            let answer = std::str::from_utf8(invoice_request)
+                .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))
+                .and_then(|invoice_request| self.handle_invoice_request(invoice_request));
+            let reply = match answer {
+                Ok(invoice) => OnionMessageContents::new().with(TLV_INVOICE, invoice.into_bytes()),
+                Err(e) => {
+                    warn!("Refusing invoice request: {}", e);
+                    OnionMessageContents::new().with(TLV_INVOICE_ERROR, e.to_string().into_bytes())
+                }
+            };
+            
+            let (next_node_id, packet) = self.onion_messenger.lock().unwrap()
+                .create_message(&[], Destination::BlindedPath(reply_path), reply, None)
+                .map_err(|e| ManagerError::OnionMessageError(e.to_string()))?;
+            return Ok(OnionMessageAction::Forward { next_node_id, packet });
+        }
+        
+        if let Some(invoice) = contents.get(TLV_INVOICE) {
+            let invoice = std::str::from_utf8(invoice)
+                .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+            let invoice = self.verify_offer_invoice(invoice)?;
+            info!("Received invoice {} for offer {}", invoice.payment_hash(), invoice.offer_id());
+            self.offer_invoices.write().unwrap().insert(invoice.payment_hash(), invoice);
+        } else if let Some(error) = contents.get(TLV_INVOICE_ERROR) {
+            warn!("Invoice request refused: {}", String::from_utf8_lossy(error));
+        }
+        
+        Ok(OnionMessageAction::Receive { contents, reply_path, path_id })
+    }
+    
+    /// Announce this node, advertising `environmental` for carbon-aware routing
+    pub fn create_node_announcement(
+        &self,
+        alias: &str,
+        environmental: Option<NodeEnvironmentalData>,
+    ) -> Result<Message, ManagerError> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        self.message_factory().create_node_announcement(alias, timestamp, environmental)
+            .map_err(|e| ManagerError::NetworkError(e.to_string()))
+    }
+    
+    /// Apply a node announcement received through gossip
+    ///
+    /// Returns whether it updated the environmental data used for routing.
+    pub fn handle_node_announcement(&self, message: &Message) -> Result<bool, ManagerError> {
+        if message.msg_type != MessageType::NodeAnnouncement {
+            return Err(ManagerError::NetworkError(format!("Expected NodeAnnouncement, got {:?}", message.msg_type)));
+        }
+        let announcement: NodeAnnouncementPayload = message.decode_payload()
+            .map_err(|e| ManagerError::NetworkError(e.to_string()))?;
+        
+        Ok(match announcement.environmental {
+            Some(data) => self.router.update_node_environmental(NodeId::new(announcement.node_id), data, announcement.timestamp),
+            None => false,
+        })
+    }
+    
+    /// Relay trampoline payments for other nodes, charging `policy`
+    ///
+    /// Init messages advertise the trampoline feature from now on.
+    pub fn enable_trampoline_routing(&self, policy: TrampolineFeePolicy) {
+        let relay = TrampolineRelay::new(self.kem_keypair.clone(), Box::new(SystemKem), policy);
+        *self.trampoline_relay.write().unwrap() = Some(relay);
+    }
+    
+    /// Stop relaying trampoline payments
+    pub fn disable_trampoline_routing(&self) {
+        *self.trampoline_relay.write().unwrap() = None;
+    }
+    
+    /// Build a payment to `recipient` that only needs a route to the first trampoline
+    pub fn build_trampoline_payment(
+        &self,
+        trampolines: &[TrampolineHop],
+        recipient: TrampolineRecipient<'_>,
+    ) -> Result<TrampolinePayment, ManagerError> {
+        build_trampoline_payment(&self.router, &SystemKem, trampolines, recipient)
+            .map_err(|e| ManagerError::TrampolineError(e.to_string()))
+    }
+    
+    /// Handle a trampoline onion that arrived with an incoming HTLC
+    ///
+    /// Forwards come back with the route to the next node found by our router.
+    pub fn handle_trampoline_onion(
+        &self,
+        onion: &TrampolineOnion,
+        incoming_amount_msat: u64,
+        incoming_cltv_expiry: u32,
+    ) -> Result<TrampolineAction, ManagerError> {
+        let relay = self.trampoline_relay.read().unwrap();
+        let relay = relay.as_ref()
+            .ok_or_else(|| ManagerError::ConfigError("Trampoline routing is not enabled".to_string()))?;
+        
+        relay.process(&self.router, onion, incoming_amount_msat, incoming_cltv_expiry)
+            .map_err(|e| ManagerError::TrampolineError(e.to_string()))
+    }
+    
+    fn channel_peer(channel: &Arc<AtomicChannel>) -> String {
+        channel.channel.lock()
+            .map(|channel| hex::encode(channel.remote_node_id.serialize()))
+            .unwrap_or_default()
+    }
+    
+    /// Keep offers state in `store`, restoring what it already holds
+    ///
+    /// A stored signing key replaces the one generated at startup so offers
+    /// published before a restart can still be answered.
+    pub fn set_offer_store(&self, store: OfferStore) -> Result<(), ManagerError> {
+        let map_err = |e: OfferStoreError| ManagerError::OfferStoreError(e.to_string());
+        
+        match store.node_key().map_err(map_err)? {
+            Some(node_key) => *self.node_key.write().unwrap() = node_key,
+            None => store.set_node_key(&self.node_key()).map_err(map_err)?,
+        }
+        
+        self.offers.write().unwrap()
+            .extend(store.offers().map_err(map_err)?.into_iter().map(|offer| (offer.id(), offer)));
+        self.outgoing_invoice_requests.write().unwrap()
+            .extend(store.invoice_requests().map_err(map_err)?.into_iter()
+                .map(|(offer, request, payer_key)| (request.id(), (offer, request, payer_key))));
+        self.recurrence_invoices.write().unwrap()
+            .extend(store.recurrence_invoices().map_err(map_err)?.into_iter()
+                .filter_map(|invoice| Some((Self::recurrence_key(&invoice)?, invoice))));
+        
+        *self.offer_store.write().unwrap() = Some(store);
+        Ok(())
+    }
+    
+    /// Create and sign a reusable offer
+    pub fn create_offer(
+        &self,
+        description: &str,
+        amount_msat: Option<u64>,
+        issuer: Option<String>,
+        quantity_max: Option<u64>,
+        absolute_expiry: Option<u64>,
+        recurrence: Option<Recurrence>,
+    ) -> Result<OfferResponse, ManagerError> {
+        info!("Creating offer: {}", description);
+        
+        let node_key = self.node_key();
+        let mut offer = Offer::new(description.to_string(), amount_msat, self.get_node_id(), &node_key)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        if let Some(issuer) = issuer {
+            offer.set_issuer(issuer, &node_key)
+                .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        }
+        if let Some(quantity_max) = quantity_max {
+            offer.set_quantity_max(quantity_max, &node_key)
+                .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        }
+        if let Some(expiry) = absolute_expiry {
+            offer.set_absolute_expiry(expiry, &node_key)
+                .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        }
+        if let Some(recurrence) = recurrence {
+            offer.set_recurrence(recurrence, &node_key)
+                .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        }
+        offer.set_blinded_paths(self.build_blinded_paths()?, &node_key)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        let response = Self::offer_to_response(&offer)?;
+        
+        if let Some(store) = self.offer_store.read().unwrap().as_ref() {
+            store.save_offer(&offer)
+                .map_err(|e| ManagerError::OfferStoreError(e.to_string()))?;
+        }
+        {
+            let mut offers = self.offers.write().unwrap();
+            offers.insert(offer.id(), offer);
+        }
+        
+        Ok(response)
+    }
+    
+    /// List offers published by this node
+    pub fn list_offers(&self) -> Result<Vec<OfferResponse>, ManagerError> {
+        let offers = self.offers.read().unwrap();
+        
+        let mut result = offers.values()
+            .map(Self::offer_to_response)
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        result.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        
+        Ok(result)
+    }
+    
+    /// Build an invoice request for someone else's offer (payer side)
+    ///
+    /// Returns the encoded request, to be delivered to the offer's issuer.
+    pub fn request_invoice(
+        &self,
+        offer: &str,
+        amount_msat: Option<u64>,
+        quantity: Option<u64>,
+        recurrence_counter: Option<u32>,
+        payer_note: Option<String>,
+    ) -> Result<String, ManagerError> {
+        let offer = Offer::decode(offer)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        // Periods of a recurring offer are paid with the same payer key so the
+        // issuer can tell its payers apart. Other requests get a fresh key so
+        // payments cannot be linked
+        let recurring_key = recurrence_counter.and_then(|_| {
+            self.outgoing_invoice_requests.read().unwrap().values()
+                .find(|(known, request, _)| known.id() == offer.id() && request.recurrence_counter().is_some())
+                .map(|(_, _, payer_key)| payer_key.clone())
+        });
+        let payer_key = match recurring_key {
+            Some(payer_key) => payer_key,
+            None => QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium))
+                .map_err(|e| ManagerError::QuantumSecurityError(e.to_string()))?,
+        };
+        
+        let request = InvoiceRequest::new(&offer, amount_msat, quantity, recurrence_counter, payer_note, &payer_key)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        let encoded = request.encode()
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        if let Some(store) = self.offer_store.read().unwrap().as_ref() {
+            store.save_invoice_request(&offer, &request, &payer_key)
+                .map_err(|e| ManagerError::OfferStoreError(e.to_string()))?;
+        }
+        {
+            let mut requests = self.outgoing_invoice_requests.write().unwrap();
+            requests.insert(request.id(), (offer, request, payer_key));
+        }
+        
+        Ok(encoded)
+    }
+    
+    /// Answer an invoice request for one of our offers (issuer side)
+    ///
+    /// Returns the encoded invoice. The preimage is kept so the payment can be settled.
+    /// A payer asking again for a period of a recurring offer gets the invoice
+    /// already issued for it rather than a second one.
+    pub fn handle_invoice_request(&self, invoice_request: &str) -> Result<String, ManagerError> {
+        let request = InvoiceRequest::decode(invoice_request)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        let offer = {
+            let offers = self.offers.read().unwrap();
+            offers.get(&request.offer_id()).cloned()
+                .ok_or_else(|| ManagerError::InvalidPaymentRequest(
+                    format!("Unknown offer: {}", request.offer_id())
+                ))?
+        };
+        
+        // Held until the new invoice is recorded so a period is only invoiced once
+        let mut recurrence_invoices = self.recurrence_invoices.write().unwrap();
+        let recurrence_key = request.recurrence_counter()
+            .map(|counter| (offer.id(), request.payer_key().to_vec(), counter));
+        if let Some(issued) = recurrence_key.as_ref().and_then(|key| recurrence_invoices.get(key)) {
+            // Without the preimage, lost on restart, the issued invoice cannot be settled
+            if !self.invoices.read().unwrap().contains_key(&issued.payment_hash()) {
+                return Err(ManagerError::InvalidPaymentRequest(format!(
+                    "Recurrence period {} was already invoiced", issued.recurrence_counter().unwrap_or_default()
+                )));
+            }
+            return issued.encode()
+                .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()));
+        }
+        
+        let preimage = PaymentPreimage::new_random();
+        let payment_hash = preimage.payment_hash();
+        
+        let offer_invoice = OfferInvoice::for_request(
+            &offer,
+            &request,
+            payment_hash,
+            crate::lightning::invoice::DEFAULT_OFFER_INVOICE_EXPIRY,
+            self.build_blinded_paths()?,
+            &self.node_key(),
+        ).map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        let invoice = Invoice::new(
+            payment_hash,
+            offer_invoice.amount_msat(),
+            offer.description().to_string(),
+            offer_invoice.relative_expiry(),
+            false,
+            self.get_node_id(),
+            preimage,
+        );
+        
+        {
+            let mut invoices = self.invoices.write().unwrap();
+            invoices.insert(payment_hash, invoice);
+        }
+        self.invoice_index.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        
+        let _ = self.event_sender.send(LightningEvent::InvoiceCreated(payment_hash));
+        
+        if let Some(key) = recurrence_key {
+            if let Some(store) = self.offer_store.read().unwrap().as_ref() {
+                store.save_recurrence_invoice(&offer_invoice)
+                    .map_err(|e| ManagerError::OfferStoreError(e.to_string()))?;
+            }
+            recurrence_invoices.insert(key, offer_invoice.clone());
+        }
+        
+        offer_invoice.encode()
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))
+    }
+    
+    /// Pay an invoice received in response to one of our invoice requests
+    pub async fn pay_offer_invoice(
+        &self,
+        offer_invoice: &str,
+        fee_limit_msat: Option<u64>,
+    ) -> Result<PaymentResponse, ManagerError> {
+        let offer_invoice = self.verify_offer_invoice(offer_invoice)?;
+        
+        let parsed = ParsedInvoice {
+            payment_hash: offer_invoice.payment_hash(),
+            amount_msat: offer_invoice.amount_msat(),
+            destination: offer_invoice.destination().to_string(),
+            blinded_paths: offer_invoice.blinded_paths().to_vec(),
+            expiry: offer_invoice.relative_expiry(),
+            description: String::new(),
+        };
+        
+        let response = self.pay_parsed_invoice(&parsed, parsed.amount_msat, fee_limit_msat, self.router.preferences()).await?;
+        
+        {
+            let mut offer_invoices = self.offer_invoices.write().unwrap();
+            offer_invoices.insert(offer_invoice.payment_hash(), offer_invoice);
+        }
+        
+        Ok(response)
+    }
+    
+    /// Decode an invoice and check it answers one of our invoice requests
+    fn verify_offer_invoice(&self, offer_invoice: &str) -> Result<OfferInvoice, ManagerError> {
+        let offer_invoice = OfferInvoice::decode(offer_invoice)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        let requests = self.outgoing_invoice_requests.read().unwrap();
+        let (offer, request, _) = requests.get(offer_invoice.invoice_request_id())
+            .ok_or_else(|| ManagerError::InvalidPaymentRequest(
+                "Invoice does not answer any of our invoice requests".to_string()
+            ))?;
+        
+        offer_invoice.verify_for_request(offer, request)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        Ok(offer_invoice)
+    }
+    
+    /// Create a proof that we paid an offer invoice
+    pub fn create_payer_proof(&self, payment_hash: &str, note: Option<String>) -> Result<String, ManagerError> {
+        let payment_hash = PaymentHash::from_hex(payment_hash)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        let offer_invoice = self.offer_invoices.read().unwrap()
+            .get(&payment_hash)
+            .cloned()
+            .ok_or_else(|| ManagerError::PaymentNotFound(payment_hash.to_hex()))?;
+        
+        let preimage = self.payments.read().unwrap()
+            .get(&payment_hash)
+            .filter(|p| p.status == PaymentStatus::Succeeded)
+            .and_then(|p| p.payment_preimage)
+            .ok_or_else(|| ManagerError::PaymentNotFound(payment_hash.to_hex()))?;
+        
+        let requests = self.outgoing_invoice_requests.read().unwrap();
+        let (_, _, payer_key) = requests.get(offer_invoice.invoice_request_id())
+            .ok_or_else(|| ManagerError::PaymentNotFound(payment_hash.to_hex()))?;
+        
+        let proof = PayerProof::new(offer_invoice, preimage, note, payer_key)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
+        
+        proof.encode()
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))
+    }
+    
+    /// Build blinded paths to this node
+    ///
+    /// Each connected peer with a known KEM key and an active channel to us becomes
+    /// the introduction node of a two-hop path. Without such peers we fall back to a
+    /// single-hop path with ourselves as the introduction node.
+    fn build_blinded_paths(&self) -> Result<Vec<BlindedPath>, ManagerError> {
+        let node_id = self.get_node_id();
+        let max_cltv_expiry = self.get_current_height() as u32 + 2016;
+        
+        let mut path_id = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut path_id);
+        
+        let mut forwarders = Vec::new();
+        {
+            let channels = self.channels.read().unwrap();
+            let peers = self.peers.read().unwrap();
+            let short_channel_ids = self.short_channel_ids.read().unwrap();
+            let peer_policies = self.peer_routing_policies.read().unwrap();
+            
+            for (channel_id, atomic_channel) in channels.iter() {
+                let channel = match atomic_channel.channel.lock() {
+                    Ok(channel) => channel,
+                    Err(_) => continue,
+                };
+                
+                if channel.state != ChannelState::Active {
+                    continue;
+                }
+                
+                let peer_id = hex::encode(channel.remote_node_id.serialize());
+                let kem_public_key = match peers.get(&peer_id) {
+                    Some(PeerInfo { connected: true, kem_public_key: Some(key), .. }) => key.clone(),
+                    _ => continue,
+                };
+                
+                // The peer forwards to us under the policy it advertises for the channel
+                let short_channel_id = match short_channel_ids.get(channel_id) {
+                    Some(short_channel_id) => *short_channel_id,
+                    None => continue,
+                };
+                let policy = match peer_policies.get(channel_id) {
+                    Some(policy) if !policy.disabled => policy,
+                    _ => continue,
+                };
+                let relay = match (
+                    u16::try_from(policy.time_lock_delta),
+                    u32::try_from(policy.fee_rate_milli_msat),
+                    u32::try_from(policy.fee_base_msat),
+                ) {
+                    (Ok(cltv_expiry_delta), Ok(fee_proportional_millionths), Ok(fee_base_msat)) => PaymentRelay {
+                        cltv_expiry_delta,
+                        fee_proportional_millionths,
+                        fee_base_msat,
+                    },
+                    _ => continue,
+                };
+                
+                forwarders.push(BlindedForwardNode {
+                    node_id: peer_id,
+                    kem_public_key,
+                    short_channel_id,
+                    relay,
+                    htlc_minimum_msat: policy.min_htlc.max(channel.min_htlc_value_novas * 1000),
+                });
+            }
+        }
+        
+        let mut paths = Vec::new();
+        for forwarder in forwarders.iter().take(3) {
+            let path = BlindedPath::new(
+                std::slice::from_ref(forwarder),
+                &node_id,
+                &self.kem_keypair.public_key,
+                path_id,
+                max_cltv_expiry,
+                self.hop_kem.as_ref(),
+            ).map_err(|e| ManagerError::NetworkError(e.to_string()))?;
+            paths.push(path);
+        }
+        
+        if paths.is_empty() {
+            let path = BlindedPath::new(&[], &node_id, &self.kem_keypair.public_key, path_id, max_cltv_expiry, self.hop_kem.as_ref())
+                .map_err(|e| ManagerError::NetworkError(e.to_string()))?;
+            paths.push(path);
+        }
+        
+        self.blinded_path_ids.write().unwrap().insert(path_id);
+        Ok(paths)
+    }
+    
+    fn recurrence_key(invoice: &OfferInvoice) -> Option<RecurrenceKey> {
+        Some((invoice.offer_id(), invoice.payer_key().to_vec(), invoice.recurrence_counter()?))
+    }
+    
+    fn offer_to_response(offer: &Offer) -> Result<OfferResponse, ManagerError> {
+        Ok(OfferResponse {
+            offer: offer.encode().map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?,
+            offer_id: offer.id().to_hex(),
+            description: offer.description().to_string(),
+            amount_msat: offer.amount_msat(),
+            recurring: offer.recurrence().is_some(),
+            created_at: offer.created_at(),
+            expired: offer.is_expired(),
+        })
+    }
+    
+    // Helper methods
+    fn message_factory(&self) -> MessageFactory {
+        let factory = MessageFactory::new(self.get_node_id(), self.node_key.read().unwrap().secret_key.clone());
+        if self.trampoline_relay.read().unwrap().is_some() {
+            factory.with_feature(FEATURE_TRAMPOLINE_ROUTING)
+        } else {
+            factory
+        }
+    }
+    
+    fn node_key(&self) -> QuantumKeyPair {
+        self.node_key.read().unwrap().clone()
+    }
+    
+    fn get_node_id(&self) -> String {
+        // In a real implementation, this would return the node's public key
+        LOCAL_NODE_ID.to_string()
+    }
+    
+    fn channel_to_lightning_channel(&self, atomic_channel: &Arc<AtomicChannel>) -> LightningChannel {
+        // Get channel info atomically
+        let channel_info = atomic_channel.get_channel_info()
+            .unwrap_or_else(|_| panic!("Failed to get channel info"));
+        
+        // Get the underlying channel for detailed info
+        let channel = atomic_channel.channel.lock()
+            .unwrap_or_else(|_| panic!("Failed to lock channel"));
+        
+        // Calculate actual statistics from channel data
+        let total_novas_sent = channel.pending_htlcs.iter()
+            .filter(|htlc| htlc.is_outgoing)
+            .map(|htlc| htlc.amount_novas)
+            .sum::<u64>();
+            
+        let total_novas_received = channel.pending_htlcs.iter()
+            .filter(|htlc| !htlc.is_outgoing)
+            .map(|htlc| htlc.amount_novas)
+            .sum::<u64>();
+            
+        let num_updates = channel_info.commitment_number;
+        
+        // Calculate uptime
+        let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let uptime = current_time - channel_info.last_operation_time;
+        
+        LightningChannel {
+            channel_id: hex::encode(channel_info.channel_id),
+            remote_pubkey: hex::encode(channel.remote_node_id.serialize()),
+            capacity: channel_info.capacity_novas,
+            local_balance: channel_info.local_balance_novas,
+            remote_balance: channel_info.remote_balance_novas,
+            commit_fee: 1000, // Standard commitment fee
+            commit_weight: 724, // Standard commitment weight
+            fee_per_kw: 2500, // Standard fee per kiloweight
+            unsettled_balance: channel.pending_htlcs.iter()
+                .map(|htlc| htlc.amount_novas)
+                .sum(),
+            total_satoshis_sent: total_novas_sent,
+            total_satoshis_received: total_novas_received,
+            num_updates,
+            pending_htlcs: channel.pending_htlcs.iter().map(|htlc| PendingHTLC {
+                incoming: !htlc.is_outgoing,
+                amount: htlc.amount_novas,
+                outpoint: format!("{}:{}", hex::encode(htlc.payment_hash), htlc.id),
+                maturity_height: htlc.expiry_height,
+                blocks_til_maturity: htlc.expiry_height as i32 - self.get_current_height() as i32,
+                stage: 1, // Simplified stage
+            }).collect(),
+            csv_delay: channel.to_self_delay as u32,
+            private: !channel.is_public,
+            initiator: channel.is_initiator,
+            chan_status_flags: "ChanStatusDefault".to_string(),
+            local_chan_reserve_sat: channel.channel_reserve_novas,
+            remote_chan_reserve_sat: channel.channel_reserve_novas,
+            static_remote_key: false,
             commitment_type: "ANCHORS".to_string(),
             lifetime: uptime,
             uptime,
@@ -1196,6 +2856,7 @@ impl LightningManager {
             payment_hash: crate::lightning::payment::PaymentHash::new([0u8; 32]), // Placeholder
             amount_msat: 1000000, // Placeholder
             destination: "destination_node".to_string(),
+            blinded_paths: Vec::new(),
             expiry: 3600,
             description: "Test payment".to_string(),
         })
@@ -1238,9 +2899,14 @@ impl LightningManager {
         Ok(payment_request)
     }
     
-    async fn send_payment_through_route(&self, route: &crate::lightning::router::PaymentPath, invoice: &ParsedInvoice) -> Result<crate::lightning::payment::PaymentPreimage, ManagerError> {
+    async fn send_payment_through_route(
+        &self,
+        route: &crate::lightning::router::PaymentPath,
+        blinded_payloads: &[PerHopPayload],
+        invoice: &ParsedInvoice,
+    ) -> Result<crate::lightning::payment::PaymentPreimage, ManagerError> {
         // Simplified payment sending - in production would handle onion routing
-        info!("Sending payment through route with {} hops", route.hops.len());
+        info!("Sending payment through route with {} hops and {} blinded hops", route.hops.len(), blinded_payloads.len());
         
         // For now, just return a random preimage
         Ok(crate::lightning::payment::PaymentPreimage::new_random())
@@ -1309,6 +2975,53 @@ impl LightningManager {
         }
     }
     
+    /// Pay ourselves along a circular route
+    ///
+    /// The route must leave through one of our channels and come back through
+    /// another, as built by `Router::find_circular_route`. Liquidity moves from
+    /// the first channel to the last; returns the fee paid in millinovas.
+    pub fn pay_circular_route(&self, route: &crate::lightning::router::PaymentPath) -> Result<u64, ManagerError> {
+        let (first, last) = match (route.hops.first(), route.hops.last()) {
+            (Some(first), Some(last)) if route.hops.len() >= 2 => (first, last),
+            _ => return Err(ManagerError::RouterError("Circular route needs at least two hops".to_string())),
+        };
+        
+        let (outgoing, incoming) = {
+            let channels = self.channels.read().unwrap();
+            let outgoing = channels.get(&first.channel_id).cloned()
+                .ok_or_else(|| ManagerError::ChannelNotFound(first.channel_id.to_hex()))?;
+            let incoming = channels.get(&last.channel_id).cloned()
+                .ok_or_else(|| ManagerError::ChannelNotFound(last.channel_id.to_hex()))?;
+            (outgoing, incoming)
+        };
+        
+        let preimage = PaymentPreimage::new_random();
+        let payment_hash = preimage.payment_hash().into_inner();
+        let expiry_height = (self.get_current_height() + route.total_cltv_delta as u64) as u32;
+        let sent_novas = (route.total_amount_msat + 999) / 1000;
+        let received_novas = last.amount_msat / 1000;
+        
+        let outgoing_htlc = outgoing.add_htlc(payment_hash, sent_novas, expiry_height, true)
+            .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+        let incoming_htlc = match incoming.add_htlc(payment_hash, received_novas, expiry_height, false) {
+            Ok(id) => id,
+            Err(e) => {
+                let _ = outgoing.fail_htlc(outgoing_htlc, "circular payment not received");
+                return Err(ManagerError::ChannelError(e.to_string()));
+            }
+        };
+        
+        incoming.settle_htlc(incoming_htlc, *preimage.as_bytes())
+            .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+        outgoing.settle_htlc(outgoing_htlc, *preimage.as_bytes())
+            .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+        
+        info!("Moved {} novas from channel {} to {} over {} hops, fee {} msat",
+            received_novas, first.channel_id.to_hex(), last.channel_id.to_hex(), route.hops.len(), route.total_fee_msat);
+        
+        Ok(route.total_fee_msat)
+    }
+    
     /// Get the current blockchain height
     fn get_current_height(&self) -> u64 {
         // In a real implementation, this would query the blockchain state
@@ -1378,6 +3091,7 @@ struct ParsedInvoice {
     payment_hash: crate::lightning::payment::PaymentHash,
     amount_msat: u64,
     destination: String,
+    blinded_paths: Vec<BlindedPath>,
     expiry: u32,
     description: String,
 }
@@ -1409,6 +3123,27 @@ pub struct InvoiceResponse {
     pub add_index: u64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldInvoiceResponse {
+    pub payment_hash: String,
+    pub state: String,
+    pub value_msat: u64,
+    pub amount_received_msat: u64,
+    pub held_htlcs: usize,
+    pub cancel_height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfferResponse {
+    pub offer: String,
+    pub offer_id: String,
+    pub description: String,
+    pub amount_msat: Option<u64>,
+    pub recurring: bool,
+    pub created_at: u64,
+    pub expired: bool,
+}
+
 // Error conversions
 impl From<LightningNetworkError> for ManagerError {
     fn from(err: LightningNetworkError) -> Self {
@@ -1420,4 +3155,532 @@ impl From<crate::lightning::QuantumSecurityError> for ManagerError {
     fn from(err: crate::lightning::QuantumSecurityError) -> Self {
         ManagerError::QuantumSecurityError(err.to_string())
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightning::interactive_tx::FundingInput;
+    use crate::types::transaction::OutPoint;
+
+    fn manager() -> LightningManager {
+        let wallet = LightningWallet::new_test_wallet(10_000_000);
+        LightningManager::new(LightningConfig::default(), wallet).unwrap().0
+    }
+
+    fn contribution(funding_novas: u64, input_value: u64, seed: u8) -> FundingContribution {
+        FundingContribution {
+            funding_novas,
+            inputs: vec![FundingInput {
+                outpoint: OutPoint { txid: [seed; 32], vout: 1 },
+                value_novas: input_value,
+            }],
+            change_script: vec![0x00, 0x14, seed],
+        }
+    }
+
+    /// Deliver messages back and forth until both managers go quiet
+    fn exchange(alice: &LightningManager, bob: &LightningManager, first: Message) {
+        let mut to_bob = vec![first];
+        let mut to_alice = Vec::new();
+        while !to_bob.is_empty() || !to_alice.is_empty() {
+            for message in to_bob.drain(..) {
+                to_alice.extend(bob.handle_dual_funding_message(&message).unwrap());
+            }
+            for message in to_alice.drain(..) {
+                to_bob.extend(alice.handle_dual_funding_message(&message).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_dual_funded_channel_between_managers() {
+        let alice = manager();
+        let bob = manager();
+        bob.set_dual_funding_contribution(Some(contribution(300_000, 320_000, 2)));
+
+        let open = alice.open_dual_funded_channel("bob", contribution(500_000, 800_000, 1), 253).unwrap();
+        let channel_id = open.channel_id.clone().unwrap();
+        exchange(&alice, &bob, open);
+
+        let alice_channel = alice.get_channel(&channel_id.to_hex()).unwrap().unwrap();
+        let bob_channel = bob.get_channel(&channel_id.to_hex()).unwrap().unwrap();
+        assert_eq!(alice_channel.capacity, 800_000);
+        assert_eq!(alice_channel.local_balance, 500_000);
+        assert_eq!(alice_channel.remote_balance, 300_000);
+        assert_eq!(bob_channel.local_balance, 300_000);
+        assert!(alice_channel.initiator);
+        assert!(!bob_channel.initiator);
+
+        let first_txid = alice.get_dual_funding_candidates(&channel_id.to_hex()).unwrap()[0].outpoint.txid;
+
+        // Replace the funding transaction at a higher fee rate
+        let rbf = alice.bump_dual_funded_channel(&channel_id.to_hex(), 1_000).unwrap();
+        exchange(&alice, &bob, rbf);
+
+        let candidates = bob.get_dual_funding_candidates(&channel_id.to_hex()).unwrap();
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].outpoint.txid, first_txid);
+        let replacement_txid = candidates[1].outpoint.txid;
+
+        // The replacement confirms on both sides
+        for node in [&alice, &bob] {
+            node.dual_funding_confirmed(&channel_id.to_hex(), &hex::encode(replacement_txid)).unwrap();
+            assert_eq!(node.get_channels(false, false).unwrap().len(), 1);
+            assert_eq!(node.get_info().unwrap().num_pending_channels, 0);
+            assert!(node.get_dual_funding_candidates(&channel_id.to_hex()).is_err());
+        }
+    }
+
+    /// Add an active channel with the given balances, returning its ID
+    fn add_active_channel(manager: &LightningManager, seed: u8, local: u64, remote: u64) -> ChannelId {
+        let mut channel = Channel::new(ChannelPublicKey::from_bytes([1u8; 33]), ChannelPublicKey::from_bytes([seed; 33]), local + remote, true, false);
+        channel.channel_id = [seed; 32];
+        channel.state = ChannelState::Active;
+        channel.local_balance_novas = local;
+        channel.remote_balance_novas = remote;
+
+        let channel_id = ChannelId::from_bytes([seed; 32]);
+        manager.channels.write().unwrap().insert(channel_id.clone(), Arc::new(AtomicChannel::new(channel)));
+        channel_id
+    }
+
+    #[test]
+    fn test_rebalance_through_circular_route() {
+        use crate::lightning::rebalance::{FeePolicyConfig, FeePolicyEngine, Rebalancer, RebalancerConfig};
+        use crate::lightning::router::{ChannelInfo as RouterChannelInfo, NodeId};
+
+        let node = manager();
+        let full = add_active_channel(&node, 1, 900_000, 100_000);
+        let depleted = add_active_channel(&node, 2, 100_000, 900_000);
+
+        // us -> x over the full channel, x -> y, y -> us over the depleted channel
+        let edge = |channel_id: ChannelId, source: &str, destination: &str| RouterChannelInfo {
+            channel_id,
+            source: NodeId::new(source.to_string()),
+            destination: NodeId::new(destination.to_string()),
+            capacity: 1_000_000,
+            base_fee_msat: 1_000,
+            fee_rate_millionths: 100,
+            cltv_expiry_delta: 40,
+            is_active: true,
+            last_update: 0,
+        };
+        let mut router = Router::new();
+        router.set_local_node(NodeId::new("us".to_string()));
+        router.update_channel(edge(full.clone(), "us", "x"), false);
+        router.update_channel(edge(ChannelId::from_bytes([3u8; 32]), "x", "y"), false);
+        router.update_channel(edge(depleted.clone(), "y", "us"), false);
+
+        let rebalancer = Rebalancer::new(RebalancerConfig::default());
+        let results = rebalancer.run(&node, &router).unwrap();
+        assert_eq!(results.len(), 1);
+        let result = results[0].as_ref().unwrap();
+        assert_eq!(result.amount_novas, 400_000);
+        assert_eq!(result.hops, 3);
+        assert!(result.fee_msat <= rebalancer.fee_budget_msat(400_000));
+
+        let full_after = node.get_channel(&full.to_hex()).unwrap().unwrap();
+        let depleted_after = node.get_channel(&depleted.to_hex()).unwrap().unwrap();
+        assert_eq!(depleted_after.local_balance, 500_000);
+        assert_eq!(full_after.local_balance, 900_000 - 400_000 - (result.fee_msat + 999) / 1000);
+
+        // Nothing left to do once both channels are balanced
+        assert!(rebalancer.run(&node, &router).unwrap().is_empty());
+
+        // A budget below the route fee refuses to pay
+        add_active_channel(&node, 4, 950_000, 50_000);
+        let stingy = Rebalancer::new(RebalancerConfig { max_fee_ppm: 1, ..Default::default() });
+        let candidate = crate::lightning::rebalance::RebalanceCandidate {
+            outgoing: full.to_hex(),
+            incoming: depleted.to_hex(),
+            amount_novas: 50_000,
+        };
+        assert!(matches!(
+            stingy.execute(&node, &router, &candidate),
+            Err(crate::lightning::rebalance::RebalanceError::FeeBudgetExceeded { .. })
+        ));
+
+        // Policies are published for every active channel
+        let mut engine = FeePolicyEngine::new(FeePolicyConfig::default());
+        let updated = engine.apply(&node).unwrap();
+        assert_eq!(updated.len(), 3);
+        let policy = node.get_routing_policy(&full.to_hex()).unwrap().unwrap();
+        assert!(policy.fee_rate_milli_msat > 0);
+        assert!(node.get_routing_policy(&hex::encode([9u8; 32])).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_forwarding_ledger_records_forwards() {
+        let node = manager();
+        let dir = tempfile::tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        node.set_forwarding_ledger(ForwardingLedger::new(db.open_tree("forwards").unwrap()).unwrap());
+
+        let incoming = add_active_channel(&node, 1, 500_000, 500_000);
+        let outgoing = add_active_channel(&node, 2, 500_000, 500_000);
+
+        // A forward that settles earns the difference
+        let preimage = PaymentPreimage::new([7u8; 32]);
+        let hash = preimage.payment_hash().to_hex();
+        node.forward_htlc(&incoming.to_hex(), &outgoing.to_hex(), &hash, 101_000_000, 100_000_000, 800_000).unwrap();
+        assert_eq!(node.get_channel(&outgoing.to_hex()).unwrap().unwrap().local_balance, 400_000);
+        assert_eq!(node.settle_forward(&preimage.to_hex()).unwrap(), 1_000_000);
+        assert_eq!(node.get_channel(&incoming.to_hex()).unwrap().unwrap().local_balance, 601_000);
+
+        // A forward failed downstream earns nothing and returns the balance
+        let failed = PaymentPreimage::new([8u8; 32]).payment_hash().to_hex();
+        node.forward_htlc(&incoming.to_hex(), &outgoing.to_hex(), &failed, 50_500_000, 50_000_000, 800_000).unwrap();
+        node.fail_forward(&failed, "incorrect payment details").unwrap();
+        assert_eq!(node.get_channel(&outgoing.to_hex()).unwrap().unwrap().local_balance, 400_000);
+
+        // Our policy refuses an underpaying forward up front
+        let policy = RoutingPolicy {
+            time_lock_delta: 40,
+            min_htlc: 1_000,
+            fee_base_msat: 1_000,
+            fee_rate_milli_msat: 1_000,
+            disabled: false,
+            max_htlc_msat: 400_000_000,
+            last_update: 0,
+        };
+        node.set_routing_policy(&outgoing.to_hex(), policy).unwrap();
+        let cheap = PaymentPreimage::new([9u8; 32]).payment_hash().to_hex();
+        assert!(node.forward_htlc(&incoming.to_hex(), &outgoing.to_hex(), &cheap, 10_005_000, 10_000_000, 800_000).is_err());
+
+        let forwards = node.get_forwards(0, u64::MAX).unwrap();
+        assert_eq!(forwards.len(), 3);
+        assert_eq!(forwards.iter().filter(|f| f.status == ForwardStatus::Failed).count(), 2);
+        assert!(forwards.iter().any(|f| f.failure_reason.as_deref().unwrap_or("").starts_with("fee insufficient")));
+
+        let report = node.get_forwarding_report(0, u64::MAX).unwrap();
+        assert_eq!((report.settled, report.failed, report.total_fees_msat), (1, 2, 1_000_000));
+        let earnings = report.channels.iter().find(|c| c.channel_id == outgoing.to_hex()).unwrap();
+        assert_eq!((earnings.forwards_out, earnings.fees_msat, earnings.failures), (1, 1_000_000, 2));
+        assert_eq!(report.peers.len(), 1);
+        assert_eq!(report.peers[0].peer, hex::encode([2u8; 33]));
+    }
+
+    #[test]
+    fn test_blinded_paths_use_peer_policy_and_short_channel_id() {
+        let node = manager();
+        let channel_id = add_active_channel(&node, 7, 500_000, 500_000);
+        node.atomic_channel(&channel_id).unwrap().channel.lock().unwrap().funding_outpoint = Some(OutPoint { txid: [7u8; 32], vout: 1 });
+        let peer_id = hex::encode([7u8; 33]);
+        node.peers.write().unwrap().insert(peer_id.clone(), PeerInfo {
+            node_id: peer_id.clone(),
+            address: "127.0.0.1:9735".to_string(),
+            connected: true,
+            last_seen: SystemTime::now(),
+            features: Vec::new(),
+            kem_public_key: Some(node.kem_keypair.public_key.clone()),
+        });
+
+        // Until the funding is buried and the peer advertised its policy, we introduce our own paths
+        let paths = node.build_blinded_paths().unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].introduction_node_id, node.get_node_id());
+
+        let short_channel_id = node.set_short_channel_id(&channel_id.to_hex(), 800_000, 12).unwrap();
+        assert_eq!(short_channel_id, (800_000 << 40) | (12 << 16) | 1);
+        assert_eq!(node.get_short_channel_id(&channel_id.to_hex()).unwrap(), Some(short_channel_id));
+
+        let mut update = ChannelUpdate {
+            signature: Vec::new(),
+            chain_hash: Vec::new(),
+            chan_id: short_channel_id,
+            timestamp: 1_700_000_000,
+            message_flags: 1,
+            channel_flags: 0,
+            time_lock_delta: 80,
+            htlc_minimum_msat: 5_000,
+            base_fee: 2_000,
+            fee_rate: 100,
+            htlc_maximum_msat: 400_000_000,
+            extra_opaque_data: Vec::new(),
+        };
+
+        // Our own side of the channel is not the peer's policy
+        node.handle_channel_update(&update).unwrap();
+        assert_eq!(node.build_blinded_paths().unwrap()[0].introduction_node_id, node.get_node_id());
+
+        // The peer has the greater node ID, so its updates set bit 0
+        update.channel_flags = 1;
+        node.handle_channel_update(&update).unwrap();
+        let paths = node.build_blinded_paths().unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].introduction_node_id, peer_id);
+        assert_eq!(paths[0].payinfo.fee_base_msat, 2_000);
+        assert_eq!(paths[0].payinfo.fee_proportional_millionths, 100);
+        assert_eq!(paths[0].payinfo.htlc_minimum_msat, 1_000_000);
+
+        // Stale updates are ignored, a disabled channel is not offered
+        update.base_fee = 1;
+        node.handle_channel_update(&update).unwrap();
+        assert_eq!(node.build_blinded_paths().unwrap()[0].payinfo.fee_base_msat, 2_000);
+        update.timestamp += 1;
+        update.channel_flags = 3;
+        node.handle_channel_update(&update).unwrap();
+        assert_eq!(node.build_blinded_paths().unwrap()[0].introduction_node_id, node.get_node_id());
+    }
+
+    #[test]
+    fn test_blinded_htlcs_are_unblinded_by_their_hops() {
+        use crate::lightning::test_utils::TestKem;
+
+        fn test_kem_manager() -> LightningManager {
+            let mut node = manager();
+            node.hop_kem = Box::new(TestKem);
+            node.kem_keypair.secret_key = node.kem_keypair.public_key.clone();
+            node
+        }
+        let forwarder = test_kem_manager();
+        let recipient = test_kem_manager();
+
+        // The forwarder's channel to the recipient is buried at 800_000:3:1
+        let incoming = add_active_channel(&forwarder, 2, 500_000, 500_000);
+        let outgoing = add_active_channel(&forwarder, 7, 500_000, 500_000);
+        forwarder.atomic_channel(&outgoing).unwrap().channel.lock().unwrap().funding_outpoint = Some(OutPoint { txid: [7u8; 32], vout: 1 });
+        let short_channel_id = forwarder.set_short_channel_id(&outgoing.to_hex(), 800_000, 3).unwrap();
+        let into_recipient = add_active_channel(&recipient, 7, 500_000, 500_000);
+
+        let path_id = [5u8; 32];
+        recipient.blinded_path_ids.write().unwrap().insert(path_id);
+        let forward_node = BlindedForwardNode {
+            node_id: forwarder.get_node_id(),
+            kem_public_key: forwarder.kem_keypair.public_key.clone(),
+            short_channel_id,
+            relay: PaymentRelay { cltv_expiry_delta: 40, fee_proportional_millionths: 100, fee_base_msat: 1_000 },
+            htlc_minimum_msat: 1_000,
+        };
+        let path = BlindedPath::new(&[forward_node], &recipient.get_node_id(), &recipient.kem_keypair.public_key, path_id, 702_016, &TestKem).unwrap();
+        let invoice = recipient.create_invoice(100_000_000, "blinded", 3600, false).unwrap();
+        let amount_in = 100_000_000 + path.payinfo.fee_msat(100_000_000);
+
+        // Only the forwarder can read its hop, which must be within the path's constraints
+        assert!(recipient.receive_blinded_htlc(&into_recipient.to_hex(), &invoice.payment_hash, amount_in, 701_000, 700_000, &path.hops[0]).is_err());
+        let error = forwarder.forward_blinded_htlc(&incoming.to_hex(), &invoice.payment_hash, amount_in, 702_017, &path.hops[0]).unwrap_err();
+        assert!(error.to_string().contains("beyond blinded path limit"), "{}", error);
+
+        // The forwarder relays over the channel and with the fee and delta named by its hop
+        forwarder.forward_blinded_htlc(&incoming.to_hex(), &invoice.payment_hash, amount_in, 701_000, &path.hops[0]).unwrap();
+        let htlc = forwarder.atomic_channel(&outgoing).unwrap().channel.lock().unwrap().pending_htlcs[0].clone();
+        assert_eq!((htlc.amount_novas, htlc.expiry_height), (100_000, 700_960));
+
+        // The recipient takes the HTLC on the last hop, and the forwarder earns its fee
+        assert!(forwarder.receive_blinded_htlc(&outgoing.to_hex(), &invoice.payment_hash, 100_000_000, 700_960, 700_000, &path.hops[1]).is_err());
+        let preimage = recipient.receive_blinded_htlc(&into_recipient.to_hex(), &invoice.payment_hash, 100_000_000, 700_960, 700_000, &path.hops[1]).unwrap().unwrap();
+        assert_eq!(forwarder.settle_forward(&preimage.to_hex()).unwrap(), 11_000);
+
+        // Paths the recipient did not hand out are refused, the ones it builds are not
+        let invoice = recipient.create_invoice(50_000_000, "direct", 3600, false).unwrap();
+        let foreign = BlindedPath::new(&[], &recipient.get_node_id(), &recipient.kem_keypair.public_key, [9u8; 32], 702_016, &TestKem).unwrap();
+        assert!(recipient.receive_blinded_htlc(&into_recipient.to_hex(), &invoice.payment_hash, 50_000_000, 701_000, 700_000, &foreign.hops[0]).is_err());
+        let path = recipient.build_blinded_paths().unwrap().remove(0);
+        assert!(recipient.receive_blinded_htlc(&into_recipient.to_hex(), &invoice.payment_hash, 50_000_000, 701_000, 700_000, &path.hops[0]).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_payments_carry_the_blinded_tail() {
+        use crate::lightning::test_utils::TestKem;
+
+        // A path introduced by the router's own node needs no clear hops
+        let node = manager();
+        let introduction = BlindedForwardNode {
+            node_id: "local".to_string(),
+            kem_public_key: b"local".to_vec(),
+            short_channel_id: 5,
+            relay: PaymentRelay { cltv_expiry_delta: 40, fee_proportional_millionths: 100, fee_base_msat: 1_000 },
+            htlc_minimum_msat: 1_000,
+        };
+        let path = BlindedPath::new(&[introduction], "recipient", b"recipient", [4u8; 32], 800_000, &TestKem).unwrap();
+        let invoice = ParsedInvoice {
+            payment_hash: PaymentHash::new([6u8; 32]),
+            amount_msat: 1_000_000,
+            destination: "recipient".to_string(),
+            blinded_paths: vec![path.clone()],
+            expiry: 3600,
+            description: String::new(),
+        };
+        let response = node.pay_parsed_invoice(&invoice, 1_000_000, None, &node.router_preferences()).await.unwrap();
+
+        // The payment goes over the blinded hops, paying the introduction node the path's fee
+        let fee = path.payinfo.fee_msat(1_000_000);
+        let blinded_node_ids: Vec<String> = path.hops.iter().map(|hop| hex::encode(&hop.blinded_node_id)).collect();
+        assert_eq!(response.payment_route, blinded_node_ids);
+        assert_eq!(response.fee_msat, fee);
+
+        let route = node.payments.read().unwrap()[&invoice.payment_hash].route.clone().unwrap();
+        assert_eq!(route.iter().map(|hop| hop.node_id.clone()).collect::<Vec<_>>(), blinded_node_ids);
+        assert_eq!((route[0].amount_msat, route[0].fee_msat), (1_000_000 + fee, fee));
+        assert_eq!((route[1].amount_msat, route[1].fee_msat), (1_000_000, 0));
+    }
+
+    #[test]
+    fn test_receive_path_holds_hold_invoice_htlcs() {
+        let node = manager();
+        let channel_id = add_active_channel(&node, 1, 500_000, 500_000);
+
+        // A hold invoice's HTLC is held rather than settled
+        let preimage = PaymentPreimage::new([7u8; 32]);
+        let hash = preimage.payment_hash().to_hex();
+        node.add_hold_invoice(&hash, 100_000_000, "held", 3600).unwrap();
+        assert!(node.receive_htlc(&channel_id.to_hex(), &hash, 100_000_000, 800_200, 800_000).unwrap().is_none());
+        let held = node.lookup_hold_invoice(&hash).unwrap();
+        assert_eq!((held.state.as_str(), held.held_htlcs), ("ACCEPTED", 1));
+        assert_eq!(node.get_channel(&channel_id.to_hex()).unwrap().unwrap().local_balance, 500_000);
+
+        // Releasing the preimage settles it into our balance
+        node.settle_hold_invoice(&preimage.to_hex()).unwrap();
+        assert_eq!(node.get_channel(&channel_id.to_hex()).unwrap().unwrap().local_balance, 600_000);
+
+        // A regular invoice is settled as soon as its HTLC arrives
+        let invoice = node.create_invoice(50_000_000, "regular", 3600, false).unwrap();
+        let received = node.receive_htlc(&channel_id.to_hex(), &invoice.payment_hash, 50_000_000, 800_200, 800_000).unwrap();
+        assert_eq!(received.unwrap().payment_hash().to_hex(), invoice.payment_hash);
+        assert_eq!(node.get_channel(&channel_id.to_hex()).unwrap().unwrap().local_balance, 650_000);
+
+        // An HTLC too close to expiry to hold is failed back
+        let late = PaymentPreimage::new([8u8; 32]).payment_hash().to_hex();
+        node.add_hold_invoice(&late, 10_000_000, "late", 3600).unwrap();
+        assert!(node.receive_htlc(&channel_id.to_hex(), &late, 10_000_000, 800_001, 800_000).is_err());
+        assert_eq!(node.lookup_hold_invoice(&late).unwrap().held_htlcs, 0);
+    }
+
+    #[test]
+    fn test_offers_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        let issuer = manager();
+        issuer.set_offer_store(OfferStore::new(db.open_tree("offers").unwrap())).unwrap();
+        let offer = issuer.create_offer("Coffee", Some(5_000), None, None, None, None).unwrap();
+
+        let payer = manager();
+        payer.set_offer_store(OfferStore::new(db.open_tree("payer_offers").unwrap())).unwrap();
+        let request = payer.request_invoice(&offer.offer, None, None, None, None).unwrap();
+
+        // After a restart the issuer still answers with the key the offer was signed with
+        let issuer = manager();
+        issuer.set_offer_store(OfferStore::new(db.open_tree("offers").unwrap())).unwrap();
+        assert_eq!(issuer.list_offers().unwrap().len(), 1);
+        let invoice = issuer.handle_invoice_request(&request).unwrap();
+
+        // And the payer still recognises the invoice as the answer to its request
+        let payer = manager();
+        payer.set_offer_store(OfferStore::new(db.open_tree("payer_offers").unwrap())).unwrap();
+        let invoice = OfferInvoice::decode(&invoice).unwrap();
+        let requests = payer.outgoing_invoice_requests.read().unwrap();
+        let (offer, request, _) = &requests[invoice.invoice_request_id()];
+        invoice.verify_for_request(offer, request).unwrap();
+    }
+
+    #[test]
+    fn test_recurring_periods_are_invoiced_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        let issuer = manager();
+        issuer.set_offer_store(OfferStore::new(db.open_tree("offers").unwrap())).unwrap();
+        let recurrence = Recurrence { period: crate::lightning::RecurrencePeriod::Days(30), limit: None, paywindow_seconds: None };
+        let offer = issuer.create_offer("Subscription", Some(10_000), None, None, None, Some(recurrence)).unwrap();
+        let payer = manager();
+
+        // Asking again for a period returns the invoice already issued for it
+        let first = issuer.handle_invoice_request(&payer.request_invoice(&offer.offer, None, None, Some(0), None).unwrap()).unwrap();
+        let again = issuer.handle_invoice_request(&payer.request_invoice(&offer.offer, None, None, Some(0), None).unwrap()).unwrap();
+        assert_eq!(first, again);
+
+        // The next period gets its own invoice, to the same payer key
+        let next = issuer.handle_invoice_request(&payer.request_invoice(&offer.offer, None, None, Some(1), None).unwrap()).unwrap();
+        let (first, next) = (OfferInvoice::decode(&first).unwrap(), OfferInvoice::decode(&next).unwrap());
+        assert_ne!(first.payment_hash(), next.payment_hash());
+        assert_eq!(first.payer_key(), next.payer_key());
+
+        // After a restart the issued invoice cannot be settled, so the period is refused
+        let issuer = manager();
+        issuer.set_offer_store(OfferStore::new(db.open_tree("offers").unwrap())).unwrap();
+        let error = issuer.handle_invoice_request(&payer.request_invoice(&offer.offer, None, None, Some(0), None).unwrap()).unwrap_err();
+        assert!(error.to_string().contains("already invoiced"), "{}", error);
+    }
+
+    #[test]
+    fn test_offers_messages_are_dispatched_to_the_offer_handlers() {
+        let issuer = manager();
+        let payer = manager();
+        let offer = issuer.create_offer("Coffee", Some(5_000), None, None, None, None).unwrap();
+        let request = payer.request_invoice(&offer.offer, None, None, None, None).unwrap();
+
+        // An invoice request delivered to the issuer is answered over its reply path
+        let (reply_path, path_id) = payer.create_message_reply_path(&[]).unwrap();
+        let introduction_node = reply_path.introduction_node_id.clone();
+        let contents = OnionMessageContents::new().with(TLV_INVOICE_REQUEST, request.clone().into_bytes());
+        match issuer.handle_offers_message(contents.clone(), Some(reply_path), None).unwrap() {
+            OnionMessageAction::Forward { next_node_id, packet: OnionMessagePacket::Blinded { .. } } => {
+                assert_eq!(next_node_id, introduction_node);
+            }
+            other => panic!("expected a reply, got {:?}", other),
+        }
+        assert!(issuer.handle_offers_message(contents, None, None).is_err());
+
+        // The payer keeps an invoice delivered to it once it checks out against the request
+        let invoice = issuer.handle_invoice_request(&request).unwrap();
+        let contents = OnionMessageContents::new().with(TLV_INVOICE, invoice.into_bytes());
+        assert!(matches!(
+            payer.handle_offers_message(contents.clone(), None, Some(path_id)).unwrap(),
+            OnionMessageAction::Receive { path_id: Some(received), .. } if received == path_id
+        ));
+        let invoices = payer.offer_invoices.read().unwrap();
+        assert_eq!(invoices.len(), 1);
+        assert_eq!(invoices.values().next().unwrap().offer_id().to_hex(), offer.offer_id);
+
+        // Invoices for requests we never sent are refused
+        assert!(manager().handle_offers_message(contents, None, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_and_ack_backs_up_counterparty_commitment() {
+        use crate::lightning::tower::{TowerServer, TowerServerConfig};
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let tower_addr = listener.local_addr().unwrap();
+        let tower = Arc::new(TowerServer::new(TowerServerConfig::default()));
+        tokio::spawn(Arc::clone(&tower).serve(listener));
+
+        let node = manager();
+        node.add_watchtower(tower_addr, SessionPolicy::default());
+
+        let mut channel = Channel::new(ChannelPublicKey::from_bytes([1u8; 33]), ChannelPublicKey::from_bytes([2u8; 33]), 1_000_000, true, false);
+        channel.channel_id = [5u8; 32];
+        channel.state = ChannelState::Active;
+        channel.local_balance_novas = 600_000;
+        channel.remote_balance_novas = 400_000;
+        channel.funding_outpoint = Some(OutPoint { txid: [6u8; 32], vout: 0 });
+        let revoked = channel.remote_commitment_transaction().unwrap();
+        let channel_id = node.register_open_channel(channel);
+
+        // The counterparty's commitment pays them first, then us
+        assert_eq!(revoked.outputs()[0].amount(), 400_000);
+        assert_eq!(revoked.outputs()[1].amount(), 600_000);
+
+        // A payment moves the balance, then the peer revokes its old commitment
+        node.atomic_channel(&channel_id).unwrap().add_htlc([9u8; 32], 50_000, 800_100, true).unwrap();
+        let revoke = node.message_factory().create_revoke_and_ack(&RevokeAndAckPayload {
+            channel_id: channel_id.clone(),
+            per_commitment_secret: [3u8; 32],
+            next_per_commitment_point: vec![2u8; 33],
+        }).unwrap();
+        assert!(node.handle_peer_message("peer", &revoke).unwrap().is_empty());
+
+        for _ in 0..100 {
+            if tower.blob_count() == 1 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(tower.blob_count(), 1);
+
+        // Broadcasting the revoked commitment gets it punished
+        let justice = tower.process_transactions(&[revoked.clone()]);
+        assert_eq!(justice.len(), 1);
+        assert!(justice[0].inputs().iter().all(|input| input.prev_tx_hash() == revoked.hash()));
+    }
+}
@@ -16,19 +16,41 @@ pub mod atomic_operations;
 pub mod quantum_lightning;
 pub mod green_routing;
 pub mod quantum_channel;
+pub mod wire;
+pub mod interactive_tx;
+pub mod swap;
+pub mod tower;
+pub mod anchors;
+pub mod rebalance;
+pub mod forwarding;
+pub mod offer_store;
+pub mod onion_message;
+pub mod trampoline;
 
 #[cfg(test)]
 pub mod race_condition_tests;
+#[cfg(test)]
+pub(crate) mod test_utils;
 
-pub use channel::{Channel, ChannelId, ChannelState, ChannelConfig, ChannelError, ChannelManager};
-pub use invoice::{Invoice, InvoiceError, EnhancedInvoice, InvoiceDatabase, RouteHint};
+pub use channel::{Channel, ChannelId, ChannelState, ChannelConfig, ChannelError, ChannelManager, CommitmentFormat};
+pub use invoice::{Invoice, InvoiceError, EnhancedInvoice, InvoiceDatabase, InvoiceState, HeldHtlc, RouteHint, Offer, OfferId, InvoiceRequest, OfferInvoice, PayerProof, Recurrence, RecurrencePeriod};
 pub use payment::{PaymentHash, PaymentPreimage, PaymentStatus, PaymentError, PaymentProcessor, Payment, RouteHop, Htlc, HtlcState};
 pub use router::{Router, RoutingError, PaymentPath, PathHop, ChannelInfo as RouterChannelInfo, NodeId};
-pub use wallet::{LightningWallet, WalletError};
+pub use router::{RouterPreferences, RouteCostFunction, HopCostInputs, NodeEnvironmentalData};
+pub use wallet::{LightningWallet, WalletError, WalletUtxo};
 pub use watchtower::{Watchtower, WatchError, WatchtowerConfig, WatchtowerClient, BreachRemedy, ChannelMonitor, EncryptedChannelState};
 pub use onion::{OnionRouter, OnionPacket, PerHopPayload, SharedSecret};
 pub use quantum_security::{QuantumChannelSecurity, QuantumSecurityError, QuantumChannelConfig};
-pub use manager::{LightningManager, ManagerError, LightningInfo, LightningChannel, LightningPayment, LightningInvoice};
+pub use manager::{LightningManager, ManagerError, LightningInfo, LightningChannel, LightningPayment, LightningInvoice, HoldInvoiceResponse, OfferResponse};
+pub use interactive_tx::{DualFundingSession, FundingContribution, FundingInput, InteractiveTxConstructor, InteractiveTxError, NegotiatedFunding};
+pub use anchors::{AnchorError, FeeBump, HtlcTransaction, HtlcTxKind, SigHashType};
+pub use rebalance::{Rebalancer, RebalancerConfig, RebalanceCandidate, RebalanceResult, RebalanceError, ChannelBalance, FeePolicyEngine, FeePolicyConfig, ForwardStats};
+pub use forwarding::{ForwardingLedger, ForwardingEvent, ForwardingReport, ForwardStatus, ChannelEarnings, PeerEarnings, LedgerError};
+pub use offer_store::{OfferStore, OfferStoreError};
+pub use onion_message::{OnionMessenger, OnionMessagePacket, OnionMessageContents, OnionMessageAction, OnionMessageError, MessageHop, MessagePath, Destination, RateLimitConfig};
+pub use trampoline::{TrampolineRelay, TrampolineHop, TrampolineOnion, TrampolinePayment, TrampolineRecipient, TrampolineAction, TrampolineFeePolicy, TrampolineError};
+pub use tower::{TowerServer, TowerServerConfig, TowerClient, TowerClientHandle, TowerClientStats, TowerMessage, TowerCode, TowerError, SessionPolicy, JusticeKit};
+pub use swap::{SwapScript, SwapHash, SwapTimelock, SwapTerms, SwapServer, InProcessSwapServer, SwapClient, SwapError, SwapState};
 pub use atomic_operations::{AtomicChannel, AtomicChannelState, AtomicOperationError};
 pub use quantum_lightning::{
     QuantumLightningChannel, QuantumLightningManager, QuantumHTLC,
@@ -40,7 +62,7 @@ pub use quantum_lightning::{
 };
 
 pub use green_routing::{
-    GreenLightningRouter, EnvironmentalNetworkGraph, EnvironmentalNode,
+    GreenLightningRouter, GreenRoutingParameters, EnvironmentalNetworkGraph, EnvironmentalNode,
     EnvironmentalChannel, GreenIncentiveProgram, PaymentEnvironmentalImpact,
     EnvironmentalSavingsReport, GreenPaymentCertificate, EnvironmentalLightningStats,
     EnvironmentalRoutingPreferences, RoutingPriority, TimePeriod,
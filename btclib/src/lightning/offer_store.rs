@@ -0,0 +1,172 @@
+//! Offer Store
+//!
+//! Keeps the key our offers are signed with, the offers we published, the
+//! invoice requests we sent and the invoices we issued for periods of
+//! recurring offers in a database tree. Offers are long-lived: one signed
+//! by a key the node has since forgotten can no longer be answered, and an
+//! invoice for one of our requests can only be checked and paid while we
+//! still hold the request and its payer key.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::crypto::quantum::QuantumKeyPair;
+
+use super::invoice::{InvoiceRequest, Offer, OfferInvoice};
+
+/// Key of the offer signing key
+const NODE_KEY: &[u8] = b"node_key";
+
+/// Prefix of published offers, followed by the offer ID
+const OFFER_PREFIX: &[u8] = b"offer/";
+
+/// Prefix of sent invoice requests, followed by the request ID
+const REQUEST_PREFIX: &[u8] = b"request/";
+
+/// Prefix of invoices issued for recurring offers, followed by the request ID
+const RECURRENCE_PREFIX: &[u8] = b"recurrence/";
+
+/// Offer store errors
+#[derive(Debug, Error)]
+pub enum OfferStoreError {
+    #[error("Database error: {0}")]
+    Database(#[from] sled::Error),
+    
+    #[error("Codec error: {0}")]
+    Codec(#[from] bincode::Error),
+}
+
+/// An invoice request we sent, with the offer it is for and the key that signed it
+#[derive(Serialize, Deserialize)]
+struct StoredRequest {
+    offer: Offer,
+    request: InvoiceRequest,
+    payer_key: QuantumKeyPair,
+}
+
+/// Persistent offers state of a node
+pub struct OfferStore {
+    tree: sled::Tree,
+}
+
+impl OfferStore {
+    /// Use `tree` to store offers state
+    pub fn new(tree: sled::Tree) -> Self {
+        Self { tree }
+    }
+    
+    /// The stored offer signing key
+    pub fn node_key(&self) -> Result<Option<QuantumKeyPair>, OfferStoreError> {
+        match self.tree.get(NODE_KEY)? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Store the offer signing key
+    pub fn set_node_key(&self, key: &QuantumKeyPair) -> Result<(), OfferStoreError> {
+        self.tree.insert(NODE_KEY, bincode::serialize(key)?)?;
+        self.tree.flush()?;
+        Ok(())
+    }
+    
+    /// Store an offer we published
+    pub fn save_offer(&self, offer: &Offer) -> Result<(), OfferStoreError> {
+        self.tree.insert(Self::key(OFFER_PREFIX, offer.id().as_bytes()), bincode::serialize(offer)?)?;
+        Ok(())
+    }
+    
+    /// All offers we published
+    pub fn offers(&self) -> Result<Vec<Offer>, OfferStoreError> {
+        self.tree.scan_prefix(OFFER_PREFIX)
+            .map(|entry| {
+                let (_, value) = entry?;
+                Ok(bincode::deserialize(&value)?)
+            })
+            .collect()
+    }
+    
+    /// Store an invoice request we sent for `offer`, signed by `payer_key`
+    pub fn save_invoice_request(
+        &self,
+        offer: &Offer,
+        request: &InvoiceRequest,
+        payer_key: &QuantumKeyPair,
+    ) -> Result<(), OfferStoreError> {
+        let stored = StoredRequest {
+            offer: offer.clone(),
+            request: request.clone(),
+            payer_key: payer_key.clone(),
+        };
+        self.tree.insert(Self::key(REQUEST_PREFIX, &request.id()), bincode::serialize(&stored)?)?;
+        Ok(())
+    }
+    
+    /// All invoice requests we sent, with their offers and payer keys
+    pub fn invoice_requests(&self) -> Result<Vec<(Offer, InvoiceRequest, QuantumKeyPair)>, OfferStoreError> {
+        self.tree.scan_prefix(REQUEST_PREFIX)
+            .map(|entry| {
+                let (_, value) = entry?;
+                let stored: StoredRequest = bincode::deserialize(&value)?;
+                Ok((stored.offer, stored.request, stored.payer_key))
+            })
+            .collect()
+    }
+    
+    /// Store an invoice we issued for a period of a recurring offer
+    pub fn save_recurrence_invoice(&self, invoice: &OfferInvoice) -> Result<(), OfferStoreError> {
+        self.tree.insert(Self::key(RECURRENCE_PREFIX, invoice.invoice_request_id()), bincode::serialize(invoice)?)?;
+        Ok(())
+    }
+    
+    /// All invoices we issued for periods of recurring offers
+    pub fn recurrence_invoices(&self) -> Result<Vec<OfferInvoice>, OfferStoreError> {
+        self.tree.scan_prefix(RECURRENCE_PREFIX)
+            .map(|entry| {
+                let (_, value) = entry?;
+                Ok(bincode::deserialize(&value)?)
+            })
+            .collect()
+    }
+    
+    fn key(prefix: &[u8], id: &[u8; 32]) -> Vec<u8> {
+        let mut key = prefix.to_vec();
+        key.extend_from_slice(id);
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::quantum::{QuantumParameters, QuantumScheme};
+    
+    #[test]
+    fn test_offer_state_survives_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let node_key = QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap();
+        let payer_key = QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap();
+        let offer = Offer::new("Coffee".to_string(), Some(5_000), "node".to_string(), &node_key).unwrap();
+        let request = InvoiceRequest::new(&offer, None, None, None, None, &payer_key).unwrap();
+        
+        {
+            let db = sled::open(dir.path()).unwrap();
+            let store = OfferStore::new(db.open_tree("offers").unwrap());
+            assert!(store.node_key().unwrap().is_none());
+            store.set_node_key(&node_key).unwrap();
+            store.save_offer(&offer).unwrap();
+            store.save_invoice_request(&offer, &request, &payer_key).unwrap();
+            db.flush().unwrap();
+        }
+        
+        let db = sled::open(dir.path()).unwrap();
+        let store = OfferStore::new(db.open_tree("offers").unwrap());
+        assert_eq!(store.node_key().unwrap().unwrap().public_key, node_key.public_key);
+        assert_eq!(store.offers().unwrap(), vec![offer.clone()]);
+        
+        let requests = store.invoice_requests().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!((&requests[0].0, &requests[0].1), (&offer, &request));
+        assert_eq!(requests[0].2.public_key, payer_key.public_key);
+    }
+}
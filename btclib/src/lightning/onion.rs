@@ -11,6 +11,7 @@ use tracing::{debug, info, warn, error};
 use rand::{Rng, RngCore};
 use sha2::{Sha256, Digest};
 use serde_bytes;
+use subtle::ConstantTimeEq;
 
 /// Maximum number of hops in an onion route
 pub const MAX_ONION_HOPS: usize = 20;
@@ -158,6 +159,14 @@ pub struct PerHopPayload {
     pub tlv_payload: HashMap<u64, Vec<u8>>,
 }
 
+impl PerHopPayload {
+    /// The blinded hop this payload carries, if any
+    pub fn blinded_hop(&self) -> Option<BlindedHop> {
+        self.tlv_payload.get(&TLV_BLINDED_HOP)
+            .and_then(|bytes| bincode::deserialize(bytes).ok())
+    }
+}
+
 /// Route hop information for onion construction
 #[derive(Debug, Clone)]
 pub struct RouteHop {
@@ -591,11 +600,444 @@ pub enum OnionError {
     
     #[error("Quantum signature error: {0}")]
     QuantumSignatureError(String),
+    
+    #[error("Invalid blinded hop: {0}")]
+    InvalidBlindedHop(String),
+}
+
+// Route blinding
+//
+// A receiver builds a blinded path from an introduction node to itself and
+// publishes it instead of route hints. Each hop gets a KEM ciphertext; the
+// shared secret it decapsulates both blinds the hop's node ID and encrypts the
+// forwarding instructions only that hop can read. The payer routes to the
+// introduction node in the clear and sees nothing behind it but aggregate fees.
+
+/// Size of the authentication tag appended to encrypted blinded hop data
+pub const BLINDED_DATA_TAG_SIZE: usize = 16;
+
+/// Per-hop payload TLV carrying a blinded hop to the node it was blinded for
+pub const TLV_BLINDED_HOP: u64 = 10;
+
+/// Key encapsulation used to derive per-hop blinding secrets
+pub trait HopKem: Send + Sync {
+    /// Encapsulate to a hop's KEM public key, returning (ciphertext, shared secret)
+    fn encapsulate(&self, public_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>), OnionError>;
+    
+    /// Recover the shared secret from a ciphertext with the hop's KEM secret key
+    fn decapsulate(&self, secret_key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, OnionError>;
+}
+
+/// `HopKem` backed by the node's post-quantum KEM (`crate::crypto::kem`)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemKem;
+
+impl HopKem for SystemKem {
+    fn encapsulate(&self, public_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>), OnionError> {
+        crate::crypto::kem::encapsulate(public_key)
+            .map_err(|e| OnionError::KeyDerivationError(e.to_string()))
+    }
+    
+    fn decapsulate(&self, secret_key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, OnionError> {
+        crate::crypto::kem::decapsulate(secret_key, ciphertext)
+            .map_err(|e| OnionError::KeyDerivationError(e.to_string()))
+    }
+}
+
+/// Fee and CLTV parameters a blinded hop applies when relaying
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRelay {
+    /// CLTV expiry delta
+    pub cltv_expiry_delta: u16,
+    /// Fee rate in parts per million
+    pub fee_proportional_millionths: u32,
+    /// Base fee in millisatoshis
+    pub fee_base_msat: u32,
+}
+
+impl PaymentRelay {
+    /// Amount to forward for a given incoming amount (inverse of the fee formula)
+    pub fn amount_to_forward(&self, incoming_amount_msat: u64) -> u64 {
+        let after_base = incoming_amount_msat.saturating_sub(self.fee_base_msat as u64) as u128;
+        let denominator = 1_000_000u128 + self.fee_proportional_millionths as u128;
+        
+        (after_base * 1_000_000 / denominator) as u64
+    }
+}
+
+/// Constraints a blinded hop enforces on incoming HTLCs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentConstraints {
+    /// Maximum absolute CLTV expiry accepted
+    pub max_cltv_expiry: u32,
+    /// Minimum HTLC amount in millisatoshis
+    pub htlc_minimum_msat: u64,
+}
+
+/// Instructions encrypted to a single blinded hop
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlindedHopData {
+    /// Channel to forward over (None for the recipient)
+    pub next_short_channel_id: Option<u64>,
+    /// Relay parameters (None for the recipient)
+    pub payment_relay: Option<PaymentRelay>,
+    /// HTLC constraints
+    pub payment_constraints: PaymentConstraints,
+    /// Recipient-chosen identifier, lets the recipient recognise its own paths
+    pub path_id: Option<[u8; 32]>,
+}
+
+/// A hop of a blinded path as seen by the payer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlindedHop {
+    /// Blinded node ID (unlinkable to the real node)
+    pub blinded_node_id: Vec<u8>,
+    /// KEM ciphertext the hop decapsulates to obtain its blinding secret
+    pub kem_ciphertext: Vec<u8>,
+    /// Encrypted `BlindedHopData` followed by an authentication tag
+    pub encrypted_data: Vec<u8>,
+}
+
+impl BlindedHop {
+    /// Decrypt this hop's instructions (run by the hop itself)
+    pub fn decrypt(
+        &self,
+        node_id: &str,
+        kem_secret_key: &[u8],
+        kem: &dyn HopKem,
+    ) -> Result<BlindedHopData, OnionError> {
+        self.decrypt_data(node_id, kem_secret_key, kem)
+    }
+    
+    /// Decrypt this hop's data as any type it was blinded with
+    pub(crate) fn decrypt_data<T: serde::de::DeserializeOwned>(
+        &self,
+        node_id: &str,
+        kem_secret_key: &[u8],
+        kem: &dyn HopKem,
+    ) -> Result<T, OnionError> {
+        let shared_secret = kem.decapsulate(kem_secret_key, &self.kem_ciphertext)?;
+        
+        if blinded_node_id(node_id, &shared_secret) != self.blinded_node_id {
+            return Err(OnionError::InvalidBlindedHop("Blinded node ID does not match".to_string()));
+        }
+        
+        if self.encrypted_data.len() < BLINDED_DATA_TAG_SIZE {
+            return Err(OnionError::InvalidBlindedHop("Encrypted data too short".to_string()));
+        }
+        
+        let rho = blinding_rho(&shared_secret);
+        let (ciphertext, tag) = self.encrypted_data.split_at(self.encrypted_data.len() - BLINDED_DATA_TAG_SIZE);
+        if !verify_blinded_data_tag(&rho, ciphertext, tag) {
+            return Err(OnionError::InvalidHmac);
+        }
+        
+        let mut plaintext = ciphertext.to_vec();
+        xor_keystream(&mut plaintext, &rho);
+        
+        bincode::deserialize(&plaintext)
+            .map_err(|e| OnionError::InvalidBlindedHop(e.to_string()))
+    }
+}
+
+/// Aggregate fees and constraints of a whole blinded path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlindedPayInfo {
+    /// Aggregate base fee in millisatoshis
+    pub fee_base_msat: u32,
+    /// Aggregate fee rate in parts per million
+    pub fee_proportional_millionths: u32,
+    /// Aggregate CLTV expiry delta
+    pub cltv_expiry_delta: u16,
+    /// Minimum HTLC amount accepted by every hop
+    pub htlc_minimum_msat: u64,
+}
+
+impl BlindedPayInfo {
+    /// Fee charged by the blinded part of the route for delivering `amount_msat`
+    pub fn fee_msat(&self, amount_msat: u64) -> u64 {
+        self.fee_base_msat as u64
+            + (amount_msat as u128 * self.fee_proportional_millionths as u128 / 1_000_000) as u64
+    }
+}
+
+/// A forwarding node on a blinded path, as known to the receiver building it
+#[derive(Debug, Clone)]
+pub struct BlindedForwardNode {
+    /// Real node ID
+    pub node_id: String,
+    /// KEM public key of the node
+    pub kem_public_key: Vec<u8>,
+    /// Channel from this node to the next hop
+    pub short_channel_id: u64,
+    /// Relay parameters of that channel
+    pub relay: PaymentRelay,
+    /// Minimum HTLC amount of that channel
+    pub htlc_minimum_msat: u64,
+}
+
+/// A blinded route from an introduction node to the receiver
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlindedPath {
+    /// Real node ID of the introduction node (the first hop)
+    pub introduction_node_id: String,
+    /// Blinded hops, starting with the introduction node and ending with the receiver
+    pub hops: Vec<BlindedHop>,
+    /// Aggregate fees and constraints
+    pub payinfo: BlindedPayInfo,
+}
+
+impl BlindedPath {
+    /// Build a blinded path through `forwarders` to the recipient
+    ///
+    /// With no forwarders the recipient is its own introduction node.
+    pub fn new(
+        forwarders: &[BlindedForwardNode],
+        recipient_node_id: &str,
+        recipient_kem_public_key: &[u8],
+        path_id: [u8; 32],
+        max_cltv_expiry: u32,
+        kem: &dyn HopKem,
+    ) -> Result<Self, OnionError> {
+        if forwarders.len() + 1 > MAX_ONION_HOPS {
+            return Err(OnionError::TooManyHops(forwarders.len() + 1));
+        }
+        
+        let htlc_minimum_msat = forwarders.iter()
+            .map(|f| f.htlc_minimum_msat)
+            .max()
+            .unwrap_or(0);
+        
+        let mut hops = Vec::with_capacity(forwarders.len() + 1);
+        
+        for forwarder in forwarders {
+            let data = BlindedHopData {
+                next_short_channel_id: Some(forwarder.short_channel_id),
+                payment_relay: Some(forwarder.relay),
+                payment_constraints: PaymentConstraints {
+                    max_cltv_expiry,
+                    htlc_minimum_msat: forwarder.htlc_minimum_msat,
+                },
+                path_id: None,
+            };
+            
+            hops.push(Self::blind_hop(&forwarder.node_id, &forwarder.kem_public_key, &data, kem)?);
+        }
+        
+        let recipient_data = BlindedHopData {
+            next_short_channel_id: None,
+            payment_relay: None,
+            payment_constraints: PaymentConstraints {
+                max_cltv_expiry,
+                htlc_minimum_msat,
+            },
+            path_id: Some(path_id),
+        };
+        hops.push(Self::blind_hop(recipient_node_id, recipient_kem_public_key, &recipient_data, kem)?);
+        
+        let introduction_node_id = forwarders.first()
+            .map(|f| f.node_id.clone())
+            .unwrap_or_else(|| recipient_node_id.to_string());
+        
+        Ok(Self {
+            introduction_node_id,
+            hops,
+            payinfo: Self::aggregate_payinfo(forwarders, htlc_minimum_msat),
+        })
+    }
+    
+    /// Number of hops including the introduction node and the recipient
+    pub fn len(&self) -> usize {
+        self.hops.len()
+    }
+    
+    /// Check if the path has no hops
+    pub fn is_empty(&self) -> bool {
+        self.hops.is_empty()
+    }
+    
+    /// Per-hop payloads paying `amount_msat` to the recipient in an HTLC
+    /// expiring at `final_cltv_expiry`
+    ///
+    /// Only the introduction node and the recipient are told an amount and
+    /// expiry. The hops in between derive theirs from their relay parameters.
+    pub fn hop_payloads(&self, amount_msat: u64, final_cltv_expiry: u32) -> Result<Vec<PerHopPayload>, OnionError> {
+        let last = self.hops.len().saturating_sub(1);
+        
+        self.hops.iter().enumerate().map(|(index, hop)| {
+            let (amount_msat, outgoing_cltv_value) = if index == last {
+                (amount_msat, final_cltv_expiry)
+            } else if index == 0 {
+                (amount_msat + self.payinfo.fee_msat(amount_msat), final_cltv_expiry + self.payinfo.cltv_expiry_delta as u32)
+            } else {
+                (0, 0)
+            };
+            let hop = bincode::serialize(hop)
+                .map_err(|e| OnionError::EncryptionError(e.to_string()))?;
+            
+            Ok(PerHopPayload {
+                amount_msat,
+                outgoing_cltv_value,
+                short_channel_id: 0,
+                tlv_payload: HashMap::from([(TLV_BLINDED_HOP, hop)]),
+            })
+        }).collect()
+    }
+    
+    /// Encrypt `data` to one hop
+    pub(crate) fn blind_hop<T: Serialize>(
+        node_id: &str,
+        kem_public_key: &[u8],
+        data: &T,
+        kem: &dyn HopKem,
+    ) -> Result<BlindedHop, OnionError> {
+        let (kem_ciphertext, shared_secret) = kem.encapsulate(kem_public_key)?;
+        let rho = blinding_rho(&shared_secret);
+        
+        let mut encrypted_data = bincode::serialize(data)
+            .map_err(|e| OnionError::EncryptionError(e.to_string()))?;
+        xor_keystream(&mut encrypted_data, &rho);
+        let tag = blinded_data_tag(&rho, &encrypted_data);
+        encrypted_data.extend_from_slice(&tag);
+        
+        Ok(BlindedHop {
+            blinded_node_id: blinded_node_id(node_id, &shared_secret),
+            kem_ciphertext,
+            encrypted_data,
+        })
+    }
+    
+    /// Aggregate relay parameters, rounding fees up so every hop is paid enough
+    fn aggregate_payinfo(forwarders: &[BlindedForwardNode], htlc_minimum_msat: u64) -> BlindedPayInfo {
+        let mut base: u128 = 0;
+        let mut proportional: u128 = 0;
+        let mut cltv_expiry_delta: u16 = 0;
+        
+        for forwarder in forwarders.iter().rev() {
+            let hop_base = forwarder.relay.fee_base_msat as u128;
+            let hop_prop = forwarder.relay.fee_proportional_millionths as u128;
+            
+            base = (hop_base * 1_000_000 + base * (1_000_000 + hop_prop) + 999_999) / 1_000_000;
+            proportional = ((proportional + hop_prop) * 1_000_000 + proportional * hop_prop + 999_999) / 1_000_000;
+            cltv_expiry_delta = cltv_expiry_delta.saturating_add(forwarder.relay.cltv_expiry_delta);
+        }
+        
+        BlindedPayInfo {
+            fee_base_msat: base.min(u32::MAX as u128) as u32,
+            fee_proportional_millionths: proportional.min(u32::MAX as u128) as u32,
+            cltv_expiry_delta,
+            htlc_minimum_msat,
+        }
+    }
+}
+
+/// Blinded node ID derived from the real node ID and the hop's shared secret
+fn blinded_node_id(node_id: &str, shared_secret: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(b"blinded_node_id");
+    hasher.update(node_id.as_bytes());
+    hasher.update(shared_secret);
+    
+    let mut blinded = Vec::with_capacity(33);
+    blinded.push(0x02);
+    blinded.extend_from_slice(&hasher.finalize());
+    blinded
+}
+
+/// Encryption key for a hop's blinded data
+fn blinding_rho(shared_secret: &[u8]) -> SharedSecret {
+    let mut hasher = Sha256::new();
+    hasher.update(b"rho");
+    hasher.update(shared_secret);
+    SharedSecret::new(hasher.finalize().into())
+}
+
+/// Authentication tag over encrypted blinded data
+pub(crate) fn blinded_data_tag(rho: &SharedSecret, ciphertext: &[u8]) -> [u8; BLINDED_DATA_TAG_SIZE] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"blinded_data_tag");
+    hasher.update(rho.as_bytes());
+    hasher.update(ciphertext);
+    
+    let mut tag = [0u8; BLINDED_DATA_TAG_SIZE];
+    tag.copy_from_slice(&hasher.finalize()[..BLINDED_DATA_TAG_SIZE]);
+    tag
+}
+
+/// Check an authentication tag in constant time
+pub(crate) fn verify_blinded_data_tag(rho: &SharedSecret, ciphertext: &[u8], tag: &[u8]) -> bool {
+    blinded_data_tag(rho, ciphertext).ct_eq(tag).into()
+}
+
+/// XOR data with a SHA-256 counter-mode keystream
+pub(crate) fn xor_keystream(data: &mut [u8], key: &SharedSecret) {
+    for (counter, chunk) in data.chunks_mut(32).enumerate() {
+        let mut hasher = Sha256::new();
+        hasher.update(key.as_bytes());
+        hasher.update((counter as u64).to_be_bytes());
+        let block = hasher.finalize();
+        
+        for (byte, k) in chunk.iter_mut().zip(block.iter()) {
+            *byte ^= k;
+        }
+    }
+}
+
+/// Encrypt a layer to a hop's KEM key, returning (ciphertext, payload with tag)
+///
+/// `domain` separates the keys of different layer kinds derived from one secret.
+pub(crate) fn seal_layer(
+    kem: &dyn HopKem,
+    public_key: &[u8],
+    domain: &[u8],
+    mut plaintext: Vec<u8>,
+) -> Result<(Vec<u8>, Vec<u8>), OnionError> {
+    let (kem_ciphertext, shared_secret) = kem.encapsulate(public_key)?;
+    let key = layer_key(domain, &shared_secret);
+    
+    xor_keystream(&mut plaintext, &key);
+    let tag = blinded_data_tag(&key, &plaintext);
+    plaintext.extend_from_slice(&tag);
+    
+    Ok((kem_ciphertext, plaintext))
+}
+
+/// Decrypt a layer sealed to us with `seal_layer`
+pub(crate) fn open_layer(
+    kem: &dyn HopKem,
+    secret_key: &[u8],
+    domain: &[u8],
+    kem_ciphertext: &[u8],
+    payload: &[u8],
+) -> Result<Vec<u8>, OnionError> {
+    if payload.len() < BLINDED_DATA_TAG_SIZE {
+        return Err(OnionError::InvalidPayload);
+    }
+    
+    let shared_secret = kem.decapsulate(secret_key, kem_ciphertext)?;
+    let key = layer_key(domain, &shared_secret);
+    
+    let (ciphertext, tag) = payload.split_at(payload.len() - BLINDED_DATA_TAG_SIZE);
+    if !verify_blinded_data_tag(&key, ciphertext, tag) {
+        return Err(OnionError::InvalidHmac);
+    }
+    
+    let mut plaintext = ciphertext.to_vec();
+    xor_keystream(&mut plaintext, &key);
+    Ok(plaintext)
+}
+
+fn layer_key(domain: &[u8], shared_secret: &[u8]) -> SharedSecret {
+    let mut hasher = Sha256::new();
+    hasher.update(domain);
+    hasher.update(shared_secret);
+    SharedSecret::new(hasher.finalize().into())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::lightning::test_utils::TestKem;
     
     #[test]
     fn test_onion_construction() {
@@ -662,4 +1104,98 @@ mod tests {
         assert_eq!(deserialized.outgoing_cltv_value, payload.outgoing_cltv_value);
         assert_eq!(deserialized.short_channel_id, payload.short_channel_id);
     }
-} 
\ No newline at end of file
+    
+    fn forward_node(name: &str, scid: u64, base: u32, prop: u32, cltv: u16) -> BlindedForwardNode {
+        BlindedForwardNode {
+            node_id: name.to_string(),
+            kem_public_key: name.as_bytes().to_vec(),
+            short_channel_id: scid,
+            relay: PaymentRelay {
+                cltv_expiry_delta: cltv,
+                fee_proportional_millionths: prop,
+                fee_base_msat: base,
+            },
+            htlc_minimum_msat: 1_000,
+        }
+    }
+    
+    #[test]
+    fn test_blinded_path_hops_decrypt() {
+        let forwarders = vec![
+            forward_node("intro", 11, 1_000, 100, 40),
+            forward_node("middle", 22, 500, 200, 30),
+        ];
+        let path = BlindedPath::new(&forwarders, "recipient", b"recipient", [7u8; 32], 800_000, &TestKem).unwrap();
+        
+        assert_eq!(path.introduction_node_id, "intro");
+        assert_eq!(path.len(), 3);
+        
+        let intro = path.hops[0].decrypt("intro", b"intro", &TestKem).unwrap();
+        assert_eq!(intro.next_short_channel_id, Some(11));
+        assert_eq!(intro.payment_relay.unwrap().fee_base_msat, 1_000);
+        
+        let middle = path.hops[1].decrypt("middle", b"middle", &TestKem).unwrap();
+        assert_eq!(middle.next_short_channel_id, Some(22));
+        
+        let recipient = path.hops[2].decrypt("recipient", b"recipient", &TestKem).unwrap();
+        assert_eq!(recipient.next_short_channel_id, None);
+        assert_eq!(recipient.path_id, Some([7u8; 32]));
+        
+        // Other nodes cannot read a hop, and the blinded ID hides the real one
+        assert!(path.hops[1].decrypt("intro", b"intro", &TestKem).is_err());
+        assert_ne!(path.hops[0].blinded_node_id, path.hops[1].blinded_node_id);
+    }
+    
+    #[test]
+    fn test_blinded_hop_tamper_detected() {
+        let path = BlindedPath::new(&[], "recipient", b"recipient", [1u8; 32], 800_000, &TestKem).unwrap();
+        assert_eq!(path.introduction_node_id, "recipient");
+        
+        let mut hop = path.hops[0].clone();
+        hop.encrypted_data[0] ^= 1;
+        assert!(matches!(hop.decrypt("recipient", b"recipient", &TestKem), Err(OnionError::InvalidHmac)));
+    }
+    
+    #[test]
+    fn test_blinded_payinfo_covers_hop_fees() {
+        let forwarders = vec![
+            forward_node("intro", 11, 1_000, 100, 40),
+            forward_node("middle", 22, 500, 200, 30),
+        ];
+        let path = BlindedPath::new(&forwarders, "recipient", b"recipient", [0u8; 32], 800_000, &TestKem).unwrap();
+        assert_eq!(path.payinfo.cltv_expiry_delta, 70);
+        assert_eq!(path.payinfo.htlc_minimum_msat, 1_000);
+        
+        // Walking the path with the aggregate fee must leave at least the amount
+        let amount = 1_000_000;
+        let mut forwarded = amount + path.payinfo.fee_msat(amount);
+        for forwarder in &forwarders {
+            forwarded = forwarder.relay.amount_to_forward(forwarded);
+        }
+        assert!(forwarded >= amount);
+    }
+    
+    #[test]
+    fn test_blinded_hop_payloads() {
+        let forwarders = vec![
+            forward_node("intro", 11, 1_000, 100, 40),
+            forward_node("middle", 22, 500, 200, 30),
+        ];
+        let path = BlindedPath::new(&forwarders, "recipient", b"recipient", [3u8; 32], 800_000, &TestKem).unwrap();
+        let payloads = path.hop_payloads(1_000_000, 700_040).unwrap();
+        assert_eq!(payloads.len(), 3);
+        
+        // The introduction node is paid the aggregate fee on top, the recipient exactly the amount
+        assert_eq!(payloads[0].amount_msat, 1_000_000 + path.payinfo.fee_msat(1_000_000));
+        assert_eq!(payloads[0].outgoing_cltv_value, 700_110);
+        assert_eq!((payloads[1].amount_msat, payloads[1].outgoing_cltv_value), (0, 0));
+        assert_eq!((payloads[2].amount_msat, payloads[2].outgoing_cltv_value), (1_000_000, 700_040));
+        
+        // Each hop reads its own instructions out of its payload
+        let middle = payloads[1].blinded_hop().unwrap().decrypt("middle", b"middle", &TestKem).unwrap();
+        assert_eq!(middle.next_short_channel_id, Some(22));
+        let recipient = payloads[2].blinded_hop().unwrap().decrypt("recipient", b"recipient", &TestKem).unwrap();
+        assert_eq!(recipient.path_id, Some([3u8; 32]));
+        assert!(PerHopPayload { tlv_payload: HashMap::new(), ..payloads[0].clone() }.blinded_hop().is_none());
+    }
+}
@@ -0,0 +1,615 @@
+//! Onion Messages
+//!
+//! Onion messages carry TLV payloads such as invoice requests or async
+//! payment notifications across the network without locking up an HTLC.
+//! Every hop peels one layer encrypted to its KEM key, the same way blinded
+//! payment hops are read. A message is delivered either to a node or along a
+//! blinded path, and may carry a reply path so the recipient can answer
+//! without learning who asked. Nodes limit how many messages each peer may
+//! push through them, since relaying is free.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::crypto::kem::KemKeyPair;
+
+use super::onion::{
+    blinded_data_tag, open_layer, seal_layer, verify_blinded_data_tag, xor_keystream, BlindedHop, BlindedPath, HopKem, OnionError,
+    SharedSecret, BLINDED_DATA_TAG_SIZE, MAX_ONION_HOPS,
+};
+
+/// Key separation for sealed onion message layers
+const LAYER_DOMAIN: &[u8] = b"onion_message_layer";
+
+/// Largest serialized onion message a node relays
+pub const MAX_ONION_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// TLV type of a BOLT 12 invoice request
+pub const TLV_INVOICE_REQUEST: u64 = 64;
+
+/// TLV type of a BOLT 12 invoice
+pub const TLV_INVOICE: u64 = 66;
+
+/// TLV type of an invoice error
+pub const TLV_INVOICE_ERROR: u64 = 68;
+
+/// TLV type telling an async recipient's LSP that a payment is being held
+pub const TLV_HELD_HTLC_AVAILABLE: u64 = 72;
+
+/// TLV type asking the sender's LSP to release a held payment
+pub const TLV_RELEASE_HELD_HTLC: u64 = 74;
+
+/// Onion message errors
+#[derive(Debug, Error)]
+pub enum OnionMessageError {
+    #[error("Onion error: {0}")]
+    Onion(#[from] OnionError),
+    
+    #[error("Codec error: {0}")]
+    Codec(#[from] bincode::Error),
+    
+    #[error("Message too large: {0} bytes")]
+    TooLarge(usize),
+    
+    #[error("Rate limit exceeded for peer {0}")]
+    RateLimited(String),
+    
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
+}
+
+/// TLV records delivered to the final hop
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OnionMessageContents {
+    records: BTreeMap<u64, Vec<u8>>,
+}
+
+impl OnionMessageContents {
+    /// Create empty contents
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Add a record, replacing any record of the same type
+    pub fn with(mut self, tlv_type: u64, value: Vec<u8>) -> Self {
+        self.records.insert(tlv_type, value);
+        self
+    }
+    
+    /// Get the value of a record
+    pub fn get(&self, tlv_type: u64) -> Option<&[u8]> {
+        self.records.get(&tlv_type).map(|value| value.as_slice())
+    }
+    
+    /// Iterate over records in type order
+    pub fn records(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        self.records.iter().map(|(tlv_type, value)| (*tlv_type, value.as_slice()))
+    }
+    
+    /// Check if there are no records
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+/// A node on the clear part of a message route
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageHop {
+    /// Node ID
+    pub node_id: String,
+    
+    /// KEM public key of the node
+    pub kem_public_key: Vec<u8>,
+}
+
+/// Instructions encrypted to one hop of a blinded message path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MessageHopData {
+    /// Node to pass the message to (None for the path's creator)
+    next_node_id: Option<String>,
+    
+    /// Creator-chosen identifier, only on the last hop
+    path_id: Option<[u8; 32]>,
+}
+
+/// A blinded path onion messages can be delivered along, usually a reply path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessagePath {
+    /// Real node ID of the introduction node (the first hop)
+    pub introduction_node_id: String,
+    
+    /// Blinded hops, starting with the introduction node and ending with the creator
+    pub hops: Vec<BlindedHop>,
+    
+    /// Key the creator can rederive to read contents sent along this path
+    pub contents_key: [u8; 32],
+}
+
+/// Where an onion message is delivered
+#[derive(Debug, Clone)]
+pub enum Destination {
+    /// A node we know the KEM key of
+    Node(MessageHop),
+    
+    /// The creator of a blinded path
+    BlindedPath(MessagePath),
+}
+
+/// An onion message as exchanged between peers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OnionMessagePacket {
+    /// A layer only the receiving node can decrypt
+    Sealed {
+        kem_ciphertext: Vec<u8>,
+        payload: Vec<u8>,
+    },
+    
+    /// The remaining hops of a blinded path, with contents for its creator
+    Blinded {
+        hops: Vec<BlindedHop>,
+        encrypted_contents: Vec<u8>,
+    },
+}
+
+/// Plaintext of a sealed layer
+#[derive(Debug, Serialize, Deserialize)]
+enum SealedLayer {
+    Forward {
+        next_node_id: String,
+        next: OnionMessagePacket,
+    },
+    Receive {
+        contents: OnionMessageContents,
+        reply_path: Option<MessagePath>,
+    },
+}
+
+/// Plaintext delivered at the end of a blinded path
+#[derive(Debug, Serialize, Deserialize)]
+struct BlindedContents {
+    contents: OnionMessageContents,
+    reply_path: Option<MessagePath>,
+}
+
+/// What to do with an incoming onion message
+#[derive(Debug)]
+pub enum OnionMessageAction {
+    /// Pass the packet on to another node
+    Forward {
+        next_node_id: String,
+        packet: OnionMessagePacket,
+    },
+    
+    /// The message is for us
+    Receive {
+        contents: OnionMessageContents,
+        reply_path: Option<MessagePath>,
+        /// Set when the message arrived over one of our own blinded paths
+        path_id: Option<[u8; 32]>,
+    },
+}
+
+/// Per-peer message allowance
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Messages a peer may send in a burst
+    pub burst: u32,
+    
+    /// Messages per second a peer earns back
+    pub per_second: f64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            burst: 32,
+            per_second: 4.0,
+        }
+    }
+}
+
+/// Token bucket per peer
+pub struct MessageRateLimiter {
+    config: RateLimitConfig,
+    buckets: HashMap<String, (f64, Instant)>,
+}
+
+impl MessageRateLimiter {
+    /// Create a rate limiter
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: HashMap::new(),
+        }
+    }
+    
+    /// Take a token for a message from `peer`, returning false if it has none left
+    pub fn check(&mut self, peer: &str, now: Instant) -> bool {
+        let burst = self.config.burst as f64;
+        let (tokens, last) = self.buckets.entry(peer.to_string()).or_insert((burst, now));
+        
+        let elapsed = now.saturating_duration_since(*last).as_secs_f64();
+        *tokens = (*tokens + elapsed * self.config.per_second).min(burst);
+        *last = now;
+        
+        if *tokens < 1.0 {
+            return false;
+        }
+        *tokens -= 1.0;
+        true
+    }
+    
+    /// Drop the state of a disconnected peer
+    pub fn forget(&mut self, peer: &str) {
+        self.buckets.remove(peer);
+    }
+}
+
+/// Creates, relays and receives onion messages for one node
+pub struct OnionMessenger {
+    node_id: String,
+    kem_keypair: KemKeyPair,
+    kem: Box<dyn HopKem>,
+    rate_limiter: MessageRateLimiter,
+}
+
+impl OnionMessenger {
+    /// Create a messenger for the node `node_id`
+    pub fn new(node_id: String, kem_keypair: KemKeyPair, kem: Box<dyn HopKem>, rate_limit: RateLimitConfig) -> Self {
+        Self {
+            node_id,
+            kem_keypair,
+            kem,
+            rate_limiter: MessageRateLimiter::new(rate_limit),
+        }
+    }
+    
+    /// Build a message to `destination` through `route`
+    ///
+    /// `route` lists the nodes between us and the destination, or between us
+    /// and the introduction node of a blinded destination. Returns the node
+    /// to hand the packet to.
+    pub fn create_message(
+        &self,
+        route: &[MessageHop],
+        destination: Destination,
+        contents: OnionMessageContents,
+        reply_path: Option<MessagePath>,
+    ) -> Result<(String, OnionMessagePacket), OnionMessageError> {
+        let (mut next_node_id, mut packet, hop_count) = match destination {
+            Destination::Node(hop) => {
+                let packet = self.seal(&hop, &SealedLayer::Receive { contents, reply_path })?;
+                (hop.node_id, packet, route.len() + 1)
+            }
+            Destination::BlindedPath(path) => {
+                if path.hops.is_empty() {
+                    return Err(OnionMessageError::InvalidMessage("Blinded path has no hops".to_string()));
+                }
+                let plaintext = bincode::serialize(&BlindedContents { contents, reply_path })?;
+                let packet = OnionMessagePacket::Blinded {
+                    encrypted_contents: encrypt_contents(&path.contents_key, plaintext),
+                    hops: path.hops,
+                };
+                let hop_count = route.len() + path_len(&packet);
+                (path.introduction_node_id, packet, hop_count)
+            }
+        };
+        
+        if hop_count > MAX_ONION_HOPS {
+            return Err(OnionError::TooManyHops(hop_count).into());
+        }
+        
+        for hop in route.iter().rev() {
+            packet = self.seal(hop, &SealedLayer::Forward { next_node_id, next: packet })?;
+            next_node_id = hop.node_id.clone();
+        }
+        
+        let size = bincode::serialized_size(&packet)? as usize;
+        if size > MAX_ONION_MESSAGE_SIZE {
+            return Err(OnionMessageError::TooLarge(size));
+        }
+        
+        Ok((next_node_id, packet))
+    }
+    
+    /// Build a blinded path through `forwarders` that delivers messages back to us
+    pub fn create_reply_path(&self, forwarders: &[MessageHop], path_id: [u8; 32]) -> Result<MessagePath, OnionMessageError> {
+        if forwarders.len() + 1 > MAX_ONION_HOPS {
+            return Err(OnionError::TooManyHops(forwarders.len() + 1).into());
+        }
+        
+        let mut hops = Vec::with_capacity(forwarders.len() + 1);
+        for (i, forwarder) in forwarders.iter().enumerate() {
+            let next_node_id = forwarders.get(i + 1)
+                .map(|next| next.node_id.clone())
+                .unwrap_or_else(|| self.node_id.clone());
+            let data = MessageHopData {
+                next_node_id: Some(next_node_id),
+                path_id: None,
+            };
+            hops.push(BlindedPath::blind_hop(&forwarder.node_id, &forwarder.kem_public_key, &data, self.kem.as_ref())?);
+        }
+        
+        let data = MessageHopData {
+            next_node_id: None,
+            path_id: Some(path_id),
+        };
+        hops.push(BlindedPath::blind_hop(&self.node_id, &self.kem_keypair.public_key, &data, self.kem.as_ref())?);
+        
+        Ok(MessagePath {
+            introduction_node_id: forwarders.first()
+                .map(|forwarder| forwarder.node_id.clone())
+                .unwrap_or_else(|| self.node_id.clone()),
+            hops,
+            contents_key: self.contents_key(&path_id),
+        })
+    }
+    
+    /// Process a message received from `from_peer`
+    pub fn handle_message(&mut self, from_peer: &str, packet: &OnionMessagePacket) -> Result<OnionMessageAction, OnionMessageError> {
+        if !self.rate_limiter.check(from_peer, Instant::now()) {
+            return Err(OnionMessageError::RateLimited(from_peer.to_string()));
+        }
+        
+        let size = bincode::serialized_size(packet)? as usize;
+        if size > MAX_ONION_MESSAGE_SIZE {
+            return Err(OnionMessageError::TooLarge(size));
+        }
+        
+        match packet {
+            OnionMessagePacket::Sealed { kem_ciphertext, payload } => {
+                match self.open(kem_ciphertext, payload)? {
+                    SealedLayer::Forward { next_node_id, next } => Ok(OnionMessageAction::Forward {
+                        next_node_id,
+                        packet: next,
+                    }),
+                    SealedLayer::Receive { contents, reply_path } => Ok(OnionMessageAction::Receive {
+                        contents,
+                        reply_path,
+                        path_id: None,
+                    }),
+                }
+            }
+            OnionMessagePacket::Blinded { hops, encrypted_contents } => {
+                let (first, rest) = hops.split_first()
+                    .ok_or_else(|| OnionMessageError::InvalidMessage("Blinded path has no hops".to_string()))?;
+                let data: MessageHopData = first.decrypt_data(&self.node_id, &self.kem_keypair.secret_key, self.kem.as_ref())?;
+                
+                if let Some(next_node_id) = data.next_node_id {
+                    return Ok(OnionMessageAction::Forward {
+                        next_node_id,
+                        packet: OnionMessagePacket::Blinded {
+                            hops: rest.to_vec(),
+                            encrypted_contents: encrypted_contents.clone(),
+                        },
+                    });
+                }
+                
+                let path_id = match (data.path_id, rest.is_empty()) {
+                    (Some(path_id), true) => path_id,
+                    _ => return Err(OnionMessageError::InvalidMessage("Blinded path continues past its creator".to_string())),
+                };
+                let plaintext = decrypt_contents(&self.contents_key(&path_id), encrypted_contents)?;
+                let delivered: BlindedContents = bincode::deserialize(&plaintext)?;
+                
+                Ok(OnionMessageAction::Receive {
+                    contents: delivered.contents,
+                    reply_path: delivered.reply_path,
+                    path_id: Some(path_id),
+                })
+            }
+        }
+    }
+    
+    /// Drop the rate limiting state of a disconnected peer
+    pub fn peer_disconnected(&mut self, peer: &str) {
+        self.rate_limiter.forget(peer);
+    }
+    
+    /// Encrypt a layer to `hop`
+    fn seal(&self, hop: &MessageHop, layer: &SealedLayer) -> Result<OnionMessagePacket, OnionMessageError> {
+        let plaintext = bincode::serialize(layer)?;
+        let (kem_ciphertext, payload) = seal_layer(self.kem.as_ref(), &hop.kem_public_key, LAYER_DOMAIN, plaintext)?;
+        
+        Ok(OnionMessagePacket::Sealed { kem_ciphertext, payload })
+    }
+    
+    /// Decrypt a layer sealed to us
+    fn open(&self, kem_ciphertext: &[u8], payload: &[u8]) -> Result<SealedLayer, OnionMessageError> {
+        let plaintext = open_layer(self.kem.as_ref(), &self.kem_keypair.secret_key, LAYER_DOMAIN, kem_ciphertext, payload)?;
+        Ok(bincode::deserialize(&plaintext)?)
+    }
+    
+    /// Contents key of one of our blinded paths, rederived from its path ID
+    fn contents_key(&self, path_id: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"onion_message_contents");
+        hasher.update(&self.kem_keypair.secret_key);
+        hasher.update(path_id);
+        hasher.finalize().into()
+    }
+}
+
+fn path_len(packet: &OnionMessagePacket) -> usize {
+    match packet {
+        OnionMessagePacket::Blinded { hops, .. } => hops.len(),
+        OnionMessagePacket::Sealed { .. } => 1,
+    }
+}
+
+/// Per-message key, so replies along the same path never share a keystream
+fn nonce_key(contents_key: &[u8; 32], nonce: &[u8]) -> SharedSecret {
+    let mut hasher = Sha256::new();
+    hasher.update(contents_key);
+    hasher.update(nonce);
+    SharedSecret::new(hasher.finalize().into())
+}
+
+/// Encrypt contents for a path's creator: nonce, ciphertext, tag
+fn encrypt_contents(contents_key: &[u8; 32], mut plaintext: Vec<u8>) -> Vec<u8> {
+    let mut nonce = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let key = nonce_key(contents_key, &nonce);
+    
+    xor_keystream(&mut plaintext, &key);
+    let tag = blinded_data_tag(&key, &plaintext);
+    
+    let mut encrypted = nonce.to_vec();
+    encrypted.extend_from_slice(&plaintext);
+    encrypted.extend_from_slice(&tag);
+    encrypted
+}
+
+fn decrypt_contents(contents_key: &[u8; 32], encrypted: &[u8]) -> Result<Vec<u8>, OnionMessageError> {
+    if encrypted.len() < 32 + BLINDED_DATA_TAG_SIZE {
+        return Err(OnionMessageError::InvalidMessage("Contents too short".to_string()));
+    }
+    
+    let (nonce, rest) = encrypted.split_at(32);
+    let (ciphertext, tag) = rest.split_at(rest.len() - BLINDED_DATA_TAG_SIZE);
+    let key = nonce_key(contents_key, nonce);
+    if !verify_blinded_data_tag(&key, ciphertext, tag) {
+        return Err(OnionError::InvalidHmac.into());
+    }
+    
+    let mut plaintext = ciphertext.to_vec();
+    xor_keystream(&mut plaintext, &key);
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightning::test_utils::TestKem;
+    
+    fn messenger(name: &str, rate_limit: RateLimitConfig) -> OnionMessenger {
+        let keypair = KemKeyPair {
+            public_key: name.as_bytes().to_vec(),
+            secret_key: name.as_bytes().to_vec(),
+        };
+        OnionMessenger::new(name.to_string(), keypair, Box::new(TestKem), rate_limit)
+    }
+    
+    fn hop(name: &str) -> MessageHop {
+        MessageHop {
+            node_id: name.to_string(),
+            kem_public_key: name.as_bytes().to_vec(),
+        }
+    }
+    
+    /// Relay a packet between messengers until someone receives it
+    fn deliver(nodes: &mut HashMap<String, OnionMessenger>, from: &str, to: String, packet: OnionMessagePacket) -> (String, OnionMessageAction) {
+        let (mut from, mut to, mut packet) = (from.to_string(), to, packet);
+        loop {
+            let action = nodes.get_mut(&to).unwrap().handle_message(&from, &packet).unwrap();
+            match action {
+                OnionMessageAction::Forward { next_node_id, packet: next } => {
+                    from = std::mem::replace(&mut to, next_node_id);
+                    packet = next;
+                }
+                received => return (to, received),
+            }
+        }
+    }
+    
+    #[test]
+    fn test_invoice_request_and_reply_over_reply_path() {
+        let mut nodes: HashMap<String, OnionMessenger> = ["alice", "bob", "carol", "dave"].iter()
+            .map(|name| (name.to_string(), messenger(name, RateLimitConfig::default())))
+            .collect();
+        
+        // Alice asks Dave through Bob and Carol, with a reply path through Carol
+        let reply_path = nodes["alice"].create_reply_path(&[hop("carol")], [5u8; 32]).unwrap();
+        assert_eq!(reply_path.introduction_node_id, "carol");
+        
+        let request = OnionMessageContents::new().with(TLV_INVOICE_REQUEST, b"offer-1".to_vec());
+        let (first, packet) = nodes["alice"]
+            .create_message(&[hop("bob"), hop("carol")], Destination::Node(hop("dave")), request, Some(reply_path))
+            .unwrap();
+        assert_eq!(first, "bob");
+        
+        // Intermediate hops cannot read the request
+        assert!(nodes.get_mut("carol").unwrap().handle_message("alice", &packet).is_err());
+        
+        let (receiver, action) = deliver(&mut nodes, "alice", first, packet);
+        assert_eq!(receiver, "dave");
+        let reply_path = match action {
+            OnionMessageAction::Receive { contents, reply_path, path_id } => {
+                assert_eq!(contents.get(TLV_INVOICE_REQUEST), Some(&b"offer-1"[..]));
+                assert_eq!(path_id, None);
+                reply_path.unwrap()
+            }
+            other => panic!("unexpected {:?}", other),
+        };
+        
+        // Dave answers along the reply path via Bob without learning it ends at Alice
+        let invoice = OnionMessageContents::new().with(TLV_INVOICE, b"invoice-1".to_vec());
+        let (first, packet) = nodes["dave"]
+            .create_message(&[hop("bob")], Destination::BlindedPath(reply_path), invoice, None)
+            .unwrap();
+        assert_eq!(first, "bob");
+        
+        let (receiver, action) = deliver(&mut nodes, "dave", first, packet);
+        assert_eq!(receiver, "alice");
+        match action {
+            OnionMessageAction::Receive { contents, reply_path, path_id } => {
+                assert_eq!(contents.get(TLV_INVOICE), Some(&b"invoice-1"[..]));
+                assert!(reply_path.is_none());
+                assert_eq!(path_id, Some([5u8; 32]));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_blinded_path_only_opens_for_its_hops() {
+        let alice = messenger("alice", RateLimitConfig::default());
+        let mut mallory = messenger("mallory", RateLimitConfig::default());
+        
+        let path = alice.create_reply_path(&[hop("carol")], [1u8; 32]).unwrap();
+        let contents = OnionMessageContents::new().with(TLV_HELD_HTLC_AVAILABLE, vec![1]);
+        let (_, packet) = mallory.create_message(&[], Destination::BlindedPath(path), contents, None).unwrap();
+        
+        // Mallory is not the introduction node and cannot peel the first hop
+        assert!(mallory.handle_message("x", &packet).is_err());
+        
+        // Tampered layers are rejected
+        let (_, mut sealed) = alice.create_message(&[], Destination::Node(hop("mallory")), OnionMessageContents::new(), None).unwrap();
+        if let OnionMessagePacket::Sealed { payload, .. } = &mut sealed {
+            payload[0] ^= 1;
+        }
+        assert!(matches!(
+            mallory.handle_message("alice", &sealed),
+            Err(OnionMessageError::Onion(OnionError::InvalidHmac))
+        ));
+    }
+    
+    #[test]
+    fn test_per_peer_rate_limit() {
+        let mut bob = messenger("bob", RateLimitConfig { burst: 3, per_second: 0.0 });
+        let alice = messenger("alice", RateLimitConfig::default());
+        let (_, packet) = alice.create_message(&[], Destination::Node(hop("bob")), OnionMessageContents::new(), None).unwrap();
+        
+        for _ in 0..3 {
+            bob.handle_message("alice", &packet).unwrap();
+        }
+        assert!(matches!(bob.handle_message("alice", &packet), Err(OnionMessageError::RateLimited(_))));
+        
+        // Other peers have their own allowance, and a reconnect starts afresh
+        assert!(bob.handle_message("carol", &packet).is_ok());
+        bob.peer_disconnected("alice");
+        assert!(bob.handle_message("alice", &packet).is_ok());
+        
+        // Tokens are earned back over time
+        let mut limiter = MessageRateLimiter::new(RateLimitConfig { burst: 1, per_second: 2.0 });
+        let start = Instant::now();
+        assert!(limiter.check("p", start));
+        assert!(!limiter.check("p", start));
+        assert!(limiter.check("p", start + std::time::Duration::from_millis(600)));
+    }
+}
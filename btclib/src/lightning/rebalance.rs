@@ -0,0 +1,485 @@
+//! Channel Rebalancing
+//!
+//! Moves liquidity between our own channels with circular self-payments and
+//! keeps the routing policy of each channel in step with its balance. A
+//! rebalance leaves through a channel holding too much local balance and
+//! comes back through a depleted one, paying the hops in between a fee that
+//! must stay within the configured budget.
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+use tracing::{info, warn};
+
+use super::channel::ChannelId;
+use super::manager::{LightningChannel, LightningManager, ManagerError, RoutingPolicy};
+use super::router::{Router, RoutingError};
+
+/// Rebalancing errors
+#[derive(Debug, Error)]
+pub enum RebalanceError {
+    #[error("Manager error: {0}")]
+    Manager(#[from] ManagerError),
+    
+    #[error("Routing error: {0}")]
+    Routing(#[from] RoutingError),
+    
+    #[error("Invalid channel ID: {0}")]
+    InvalidChannelId(String),
+    
+    #[error("Route fee {fee_msat} msat exceeds budget of {budget_msat} msat")]
+    FeeBudgetExceeded { fee_msat: u64, budget_msat: u64 },
+}
+
+/// When and how much to rebalance
+#[derive(Debug, Clone)]
+pub struct RebalancerConfig {
+    /// Channels with a local balance ratio below this need inbound liquidity moved back
+    pub depleted_ratio: f64,
+    
+    /// Channels with a local balance ratio above this can give liquidity away
+    pub full_ratio: f64,
+    
+    /// Ratio both sides of a rebalance are moved towards
+    pub target_ratio: f64,
+    
+    /// Maximum fee paid, in parts per million of the rebalanced amount
+    pub max_fee_ppm: u64,
+    
+    /// Smallest rebalance worth paying for, in novas
+    pub min_amount_novas: u64,
+    
+    /// Largest single rebalance, in novas
+    pub max_amount_novas: u64,
+}
+
+impl Default for RebalancerConfig {
+    fn default() -> Self {
+        Self {
+            depleted_ratio: 0.2,
+            full_ratio: 0.8,
+            target_ratio: 0.5,
+            max_fee_ppm: 1_000,
+            min_amount_novas: 10_000,
+            max_amount_novas: 1_000_000,
+        }
+    }
+}
+
+/// Local view of a channel's balance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelBalance {
+    /// Channel ID in hex
+    pub channel_id: String,
+    
+    /// Channel capacity in novas
+    pub capacity: u64,
+    
+    /// Our side of the channel in novas
+    pub local_balance: u64,
+}
+
+impl ChannelBalance {
+    /// Fraction of the capacity on our side
+    pub fn local_ratio(&self) -> f64 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+        self.local_balance as f64 / self.capacity as f64
+    }
+    
+    fn amount_at(&self, ratio: f64) -> u64 {
+        (self.capacity as f64 * ratio) as u64
+    }
+}
+
+impl From<&LightningChannel> for ChannelBalance {
+    fn from(channel: &LightningChannel) -> Self {
+        Self {
+            channel_id: channel.channel_id.clone(),
+            capacity: channel.capacity,
+            local_balance: channel.local_balance,
+        }
+    }
+}
+
+/// A planned move of liquidity from a full channel to a depleted one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebalanceCandidate {
+    /// Channel the self-payment leaves through
+    pub outgoing: String,
+    
+    /// Channel the self-payment comes back through
+    pub incoming: String,
+    
+    /// Amount to move in novas
+    pub amount_novas: u64,
+}
+
+/// Outcome of an executed rebalance
+#[derive(Debug, Clone)]
+pub struct RebalanceResult {
+    /// Channel the self-payment left through
+    pub outgoing: String,
+    
+    /// Channel the self-payment came back through
+    pub incoming: String,
+    
+    /// Amount moved in novas
+    pub amount_novas: u64,
+    
+    /// Fee paid to intermediate hops in millinovas
+    pub fee_msat: u64,
+    
+    /// Number of hops in the circular route
+    pub hops: usize,
+}
+
+/// Finds and executes circular rebalances
+pub struct Rebalancer {
+    config: RebalancerConfig,
+}
+
+impl Rebalancer {
+    /// Create a rebalancer
+    pub fn new(config: RebalancerConfig) -> Self {
+        Self { config }
+    }
+    
+    /// Get the configuration
+    pub fn config(&self) -> &RebalancerConfig {
+        &self.config
+    }
+    
+    /// Fee budget for moving `amount_novas`, in millinovas
+    pub fn fee_budget_msat(&self, amount_novas: u64) -> u64 {
+        amount_novas * 1000 * self.config.max_fee_ppm / 1_000_000
+    }
+    
+    /// Pair depleted channels with full ones
+    ///
+    /// The most depleted channels are served first, each from the fullest
+    /// channels that still have surplus above the target ratio.
+    pub fn plan(&self, channels: &[ChannelBalance]) -> Vec<RebalanceCandidate> {
+        let mut depleted: Vec<&ChannelBalance> = channels.iter()
+            .filter(|channel| channel.capacity > 0 && channel.local_ratio() < self.config.depleted_ratio)
+            .collect();
+        depleted.sort_by(|a, b| a.local_ratio().total_cmp(&b.local_ratio()));
+        
+        let mut full: Vec<(&ChannelBalance, u64)> = channels.iter()
+            .filter(|channel| channel.capacity > 0 && channel.local_ratio() > self.config.full_ratio)
+            .map(|channel| {
+                let surplus = channel.local_balance.saturating_sub(channel.amount_at(self.config.target_ratio));
+                (channel, surplus)
+            })
+            .collect();
+        full.sort_by(|a, b| b.0.local_ratio().total_cmp(&a.0.local_ratio()));
+        
+        let mut candidates = Vec::new();
+        for channel in depleted {
+            let mut deficit = channel.amount_at(self.config.target_ratio).saturating_sub(channel.local_balance);
+            
+            for (source, surplus) in full.iter_mut() {
+                if deficit < self.config.min_amount_novas {
+                    break;
+                }
+                
+                let amount = deficit.min(*surplus).min(self.config.max_amount_novas);
+                if amount < self.config.min_amount_novas {
+                    continue;
+                }
+                
+                candidates.push(RebalanceCandidate {
+                    outgoing: source.channel_id.clone(),
+                    incoming: channel.channel_id.clone(),
+                    amount_novas: amount,
+                });
+                *surplus -= amount;
+                deficit -= amount;
+            }
+        }
+        
+        candidates
+    }
+    
+    /// Route and pay a single rebalance within the fee budget
+    pub fn execute(
+        &self,
+        manager: &LightningManager,
+        router: &Router,
+        candidate: &RebalanceCandidate,
+    ) -> Result<RebalanceResult, RebalanceError> {
+        let outgoing = ChannelId::from_hex(&candidate.outgoing)
+            .map_err(|_| RebalanceError::InvalidChannelId(candidate.outgoing.clone()))?;
+        let incoming = ChannelId::from_hex(&candidate.incoming)
+            .map_err(|_| RebalanceError::InvalidChannelId(candidate.incoming.clone()))?;
+        
+        let route = router.find_circular_route(&outgoing, &incoming, candidate.amount_novas * 1000)?;
+        
+        let budget_msat = self.fee_budget_msat(candidate.amount_novas);
+        if route.total_fee_msat > budget_msat {
+            return Err(RebalanceError::FeeBudgetExceeded {
+                fee_msat: route.total_fee_msat,
+                budget_msat,
+            });
+        }
+        
+        let fee_msat = manager.pay_circular_route(&route)?;
+        
+        Ok(RebalanceResult {
+            outgoing: candidate.outgoing.clone(),
+            incoming: candidate.incoming.clone(),
+            amount_novas: candidate.amount_novas,
+            fee_msat,
+            hops: route.hops.len(),
+        })
+    }
+    
+    /// Plan and execute rebalances for all active channels of `manager`
+    ///
+    /// A failed rebalance does not stop the others; each outcome is returned.
+    pub fn run(
+        &self,
+        manager: &LightningManager,
+        router: &Router,
+    ) -> Result<Vec<Result<RebalanceResult, RebalanceError>>, RebalanceError> {
+        let balances: Vec<ChannelBalance> = manager.get_channels(false, false)?
+            .iter()
+            .map(ChannelBalance::from)
+            .collect();
+        
+        let results: Vec<_> = self.plan(&balances).iter()
+            .map(|candidate| {
+                let result = self.execute(manager, router, candidate);
+                match &result {
+                    Ok(done) => info!("Rebalanced {} novas from {} to {} for {} msat",
+                        done.amount_novas, done.outgoing, done.incoming, done.fee_msat),
+                    Err(e) => warn!("Rebalance from {} to {} failed: {}", candidate.outgoing, candidate.incoming, e),
+                }
+                result
+            })
+            .collect();
+        
+        Ok(results)
+    }
+}
+
+/// Parameters of the dynamic fee policy
+#[derive(Debug, Clone)]
+pub struct FeePolicyConfig {
+    /// Base fee in millinovas
+    pub base_fee_msat: u64,
+    
+    /// Fee rate of a balanced channel with ordinary demand, in parts per million
+    pub fee_rate_ppm: u64,
+    
+    /// Lowest fee rate ever advertised
+    pub min_fee_rate_ppm: u64,
+    
+    /// Highest fee rate ever advertised
+    pub max_fee_rate_ppm: u64,
+    
+    /// How strongly the balance ratio moves the fee rate, 0 disables it
+    pub balance_sensitivity: f64,
+    
+    /// Forwards per window above which a channel counts as busy
+    pub busy_forwards: u64,
+    
+    /// Fraction the fee rate is raised for busy channels and lowered for idle ones
+    pub demand_step: f64,
+    
+    /// CLTV delta we require
+    pub time_lock_delta: u32,
+    
+    /// Smallest HTLC we forward, in millinovas
+    pub min_htlc_msat: u64,
+}
+
+impl Default for FeePolicyConfig {
+    fn default() -> Self {
+        Self {
+            base_fee_msat: 1_000,
+            fee_rate_ppm: 500,
+            min_fee_rate_ppm: 10,
+            max_fee_rate_ppm: 5_000,
+            balance_sensitivity: 1.0,
+            busy_forwards: 10,
+            demand_step: 0.25,
+            time_lock_delta: 40,
+            min_htlc_msat: 1_000,
+        }
+    }
+}
+
+/// Forwards through one outgoing channel in the current window
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardStats {
+    /// Number of forwards
+    pub forwards: u64,
+    
+    /// Amount forwarded in millinovas
+    pub volume_msat: u64,
+    
+    /// Fees earned in millinovas
+    pub fees_msat: u64,
+}
+
+/// Adjusts routing policies from channel balances and forwarding history
+///
+/// Channels low on local balance get more expensive so they drain slower,
+/// full ones get cheaper. Busy channels are priced up and idle ones down.
+/// Forwarding history is collected per window and cleared on each `apply`.
+pub struct FeePolicyEngine {
+    config: FeePolicyConfig,
+    history: HashMap<String, ForwardStats>,
+}
+
+impl FeePolicyEngine {
+    /// Create a fee policy engine
+    pub fn new(config: FeePolicyConfig) -> Self {
+        Self {
+            config,
+            history: HashMap::new(),
+        }
+    }
+    
+    /// Record a forward leaving through `channel_id`
+    pub fn record_forward(&mut self, channel_id: &str, amount_msat: u64, fee_msat: u64) {
+        let stats = self.history.entry(channel_id.to_string()).or_default();
+        stats.forwards += 1;
+        stats.volume_msat += amount_msat;
+        stats.fees_msat += fee_msat;
+    }
+    
+    /// Forwards recorded for a channel in the current window
+    pub fn forward_stats(&self, channel_id: &str) -> Option<&ForwardStats> {
+        self.history.get(channel_id)
+    }
+    
+    /// Policy a channel should advertise right now
+    pub fn policy_for(&self, channel: &ChannelBalance) -> RoutingPolicy {
+        let balance_factor = (1.0 + self.config.balance_sensitivity * (1.0 - 2.0 * channel.local_ratio())).max(0.0);
+        
+        let forwards = self.history.get(&channel.channel_id).map(|stats| stats.forwards).unwrap_or(0);
+        let demand_factor = if forwards >= self.config.busy_forwards {
+            1.0 + self.config.demand_step
+        } else if forwards == 0 {
+            1.0 - self.config.demand_step
+        } else {
+            1.0
+        };
+        
+        let fee_rate = (self.config.fee_rate_ppm as f64 * balance_factor * demand_factor).round() as u64;
+        
+        RoutingPolicy {
+            time_lock_delta: self.config.time_lock_delta,
+            min_htlc: self.config.min_htlc_msat,
+            fee_base_msat: self.config.base_fee_msat,
+            fee_rate_milli_msat: fee_rate.clamp(self.config.min_fee_rate_ppm, self.config.max_fee_rate_ppm),
+            disabled: channel.local_balance == 0,
+            max_htlc_msat: channel.local_balance * 1000,
+            last_update: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as u32)
+                .unwrap_or(0),
+        }
+    }
+    
+    /// Update the policy of every active channel of `manager` and start a new window
+    pub fn apply(&mut self, manager: &LightningManager) -> Result<Vec<(String, RoutingPolicy)>, ManagerError> {
+        let mut updated = Vec::new();
+        
+        for channel in manager.get_channels(false, false)? {
+            let policy = self.policy_for(&ChannelBalance::from(&channel));
+            manager.set_routing_policy(&channel.channel_id, policy.clone())?;
+            updated.push((channel.channel_id, policy));
+        }
+        
+        self.history.clear();
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn balance(id: u8, capacity: u64, local_balance: u64) -> ChannelBalance {
+        ChannelBalance {
+            channel_id: hex::encode([id; 32]),
+            capacity,
+            local_balance,
+        }
+    }
+    
+    #[test]
+    fn test_plan_moves_surplus_to_depleted_channels() {
+        let rebalancer = Rebalancer::new(RebalancerConfig {
+            max_amount_novas: 300_000,
+            ..Default::default()
+        });
+        let channels = vec![
+            balance(1, 1_000_000, 900_000),
+            balance(2, 1_000_000, 100_000),
+            balance(3, 1_000_000, 500_000),
+            balance(4, 1_000_000, 50_000),
+            balance(5, 1_000_000, 850_000),
+        ];
+        
+        let plan = rebalancer.plan(&channels);
+        let moves: Vec<(u8, u8, u64)> = plan.iter()
+            .map(|c| (hex::decode(&c.outgoing).unwrap()[0], hex::decode(&c.incoming).unwrap()[0], c.amount_novas))
+            .collect();
+        
+        // Most depleted first, fed from the fullest; amounts capped per move
+        assert_eq!(moves, vec![
+            (1, 4, 300_000),
+            (5, 4, 150_000),
+            (1, 2, 100_000),
+            (5, 2, 200_000),
+        ]);
+    }
+    
+    #[test]
+    fn test_plan_skips_small_moves() {
+        let rebalancer = Rebalancer::new(RebalancerConfig {
+            min_amount_novas: 50_000,
+            ..Default::default()
+        });
+        
+        // Only 35_000 novas of surplus above the target, below the minimum
+        let channels = vec![balance(1, 100_000, 85_000), balance(2, 100_000, 15_000)];
+        assert!(rebalancer.plan(&channels).is_empty());
+        assert_eq!(rebalancer.fee_budget_msat(100_000), 100_000);
+    }
+    
+    #[test]
+    fn test_fee_policy_follows_balance_and_demand() {
+        let mut engine = FeePolicyEngine::new(FeePolicyConfig::default());
+        let depleted = balance(1, 1_000_000, 100_000);
+        let balanced = balance(2, 1_000_000, 500_000);
+        let full = balance(3, 1_000_000, 900_000);
+        
+        // Ordinary demand on every channel
+        for channel in [&depleted, &balanced, &full] {
+            engine.record_forward(&channel.channel_id, 10_000_000, 5_000);
+        }
+        assert_eq!(engine.policy_for(&balanced).fee_rate_milli_msat, 500);
+        assert_eq!(engine.policy_for(&depleted).fee_rate_milli_msat, 900);
+        assert_eq!(engine.policy_for(&full).fee_rate_milli_msat, 100);
+        assert_eq!(engine.policy_for(&full).max_htlc_msat, 900_000_000);
+        
+        // A busy channel is priced up
+        for _ in 0..9 {
+            engine.record_forward(&balanced.channel_id, 10_000_000, 5_000);
+        }
+        assert_eq!(engine.forward_stats(&balanced.channel_id).unwrap().forwards, 10);
+        assert_eq!(engine.policy_for(&balanced).fee_rate_milli_msat, 625);
+        
+        // An idle channel is priced down, an empty one is disabled
+        let idle = balance(4, 1_000_000, 500_000);
+        assert_eq!(engine.policy_for(&idle).fee_rate_milli_msat, 375);
+        assert!(engine.policy_for(&balance(5, 1_000_000, 0)).disabled);
+    }
+}
@@ -4,6 +4,8 @@
 // which handles finding payment paths and routing payments through the network.
 
 use crate::lightning::channel::{ChannelId, ChannelState};
+use crate::lightning::onion::BlindedPath;
+use crate::lightning::green_routing::GreenRoutingParameters;
 use std::collections::{HashMap, HashSet, BTreeMap};
 use std::cmp::Ordering;
 use std::sync::{Arc, RwLock, Mutex};
@@ -33,6 +35,9 @@ pub enum RoutingError {
     
     #[error("Routing constraint error: {0}")]
     ConstraintError(String),
+    
+    #[error("Scorer persistence error: {0}")]
+    PersistenceError(String),
 }
 
 /// Node identifier in the Lightning Network
@@ -155,6 +160,9 @@ pub struct PaymentPath {
     
     /// Total amount to send including fees
     pub total_amount_msat: u64,
+    
+    /// Blinded path appended after the last hop (the introduction node)
+    pub blinded_tail: Option<BlindedPath>,
 }
 
 impl PaymentPath {
@@ -165,12 +173,13 @@ impl PaymentPath {
             total_fee_msat: 0,
             total_cltv_delta: 0,
             total_amount_msat: 0,
+            blinded_tail: None,
         }
     }
     
     /// Check if the path is empty
     pub fn is_empty(&self) -> bool {
-        self.hops.is_empty()
+        self.hops.is_empty() && self.blinded_tail.is_none()
     }
     
     /// Get the number of hops
@@ -210,6 +219,9 @@ pub struct RouterPreferences {
     
     /// Channels to avoid
     pub avoid_channels: HashSet<ChannelId>,
+    
+    /// Cost that path finding minimizes
+    pub cost_function: RouteCostFunction,
 }
 
 impl Default for RouterPreferences {
@@ -223,6 +235,81 @@ impl Default for RouterPreferences {
             preferred_nodes: HashSet::new(),
             avoid_nodes: HashSet::new(),
             avoid_channels: HashSet::new(),
+            cost_function: RouteCostFunction::FeeAndReliability,
+        }
+    }
+}
+
+/// Environmental data a node advertises in its node announcement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeEnvironmentalData {
+    /// Carbon intensity of the node's electricity in gCO2e/kWh
+    pub carbon_intensity: f64,
+    
+    /// Share of renewable energy in percent
+    pub renewable_percentage: f64,
+    
+    /// Whether the node holds a green certification
+    pub green_certified: bool,
+}
+
+/// What path finding knows about one hop when costing it
+#[derive(Debug, Clone)]
+pub struct HopCostInputs {
+    /// Amount the hop carries in millinovas
+    pub amount_msat: u64,
+    
+    /// Fee charged for the hop in millinovas
+    pub fee_msat: u64,
+    
+    /// Liquidity penalty of the channel in millinovas
+    pub liquidity_penalty_msat: u64,
+    
+    /// Probability that the channel can carry the amount
+    pub success_probability: f64,
+    
+    /// Advertised carbon intensity of the node the hop leads to
+    pub carbon_intensity: Option<f64>,
+    
+    /// Advertised renewable share of the node the hop leads to
+    pub renewable_percentage: Option<f64>,
+    
+    /// Whether the node the hop leads to is green certified
+    pub green_certified: bool,
+}
+
+/// Cost function for path finding
+#[derive(Debug, Clone)]
+pub enum RouteCostFunction {
+    /// Fees plus liquidity penalties
+    FeeAndReliability,
+    
+    /// Fees plus liquidity penalties plus a carbon penalty per hop,
+    /// weighted by the green routing parameters
+    CarbonAware(GreenRoutingParameters),
+    
+    /// Custom cost of a hop
+    Custom(fn(&HopCostInputs) -> u64),
+}
+
+impl RouteCostFunction {
+    /// Cost of one hop in millinovas
+    pub fn hop_cost_msat(&self, inputs: &HopCostInputs) -> u64 {
+        match self {
+            RouteCostFunction::FeeAndReliability => {
+                inputs.fee_msat.saturating_add(inputs.liquidity_penalty_msat)
+            },
+            RouteCostFunction::CarbonAware(params) => {
+                inputs.fee_msat
+                    .saturating_add(inputs.liquidity_penalty_msat)
+                    .saturating_add(params.hop_penalty_msat(
+                        inputs.amount_msat,
+                        inputs.carbon_intensity,
+                        inputs.renewable_percentage,
+                        inputs.green_certified,
+                    ))
+            },
+            RouteCostFunction::Custom(cost) => cost(inputs),
         }
     }
 }
@@ -357,6 +444,201 @@ impl ChannelScorer {
     }
 }
 
+/// Parameters of the liquidity-bounds scorer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidityScorerParams {
+    /// Time after which learned bounds have relaxed halfway back to [0, capacity]
+    pub liquidity_half_life_secs: u64,
+    
+    /// Flat penalty per hop in millisatoshis
+    pub base_penalty_msat: u64,
+    
+    /// Penalty per unit of -log10(success probability) in millisatoshis
+    pub liquidity_penalty_multiplier_msat: u64,
+    
+    /// Additional penalty per unit of -log10(success probability), in parts per
+    /// million of the amount sent
+    pub liquidity_penalty_amount_multiplier_ppm: u64,
+}
+
+impl Default for LiquidityScorerParams {
+    fn default() -> Self {
+        Self {
+            liquidity_half_life_secs: 6 * 3600,
+            base_penalty_msat: 500,
+            liquidity_penalty_multiplier_msat: 30_000,
+            liquidity_penalty_amount_multiplier_ppm: 2_000,
+        }
+    }
+}
+
+/// Learned bounds on the liquidity available in one direction of a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiquidityBounds {
+    /// Amount known to be forwardable in millisatoshis
+    pub min_liquidity_msat: u64,
+    
+    /// Amount known not to be forwardable (exclusive upper bound) in millisatoshis
+    pub max_liquidity_msat: u64,
+    
+    /// Time the bounds were last updated
+    pub last_updated: u64,
+}
+
+impl LiquidityBounds {
+    /// Bounds relaxed towards [0, capacity] according to their age
+    fn decayed(&self, capacity_msat: u64, now: u64, half_life_secs: u64) -> (u64, u64) {
+        let max = self.max_liquidity_msat.min(capacity_msat);
+        let min = self.min_liquidity_msat.min(max);
+        
+        if half_life_secs == 0 {
+            return (0, capacity_msat);
+        }
+        
+        let elapsed = now.saturating_sub(self.last_updated) as f64;
+        let factor = 0.5f64.powf(elapsed / half_life_secs as f64);
+        
+        let min = (min as f64 * factor) as u64;
+        let max = capacity_msat - ((capacity_msat - max) as f64 * factor) as u64;
+        
+        (min, max)
+    }
+}
+
+/// Probabilistic scorer that learns liquidity bounds per directed channel
+///
+/// Liquidity is assumed uniformly distributed between the learned bounds, so the
+/// chance that a channel can forward `amount` is `(max - amount) / (max - min)`.
+/// Successes raise the lower bound, failures lower the upper bound, and both relax
+/// back towards `[0, capacity]` over time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LiquidityScorer {
+    /// Scorer parameters
+    params: LiquidityScorerParams,
+    
+    /// Learned bounds keyed by channel and the node sending over it
+    bounds: HashMap<(ChannelId, NodeId), LiquidityBounds>,
+}
+
+impl LiquidityScorer {
+    /// Create a new scorer with the given parameters
+    pub fn new(params: LiquidityScorerParams) -> Self {
+        Self {
+            params,
+            bounds: HashMap::new(),
+        }
+    }
+    
+    /// Get the scorer parameters
+    pub fn params(&self) -> &LiquidityScorerParams {
+        &self.params
+    }
+    
+    /// Current (decayed) bounds for a directed channel
+    pub fn liquidity_bounds(&self, channel: &ChannelInfo, now: u64) -> (u64, u64) {
+        let capacity_msat = channel.capacity.saturating_mul(1000);
+        
+        match self.bounds.get(&(channel.channel_id.clone(), channel.source.clone())) {
+            Some(bounds) => bounds.decayed(capacity_msat, now, self.params.liquidity_half_life_secs),
+            None => (0, capacity_msat),
+        }
+    }
+    
+    /// Probability that `channel` can forward `amount_msat`
+    pub fn success_probability(&self, channel: &ChannelInfo, amount_msat: u64, now: u64) -> f64 {
+        let (min, max) = self.liquidity_bounds(channel, now);
+        
+        if amount_msat <= min {
+            1.0
+        } else if amount_msat >= max {
+            0.0
+        } else {
+            (max - amount_msat) as f64 / (max - min) as f64
+        }
+    }
+    
+    /// Penalty in millisatoshis for sending `amount_msat` over `channel`
+    ///
+    /// Returns `None` if the channel is known not to have enough liquidity.
+    pub fn channel_penalty_msat(&self, channel: &ChannelInfo, amount_msat: u64, now: u64) -> Option<u64> {
+        let probability = self.success_probability(channel, amount_msat, now);
+        if probability <= 0.0 {
+            return None;
+        }
+        
+        let multiplier = self.params.liquidity_penalty_multiplier_msat as f64
+            + amount_msat as f64 * self.params.liquidity_penalty_amount_multiplier_ppm as f64 / 1_000_000.0;
+        let liquidity_penalty = -probability.log10() * multiplier;
+        
+        Some(self.params.base_penalty_msat + liquidity_penalty as u64)
+    }
+    
+    /// Record that `source` forwarded `amount_msat` over a channel
+    pub fn record_success(&mut self, channel: &ChannelInfo, amount_msat: u64, now: u64) {
+        let (min, max) = self.liquidity_bounds(channel, now);
+        
+        // The success disproves the upper bound if it was at or below the amount
+        let max = if amount_msat >= max {
+            channel.capacity.saturating_mul(1000)
+        } else {
+            max
+        };
+        
+        self.bounds.insert((channel.channel_id.clone(), channel.source.clone()), LiquidityBounds {
+            min_liquidity_msat: min.max(amount_msat).min(max),
+            max_liquidity_msat: max,
+            last_updated: now,
+        });
+    }
+    
+    /// Record that a channel failed to forward `amount_msat`
+    pub fn record_failure(&mut self, channel: &ChannelInfo, amount_msat: u64, now: u64) {
+        let (min, max) = self.liquidity_bounds(channel, now);
+        
+        // The failure disproves the lower bound if it was below the amount
+        let max = max.min(amount_msat);
+        let min = if min >= max { 0 } else { min };
+        
+        self.bounds.insert((channel.channel_id.clone(), channel.source.clone()), LiquidityBounds {
+            min_liquidity_msat: min,
+            max_liquidity_msat: max,
+            last_updated: now,
+        });
+    }
+    
+    /// Forget bounds that have fully decayed
+    pub fn prune(&mut self, now: u64) {
+        let horizon = self.params.liquidity_half_life_secs.saturating_mul(16);
+        self.bounds.retain(|_, bounds| now.saturating_sub(bounds.last_updated) < horizon);
+    }
+    
+    /// Number of directed channels with learned bounds
+    pub fn tracked_channels(&self) -> usize {
+        self.bounds.len()
+    }
+    
+    /// Save the scorer state to a file
+    pub fn save(&self, path: &std::path::Path) -> Result<(), RoutingError> {
+        let bytes = bincode::serialize(self)
+            .map_err(|e| RoutingError::PersistenceError(e.to_string()))?;
+        
+        // Write to a temporary file first so a crash cannot leave a torn file
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, bytes)
+            .and_then(|_| std::fs::rename(&tmp_path, path))
+            .map_err(|e| RoutingError::PersistenceError(e.to_string()))
+    }
+    
+    /// Load scorer state from a file
+    pub fn load(path: &std::path::Path) -> Result<Self, RoutingError> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| RoutingError::PersistenceError(e.to_string()))?;
+        
+        bincode::deserialize(&bytes)
+            .map_err(|e| RoutingError::PersistenceError(e.to_string()))
+    }
+}
+
 /// Network graph representing the Lightning Network
 #[derive(Clone)]
 pub struct NetworkGraph {
@@ -520,8 +802,15 @@ pub struct Router {
     /// Channel scorer
     scorer: ChannelScorer,
     
+    /// Liquidity-bounds scorer used as part of the path cost
+    liquidity_scorer: LiquidityScorer,
+    
     /// Local node ID
     local_node: NodeId,
+    
+    /// Environmental data from node announcements, shared by clones so
+    /// gossip reaches routers in use
+    node_environmental: Arc<RwLock<HashMap<NodeId, (u64, NodeEnvironmentalData)>>>,
 }
 
 impl Router {
@@ -531,7 +820,9 @@ impl Router {
             graph: NetworkGraph::new(),
             preferences: RouterPreferences::default(),
             scorer: ChannelScorer::new(ScoringFunction::SuccessProbability),
+            liquidity_scorer: LiquidityScorer::default(),
             local_node: NodeId::new("local".to_string()),
+            node_environmental: Arc::new(RwLock::new(HashMap::new())),
         }
     }
     
@@ -562,12 +853,48 @@ impl Router {
         self.preferences = preferences;
     }
     
+    /// Routing preferences used when a payment does not bring its own
+    pub fn preferences(&self) -> &RouterPreferences {
+        &self.preferences
+    }
+    
+    /// Record environmental data from a node announcement
+    ///
+    /// Returns false if an announcement at least as recent was already applied.
+    pub fn update_node_environmental(&self, node_id: NodeId, data: NodeEnvironmentalData, timestamp: u64) -> bool {
+        let mut environmental = self.node_environmental.write().unwrap();
+        if let Some((last_timestamp, _)) = environmental.get(&node_id) {
+            if *last_timestamp >= timestamp {
+                return false;
+            }
+        }
+        
+        environmental.insert(node_id, (timestamp, data));
+        true
+    }
+    
+    /// Environmental data a node has advertised
+    pub fn node_environmental(&self, node_id: &NodeId) -> Option<NodeEnvironmentalData> {
+        self.node_environmental.read().unwrap().get(node_id).map(|(_, data)| data.clone())
+    }
+    
     /// Find a route to a destination
     pub fn find_route(
         &self,
         destination: &str,
         amount_msat: u64,
         route_hints: &[RouteHint],
+    ) -> Result<PaymentPath, RoutingError> {
+        self.find_route_with_preferences(destination, amount_msat, route_hints, &self.preferences)
+    }
+    
+    /// Find a route to a destination under preferences given for this payment
+    pub fn find_route_with_preferences(
+        &self,
+        destination: &str,
+        amount_msat: u64,
+        route_hints: &[RouteHint],
+        preferences: &RouterPreferences,
     ) -> Result<PaymentPath, RoutingError> {
         let destination_id = NodeId::new(destination.to_string());
         
@@ -579,7 +906,7 @@ impl Router {
         }
         
         // Set up timeout
-        let timeout = Duration::from_millis(self.preferences.path_finding_timeout_ms);
+        let timeout = Duration::from_millis(preferences.path_finding_timeout_ms);
         let start_time = Instant::now();
         
         // Add route hints to the graph temporarily
@@ -609,6 +936,7 @@ impl Router {
             &self.local_node,
             &destination_id,
             amount_msat,
+            preferences,
             &start_time,
             &timeout,
         )?;
@@ -616,6 +944,141 @@ impl Router {
         Ok(path)
     }
     
+    /// Find a route to the introduction node of a blinded path
+    ///
+    /// The clear part of the route carries the amount plus the blinded path's
+    /// aggregate fee; the blinded hops are attached as the route's tail.
+    pub fn find_route_to_blinded_path(
+        &self,
+        blinded_path: &BlindedPath,
+        amount_msat: u64,
+    ) -> Result<PaymentPath, RoutingError> {
+        if blinded_path.is_empty() {
+            return Err(RoutingError::InvalidDestination("Blinded path has no hops".to_string()));
+        }
+        
+        if amount_msat < blinded_path.payinfo.htlc_minimum_msat {
+            return Err(RoutingError::ConstraintError(
+                format!("Amount {} below blinded path minimum {}", amount_msat, blinded_path.payinfo.htlc_minimum_msat)
+            ));
+        }
+        
+        let blinded_fee = blinded_path.payinfo.fee_msat(amount_msat);
+        let introduction_amount = amount_msat + blinded_fee;
+        
+        // We may be the introduction node ourselves
+        let mut path = if blinded_path.introduction_node_id == self.local_node.as_str() {
+            PaymentPath::new()
+        } else {
+            self.find_route(&blinded_path.introduction_node_id, introduction_amount, &[])?
+        };
+        
+        path.total_fee_msat += blinded_fee;
+        path.total_cltv_delta += blinded_path.payinfo.cltv_expiry_delta as u32;
+        path.total_amount_msat = amount_msat + path.total_fee_msat;
+        
+        if path.total_cltv_delta > self.preferences.max_cltv_expiry_delta as u32 {
+            return Err(RoutingError::ConstraintError(
+                format!("Total CLTV delta {} exceeds maximum", path.total_cltv_delta)
+            ));
+        }
+        
+        path.blinded_tail = Some(blinded_path.clone());
+        
+        Ok(path)
+    }
+    
+    /// Find a circular route that leaves through `outgoing` and comes back through `incoming`
+    ///
+    /// Both channels must be ours. The middle of the route is found from the
+    /// peer of `outgoing` to the peer of `incoming` without touching the local
+    /// node; the fee of the last channel is charged by the peer of `incoming`.
+    pub fn find_circular_route(
+        &self,
+        outgoing: &ChannelId,
+        incoming: &ChannelId,
+        amount_msat: u64,
+    ) -> Result<PaymentPath, RoutingError> {
+        if outgoing == incoming {
+            return Err(RoutingError::ConstraintError("Circular route needs two distinct channels".to_string()));
+        }
+        
+        let out_channel = self.graph.get_channel(outgoing, true)
+            .filter(|channel| channel.source == self.local_node)
+            .ok_or_else(|| RoutingError::GraphError(format!("Outgoing channel {} is not a local channel", outgoing.to_hex())))?
+            .clone();
+        let in_channel = self.graph.get_channel(incoming, true)
+            .filter(|channel| channel.destination == self.local_node)
+            .ok_or_else(|| RoutingError::GraphError(format!("Incoming channel {} is not a local channel", incoming.to_hex())))?
+            .clone();
+        
+        if !out_channel.is_active || !in_channel.is_active {
+            return Err(RoutingError::NoRouteFound);
+        }
+        
+        let last_fee = in_channel.base_fee_msat as u64
+            + (amount_msat * in_channel.fee_rate_millionths as u64) / 1_000_000;
+        
+        // Route between the two peers without going back through ourselves
+        let middle = if out_channel.destination == in_channel.source {
+            PaymentPath::new()
+        } else {
+            let mut peer_router = self.clone();
+            peer_router.local_node = out_channel.destination.clone();
+            peer_router.preferences.avoid_nodes.insert(self.local_node.clone());
+            peer_router.preferences.avoid_channels.insert(outgoing.clone());
+            peer_router.preferences.avoid_channels.insert(incoming.clone());
+            peer_router.preferences.max_hops = self.preferences.max_hops.saturating_sub(2);
+            peer_router.find_route(in_channel.source.as_str(), amount_msat + last_fee, &[])?
+        };
+        
+        let total_fee_msat = middle.total_fee_msat + last_fee;
+        let total_cltv_delta = out_channel.cltv_expiry_delta as u32
+            + middle.total_cltv_delta
+            + in_channel.cltv_expiry_delta as u32;
+        
+        if total_cltv_delta > self.preferences.max_cltv_expiry_delta as u32 {
+            return Err(RoutingError::ConstraintError(
+                format!("Total CLTV delta {} exceeds maximum", total_cltv_delta)
+            ));
+        }
+        
+        if out_channel.capacity * 1000 < amount_msat + total_fee_msat {
+            return Err(RoutingError::InsufficientCapacity(
+                format!("Outgoing channel cannot carry {} msat", amount_msat + total_fee_msat)
+            ));
+        }
+        
+        let mut path = PaymentPath::new();
+        path.add_hop(PathHop {
+            node_id: out_channel.destination.clone(),
+            channel_id: out_channel.channel_id.clone(),
+            amount_msat: amount_msat + total_fee_msat,
+            cltv_expiry: out_channel.cltv_expiry_delta as u32,
+            base_fee_msat: 0,
+            fee_rate_millionths: 0,
+            cltv_expiry_delta: out_channel.cltv_expiry_delta,
+        });
+        for mut hop in middle.hops {
+            hop.cltv_expiry += out_channel.cltv_expiry_delta as u32;
+            path.add_hop(hop);
+        }
+        path.add_hop(PathHop {
+            node_id: self.local_node.clone(),
+            channel_id: in_channel.channel_id.clone(),
+            amount_msat,
+            cltv_expiry: total_cltv_delta,
+            base_fee_msat: in_channel.base_fee_msat,
+            fee_rate_millionths: in_channel.fee_rate_millionths,
+            cltv_expiry_delta: in_channel.cltv_expiry_delta,
+        });
+        path.total_fee_msat = total_fee_msat;
+        path.total_cltv_delta = total_cltv_delta;
+        path.total_amount_msat = amount_msat + total_fee_msat;
+        
+        Ok(path)
+    }
+    
     /// Find the shortest path using Dijkstra's algorithm
     fn find_shortest_path(
         &self,
@@ -623,6 +1086,7 @@ impl Router {
         source: &NodeId,
         destination: &NodeId,
         amount_msat: u64,
+        preferences: &RouterPreferences,
         start_time: &Instant,
         timeout: &Duration,
     ) -> Result<PaymentPath, RoutingError> {
@@ -633,6 +1097,7 @@ impl Router {
         #[derive(Debug, Clone, PartialEq, Eq)]
         struct RouteState {
             cost: u64,
+            fee: u64,
             node: NodeId,
             path: Vec<PathHop>,
             total_cltv: u32,
@@ -657,13 +1122,20 @@ impl Router {
         let mut visited: HashSet<NodeId> = HashSet::new();
         
         // Initialize with source node
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+        
         heap.push(RouteState {
             cost: 0,
+            fee: 0,
             node: source.clone(),
             path: Vec::new(),
             total_cltv: 0,
         });
         distances.insert(source.clone(), 0);
+        let environmental = self.node_environmental.read().unwrap();
         
         while let Some(current) = heap.pop() {
             // Check timeout
@@ -685,19 +1157,19 @@ impl Router {
             if current.node == *destination {
                 let mut path = PaymentPath::new();
                 path.hops = current.path;
-                path.total_fee_msat = current.cost;
+                path.total_fee_msat = current.fee;
                 path.total_cltv_delta = current.total_cltv;
-                path.total_amount_msat = amount_msat + current.cost;
+                path.total_amount_msat = amount_msat + current.fee;
                 return Ok(path);
             }
             
             // Check hop limit
-            if current.path.len() >= self.preferences.max_hops as usize {
+            if current.path.len() >= preferences.max_hops as usize {
                 continue;
             }
             
             // Explore neighbors
-            let channels = graph.get_node_channels(&current.node, self.preferences.use_private_channels);
+            let channels = graph.get_node_channels(&current.node, preferences.use_private_channels);
             
             for channel in channels {
                 // Skip if channel is not active
@@ -706,35 +1178,53 @@ impl Router {
                 }
                 
                 // Skip if channel doesn't have enough capacity
-                if channel.capacity * 1000 < amount_msat + current.cost {
+                if channel.capacity * 1000 < amount_msat + current.fee {
                     continue;
                 }
                 
                 // Skip if we should avoid this channel
-                if self.preferences.avoid_channels.contains(&channel.channel_id) {
+                if preferences.avoid_channels.contains(&channel.channel_id) {
                     continue;
                 }
                 
                 // Skip if we should avoid the destination node
-                if self.preferences.avoid_nodes.contains(&channel.destination) {
+                if preferences.avoid_nodes.contains(&channel.destination) {
                     continue;
                 }
                 
                 // Calculate fee for this hop
-                let hop_amount = amount_msat + current.cost;
+                let hop_amount = amount_msat + current.fee;
                 let fee = channel.base_fee_msat as u64 + 
                     (hop_amount * channel.fee_rate_millionths as u64) / 1_000_000;
                 
                 // Check fee rate limits
-                if channel.fee_rate_millionths > self.preferences.max_fee_rate_millionths {
+                if channel.fee_rate_millionths > preferences.max_fee_rate_millionths {
                     continue;
                 }
                 
-                let new_cost = current.cost + fee;
+                // Penalize channels unlikely to have enough liquidity, skip those known not to
+                let penalty = match self.liquidity_scorer.channel_penalty_msat(channel, hop_amount, now) {
+                    Some(penalty) => penalty,
+                    None => continue,
+                };
+                
+                let node_environmental = environmental.get(&channel.destination).map(|(_, data)| data);
+                let hop_cost = preferences.cost_function.hop_cost_msat(&HopCostInputs {
+                    amount_msat: hop_amount,
+                    fee_msat: fee,
+                    liquidity_penalty_msat: penalty,
+                    success_probability: self.liquidity_scorer.success_probability(channel, hop_amount, now),
+                    carbon_intensity: node_environmental.map(|data| data.carbon_intensity),
+                    renewable_percentage: node_environmental.map(|data| data.renewable_percentage),
+                    green_certified: node_environmental.map_or(false, |data| data.green_certified),
+                });
+                
+                let new_fee = current.fee + fee;
+                let new_cost = current.cost.saturating_add(hop_cost);
                 let new_cltv = current.total_cltv + channel.cltv_expiry_delta as u32;
                 
                 // Check CLTV limits
-                if new_cltv > self.preferences.max_cltv_expiry_delta as u32 {
+                if new_cltv > preferences.max_cltv_expiry_delta as u32 {
                     continue;
                 }
                 
@@ -761,6 +1251,7 @@ impl Router {
                     // Add to heap for exploration
                     heap.push(RouteState {
                         cost: new_cost,
+                        fee: new_fee,
                         node: channel.destination.clone(),
                         path: new_path,
                         total_cltv: new_cltv,
@@ -797,6 +1288,15 @@ impl Router {
                     continue;
                 }
                 
+                // Skip channels known not to have enough liquidity
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or(Duration::from_secs(0))
+                    .as_secs();
+                if self.liquidity_scorer.success_probability(channel, amount_msat, now) <= 0.0 {
+                    continue;
+                }
+                
                 // Calculate fee
                 let fee_msat = channel.base_fee_msat as u64 + 
                     (amount_msat * channel.fee_rate_millionths as u64) / 1_000_000;
@@ -845,6 +1345,9 @@ impl Router {
             self.scorer.update_success_probability(&failed_hop.channel_id, false);
         }
         
+        // Learn liquidity bounds from the failure
+        self.payment_path_failed(path, failure_point);
+        
         // Find a new route with modified preferences
         let mut new_prefs = self.preferences.clone();
         
@@ -873,6 +1376,127 @@ impl Router {
         result
     }
     
+    /// Get the liquidity scorer
+    pub fn liquidity_scorer(&self) -> &LiquidityScorer {
+        &self.liquidity_scorer
+    }
+    
+    /// Replace the liquidity scorer (e.g. with one loaded from disk)
+    pub fn set_liquidity_scorer(&mut self, scorer: LiquidityScorer) {
+        self.liquidity_scorer = scorer;
+    }
+    
+    /// Persist the liquidity scorer
+    pub fn save_scorer(&self, path: &std::path::Path) -> Result<(), RoutingError> {
+        self.liquidity_scorer.save(path)
+    }
+    
+    /// Load a previously persisted liquidity scorer
+    pub fn load_scorer(&mut self, path: &std::path::Path) -> Result<(), RoutingError> {
+        self.liquidity_scorer = LiquidityScorer::load(path)?;
+        Ok(())
+    }
+    
+    /// Resolve the directed channels a path's hops were sent over
+    fn path_channels(&self, path: &PaymentPath) -> Vec<Option<ChannelInfo>> {
+        let mut source = self.local_node.clone();
+        
+        path.hops.iter().map(|hop| {
+            let channel = self.graph.get_channel(&hop.channel_id, true).map(|channel| {
+                let mut directed = channel.clone();
+                directed.source = source.clone();
+                directed.destination = hop.node_id.clone();
+                directed
+            });
+            source = hop.node_id.clone();
+            channel
+        }).collect()
+    }
+    
+    /// Learn from a payment that failed at hop `failure_point`
+    ///
+    /// Hops before the failure forwarded the amount; the failing hop could not.
+    pub fn payment_path_failed(&mut self, path: &PaymentPath, failure_point: usize) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+        
+        self.payment_path_failed_at(path, failure_point, now);
+    }
+    
+    /// Learn from a payment that reached its destination
+    pub fn payment_path_succeeded(&mut self, path: &PaymentPath) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+        
+        self.payment_path_succeeded_at(path, now);
+    }
+    
+    fn payment_path_failed_at(&mut self, path: &PaymentPath, failure_point: usize, now: u64) {
+        let channels = self.path_channels(path);
+        
+        for (i, (hop, channel)) in path.hops.iter().zip(channels).enumerate().take(failure_point + 1) {
+            if let Some(channel) = channel {
+                if i < failure_point {
+                    self.liquidity_scorer.record_success(&channel, hop.amount_msat, now);
+                } else {
+                    self.liquidity_scorer.record_failure(&channel, hop.amount_msat, now);
+                }
+            }
+        }
+    }
+    
+    fn payment_path_succeeded_at(&mut self, path: &PaymentPath, now: u64) {
+        let channels = self.path_channels(path);
+        
+        for (hop, channel) in path.hops.iter().zip(channels) {
+            if let Some(channel) = channel {
+                self.liquidity_scorer.record_success(&channel, hop.amount_msat, now);
+            }
+            self.scorer.update_success_probability(&hop.channel_id, true);
+        }
+    }
+    
+    /// Probability that every hop of `path` can forward its amount
+    pub fn path_success_probability(&self, path: &PaymentPath) -> f64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+        
+        path.hops.iter()
+            .zip(self.path_channels(path))
+            .map(|(hop, channel)| match channel {
+                Some(channel) => self.liquidity_scorer.success_probability(&channel, hop.amount_msat, now),
+                None => 1.0,
+            })
+            .product()
+    }
+    
+    /// Combined cost of a path: fees plus liquidity penalties
+    pub fn path_cost_msat(&self, path: &PaymentPath) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+        
+        path.hops.iter()
+            .zip(self.path_channels(path))
+            .fold(path.total_fee_msat, |cost, (hop, channel)| {
+                let penalty = channel
+                    .map(|channel| self.liquidity_scorer.channel_penalty_msat(&channel, hop.amount_msat, now))
+                    .unwrap_or(Some(0));
+                
+                match penalty {
+                    Some(penalty) => cost.saturating_add(penalty),
+                    None => u64::MAX,
+                }
+            })
+    }
+    
     /// Update the network graph with a new channel
     pub fn update_channel(&mut self, channel: ChannelInfo, is_private: bool) {
         self.graph.add_channel(channel, is_private);
@@ -981,6 +1605,11 @@ impl Router {
             adjusted_num_parts,
         )?;
         
+        // Prefer the routes with the lowest fee-plus-liquidity cost
+        let mut routes = routes;
+        routes.sort_by_key(|route| self.path_cost_msat(route));
+        routes.retain(|route| self.path_cost_msat(route) != u64::MAX);
+        
         if routes.is_empty() {
             return Err(RoutingError::NoRouteFound);
         }
@@ -1519,4 +2148,270 @@ impl RouteHop {
         let proportional_fee = (amount_msat * 100) / 1_000_000; // 0.01% proportional fee
         base_fee + proportional_fee
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    
+    fn channel(id: u8, index: u16, source: &str, destination: &str, capacity: u64) -> ChannelInfo {
+        let mut bytes = [0u8; 32];
+        bytes[0] = id;
+        bytes[1..3].copy_from_slice(&index.to_be_bytes());
+        
+        ChannelInfo {
+            channel_id: ChannelId::from_bytes(bytes),
+            source: NodeId::new(source.to_string()),
+            destination: NodeId::new(destination.to_string()),
+            capacity,
+            base_fee_msat: 1_000,
+            fee_rate_millionths: 100,
+            cltv_expiry_delta: 40,
+            is_active: true,
+            last_update: 0,
+        }
+    }
+    
+    #[test]
+    fn test_liquidity_bounds_update_and_decay() {
+        let mut scorer = LiquidityScorer::new(LiquidityScorerParams {
+            liquidity_half_life_secs: 100,
+            ..Default::default()
+        });
+        let chan = channel(1, 0, "a", "b", 1_000);
+        
+        assert_eq!(scorer.success_probability(&chan, 500_000, 0), 0.5);
+        
+        scorer.record_failure(&chan, 400_000, 0);
+        assert_eq!(scorer.success_probability(&chan, 400_000, 0), 0.0);
+        assert!(scorer.channel_penalty_msat(&chan, 400_000, 0).is_none());
+        
+        scorer.record_success(&chan, 100_000, 0);
+        assert_eq!(scorer.success_probability(&chan, 100_000, 0), 1.0);
+        assert_eq!(scorer.success_probability(&chan, 250_000, 0), 0.5);
+        
+        // The other direction is unaffected
+        let reverse = channel(1, 0, "b", "a", 1_000);
+        assert_eq!(scorer.success_probability(&reverse, 500_000, 0), 0.5);
+        
+        // After one half-life the bounds have relaxed halfway
+        assert_eq!(scorer.liquidity_bounds(&chan, 100), (50_000, 700_000));
+        assert!(scorer.success_probability(&chan, 400_000, 100) > 0.0);
+    }
+    
+    #[test]
+    fn test_liquidity_scorer_persistence() {
+        let mut scorer = LiquidityScorer::default();
+        let chan = channel(1, 0, "a", "b", 1_000);
+        scorer.record_failure(&chan, 400_000, 10);
+        
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scorer.bin");
+        scorer.save(&path).unwrap();
+        
+        let loaded = LiquidityScorer::load(&path).unwrap();
+        assert_eq!(loaded.tracked_channels(), 1);
+        assert_eq!(loaded.liquidity_bounds(&chan, 10), scorer.liquidity_bounds(&chan, 10));
+    }
+    
+    /// Synthetic network with hidden per-direction liquidity
+    struct Simulation {
+        router: Router,
+        nodes: Vec<String>,
+        liquidity: HashMap<ChannelId, u64>,
+    }
+    
+    impl Simulation {
+        fn new(seed: u64) -> Self {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut router = Router::new();
+            let nodes: Vec<String> = (0..30).map(|i| format!("node{}", i)).collect();
+            let mut liquidity = HashMap::new();
+            let mut index = 0u16;
+            
+            for (i, node) in nodes.iter().enumerate() {
+                for _ in 0..3 {
+                    let j = rng.gen_range(0..nodes.len());
+                    if i == j {
+                        continue;
+                    }
+                    
+                    let capacity = rng.gen_range(100_000..1_000_000);
+                    for (source, destination, id) in [(node, &nodes[j], 1u8), (&nodes[j], node, 2u8)] {
+                        let mut chan = channel(id, index, source, destination, capacity);
+                        chan.base_fee_msat = rng.gen_range(0..2_000);
+                        chan.fee_rate_millionths = rng.gen_range(1..1_000);
+                        liquidity.insert(chan.channel_id.clone(), rng.gen_range(0..capacity * 1000));
+                        router.update_channel(chan, false);
+                    }
+                    index += 1;
+                }
+            }
+            
+            Self { router, nodes, liquidity }
+        }
+        
+        /// Attempt a payment; returns the index of the failing hop, if any
+        fn attempt(&self, path: &PaymentPath) -> Option<usize> {
+            path.hops.iter().position(|hop| self.liquidity[&hop.channel_id] < hop.amount_msat)
+        }
+        
+        /// Run a workload and return the first-attempt success rate
+        fn run(&mut self, learn: bool, payments: usize, seed: u64) -> f64 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut succeeded = 0;
+            let mut attempted = 0;
+            
+            for _ in 0..payments {
+                let source = &self.nodes[rng.gen_range(0..self.nodes.len())];
+                let destination = &self.nodes[rng.gen_range(0..self.nodes.len())];
+                let amount = rng.gen_range(20_000_000..200_000_000);
+                if source == destination {
+                    continue;
+                }
+                
+                // Payments the router refuses to route count as failures
+                attempted += 1;
+                self.router.set_local_node(NodeId::new(source.clone()));
+                let path = match self.router.find_route(destination, amount, &[]) {
+                    Ok(path) => path,
+                    Err(_) => continue,
+                };
+                
+                match self.attempt(&path) {
+                    None => {
+                        succeeded += 1;
+                        if learn {
+                            self.router.payment_path_succeeded(&path);
+                        }
+                    },
+                    Some(failure_point) => {
+                        if learn {
+                            self.router.payment_path_failed(&path, failure_point);
+                        }
+                    },
+                }
+            }
+            
+            succeeded as f64 / attempted.max(1) as f64
+        }
+    }
+    
+    #[test]
+    fn test_liquidity_scorer_improves_success_rate() {
+        let mut baseline = Simulation::new(7);
+        let mut learning = Simulation::new(7);
+        
+        // Warm up the learning router, then compare on the same workload
+        learning.run(true, 400, 1);
+        let baseline_rate = baseline.run(false, 300, 2);
+        let learning_rate = learning.run(true, 300, 2);
+        
+        assert!(
+            learning_rate > baseline_rate + 0.05,
+            "learning {:.2} vs baseline {:.2}", learning_rate, baseline_rate
+        );
+    }
+    
+    #[test]
+    fn test_split_payment_prefers_likely_routes() {
+        let mut router = Router::new();
+        router.set_local_node(NodeId::new("a".to_string()));
+        
+        // Two parallel two-hop routes a -> x -> d and a -> y -> d
+        let ax = channel(1, 0, "a", "x", 1_000_000);
+        let xd = channel(1, 1, "x", "d", 1_000_000);
+        let ay = channel(1, 2, "a", "y", 1_000_000);
+        let yd = channel(1, 3, "y", "d", 1_000_000);
+        for chan in [&ax, &xd, &ay, &yd] {
+            router.update_channel(chan.clone(), false);
+        }
+        
+        // x -> d is known to be nearly drained
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        router.liquidity_scorer.record_failure(&xd, 10_000_000, now);
+        
+        let route = router.find_route("d", 50_000_000, &[]).unwrap();
+        assert_eq!(route.hops[0].node_id.as_str(), "y");
+        
+        let parts = router.split_payment("d", 100_000_000, &[], 2).unwrap();
+        assert!(parts.iter().all(|(path, _)| path.hops[0].node_id.as_str() == "y"));
+    }
+    
+    #[test]
+    fn test_find_circular_route() {
+        let mut router = Router::new();
+        router.set_local_node(NodeId::new("a".to_string()));
+        
+        // a -> x -> y -> a, plus a cheap shortcut x -> a that must not be used
+        let ax = channel(2, 0, "a", "x", 1_000_000);
+        let xy = channel(2, 1, "x", "y", 1_000_000);
+        let ya = channel(2, 2, "y", "a", 1_000_000);
+        let xa = channel(2, 3, "x", "a", 1_000_000);
+        for chan in [&ax, &xy, &ya, &xa] {
+            router.update_channel(chan.clone(), false);
+        }
+        
+        let route = router.find_circular_route(&ax.channel_id, &ya.channel_id, 100_000_000).unwrap();
+        let nodes: Vec<&str> = route.hops.iter().map(|hop| hop.node_id.as_str()).collect();
+        assert_eq!(nodes, vec!["x", "y", "a"]);
+        assert_eq!(route.hops[0].channel_id, ax.channel_id);
+        assert_eq!(route.hops[2].channel_id, ya.channel_id);
+        
+        // y charges for the last channel, x for the middle one
+        let last_fee = 1_000 + 100_000_000 * 100 / 1_000_000;
+        let middle_fee = 1_000 + (100_000_000 + last_fee) * 100 / 1_000_000;
+        assert_eq!(route.total_fee_msat, last_fee + middle_fee);
+        assert_eq!(route.total_amount_msat, 100_000_000 + last_fee + middle_fee);
+        assert_eq!(route.total_cltv_delta, 120);
+        
+        // Channels that are not ours in the right direction are rejected
+        assert!(router.find_circular_route(&xy.channel_id, &ya.channel_id, 1_000).is_err());
+        assert!(router.find_circular_route(&ax.channel_id, &ax.channel_id, 1_000).is_err());
+    }
+    
+    #[test]
+    fn test_carbon_aware_cost_function() {
+        let mut router = Router::new();
+        router.set_local_node(NodeId::new("a".to_string()));
+        
+        // a -> x -> d is cheaper, a -> y -> d runs on renewable power
+        let mut yd = channel(3, 3, "y", "d", 1_000_000);
+        yd.base_fee_msat = 2_000;
+        for chan in [channel(3, 0, "a", "x", 1_000_000), channel(3, 1, "x", "d", 1_000_000), channel(3, 2, "a", "y", 1_000_000), yd] {
+            router.update_channel(chan, false);
+        }
+        
+        let coal = NodeEnvironmentalData {
+            carbon_intensity: 900.0,
+            renewable_percentage: 0.0,
+            green_certified: false,
+        };
+        let hydro = NodeEnvironmentalData {
+            carbon_intensity: 10.0,
+            renewable_percentage: 100.0,
+            green_certified: true,
+        };
+        assert!(router.update_node_environmental(NodeId::new("x".to_string()), coal.clone(), 10));
+        assert!(router.update_node_environmental(NodeId::new("y".to_string()), hydro.clone(), 10));
+        
+        // Stale announcements are ignored
+        assert!(!router.update_node_environmental(NodeId::new("y".to_string()), coal, 9));
+        assert_eq!(router.node_environmental(&NodeId::new("y".to_string())), Some(hydro));
+        
+        let via = |path: PaymentPath| path.hops[0].node_id.as_str().to_string();
+        assert_eq!(via(router.find_route("d", 10_000_000, &[]).unwrap()), "x");
+        
+        let mut preferences = router.preferences().clone();
+        preferences.cost_function = RouteCostFunction::CarbonAware(GreenRoutingParameters::default());
+        assert_eq!(via(router.find_route_with_preferences("d", 10_000_000, &[], &preferences).unwrap()), "y");
+        
+        // Penalties follow the carbon and renewable weights relative to the fee weight
+        let params = GreenRoutingParameters::default();
+        assert_eq!(params.hop_penalty_msat(10_000_000, Some(0.0), Some(100.0), false), 0);
+        assert_eq!(params.hop_penalty_msat(10_000_000, Some(900.0), Some(0.0), false), 11_100);
+        assert_eq!(params.hop_penalty_msat(10_000_000, Some(10.0), Some(100.0), true), 75);
+        assert_eq!(params.hop_penalty_msat(10_000_000, None, None, false), 7_500);
+    }
+}
@@ -0,0 +1,1176 @@
+//! Submarine Swaps
+//!
+//! This module moves funds between on-chain outputs and Lightning without
+//! trusting the swap counterparty. Both legs are locked to the same
+//! preimage: the Lightning leg by its SHA256 payment hash, the on-chain leg
+//! by a SHA3-512 hashlock with a CLTV or CSV refund path.
+//!
+//! - Loop out (Lightning → on-chain): the client pays the server's hold
+//!   invoice, the server locks funds in an on-chain HTLC, and the client's
+//!   claim reveals the preimage that settles the hold invoice.
+//! - Loop in (on-chain → Lightning): the client locks funds in an on-chain
+//!   HTLC, the server pays the client's invoice, and the preimage revealed by
+//!   settling it lets the server claim on-chain.
+
+use std::collections::HashMap;
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use sha2::{Digest, Sha256};
+use sha3::Sha3_512;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use crate::crypto::signature::{verify_signature, SignatureType};
+use crate::script::interpreter::{
+    lock_time_satisfied, sequence_satisfied, ScriptError, ScriptInterpreter, SignatureChecker, SEQUENCE_LOCKTIME_MASK,
+};
+use crate::script::{Opcode, ScriptBuilder};
+use crate::types::transaction::{OutPoint, Transaction, TransactionInput, TransactionOutput};
+
+use super::invoice::{EnhancedInvoice, HeldHtlc, Invoice, InvoiceDatabase, InvoiceError, HOLD_INVOICE_CANCEL_DELTA};
+use super::channel::ChannelId;
+use super::payment::{PaymentHash, PaymentPreimage};
+
+/// Blocks until the on-chain HTLC of a swap can be refunded
+pub const DEFAULT_SWAP_TIMEOUT_BLOCKS: u32 = 144;
+
+/// Confirmations a loop in HTLC needs before the server pays for it
+pub const DEFAULT_LOOP_IN_CONFIRMATIONS: u32 = 3;
+
+/// Blocks the server keeps between the expiry of its loop in payment and
+/// the on-chain refund, to claim the HTLC once the preimage is revealed
+pub const DEFAULT_LOOP_IN_CLAIM_DELTA: u32 = 24;
+
+/// Sequence used by claim transactions (final, but locktime enabled)
+const CLAIM_SEQUENCE: u32 = 0xFFFF_FFFE;
+
+/// Version of HTLC spends, so OP_CHECKSEQUENCEVERIFY refunds are valid (BIP112)
+const SPEND_TX_VERSION: u32 = 2;
+
+/// Swap errors
+#[derive(Debug, Error)]
+pub enum SwapError {
+    #[error("Swap not found: {0}")]
+    NotFound(String),
+    
+    #[error("Invalid swap state: {0}")]
+    InvalidState(String),
+    
+    #[error("Swap amount {amount} outside server limits {min}..={max}")]
+    AmountOutOfRange { amount: u64, min: u64, max: u64 },
+    
+    #[error("Swap terms rejected: {0}")]
+    TermsRejected(String),
+    
+    #[error("HTLC output not found in funding transaction")]
+    HtlcOutputNotFound,
+    
+    #[error("HTLC has {0} confirmations, {1} required")]
+    InsufficientConfirmations(u32, u32),
+    
+    #[error("Timelock not expired: spendable at {0}")]
+    TimelockNotExpired(u32),
+    
+    #[error("Invalid preimage for swap")]
+    InvalidPreimage,
+    
+    #[error("Script verification failed: {0:?}")]
+    Script(ScriptError),
+    
+    #[error("Key error: {0}")]
+    Key(String),
+    
+    #[error("Invoice error: {0}")]
+    Invoice(#[from] InvoiceError),
+}
+
+/// SHA3-512 hash of a payment preimage, used as the on-chain hashlock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapHash([u8; 64]);
+
+impl SwapHash {
+    /// Hash a preimage
+    pub fn from_preimage(preimage: &PaymentPreimage) -> Self {
+        let mut hasher = Sha3_512::new();
+        hasher.update(preimage.as_bytes());
+        let mut hash = [0u8; 64];
+        hash.copy_from_slice(&hasher.finalize());
+        Self(hash)
+    }
+    
+    /// Raw hash bytes
+    pub fn as_bytes(&self) -> &[u8; 64] {
+        &self.0
+    }
+}
+
+/// Refund timelock of an on-chain swap HTLC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapTimelock {
+    /// Refundable from an absolute block height (OP_CHECKLOCKTIMEVERIFY)
+    Absolute(u32),
+    /// Refundable a number of blocks after confirmation (OP_CHECKSEQUENCEVERIFY)
+    Relative(u32),
+}
+
+impl SwapTimelock {
+    /// Height from which the refund path is spendable
+    pub fn refund_height(&self, confirmation_height: u32) -> u32 {
+        match self {
+            SwapTimelock::Absolute(height) => *height,
+            SwapTimelock::Relative(blocks) => confirmation_height + blocks,
+        }
+    }
+}
+
+/// On-chain HTLC script of a swap
+///
+/// ```text
+/// OP_IF
+///     OP_DUP OP_SHA256 <payment_hash> OP_EQUALVERIFY
+///     OP_SHA3_512 <swap_hash> OP_EQUALVERIFY
+///     OP_DUP OP_HASH160 <claim_pubkey_hash>
+/// OP_ELSE
+///     <timeout> OP_CHECKLOCKTIMEVERIFY|OP_CHECKSEQUENCEVERIFY OP_DROP
+///     OP_DUP OP_HASH160 <refund_pubkey_hash>
+/// OP_ENDIF
+/// OP_EQUALVERIFY OP_CHECKSIG
+/// ```
+///
+/// The claim path also checks the payment hash, so an on-chain claim always
+/// reveals a preimage that settles the Lightning leg.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapScript {
+    pub payment_hash: PaymentHash,
+    pub swap_hash: SwapHash,
+    pub claim_pubkey_hash: Vec<u8>,
+    pub refund_pubkey_hash: Vec<u8>,
+    pub timelock: SwapTimelock,
+}
+
+impl SwapScript {
+    /// Create a swap script from the preimage hashes and the two public keys
+    pub fn new(
+        payment_hash: PaymentHash,
+        swap_hash: SwapHash,
+        claim_pubkey: &[u8],
+        refund_pubkey: &[u8],
+        timelock: SwapTimelock,
+    ) -> Self {
+        Self {
+            payment_hash,
+            swap_hash,
+            claim_pubkey_hash: ScriptBuilder::hash_pubkey(claim_pubkey),
+            refund_pubkey_hash: ScriptBuilder::hash_pubkey(refund_pubkey),
+            timelock,
+        }
+    }
+    
+    /// Build the redeem script
+    pub fn redeem_script(&self) -> Vec<u8> {
+        let (timeout, timelock_opcode) = match self.timelock {
+            SwapTimelock::Absolute(height) => (height, Opcode::OP_CHECKLOCKTIMEVERIFY),
+            SwapTimelock::Relative(blocks) => (blocks, Opcode::OP_CHECKSEQUENCEVERIFY),
+        };
+        
+        ScriptBuilder::new()
+            .push_opcode(Opcode::OP_IF)
+            .push_opcode(Opcode::OP_DUP)
+            .push_opcode(Opcode::OP_SHA256)
+            .push_data(self.payment_hash.as_bytes())
+            .push_opcode(Opcode::OP_EQUALVERIFY)
+            .push_opcode(Opcode::OP_SHA3_512)
+            .push_data(self.swap_hash.as_bytes())
+            .push_opcode(Opcode::OP_EQUALVERIFY)
+            .push_opcode(Opcode::OP_DUP)
+            .push_opcode(Opcode::OP_HASH160)
+            .push_data(&self.claim_pubkey_hash)
+            .push_opcode(Opcode::OP_ELSE)
+            .push_number(timeout as i64)
+            .push_opcode(timelock_opcode)
+            .push_opcode(Opcode::OP_DROP)
+            .push_opcode(Opcode::OP_DUP)
+            .push_opcode(Opcode::OP_HASH160)
+            .push_data(&self.refund_pubkey_hash)
+            .push_opcode(Opcode::OP_ENDIF)
+            .push_opcode(Opcode::OP_EQUALVERIFY)
+            .push_opcode(Opcode::OP_CHECKSIG)
+            .build()
+    }
+    
+    /// P2SH output script paying to this HTLC
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        ScriptBuilder::pay_to_script_hash(&ScriptBuilder::hash_pubkey(&self.redeem_script()))
+    }
+    
+    /// Output funding this HTLC
+    pub fn funding_output(&self, amount: u64) -> TransactionOutput {
+        TransactionOutput::new(amount, self.script_pubkey())
+    }
+    
+    /// Find the HTLC output in a funding transaction
+    pub fn find_output(&self, funding_tx: &Transaction) -> Result<(OutPoint, u64), SwapError> {
+        let script_pubkey = self.script_pubkey();
+        funding_tx.outputs().iter()
+            .position(|output| output.script_pubkey() == script_pubkey.as_slice())
+            .map(|vout| (
+                OutPoint { txid: funding_tx.hash(), vout: vout as u32 },
+                funding_tx.outputs()[vout].amount(),
+            ))
+            .ok_or(SwapError::HtlcOutputNotFound)
+    }
+    
+    /// Build a transaction claiming the HTLC with the preimage
+    pub fn claim_transaction(
+        &self,
+        htlc: &OutPoint,
+        amount: u64,
+        fee: u64,
+        destination: Vec<u8>,
+        preimage: &PaymentPreimage,
+        claim_key: &SecretKey,
+    ) -> Result<Transaction, SwapError> {
+        if SwapHash::from_preimage(preimage) != self.swap_hash || preimage.payment_hash() != self.payment_hash {
+            return Err(SwapError::InvalidPreimage);
+        }
+        
+        let params = SpendParams {
+            htlc,
+            amount,
+            fee,
+            destination,
+            sequence: CLAIM_SEQUENCE,
+            lock_time: 0,
+            key: claim_key,
+        };
+        self.spend(params, |builder| builder.push_data(preimage.as_bytes()).push_number(1))
+    }
+    
+    /// Build a transaction refunding the HTLC after the timelock
+    pub fn refund_transaction(
+        &self,
+        htlc: &OutPoint,
+        amount: u64,
+        fee: u64,
+        destination: Vec<u8>,
+        refund_key: &SecretKey,
+    ) -> Result<Transaction, SwapError> {
+        let (sequence, lock_time) = match self.timelock {
+            SwapTimelock::Absolute(height) => (CLAIM_SEQUENCE, height),
+            SwapTimelock::Relative(blocks) => (blocks, 0),
+        };
+        
+        let params = SpendParams {
+            htlc,
+            amount,
+            fee,
+            destination,
+            sequence,
+            lock_time,
+            key: refund_key,
+        };
+        self.spend(params, |builder| builder.push_number(0))
+    }
+    
+    /// Verify that input `input_index` of `tx` spends this HTLC
+    ///
+    /// `confirmations` is the depth of the HTLC output, checked against
+    /// relative timelocks.
+    pub fn verify_spend(&self, tx: &Transaction, input_index: usize, confirmations: u32) -> Result<(), SwapError> {
+        let input = tx.inputs().get(input_index)
+            .ok_or_else(|| SwapError::InvalidState(format!("No input {}", input_index)))?;
+        
+        // The script signature must end with a push of our redeem script
+        let redeem_script = self.redeem_script();
+        let redeem_push = ScriptBuilder::new().push_data(&redeem_script).build();
+        let script_sig = input.signature_script();
+        if !script_sig.ends_with(&redeem_push) {
+            return Err(SwapError::Script(ScriptError::EqualVerifyFailed));
+        }
+        
+        let checker = SwapSpendChecker {
+            sighash: spend_sighash(tx),
+            version: tx.version(),
+            lock_time: tx.lock_time(),
+            sequence: input.sequence(),
+            confirmations,
+        };
+        
+        let mut interpreter = ScriptInterpreter::new();
+        interpreter.execute(&script_sig[..script_sig.len() - redeem_push.len()], &checker)
+            .map_err(SwapError::Script)?;
+        match interpreter.execute(&redeem_script, &checker) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(SwapError::Script(ScriptError::SignatureFailed)),
+            Err(e) => Err(SwapError::Script(e)),
+        }
+    }
+    
+    /// Extract the preimage from a claim transaction spending this HTLC
+    pub fn extract_preimage(&self, claim_tx: &Transaction) -> Option<PaymentPreimage> {
+        claim_tx.inputs().iter()
+            .filter_map(|input| parse_pushes(input.signature_script()))
+            .filter_map(|items| items.get(2).cloned())
+            .filter_map(|item| <[u8; 32]>::try_from(item.as_slice()).ok())
+            .map(PaymentPreimage::new)
+            .find(|preimage| SwapHash::from_preimage(preimage) == self.swap_hash)
+    }
+    
+    fn spend(
+        &self,
+        params: SpendParams<'_>,
+        selector: impl FnOnce(ScriptBuilder) -> ScriptBuilder,
+    ) -> Result<Transaction, SwapError> {
+        let SpendParams { htlc, amount, fee, destination, sequence, lock_time, key } = params;
+        let value = amount.checked_sub(fee)
+            .ok_or_else(|| SwapError::InvalidState(format!("Fee {} exceeds HTLC amount {}", fee, amount)))?;
+        
+        let unsigned = Transaction::new(
+            SPEND_TX_VERSION,
+            vec![TransactionInput::new(htlc.txid, htlc.vout, Vec::new(), sequence)],
+            vec![TransactionOutput::new(value, destination.clone())],
+            lock_time,
+        );
+        
+        let secp = Secp256k1::new();
+        let message = Message::from_slice(&spend_sighash(&unsigned))
+            .map_err(|e| SwapError::Key(e.to_string()))?;
+        let signature = secp.sign_ecdsa(&message, key).serialize_compact();
+        let pubkey = PublicKey::from_secret_key(&secp, key).serialize();
+        
+        let script_sig = selector(ScriptBuilder::new().push_data(&signature).push_data(&pubkey))
+            .push_data(&self.redeem_script())
+            .build();
+        
+        Ok(Transaction::new(
+            SPEND_TX_VERSION,
+            vec![TransactionInput::new(htlc.txid, htlc.vout, script_sig, sequence)],
+            vec![TransactionOutput::new(value, destination)],
+            lock_time,
+        ))
+    }
+}
+
+/// Inputs of a single-input HTLC spend
+struct SpendParams<'a> {
+    htlc: &'a OutPoint,
+    amount: u64,
+    fee: u64,
+    destination: Vec<u8>,
+    sequence: u32,
+    lock_time: u32,
+    key: &'a SecretKey,
+}
+
+/// Digest signed by HTLC spends: the transaction with empty script signatures
+fn spend_sighash(tx: &Transaction) -> [u8; 32] {
+    let inputs = tx.inputs().iter()
+        .map(|input| TransactionInput::new(input.prev_tx_hash(), input.prev_output_index(), Vec::new(), input.sequence()))
+        .collect();
+    Transaction::new(tx.version(), inputs, tx.outputs().to_vec(), tx.lock_time()).hash()
+}
+
+/// Split a push-only script into its data items
+fn parse_pushes(script: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut items = Vec::new();
+    let mut pc = 0;
+    
+    while pc < script.len() {
+        let opcode = script[pc];
+        pc += 1;
+        
+        let len = match opcode {
+            0x00 => 0,
+            0x01..=0x4b => opcode as usize,
+            0x4c => {
+                let len = *script.get(pc)? as usize;
+                pc += 1;
+                len
+            }
+            0x4d => {
+                let len = u16::from_le_bytes([*script.get(pc)?, *script.get(pc + 1)?]) as usize;
+                pc += 2;
+                len
+            }
+            0x51..=0x60 => {
+                items.push(vec![opcode - 0x50]);
+                continue;
+            }
+            _ => return None,
+        };
+        
+        items.push(script.get(pc..pc + len)?.to_vec());
+        pc += len;
+    }
+    
+    Some(items)
+}
+
+/// Signature checker for HTLC spends
+struct SwapSpendChecker {
+    sighash: [u8; 32],
+    version: u32,
+    lock_time: u32,
+    sequence: u32,
+    confirmations: u32,
+}
+
+impl SignatureChecker for SwapSpendChecker {
+    fn check_signature(&self, signature: &[u8], pubkey: &[u8]) -> Result<bool, ScriptError> {
+        Ok(verify_signature(SignatureType::Secp256k1, pubkey, &self.sighash, signature).unwrap_or(false))
+    }
+    
+    fn check_lock_time(&self, lock_time: u32) -> bool {
+        lock_time_satisfied(lock_time, self.lock_time, self.sequence)
+    }
+    
+    fn check_sequence(&self, sequence: u32) -> bool {
+        sequence_satisfied(sequence, self.version, self.sequence)
+            && sequence & SEQUENCE_LOCKTIME_MASK <= self.confirmations
+    }
+}
+
+/// Limits and pricing published by a swap server
+#[derive(Debug, Clone)]
+pub struct SwapTerms {
+    /// Smallest swap in novas
+    pub min_amount: u64,
+    /// Largest swap in novas
+    pub max_amount: u64,
+    /// Server fee in parts per million of the swap amount
+    pub fee_ppm: u64,
+    /// Blocks until the on-chain HTLC can be refunded
+    pub timeout_blocks: u32,
+    /// Confirmations a loop in HTLC needs before the server pays
+    pub min_confirmations: u32,
+    /// Blocks between the expiry of a loop in payment and the refund
+    pub claim_delta: u32,
+}
+
+impl Default for SwapTerms {
+    fn default() -> Self {
+        Self {
+            min_amount: 10_000,
+            max_amount: 10_000_000,
+            fee_ppm: 1_000,
+            timeout_blocks: DEFAULT_SWAP_TIMEOUT_BLOCKS,
+            min_confirmations: DEFAULT_LOOP_IN_CONFIRMATIONS,
+            claim_delta: DEFAULT_LOOP_IN_CLAIM_DELTA,
+        }
+    }
+}
+
+impl SwapTerms {
+    /// Server fee for a swap amount
+    pub fn fee(&self, amount: u64) -> u64 {
+        amount * self.fee_ppm / 1_000_000
+    }
+    
+    fn check_amount(&self, amount: u64) -> Result<(), SwapError> {
+        if amount < self.min_amount || amount > self.max_amount {
+            return Err(SwapError::AmountOutOfRange { amount, min: self.min_amount, max: self.max_amount });
+        }
+        Ok(())
+    }
+}
+
+/// Loop out request sent by the client
+#[derive(Debug, Clone)]
+pub struct LoopOutRequest {
+    pub payment_hash: PaymentHash,
+    pub swap_hash: SwapHash,
+    pub claim_pubkey: Vec<u8>,
+    pub amount: u64,
+}
+
+/// Server's answer to a loop out request
+#[derive(Debug, Clone)]
+pub struct LoopOutContract {
+    /// Hold invoice for amount plus fee
+    pub invoice: Invoice,
+    /// Public key of the server's refund path
+    pub refund_pubkey: Vec<u8>,
+    /// On-chain HTLC the server will fund
+    pub script: SwapScript,
+    pub fee: u64,
+}
+
+/// Loop in request sent by the client
+#[derive(Debug, Clone)]
+pub struct LoopInRequest {
+    pub payment_hash: PaymentHash,
+    pub swap_hash: SwapHash,
+    pub refund_pubkey: Vec<u8>,
+    pub amount: u64,
+}
+
+/// Server's answer to a loop in request
+#[derive(Debug, Clone)]
+pub struct LoopInContract {
+    /// Public key of the server's claim path
+    pub claim_pubkey: Vec<u8>,
+    /// On-chain HTLC the client has to fund
+    pub script: SwapScript,
+    pub fee: u64,
+}
+
+/// Interface of a swap server
+///
+/// Lightning payments between client and server are represented by the
+/// calls that carry them: `pay_loop_out_invoice` stands for the client's
+/// HTLC reaching the server, and `settle_loop_in` for the client settling
+/// the server's payment.
+pub trait SwapServer {
+    /// Current limits and pricing
+    fn terms(&self) -> SwapTerms;
+    
+    /// Start a loop out
+    fn new_loop_out(&mut self, request: LoopOutRequest, current_height: u32) -> Result<LoopOutContract, SwapError>;
+    
+    /// The client's payment for a loop out arrived; returns the HTLC
+    /// funding transaction once the invoice is fully paid
+    fn pay_loop_out_invoice(
+        &mut self,
+        payment_hash: &PaymentHash,
+        amount_msat: u64,
+        cltv_expiry: u32,
+        current_height: u32,
+    ) -> Result<Option<Transaction>, SwapError>;
+    
+    /// The client claimed a loop out HTLC on-chain
+    fn loop_out_claimed(&mut self, payment_hash: &PaymentHash, claim_tx: &Transaction) -> Result<(), SwapError>;
+    
+    /// Start a loop in
+    fn new_loop_in(&mut self, request: LoopInRequest, current_height: u32) -> Result<LoopInContract, SwapError>;
+    
+    /// The client funded a loop in HTLC; returns the amount paid to the
+    /// client's invoice in millinovas
+    ///
+    /// The HTLC must be buried `min_confirmations` deep at `current_height`,
+    /// and the payment, expiring at `invoice_cltv_expiry`, must leave
+    /// `claim_delta` blocks to claim on-chain before the client can refund.
+    fn loop_in_funded(
+        &mut self,
+        payment_hash: &PaymentHash,
+        funding_tx: &Transaction,
+        confirmation_height: u32,
+        current_height: u32,
+        invoice_cltv_expiry: u32,
+    ) -> Result<u64, SwapError>;
+    
+    /// The client settled the loop in payment; returns the server's claim
+    fn settle_loop_in(&mut self, preimage: &PaymentPreimage) -> Result<Transaction, SwapError>;
+}
+
+/// State of a swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapState {
+    /// Terms agreed, waiting for the first leg
+    Initiated,
+    /// On-chain HTLC funded
+    HtlcPublished,
+    /// Preimage revealed and both legs settled
+    Succeeded,
+    /// On-chain HTLC refunded
+    Refunded,
+}
+
+struct ServerSwap {
+    script: SwapScript,
+    amount: u64,
+    fee: u64,
+    htlc: Option<(OutPoint, u64)>,
+    state: SwapState,
+}
+
+/// In-process swap server for tests and local development
+pub struct InProcessSwapServer {
+    terms: SwapTerms,
+    key: SecretKey,
+    /// Hold invoices for loop outs
+    invoices: InvoiceDatabase,
+    loop_outs: HashMap<PaymentHash, ServerSwap>,
+    loop_ins: HashMap<PaymentHash, ServerSwap>,
+    /// On-chain funds available for loop outs
+    balance: u64,
+    /// Destination of claims and refunds
+    sweep_script: Vec<u8>,
+    funding_nonce: u64,
+}
+
+impl InProcessSwapServer {
+    /// Create a server with on-chain funds for loop outs
+    pub fn new(terms: SwapTerms, key: SecretKey, balance: u64) -> Self {
+        let pubkey = PublicKey::from_secret_key(&Secp256k1::new(), &key).serialize();
+        Self {
+            terms,
+            key,
+            invoices: InvoiceDatabase::new(),
+            loop_outs: HashMap::new(),
+            loop_ins: HashMap::new(),
+            balance,
+            sweep_script: ScriptBuilder::pay_to_pubkey_hash(&ScriptBuilder::hash_pubkey(&pubkey)),
+            funding_nonce: 0,
+        }
+    }
+    
+    /// Public key of the server
+    pub fn pubkey(&self) -> Vec<u8> {
+        PublicKey::from_secret_key(&Secp256k1::new(), &self.key).serialize().to_vec()
+    }
+    
+    /// On-chain funds left for loop outs
+    pub fn balance(&self) -> u64 {
+        self.balance
+    }
+    
+    /// State of a swap
+    pub fn swap_state(&self, payment_hash: &PaymentHash) -> Option<SwapState> {
+        self.loop_outs.get(payment_hash)
+            .or_else(|| self.loop_ins.get(payment_hash))
+            .map(|swap| swap.state)
+    }
+    
+    /// Refund loop out HTLCs the client never claimed
+    ///
+    /// Cancels the hold invoice, failing the client's payment back, and
+    /// returns the refund transactions to broadcast.
+    pub fn refund_expired_loop_outs(&mut self, current_height: u32) -> Vec<Transaction> {
+        let mut refunds = Vec::new();
+        
+        for (payment_hash, swap) in self.loop_outs.iter_mut() {
+            let (htlc, amount) = match (&swap.htlc, swap.state) {
+                (Some(htlc), SwapState::HtlcPublished) => htlc.clone(),
+                _ => continue,
+            };
+            if current_height < swap.script.timelock.refund_height(0) {
+                continue;
+            }
+            
+            match swap.script.refund_transaction(&htlc, amount, 0, self.sweep_script.clone(), &self.key) {
+                Ok(tx) => {
+                    warn!("Refunding unclaimed loop out {} at height {}", payment_hash, current_height);
+                    let _ = self.invoices.cancel_hold_invoice(payment_hash);
+                    self.balance += amount;
+                    swap.state = SwapState::Refunded;
+                    refunds.push(tx);
+                }
+                Err(e) => warn!("Failed to refund loop out {}: {}", payment_hash, e),
+            }
+        }
+        
+        refunds
+    }
+    
+    fn next_funding_input(&mut self) -> TransactionInput {
+        self.funding_nonce += 1;
+        let mut hasher = Sha256::new();
+        hasher.update(self.pubkey());
+        hasher.update(self.funding_nonce.to_le_bytes());
+        TransactionInput::new(hasher.finalize().into(), 0, Vec::new(), CLAIM_SEQUENCE)
+    }
+}
+
+impl SwapServer for InProcessSwapServer {
+    fn terms(&self) -> SwapTerms {
+        self.terms.clone()
+    }
+    
+    fn new_loop_out(&mut self, request: LoopOutRequest, current_height: u32) -> Result<LoopOutContract, SwapError> {
+        self.terms.check_amount(request.amount)?;
+        if self.loop_outs.contains_key(&request.payment_hash) {
+            return Err(SwapError::InvalidState(format!("Swap {} already exists", request.payment_hash)));
+        }
+        
+        let fee = self.terms.fee(request.amount);
+        let refund_pubkey = self.pubkey();
+        let script = SwapScript::new(
+            request.payment_hash,
+            request.swap_hash,
+            &request.claim_pubkey,
+            &refund_pubkey,
+            SwapTimelock::Absolute(current_height + self.terms.timeout_blocks),
+        );
+        
+        let invoice = EnhancedInvoice::new_hold(
+            request.payment_hash,
+            (request.amount + fee) * 1000,
+            format!("Loop out {} novas", request.amount),
+            3600,
+            0,
+        )?;
+        let base_invoice = invoice.base_invoice().clone();
+        self.invoices.add_invoice(invoice)?;
+        
+        info!("New loop out {} for {} novas", request.payment_hash, request.amount);
+        self.loop_outs.insert(request.payment_hash, ServerSwap {
+            script: script.clone(),
+            amount: request.amount,
+            fee,
+            htlc: None,
+            state: SwapState::Initiated,
+        });
+        
+        Ok(LoopOutContract { invoice: base_invoice, refund_pubkey, script, fee })
+    }
+    
+    fn pay_loop_out_invoice(
+        &mut self,
+        payment_hash: &PaymentHash,
+        amount_msat: u64,
+        cltv_expiry: u32,
+        current_height: u32,
+    ) -> Result<Option<Transaction>, SwapError> {
+        let swap = self.loop_outs.get(payment_hash)
+            .ok_or_else(|| SwapError::NotFound(payment_hash.to_hex()))?;
+        
+        // The held payment must outlive the on-chain refund
+        let refund_height = swap.script.timelock.refund_height(current_height);
+        if cltv_expiry <= refund_height + HOLD_INVOICE_CANCEL_DELTA {
+            return Err(SwapError::TermsRejected(format!(
+                "HTLC expiry {} does not outlive the refund at {}", cltv_expiry, refund_height
+            )));
+        }
+        
+        let htlc = HeldHtlc {
+            channel_id: ChannelId::from_bytes(*payment_hash.as_bytes()),
+            htlc_id: self.invoices.get_invoice(payment_hash).map_or(0, |invoice| invoice.held_htlcs().len() as u64),
+            amount_msat,
+            cltv_expiry,
+            accepted_at: 0,
+        };
+        let state = self.invoices.accept_hold_htlc(payment_hash, htlc, current_height)?;
+        if state != super::invoice::InvoiceState::Accepted || swap.state != SwapState::Initiated {
+            return Ok(None);
+        }
+        
+        if self.balance < swap.amount {
+            warn!("Loop out {} lacks on-chain funds, failing payment back", payment_hash);
+            self.invoices.cancel_hold_invoice(payment_hash)?;
+            return Err(SwapError::InvalidState("Server out of on-chain funds".to_string()));
+        }
+        
+        let input = self.next_funding_input();
+        let swap = self.loop_outs.get_mut(payment_hash).expect("swap checked above");
+        let funding_tx = Transaction::new(1, vec![input], vec![swap.script.funding_output(swap.amount)], 0);
+        
+        self.balance -= swap.amount;
+        swap.htlc = Some(swap.script.find_output(&funding_tx)?);
+        swap.state = SwapState::HtlcPublished;
+        debug!("Published loop out HTLC {} for {}", hex::encode(funding_tx.hash()), payment_hash);
+        
+        Ok(Some(funding_tx))
+    }
+    
+    fn loop_out_claimed(&mut self, payment_hash: &PaymentHash, claim_tx: &Transaction) -> Result<(), SwapError> {
+        let swap = self.loop_outs.get_mut(payment_hash)
+            .ok_or_else(|| SwapError::NotFound(payment_hash.to_hex()))?;
+        
+        let preimage = swap.script.extract_preimage(claim_tx)
+            .ok_or(SwapError::InvalidPreimage)?;
+        
+        self.invoices.settle_hold_invoice(&preimage)?;
+        swap.state = SwapState::Succeeded;
+        info!("Loop out {} succeeded, earned {} novas", payment_hash, swap.fee);
+        
+        Ok(())
+    }
+    
+    fn new_loop_in(&mut self, request: LoopInRequest, _current_height: u32) -> Result<LoopInContract, SwapError> {
+        self.terms.check_amount(request.amount)?;
+        if self.loop_ins.contains_key(&request.payment_hash) {
+            return Err(SwapError::InvalidState(format!("Swap {} already exists", request.payment_hash)));
+        }
+        
+        let fee = self.terms.fee(request.amount);
+        let claim_pubkey = self.pubkey();
+        let script = SwapScript::new(
+            request.payment_hash,
+            request.swap_hash,
+            &claim_pubkey,
+            &request.refund_pubkey,
+            SwapTimelock::Relative(self.terms.timeout_blocks),
+        );
+        
+        info!("New loop in {} for {} novas", request.payment_hash, request.amount);
+        self.loop_ins.insert(request.payment_hash, ServerSwap {
+            script: script.clone(),
+            amount: request.amount,
+            fee,
+            htlc: None,
+            state: SwapState::Initiated,
+        });
+        
+        Ok(LoopInContract { claim_pubkey, script, fee })
+    }
+    
+    fn loop_in_funded(
+        &mut self,
+        payment_hash: &PaymentHash,
+        funding_tx: &Transaction,
+        confirmation_height: u32,
+        current_height: u32,
+        invoice_cltv_expiry: u32,
+    ) -> Result<u64, SwapError> {
+        let swap = self.loop_ins.get_mut(payment_hash)
+            .ok_or_else(|| SwapError::NotFound(payment_hash.to_hex()))?;
+        if swap.state != SwapState::Initiated {
+            return Err(SwapError::InvalidState(format!("Loop in {} is {:?}", payment_hash, swap.state)));
+        }
+        
+        let (outpoint, amount) = swap.script.find_output(funding_tx)?;
+        if amount < swap.amount {
+            return Err(SwapError::TermsRejected(format!("HTLC holds {} novas, expected {}", amount, swap.amount)));
+        }
+        
+        // A shallow HTLC could be reorganized away after we paid
+        let confirmations = (current_height + 1).saturating_sub(confirmation_height);
+        if confirmations < self.terms.min_confirmations {
+            return Err(SwapError::InsufficientConfirmations(confirmations, self.terms.min_confirmations));
+        }
+        
+        // Once the client settles, we must claim before the refund path opens
+        let refund_height = swap.script.timelock.refund_height(confirmation_height);
+        if invoice_cltv_expiry + self.terms.claim_delta >= refund_height {
+            return Err(SwapError::TermsRejected(format!(
+                "Invoice expiry {} leaves no time to claim before the refund at {}", invoice_cltv_expiry, refund_height
+            )));
+        }
+        
+        swap.htlc = Some((outpoint, amount));
+        swap.state = SwapState::HtlcPublished;
+        
+        // Pay the client's invoice, keeping the fee
+        Ok((swap.amount - swap.fee) * 1000)
+    }
+    
+    fn settle_loop_in(&mut self, preimage: &PaymentPreimage) -> Result<Transaction, SwapError> {
+        let payment_hash = preimage.payment_hash();
+        let swap = self.loop_ins.get_mut(&payment_hash)
+            .ok_or_else(|| SwapError::NotFound(payment_hash.to_hex()))?;
+        let (htlc, amount) = swap.htlc.clone()
+            .ok_or_else(|| SwapError::InvalidState(format!("Loop in {} is not funded", payment_hash)))?;
+        
+        let claim_tx = swap.script.claim_transaction(&htlc, amount, 0, self.sweep_script.clone(), preimage, &self.key)?;
+        swap.state = SwapState::Succeeded;
+        info!("Loop in {} succeeded, claimed {} novas", payment_hash, amount);
+        
+        Ok(claim_tx)
+    }
+}
+
+/// A swap tracked by the client
+#[derive(Debug, Clone)]
+pub struct ClientSwap {
+    pub preimage: PaymentPreimage,
+    pub script: SwapScript,
+    pub amount: u64,
+    pub fee: u64,
+    pub state: SwapState,
+}
+
+/// Client side of submarine swaps
+pub struct SwapClient {
+    key: SecretKey,
+    /// Largest server fee accepted, in parts per million
+    max_fee_ppm: u64,
+    swaps: HashMap<PaymentHash, ClientSwap>,
+}
+
+impl SwapClient {
+    /// Create a swap client
+    pub fn new(key: SecretKey, max_fee_ppm: u64) -> Self {
+        Self {
+            key,
+            max_fee_ppm,
+            swaps: HashMap::new(),
+        }
+    }
+    
+    /// Public key of the client
+    pub fn pubkey(&self) -> Vec<u8> {
+        PublicKey::from_secret_key(&Secp256k1::new(), &self.key).serialize().to_vec()
+    }
+    
+    /// Look up a swap
+    pub fn swap(&self, payment_hash: &PaymentHash) -> Option<&ClientSwap> {
+        self.swaps.get(payment_hash)
+    }
+    
+    /// Start a loop out, returning the checked hold invoice to pay
+    pub fn start_loop_out(
+        &mut self,
+        server: &mut dyn SwapServer,
+        amount: u64,
+        current_height: u32,
+    ) -> Result<LoopOutContract, SwapError> {
+        let preimage = PaymentPreimage::new_random();
+        let payment_hash = preimage.payment_hash();
+        let swap_hash = SwapHash::from_preimage(&preimage);
+        
+        let contract = server.new_loop_out(LoopOutRequest {
+            payment_hash,
+            swap_hash,
+            claim_pubkey: self.pubkey(),
+            amount,
+        }, current_height)?;
+        
+        self.check_fee(amount, contract.fee)?;
+        let expected = SwapScript::new(payment_hash, swap_hash, &self.pubkey(), &contract.refund_pubkey, contract.script.timelock);
+        if contract.script != expected || !matches!(contract.script.timelock, SwapTimelock::Absolute(_)) {
+            return Err(SwapError::TermsRejected("Unexpected HTLC script".to_string()));
+        }
+        if contract.invoice.payment_hash() != payment_hash || contract.invoice.amount_msat() != (amount + contract.fee) * 1000 {
+            return Err(SwapError::TermsRejected("Invoice does not match the swap".to_string()));
+        }
+        
+        self.swaps.insert(payment_hash, ClientSwap {
+            preimage,
+            script: contract.script.clone(),
+            amount,
+            fee: contract.fee,
+            state: SwapState::Initiated,
+        });
+        
+        Ok(contract)
+    }
+    
+    /// Claim a loop out HTLC once the server published it
+    pub fn claim_loop_out(
+        &mut self,
+        payment_hash: &PaymentHash,
+        funding_tx: &Transaction,
+        fee: u64,
+        destination: Vec<u8>,
+    ) -> Result<Transaction, SwapError> {
+        let swap = self.swaps.get_mut(payment_hash)
+            .ok_or_else(|| SwapError::NotFound(payment_hash.to_hex()))?;
+        
+        let (htlc, amount) = swap.script.find_output(funding_tx)?;
+        if amount < swap.amount {
+            return Err(SwapError::TermsRejected(format!("HTLC holds {} novas, expected {}", amount, swap.amount)));
+        }
+        
+        let claim_tx = swap.script.claim_transaction(&htlc, amount, fee, destination, &swap.preimage, &self.key)?;
+        swap.state = SwapState::Succeeded;
+        
+        Ok(claim_tx)
+    }
+    
+    /// Start a loop in, returning the HTLC to fund
+    pub fn start_loop_in(
+        &mut self,
+        server: &mut dyn SwapServer,
+        amount: u64,
+        current_height: u32,
+    ) -> Result<LoopInContract, SwapError> {
+        let preimage = PaymentPreimage::new_random();
+        let payment_hash = preimage.payment_hash();
+        let swap_hash = SwapHash::from_preimage(&preimage);
+        
+        let contract = server.new_loop_in(LoopInRequest {
+            payment_hash,
+            swap_hash,
+            refund_pubkey: self.pubkey(),
+            amount,
+        }, current_height)?;
+        
+        self.check_fee(amount, contract.fee)?;
+        let expected = SwapScript::new(payment_hash, swap_hash, &contract.claim_pubkey, &self.pubkey(), contract.script.timelock);
+        if contract.script != expected {
+            return Err(SwapError::TermsRejected("Unexpected HTLC script".to_string()));
+        }
+        
+        self.swaps.insert(payment_hash, ClientSwap {
+            preimage,
+            script: contract.script.clone(),
+            amount,
+            fee: contract.fee,
+            state: SwapState::Initiated,
+        });
+        
+        Ok(contract)
+    }
+    
+    /// Record that the loop in HTLC is funded
+    pub fn loop_in_funded(&mut self, payment_hash: &PaymentHash) -> Result<(), SwapError> {
+        let swap = self.swaps.get_mut(payment_hash)
+            .ok_or_else(|| SwapError::NotFound(payment_hash.to_hex()))?;
+        swap.state = SwapState::HtlcPublished;
+        Ok(())
+    }
+    
+    /// Accept the server's Lightning payment, returning the preimage to settle it
+    pub fn loop_in_payment_received(&mut self, payment_hash: &PaymentHash, amount_msat: u64) -> Result<PaymentPreimage, SwapError> {
+        let swap = self.swaps.get_mut(payment_hash)
+            .ok_or_else(|| SwapError::NotFound(payment_hash.to_hex()))?;
+        
+        let expected_msat = (swap.amount - swap.fee) * 1000;
+        if amount_msat < expected_msat {
+            return Err(SwapError::TermsRejected(format!("Paid {} msat, expected {}", amount_msat, expected_msat)));
+        }
+        
+        swap.state = SwapState::Succeeded;
+        Ok(swap.preimage)
+    }
+    
+    /// Refund a loop in HTLC the server never paid for
+    pub fn refund_loop_in(
+        &mut self,
+        payment_hash: &PaymentHash,
+        funding_tx: &Transaction,
+        confirmation_height: u32,
+        current_height: u32,
+        fee: u64,
+        destination: Vec<u8>,
+    ) -> Result<Transaction, SwapError> {
+        let swap = self.swaps.get_mut(payment_hash)
+            .ok_or_else(|| SwapError::NotFound(payment_hash.to_hex()))?;
+        if swap.state == SwapState::Succeeded {
+            return Err(SwapError::InvalidState(format!("Loop in {} already succeeded", payment_hash)));
+        }
+        
+        let refund_height = swap.script.timelock.refund_height(confirmation_height);
+        if current_height < refund_height {
+            return Err(SwapError::TimelockNotExpired(refund_height));
+        }
+        
+        let (htlc, amount) = swap.script.find_output(funding_tx)?;
+        let refund_tx = swap.script.refund_transaction(&htlc, amount, fee, destination, &self.key)?;
+        swap.state = SwapState::Refunded;
+        
+        Ok(refund_tx)
+    }
+    
+    fn check_fee(&self, amount: u64, fee: u64) -> Result<(), SwapError> {
+        if fee > amount * self.max_fee_ppm / 1_000_000 {
+            return Err(SwapError::TermsRejected(format!("Fee {} exceeds our limit", fee)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn key(byte: u8) -> SecretKey {
+        SecretKey::from_slice(&[byte; 32]).unwrap()
+    }
+    
+    fn destination() -> Vec<u8> {
+        ScriptBuilder::pay_to_pubkey_hash(&[9u8; 20])
+    }
+    
+    #[test]
+    fn test_swap_script_claim_and_refund_paths() {
+        let preimage = PaymentPreimage::new_random();
+        let claim_key = key(1);
+        let refund_key = key(2);
+        let secp = Secp256k1::new();
+        let script = SwapScript::new(
+            preimage.payment_hash(),
+            SwapHash::from_preimage(&preimage),
+            &PublicKey::from_secret_key(&secp, &claim_key).serialize(),
+            &PublicKey::from_secret_key(&secp, &refund_key).serialize(),
+            SwapTimelock::Absolute(800),
+        );
+        let funding = Transaction::new(1, vec![], vec![script.funding_output(50_000)], 0);
+        let (htlc, amount) = script.find_output(&funding).unwrap();
+        
+        let claim = script.claim_transaction(&htlc, amount, 100, destination(), &preimage, &claim_key).unwrap();
+        script.verify_spend(&claim, 0, 1).unwrap();
+        assert_eq!(script.extract_preimage(&claim), Some(preimage));
+        
+        // Wrong preimage or wrong key cannot claim
+        assert!(script.claim_transaction(&htlc, amount, 100, destination(), &PaymentPreimage::new_random(), &claim_key).is_err());
+        let stolen = script.claim_transaction(&htlc, amount, 100, destination(), &preimage, &refund_key).unwrap();
+        assert!(script.verify_spend(&stolen, 0, 1).is_err());
+        
+        // The refund commits to the timelock height
+        let refund = script.refund_transaction(&htlc, amount, 100, destination(), &refund_key).unwrap();
+        assert_eq!(refund.lock_time(), 800);
+        script.verify_spend(&refund, 0, 1).unwrap();
+        
+        let early = Transaction::new(1, refund.inputs().to_vec(), refund.outputs().to_vec(), 799);
+        assert!(script.verify_spend(&early, 0, 1).is_err());
+    }
+    
+    #[test]
+    fn test_loop_out() {
+        let mut server = InProcessSwapServer::new(SwapTerms::default(), key(3), 1_000_000);
+        let mut client = SwapClient::new(key(4), 5_000);
+        
+        let contract = client.start_loop_out(&mut server, 100_000, 1_000).unwrap();
+        let payment_hash = contract.invoice.payment_hash();
+        
+        // Too short a CLTV on the Lightning HTLC is refused
+        assert!(server.pay_loop_out_invoice(&payment_hash, contract.invoice.amount_msat(), 1_100, 1_000).is_err());
+        
+        let funding = server.pay_loop_out_invoice(&payment_hash, contract.invoice.amount_msat(), 1_200, 1_000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(server.balance(), 900_000);
+        
+        let claim = client.claim_loop_out(&payment_hash, &funding, 200, destination()).unwrap();
+        contract.script.verify_spend(&claim, 0, 1).unwrap();
+        
+        server.loop_out_claimed(&payment_hash, &claim).unwrap();
+        assert_eq!(server.swap_state(&payment_hash), Some(SwapState::Succeeded));
+        assert!(server.refund_expired_loop_outs(2_000).is_empty());
+    }
+    
+    #[test]
+    fn test_loop_out_refund_when_client_disappears() {
+        let mut server = InProcessSwapServer::new(SwapTerms::default(), key(3), 1_000_000);
+        let mut client = SwapClient::new(key(4), 5_000);
+        
+        let contract = client.start_loop_out(&mut server, 100_000, 1_000).unwrap();
+        let payment_hash = contract.invoice.payment_hash();
+        server.pay_loop_out_invoice(&payment_hash, contract.invoice.amount_msat(), 1_200, 1_000).unwrap();
+        
+        assert!(server.refund_expired_loop_outs(1_143).is_empty());
+        let refunds = server.refund_expired_loop_outs(1_144);
+        assert_eq!(refunds.len(), 1);
+        contract.script.verify_spend(&refunds[0], 0, 144).unwrap();
+        assert_eq!(server.swap_state(&payment_hash), Some(SwapState::Refunded));
+        assert_eq!(server.balance(), 1_000_000);
+    }
+    
+    #[test]
+    fn test_loop_in_and_refund() {
+        let mut server = InProcessSwapServer::new(SwapTerms::default(), key(3), 0);
+        let mut client = SwapClient::new(key(4), 5_000);
+        
+        // Happy path: the server pays and claims with the revealed preimage
+        let contract = client.start_loop_in(&mut server, 200_000, 1_000).unwrap();
+        let payment_hash = contract.script.payment_hash;
+        let funding = Transaction::new(1, vec![], vec![contract.script.funding_output(200_000)], 0);
+        client.loop_in_funded(&payment_hash).unwrap();
+        
+        // The server waits for confirmations and pays only if it can claim before the refund at 1_145
+        assert!(matches!(
+            server.loop_in_funded(&payment_hash, &funding, 1_001, 1_002, 1_100),
+            Err(SwapError::InsufficientConfirmations(2, 3))
+        ));
+        assert!(server.loop_in_funded(&payment_hash, &funding, 1_001, 1_003, 1_121).is_err());
+        let paid_msat = server.loop_in_funded(&payment_hash, &funding, 1_001, 1_003, 1_120).unwrap();
+        let preimage = client.loop_in_payment_received(&payment_hash, paid_msat).unwrap();
+        let claim = server.settle_loop_in(&preimage).unwrap();
+        contract.script.verify_spend(&claim, 0, 1).unwrap();
+        assert!(client.refund_loop_in(&payment_hash, &funding, 1_001, 2_000, 100, destination()).is_err());
+        
+        // The server disappears after the HTLC is funded
+        let contract = client.start_loop_in(&mut server, 200_000, 1_000).unwrap();
+        let payment_hash = contract.script.payment_hash;
+        let funding = Transaction::new(1, vec![], vec![contract.script.funding_output(200_000)], 1);
+        client.loop_in_funded(&payment_hash).unwrap();
+        
+        assert!(matches!(
+            client.refund_loop_in(&payment_hash, &funding, 1_001, 1_100, 100, destination()),
+            Err(SwapError::TimelockNotExpired(1_145))
+        ));
+        let refund = client.refund_loop_in(&payment_hash, &funding, 1_001, 1_145, 100, destination()).unwrap();
+        assert!(contract.script.verify_spend(&refund, 0, 143).is_err());
+        contract.script.verify_spend(&refund, 0, 144).unwrap();
+    }
+}
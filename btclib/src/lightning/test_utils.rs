@@ -0,0 +1,25 @@
+//! Shared fixtures for Lightning tests
+
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use super::onion::{HopKem, OnionError};
+
+/// Deterministic KEM for tests: the secret key equals the public key
+pub(crate) struct TestKem;
+
+impl HopKem for TestKem {
+    fn encapsulate(&self, public_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>), OnionError> {
+        let mut ciphertext = vec![0u8; 32];
+        rand::thread_rng().fill_bytes(&mut ciphertext);
+        let shared_secret = self.decapsulate(public_key, &ciphertext)?;
+        Ok((ciphertext, shared_secret))
+    }
+    
+    fn decapsulate(&self, secret_key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, OnionError> {
+        let mut hasher = Sha256::new();
+        hasher.update(secret_key);
+        hasher.update(ciphertext);
+        Ok(hasher.finalize().to_vec())
+    }
+}
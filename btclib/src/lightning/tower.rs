@@ -0,0 +1,913 @@
+//! Network Watchtower Protocol
+//!
+//! This module lets a node outsource breach monitoring to a remote tower.
+//! After every revocation the client builds a justice transaction for the
+//! revoked commitment, encrypts it under a key derived from the commitment's
+//! transaction ID and uploads it tagged with a short breach hint. The tower
+//! learns nothing until the revoked commitment appears on-chain: only then
+//! can it derive the key, decrypt the blob and broadcast the justice
+//! transaction, which pays the tower its negotiated reward.
+//!
+//! - Sessions are created per client Dilithium key and carry an update quota
+//!   and a reward policy.
+//! - Every message is signed by the client key; updates and deletions are
+//!   only accepted from the key that created the session.
+//! - Messages are bincode-encoded and length-prefixed over TCP.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use chacha20poly1305::{aead::{Aead, KeyInit}, ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::crypto::quantum::{verify_quantum_signature, QuantumKeyPair, QuantumParameters, QuantumScheme};
+use crate::script::ScriptBuilder;
+use crate::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+
+/// Length of the breach hint attached to each encrypted blob
+pub const BREACH_HINT_LEN: usize = 16;
+
+/// Largest frame accepted on a tower connection
+const MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+/// Domain separator for blob encryption keys
+const BLOB_KEY_DOMAIN: &[u8] = b"supernova-watchtower-blob";
+
+/// Prefix of a breach transaction ID, used to look up blobs
+pub type BreachHint = [u8; BREACH_HINT_LEN];
+
+/// Identifier of a tower session
+pub type SessionId = [u8; 32];
+
+/// Watchtower protocol errors
+#[derive(Debug, Error)]
+pub enum TowerError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    
+    #[error("Codec error: {0}")]
+    Codec(String),
+    
+    #[error("Encryption error: {0}")]
+    Encryption(String),
+    
+    #[error("Signature error: {0}")]
+    Signature(String),
+    
+    #[error("Tower rejected request: {0:?}")]
+    Rejected(TowerCode),
+    
+    #[error("Unexpected reply: {0}")]
+    UnexpectedReply(String),
+    
+    #[error("Invalid justice transaction: {0}")]
+    InvalidJustice(String),
+    
+    #[error("Tower client stopped")]
+    ClientStopped,
+}
+
+/// Result code returned by the tower
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TowerCode {
+    Ok,
+    SessionNotFound,
+    QuotaExceeded,
+    SeqOutOfOrder,
+    PolicyRejected,
+    Unauthorized,
+    TowerFull,
+}
+
+/// Terms of a tower session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionPolicy {
+    /// Number of state updates the session accepts
+    pub max_updates: u16,
+    
+    /// Flat reward paid to the tower by each justice transaction, in novas
+    pub reward_base: u64,
+    
+    /// Proportional reward in millionths of the swept amount
+    pub reward_rate: u32,
+    
+    /// Fee left to miners by each justice transaction, in novas
+    pub sweep_fee: u64,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Self {
+            max_updates: 1024,
+            reward_base: 1_000,
+            reward_rate: 10_000,
+            sweep_fee: 500,
+        }
+    }
+}
+
+impl SessionPolicy {
+    /// Reward owed to the tower for sweeping `amount` novas
+    pub fn reward(&self, amount: u64) -> u64 {
+        self.reward_base + (amount as u128 * self.reward_rate as u128 / 1_000_000) as u64
+    }
+}
+
+/// Messages exchanged between tower clients and towers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TowerMessage {
+    /// Open a session with the given policy
+    CreateSession {
+        policy: SessionPolicy,
+    },
+    
+    /// Reply to `CreateSession`
+    CreateSessionReply {
+        code: TowerCode,
+        session_id: SessionId,
+        /// Script justice transactions must pay the reward to
+        reward_script: Vec<u8>,
+        last_applied: u16,
+    },
+    
+    /// Upload an encrypted justice kit
+    StateUpdate {
+        session_id: SessionId,
+        seq_num: u16,
+        hint: BreachHint,
+        encrypted_blob: Vec<u8>,
+    },
+    
+    /// Reply to `StateUpdate`
+    StateUpdateReply {
+        code: TowerCode,
+        last_applied: u16,
+    },
+    
+    /// Close a session and drop its blobs
+    DeleteSession {
+        session_id: SessionId,
+    },
+    
+    /// Reply to `DeleteSession`
+    DeleteSessionReply {
+        code: TowerCode,
+    },
+}
+
+/// Tower message signed by the client's Dilithium key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedTowerMessage {
+    /// Client public key
+    pub public_key: Vec<u8>,
+    
+    /// Security level of the client key
+    pub security_level: u8,
+    
+    /// The signed message
+    pub message: TowerMessage,
+    
+    /// Signature over the SHA-256 of the encoded message
+    pub signature: Vec<u8>,
+}
+
+impl SignedTowerMessage {
+    /// Sign a message with a Dilithium key pair
+    pub fn sign(message: TowerMessage, key: &QuantumKeyPair) -> Result<Self, TowerError> {
+        if key.parameters.scheme != QuantumScheme::Dilithium {
+            return Err(TowerError::Signature(
+                format!("Tower messages must be signed with Dilithium, not {:?}", key.parameters.scheme)
+            ));
+        }
+        
+        let signature = key.sign(&message_digest(&message)?)
+            .map_err(|e| TowerError::Signature(e.to_string()))?;
+        
+        Ok(Self {
+            public_key: key.public_key.clone(),
+            security_level: key.parameters.security_level,
+            message,
+            signature,
+        })
+    }
+    
+    /// Check the signature against the embedded public key
+    pub fn verify(&self) -> Result<(), TowerError> {
+        let parameters = QuantumParameters::with_security_level(QuantumScheme::Dilithium, self.security_level);
+        
+        match verify_quantum_signature(&self.public_key, &message_digest(&self.message)?, &self.signature, parameters) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(TowerError::Signature("Dilithium signature does not match".to_string())),
+            Err(e) => Err(TowerError::Signature(e.to_string())),
+        }
+    }
+}
+
+fn message_digest(message: &TowerMessage) -> Result<[u8; 32], TowerError> {
+    let bytes = bincode::serialize(message).map_err(|e| TowerError::Codec(e.to_string()))?;
+    Ok(Sha256::digest(&bytes).into())
+}
+
+/// Data the tower needs to punish a breach
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JusticeKit {
+    /// Fully signed transaction sweeping the revoked commitment
+    pub justice_tx: Transaction,
+}
+
+/// Breach hint of a commitment transaction ID
+pub fn breach_hint(txid: &[u8; 32]) -> BreachHint {
+    let mut hint = [0u8; BREACH_HINT_LEN];
+    hint.copy_from_slice(&txid[..BREACH_HINT_LEN]);
+    hint
+}
+
+fn blob_key(txid: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(BLOB_KEY_DOMAIN);
+    hasher.update(txid);
+    hasher.finalize().into()
+}
+
+/// Encrypt a justice kit under the breach transaction ID
+///
+/// The blob is the 12-byte nonce followed by the ChaCha20-Poly1305
+/// ciphertext.
+pub fn encrypt_justice_kit(kit: &JusticeKit, breach_txid: &[u8; 32]) -> Result<Vec<u8>, TowerError> {
+    let plaintext = bincode::serialize(kit).map_err(|e| TowerError::Codec(e.to_string()))?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&blob_key(breach_txid)));
+    
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|e| TowerError::Encryption(e.to_string()))?;
+    
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(blob)
+}
+
+/// Decrypt a justice kit with the breach transaction ID
+pub fn decrypt_justice_kit(blob: &[u8], breach_txid: &[u8; 32]) -> Result<JusticeKit, TowerError> {
+    if blob.len() < 12 {
+        return Err(TowerError::Encryption("Blob too short".to_string()));
+    }
+    
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&blob_key(breach_txid)));
+    let plaintext = cipher.decrypt(Nonce::from_slice(&blob[..12]), &blob[12..])
+        .map_err(|e| TowerError::Encryption(e.to_string()))?;
+    
+    bincode::deserialize(&plaintext).map_err(|e| TowerError::Codec(e.to_string()))
+}
+
+/// Build a justice transaction sweeping every output of a revoked commitment
+///
+/// Each input reveals the per-commitment secret. The tower's reward goes to
+/// `reward_script`, the rest minus the sweep fee to `sweep_script`.
+pub fn build_justice_transaction(
+    revoked_commitment: &Transaction,
+    per_commitment_secret: &[u8; 32],
+    policy: &SessionPolicy,
+    reward_script: &[u8],
+    sweep_script: &[u8],
+) -> Result<Transaction, TowerError> {
+    let txid = revoked_commitment.hash();
+    let total: u64 = revoked_commitment.outputs().iter().map(|output| output.amount()).sum();
+    let reward = policy.reward(total);
+    
+    let sweep = total.checked_sub(reward + policy.sweep_fee)
+        .filter(|sweep| *sweep > 0)
+        .ok_or_else(|| TowerError::InvalidJustice(
+            format!("Commitment value {} does not cover reward {} and fee {}", total, reward, policy.sweep_fee)
+        ))?;
+    
+    let witness = ScriptBuilder::new().push_data(per_commitment_secret).build();
+    let inputs = (0..revoked_commitment.outputs().len())
+        .map(|vout| TransactionInput::new(txid, vout as u32, witness.clone(), 0xFFFF_FFFF))
+        .collect();
+    
+    Ok(Transaction::new(
+        1,
+        inputs,
+        vec![
+            TransactionOutput::new(reward, reward_script.to_vec()),
+            TransactionOutput::new(sweep, sweep_script.to_vec()),
+        ],
+        0,
+    ))
+}
+
+/// Write a length-prefixed bincode frame
+pub async fn write_frame<W, T>(writer: &mut W, value: &T) -> Result<(), TowerError>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let bytes = bincode::serialize(value).map_err(|e| TowerError::Codec(e.to_string()))?;
+    writer.write_u32(bytes.len() as u32).await?;
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read a length-prefixed bincode frame
+pub async fn read_frame<R, T>(reader: &mut R) -> Result<T, TowerError>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let len = reader.read_u32().await? as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(TowerError::Codec(format!("Frame of {} bytes exceeds limit", len)));
+    }
+    
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes).await?;
+    bincode::deserialize(&bytes).map_err(|e| TowerError::Codec(e.to_string()))
+}
+
+/// Tower service configuration
+#[derive(Debug, Clone)]
+pub struct TowerServerConfig {
+    /// Maximum number of open sessions
+    pub max_sessions: usize,
+    
+    /// Largest update quota granted to a session
+    pub max_updates_per_session: u16,
+    
+    /// Smallest flat reward accepted
+    pub min_reward_base: u64,
+    
+    /// Smallest proportional reward accepted, in millionths
+    pub min_reward_rate: u32,
+    
+    /// Script the tower's rewards are paid to
+    pub reward_script: Vec<u8>,
+}
+
+impl Default for TowerServerConfig {
+    fn default() -> Self {
+        Self {
+            max_sessions: 10_000,
+            max_updates_per_session: 1024,
+            min_reward_base: 0,
+            min_reward_rate: 0,
+            reward_script: Vec::new(),
+        }
+    }
+}
+
+struct TowerSession {
+    client_key: Vec<u8>,
+    policy: SessionPolicy,
+    last_applied: u16,
+}
+
+struct StoredBlob {
+    session_id: SessionId,
+    encrypted_blob: Vec<u8>,
+}
+
+#[derive(Default)]
+struct TowerState {
+    sessions: HashMap<SessionId, TowerSession>,
+    blobs: HashMap<BreachHint, Vec<StoredBlob>>,
+}
+
+/// Watchtower service storing encrypted blobs for remote clients
+pub struct TowerServer {
+    config: TowerServerConfig,
+    state: Mutex<TowerState>,
+}
+
+impl TowerServer {
+    /// Create a tower
+    pub fn new(config: TowerServerConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(TowerState::default()),
+        }
+    }
+    
+    /// Number of open sessions
+    pub fn session_count(&self) -> usize {
+        self.state.lock().unwrap().sessions.len()
+    }
+    
+    /// Number of stored blobs
+    pub fn blob_count(&self) -> usize {
+        self.state.lock().unwrap().blobs.values().map(Vec::len).sum()
+    }
+    
+    /// Handle a signed client message, returning the reply
+    pub fn handle(&self, request: &SignedTowerMessage) -> Result<TowerMessage, TowerError> {
+        request.verify()?;
+        
+        let mut state = self.state.lock().unwrap();
+        
+        let reply = match &request.message {
+            TowerMessage::CreateSession { policy } => {
+                let code = if state.sessions.len() >= self.config.max_sessions {
+                    TowerCode::TowerFull
+                } else if policy.max_updates > self.config.max_updates_per_session
+                    || policy.reward_base < self.config.min_reward_base
+                    || policy.reward_rate < self.config.min_reward_rate
+                {
+                    TowerCode::PolicyRejected
+                } else {
+                    TowerCode::Ok
+                };
+                
+                let mut session_id = [0u8; 32];
+                if code == TowerCode::Ok {
+                    rand::thread_rng().fill_bytes(&mut session_id);
+                    state.sessions.insert(session_id, TowerSession {
+                        client_key: request.public_key.clone(),
+                        policy: *policy,
+                        last_applied: 0,
+                    });
+                    debug!("Opened tower session {}", hex::encode(session_id));
+                }
+                
+                TowerMessage::CreateSessionReply {
+                    code,
+                    session_id,
+                    reward_script: self.config.reward_script.clone(),
+                    last_applied: 0,
+                }
+            }
+            TowerMessage::StateUpdate { session_id, seq_num, hint, encrypted_blob } => {
+                let (code, last_applied) = match state.sessions.get_mut(session_id) {
+                    None => (TowerCode::SessionNotFound, 0),
+                    Some(session) if session.client_key != request.public_key => (TowerCode::Unauthorized, 0),
+                    Some(session) if *seq_num > session.policy.max_updates => {
+                        (TowerCode::QuotaExceeded, session.last_applied)
+                    }
+                    Some(session) if *seq_num != session.last_applied + 1 => {
+                        (TowerCode::SeqOutOfOrder, session.last_applied)
+                    }
+                    Some(session) => {
+                        session.last_applied = *seq_num;
+                        (TowerCode::Ok, session.last_applied)
+                    }
+                };
+                
+                if code == TowerCode::Ok {
+                    state.blobs.entry(*hint).or_default().push(StoredBlob {
+                        session_id: *session_id,
+                        encrypted_blob: encrypted_blob.clone(),
+                    });
+                }
+                
+                TowerMessage::StateUpdateReply { code, last_applied }
+            }
+            TowerMessage::DeleteSession { session_id } => {
+                let code = match state.sessions.get(session_id) {
+                    None => TowerCode::SessionNotFound,
+                    Some(session) if session.client_key != request.public_key => TowerCode::Unauthorized,
+                    Some(_) => TowerCode::Ok,
+                };
+                
+                if code == TowerCode::Ok {
+                    state.sessions.remove(session_id);
+                    for blobs in state.blobs.values_mut() {
+                        blobs.retain(|blob| blob.session_id != *session_id);
+                    }
+                    state.blobs.retain(|_, blobs| !blobs.is_empty());
+                }
+                
+                TowerMessage::DeleteSessionReply { code }
+            }
+            other => {
+                return Err(TowerError::UnexpectedReply(format!("{:?} is not a client request", other)));
+            }
+        };
+        
+        Ok(reply)
+    }
+    
+    /// Scan transactions for breaches, returning the justice transactions to broadcast
+    pub fn process_transactions(&self, transactions: &[Transaction]) -> Vec<Transaction> {
+        let mut state = self.state.lock().unwrap();
+        let mut justice = Vec::new();
+        
+        for tx in transactions {
+            let txid = tx.hash();
+            let hint = breach_hint(&txid);
+            let Some(blobs) = state.blobs.remove(&hint) else {
+                continue;
+            };
+            
+            let mut unmatched = Vec::new();
+            for blob in blobs {
+                let kit = match decrypt_justice_kit(&blob.encrypted_blob, &txid) {
+                    Ok(kit) => kit,
+                    // Hint collision with another commitment
+                    Err(_) => {
+                        unmatched.push(blob);
+                        continue;
+                    }
+                };
+                
+                let Some(session) = state.sessions.get(&blob.session_id) else {
+                    continue;
+                };
+                
+                match self.check_justice(&kit.justice_tx, tx, &session.policy) {
+                    Ok(()) => {
+                        info!("Breach detected for commitment {}, broadcasting justice transaction", hex::encode(txid));
+                        justice.push(kit.justice_tx);
+                    }
+                    Err(e) => warn!("Discarding justice kit for {}: {}", hex::encode(txid), e),
+                }
+            }
+            
+            if !unmatched.is_empty() {
+                state.blobs.insert(hint, unmatched);
+            }
+        }
+        
+        justice
+    }
+    
+    fn check_justice(&self, justice_tx: &Transaction, breach_tx: &Transaction, policy: &SessionPolicy) -> Result<(), TowerError> {
+        let txid = breach_tx.hash();
+        
+        let mut swept = 0u64;
+        for input in justice_tx.inputs() {
+            if input.prev_tx_hash() != txid {
+                return Err(TowerError::InvalidJustice("Input does not spend the breach transaction".to_string()));
+            }
+            let output = breach_tx.outputs().get(input.prev_output_index() as usize)
+                .ok_or_else(|| TowerError::InvalidJustice("Input spends a missing output".to_string()))?;
+            swept += output.amount();
+        }
+        
+        let reward: u64 = justice_tx.outputs().iter()
+            .filter(|output| output.script_pubkey() == self.config.reward_script.as_slice())
+            .map(|output| output.amount())
+            .sum();
+        
+        if reward < policy.reward(swept) {
+            return Err(TowerError::InvalidJustice(
+                format!("Reward {} below the agreed {}", reward, policy.reward(swept))
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    /// Accept client connections until the listener fails
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<(), TowerError> {
+        info!("Watchtower listening on {}", listener.local_addr()?);
+        
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let tower = Arc::clone(&self);
+            
+            tokio::spawn(async move {
+                if let Err(e) = tower.handle_connection(stream).await {
+                    debug!("Tower connection from {} closed: {}", peer, e);
+                }
+            });
+        }
+    }
+    
+    async fn handle_connection(&self, mut stream: TcpStream) -> Result<(), TowerError> {
+        loop {
+            let request: SignedTowerMessage = read_frame(&mut stream).await?;
+            let reply = self.handle(&request)?;
+            write_frame(&mut stream, &reply).await?;
+        }
+    }
+}
+
+struct ClientSession {
+    session_id: SessionId,
+    reward_script: Vec<u8>,
+    last_applied: u16,
+}
+
+/// Counters reported by a tower client
+#[derive(Debug, Clone, Default)]
+pub struct TowerClientStats {
+    /// Justice kits accepted by the tower
+    pub backups_uploaded: u64,
+    
+    /// Sessions opened with the tower
+    pub sessions_created: u64,
+    
+    /// Backups that could not be uploaded
+    pub backups_failed: u64,
+}
+
+/// Client uploading justice kits to a remote tower
+pub struct TowerClient {
+    tower_addr: SocketAddr,
+    key: QuantumKeyPair,
+    policy: SessionPolicy,
+    sweep_script: Vec<u8>,
+    stream: Option<TcpStream>,
+    session: Option<ClientSession>,
+    stats: Arc<Mutex<TowerClientStats>>,
+}
+
+impl TowerClient {
+    /// Create a client for the tower at `tower_addr`
+    ///
+    /// Swept funds are paid to `sweep_script`. The connection and session
+    /// are opened on the first backup.
+    pub fn new(tower_addr: SocketAddr, key: QuantumKeyPair, policy: SessionPolicy, sweep_script: Vec<u8>) -> Self {
+        Self {
+            tower_addr,
+            key,
+            policy,
+            sweep_script,
+            stream: None,
+            session: None,
+            stats: Arc::new(Mutex::new(TowerClientStats::default())),
+        }
+    }
+    
+    /// Current counters
+    pub fn stats(&self) -> TowerClientStats {
+        self.stats.lock().unwrap().clone()
+    }
+    
+    /// Back up a revoked commitment with the secret that revoked it
+    pub async fn backup(&mut self, revoked_commitment: &Transaction, per_commitment_secret: &[u8; 32]) -> Result<(), TowerError> {
+        let result = self.try_backup(revoked_commitment, per_commitment_secret).await;
+        
+        let mut stats = self.stats.lock().unwrap();
+        match &result {
+            Ok(()) => stats.backups_uploaded += 1,
+            Err(_) => stats.backups_failed += 1,
+        }
+        
+        result
+    }
+    
+    async fn try_backup(&mut self, revoked_commitment: &Transaction, per_commitment_secret: &[u8; 32]) -> Result<(), TowerError> {
+        // Open a fresh session once the current one is used up
+        if self.session.as_ref().is_none_or(|session| session.last_applied >= self.policy.max_updates) {
+            self.create_session().await?;
+        }
+        
+        let session = self.session.as_ref().ok_or(TowerError::ClientStopped)?;
+        let justice_tx = build_justice_transaction(
+            revoked_commitment,
+            per_commitment_secret,
+            &self.policy,
+            &session.reward_script,
+            &self.sweep_script,
+        )?;
+        
+        let txid = revoked_commitment.hash();
+        let update = TowerMessage::StateUpdate {
+            session_id: session.session_id,
+            seq_num: session.last_applied + 1,
+            hint: breach_hint(&txid),
+            encrypted_blob: encrypt_justice_kit(&JusticeKit { justice_tx }, &txid)?,
+        };
+        
+        match self.request(update).await? {
+            TowerMessage::StateUpdateReply { code: TowerCode::Ok, last_applied } => {
+                if let Some(session) = self.session.as_mut() {
+                    session.last_applied = last_applied;
+                }
+                Ok(())
+            }
+            TowerMessage::StateUpdateReply { code, .. } => {
+                // Let the next backup start over with a new session
+                self.session = None;
+                Err(TowerError::Rejected(code))
+            }
+            other => Err(TowerError::UnexpectedReply(format!("{:?}", other))),
+        }
+    }
+    
+    async fn create_session(&mut self) -> Result<(), TowerError> {
+        match self.request(TowerMessage::CreateSession { policy: self.policy }).await? {
+            TowerMessage::CreateSessionReply { code: TowerCode::Ok, session_id, reward_script, last_applied } => {
+                debug!("Created session {} with tower {}", hex::encode(session_id), self.tower_addr);
+                self.session = Some(ClientSession { session_id, reward_script, last_applied });
+                self.stats.lock().unwrap().sessions_created += 1;
+                Ok(())
+            }
+            TowerMessage::CreateSessionReply { code, .. } => Err(TowerError::Rejected(code)),
+            other => Err(TowerError::UnexpectedReply(format!("{:?}", other))),
+        }
+    }
+    
+    async fn request(&mut self, message: TowerMessage) -> Result<TowerMessage, TowerError> {
+        let signed = SignedTowerMessage::sign(message, &self.key)?;
+        
+        if self.stream.is_none() {
+            self.stream = Some(TcpStream::connect(self.tower_addr).await?);
+        }
+        let stream = self.stream.as_mut().ok_or(TowerError::ClientStopped)?;
+        
+        let result = async {
+            write_frame(stream, &signed).await?;
+            read_frame(stream).await
+        }.await;
+        
+        // Reconnect on the next request after a broken connection
+        if matches!(result, Err(TowerError::Io(_))) {
+            self.stream = None;
+        }
+        
+        result
+    }
+    
+    /// Run the client on a background task fed by the returned handle
+    pub fn spawn(self) -> TowerClientHandle {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<(Transaction, [u8; 32])>();
+        let stats = Arc::clone(&self.stats);
+        
+        tokio::spawn(async move {
+            let mut client = self;
+            while let Some((commitment, secret)) = receiver.recv().await {
+                if let Err(e) = client.backup(&commitment, &secret).await {
+                    warn!("Failed to back up revoked commitment to tower {}: {}", client.tower_addr, e);
+                }
+            }
+        });
+        
+        TowerClientHandle { sender, stats }
+    }
+}
+
+/// Handle queueing backups for a spawned tower client
+#[derive(Clone)]
+pub struct TowerClientHandle {
+    sender: mpsc::UnboundedSender<(Transaction, [u8; 32])>,
+    stats: Arc<Mutex<TowerClientStats>>,
+}
+
+impl TowerClientHandle {
+    /// Queue a revoked commitment for upload
+    pub fn backup(&self, revoked_commitment: Transaction, per_commitment_secret: [u8; 32]) -> Result<(), TowerError> {
+        self.sender.send((revoked_commitment, per_commitment_secret))
+            .map_err(|_| TowerError::ClientStopped)
+    }
+    
+    /// Current counters
+    pub fn stats(&self) -> TowerClientStats {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    
+    fn commitment(number: u32, to_local: u64, to_remote: u64) -> Transaction {
+        Transaction::new(
+            2,
+            vec![TransactionInput::new([7u8; 32], 0, Vec::new(), 0x8000_0000 | number)],
+            vec![
+                TransactionOutput::new(to_local, vec![0x51]),
+                TransactionOutput::new(to_remote, vec![0x52]),
+            ],
+            number,
+        )
+    }
+    
+    fn client_key() -> QuantumKeyPair {
+        QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap()
+    }
+    
+    async fn start_tower(config: TowerServerConfig) -> (Arc<TowerServer>, SocketAddr) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let tower = Arc::new(TowerServer::new(config));
+        tokio::spawn(Arc::clone(&tower).serve(listener));
+        (tower, addr)
+    }
+    
+    #[tokio::test]
+    async fn test_tower_punishes_breach() {
+        let reward_script = ScriptBuilder::pay_to_pubkey_hash(&[0xAA; 20]);
+        let sweep_script = ScriptBuilder::pay_to_pubkey_hash(&[0xBB; 20]);
+        let (tower, addr) = start_tower(TowerServerConfig {
+            reward_script: reward_script.clone(),
+            ..Default::default()
+        }).await;
+        
+        let policy = SessionPolicy { max_updates: 2, ..Default::default() };
+        let mut client = TowerClient::new(addr, client_key(), policy, sweep_script.clone());
+        
+        // Three revocations: the third needs a second session
+        let revoked: Vec<Transaction> = (1..=3).map(|n| commitment(n, 600_000, 400_000 - n as u64)).collect();
+        for (n, tx) in revoked.iter().enumerate() {
+            client.backup(tx, &[n as u8 + 1; 32]).await.unwrap();
+        }
+        assert_eq!(client.stats().backups_uploaded, 3);
+        assert_eq!(client.stats().sessions_created, 2);
+        assert_eq!(tower.session_count(), 2);
+        assert_eq!(tower.blob_count(), 3);
+        
+        // The tower learns nothing from unrelated transactions
+        assert!(tower.process_transactions(&[commitment(9, 1, 1)]).is_empty());
+        
+        // The counterparty broadcasts the second revoked commitment
+        let breach = &revoked[1];
+        let justice = tower.process_transactions(&[breach.clone()]);
+        assert_eq!(justice.len(), 1);
+        
+        let justice_tx = &justice[0];
+        assert!(justice_tx.inputs().iter().all(|input| input.prev_tx_hash() == breach.hash()));
+        assert_eq!(justice_tx.inputs().len(), 2);
+        let witness = ScriptBuilder::new().push_data(&[2u8; 32]).build();
+        assert_eq!(justice_tx.inputs()[0].signature_script(), witness.as_slice());
+        
+        let total = 600_000 + 400_000 - 2;
+        assert_eq!(justice_tx.outputs()[0].script_pubkey(), reward_script.as_slice());
+        assert_eq!(justice_tx.outputs()[0].amount(), policy.reward(total));
+        assert_eq!(justice_tx.outputs()[1].script_pubkey(), sweep_script.as_slice());
+        assert_eq!(justice_tx.outputs()[1].amount(), total - policy.reward(total) - policy.sweep_fee);
+        
+        // Each blob is acted on once
+        assert_eq!(tower.blob_count(), 2);
+        assert!(tower.process_transactions(&[breach.clone()]).is_empty());
+        
+        // Backups queued through a spawned client are uploaded in the background
+        let handle = TowerClient::new(addr, client_key(), policy, sweep_script).spawn();
+        handle.backup(commitment(4, 500_000, 500_000), [4u8; 32]).unwrap();
+        for _ in 0..100 {
+            if handle.stats().backups_uploaded == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(handle.stats().backups_uploaded, 1);
+        assert_eq!(tower.blob_count(), 3);
+    }
+    
+    #[test]
+    fn test_tower_session_rules() {
+        let tower = TowerServer::new(TowerServerConfig {
+            max_sessions: 1,
+            max_updates_per_session: 1,
+            min_reward_base: 100,
+            ..Default::default()
+        });
+        let owner = client_key();
+        let intruder = client_key();
+        
+        let request = |key: &QuantumKeyPair, message: TowerMessage| {
+            tower.handle(&SignedTowerMessage::sign(message, key).unwrap()).unwrap()
+        };
+        
+        // Policies outside the tower's limits are rejected
+        let greedy = SessionPolicy { max_updates: 5, reward_base: 100, ..Default::default() };
+        assert!(matches!(
+            request(&owner, TowerMessage::CreateSession { policy: greedy }),
+            TowerMessage::CreateSessionReply { code: TowerCode::PolicyRejected, .. }
+        ));
+        
+        let policy = SessionPolicy { max_updates: 1, reward_base: 100, ..Default::default() };
+        let session_id = match request(&owner, TowerMessage::CreateSession { policy }) {
+            TowerMessage::CreateSessionReply { code: TowerCode::Ok, session_id, .. } => session_id,
+            other => panic!("unexpected reply {:?}", other),
+        };
+        assert!(matches!(
+            request(&intruder, TowerMessage::CreateSession { policy }),
+            TowerMessage::CreateSessionReply { code: TowerCode::TowerFull, .. }
+        ));
+        
+        let update = |seq_num| TowerMessage::StateUpdate {
+            session_id,
+            seq_num,
+            hint: [seq_num as u8; BREACH_HINT_LEN],
+            encrypted_blob: vec![0u8; 32],
+        };
+        
+        // Only the session owner may upload, in order, within the quota
+        assert!(matches!(request(&intruder, update(1)), TowerMessage::StateUpdateReply { code: TowerCode::Unauthorized, .. }));
+        assert!(matches!(request(&owner, update(2)), TowerMessage::StateUpdateReply { code: TowerCode::QuotaExceeded, .. }));
+        assert!(matches!(request(&owner, update(1)), TowerMessage::StateUpdateReply { code: TowerCode::Ok, last_applied: 1 }));
+        assert!(matches!(request(&owner, update(1)), TowerMessage::StateUpdateReply { code: TowerCode::SeqOutOfOrder, .. }));
+        
+        // Forged signatures are refused outright
+        let mut forged = SignedTowerMessage::sign(TowerMessage::DeleteSession { session_id }, &intruder).unwrap();
+        forged.public_key = owner.public_key.clone();
+        assert!(matches!(tower.handle(&forged), Err(TowerError::Signature(_))));
+        
+        assert!(matches!(request(&intruder, TowerMessage::DeleteSession { session_id }), TowerMessage::DeleteSessionReply { code: TowerCode::Unauthorized }));
+        assert!(matches!(request(&owner, TowerMessage::DeleteSession { session_id }), TowerMessage::DeleteSessionReply { code: TowerCode::Ok }));
+        assert_eq!(tower.session_count(), 0);
+        assert_eq!(tower.blob_count(), 0);
+    }
+}
@@ -0,0 +1,560 @@
+//! Trampoline Routing
+//!
+//! Light clients cannot keep the whole network graph. With trampoline routing
+//! the sender only finds a route to a trampoline node it is close to, and
+//! attaches an inner trampoline onion naming the next trampoline or the
+//! recipient. Each trampoline peels its layer, finds the rest of the route
+//! with its own `Router`, and keeps what is left of the fee it was offered
+//! after paying for that route. Trampolines advertise themselves with
+//! `FEATURE_TRAMPOLINE_ROUTING` in their init message.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::crypto::kem::KemKeyPair;
+
+use super::onion::{open_layer, seal_layer, HopKem, OnionError, MAX_ONION_HOPS};
+use super::router::{PaymentPath, Router, RoutingError};
+use super::wire::InitPayload;
+
+/// Key separation for trampoline onion layers
+const LAYER_DOMAIN: &[u8] = b"trampoline_layer";
+
+/// Trampoline routing errors
+#[derive(Debug, Error)]
+pub enum TrampolineError {
+    #[error("Onion error: {0}")]
+    Onion(#[from] OnionError),
+    
+    #[error("Codec error: {0}")]
+    Codec(#[from] bincode::Error),
+    
+    #[error("Routing error: {0}")]
+    Routing(#[from] RoutingError),
+    
+    #[error("No trampoline nodes given")]
+    NoTrampolines,
+    
+    #[error("Node {0} does not support trampoline routing")]
+    NotTrampolineCapable(String),
+    
+    #[error("Trampoline fee insufficient: required {required} msat, offered {offered} msat")]
+    FeeInsufficient { required: u64, offered: u64 },
+    
+    #[error("CLTV expiry too soon: required {required}, offered {offered}")]
+    CltvExpiryTooSoon { required: u32, offered: u32 },
+    
+    #[error("Amount below expected: expected {expected} msat, received {received} msat")]
+    AmountBelowExpected { expected: u64, received: u64 },
+}
+
+/// Fee a trampoline asks for relaying a payment
+///
+/// The fee must also pay for the route the trampoline finds to the next node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrampolineFeePolicy {
+    /// Base fee in millinovas
+    pub base_fee_msat: u32,
+    
+    /// Fee rate in parts per million
+    pub fee_rate_millionths: u32,
+    
+    /// Blocks the trampoline needs between incoming and outgoing expiry
+    pub cltv_expiry_delta: u16,
+}
+
+impl TrampolineFeePolicy {
+    /// Fee for relaying `amount_msat` to the next node
+    pub fn fee_msat(&self, amount_msat: u64) -> u64 {
+        self.base_fee_msat as u64 + amount_msat * self.fee_rate_millionths as u64 / 1_000_000
+    }
+}
+
+impl Default for TrampolineFeePolicy {
+    fn default() -> Self {
+        Self {
+            base_fee_msat: 1_000,
+            fee_rate_millionths: 2_000,
+            cltv_expiry_delta: 288,
+        }
+    }
+}
+
+/// A trampoline node the sender routes through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrampolineHop {
+    /// Node ID
+    pub node_id: String,
+    
+    /// KEM public key of the node
+    pub kem_public_key: Vec<u8>,
+    
+    /// Fee the node advertises
+    pub policy: TrampolineFeePolicy,
+}
+
+impl TrampolineHop {
+    /// Use a peer as a trampoline, checking the features from its init message
+    pub fn from_init(
+        init: &InitPayload,
+        kem_public_key: Vec<u8>,
+        policy: TrampolineFeePolicy,
+    ) -> Result<Self, TrampolineError> {
+        if !init.supports_trampoline() {
+            return Err(TrampolineError::NotTrampolineCapable(init.node_id.clone()));
+        }
+        
+        Ok(Self {
+            node_id: init.node_id.clone(),
+            kem_public_key,
+            policy,
+        })
+    }
+}
+
+/// Inner onion carried to each trampoline on top of the regular route
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrampolineOnion {
+    /// KEM ciphertext for the receiving node
+    pub kem_ciphertext: Vec<u8>,
+    
+    /// Encrypted `TrampolinePayload` with its tag
+    pub payload: Vec<u8>,
+}
+
+/// Plaintext of a trampoline onion layer
+#[derive(Debug, Serialize, Deserialize)]
+struct TrampolinePayload {
+    /// Amount the next node must receive, or the payment amount at the recipient
+    amount_msat: u64,
+    
+    /// Expiry of the HTLC towards the next node, or the final expiry at the recipient
+    cltv_expiry: u32,
+    
+    /// Payment hash, checked by the recipient
+    payment_hash: [u8; 32],
+    
+    /// Next node and its layer; None at the recipient
+    next: Option<(String, TrampolineOnion)>,
+}
+
+/// A trampoline payment as built by the sender
+#[derive(Debug, Clone)]
+pub struct TrampolinePayment {
+    /// Route to the first trampoline (the outer onion)
+    pub route: PaymentPath,
+    
+    /// First trampoline, the last hop of `route`
+    pub first_trampoline: String,
+    
+    /// Inner onion handed to the first trampoline
+    pub trampoline_onion: TrampolineOnion,
+    
+    /// Amount the first trampoline must receive in millinovas
+    pub trampoline_amount_msat: u64,
+    
+    /// HTLC expiry at the first trampoline
+    pub trampoline_cltv_expiry: u32,
+    
+    /// Amount the recipient receives in millinovas
+    pub amount_msat: u64,
+    
+    /// Fees offered to trampolines in millinovas
+    pub trampoline_fees_msat: u64,
+}
+
+impl TrampolinePayment {
+    /// Total amount the sender pays, including all fees
+    pub fn total_amount_msat(&self) -> u64 {
+        self.route.total_amount_msat
+    }
+}
+
+/// Final destination of a trampoline payment
+#[derive(Debug, Clone, Copy)]
+pub struct TrampolineRecipient<'a> {
+    pub node_id: &'a str,
+    
+    /// Key the innermost layer is sealed to
+    pub kem_public_key: &'a [u8],
+    
+    pub payment_hash: [u8; 32],
+    
+    /// Amount the recipient receives in millinovas
+    pub amount_msat: u64,
+    
+    /// HTLC expiry at the recipient
+    pub final_cltv_expiry: u32,
+}
+
+/// Build a payment to `recipient` through `trampolines`, in order
+///
+/// `router` only needs to know the sender's surroundings: it is used to find
+/// the route to the first trampoline.
+pub fn build_trampoline_payment(
+    router: &Router,
+    kem: &dyn HopKem,
+    trampolines: &[TrampolineHop],
+    recipient: TrampolineRecipient<'_>,
+) -> Result<TrampolinePayment, TrampolineError> {
+    let TrampolineRecipient { node_id: recipient, kem_public_key: recipient_kem_public_key, payment_hash, amount_msat, final_cltv_expiry } = recipient;
+    if trampolines.is_empty() {
+        return Err(TrampolineError::NoTrampolines);
+    }
+    if trampolines.len() + 1 > MAX_ONION_HOPS {
+        return Err(OnionError::TooManyHops(trampolines.len() + 1).into());
+    }
+    
+    let mut onion = seal(kem, recipient_kem_public_key, &TrampolinePayload {
+        amount_msat,
+        cltv_expiry: final_cltv_expiry,
+        payment_hash,
+        next: None,
+    })?;
+    let mut next_node_id = recipient.to_string();
+    let mut next_amount = amount_msat;
+    let mut next_cltv = final_cltv_expiry;
+    
+    // Wrap from the last trampoline back to the first, adding each one's fee
+    for trampoline in trampolines.iter().rev() {
+        onion = seal(kem, &trampoline.kem_public_key, &TrampolinePayload {
+            amount_msat: next_amount,
+            cltv_expiry: next_cltv,
+            payment_hash,
+            next: Some((next_node_id, onion)),
+        })?;
+        next_node_id = trampoline.node_id.clone();
+        next_amount += trampoline.policy.fee_msat(next_amount);
+        next_cltv += trampoline.policy.cltv_expiry_delta as u32;
+    }
+    
+    let route = router.find_route(&next_node_id, next_amount, &[])?;
+    
+    Ok(TrampolinePayment {
+        route,
+        first_trampoline: next_node_id,
+        trampoline_onion: onion,
+        trampoline_amount_msat: next_amount,
+        trampoline_cltv_expiry: next_cltv,
+        amount_msat,
+        trampoline_fees_msat: next_amount - amount_msat,
+    })
+}
+
+/// What a node does with a trampoline onion it received
+#[derive(Debug, Clone)]
+pub enum TrampolineAction {
+    /// Relay the payment over `route` to the next node
+    Forward {
+        next_node_id: String,
+        
+        /// Route to the next node, found with our own router
+        route: PaymentPath,
+        
+        /// Amount the next node must receive
+        amount_msat: u64,
+        
+        /// HTLC expiry at the next node
+        cltv_expiry: u32,
+        
+        /// Layer for the next node
+        onion: TrampolineOnion,
+        
+        /// What we keep after paying for `route`
+        fee_earned_msat: u64,
+    },
+    
+    /// We are the recipient
+    Receive {
+        payment_hash: [u8; 32],
+        amount_msat: u64,
+    },
+}
+
+/// Trampoline side: peels layers and routes payments on
+pub struct TrampolineRelay {
+    kem_keypair: KemKeyPair,
+    kem: Box<dyn HopKem>,
+    policy: TrampolineFeePolicy,
+}
+
+impl TrampolineRelay {
+    /// Create a relay charging `policy`
+    pub fn new(kem_keypair: KemKeyPair, kem: Box<dyn HopKem>, policy: TrampolineFeePolicy) -> Self {
+        Self {
+            kem_keypair,
+            kem,
+            policy,
+        }
+    }
+    
+    /// Fee this relay charges
+    pub fn policy(&self) -> &TrampolineFeePolicy {
+        &self.policy
+    }
+    
+    /// Process an onion that arrived with an HTLC of `incoming_amount_msat` expiring at `incoming_cltv_expiry`
+    pub fn process(
+        &self,
+        router: &Router,
+        onion: &TrampolineOnion,
+        incoming_amount_msat: u64,
+        incoming_cltv_expiry: u32,
+    ) -> Result<TrampolineAction, TrampolineError> {
+        let plaintext = open_layer(
+            self.kem.as_ref(),
+            &self.kem_keypair.secret_key,
+            LAYER_DOMAIN,
+            &onion.kem_ciphertext,
+            &onion.payload,
+        )?;
+        let payload: TrampolinePayload = bincode::deserialize(&plaintext)?;
+        
+        let Some((next_node_id, next_onion)) = payload.next else {
+            if incoming_amount_msat < payload.amount_msat {
+                return Err(TrampolineError::AmountBelowExpected {
+                    expected: payload.amount_msat,
+                    received: incoming_amount_msat,
+                });
+            }
+            if incoming_cltv_expiry < payload.cltv_expiry {
+                return Err(TrampolineError::CltvExpiryTooSoon {
+                    required: payload.cltv_expiry,
+                    offered: incoming_cltv_expiry,
+                });
+            }
+            return Ok(TrampolineAction::Receive {
+                payment_hash: payload.payment_hash,
+                amount_msat: incoming_amount_msat,
+            });
+        };
+        
+        let offered = incoming_amount_msat.saturating_sub(payload.amount_msat);
+        let required = self.policy.fee_msat(payload.amount_msat);
+        if offered < required {
+            return Err(TrampolineError::FeeInsufficient { required, offered });
+        }
+        
+        let required_cltv = payload.cltv_expiry + self.policy.cltv_expiry_delta as u32;
+        if incoming_cltv_expiry < required_cltv {
+            return Err(TrampolineError::CltvExpiryTooSoon {
+                required: required_cltv,
+                offered: incoming_cltv_expiry,
+            });
+        }
+        
+        let route = router.find_route(&next_node_id, payload.amount_msat, &[])?;
+        if route.total_fee_msat > offered {
+            return Err(TrampolineError::FeeInsufficient {
+                required: route.total_fee_msat,
+                offered,
+            });
+        }
+        if payload.cltv_expiry + route.total_cltv_delta > incoming_cltv_expiry {
+            return Err(TrampolineError::CltvExpiryTooSoon {
+                required: payload.cltv_expiry + route.total_cltv_delta,
+                offered: incoming_cltv_expiry,
+            });
+        }
+        
+        Ok(TrampolineAction::Forward {
+            next_node_id,
+            fee_earned_msat: offered - route.total_fee_msat,
+            route,
+            amount_msat: payload.amount_msat,
+            cltv_expiry: payload.cltv_expiry,
+            onion: next_onion,
+        })
+    }
+}
+
+fn seal(kem: &dyn HopKem, public_key: &[u8], payload: &TrampolinePayload) -> Result<TrampolineOnion, TrampolineError> {
+    let (kem_ciphertext, payload) = seal_layer(kem, public_key, LAYER_DOMAIN, bincode::serialize(payload)?)?;
+    Ok(TrampolineOnion { kem_ciphertext, payload })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightning::channel::ChannelId;
+    use crate::lightning::router::{ChannelInfo, NodeId};
+    use crate::lightning::test_utils::TestKem;
+    use crate::lightning::wire::{set_feature_bit, FEATURE_TRAMPOLINE_ROUTING};
+    
+    fn channel(index: u8, source: &str, destination: &str) -> ChannelInfo {
+        ChannelInfo {
+            channel_id: ChannelId::from_bytes([index; 32]),
+            source: NodeId::new(source.to_string()),
+            destination: NodeId::new(destination.to_string()),
+            capacity: 10_000_000,
+            base_fee_msat: 1_000,
+            fee_rate_millionths: 100,
+            cltv_expiry_delta: 40,
+            is_active: true,
+            last_update: 0,
+        }
+    }
+    
+    /// Router seeing the graph from `local`
+    fn router(local: &str, channels: &[ChannelInfo]) -> Router {
+        let mut router = Router::new();
+        router.set_local_node(NodeId::new(local.to_string()));
+        for channel in channels {
+            router.update_channel(channel.clone(), false);
+        }
+        router
+    }
+    
+    fn relay(name: &str, policy: TrampolineFeePolicy) -> TrampolineRelay {
+        let keypair = KemKeyPair {
+            public_key: name.as_bytes().to_vec(),
+            secret_key: name.as_bytes().to_vec(),
+        };
+        TrampolineRelay::new(keypair, Box::new(TestKem), policy)
+    }
+    
+    fn trampoline_init(name: &str) -> InitPayload {
+        let mut local_features = vec![0, 0];
+        set_feature_bit(&mut local_features, FEATURE_TRAMPOLINE_ROUTING);
+        InitPayload {
+            version: 1,
+            local_features,
+            global_features: vec![0, 0],
+            node_id: name.to_string(),
+        }
+    }
+    
+    /// mobile - t1 - a - b - t2 - c - recipient; the mobile client only knows its channel to t1
+    fn recipient(payment_hash: [u8; 32]) -> TrampolineRecipient<'static> {
+        TrampolineRecipient {
+            node_id: "recipient",
+            kem_public_key: b"recipient",
+            payment_hash,
+            amount_msat: 5_000_000,
+            final_cltv_expiry: 1_000,
+        }
+    }
+    
+    fn graph() -> Vec<ChannelInfo> {
+        vec![
+            channel(1, "mobile", "t1"),
+            channel(2, "t1", "a"),
+            channel(3, "a", "b"),
+            channel(4, "b", "t2"),
+            channel(5, "t2", "c"),
+            channel(6, "c", "recipient"),
+        ]
+    }
+    
+    #[test]
+    fn test_trampoline_payment_across_two_trampolines() {
+        let graph = graph();
+        let policy = TrampolineFeePolicy::default();
+        let trampolines = vec![
+            TrampolineHop::from_init(&trampoline_init("t1"), b"t1".to_vec(), policy).unwrap(),
+            TrampolineHop::from_init(&trampoline_init("t2"), b"t2".to_vec(), policy).unwrap(),
+        ];
+        
+        let mobile = router("mobile", &graph[..1]);
+        let payment = build_trampoline_payment(&mobile, &TestKem, &trampolines, recipient([7u8; 32])).unwrap();
+        
+        // The sender only routes to t1 and pays both trampoline fees up front
+        assert_eq!(payment.first_trampoline, "t1");
+        assert_eq!(payment.route.hops.len(), 1);
+        let t2_amount = 5_000_000 + policy.fee_msat(5_000_000);
+        assert_eq!(payment.trampoline_amount_msat, t2_amount + policy.fee_msat(t2_amount));
+        assert_eq!(payment.trampoline_fees_msat, payment.trampoline_amount_msat - 5_000_000);
+        assert_eq!(payment.trampoline_cltv_expiry, 1_000 + 2 * 288);
+        assert!(payment.total_amount_msat() > payment.trampoline_amount_msat);
+        
+        // t1 routes to t2 over a and b
+        let t1 = relay("t1", policy);
+        let action = t1.process(&router("t1", &graph), &payment.trampoline_onion, payment.trampoline_amount_msat, payment.trampoline_cltv_expiry).unwrap();
+        let (onion, amount, cltv) = match action {
+            TrampolineAction::Forward { next_node_id, route, amount_msat, cltv_expiry, onion, fee_earned_msat } => {
+                assert_eq!(next_node_id, "t2");
+                let nodes: Vec<&str> = route.hops.iter().map(|hop| hop.node_id.as_str()).collect();
+                assert_eq!(nodes, vec!["a", "b", "t2"]);
+                assert_eq!(amount_msat, t2_amount);
+                assert_eq!(fee_earned_msat, payment.trampoline_amount_msat - t2_amount - route.total_fee_msat);
+                (onion, amount_msat, cltv_expiry)
+            }
+            other => panic!("unexpected {:?}", other),
+        };
+        
+        // t2 routes to the recipient over c
+        let t2 = relay("t2", policy);
+        let (onion, amount, cltv) = match t2.process(&router("t2", &graph), &onion, amount, cltv).unwrap() {
+            TrampolineAction::Forward { next_node_id, route, amount_msat, cltv_expiry, onion, .. } => {
+                assert_eq!(next_node_id, "recipient");
+                assert_eq!(route.hops.len(), 2);
+                assert_eq!(amount_msat, 5_000_000);
+                (onion, amount_msat, cltv_expiry)
+            }
+            other => panic!("unexpected {:?}", other),
+        };
+        
+        let recipient = relay("recipient", policy);
+        match recipient.process(&router("recipient", &graph), &onion, amount, cltv).unwrap() {
+            TrampolineAction::Receive { payment_hash, amount_msat } => {
+                assert_eq!(payment_hash, [7u8; 32]);
+                assert_eq!(amount_msat, 5_000_000);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        
+        // Layers only open for the node they were sealed to
+        assert!(t2.process(&router("t2", &graph), &payment.trampoline_onion, payment.trampoline_amount_msat, payment.trampoline_cltv_expiry).is_err());
+    }
+    
+    #[test]
+    fn test_trampoline_rejects_short_fee_and_expiry() {
+        let graph = graph();
+        let advertised = TrampolineFeePolicy {
+            base_fee_msat: 100,
+            fee_rate_millionths: 0,
+            cltv_expiry_delta: 144,
+        };
+        let trampolines = vec![TrampolineHop::from_init(&trampoline_init("t1"), b"t1".to_vec(), advertised).unwrap()];
+        let payment = build_trampoline_payment(&router("mobile", &graph[..1]), &TestKem, &trampolines, recipient([1u8; 32])).unwrap();
+        let t1_router = router("t1", &graph);
+        
+        // t1 charges more than the sender offered
+        let t1 = relay("t1", TrampolineFeePolicy::default());
+        assert!(matches!(
+            t1.process(&t1_router, &payment.trampoline_onion, payment.trampoline_amount_msat, payment.trampoline_cltv_expiry),
+            Err(TrampolineError::FeeInsufficient { offered: 100, .. })
+        ));
+        
+        // The fee t1 asks for is paid, but does not cover the five-hop route to the recipient
+        let t1 = relay("t1", advertised);
+        assert!(matches!(
+            t1.process(&t1_router, &payment.trampoline_onion, payment.trampoline_amount_msat, payment.trampoline_cltv_expiry),
+            Err(TrampolineError::FeeInsufficient { offered: 100, .. })
+        ));
+        
+        // An HTLC expiring too early is refused
+        assert!(matches!(
+            t1.process(&t1_router, &payment.trampoline_onion, payment.trampoline_amount_msat, 1_100),
+            Err(TrampolineError::CltvExpiryTooSoon { .. })
+        ));
+    }
+    
+    #[test]
+    fn test_trampoline_feature_bit() {
+        let mut init = trampoline_init("t1");
+        assert!(init.supports_trampoline());
+        assert_eq!(init.local_features.len(), 8);
+        
+        init.local_features = vec![0, 0];
+        assert!(!init.supports_trampoline());
+        assert!(matches!(
+            TrampolineHop::from_init(&init, b"t1".to_vec(), TrampolineFeePolicy::default()),
+            Err(TrampolineError::NotTrampolineCapable(_))
+        ));
+        
+        // The required bit also counts
+        set_feature_bit(&mut init.local_features, FEATURE_TRAMPOLINE_ROUTING - 1);
+        assert!(init.supports_trampoline());
+    }
+}
@@ -7,6 +7,8 @@ use crate::crypto::quantum::{QuantumKeyPair, QuantumScheme};
 use crate::lightning::payment::{PaymentHash, PaymentPreimage};
 use crate::lightning::invoice::{Invoice, InvoiceError};
 use crate::lightning::channel::ChannelId;
+use crate::script::ScriptBuilder;
+use crate::types::transaction::OutPoint;
 
 use std::collections::HashMap;
 use thiserror::Error;
@@ -214,6 +216,16 @@ pub struct Payment {
     channel_id: Option<ChannelId>,
 }
 
+/// Confirmed on-chain output owned by the wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletUtxo {
+    /// Outpoint of the output
+    pub outpoint: OutPoint,
+    
+    /// Value in novas
+    pub amount: u64,
+}
+
 /// Main Lightning wallet implementation
 pub struct LightningWallet {
     /// Key manager
@@ -233,6 +245,9 @@ pub struct LightningWallet {
     
     /// Payment preimages
     preimages: HashMap<PaymentHash, PaymentPreimage>,
+    
+    /// Spendable on-chain outputs
+    utxos: Vec<WalletUtxo>,
 }
 
 impl LightningWallet {
@@ -247,6 +262,7 @@ impl LightningWallet {
             invoices: HashMap::new(),
             payments: HashMap::new(),
             preimages: HashMap::new(),
+            utxos: Vec::new(),
         })
     }
     
@@ -272,6 +288,7 @@ impl LightningWallet {
             invoices: HashMap::new(),
             payments: HashMap::new(),
             preimages: HashMap::new(),
+            utxos: Vec::new(),
         }
     }
     
@@ -301,6 +318,48 @@ impl LightningWallet {
         self.on_chain_balance = balance;
     }
     
+    /// Track an on-chain output paying to the wallet's on-chain key
+    pub fn add_utxo(&mut self, utxo: WalletUtxo) {
+        self.on_chain_balance += utxo.amount;
+        self.utxos.push(utxo);
+    }
+    
+    /// Spendable on-chain outputs
+    pub fn utxos(&self) -> &[WalletUtxo] {
+        &self.utxos
+    }
+    
+    /// Stop tracking outputs spent by a broadcast transaction
+    pub fn spend_utxos(&mut self, outpoints: &[OutPoint]) {
+        let spent: u64 = self.utxos.iter()
+            .filter(|utxo| outpoints.contains(&utxo.outpoint))
+            .map(|utxo| utxo.amount)
+            .sum();
+        self.utxos.retain(|utxo| !outpoints.contains(&utxo.outpoint));
+        self.on_chain_balance = self.on_chain_balance.saturating_sub(spent);
+    }
+    
+    /// Key controlling the wallet's on-chain outputs and our anchors
+    pub fn onchain_key(&self) -> Result<secp256k1::SecretKey, WalletError> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.key_manager.node_private_key());
+        hasher.update(b"onchain");
+        
+        secp256k1::SecretKey::from_slice(&hasher.finalize())
+            .map_err(|e| WalletError::KeyError(e.to_string()))
+    }
+    
+    /// Compressed public key of the on-chain key
+    pub fn onchain_pubkey(&self) -> Result<[u8; 33], WalletError> {
+        let secp = secp256k1::Secp256k1::new();
+        Ok(secp256k1::PublicKey::from_secret_key(&secp, &self.onchain_key()?).serialize())
+    }
+    
+    /// P2PKH script change is returned to
+    pub fn change_script(&self) -> Result<Vec<u8>, WalletError> {
+        Ok(ScriptBuilder::pay_to_pubkey_hash(&ScriptBuilder::hash_pubkey(&self.onchain_pubkey()?)))
+    }
+    
     /// Update a channel balance
     pub fn update_channel_balance(&mut self, channel_id: ChannelId, balance: u64) {
         self.channel_balances.insert(channel_id, balance);
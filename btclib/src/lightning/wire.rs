@@ -5,8 +5,12 @@
 
 use crate::lightning::channel::{ChannelId, ChannelState};
 use crate::lightning::payment::{PaymentHash, PaymentPreimage};
+use crate::lightning::onion_message::OnionMessagePacket;
+use crate::lightning::router::NodeEnvironmentalData;
+use crate::crypto::quantum::QuantumScheme;
 use thiserror::Error;
 use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
 use std::time::{SystemTime, UNIX_EPOCH};
 use rand::{thread_rng, Rng, RngCore};
 use sha2::{Sha256, Digest};
@@ -101,6 +105,51 @@ pub enum MessageType {
     
     /// Request gossip messages
     GossipTimestampFilter,
+    
+    /// Propose resizing a channel with a new funding transaction
+    SpliceInit,
+    
+    /// Accept a splice proposal
+    SpliceAck,
+    
+    /// Splice transaction has confirmed and replaces the old funding output
+    SpliceLocked,
+    
+    /// Open a dual-funded channel
+    OpenChannel2,
+    
+    /// Accept a dual-funded channel, with the acceptor's contribution
+    AcceptChannel2,
+    
+    /// Add an input to the transaction under construction
+    TxAddInput,
+    
+    /// Add an output to the transaction under construction
+    TxAddOutput,
+    
+    /// Remove a previously added input
+    TxRemoveInput,
+    
+    /// Remove a previously added output
+    TxRemoveOutput,
+    
+    /// Sender has no further inputs or outputs to add
+    TxComplete,
+    
+    /// Witnesses for the sender's inputs
+    TxSignatures,
+    
+    /// Propose replacing the funding transaction at a higher fee rate
+    TxInitRbf,
+    
+    /// Accept a funding transaction replacement
+    TxAckRbf,
+    
+    /// Abort interactive transaction construction
+    TxAbort,
+    
+    /// Onion message relayed without an HTLC
+    OnionMessage,
 }
 
 /// Main message structure for Lightning Network
@@ -179,6 +228,12 @@ impl Message {
             .map_err(|e| LightningError::DeserializationError(e.to_string()))
     }
     
+    /// Decode the payload as the given message body
+    pub fn decode_payload<T: DeserializeOwned>(&self) -> Result<T, LightningError> {
+        bincode::deserialize(&self.payload)
+            .map_err(|e| LightningError::DeserializationError(e.to_string()))
+    }
+    
     /// Get hash of the message
     pub fn hash(&self) -> [u8; 32] {
         let serialized = self.serialize().unwrap_or_default();
@@ -209,6 +264,42 @@ pub struct InitPayload {
     pub node_id: String,
 }
 
+/// Feature bit advertising that a node relays trampoline payments
+///
+/// Feature bits come in pairs: the even bit means required, the odd bit
+/// optional. This is the optional bit.
+pub const FEATURE_TRAMPOLINE_ROUTING: usize = 57;
+
+impl InitPayload {
+    /// Check whether a local feature is set, as either its required or optional bit
+    pub fn has_feature(&self, bit: usize) -> bool {
+        feature_bit_set(&self.local_features, bit & !1) || feature_bit_set(&self.local_features, bit | 1)
+    }
+    
+    /// Check whether the node relays trampoline payments
+    pub fn supports_trampoline(&self) -> bool {
+        self.has_feature(FEATURE_TRAMPOLINE_ROUTING)
+    }
+}
+
+/// Check a bit of a big-endian feature vector, where bit 0 is the lowest bit of the last byte
+pub fn feature_bit_set(features: &[u8], bit: usize) -> bool {
+    let index = bit / 8;
+    index < features.len() && features[features.len() - 1 - index] & (1 << (bit % 8)) != 0
+}
+
+/// Set a bit of a big-endian feature vector, growing it as needed
+pub fn set_feature_bit(features: &mut Vec<u8>, bit: usize) {
+    let index = bit / 8;
+    if index >= features.len() {
+        let mut grown = vec![0u8; index + 1 - features.len()];
+        grown.extend_from_slice(features);
+        *features = grown;
+    }
+    let len = features.len();
+    features[len - 1 - index] |= 1 << (bit % 8);
+}
+
 /// Error message payload
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorPayload {
@@ -328,6 +419,236 @@ pub struct HtlcFailPayload {
     pub reason: Vec<u8>,
 }
 
+/// Splice init message payload
+///
+/// Positive contributions splice funds into the channel, negative
+/// contributions splice them out to outputs of the splice transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpliceInitPayload {
+    /// Channel ID
+    pub channel_id: ChannelId,
+    
+    /// Change to the initiator's balance in novas
+    pub funding_contribution_novas: i64,
+    
+    /// Fee rate for the splice transaction
+    pub funding_feerate_per_kw: u32,
+    
+    /// Lock time of the splice transaction
+    pub locktime: u32,
+    
+    /// Funding public key for the new funding output
+    pub funding_pubkey: Vec<u8>,
+}
+
+/// Splice ack message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpliceAckPayload {
+    /// Channel ID
+    pub channel_id: ChannelId,
+    
+    /// Change to the acceptor's balance in novas
+    pub funding_contribution_novas: i64,
+    
+    /// Funding public key for the new funding output
+    pub funding_pubkey: Vec<u8>,
+}
+
+/// Splice locked message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpliceLockedPayload {
+    /// Channel ID
+    pub channel_id: ChannelId,
+    
+    /// Transaction ID of the confirmed splice transaction
+    pub splice_txid: [u8; 32],
+}
+
+/// Node announcement message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeAnnouncementPayload {
+    /// Node ID
+    pub node_id: String,
+    
+    /// Human-readable alias
+    pub alias: String,
+    
+    /// Announcement time; later announcements replace earlier ones
+    pub timestamp: u64,
+    
+    /// Features the node supports
+    pub features: Vec<u8>,
+    
+    /// Environmental data for carbon-aware routing
+    pub environmental: Option<NodeEnvironmentalData>,
+}
+
+/// Open dual-funded channel message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenChannel2Payload {
+    /// Temporary channel ID
+    pub temporary_channel_id: ChannelId,
+    
+    /// Fee rate for the funding transaction
+    pub funding_feerate_per_kw: u32,
+    
+    /// Lock time of the funding transaction
+    pub locktime: u32,
+    
+    /// Initiator's contribution in novas
+    pub funding_novas: u64,
+    
+    /// To-self delay in blocks
+    pub to_self_delay: u16,
+    
+    /// Maximum accepted HTLCs
+    pub max_accepted_htlcs: u16,
+    
+    /// Quantum public key signing the initiator's funding inputs
+    pub funding_pubkey: Vec<u8>,
+    
+    /// Security level of the funding key
+    pub funding_security_level: u8,
+}
+
+/// Accept dual-funded channel message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptChannel2Payload {
+    /// Temporary channel ID
+    pub temporary_channel_id: ChannelId,
+    
+    /// Acceptor's contribution in novas
+    pub funding_novas: u64,
+    
+    /// Quantum public key signing the acceptor's funding inputs
+    pub funding_pubkey: Vec<u8>,
+    
+    /// Security level of the funding key
+    pub funding_security_level: u8,
+}
+
+/// Add input message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxAddInputPayload {
+    /// Channel ID
+    pub channel_id: ChannelId,
+    
+    /// Serial ID, even for the initiator and odd for the acceptor
+    pub serial_id: u64,
+    
+    /// Transaction containing the spent output
+    pub prev_txid: [u8; 32],
+    
+    /// Index of the spent output
+    pub prev_vout: u32,
+    
+    /// Value of the spent output in novas
+    pub value_novas: u64,
+    
+    /// Sequence number of the input
+    pub sequence: u32,
+}
+
+/// Add output message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxAddOutputPayload {
+    /// Channel ID
+    pub channel_id: ChannelId,
+    
+    /// Serial ID, even for the initiator and odd for the acceptor
+    pub serial_id: u64,
+    
+    /// Output amount in novas
+    pub amount_novas: u64,
+    
+    /// Output script
+    pub script: Vec<u8>,
+}
+
+/// Remove input or output message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxRemovePayload {
+    /// Channel ID
+    pub channel_id: ChannelId,
+    
+    /// Serial ID of the removed input or output
+    pub serial_id: u64,
+}
+
+/// Quantum signature over a funding input
+///
+/// Witnesses travel next to the transaction rather than inside it, so the
+/// funding transaction ID is fixed before either side signs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuantumWitness {
+    /// Serial ID of the signed input
+    pub serial_id: u64,
+    
+    /// Signature scheme
+    pub scheme: QuantumScheme,
+    
+    /// Security level of the signature
+    pub security_level: u8,
+    
+    /// Public key of the signer
+    pub public_key: Vec<u8>,
+    
+    /// Signature over the transaction ID and serial ID
+    pub signature: Vec<u8>,
+}
+
+/// Transaction signatures message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxSignaturesPayload {
+    /// Channel ID
+    pub channel_id: ChannelId,
+    
+    /// ID of the signed transaction
+    pub txid: [u8; 32],
+    
+    /// Witnesses for the sender's inputs
+    pub witnesses: Vec<QuantumWitness>,
+}
+
+/// Funding transaction replacement message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxInitRbfPayload {
+    /// Channel ID
+    pub channel_id: ChannelId,
+    
+    /// Lock time of the replacement
+    pub locktime: u32,
+    
+    /// Fee rate of the replacement
+    pub funding_feerate_per_kw: u32,
+    
+    /// Initiator's contribution in novas
+    pub funding_novas: u64,
+}
+
+/// Funding transaction replacement ack message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxAckRbfPayload {
+    /// Channel ID
+    pub channel_id: ChannelId,
+    
+    /// Acceptor's contribution in novas
+    pub funding_novas: u64,
+}
+
+/// Revoke and ack message payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokeAndAckPayload {
+    /// Channel ID
+    pub channel_id: ChannelId,
+    
+    /// Secret revoking the previous commitment
+    pub per_commitment_secret: [u8; 32],
+    
+    /// Commitment point for the next commitment
+    pub next_per_commitment_point: Vec<u8>,
+}
+
 /// Message factory for creating Lightning Network messages
 pub struct MessageFactory {
     /// Local node ID
@@ -335,6 +656,9 @@ pub struct MessageFactory {
     
     /// Private key for signing
     private_key: Vec<u8>,
+    
+    /// Local features advertised in init messages
+    local_features: Vec<u8>,
 }
 
 impl MessageFactory {
@@ -343,14 +667,21 @@ impl MessageFactory {
         Self {
             local_node_id,
             private_key,
+            local_features: vec![0, 0], // No special features
         }
     }
     
+    /// Advertise an additional local feature bit in init messages
+    pub fn with_feature(mut self, bit: usize) -> Self {
+        set_feature_bit(&mut self.local_features, bit);
+        self
+    }
+    
     /// Create an init message
     pub fn create_init(&self, version: u32) -> Result<Message, LightningError> {
         let payload = InitPayload {
             version,
-            local_features: vec![0, 0], // No special features
+            local_features: self.local_features.clone(),
             global_features: vec![0, 0], // No special features
             node_id: self.local_node_id.clone(),
         };
@@ -364,6 +695,30 @@ impl MessageFactory {
         Ok(message)
     }
     
+    /// Create a node announcement
+    pub fn create_node_announcement(
+        &self,
+        alias: &str,
+        timestamp: u64,
+        environmental: Option<NodeEnvironmentalData>,
+    ) -> Result<Message, LightningError> {
+        let payload = NodeAnnouncementPayload {
+            node_id: self.local_node_id.clone(),
+            alias: alias.to_string(),
+            timestamp,
+            features: self.local_features.clone(),
+            environmental,
+        };
+        
+        let serialized = bincode::serialize(&payload)
+            .map_err(|e| LightningError::SerializationError(e.to_string()))?;
+            
+        let mut message = Message::new(MessageType::NodeAnnouncement, None, serialized);
+        message.sign(&self.private_key)?;
+        
+        Ok(message)
+    }
+    
     /// Create an error message
     pub fn create_error(&self, channel_id: Option<ChannelId>, code: u16, message: &str) -> Result<Message, LightningError> {
         let payload = ErrorPayload {
@@ -519,4 +874,172 @@ impl MessageFactory {
         
         Ok(message)
     }
-} 
\ No newline at end of file
+    
+    /// Create a splice init message
+    pub fn create_splice_init(
+        &self,
+        channel_id: ChannelId,
+        funding_contribution_novas: i64,
+        funding_feerate_per_kw: u32,
+        locktime: u32,
+    ) -> Result<Message, LightningError> {
+        let payload = SpliceInitPayload {
+            channel_id: channel_id.clone(),
+            funding_contribution_novas,
+            funding_feerate_per_kw,
+            locktime,
+            funding_pubkey: vec![0; 33], // Dummy public key
+        };
+        
+        let serialized = bincode::serialize(&payload)
+            .map_err(|e| LightningError::SerializationError(e.to_string()))?;
+            
+        let mut message = Message::new(MessageType::SpliceInit, Some(channel_id), serialized);
+        message.sign(&self.private_key)?;
+        
+        Ok(message)
+    }
+    
+    /// Create a splice ack message in response to a splice init
+    pub fn create_splice_ack(
+        &self,
+        splice_init: &Message,
+        funding_contribution_novas: i64,
+    ) -> Result<Message, LightningError> {
+        if splice_init.msg_type != MessageType::SpliceInit {
+            return Err(LightningError::ProtocolError(
+                format!("Expected splice init, got {:?}", splice_init.msg_type)
+            ));
+        }
+        
+        let init: SpliceInitPayload = splice_init.decode_payload()?;
+        
+        let payload = SpliceAckPayload {
+            channel_id: init.channel_id.clone(),
+            funding_contribution_novas,
+            funding_pubkey: vec![0; 33], // Dummy public key
+        };
+        
+        let serialized = bincode::serialize(&payload)
+            .map_err(|e| LightningError::SerializationError(e.to_string()))?;
+            
+        let mut message = Message::new(MessageType::SpliceAck, Some(init.channel_id), serialized);
+        message.sign(&self.private_key)?;
+        
+        Ok(message)
+    }
+    
+    /// Create a splice locked message once the splice transaction confirms
+    pub fn create_splice_locked(
+        &self,
+        channel_id: ChannelId,
+        splice_txid: [u8; 32],
+    ) -> Result<Message, LightningError> {
+        let payload = SpliceLockedPayload {
+            channel_id: channel_id.clone(),
+            splice_txid,
+        };
+        
+        let serialized = bincode::serialize(&payload)
+            .map_err(|e| LightningError::SerializationError(e.to_string()))?;
+            
+        let mut message = Message::new(MessageType::SpliceLocked, Some(channel_id), serialized);
+        message.sign(&self.private_key)?;
+        
+        Ok(message)
+    }
+    
+    /// Create an open dual-funded channel message
+    pub fn create_open_channel2(&self, payload: &OpenChannel2Payload) -> Result<Message, LightningError> {
+        self.create_channel_message(MessageType::OpenChannel2, &payload.temporary_channel_id, payload)
+    }
+    
+    /// Create an accept dual-funded channel message
+    pub fn create_accept_channel2(&self, payload: &AcceptChannel2Payload) -> Result<Message, LightningError> {
+        self.create_channel_message(MessageType::AcceptChannel2, &payload.temporary_channel_id, payload)
+    }
+    
+    /// Create an add input message
+    pub fn create_tx_add_input(&self, payload: &TxAddInputPayload) -> Result<Message, LightningError> {
+        self.create_channel_message(MessageType::TxAddInput, &payload.channel_id, payload)
+    }
+    
+    /// Create an add output message
+    pub fn create_tx_add_output(&self, payload: &TxAddOutputPayload) -> Result<Message, LightningError> {
+        self.create_channel_message(MessageType::TxAddOutput, &payload.channel_id, payload)
+    }
+    
+    /// Create a remove input message
+    pub fn create_tx_remove_input(&self, channel_id: ChannelId, serial_id: u64) -> Result<Message, LightningError> {
+        let payload = TxRemovePayload { channel_id: channel_id.clone(), serial_id };
+        self.create_channel_message(MessageType::TxRemoveInput, &channel_id, &payload)
+    }
+    
+    /// Create a remove output message
+    pub fn create_tx_remove_output(&self, channel_id: ChannelId, serial_id: u64) -> Result<Message, LightningError> {
+        let payload = TxRemovePayload { channel_id: channel_id.clone(), serial_id };
+        self.create_channel_message(MessageType::TxRemoveOutput, &channel_id, &payload)
+    }
+    
+    /// Create a transaction complete message
+    pub fn create_tx_complete(&self, channel_id: ChannelId) -> Result<Message, LightningError> {
+        self.create_channel_message(MessageType::TxComplete, &channel_id, &channel_id)
+    }
+    
+    /// Create a transaction signatures message
+    pub fn create_tx_signatures(&self, payload: &TxSignaturesPayload) -> Result<Message, LightningError> {
+        self.create_channel_message(MessageType::TxSignatures, &payload.channel_id, payload)
+    }
+    
+    /// Create a funding transaction replacement message
+    pub fn create_tx_init_rbf(&self, payload: &TxInitRbfPayload) -> Result<Message, LightningError> {
+        self.create_channel_message(MessageType::TxInitRbf, &payload.channel_id, payload)
+    }
+    
+    /// Create a funding transaction replacement ack message
+    pub fn create_tx_ack_rbf(&self, payload: &TxAckRbfPayload) -> Result<Message, LightningError> {
+        self.create_channel_message(MessageType::TxAckRbf, &payload.channel_id, payload)
+    }
+    
+    /// Create a revoke and ack message
+    pub fn create_revoke_and_ack(&self, payload: &RevokeAndAckPayload) -> Result<Message, LightningError> {
+        self.create_channel_message(MessageType::RevokeAndAck, &payload.channel_id, payload)
+    }
+    
+    /// Create an abort message for interactive transaction construction
+    pub fn create_tx_abort(&self, channel_id: ChannelId, reason: &str) -> Result<Message, LightningError> {
+        let payload = ErrorPayload {
+            code: 0,
+            message: reason.to_string(),
+            data: None,
+        };
+        self.create_channel_message(MessageType::TxAbort, &channel_id, &payload)
+    }
+    
+    /// Create an onion message for the next node on its route
+    pub fn create_onion_message(&self, packet: &OnionMessagePacket) -> Result<Message, LightningError> {
+        let serialized = bincode::serialize(packet)
+            .map_err(|e| LightningError::SerializationError(e.to_string()))?;
+            
+        let mut message = Message::new(MessageType::OnionMessage, None, serialized);
+        message.sign(&self.private_key)?;
+        
+        Ok(message)
+    }
+    
+    /// Serialize a channel-scoped payload into a signed message
+    fn create_channel_message<T: Serialize>(
+        &self,
+        msg_type: MessageType,
+        channel_id: &ChannelId,
+        payload: &T,
+    ) -> Result<Message, LightningError> {
+        let serialized = bincode::serialize(payload)
+            .map_err(|e| LightningError::SerializationError(e.to_string()))?;
+            
+        let mut message = Message::new(msg_type, Some(channel_id.clone()), serialized);
+        message.sign(&self.private_key)?;
+        
+        Ok(message)
+    }
+}
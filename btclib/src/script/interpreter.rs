@@ -7,6 +7,7 @@ use crate::script::opcodes::Opcode;
 use crate::script::ScriptVerificationError;
 use sha2::{Sha256, Digest};
 use ripemd::{Ripemd160, Digest as RipemdDigest};
+use sha3::Sha3_512;
 use std::cmp;
 
 /// Maximum script size in bytes
@@ -21,6 +22,21 @@ pub const MAX_STACK_SIZE: usize = 1000;
 /// Maximum script element size
 pub const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
 
+/// Lock times below this are block heights, at or above it timestamps (BIP65)
+pub const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+/// Input sequence that disables lock time for the input
+pub const SEQUENCE_FINAL: u32 = 0xFFFF_FFFF;
+
+/// Sequence bit that disables relative lock time (BIP68)
+pub const SEQUENCE_LOCKTIME_DISABLE_FLAG: u32 = 1 << 31;
+
+/// Sequence bit selecting time-based rather than height-based relative lock time
+pub const SEQUENCE_LOCKTIME_TYPE_FLAG: u32 = 1 << 22;
+
+/// Bits of the sequence holding the relative lock time value
+pub const SEQUENCE_LOCKTIME_MASK: u32 = 0x0000_FFFF;
+
 /// Script execution errors
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ScriptError {
@@ -58,6 +74,8 @@ pub enum ScriptError {
     InvalidPubkeyEncoding,
     /// Element too large
     ElementTooLarge,
+    /// Locktime or sequence requirement not met
+    LockTimeFailed,
 }
 
 /// Stack for script execution
@@ -151,6 +169,8 @@ pub struct ScriptInterpreter {
     cond_stack: Vec<bool>,
     /// Operation count
     op_count: usize,
+    /// Whether OP_ELSE after a false condition switches to its branch
+    conditional_else: bool,
 }
 
 impl ScriptInterpreter {
@@ -161,9 +181,19 @@ impl ScriptInterpreter {
             alt_stack: ExecutionStack::new(),
             cond_stack: Vec::new(),
             op_count: 0,
+            conditional_else: true,
         }
     }
     
+    /// Choose whether OP_ELSE after a false condition switches to its branch
+    ///
+    /// Before `CONDITIONAL_ELSE_ACTIVATION_HEIGHT` consensus kept such
+    /// branches disabled.
+    pub fn with_conditional_else(mut self, enabled: bool) -> Self {
+        self.conditional_else = enabled;
+        self
+    }
+    
     /// Execute a script
     pub fn execute(
         &mut self,
@@ -290,6 +320,11 @@ impl ScriptInterpreter {
                         if self.cond_stack.is_empty() {
                             return Err(ScriptError::UnbalancedConditional);
                         }
+                        // Enclosing false branches keep this one disabled
+                        if self.conditional_else {
+                            let last = self.cond_stack.len() - 1;
+                            self.cond_stack[last] = !self.cond_stack[last];
+                        }
                     },
                     Opcode::OP_ENDIF => {
                         if self.cond_stack.is_empty() {
@@ -511,6 +546,31 @@ impl ScriptInterpreter {
                 Ok(())
             },
             
+            Opcode::OP_SHA3_512 => {
+                let data = self.stack.pop()?;
+                let mut hasher = Sha3_512::new();
+                hasher.update(&data);
+                let result = hasher.finalize();
+                self.stack.push(result.to_vec())?;
+                Ok(())
+            },
+            
+            // Locktime
+            Opcode::OP_CHECKLOCKTIMEVERIFY => {
+                let lock_time = decode_script_number(self.stack.peek()?)?;
+                if lock_time < 0 || !checker.check_lock_time(lock_time as u32) {
+                    return Err(ScriptError::LockTimeFailed);
+                }
+                Ok(())
+            },
+            Opcode::OP_CHECKSEQUENCEVERIFY => {
+                let sequence = decode_script_number(self.stack.peek()?)?;
+                if sequence < 0 || !checker.check_sequence(sequence as u32) {
+                    return Err(ScriptError::LockTimeFailed);
+                }
+                Ok(())
+            },
+            
             // Comparison
             Opcode::OP_EQUAL => {
                 if self.stack.len() < 2 {
@@ -588,10 +648,76 @@ impl ScriptInterpreter {
     }
 }
 
+/// Decode a minimally encoded script number of up to 5 bytes
+fn decode_script_number(bytes: &[u8]) -> Result<i64, ScriptError> {
+    if bytes.len() > 5 {
+        return Err(ScriptError::InvalidNumber);
+    }
+    if bytes.is_empty() {
+        return Ok(0);
+    }
+    
+    let mut result: i64 = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        result |= (byte as i64) << (8 * i);
+    }
+    
+    // The high bit of the last byte is the sign
+    let sign_bit = 0x80i64 << (8 * (bytes.len() - 1));
+    if result & sign_bit != 0 {
+        result = -(result & !sign_bit);
+    }
+    
+    Ok(result)
+}
+
+/// Whether a transaction satisfies an OP_CHECKLOCKTIMEVERIFY argument (BIP65)
+///
+/// The lock time types (height or timestamp) must match, the transaction's
+/// lock time must have reached `required`, and the input must not be final,
+/// since a final input disables the transaction lock time.
+pub fn lock_time_satisfied(required: u32, tx_lock_time: u32, input_sequence: u32) -> bool {
+    let same_type = (required < LOCKTIME_THRESHOLD) == (tx_lock_time < LOCKTIME_THRESHOLD);
+    same_type && required <= tx_lock_time && input_sequence != SEQUENCE_FINAL
+}
+
+/// Whether an input satisfies an OP_CHECKSEQUENCEVERIFY argument (BIP112)
+///
+/// An argument with the disable flag set passes as a no-op. Otherwise the
+/// transaction must be version 2 or later, the input's relative lock time
+/// must be enabled and of the same type, and its value must have reached
+/// `required`.
+pub fn sequence_satisfied(required: u32, tx_version: u32, input_sequence: u32) -> bool {
+    if required & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+        return true;
+    }
+    if tx_version < 2 || input_sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+        return false;
+    }
+    
+    let mask = SEQUENCE_LOCKTIME_TYPE_FLAG | SEQUENCE_LOCKTIME_MASK;
+    let required = required & mask;
+    let sequence = input_sequence & mask;
+    let same_type = (required & SEQUENCE_LOCKTIME_TYPE_FLAG) == (sequence & SEQUENCE_LOCKTIME_TYPE_FLAG);
+    same_type && (required & SEQUENCE_LOCKTIME_MASK) <= (sequence & SEQUENCE_LOCKTIME_MASK)
+}
+
 /// Trait for signature verification
 pub trait SignatureChecker {
     /// Check if a signature is valid for a public key
     fn check_signature(&self, signature: &[u8], pubkey: &[u8]) -> Result<bool, ScriptError>;
+    
+    /// Check an absolute locktime against the spending transaction
+    ///
+    /// Checkers without transaction context cannot satisfy a locktime.
+    fn check_lock_time(&self, _lock_time: u32) -> bool {
+        false
+    }
+    
+    /// Check a relative locktime against the spending input's sequence
+    fn check_sequence(&self, _sequence: u32) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
@@ -651,4 +777,23 @@ mod tests {
         // Check that we have a 32-byte hash on the stack
         assert_eq!(interpreter.stack.peek().unwrap().len(), 32);
     }
+    
+    #[test]
+    fn test_sha3_and_locktime_operations() {
+        let mut interpreter = ScriptInterpreter::new();
+        
+        // Script: push data, OP_SHA3_512
+        let script = vec![0x02, 0x01, 0x02, 0xc0];
+        assert!(interpreter.execute(&script, &MockChecker).unwrap());
+        assert_eq!(interpreter.stack.peek().unwrap().len(), 64);
+        
+        // The mock checker has no transaction context, so locktimes fail
+        let mut interpreter = ScriptInterpreter::new();
+        let script = vec![0x02, 0xe8, 0x03, 0xb1]; // 1000 OP_CHECKLOCKTIMEVERIFY
+        assert_eq!(interpreter.execute(&script, &MockChecker), Err(ScriptError::LockTimeFailed));
+        
+        assert_eq!(decode_script_number(&[0xe8, 0x03]).unwrap(), 1000);
+        assert_eq!(decode_script_number(&[0x81]).unwrap(), -1);
+        assert_eq!(decode_script_number(&[0x80, 0x00]).unwrap(), 128);
+    }
 } 
\ No newline at end of file
@@ -135,6 +135,9 @@ pub enum Opcode {
     OP_NOP9 = 0xb8,
     OP_NOP10 = 0xb9,
 
+    // Supernova extensions
+    OP_SHA3_512 = 0xc0,
+
     // Invalid opcodes
     OP_INVALIDOPCODE = 0xff,
 }
@@ -254,6 +257,7 @@ impl Opcode {
             0xb7 => Some(Opcode::OP_NOP8),
             0xb8 => Some(Opcode::OP_NOP9),
             0xb9 => Some(Opcode::OP_NOP10),
+            0xc0 => Some(Opcode::OP_SHA3_512),
             0xff => Some(Opcode::OP_INVALIDOPCODE),
             // Direct push opcodes (1-75 bytes)
             1..=75 => None, // These are handled specially
@@ -421,6 +425,7 @@ impl fmt::Display for Opcode {
             Opcode::OP_NOP8 => "OP_NOP8",
             Opcode::OP_NOP9 => "OP_NOP9",
             Opcode::OP_NOP10 => "OP_NOP10",
+            Opcode::OP_SHA3_512 => "OP_SHA3_512",
             Opcode::OP_INVALIDOPCODE => "OP_INVALIDOPCODE",
         };
         write!(f, "{}", name)
@@ -540,5 +545,6 @@ pub const ALL_OPCODES: &[Opcode] = &[
     Opcode::OP_NOP8,
     Opcode::OP_NOP9,
     Opcode::OP_NOP10,
+    Opcode::OP_SHA3_512,
     Opcode::OP_INVALIDOPCODE,
 ]; 
\ No newline at end of file
@@ -6,6 +6,7 @@
 #[cfg(test)]
 mod tests {
     use crate::script::{ScriptValidator, ScriptFlags, ScriptBuilder};
+    use crate::script::script_validator::CONDITIONAL_ELSE_ACTIVATION_HEIGHT;
     use crate::script::interpreter::{ScriptInterpreter, SignatureChecker, ScriptError};
     use crate::types::transaction::{Transaction, TransactionInput, TransactionOutput};
     use crate::crypto::signature::{sign_message, verify_signature, SignatureType};
@@ -248,4 +249,107 @@ mod tests {
         let result = interpreter.execute(&script_with_disabled, &checker);
         assert!(result.is_err(), "Disabled opcodes should be rejected");
     }
-} 
\ No newline at end of file
+    
+    /// Spend of input 0 with the given version, lock time and sequence
+    fn spending_tx(version: u32, lock_time: u32, sequence: u32) -> Transaction {
+        Transaction::new(
+            version,
+            vec![TransactionInput::new([1; 32], 0, vec![], sequence)],
+            vec![TransactionOutput::new(1000, vec![])],
+            lock_time,
+        )
+    }
+    
+    fn validate_raw_script(tx: &Transaction, script_sig: &[u8], script_pubkey: &[u8]) -> bool {
+        ScriptValidator::new(tx, 0, ScriptFlags::default())
+            .validate(script_sig, script_pubkey, 1000)
+            .is_ok()
+    }
+    
+    #[test]
+    fn test_checklocktimeverify_against_transaction() {
+        // <800> OP_CHECKLOCKTIMEVERIFY OP_DROP OP_1
+        let script_pubkey = vec![0x02, 0x20, 0x03, 0xb1, 0x75, 0x51];
+        
+        assert!(validate_raw_script(&spending_tx(1, 800, 0xFFFF_FFFE), &[], &script_pubkey));
+        assert!(validate_raw_script(&spending_tx(1, 900, 0), &[], &script_pubkey));
+        
+        // Lock time not yet reached
+        assert!(!validate_raw_script(&spending_tx(1, 799, 0xFFFF_FFFE), &[], &script_pubkey));
+        // A final input disables the transaction lock time
+        assert!(!validate_raw_script(&spending_tx(1, 800, 0xFFFF_FFFF), &[], &script_pubkey));
+        // A timestamp lock time cannot satisfy a height argument
+        assert!(!validate_raw_script(&spending_tx(1, 600_000_000, 0xFFFF_FFFE), &[], &script_pubkey));
+    }
+    
+    #[test]
+    fn test_checksequenceverify_against_input() {
+        // <100> OP_CHECKSEQUENCEVERIFY OP_DROP OP_1
+        let script_pubkey = vec![0x01, 0x64, 0xb2, 0x75, 0x51];
+        
+        assert!(validate_raw_script(&spending_tx(2, 0, 100), &[], &script_pubkey));
+        assert!(validate_raw_script(&spending_tx(2, 0, 150), &[], &script_pubkey));
+        
+        // Relative lock time not yet reached
+        assert!(!validate_raw_script(&spending_tx(2, 0, 99), &[], &script_pubkey));
+        // Relative lock times need version 2 transactions
+        assert!(!validate_raw_script(&spending_tx(1, 0, 100), &[], &script_pubkey));
+        // Disabled relative lock time on the input
+        assert!(!validate_raw_script(&spending_tx(2, 0, (1 << 31) | 100), &[], &script_pubkey));
+        // Time-based sequence cannot satisfy a height argument
+        assert!(!validate_raw_script(&spending_tx(2, 0, (1 << 22) | 100), &[], &script_pubkey));
+    }
+    
+    #[test]
+    fn test_sha3_512_hashlock() {
+        use sha3::{Digest as Sha3Digest, Sha3_512};
+        
+        let preimage = [0x42u8; 32];
+        let digest = Sha3_512::digest(preimage);
+        
+        // OP_SHA3_512 <digest> OP_EQUAL
+        let mut script_pubkey = vec![0xc0, 0x40];
+        script_pubkey.extend_from_slice(&digest);
+        script_pubkey.push(0x87);
+        
+        let mut script_sig = vec![0x20];
+        script_sig.extend_from_slice(&preimage);
+        let tx = spending_tx(1, 0, 0);
+        assert!(validate_raw_script(&tx, &script_sig, &script_pubkey));
+        
+        let mut wrong_sig = vec![0x20];
+        wrong_sig.extend_from_slice(&[0x43u8; 32]);
+        assert!(!validate_raw_script(&tx, &wrong_sig, &script_pubkey));
+    }
+    
+    #[test]
+    fn test_else_inside_skipped_branch_stays_disabled() {
+        let mut interpreter = ScriptInterpreter::new();
+        let checker = MockChecker { should_pass: true };
+        
+        // OP_0 OP_IF OP_1 OP_IF OP_1 OP_ELSE OP_0 OP_VERIFY OP_ENDIF OP_ENDIF OP_1
+        let script = vec![0x00, 0x63, 0x51, 0x63, 0x51, 0x67, 0x00, 0x69, 0x68, 0x68, 0x51];
+        assert_eq!(interpreter.execute(&script, &checker), Ok(true));
+        
+        // The same ELSE runs when the outer branch executes
+        let mut interpreter = ScriptInterpreter::new();
+        let script = vec![0x51, 0x63, 0x00, 0x63, 0x51, 0x67, 0x00, 0x69, 0x68, 0x68, 0x51];
+        assert!(interpreter.execute(&script, &checker).is_err());
+    }
+    
+    #[test]
+    fn test_conditional_else_activation() {
+        // OP_0 OP_IF OP_0 OP_ELSE OP_1 OP_ENDIF
+        let script_pubkey = vec![0x00, 0x63, 0x00, 0x67, 0x51, 0x68];
+        let tx = spending_tx(1, 0, 0);
+        let validate_at = |height| {
+            ScriptValidator::new(&tx, 0, ScriptFlags::for_height(height))
+                .validate(&[], &script_pubkey, 1000)
+                .is_ok()
+        };
+        
+        // Before activation the ELSE branch after a false OP_IF never runs
+        assert!(!validate_at(CONDITIONAL_ELSE_ACTIVATION_HEIGHT - 1));
+        assert!(validate_at(CONDITIONAL_ELSE_ACTIVATION_HEIGHT));
+    }
+}
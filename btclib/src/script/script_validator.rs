@@ -3,13 +3,16 @@
 //! This module provides high-level script validation for transactions.
 
 use crate::script::{ScriptType, identify_script_type, extract_script_hash};
-use crate::script::interpreter::{ScriptInterpreter, ScriptError, SignatureChecker};
+use crate::script::interpreter::{lock_time_satisfied, sequence_satisfied, ScriptInterpreter, ScriptError, SignatureChecker};
 use crate::script::ScriptVerificationError;
 use crate::types::transaction::Transaction;
 use crate::crypto::signature::{verify_signature, SignatureType};
 use sha2::{Sha256, Digest};
 use ripemd::{Ripemd160, Digest as RipemdDigest};
 
+/// Height from which OP_ELSE after a false OP_IF enters its branch
+pub const CONDITIONAL_ELSE_ACTIVATION_HEIGHT: u64 = 100_000;
+
 /// Script validation flags
 #[derive(Debug, Clone, Copy)]
 pub struct ScriptFlags {
@@ -27,6 +30,8 @@ pub struct ScriptFlags {
     pub verify_witness: bool,
     /// Discourage upgradable witness program
     pub verify_discourage_upgradable_witness_program: bool,
+    /// Let OP_ELSE after a false condition switch to its branch
+    pub verify_conditional_else: bool,
 }
 
 impl Default for ScriptFlags {
@@ -39,6 +44,17 @@ impl Default for ScriptFlags {
             verify_checksequenceverify: true,
             verify_witness: true,
             verify_discourage_upgradable_witness_program: true,
+            verify_conditional_else: true,
+        }
+    }
+}
+
+impl ScriptFlags {
+    /// Consensus flags for a block at `height`
+    pub fn for_height(height: u64) -> Self {
+        Self {
+            verify_conditional_else: height >= CONDITIONAL_ELSE_ACTIVATION_HEIGHT,
+            ..Self::default()
         }
     }
 }
@@ -128,7 +144,7 @@ impl<'a> ScriptValidator<'a> {
         
         // Create checker and run the scripts
         let checker = TransactionChecker::new(self.transaction, self.input_index);
-        let mut interpreter = ScriptInterpreter::new();
+        let mut interpreter = ScriptInterpreter::new().with_conditional_else(self.flags.verify_conditional_else);
         
         // First run script_sig
         interpreter.execute(script_sig, &checker)
@@ -169,7 +185,7 @@ impl<'a> ScriptValidator<'a> {
         
         // Create checker and interpreter
         let checker = TransactionChecker::new(self.transaction, self.input_index);
-        let mut interpreter = ScriptInterpreter::new();
+        let mut interpreter = ScriptInterpreter::new().with_conditional_else(self.flags.verify_conditional_else);
         
         // Run script_sig (without redeem script) + redeem script
         let script_sig_without_redeem = &script_sig[..script_sig.len() - redeem_script.len() - 1];
@@ -238,7 +254,7 @@ impl<'a> ScriptValidator<'a> {
         script_pubkey: &[u8],
     ) -> Result<(), ScriptVerificationError> {
         let checker = TransactionChecker::new(self.transaction, self.input_index);
-        let mut interpreter = ScriptInterpreter::new();
+        let mut interpreter = ScriptInterpreter::new().with_conditional_else(self.flags.verify_conditional_else);
         
         // Execute script_sig first
         interpreter.execute(script_sig, &checker)
@@ -352,6 +368,20 @@ impl<'a> SignatureChecker for TransactionChecker<'a> {
             Err(_) => Err(ScriptError::SignatureFailed),
         }
     }
+    
+    fn check_lock_time(&self, lock_time: u32) -> bool {
+        match self.transaction.inputs().get(self.input_index) {
+            Some(input) => lock_time_satisfied(lock_time, self.transaction.lock_time(), input.sequence()),
+            None => false,
+        }
+    }
+    
+    fn check_sequence(&self, sequence: u32) -> bool {
+        match self.transaction.inputs().get(self.input_index) {
+            Some(input) => sequence_satisfied(sequence, self.transaction.version(), input.sequence()),
+            None => false,
+        }
+    }
 }
 
 #[cfg(test)]
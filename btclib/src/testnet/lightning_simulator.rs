@@ -0,0 +1,1091 @@
+//! Lightning Network Simulator
+//!
+//! Runs payment workloads through in-process `LightningManager`s wired up
+//! from a graph description file, so routing changes can be evaluated in CI.
+//! Every channel exists in the managers of both its nodes, and payments add,
+//! forward and settle HTLCs on those channels the way a live node would. A
+//! payment fails where liquidity runs out, where a node is offline or where a
+//! failure is injected. Workloads, injected failures and hop latencies all
+//! come from seeded RNGs, so runs are reproducible.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::environmental::types::Region;
+use crate::lightning::channel::{Channel, ChannelId, ChannelState, PublicKey as ChannelPublicKey};
+use crate::lightning::green_routing::{EnvironmentalChannel, EnvironmentalNode, GreenLightningRouter};
+use crate::lightning::manager::LightningManager;
+use crate::lightning::payment::PaymentPreimage;
+use crate::lightning::router::{ChannelInfo, NodeId, PaymentPath, Router, RouterPreferences};
+use crate::lightning::wallet::LightningWallet;
+use crate::lightning::LightningConfig;
+
+/// Error types for Lightning simulation
+#[derive(Debug, Error)]
+pub enum LightningSimulationError {
+    #[error("Invalid graph: {0}")]
+    InvalidGraph(String),
+    
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    
+    #[error("TOML error: {0}")]
+    Toml(#[from] toml::de::Error),
+    
+    #[error("Manager error: {0}")]
+    Manager(String),
+}
+
+/// A node in the graph description
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimNode {
+    /// Node name, used as its node ID
+    pub id: String,
+    
+    /// Share of renewable energy, used by green routing
+    #[serde(default)]
+    pub renewable_percentage: f64,
+    
+    /// Whether the node is green certified, used by green routing
+    #[serde(default)]
+    pub green_certified: bool,
+}
+
+/// A channel in the graph description
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimChannel {
+    /// First node
+    pub node1: String,
+    
+    /// Second node
+    pub node2: String,
+    
+    /// Capacity in novas
+    pub capacity_sats: u64,
+    
+    /// Balance of the first node in novas (half the capacity if unset)
+    #[serde(default)]
+    pub node1_balance_sats: Option<u64>,
+    
+    /// Base fee in millinovas, charged in both directions
+    #[serde(default = "default_base_fee_msat")]
+    pub base_fee_msat: u32,
+    
+    /// Fee rate in parts per million, charged in both directions
+    #[serde(default = "default_fee_rate_ppm")]
+    pub fee_rate_ppm: u32,
+    
+    /// CLTV expiry delta
+    #[serde(default = "default_cltv_expiry_delta")]
+    pub cltv_expiry_delta: u16,
+}
+
+fn default_base_fee_msat() -> u32 {
+    1_000
+}
+
+fn default_fee_rate_ppm() -> u32 {
+    100
+}
+
+fn default_cltv_expiry_delta() -> u16 {
+    40
+}
+
+impl SimChannel {
+    /// Fee for forwarding `amount_msat` over this channel
+    pub fn fee_msat(&self, amount_msat: u64) -> u64 {
+        self.base_fee_msat as u64 + amount_msat * self.fee_rate_ppm as u64 / 1_000_000
+    }
+    
+    fn node1_balance(&self) -> u64 {
+        self.node1_balance_sats.unwrap_or(self.capacity_sats / 2)
+    }
+}
+
+/// Network to simulate, loaded from a JSON or TOML graph description
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SimulationGraph {
+    /// Nodes
+    pub nodes: Vec<SimNode>,
+    
+    /// Channels between nodes
+    pub channels: Vec<SimChannel>,
+}
+
+impl SimulationGraph {
+    /// Load a graph description; files ending in `.json` are JSON, anything else TOML
+    pub fn load(path: &Path) -> Result<Self, LightningSimulationError> {
+        let contents = std::fs::read_to_string(path)?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json(&contents)
+        } else {
+            Self::from_toml(&contents)
+        }
+    }
+    
+    /// Parse a JSON graph description
+    pub fn from_json(contents: &str) -> Result<Self, LightningSimulationError> {
+        let graph: Self = serde_json::from_str(contents)?;
+        graph.validate()?;
+        Ok(graph)
+    }
+    
+    /// Parse a TOML graph description
+    pub fn from_toml(contents: &str) -> Result<Self, LightningSimulationError> {
+        let graph: Self = toml::from_str(contents)?;
+        graph.validate()?;
+        Ok(graph)
+    }
+    
+    /// Check that channels connect distinct known nodes and balances fit capacities
+    pub fn validate(&self) -> Result<(), LightningSimulationError> {
+        let mut ids = HashSet::new();
+        for node in &self.nodes {
+            if !ids.insert(node.id.as_str()) {
+                return Err(LightningSimulationError::InvalidGraph(format!("Duplicate node {}", node.id)));
+            }
+        }
+        
+        for channel in &self.channels {
+            for end in [&channel.node1, &channel.node2] {
+                if !ids.contains(end.as_str()) {
+                    return Err(LightningSimulationError::InvalidGraph(format!("Unknown node {}", end)));
+                }
+            }
+            if channel.node1 == channel.node2 {
+                return Err(LightningSimulationError::InvalidGraph(format!("Channel from {} to itself", channel.node1)));
+            }
+            if channel.node1_balance() > channel.capacity_sats {
+                return Err(LightningSimulationError::InvalidGraph(
+                    format!("Balance of {} exceeds capacity of channel to {}", channel.node1, channel.node2)
+                ));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// ID of the channel at `index`
+    pub fn channel_id(&self, index: usize) -> ChannelId {
+        let channel = &self.channels[index];
+        let mut hasher = Sha256::new();
+        hasher.update(b"sim_channel");
+        hasher.update((index as u64).to_be_bytes());
+        hasher.update(channel.node1.as_bytes());
+        hasher.update(channel.node2.as_bytes());
+        ChannelId::from_bytes(hasher.finalize().into())
+    }
+}
+
+/// Simulated compressed public key of a node
+fn node_key(id: &str) -> [u8; 33] {
+    let mut key = [0u8; 33];
+    key[0] = 0x02;
+    key[1..].copy_from_slice(&Sha256::digest(id.as_bytes()));
+    key
+}
+
+/// ID of one direction of a channel in a `Router` graph, whose edges are directed
+fn directed_channel_id(channel_id: &ChannelId, from_node1: bool) -> ChannelId {
+    let mut hasher = Sha256::new();
+    hasher.update(channel_id.as_bytes());
+    hasher.update([from_node1 as u8]);
+    ChannelId::from_bytes(hasher.finalize().into())
+}
+
+/// A hop of a simulated payment: the channel taken and the node it reaches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimHop {
+    /// Node reached over the channel
+    pub node: String,
+    
+    /// Channel ID
+    pub channel_id: ChannelId,
+}
+
+/// Path finding under evaluation
+pub trait RoutingStrategy {
+    /// Name shown in reports
+    fn name(&self) -> String;
+    
+    /// Find a path from `source` to `destination` that avoids `excluded` channels
+    fn find_path(
+        &mut self,
+        source: &str,
+        destination: &str,
+        amount_msat: u64,
+        excluded: &HashSet<ChannelId>,
+    ) -> Option<Vec<SimHop>>;
+    
+    /// Learn from the last path found; `failed_at` is the index of the hop that failed
+    fn payment_outcome(&mut self, _source: &str, _failed_at: Option<usize>) {}
+}
+
+/// Routes with `Router`, feeding attempt outcomes to its liquidity scorer
+pub struct RouterStrategy {
+    router: Router,
+    preferences: RouterPreferences,
+    directed: HashMap<ChannelId, ChannelId>,
+    last_path: Option<PaymentPath>,
+}
+
+impl RouterStrategy {
+    /// Route over `graph` with a default router
+    pub fn new(graph: &SimulationGraph) -> Self {
+        Self::with_router(Router::new(), RouterPreferences::default(), graph)
+    }
+    
+    /// Route over `graph` with a configured router
+    pub fn with_router(mut router: Router, preferences: RouterPreferences, graph: &SimulationGraph) -> Self {
+        let mut directed = HashMap::new();
+        
+        for (index, channel) in graph.channels.iter().enumerate() {
+            let channel_id = graph.channel_id(index);
+            for (from_node1, source, destination) in [(true, &channel.node1, &channel.node2), (false, &channel.node2, &channel.node1)] {
+                let directed_id = directed_channel_id(&channel_id, from_node1);
+                router.update_channel(ChannelInfo {
+                    channel_id: directed_id.clone(),
+                    source: NodeId::new(source.clone()),
+                    destination: NodeId::new(destination.clone()),
+                    capacity: channel.capacity_sats,
+                    base_fee_msat: channel.base_fee_msat,
+                    fee_rate_millionths: channel.fee_rate_ppm,
+                    cltv_expiry_delta: channel.cltv_expiry_delta,
+                    is_active: true,
+                    last_update: 0,
+                }, false);
+                directed.insert(directed_id, channel_id.clone());
+            }
+        }
+        
+        Self {
+            router,
+            preferences,
+            directed,
+            last_path: None,
+        }
+    }
+    
+    /// The router being evaluated
+    pub fn router(&self) -> &Router {
+        &self.router
+    }
+}
+
+impl RoutingStrategy for RouterStrategy {
+    fn name(&self) -> String {
+        "router".to_string()
+    }
+    
+    fn find_path(
+        &mut self,
+        source: &str,
+        destination: &str,
+        amount_msat: u64,
+        excluded: &HashSet<ChannelId>,
+    ) -> Option<Vec<SimHop>> {
+        let mut preferences = self.preferences.clone();
+        preferences.avoid_channels.extend(
+            excluded.iter().flat_map(|id| [directed_channel_id(id, true), directed_channel_id(id, false)])
+        );
+        self.router.set_preferences(preferences);
+        self.router.set_local_node(NodeId::new(source.to_string()));
+        
+        let path = self.router.find_route(destination, amount_msat, &[]).ok()?;
+        let hops = path.hops.iter()
+            .map(|hop| Some(SimHop {
+                node: hop.node_id.as_str().to_string(),
+                channel_id: self.directed.get(&hop.channel_id)?.clone(),
+            }))
+            .collect::<Option<Vec<_>>>()?;
+        
+        self.last_path = Some(path);
+        Some(hops)
+    }
+    
+    fn payment_outcome(&mut self, source: &str, failed_at: Option<usize>) {
+        let Some(path) = self.last_path.take() else {
+            return;
+        };
+        
+        self.router.set_local_node(NodeId::new(source.to_string()));
+        match failed_at {
+            Some(failure_point) => self.router.payment_path_failed(&path, failure_point),
+            None => self.router.payment_path_succeeded(&path),
+        }
+    }
+}
+
+/// Routes with `GreenLightningRouter`
+///
+/// The green router has no notion of excluded channels, so a path through
+/// one is dropped rather than retried.
+pub struct GreenRouterStrategy {
+    router: GreenLightningRouter,
+    names: HashMap<Vec<u8>, String>,
+}
+
+impl GreenRouterStrategy {
+    /// Load `graph` into `router`
+    pub fn new(router: GreenLightningRouter, graph: &SimulationGraph) -> Self {
+        let mut names = HashMap::new();
+        let mut renewable = HashMap::new();
+        
+        for node in &graph.nodes {
+            let key = node_key(&node.id);
+            router.add_node(EnvironmentalNode {
+                node_id: key,
+                public_key: key.to_vec(),
+                alias: node.id.clone(),
+                renewable_percentage: node.renewable_percentage,
+                carbon_footprint_per_tx: 0.0,
+                green_certified: node.green_certified,
+                environmental_score: node.renewable_percentage,
+                carbon_negative: false,
+                monthly_carbon_saved: 0.0,
+                region: Region::Global,
+                coordinates: None,
+                prefers_green_routes: true,
+                green_fee_discount: 0.0,
+            });
+            names.insert(key.to_vec(), node.id.clone());
+            renewable.insert(node.id.as_str(), node.renewable_percentage);
+        }
+        
+        for (index, channel) in graph.channels.iter().enumerate() {
+            let renewable_share = (renewable[channel.node1.as_str()] + renewable[channel.node2.as_str()]) / 200.0;
+            router.add_channel(EnvironmentalChannel {
+                channel_id: *graph.channel_id(index).as_bytes(),
+                node1: node_key(&channel.node1),
+                node2: node_key(&channel.node2),
+                capacity_sats: channel.capacity_sats,
+                available_balance: channel.capacity_sats,
+                base_fee_msat: channel.base_fee_msat,
+                fee_rate_ppm: channel.fee_rate_ppm,
+                // 0.1 g CO2e for a hop powered entirely by fossil energy
+                carbon_footprint: 0.0001 * (1.0 - renewable_share),
+                renewable_powered: renewable_share >= 0.5,
+                environmental_score: renewable_share * 100.0,
+                success_rate: 1.0,
+                avg_settlement_time: 1.0,
+            });
+        }
+        
+        Self { router, names }
+    }
+}
+
+impl RoutingStrategy for GreenRouterStrategy {
+    fn name(&self) -> String {
+        "green_router".to_string()
+    }
+    
+    fn find_path(
+        &mut self,
+        source: &str,
+        destination: &str,
+        amount_msat: u64,
+        excluded: &HashSet<ChannelId>,
+    ) -> Option<Vec<SimHop>> {
+        let route = self.router.find_green_route(node_key(source), node_key(destination), amount_msat / 1000).ok()?;
+        
+        route.hops.iter()
+            .map(|hop| {
+                let channel_id = ChannelId::from_bytes(hop.channel_id);
+                if excluded.contains(&channel_id) {
+                    return None;
+                }
+                Some(SimHop {
+                    node: self.names.get(&hop.node_pubkey)?.clone(),
+                    channel_id,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A node that is offline for part of a workload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineWindow {
+    /// Node ID
+    pub node: String,
+    
+    /// Index of the first payment the node is offline for
+    pub from_payment: usize,
+    
+    /// Index of the first payment the node is back for
+    pub until_payment: usize,
+}
+
+/// Failure injection and timing of a simulation
+#[derive(Debug, Clone)]
+pub struct LightningSimulationConfig {
+    /// Seed for preimages, injected failures and latencies
+    pub seed: u64,
+    
+    /// Probability that a forwarding node fails an HTLC on its own
+    pub htlc_failure_rate: f64,
+    
+    /// Nodes that go offline during the workload
+    pub offline: Vec<OfflineWindow>,
+    
+    /// Attempts per payment; failed channels are excluded on retry
+    pub max_attempts: usize,
+    
+    /// Range of one-way latency per hop in milliseconds
+    pub hop_latency_ms: (u64, u64),
+    
+    /// Block height payments start at
+    pub start_height: u32,
+    
+    /// CLTV delta required by recipients
+    pub final_cltv_delta: u32,
+}
+
+impl Default for LightningSimulationConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            htlc_failure_rate: 0.0,
+            offline: Vec::new(),
+            max_attempts: 3,
+            hop_latency_ms: (20, 200),
+            start_height: 800_000,
+            final_cltv_delta: 40,
+        }
+    }
+}
+
+/// A payment to simulate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRequest {
+    /// Sending node
+    pub source: String,
+    
+    /// Receiving node
+    pub destination: String,
+    
+    /// Amount in millinovas
+    pub amount_msat: u64,
+}
+
+/// Parameters of a synthetic workload
+#[derive(Debug, Clone)]
+pub struct WorkloadConfig {
+    /// Number of payments
+    pub payments: usize,
+    
+    /// Smallest payment in millinovas
+    pub min_amount_msat: u64,
+    
+    /// Largest payment in millinovas
+    pub max_amount_msat: u64,
+    
+    /// Seed for picking endpoints and amounts
+    pub seed: u64,
+}
+
+impl Default for WorkloadConfig {
+    fn default() -> Self {
+        Self {
+            payments: 100,
+            min_amount_msat: 1_000_000,
+            max_amount_msat: 50_000_000,
+            seed: 0,
+        }
+    }
+}
+
+/// Sequence of payments, stored as JSON so a workload can be replayed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workload {
+    /// Payments in the order they are made
+    pub payments: Vec<PaymentRequest>,
+}
+
+impl Workload {
+    /// Generate random payments between distinct nodes of `graph`
+    ///
+    /// Amounts are whole novas, since HTLCs are settled in novas.
+    pub fn generate(graph: &SimulationGraph, config: &WorkloadConfig) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+        let nodes: Vec<&str> = graph.nodes.iter().map(|node| node.id.as_str()).collect();
+        if nodes.len() < 2 {
+            return Self::default();
+        }
+        
+        let payments = (0..config.payments)
+            .map(|_| {
+                let source = rng.gen_range(0..nodes.len());
+                let destination = (source + rng.gen_range(1..nodes.len())) % nodes.len();
+                let amount_msat = rng.gen_range(config.min_amount_msat..=config.max_amount_msat) / 1000 * 1000;
+                PaymentRequest {
+                    source: nodes[source].to_string(),
+                    destination: nodes[destination].to_string(),
+                    amount_msat,
+                }
+            })
+            .collect();
+        
+        Self { payments }
+    }
+    
+    /// Load a workload saved with `to_json`
+    pub fn from_json(contents: &str) -> Result<Self, LightningSimulationError> {
+        Ok(serde_json::from_str(contents)?)
+    }
+    
+    /// Serialize the workload for replay
+    pub fn to_json(&self) -> Result<String, LightningSimulationError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Why a payment failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// The strategy found no path
+    NoRoute,
+    
+    /// The sending node was offline
+    SenderOffline,
+    
+    /// A node on the path was offline
+    NodeOffline,
+    
+    /// A forwarding node failed the HTLC on purpose
+    InjectedFailure,
+    
+    /// A channel could not carry the HTLC
+    TemporaryChannelFailure,
+}
+
+/// Summary of a set of samples
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Distribution {
+    pub count: usize,
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+}
+
+impl Distribution {
+    /// Summarize samples, using nearest-rank percentiles
+    pub fn from_samples(mut samples: Vec<u64>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        
+        let percentile = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+        Self {
+            count: samples.len(),
+            min: samples[0],
+            max: samples[samples.len() - 1],
+            mean: samples.iter().sum::<u64>() as f64 / samples.len() as f64,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+        }
+    }
+}
+
+/// Results of running a workload with one strategy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LightningSimulationReport {
+    /// Strategy name
+    pub strategy: String,
+    
+    /// Payments attempted
+    pub payments: usize,
+    
+    /// Payments that reached their destination
+    pub succeeded: usize,
+    
+    /// Payments that failed every attempt
+    pub failed: usize,
+    
+    /// Share of payments that succeeded
+    pub success_rate: f64,
+    
+    /// Attempts over all payments
+    pub attempts: usize,
+    
+    /// Fees of successful payments in millinovas
+    pub fees_msat: Distribution,
+    
+    /// Latency of successful payments in milliseconds, including failed attempts
+    pub latency_ms: Distribution,
+    
+    /// Hops of successful payments
+    pub path_length: Distribution,
+    
+    /// Failed payments by the reason of their last attempt
+    pub failures: BTreeMap<FailureReason, usize>,
+}
+
+impl LightningSimulationReport {
+    /// Serialize the report for CI artifacts
+    pub fn to_json(&self) -> Result<String, LightningSimulationError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Outcome of one attempt: the fee paid, or the failing hop and why
+type AttemptResult = Result<u64, (usize, FailureReason)>;
+
+/// Deterministic Lightning Network simulator
+pub struct LightningSimulator {
+    graph: SimulationGraph,
+    config: LightningSimulationConfig,
+    nodes: BTreeMap<String, LightningManager>,
+    channels: HashMap<ChannelId, SimChannel>,
+}
+
+impl LightningSimulator {
+    /// Start a manager per node and open every channel of `graph` in both its managers
+    pub fn new(graph: SimulationGraph, config: LightningSimulationConfig) -> Result<Self, LightningSimulationError> {
+        graph.validate()?;
+        
+        let mut nodes = BTreeMap::new();
+        for node in &graph.nodes {
+            let (manager, _events) = LightningManager::new(LightningConfig::default(), LightningWallet::new_test_wallet(0))
+                .map_err(|e| LightningSimulationError::Manager(e.to_string()))?;
+            nodes.insert(node.id.clone(), manager);
+        }
+        
+        let mut channels = HashMap::new();
+        for (index, channel) in graph.channels.iter().enumerate() {
+            let channel_id = graph.channel_id(index);
+            let node1_balance = channel.node1_balance();
+            let sides = [
+                (&channel.node1, &channel.node2, node1_balance, true),
+                (&channel.node2, &channel.node1, channel.capacity_sats - node1_balance, false),
+            ];
+            
+            for (local, remote, local_balance, is_initiator) in sides {
+                let mut state = Channel::new(
+                    ChannelPublicKey::from_bytes(node_key(local)),
+                    ChannelPublicKey::from_bytes(node_key(remote)),
+                    channel.capacity_sats,
+                    is_initiator,
+                    true,
+                );
+                state.channel_id = *channel_id.as_bytes();
+                state.state = ChannelState::Active;
+                state.local_balance_novas = local_balance;
+                state.remote_balance_novas = channel.capacity_sats - local_balance;
+                nodes[local.as_str()].register_open_channel(state);
+            }
+            channels.insert(channel_id, channel.clone());
+        }
+        
+        Ok(Self {
+            graph,
+            config,
+            nodes,
+            channels,
+        })
+    }
+    
+    /// The simulated graph
+    pub fn graph(&self) -> &SimulationGraph {
+        &self.graph
+    }
+    
+    /// Manager of a node
+    pub fn manager(&self, node: &str) -> Option<&LightningManager> {
+        self.nodes.get(node)
+    }
+    
+    /// Balance in novas a node holds in a channel
+    pub fn local_balance(&self, node: &str, channel_id: &ChannelId) -> Option<u64> {
+        let channel = self.nodes.get(node)?.atomic_channel(channel_id)?;
+        let balance = channel.channel.lock().ok()?.local_balance_novas;
+        Some(balance)
+    }
+    
+    /// Run `workload`, routing every payment with `strategy`
+    pub fn run(&mut self, strategy: &mut dyn RoutingStrategy, workload: &Workload) -> LightningSimulationReport {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.config.seed);
+        let mut attempts = 0;
+        let mut fees = Vec::new();
+        let mut latencies = Vec::new();
+        let mut path_lengths = Vec::new();
+        let mut failures: BTreeMap<FailureReason, usize> = BTreeMap::new();
+        
+        for (index, payment) in workload.payments.iter().enumerate() {
+            let offline: HashSet<&str> = self.config.offline.iter()
+                .filter(|window| (window.from_payment..window.until_payment).contains(&index))
+                .map(|window| window.node.as_str())
+                .collect();
+            
+            if offline.contains(payment.source.as_str()) {
+                *failures.entry(FailureReason::SenderOffline).or_default() += 1;
+                continue;
+            }
+            
+            let mut excluded = HashSet::new();
+            let mut latency_ms = 0;
+            let mut last_failure = FailureReason::NoRoute;
+            let mut succeeded = false;
+            
+            for _ in 0..self.config.max_attempts.max(1) {
+                // Running out of paths after a failed attempt keeps that attempt's reason
+                let Some(path) = strategy.find_path(&payment.source, &payment.destination, payment.amount_msat, &excluded) else {
+                    break;
+                };
+                attempts += 1;
+                
+                let result = self.attempt(&payment.source, &path, payment.amount_msat, &offline, &mut rng);
+                let hops_reached = match &result {
+                    Ok(_) => path.len(),
+                    Err((failed_at, _)) => failed_at + 1,
+                };
+                for _ in 0..hops_reached {
+                    // There and back: the HTLC travels out, the settle or fail comes back
+                    latency_ms += 2 * rng.gen_range(self.config.hop_latency_ms.0..=self.config.hop_latency_ms.1);
+                }
+                
+                match result {
+                    Ok(fee_msat) => {
+                        strategy.payment_outcome(&payment.source, None);
+                        fees.push(fee_msat);
+                        path_lengths.push(path.len() as u64);
+                        succeeded = true;
+                        break;
+                    }
+                    Err((failed_at, reason)) => {
+                        // An offline node says nothing about channel liquidity
+                        if reason != FailureReason::NodeOffline {
+                            strategy.payment_outcome(&payment.source, Some(failed_at));
+                        }
+                        excluded.insert(path[failed_at].channel_id.clone());
+                        last_failure = reason;
+                    }
+                }
+            }
+            
+            if succeeded {
+                latencies.push(latency_ms);
+            } else {
+                *failures.entry(last_failure).or_default() += 1;
+            }
+        }
+        
+        let payments = workload.payments.len();
+        let succeeded = fees.len();
+        LightningSimulationReport {
+            strategy: strategy.name(),
+            payments,
+            succeeded,
+            failed: payments - succeeded,
+            success_rate: if payments == 0 { 0.0 } else { succeeded as f64 / payments as f64 },
+            attempts,
+            fees_msat: Distribution::from_samples(fees),
+            latency_ms: Distribution::from_samples(latencies),
+            path_length: Distribution::from_samples(path_lengths),
+            failures,
+        }
+    }
+    
+    /// Send one HTLC along `path` and settle or fail it back
+    fn attempt(
+        &self,
+        source: &str,
+        path: &[SimHop],
+        amount_msat: u64,
+        offline: &HashSet<&str>,
+        rng: &mut ChaCha8Rng,
+    ) -> AttemptResult {
+        let last = path.len() - 1;
+        
+        // Amounts and expiries are set from the recipient backwards; each
+        // forwarding node charges the fee of the channel it sends over
+        let mut amounts = vec![amount_msat; path.len()];
+        let mut expiries = vec![self.config.start_height + self.config.final_cltv_delta; path.len()];
+        for j in (0..last).rev() {
+            let next_channel = self.channels.get(&path[j + 1].channel_id).ok_or((j + 1, FailureReason::TemporaryChannelFailure))?;
+            amounts[j] = amounts[j + 1] + next_channel.fee_msat(amounts[j + 1]);
+            expiries[j] = expiries[j + 1] + next_channel.cltv_expiry_delta as u32;
+        }
+        
+        let preimage = PaymentPreimage::new(rng.gen());
+        let payment_hash = preimage.payment_hash();
+        
+        let first_channel = self.nodes.get(source)
+            .and_then(|manager| manager.atomic_channel(&path[0].channel_id))
+            .ok_or((0, FailureReason::TemporaryChannelFailure))?;
+        let first_htlc = first_channel.add_htlc(*payment_hash.as_bytes(), amounts[0] / 1000, expiries[0], true)
+            .map_err(|_| (0, FailureReason::TemporaryChannelFailure))?;
+        
+        let mut forwarders = Vec::new();
+        let mut failure = None;
+        
+        for (j, hop) in path.iter().enumerate() {
+            let Some(manager) = self.nodes.get(&hop.node) else {
+                failure = Some((j, FailureReason::TemporaryChannelFailure));
+                break;
+            };
+            if offline.contains(hop.node.as_str()) {
+                failure = Some((j, FailureReason::NodeOffline));
+                break;
+            }
+            
+            if j == last {
+                let received = manager.atomic_channel(&hop.channel_id)
+                    .and_then(|channel| {
+                        let htlc = channel.add_htlc(*payment_hash.as_bytes(), amounts[j] / 1000, expiries[j], false).ok()?;
+                        channel.settle_htlc(htlc, *preimage.as_bytes()).ok()
+                    });
+                if received.is_none() {
+                    failure = Some((j, FailureReason::TemporaryChannelFailure));
+                }
+                break;
+            }
+            
+            if self.config.htlc_failure_rate > 0.0 && rng.gen_bool(self.config.htlc_failure_rate.min(1.0)) {
+                failure = Some((j + 1, FailureReason::InjectedFailure));
+                break;
+            }
+            
+            let forwarded = manager.forward_htlc(
+                &hop.channel_id.to_hex(),
+                &path[j + 1].channel_id.to_hex(),
+                &payment_hash.to_hex(),
+                amounts[j],
+                amounts[j + 1],
+                expiries[j],
+            );
+            if forwarded.is_err() {
+                failure = Some((j + 1, FailureReason::TemporaryChannelFailure));
+                break;
+            }
+            forwarders.push(manager);
+        }
+        
+        // Resolve back towards the sender, like update_fulfill/update_fail would
+        match failure {
+            None => {
+                for manager in forwarders.iter().rev() {
+                    let _ = manager.settle_forward(&preimage.to_hex());
+                }
+                let _ = first_channel.settle_htlc(first_htlc, *preimage.as_bytes());
+                Ok(amounts[0] - amount_msat)
+            }
+            Some((failed_at, reason)) => {
+                for manager in forwarders.iter().rev() {
+                    let _ = manager.fail_forward(&payment_hash.to_hex(), &format!("{:?}", reason));
+                }
+                let _ = first_channel.fail_htlc(first_htlc, &format!("{:?}", reason));
+                Err((failed_at, reason))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// a - b - c, with b - d - e hanging off b; paths are unique, so routing is deterministic
+    const TREE: &str = r#"
+        [[nodes]]
+        id = "a"
+        
+        [[nodes]]
+        id = "b"
+        
+        [[nodes]]
+        id = "c"
+        
+        [[nodes]]
+        id = "d"
+        
+        [[nodes]]
+        id = "e"
+        
+        [[channels]]
+        node1 = "a"
+        node2 = "b"
+        capacity_sats = 1000000
+        
+        [[channels]]
+        node1 = "b"
+        node2 = "c"
+        capacity_sats = 1000000
+        node1_balance_sats = 900000
+        base_fee_msat = 2000
+        
+        [[channels]]
+        node1 = "b"
+        node2 = "d"
+        capacity_sats = 400000
+        
+        [[channels]]
+        node1 = "d"
+        node2 = "e"
+        capacity_sats = 400000
+        fee_rate_ppm = 500
+    "#;
+    
+    fn tree() -> SimulationGraph {
+        SimulationGraph::from_toml(TREE).unwrap()
+    }
+    
+    fn workload(payments: usize, seed: u64) -> Workload {
+        Workload::generate(&tree(), &WorkloadConfig {
+            payments,
+            min_amount_msat: 10_000_000,
+            max_amount_msat: 150_000_000,
+            seed,
+        })
+    }
+    
+    fn total_balance(simulator: &LightningSimulator) -> u64 {
+        let graph = simulator.graph().clone();
+        (0..graph.channels.len())
+            .flat_map(|index| {
+                let channel = &graph.channels[index];
+                let id = graph.channel_id(index);
+                [
+                    simulator.local_balance(&channel.node1, &id).unwrap(),
+                    simulator.local_balance(&channel.node2, &id).unwrap(),
+                ]
+            })
+            .sum()
+    }
+    
+    #[test]
+    fn test_graph_loading_and_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("graph.json");
+        std::fs::write(&path, serde_json::to_string(&tree()).unwrap()).unwrap();
+        let graph = SimulationGraph::load(&path).unwrap();
+        assert_eq!(graph.nodes.len(), 5);
+        assert_eq!(graph.channels[1].base_fee_msat, 2000);
+        assert_eq!(graph.channels[0].fee_rate_ppm, 100);
+        
+        let unknown = "[[nodes]]\nid = \"a\"\n\n[[channels]]\nnode1 = \"a\"\nnode2 = \"z\"\ncapacity_sats = 10\n";
+        assert!(matches!(SimulationGraph::from_toml(unknown), Err(LightningSimulationError::InvalidGraph(_))));
+        
+        // Workloads round-trip for replay
+        let workload = workload(10, 3);
+        assert_eq!(Workload::from_json(&workload.to_json().unwrap()).unwrap(), workload);
+        assert!(workload.payments.iter().all(|p| p.source != p.destination && p.amount_msat % 1000 == 0));
+    }
+    
+    #[test]
+    fn test_runs_are_deterministic_and_conserve_funds() {
+        let config = LightningSimulationConfig {
+            seed: 7,
+            htlc_failure_rate: 0.1,
+            ..Default::default()
+        };
+        let workload = workload(60, 1);
+        
+        let mut first = LightningSimulator::new(tree(), config.clone()).unwrap();
+        let before = total_balance(&first);
+        let report = first.run(&mut RouterStrategy::new(&tree()), &workload);
+        
+        let mut second = LightningSimulator::new(tree(), config).unwrap();
+        assert_eq!(second.run(&mut RouterStrategy::new(&tree()), &workload), report);
+        
+        assert_eq!(report.payments, 60);
+        assert_eq!(report.succeeded + report.failed, 60);
+        assert!(report.succeeded > 0 && report.failed > 0);
+        assert!(report.failures.contains_key(&FailureReason::InjectedFailure));
+        assert_eq!(report.fees_msat.count, report.succeeded);
+        assert!(report.latency_ms.p50 >= 40 && report.latency_ms.p50 <= report.latency_ms.p99);
+        
+        // Every HTLC was settled or failed back, so no funds are stuck in flight
+        assert_eq!(total_balance(&first), before);
+        assert!(report.to_json().unwrap().contains("\"injected_failure\""));
+    }
+    
+    #[test]
+    fn test_offline_nodes_and_liquidity_failures() {
+        let graph = tree();
+        let b_c = graph.channel_id(1);
+        
+        // b is offline for the first two payments
+        let config = LightningSimulationConfig {
+            offline: vec![OfflineWindow {
+                node: "b".to_string(),
+                from_payment: 0,
+                until_payment: 2,
+            }],
+            ..Default::default()
+        };
+        let payment = |source: &str, destination: &str, amount_msat| PaymentRequest {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            amount_msat,
+        };
+        let workload = Workload {
+            payments: vec![
+                payment("a", "c", 50_000_000),
+                payment("b", "c", 50_000_000),
+                payment("a", "c", 50_000_000),
+                // c only has 100k novas towards b
+                payment("c", "a", 300_000_000),
+            ],
+        };
+        
+        let mut simulator = LightningSimulator::new(graph.clone(), config).unwrap();
+        let report = simulator.run(&mut RouterStrategy::new(&graph), &workload);
+        
+        assert_eq!(report.succeeded, 1);
+        assert_eq!(report.failures[&FailureReason::NodeOffline], 1);
+        assert_eq!(report.failures[&FailureReason::SenderOffline], 1);
+        assert_eq!(report.failures[&FailureReason::TemporaryChannelFailure], 1);
+        
+        // The successful payment moved 50k novas from b to c and paid b's fee
+        let fee_msat = 2_000 + 50_000_000 * 100 / 1_000_000;
+        assert_eq!(report.fees_msat.max, fee_msat);
+        assert_eq!(simulator.local_balance("c", &b_c), Some(100_000 + 50_000));
+        assert_eq!(simulator.local_balance("a", &graph.channel_id(0)), Some(500_000 - 50_000 - fee_msat / 1000));
+        
+        // b forwarded the payment and kept the fee
+        assert_eq!(simulator.local_balance("b", &graph.channel_id(0)), Some(500_000 + 50_000 + fee_msat / 1000));
+        assert_eq!(simulator.local_balance("b", &b_c), Some(900_000 - 50_000));
+    }
+    
+    #[test]
+    fn test_green_router_strategy() {
+        use crate::environmental::carbon_tracking::CarbonTracker;
+        use crate::environmental::emissions::EmissionsCalculator;
+        use crate::environmental::oracle::EnvironmentalOracle;
+        use std::sync::Arc;
+        
+        let tracker = CarbonTracker::new(Arc::new(EnvironmentalOracle::new(0)), Arc::new(EmissionsCalculator::new()));
+        let mut strategy = GreenRouterStrategy::new(GreenLightningRouter::new(Arc::new(tracker)), &tree());
+        
+        let path = strategy.find_path("a", "e", 10_000_000, &HashSet::new()).unwrap();
+        let nodes: Vec<&str> = path.iter().map(|hop| hop.node.as_str()).collect();
+        assert_eq!(nodes, vec!["b", "d", "e"]);
+        
+        // Paths through excluded channels are dropped
+        let excluded: HashSet<ChannelId> = [tree().channel_id(2)].into_iter().collect();
+        assert!(strategy.find_path("a", "e", 10_000_000, &excluded).is_none());
+        
+        let mut simulator = LightningSimulator::new(tree(), LightningSimulationConfig::default()).unwrap();
+        let report = simulator.run(&mut strategy, &workload(20, 5));
+        assert_eq!(report.strategy, "green_router");
+        assert!(report.succeeded > 0);
+    }
+}
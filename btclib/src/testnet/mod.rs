@@ -1,6 +1,7 @@
 pub mod config;
 pub mod faucet;
 pub mod network_simulator;
+pub mod lightning_simulator;
 pub mod test_harness;
 pub mod regression_testing;
 
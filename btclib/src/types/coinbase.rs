@@ -1,6 +1,154 @@
 /// Coinbase transaction utilities
-use serde::{Serialize, Deserialize};
+use crate::environmental::attestation::{AttestationError, RecAttestation};
+use crate::environmental::miner_reporting::{OFFSET_BONUS_BPS, REC_BONUS_BPS};
 use crate::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+use crate::util::merkle::MerkleTree;
+
+/// Tag identifying the environmental commitment output
+pub const ENVIRONMENTAL_COMMITMENT_TAG: &[u8; 4] = b"SNEA";
+
+/// Tag identifying an output carrying one committed attestation
+pub const ENVIRONMENTAL_ATTESTATION_TAG: &[u8; 4] = b"SNAT";
+
+/// Maximum environmental bonus in basis points of the block subsidy
+pub const MAX_ENVIRONMENTAL_BONUS_BPS: u64 = 7_500;
+
+/// Length of the data pushed by the commitment output: tag, root, count and bonus
+const COMMITMENT_DATA_LEN: usize = 4 + 32 + 2 + 8;
+
+/// Largest environmental bonus a block with `subsidy` may claim
+pub fn max_environmental_bonus(subsidy: u64) -> u64 {
+    (subsidy as u128 * MAX_ENVIRONMENTAL_BONUS_BPS as u128 / 10_000) as u64
+}
+
+/// Environmental bonus earned by a block with `subsidy` whose attestations
+/// earn `bonus_bps` under the REC-priority formula
+///
+/// The largest rate the formula awards maps to the maximum bonus.
+pub fn attested_environmental_bonus(subsidy: u64, bonus_bps: u64) -> u64 {
+    let full_bps = (REC_BONUS_BPS + OFFSET_BONUS_BPS) as u128;
+    let bonus_bps = (bonus_bps as u128).min(full_bps);
+    (max_environmental_bonus(subsidy) as u128 * bonus_bps / full_bps) as u64
+}
+
+/// Coinbase commitment to the attestations behind an environmental bonus
+///
+/// Committed as an OP_RETURN output: tag, Merkle root of the attestations,
+/// attestation count (u16 LE) and claimed bonus (u64 LE). The attestations
+/// themselves follow in one OP_RETURN output each, so every node validates
+/// the bonus from the block alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvironmentalCommitment {
+    /// Merkle root of the attestations' leaf data
+    pub attestation_root: [u8; 32],
+    /// Number of attestations
+    pub attestation_count: u16,
+    /// Bonus the miner claims on top of the subsidy
+    pub bonus_claimed: u64,
+}
+
+impl EnvironmentalCommitment {
+    /// Commit to `attestations` backing `bonus_claimed`
+    pub fn new(attestations: &[RecAttestation], bonus_claimed: u64) -> Self {
+        Self {
+            attestation_root: Self::attestation_root(attestations),
+            attestation_count: attestations.len().min(u16::MAX as usize) as u16,
+            bonus_claimed,
+        }
+    }
+    
+    /// Merkle root of `attestations`
+    pub fn attestation_root(attestations: &[RecAttestation]) -> [u8; 32] {
+        let leaves: Vec<Vec<u8>> = attestations.iter().map(|a| a.leaf_data()).collect();
+        MerkleTree::new(&leaves).root_hash()
+    }
+    
+    /// Whether `attestations` are exactly the committed ones
+    pub fn matches(&self, attestations: &[RecAttestation]) -> bool {
+        attestations.len() == self.attestation_count as usize
+            && Self::attestation_root(attestations) == self.attestation_root
+    }
+    
+    /// Output script carrying the commitment
+    pub fn to_script(&self) -> Vec<u8> {
+        let mut script = Vec::with_capacity(2 + COMMITMENT_DATA_LEN);
+        script.push(0x6a); // OP_RETURN
+        script.push(COMMITMENT_DATA_LEN as u8);
+        script.extend_from_slice(ENVIRONMENTAL_COMMITMENT_TAG);
+        script.extend_from_slice(&self.attestation_root);
+        script.extend_from_slice(&self.attestation_count.to_le_bytes());
+        script.extend_from_slice(&self.bonus_claimed.to_le_bytes());
+        script
+    }
+    
+    /// Parse a commitment output script
+    pub fn from_script(script: &[u8]) -> Option<Self> {
+        if script.len() != 2 + COMMITMENT_DATA_LEN
+            || script[0] != 0x6a
+            || script[1] as usize != COMMITMENT_DATA_LEN
+            || &script[2..6] != ENVIRONMENTAL_COMMITMENT_TAG
+        {
+            return None;
+        }
+        
+        let mut attestation_root = [0u8; 32];
+        attestation_root.copy_from_slice(&script[6..38]);
+        let attestation_count = u16::from_le_bytes([script[38], script[39]]);
+        let mut bonus = [0u8; 8];
+        bonus.copy_from_slice(&script[40..48]);
+        
+        Some(Self {
+            attestation_root,
+            attestation_count,
+            bonus_claimed: u64::from_le_bytes(bonus),
+        })
+    }
+    
+    /// Find the commitment in a coinbase transaction
+    pub fn from_coinbase(tx: &Transaction) -> Option<Self> {
+        if !is_coinbase(tx) {
+            return None;
+        }
+        tx.outputs().iter().find_map(|output| Self::from_script(output.script_pubkey()))
+    }
+    
+    /// Output script carrying one attestation: OP_RETURN, OP_PUSHDATA4 with
+    /// the payload length (u32 LE), then the tag and the encoded attestation
+    pub fn attestation_script(attestation: &RecAttestation) -> Vec<u8> {
+        let encoded = attestation.to_bytes().unwrap_or_default();
+        let mut script = Vec::with_capacity(10 + encoded.len());
+        script.push(0x6a); // OP_RETURN
+        script.push(0x4e); // OP_PUSHDATA4
+        script.extend_from_slice(&((ENVIRONMENTAL_ATTESTATION_TAG.len() + encoded.len()) as u32).to_le_bytes());
+        script.extend_from_slice(ENVIRONMENTAL_ATTESTATION_TAG);
+        script.extend_from_slice(&encoded);
+        script
+    }
+    
+    /// Attestations carried by a coinbase transaction, in output order
+    ///
+    /// Fails if a tagged output does not hold a well-formed attestation.
+    pub fn attestations_from_coinbase(tx: &Transaction) -> Result<Vec<RecAttestation>, AttestationError> {
+        if !is_coinbase(tx) {
+            return Ok(Vec::new());
+        }
+        
+        let mut attestations = Vec::new();
+        for output in tx.outputs() {
+            let script = output.script_pubkey();
+            if script.len() < 10 || script[0] != 0x6a || script[1] != 0x4e || &script[6..10] != ENVIRONMENTAL_ATTESTATION_TAG {
+                continue;
+            }
+            
+            let length = u32::from_le_bytes([script[2], script[3], script[4], script[5]]) as usize;
+            if length != script.len() - 6 {
+                return Err(AttestationError::SerializationError("Truncated attestation output".to_string()));
+            }
+            attestations.push(RecAttestation::from_bytes(&script[10..])?);
+        }
+        Ok(attestations)
+    }
+}
 
 /// Coinbase transaction builder
 pub struct CoinbaseBuilder {
@@ -10,6 +158,10 @@ pub struct CoinbaseBuilder {
     extra_nonce: u64,
     /// Coinbase message
     message: Vec<u8>,
+    /// Environmental commitment output, if the miner claims a bonus
+    environmental_commitment: Option<EnvironmentalCommitment>,
+    /// Attestations behind the commitment, carried in the coinbase
+    environmental_attestations: Vec<RecAttestation>,
 }
 
 impl CoinbaseBuilder {
@@ -19,6 +171,8 @@ impl CoinbaseBuilder {
             height,
             extra_nonce: 0,
             message: Vec::new(),
+            environmental_commitment: None,
+            environmental_attestations: Vec::new(),
         }
     }
     
@@ -34,6 +188,19 @@ impl CoinbaseBuilder {
         self
     }
     
+    /// Commit to the attestations backing an environmental bonus
+    ///
+    /// The reward passed to `build` must include the bonus.
+    pub fn with_environmental_commitment(
+        mut self,
+        attestations: &[RecAttestation],
+        bonus_claimed: u64,
+    ) -> Self {
+        self.environmental_commitment = Some(EnvironmentalCommitment::new(attestations, bonus_claimed));
+        self.environmental_attestations = attestations.to_vec();
+        self
+    }
+    
     /// Build the coinbase transaction
     pub fn build(self, reward: u64, recipient_script: Vec<u8>) -> Transaction {
         // Create coinbase input
@@ -63,13 +230,21 @@ impl CoinbaseBuilder {
         );
         
         // Create output using the proper constructor
-        let output = TransactionOutput::new(reward, recipient_script);
+        let mut outputs = vec![TransactionOutput::new(reward, recipient_script)];
+        
+        // Unspendable commitment output, followed by the attestations it commits to
+        if let Some(commitment) = &self.environmental_commitment {
+            outputs.push(TransactionOutput::new(0, commitment.to_script()));
+            for attestation in &self.environmental_attestations {
+                outputs.push(TransactionOutput::new(0, EnvironmentalCommitment::attestation_script(attestation)));
+            }
+        }
         
         // Create transaction using the proper constructor
         Transaction::new(
             2, // version
             vec![coinbase_input],
-            vec![output],
+            outputs,
             0, // lock_time
         )
     }
@@ -110,6 +285,9 @@ pub fn extract_height(tx: &Transaction) -> Option<u64> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+    use crate::environmental::attestation::{miner_id_for_script, AttestationClaim, AttestationKind, ATTESTATION_VERSION};
+    use crate::environmental::types::EnergySource;
     
     #[test]
     fn test_coinbase_builder() {
@@ -130,4 +308,52 @@ mod tests {
         let height = extract_height(&coinbase);
         assert_eq!(height, Some(123456));
     }
+    
+    #[test]
+    fn test_environmental_commitment_round_trip() {
+        let oracle = QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap();
+        let mut attestation = RecAttestation::new(AttestationClaim {
+            version: ATTESTATION_VERSION,
+            kind: AttestationKind::RenewableCertificate,
+            certificate_id: "REC-2026-0001".to_string(),
+            miner_id: miner_id_for_script(b"miner-1"),
+            energy_milli_mwh: 1_000,
+            period_start: 1_700_000_000,
+            period_end: 1_702_592_000,
+            location: "IS".to_string(),
+            energy_type: EnergySource::Geothermal,
+            oracle_epoch: 1,
+        });
+        attestation.sign(0, &oracle).unwrap();
+        
+        let coinbase = CoinbaseBuilder::new(123456)
+            .with_environmental_commitment(&[attestation.clone()], 1_000_000)
+            .build(5001000000, vec![]);
+        assert_eq!(coinbase.outputs().len(), 3);
+        assert_eq!(coinbase.outputs()[1].value(), 0);
+        
+        let commitment = EnvironmentalCommitment::from_coinbase(&coinbase).unwrap();
+        assert_eq!(commitment.bonus_claimed, 1_000_000);
+        assert!(commitment.matches(&[attestation.clone()]));
+        
+        // The attestations travel in the coinbase itself
+        let carried = EnvironmentalCommitment::attestations_from_coinbase(&coinbase).unwrap();
+        assert_eq!(carried, vec![attestation.clone()]);
+        assert!(commitment.matches(&carried));
+        
+        // Any change to an attestation or its signatures breaks the commitment
+        let mut forged = attestation.clone();
+        forged.claim.energy_milli_mwh = 2_000;
+        assert!(!commitment.matches(&[forged]));
+        let mut resigned = attestation;
+        resigned.sign(1, &oracle).unwrap();
+        assert!(!commitment.matches(&[resigned]));
+        
+        // Full REC coverage with offsets earns the maximum bonus
+        assert_eq!(attested_environmental_bonus(1_000, REC_BONUS_BPS + OFFSET_BONUS_BPS), max_environmental_bonus(1_000));
+        assert_eq!(attested_environmental_bonus(1_000, REC_BONUS_BPS), 535);
+        assert_eq!(attested_environmental_bonus(1_000, 0), 0);
+        
+        assert_eq!(EnvironmentalCommitment::from_coinbase(&CoinbaseBuilder::new(1).build(1, vec![])), None);
+    }
 } 
\ No newline at end of file
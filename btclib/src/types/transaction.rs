@@ -843,9 +843,10 @@ impl Transaction {
             return false;
         }
         
-        // Check for negative or zero outputs 
+        // Check for negative or zero outputs, except unspendable OP_RETURN
+        // outputs carrying data such as coinbase commitments
         for output in &self.outputs {
-            if output.amount == 0 {
+            if output.amount == 0 && output.pub_key_script.first() != Some(&0x6a) {
                 return false;
             }
         }
@@ -2,11 +2,17 @@
 
 use crate::types::block::Block;
 use crate::types::transaction::Transaction;
+use crate::types::coinbase::{attested_environmental_bonus, max_environmental_bonus, EnvironmentalCommitment};
+use crate::environmental::attestation::{miner_id_for_script, AttestationKind, ConsumedCertificates, OracleSetRegistry, RecAttestation};
+use crate::environmental::miner_reporting::rec_priority_bonus_bps;
+use crate::environmental::treasury_ledger::TreasuryConsensus;
 use crate::validation::ValidationError;
+use crate::validation::finality::{check_lock_times, CoinConfirmation};
 use crate::validation::transaction::TransactionValidator;
 use crate::consensus::difficulty::calculate_required_work;
 use crate::hash::Hash256;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{debug, warn};
 
@@ -92,6 +98,34 @@ pub enum BlockValidationError {
     /// Invalid block header
     #[error("Invalid block header: {0}")]
     InvalidHeader(String),
+    
+    /// Environmental bonus claimed without a coinbase commitment
+    #[error("Environmental bonus of {0} claimed without a commitment")]
+    MissingEnvironmentalCommitment(u64),
+    
+    /// Environmental commitment does not verify
+    #[error("Invalid environmental commitment: {0}")]
+    InvalidEnvironmentalCommitment(String),
+    
+    /// Coinbase underpays the environmental treasury
+    #[error("Treasury allocation too low: required {0}, paid {1}")]
+    MissingTreasuryAllocation(u64, u64),
+    
+    /// Treasury output spent without committee authorization
+    #[error("Unauthorized treasury spend: {0}")]
+    UnauthorizedTreasurySpend(String),
+    
+    /// Treasury rules configured without the chain state needed to enforce them
+    #[error("Treasury enforced without {0}")]
+    MissingTreasuryState(String),
+    
+    /// Treasury spend pays out more than its proposal has left
+    #[error("Treasury spend of {1} exceeds the {2} remaining for proposal {0}")]
+    TreasuryBudgetExceeded(String, u64, u64),
+    
+    /// Transaction not final or still sequence locked
+    #[error("Lock time not satisfied: {0}")]
+    LockTimeNotSatisfied(String),
 }
 
 /// Type for validation results
@@ -123,6 +157,16 @@ pub struct BlockValidationConfig {
     
     /// Whether to check proof-of-work
     pub validate_pow: bool,
+    
+    /// Oracle sets whose attestations back environmental bonuses
+    pub attestation_oracles: OracleSetRegistry,
+    
+    /// Energy a block is assumed to take to mine in milli-MWh, which
+    /// attested RECs must cover for the full REC bonus
+    pub block_energy_milli_mwh: u64,
+    
+    /// Environmental treasury rules, if the treasury is enforced on chain
+    pub treasury: Option<TreasuryConsensus>,
 }
 
 impl Default for BlockValidationConfig {
@@ -136,6 +180,9 @@ impl Default for BlockValidationConfig {
             validate_scripts: true,
             validate_witness: true,
             validate_pow: true,
+            attestation_oracles: OracleSetRegistry::new(),
+            block_energy_milli_mwh: 1_000,
+            treasury: None,
         }
     }
 }
@@ -152,8 +199,13 @@ pub struct ValidationContext {
     pub median_time_past: u64,
     /// Current network difficulty
     pub current_difficulty: u32,
-    /// UTXO set accessor (for script validation)
+    /// UTXO set accessor returning the locking script of a spent output
     pub utxo_provider: Option<Box<dyn Fn(&[u8; 32], u32) -> Option<Vec<u8>>>>,
+    /// Treasury funds an approved proposal may still pay out, before this
+    /// block; `None` when the proposal is not approved
+    pub proposal_budget_provider: Option<Box<dyn Fn(&str) -> Option<u64>>>,
+    /// Where a spent output was confirmed, for BIP68 sequence locks
+    pub confirmation_provider: Option<Box<dyn Fn(&[u8; 32], u32) -> Option<CoinConfirmation>>>,
 }
 
 /// Block validator
@@ -163,15 +215,15 @@ pub struct BlockValidator {
     
     /// Transaction validator
     transaction_validator: TransactionValidator,
+    
+    /// Certificates already backing a bonus in connected blocks
+    consumed_certificates: Arc<RwLock<ConsumedCertificates>>,
 }
 
 impl BlockValidator {
     /// Create a new block validator with default settings
     pub fn new() -> Self {
-        Self {
-            config: BlockValidationConfig::default(),
-            transaction_validator: TransactionValidator::new(),
-        }
+        Self::with_config(BlockValidationConfig::default())
     }
     
     /// Create a block validator with custom configuration
@@ -179,7 +231,49 @@ impl BlockValidator {
         Self {
             config,
             transaction_validator: TransactionValidator::new(),
+            consumed_certificates: Arc::new(RwLock::new(ConsumedCertificates::new())),
+        }
+    }
+    
+    /// Track consumed certificates in `consumed`, shared with other validators
+    pub fn with_consumed_certificates(mut self, consumed: Arc<RwLock<ConsumedCertificates>>) -> Self {
+        self.consumed_certificates = consumed;
+        self
+    }
+    
+    /// Validate a block and connect it to the chain
+    ///
+    /// Records the certificates behind the block's environmental bonus so
+    /// later blocks cannot reuse them.
+    pub fn connect_block(
+        &self,
+        block: &Block,
+        context: &ValidationContext,
+    ) -> BlockValidationResult {
+        self.validate_block_with_context(block, context)?;
+        self.record_certificates(block)
+    }
+    
+    /// Record the certificates behind the environmental bonus of a block
+    /// that was already validated, such as one replayed from storage
+    pub fn record_certificates(&self, block: &Block) -> BlockValidationResult {
+        let coinbase = match block.transactions().first() {
+            Some(coinbase) => coinbase,
+            None => return Err(BlockValidationError::MissingCoinbase),
+        };
+        if let Some(commitment) = EnvironmentalCommitment::from_coinbase(coinbase) {
+            let attestations = self.committed_attestations(&commitment, coinbase)?;
+            self.consumed_certificates.write().unwrap()
+                .consume(&attestations, block.height())
+                .map_err(|e| BlockValidationError::InvalidEnvironmentalCommitment(e.to_string()))?;
         }
+        
+        Ok(())
+    }
+    
+    /// Disconnect the blocks from `height` up, releasing their certificates
+    pub fn disconnect_blocks_from(&self, height: u64) {
+        self.consumed_certificates.write().unwrap().disconnect(height);
     }
     
     /// Validate a block with full context
@@ -206,6 +300,49 @@ impl BlockValidator {
         Ok(())
     }
     
+    /// Validate only the block's treasury rules: the coinbase allocation and
+    /// committee authorization of treasury spends
+    ///
+    /// For callers that validate the rest of the block elsewhere.
+    pub fn validate_treasury(
+        &self,
+        block: &Block,
+        context: &ValidationContext,
+    ) -> BlockValidationResult {
+        let (coinbase, spends) = match block.transactions().split_first() {
+            Some(split) => split,
+            None => return Err(BlockValidationError::MissingCoinbase),
+        };
+        
+        self.validate_treasury_allocation(coinbase, self.calculate_block_subsidy(block.height()))?;
+        let mut paid_in_block = HashMap::new();
+        for tx in spends {
+            self.validate_treasury_spend(tx, context, &mut paid_in_block)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Validate only the block's lock times: every transaction must be final
+    /// and past the sequence locks of its inputs
+    ///
+    /// For callers that validate the rest of the block elsewhere.
+    pub fn validate_lock_times(
+        &self,
+        block: &Block,
+        context: &ValidationContext,
+    ) -> BlockValidationResult {
+        let confirmation = |hash: &[u8; 32], index: u32| {
+            context.confirmation_provider.as_ref().and_then(|provider| provider(hash, index))
+        };
+        for tx in block.transactions() {
+            check_lock_times(tx, block.height(), context.median_time_past, confirmation)
+                .map_err(|e| BlockValidationError::LockTimeNotSatisfied(format!("{}: {}", hex::encode(tx.hash()), e)))?;
+        }
+        
+        Ok(())
+    }
+    
     /// Validate a block (simplified, without full context)
     pub fn validate_block(&self, block: &Block) -> BlockValidationResult {
         // Basic validation without chain context
@@ -392,6 +529,7 @@ impl BlockValidator {
         context: &ValidationContext,
     ) -> BlockValidationResult {
         let mut has_coinbase = false;
+        let mut paid_in_block = HashMap::new();
         
         for (index, tx) in block.transactions().iter().enumerate() {
             if index == 0 {
@@ -402,7 +540,7 @@ impl BlockValidator {
                 has_coinbase = true;
                 
                 // Validate coinbase specifics
-                self.validate_coinbase(tx, block, context)?;
+                self.validate_coinbase(tx, block)?;
             } else {
                 // Non-coinbase transactions
                 if tx.is_coinbase() {
@@ -418,6 +556,8 @@ impl BlockValidator {
                 if self.spends_immature_coinbase(tx, block.height(), context) {
                     return Err(BlockValidationError::ImmatureCoinbaseSpend);
                 }
+                
+                self.validate_treasury_spend(tx, context, &mut paid_in_block)?;
             }
         }
         
@@ -425,7 +565,7 @@ impl BlockValidator {
             return Err(BlockValidationError::MissingCoinbase);
         }
         
-        Ok(())
+        self.validate_lock_times(block, context)
     }
     
     /// Basic transaction structure validation
@@ -448,10 +588,30 @@ impl BlockValidator {
         &self,
         coinbase: &Transaction,
         block: &Block,
-        context: &ValidationContext,
     ) -> BlockValidationResult {
-        // Calculate expected subsidy
+        self.validate_treasury_allocation(coinbase, self.calculate_block_subsidy(block.height()))?;
+        
+        // For now, anything above the subsidy is an environmental bonus
+        // In full implementation, would need to account for fees
+        self.validate_environmental_bonus(block, 0)
+    }
+    
+    /// Validate the environmental bonus claimed by the block's coinbase
+    ///
+    /// Whatever the coinbase pays beyond the subsidy and the block's `fees`
+    /// is a bonus, which needs a commitment whose attestations earn it. For
+    /// callers that validate the rest of the block elsewhere.
+    pub fn validate_environmental_bonus(
+        &self,
+        block: &Block,
+        fees: u64,
+    ) -> BlockValidationResult {
+        let coinbase = match block.transactions().first() {
+            Some(coinbase) => coinbase,
+            None => return Err(BlockValidationError::MissingCoinbase),
+        };
         let expected_subsidy = self.calculate_block_subsidy(block.height());
+        let expected_reward = expected_subsidy.saturating_add(fees);
         
         // Calculate actual subsidy (outputs - inputs, but coinbase has no real inputs)
         let actual_subsidy = coinbase.outputs()
@@ -459,15 +619,208 @@ impl BlockValidator {
             .map(|out| out.value())
             .sum::<u64>();
         
-        // For now, just check it doesn't exceed maximum
-        // In full implementation, would need to account for fees
-        if actual_subsidy > expected_subsidy {
+        let bonus = actual_subsidy.saturating_sub(expected_reward);
+        let max_bonus = max_environmental_bonus(expected_subsidy);
+        if bonus > max_bonus {
             return Err(BlockValidationError::InvalidSubsidy(
-                expected_subsidy,
+                expected_reward + max_bonus,
                 actual_subsidy,
             ));
         }
         
+        let commitment = match EnvironmentalCommitment::from_coinbase(coinbase) {
+            Some(commitment) => commitment,
+            None if bonus == 0 => return Ok(()),
+            None => return Err(BlockValidationError::MissingEnvironmentalCommitment(bonus)),
+        };
+        
+        let allowed_bonus = commitment.bonus_claimed.min(max_bonus);
+        if bonus > allowed_bonus {
+            return Err(BlockValidationError::InvalidSubsidy(
+                expected_reward + allowed_bonus,
+                actual_subsidy,
+            ));
+        }
+        
+        self.validate_environmental_commitment(&commitment, block)
+    }
+    
+    /// Attestations the coinbase carries for a commitment, checked against its root
+    fn committed_attestations(
+        &self,
+        commitment: &EnvironmentalCommitment,
+        coinbase: &Transaction,
+    ) -> Result<Vec<RecAttestation>, BlockValidationError> {
+        let invalid = |reason: &str| BlockValidationError::InvalidEnvironmentalCommitment(reason.to_string());
+        
+        let attestations = EnvironmentalCommitment::attestations_from_coinbase(coinbase)
+            .map_err(|e| invalid(&e.to_string()))?;
+        if !commitment.matches(&attestations) {
+            return Err(invalid("attestations do not match the committed root"));
+        }
+        Ok(attestations)
+    }
+    
+    /// Validate the attestations behind an environmental commitment in `block`
+    ///
+    /// Every attestation must be signed by its epoch's oracle set, be issued
+    /// to the miner the coinbase pays, cover a period that started before the
+    /// block and back a certificate no connected block below it has used; a
+    /// competing block may reuse the certificates of the block it replaces.
+    /// The claimed bonus may not exceed what the attested quantities earn
+    /// under the REC-priority formula.
+    pub fn validate_environmental_commitment(
+        &self,
+        commitment: &EnvironmentalCommitment,
+        block: &Block,
+    ) -> BlockValidationResult {
+        let invalid = |reason: &str| Err(BlockValidationError::InvalidEnvironmentalCommitment(reason.to_string()));
+        
+        let coinbase = match block.transactions().first() {
+            Some(coinbase) => coinbase,
+            None => return Err(BlockValidationError::MissingCoinbase),
+        };
+        let attestations = self.committed_attestations(commitment, coinbase)?;
+        if commitment.bonus_claimed > 0 && attestations.is_empty() {
+            return invalid("bonus claimed without attestations");
+        }
+        
+        // The miner is whoever the coinbase reward output pays
+        let miner_id = match coinbase.outputs().first() {
+            Some(output) => miner_id_for_script(output.script_pubkey()),
+            None => return invalid("coinbase has no reward output"),
+        };
+        
+        let consumed = self.consumed_certificates.read().unwrap();
+        let mut certificates = HashSet::new();
+        let mut rec_milli_mwh = 0u64;
+        let mut has_offsets = false;
+        
+        for attestation in &attestations {
+            let claim = &attestation.claim;
+            if let Err(e) = self.config.attestation_oracles.verify(attestation) {
+                return invalid(&format!("{} is not attested by its oracle set: {}", claim.certificate_id, e));
+            }
+            if claim.miner_id != miner_id {
+                return invalid(&format!("{} is attested for another miner", claim.certificate_id));
+            }
+            if claim.period_start > block.timestamp() {
+                return invalid(&format!("{} covers a period after the block", claim.certificate_id));
+            }
+            if !certificates.insert((claim.kind, claim.certificate_id.as_str())) {
+                return invalid(&format!("{} is attested twice", claim.certificate_id));
+            }
+            if let Some(height) = consumed.consumed_at(claim.kind, &claim.certificate_id).filter(|height| *height < block.height()) {
+                return invalid(&format!("{} already backed the bonus at height {}", claim.certificate_id, height));
+            }
+            
+            match claim.kind {
+                AttestationKind::RenewableCertificate => rec_milli_mwh = rec_milli_mwh.saturating_add(claim.energy_milli_mwh),
+                AttestationKind::CarbonOffset => has_offsets = true,
+            }
+        }
+        
+        let subsidy = self.calculate_block_subsidy(block.height());
+        let earned = attested_environmental_bonus(
+            subsidy,
+            rec_priority_bonus_bps(rec_milli_mwh, self.config.block_energy_milli_mwh, has_offsets),
+        );
+        if commitment.bonus_claimed > earned {
+            return invalid(&format!("bonus of {} exceeds the {} the attestations earn", commitment.bonus_claimed, earned));
+        }
+        
+        Ok(())
+    }
+    
+    /// Require the coinbase to pay the treasury its share of the subsidy
+    fn validate_treasury_allocation(
+        &self,
+        coinbase: &Transaction,
+        subsidy: u64,
+    ) -> BlockValidationResult {
+        let treasury = match &self.config.treasury {
+            Some(treasury) => treasury,
+            None => return Ok(()),
+        };
+        
+        let required = treasury.required_allocation(subsidy);
+        let script = treasury.committee.script();
+        let paid = coinbase.outputs()
+            .iter()
+            .filter(|out| out.pub_key_script == script)
+            .map(|out| out.value())
+            .sum::<u64>();
+        if paid < required {
+            return Err(BlockValidationError::MissingTreasuryAllocation(required, paid));
+        }
+        
+        Ok(())
+    }
+    
+    /// Require committee authorization on every input spending a treasury output
+    ///
+    /// Spent outputs are looked up through the context's UTXO provider, and the
+    /// authorizing proposal must be approved by governance with enough budget
+    /// left for the payments, counting earlier spends in the same block in
+    /// `paid_in_block`. Both providers are required once a treasury is
+    /// configured.
+    fn validate_treasury_spend(
+        &self,
+        tx: &Transaction,
+        context: &ValidationContext,
+        paid_in_block: &mut HashMap<String, u64>,
+    ) -> BlockValidationResult {
+        let treasury = match &self.config.treasury {
+            Some(treasury) => treasury,
+            None => return Ok(()),
+        };
+        let utxo_provider = context.utxo_provider.as_ref()
+            .ok_or_else(|| BlockValidationError::MissingTreasuryState("a UTXO provider".to_string()))?;
+        let script = treasury.committee.script();
+        
+        let mut authorizing_proposal: Option<String> = None;
+        for (index, input) in tx.inputs().iter().enumerate() {
+            let spent_script = utxo_provider(&input.prev_tx_hash(), input.prev_output_index());
+            if spent_script.as_deref() != Some(script.as_slice()) {
+                continue;
+            }
+            
+            let proposal_id = treasury.committee.verify_spend(tx, index)
+                .map_err(|e| BlockValidationError::UnauthorizedTreasurySpend(e.to_string()))?;
+            match &authorizing_proposal {
+                Some(first) if *first != proposal_id => {
+                    return Err(BlockValidationError::UnauthorizedTreasurySpend(
+                        format!("inputs authorized by both {} and {}", first, proposal_id)
+                    ));
+                }
+                _ => authorizing_proposal = Some(proposal_id),
+            }
+        }
+        
+        let proposal_id = match authorizing_proposal {
+            Some(proposal_id) => proposal_id,
+            None => return Ok(()),
+        };
+        let budget_provider = context.proposal_budget_provider.as_ref()
+            .ok_or_else(|| BlockValidationError::MissingTreasuryState("a proposal budget provider".to_string()))?;
+        let budget = budget_provider(&proposal_id)
+            .ok_or_else(|| BlockValidationError::UnauthorizedTreasurySpend(
+                format!("proposal {} is not approved", proposal_id)
+            ))?;
+        
+        // Change back to the treasury does not count against the proposal
+        let paid_out = tx.outputs()
+            .iter()
+            .filter(|out| out.pub_key_script != script)
+            .map(|out| out.value())
+            .sum::<u64>();
+        let paid_before = paid_in_block.entry(proposal_id.clone()).or_insert(0);
+        let remaining = budget.saturating_sub(*paid_before);
+        if paid_out > remaining {
+            return Err(BlockValidationError::TreasuryBudgetExceeded(proposal_id, paid_out, remaining));
+        }
+        *paid_before += paid_out;
+        
         Ok(())
     }
     
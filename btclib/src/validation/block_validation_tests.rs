@@ -7,7 +7,11 @@
 mod tests {
     use crate::validation::block::{BlockValidator, BlockValidationConfig, ValidationContext, BlockValidationError};
     use crate::types::block::{Block, BlockHeader};
-    use crate::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+    use crate::types::transaction::{OutPoint, Transaction, TransactionInput, TransactionOutput};
+    use crate::validation::finality::CoinConfirmation;
+    use crate::lightning::payment::PaymentPreimage;
+    use crate::lightning::swap::{SwapHash, SwapScript, SwapTimelock};
+    use secp256k1::{PublicKey, Secp256k1, SecretKey};
     use std::time::{SystemTime, UNIX_EPOCH};
 
     /// Create a test block with specified parameters
@@ -52,6 +56,8 @@ mod tests {
             median_time_past: prev_timestamp - 3600, // 1 hour before previous block
             current_difficulty: 0x1d00ffff,
             utxo_provider: None,
+            proposal_budget_provider: None,
+            confirmation_provider: None,
         }
     }
     
@@ -263,6 +269,8 @@ mod tests {
             median_time_past: 0,
             current_difficulty: 0x1d00ffff,
             utxo_provider: None,
+            proposal_budget_provider: None,
+            confirmation_provider: None,
         };
         
         // Create a block that doesn't meet PoW requirements
@@ -370,6 +378,259 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_environmental_bonus_requires_commitment() {
+        use crate::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+        use crate::environmental::attestation::{
+            miner_id_for_script, AttestationClaim, AttestationKind, OracleSet, OracleSetMember, OracleSetRegistry,
+            RecAttestation, ATTESTATION_VERSION,
+        };
+        use crate::environmental::types::EnergySource;
+        use crate::types::coinbase::{CoinbaseBuilder, EnvironmentalCommitment};
+        
+        let oracle = QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap();
+        let mut oracles = OracleSetRegistry::new();
+        oracles.publish(OracleSet {
+            epoch: 1,
+            threshold: 1,
+            security_level: 2,
+            members: vec![OracleSetMember { oracle_id: "oracle-0".to_string(), public_key: oracle.public_key.clone() }],
+        }).unwrap();
+        let validator = BlockValidator::with_config(BlockValidationConfig {
+            validate_pow: false,
+            attestation_oracles: oracles,
+            ..BlockValidationConfig::default()
+        });
+        
+        let attest = |certificate_id: &str, reward_script: &[u8], energy_milli_mwh: u64| {
+            let mut attestation = RecAttestation::new(AttestationClaim {
+                version: ATTESTATION_VERSION,
+                kind: AttestationKind::RenewableCertificate,
+                certificate_id: certificate_id.to_string(),
+                miner_id: miner_id_for_script(reward_script),
+                energy_milli_mwh,
+                period_start: 1_700_000_000,
+                period_end: 1_702_592_000,
+                location: "IS".to_string(),
+                energy_type: EnergySource::Geothermal,
+                oracle_epoch: 1,
+            });
+            attestation.sign(0, &oracle).unwrap();
+            attestation
+        };
+        let attestation = attest("REC-1", b"miner", 500);
+        
+        let subsidy = 50_000_000_000u64;
+        let bonus = 5_000_000_000u64;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let block_with = |builder: CoinbaseBuilder, reward: u64| {
+            let mut block = Block::new(
+                BlockHeader::new(1, [0; 32], [0; 32], now, 0x1d00ffff, 0),
+                vec![builder.build(reward, b"miner".to_vec())],
+            );
+            block.header.set_height(1);
+            block.header.merkle_root = block.calculate_merkle_root();
+            block
+        };
+        let context = create_test_context(0, [0; 32], now - 600);
+        let committed_with = |attestation: &RecAttestation, bonus: u64| {
+            block_with(
+                CoinbaseBuilder::new(1).with_environmental_commitment(&[attestation.clone()], bonus),
+                subsidy + bonus,
+            )
+        };
+        let rejected = |block: &Block| matches!(
+            validator.validate_block_with_context(block, &context),
+            Err(BlockValidationError::InvalidEnvironmentalCommitment(_))
+        );
+        
+        // A bonus without a commitment is rejected
+        let uncommitted = block_with(CoinbaseBuilder::new(1), subsidy + bonus);
+        match validator.validate_block_with_context(&uncommitted, &context) {
+            Err(BlockValidationError::MissingEnvironmentalCommitment(claimed)) => assert_eq!(claimed, bonus),
+            other => panic!("Expected MissingEnvironmentalCommitment, got {:?}", other),
+        }
+        
+        // A committed bonus backed by the oracle set is accepted
+        let committed = committed_with(&attestation, bonus);
+        assert!(validator.validate_block_with_context(&committed, &context).is_ok());
+        
+        // The coinbase must carry exactly the committed attestations
+        let carrying = |carried: &[RecAttestation]| {
+            let mut outputs = vec![
+                TransactionOutput::new(subsidy + bonus, b"miner".to_vec()),
+                TransactionOutput::new(0, EnvironmentalCommitment::new(&[attestation.clone()], bonus).to_script()),
+            ];
+            outputs.extend(carried.iter().map(|a| TransactionOutput::new(0, EnvironmentalCommitment::attestation_script(a))));
+            let coinbase = Transaction::new(2, committed.transactions()[0].inputs().to_vec(), outputs, 0);
+            let mut block = Block::new(committed.header.clone(), vec![coinbase]);
+            block.header.merkle_root = block.calculate_merkle_root();
+            block
+        };
+        assert!(validator.validate_block_with_context(&carrying(&[attestation.clone()]), &context).is_ok());
+        assert!(rejected(&carrying(&[])));
+        assert!(rejected(&carrying(&[attest("REC-2", b"miner", 500)])));
+        
+        // Signatures outside the oracle set do not count
+        let mut unsigned = attestation.clone();
+        unsigned.signatures.clear();
+        assert!(rejected(&committed_with(&unsigned, bonus)));
+        
+        // Attestations issued to another miner do not back this coinbase
+        assert!(rejected(&committed_with(&attest("REC-3", b"other-miner", 500), bonus)));
+        
+        // The bonus is limited by what the attested energy earns
+        assert!(rejected(&committed_with(&attest("REC-4", b"miner", 10), bonus)));
+        
+        // The bonus cannot exceed the committed claim
+        let overclaimed = block_with(
+            CoinbaseBuilder::new(1).with_environmental_commitment(&[attestation.clone()], bonus),
+            subsidy + bonus + 1,
+        );
+        assert!(matches!(
+            validator.validate_block_with_context(&overclaimed, &context),
+            Err(BlockValidationError::InvalidSubsidy(_, _))
+        ));
+        
+        // Connected certificates cannot back a later block until disconnected,
+        // though a competing block at the same height may reuse them
+        let mut next = committed.clone();
+        next.header.set_height(2);
+        let next_context = create_test_context(1, [0; 32], now - 600);
+        validator.connect_block(&committed, &context).unwrap();
+        assert!(matches!(
+            validator.validate_block_with_context(&next, &next_context),
+            Err(BlockValidationError::InvalidEnvironmentalCommitment(_))
+        ));
+        assert!(validator.validate_block_with_context(&committed, &context).is_ok());
+        validator.disconnect_blocks_from(1);
+        assert!(validator.validate_block_with_context(&next, &next_context).is_ok());
+    }
+    
+    #[test]
+    fn test_treasury_allocation_and_spend_authorization() {
+        use crate::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+        use crate::environmental::treasury_ledger::{TreasuryAuthorization, TreasuryCommittee, TreasuryConsensus};
+        
+        let key = QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap();
+        let committee = TreasuryCommittee {
+            threshold: 1,
+            security_level: 2,
+            members: vec![key.public_key.clone()],
+        };
+        let script = committee.script();
+        let validator = BlockValidator::with_config(BlockValidationConfig {
+            validate_pow: false,
+            treasury: Some(TreasuryConsensus::new(committee.clone())),
+            ..BlockValidationConfig::default()
+        });
+        
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let block_with = |coinbase_outputs: Vec<TransactionOutput>, spends: Vec<Transaction>| {
+            let coinbase = Transaction::new(1, vec![TransactionInput::new_coinbase(vec![])], coinbase_outputs, 0);
+            let mut transactions = vec![coinbase];
+            transactions.extend(spends);
+            let mut block = Block::new(
+                BlockHeader::new(1, [0; 32], [0; 32], now, 0x1d00ffff, 0),
+                transactions,
+            );
+            block.header.set_height(1);
+            block.header.merkle_root = block.calculate_merkle_root();
+            block
+        };
+        let paying = |treasury: u64| vec![
+            TransactionOutput::new(50_000_000_000 - treasury, vec![0xaa]),
+            TransactionOutput::new(treasury, script.clone()),
+        ];
+        
+        // 2% of the subsidy must go to the treasury script
+        let underpaid = block_with(paying(999_999_999), vec![]);
+        match validator.validate_block_with_context(&underpaid, &create_test_context(0, [0; 32], now - 600)) {
+            Err(BlockValidationError::MissingTreasuryAllocation(required, paid)) => {
+                assert_eq!(required, 1_000_000_000);
+                assert_eq!(paid, 999_999_999);
+            }
+            other => panic!("Expected MissingTreasuryAllocation, got {:?}", other),
+        }
+        let paid = block_with(paying(1_000_000_000), vec![]);
+        assert!(validator.validate_block_with_context(&paid, &create_test_context(0, [0; 32], now - 600)).is_ok());
+        
+        // Spending a treasury output needs the committee's signatures on an
+        // approved proposal with budget left
+        let treasury_outpoint = [3u8; 32];
+        let context_with_treasury_utxo = || {
+            let mut context = create_test_context(0, [0; 32], now - 600);
+            let script = script.clone();
+            context.utxo_provider = Some(Box::new(move |hash: &[u8; 32], index: u32| {
+                (*hash == treasury_outpoint && index == 0).then(|| script.clone())
+            }));
+            context.proposal_budget_provider = Some(Box::new(|proposal_id: &str| {
+                (proposal_id == "proposal-1").then_some(1_500)
+            }));
+            context
+        };
+        let spend_with = |signature_script: Vec<u8>| Transaction::new(
+            1,
+            vec![TransactionInput::new(treasury_outpoint, 0, signature_script, 0xffffffff)],
+            vec![TransactionOutput::new(1_000, vec![0xbb])],
+            0,
+        );
+        
+        let unsigned = spend_with(TreasuryAuthorization::new("proposal-1").to_script());
+        let unauthorized = block_with(paying(1_000_000_000), vec![unsigned.clone()]);
+        assert!(matches!(
+            validator.validate_block_with_context(&unauthorized, &context_with_treasury_utxo()),
+            Err(BlockValidationError::UnauthorizedTreasurySpend(_))
+        ));
+        
+        let mut authorization = TreasuryAuthorization::new("proposal-1");
+        committee.sign_spend(&mut authorization, &unsigned, 0, &key).unwrap();
+        let authorized = block_with(paying(1_000_000_000), vec![spend_with(authorization.to_script())]);
+        assert!(validator.validate_block_with_context(&authorized, &context_with_treasury_utxo()).is_ok());
+        assert!(validator.validate_treasury(&authorized, &context_with_treasury_utxo()).is_ok());
+        
+        // Signed for a proposal governance has not approved
+        let mut unapproved = TreasuryAuthorization::new("proposal-2");
+        committee.sign_spend(&mut unapproved, &unsigned, 0, &key).unwrap();
+        let unapproved = block_with(paying(1_000_000_000), vec![spend_with(unapproved.to_script())]);
+        assert!(matches!(
+            validator.validate_block_with_context(&unapproved, &context_with_treasury_utxo()),
+            Err(BlockValidationError::UnauthorizedTreasurySpend(_))
+        ));
+        
+        // Payments beyond the proposal's remaining budget, alone or together
+        // with an earlier spend in the same block
+        let overpaying = Transaction::new(
+            1,
+            vec![TransactionInput::new(treasury_outpoint, 0, Vec::new(), 0xffffffff)],
+            vec![TransactionOutput::new(1_501, vec![0xbb]), TransactionOutput::new(500, script.clone())],
+            0,
+        );
+        let mut authorization = TreasuryAuthorization::new("proposal-1");
+        committee.sign_spend(&mut authorization, &overpaying, 0, &key).unwrap();
+        let overpaying = Transaction::new(
+            1,
+            vec![TransactionInput::new(treasury_outpoint, 0, authorization.to_script(), 0xffffffff)],
+            overpaying.outputs().to_vec(),
+            0,
+        );
+        assert!(matches!(
+            validator.validate_treasury(&block_with(paying(1_000_000_000), vec![overpaying]), &context_with_treasury_utxo()),
+            Err(BlockValidationError::TreasuryBudgetExceeded(_, 1_501, 1_500))
+        ));
+        let twice = block_with(paying(1_000_000_000), vec![authorized.transactions()[1].clone(); 2]);
+        assert!(matches!(
+            validator.validate_treasury(&twice, &context_with_treasury_utxo()),
+            Err(BlockValidationError::TreasuryBudgetExceeded(_, 1_000, 500))
+        ));
+        
+        // Treasury spends cannot be recognized without the UTXO set
+        assert!(matches!(
+            validator.validate_block_with_context(&authorized, &create_test_context(0, [0; 32], now - 600)),
+            Err(BlockValidationError::MissingTreasuryState(_))
+        ));
+    }
+    
     #[test]
     fn test_attack_scenario_crafted_header() {
         // This test simulates the attack where a malicious node sends
@@ -408,4 +669,46 @@ mod tests {
             e => panic!("Unexpected error type: {:?}", e),
         }
     }
+    
+    #[test]
+    fn test_premature_swap_refunds_are_rejected() {
+        let secp = Secp256k1::new();
+        let claim_key = SecretKey::from_slice(&[1u8; 32]).unwrap();
+        let refund_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+        let preimage = PaymentPreimage::new_random();
+        let swap = |timelock| SwapScript::new(
+            preimage.payment_hash(),
+            SwapHash::from_preimage(&preimage),
+            &PublicKey::from_secret_key(&secp, &claim_key).serialize(),
+            &PublicKey::from_secret_key(&secp, &refund_key).serialize(),
+            timelock,
+        );
+        let htlc = OutPoint { txid: [7u8; 32], vout: 0 };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        
+        // Refund alongside a coinbase at `height`, spending an HTLC confirmed at 1_000
+        let validate_at = |height: u64, refund: &Transaction| {
+            let block = create_test_block(height, [0; 32], now, 1);
+            let block = Block::new(block.header.clone(), vec![block.transactions()[0].clone(), refund.clone()]);
+            let mut context = create_test_context(height - 1, [0; 32], now);
+            context.confirmation_provider = Some(Box::new(|_: &[u8; 32], _| {
+                Some(CoinConfirmation { height: 1_000, median_time_past: 0 })
+            }));
+            BlockValidator::new().validate_lock_times(&block, &context)
+        };
+        
+        // The script accepts a CLTV refund whatever the height, the block does not
+        let refund = swap(SwapTimelock::Absolute(800))
+            .refund_transaction(&htlc, 50_000, 100, vec![0xbb], &refund_key)
+            .unwrap();
+        assert!(matches!(validate_at(800, &refund), Err(BlockValidationError::LockTimeNotSatisfied(_))));
+        validate_at(801, &refund).unwrap();
+        
+        // A CSV refund waits 144 blocks after the HTLC confirmed
+        let refund = swap(SwapTimelock::Relative(144))
+            .refund_transaction(&htlc, 50_000, 100, vec![0xbb], &refund_key)
+            .unwrap();
+        assert!(matches!(validate_at(1_143, &refund), Err(BlockValidationError::LockTimeNotSatisfied(_))));
+        validate_at(1_144, &refund).unwrap();
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,164 @@
+//! Transaction finality and relative lock times
+//!
+//! OP_CHECKLOCKTIMEVERIFY and OP_CHECKSEQUENCEVERIFY only compare their
+//! argument against the spending transaction's own lock time and sequence
+//! numbers. These checks tie those fields to the chain: a transaction whose
+//! lock time has not been reached is not final (IsFinalTx), and an input
+//! whose BIP68 relative lock time has not elapsed since its output was
+//! confirmed is still locked.
+
+use thiserror::Error;
+
+use crate::script::interpreter::{
+    LOCKTIME_THRESHOLD, SEQUENCE_FINAL, SEQUENCE_LOCKTIME_DISABLE_FLAG, SEQUENCE_LOCKTIME_MASK,
+    SEQUENCE_LOCKTIME_TYPE_FLAG,
+};
+use crate::types::transaction::Transaction;
+
+/// Time-based relative lock times count in units of 2^9 = 512 seconds
+pub const SEQUENCE_LOCKTIME_GRANULARITY: u32 = 9;
+
+/// Errors from lock time checks
+#[derive(Debug, Error)]
+pub enum FinalityError {
+    #[error("Transaction is not final: lock time {0} not reached")]
+    NonFinal(u32),
+    
+    #[error("Input {0} is still locked by its relative lock time")]
+    SequenceLocked(usize),
+    
+    #[error("Confirmation of the output spent by input {0} is unknown")]
+    UnknownConfirmation(usize),
+}
+
+/// Where an output spent by a transaction was confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoinConfirmation {
+    /// Height of the block that created the output
+    pub height: u64,
+    /// Median time past of the block before it
+    pub median_time_past: u64,
+}
+
+/// Whether `tx` may be included in a block at `block_height`
+///
+/// A lock time below `LOCKTIME_THRESHOLD` is a height, anything else a
+/// timestamp compared against `block_time`, the median time past for
+/// blocks (BIP113). Final sequence numbers on every input disable the lock.
+pub fn is_final_tx(tx: &Transaction, block_height: u64, block_time: u64) -> bool {
+    let lock_time = tx.lock_time();
+    if lock_time == 0 {
+        return true;
+    }
+    
+    let reference = if lock_time < LOCKTIME_THRESHOLD { block_height } else { block_time };
+    if (lock_time as u64) < reference {
+        return true;
+    }
+    
+    tx.inputs().iter().all(|input| input.sequence() == SEQUENCE_FINAL)
+}
+
+/// Check that `tx` is final and its BIP68 sequence locks have elapsed in a
+/// block at `block_height` with `median_time_past`
+///
+/// `confirmation` locates the output each input spends. Relative lock
+/// times only apply to version 2 and later transactions, and an input with
+/// the disable flag set has none.
+pub fn check_lock_times<F>(
+    tx: &Transaction,
+    block_height: u64,
+    median_time_past: u64,
+    confirmation: F,
+) -> Result<(), FinalityError>
+where
+    F: Fn(&[u8; 32], u32) -> Option<CoinConfirmation>,
+{
+    if !is_final_tx(tx, block_height, median_time_past) {
+        return Err(FinalityError::NonFinal(tx.lock_time()));
+    }
+    if tx.version() < 2 || tx.is_coinbase() {
+        return Ok(());
+    }
+    
+    for (index, input) in tx.inputs().iter().enumerate() {
+        let sequence = input.sequence();
+        if sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+            continue;
+        }
+        
+        let coin = confirmation(&input.prev_tx_hash(), input.prev_output_index())
+            .ok_or(FinalityError::UnknownConfirmation(index))?;
+        let value = (sequence & SEQUENCE_LOCKTIME_MASK) as u64;
+        let locked = if sequence & SEQUENCE_LOCKTIME_TYPE_FLAG != 0 {
+            coin.median_time_past + (value << SEQUENCE_LOCKTIME_GRANULARITY) > median_time_past
+        } else {
+            coin.height + value > block_height
+        };
+        if locked {
+            return Err(FinalityError::SequenceLocked(index));
+        }
+    }
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::transaction::{TransactionInput, TransactionOutput};
+    
+    fn spend(version: u32, sequence: u32, lock_time: u32) -> Transaction {
+        Transaction::new(
+            version,
+            vec![TransactionInput::new([1u8; 32], 0, Vec::new(), sequence)],
+            vec![TransactionOutput::new(1_000, vec![0xaa])],
+            lock_time,
+        )
+    }
+    
+    fn confirmed_at(height: u64) -> impl Fn(&[u8; 32], u32) -> Option<CoinConfirmation> {
+        move |_, _| Some(CoinConfirmation { height, median_time_past: 1_700_000_000 })
+    }
+    
+    #[test]
+    fn test_lock_time_finality() {
+        // Height lock 800 is final from block 801
+        let tx = spend(2, 0xFFFF_FFFE, 800);
+        assert!(!is_final_tx(&tx, 800, 0));
+        assert!(is_final_tx(&tx, 801, 0));
+        
+        // Timestamp locks compare against the median time past
+        let tx = spend(2, 0xFFFF_FFFE, 1_700_000_000);
+        assert!(!is_final_tx(&tx, 1_000_000, 1_700_000_000));
+        assert!(is_final_tx(&tx, 0, 1_700_000_001));
+        
+        // Final sequence numbers disable the lock
+        assert!(is_final_tx(&spend(2, SEQUENCE_FINAL, 800), 1, 0));
+        assert!(matches!(
+            check_lock_times(&spend(2, 0xFFFF_FFFE, 800), 800, 0, confirmed_at(1)),
+            Err(FinalityError::NonFinal(800))
+        ));
+    }
+    
+    #[test]
+    fn test_sequence_locks() {
+        // 144 blocks after confirmation at 1_000
+        let tx = spend(2, 144, 0);
+        assert!(matches!(check_lock_times(&tx, 1_143, 0, confirmed_at(1_000)), Err(FinalityError::SequenceLocked(0))));
+        check_lock_times(&tx, 1_144, 0, confirmed_at(1_000)).unwrap();
+        
+        // Two 512-second units after the coin's median time past
+        let tx = spend(2, SEQUENCE_LOCKTIME_TYPE_FLAG | 2, 0);
+        assert!(check_lock_times(&tx, 2_000, 1_700_001_023, confirmed_at(1_000)).is_err());
+        check_lock_times(&tx, 2_000, 1_700_001_024, confirmed_at(1_000)).unwrap();
+        
+        // Version 1 transactions and disabled inputs have no relative lock
+        check_lock_times(&spend(1, 144, 0), 1_001, 0, confirmed_at(1_000)).unwrap();
+        check_lock_times(&spend(2, SEQUENCE_LOCKTIME_DISABLE_FLAG | 144, 0), 1_001, 0, |_: &[u8; 32], _| None).unwrap();
+        assert!(matches!(
+            check_lock_times(&tx, 2_000, 1_700_001_024, |_: &[u8; 32], _| None),
+            Err(FinalityError::UnknownConfirmation(0))
+        ));
+    }
+}
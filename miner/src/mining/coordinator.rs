@@ -216,6 +216,32 @@ impl Miner {
         info!("Mining stopped");
     }
     
+    pub fn is_stopped(&self) -> bool {
+        self.stop_signal.load(Ordering::Relaxed)
+    }
+    
+    pub fn workers(&self) -> &[Arc<MiningWorker>] {
+        &self.workers
+    }
+    
+    pub fn worker_count(&self) -> usize {
+        self.workers.len()
+    }
+    
+    /// Run only the first `active` workers, pausing the rest
+    pub fn set_active_workers(&self, active: usize) -> usize {
+        let active = active.min(self.workers.len());
+        for (i, worker) in self.workers.iter().enumerate() {
+            if i < active {
+                worker.resume();
+            } else {
+                worker.pause();
+            }
+        }
+        self.metrics.active_workers.store(active as u64, Ordering::Relaxed);
+        active
+    }
+    
     pub fn request_template_refresh(&self) {
         self.template_refresh_signal.store(true, Ordering::Relaxed);
     }
@@ -81,6 +81,7 @@ impl EnvironmentalVerifier {
             efficiency_score: verified_efficiency,
             verified: true,
             rec_coverage: renewable_percentage,
+            curtailment_score: 0.0,
         };
         
         let miner_profile = VerifiedMinerProfile {
@@ -263,6 +264,7 @@ mod tests {
             efficiency_score: 1.0,
             verified: false,
             rec_coverage: 1.0,
+            curtailment_score: 0.0,
         };
         
         let result = verifier.verify_miner_profile(
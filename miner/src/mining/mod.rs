@@ -3,6 +3,7 @@ pub mod template;
 pub mod worker;
 pub mod reward;
 pub mod environmental_verification;
+pub mod scheduler;
 
 #[cfg(test)]
 mod halving_test;
@@ -20,6 +21,7 @@ pub use template::{BlockTemplate, MempoolInterface};
 pub use worker::MiningWorker;
 pub use reward::{EnvironmentalProfile, MiningReward, calculate_mining_reward, calculate_base_reward};
 pub use environmental_verification::{EnvironmentalVerifier, RECCertificate, EfficiencyAudit};
+pub use scheduler::{CarbonAwareScheduler, SchedulerConfig, SchedulerError, ScheduleSlot, CurtailmentEvent, CurtailmentReason};
 
 pub const NOVA_TOTAL_SUPPLY: u64 = 42_000_000;
 pub const NOVA_BLOCK_REWARD: u64 = 50; // Initial block reward in NOVA
@@ -29,4 +31,5 @@ pub const MAX_HALVINGS: u32 = 64; // Maximum number of halvings (same as Bitcoin
 // Environmental bonus constants
 pub const ENV_BONUS_RENEWABLE: f64 = 0.20; // 20% bonus for verified renewable energy
 pub const ENV_BONUS_EFFICIENCY: f64 = 0.10; // 10% bonus for exceptional efficiency
+pub const ENV_BONUS_CURTAILMENT: f64 = 0.05; // 5% bonus for shedding load during dirty grid hours
 pub const ENV_BONUS_MAX_TOTAL: f64 = 0.75; // Maximum 75% total bonus
\ No newline at end of file
@@ -1,4 +1,4 @@
-use super::{NOVA_BLOCK_REWARD, HALVING_INTERVAL, MAX_HALVINGS, ENV_BONUS_RENEWABLE, ENV_BONUS_EFFICIENCY, ENV_BONUS_MAX_TOTAL};
+use super::{NOVA_BLOCK_REWARD, HALVING_INTERVAL, MAX_HALVINGS, ENV_BONUS_RENEWABLE, ENV_BONUS_EFFICIENCY, ENV_BONUS_CURTAILMENT, ENV_BONUS_MAX_TOTAL};
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -7,6 +7,8 @@ pub struct EnvironmentalProfile {
     pub efficiency_score: f64,      // 0.0 to 1.0
     pub verified: bool,
     pub rec_coverage: f64,          // Renewable Energy Certificate coverage
+    #[serde(default)]
+    pub curtailment_score: f64,     // 0.0 to 1.0, share of dirty-hour load shed
 }
 
 impl Default for EnvironmentalProfile {
@@ -16,6 +18,7 @@ impl Default for EnvironmentalProfile {
             efficiency_score: 0.0,
             verified: false,
             rec_coverage: 0.0,
+            curtailment_score: 0.0,
         }
     }
 }
@@ -66,6 +69,11 @@ pub fn calculate_environmental_bonus(base_reward: u64, profile: &EnvironmentalPr
         bonus_multiplier += 0.05 * profile.rec_coverage; // Up to 5% for full REC coverage
     }
     
+    // Curtailment bonus (up to 5%)
+    if profile.curtailment_score > 0.0 {
+        bonus_multiplier += ENV_BONUS_CURTAILMENT * profile.curtailment_score.min(1.0);
+    }
+    
     // Cap total bonus at maximum
     bonus_multiplier = bonus_multiplier.min(ENV_BONUS_MAX_TOTAL);
     
@@ -145,6 +153,7 @@ mod tests {
             efficiency_score: 1.0,
             verified: false,
             rec_coverage: 1.0,
+            curtailment_score: 0.0,
         };
         assert_eq!(calculate_environmental_bonus(base_reward, &unverified_profile), 0);
         
@@ -154,6 +163,7 @@ mod tests {
             efficiency_score: 0.0,
             verified: true,
             rec_coverage: 0.0,
+            curtailment_score: 0.0,
         };
         assert_eq!(calculate_environmental_bonus(base_reward, &renewable_profile), 10 * 100_000_000); // 10 NOVA
         
@@ -163,6 +173,7 @@ mod tests {
             efficiency_score: 1.0,      // 10% bonus
             verified: true,
             rec_coverage: 1.0,          // 5% bonus
+            curtailment_score: 0.0,
         };
         assert_eq!(calculate_environmental_bonus(base_reward, &combined_profile), 17_50000000); // 17.5 NOVA (35% bonus)
         
@@ -172,6 +183,7 @@ mod tests {
             efficiency_score: 0.5,      // 5% bonus
             verified: true,
             rec_coverage: 0.0,
+            curtailment_score: 0.0,
         };
         assert_eq!(calculate_environmental_bonus(base_reward, &partial_profile), 7_50000000); // 7.5 NOVA (15% bonus)
     }
@@ -184,6 +196,7 @@ mod tests {
             efficiency_score: 1.0,
             verified: true,
             rec_coverage: 1.0,
+            curtailment_score: 0.0,
         };
         
         let reward = calculate_mining_reward(0, &env_profile);
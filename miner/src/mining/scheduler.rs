@@ -0,0 +1,478 @@
+//! Carbon-aware mining scheduler
+//!
+//! The scheduler plans each hour from a grid carbon-intensity forecast and
+//! the miner's power and price caps, then pauses `MiningWorker`s to match:
+//! above `throttle_intensity` the active share falls linearly until mining
+//! stops at `pause_intensity`, hours priced over the cap are skipped, and
+//! the power cap limits how many workers may run at all.
+//!
+//! Hours run below full power are recorded as `CurtailmentEvent`s. The share
+//! of dirty-hour load that was shed becomes the profile's
+//! `curtailment_score`, which earns an environmental bonus.
+
+use std::sync::Arc;
+
+use btclib::environmental::grid_data::{GridDataError, GridDataSource};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use super::coordinator::Miner;
+use super::reward::EnvironmentalProfile;
+
+#[derive(Debug, Error)]
+pub enum SchedulerError {
+    #[error("Invalid scheduler configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Grid data error: {0}")]
+    Grid(#[from] GridDataError),
+    #[error("Forecast does not cover {0}")]
+    NoForecast(DateTime<Utc>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    /// Grid zone the miner draws from
+    pub zone: String,
+    /// Intensity (gCO2/kWh) above which mining is throttled
+    pub throttle_intensity: f64,
+    /// Intensity (gCO2/kWh) at and above which mining pauses
+    pub pause_intensity: f64,
+    /// Power draw with every worker running, in kW
+    pub full_power_kw: f64,
+    /// Maximum power draw, in kW
+    pub max_power_kw: Option<f64>,
+    /// Maximum electricity price per MWh
+    pub max_price_per_mwh: Option<f64>,
+    /// Tariff per MWh for each UTC hour of the day (24 entries)
+    pub hourly_prices: Option<Vec<f64>>,
+    /// Hours of forecast to plan ahead
+    pub forecast_hours: u32,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            zone: String::new(),
+            throttle_intensity: 300.0,
+            pause_intensity: 500.0,
+            full_power_kw: 1.0,
+            max_power_kw: None,
+            max_price_per_mwh: None,
+            hourly_prices: None,
+            forecast_hours: 24,
+        }
+    }
+}
+
+impl SchedulerConfig {
+    pub fn validate(&self) -> Result<(), SchedulerError> {
+        if self.zone.is_empty() {
+            return Err(SchedulerError::InvalidConfig("Zone is required".to_string()));
+        }
+        if !(self.throttle_intensity >= 0.0 && self.throttle_intensity < self.pause_intensity) {
+            return Err(SchedulerError::InvalidConfig(
+                "Throttle intensity must be below pause intensity".to_string()
+            ));
+        }
+        if !(self.full_power_kw > 0.0) {
+            return Err(SchedulerError::InvalidConfig("Full power must be positive".to_string()));
+        }
+        if self.max_power_kw.map_or(false, |cap| !(cap >= 0.0)) {
+            return Err(SchedulerError::InvalidConfig("Power cap must not be negative".to_string()));
+        }
+        if self.max_price_per_mwh.is_some() && self.hourly_prices.is_none() {
+            return Err(SchedulerError::InvalidConfig("Price cap requires hourly prices".to_string()));
+        }
+        if self.hourly_prices.as_ref().map_or(false, |prices| prices.len() != 24) {
+            return Err(SchedulerError::InvalidConfig("Hourly prices need 24 entries".to_string()));
+        }
+        Ok(())
+    }
+    
+    /// Tariff for the hour containing `time`
+    pub fn price_at(&self, time: DateTime<Utc>) -> Option<f64> {
+        self.hourly_prices.as_ref().map(|prices| prices[time.hour() as usize])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CurtailmentReason {
+    /// Grid carbon intensity above the throttle threshold
+    CarbonIntensity,
+    /// Electricity price above the cap
+    Price,
+    /// Power cap below full power
+    PowerCap,
+}
+
+/// Planned operation for one forecast hour
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleSlot {
+    pub start: DateTime<Utc>,
+    pub carbon_intensity: f64,
+    pub price_per_mwh: Option<f64>,
+    /// Share of full power to run at, 0.0 to 1.0
+    pub active_fraction: f64,
+    /// Binding reason when running below full power
+    pub reason: Option<CurtailmentReason>,
+}
+
+/// A period run below full power
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurtailmentEvent {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub reason: CurtailmentReason,
+    /// Share of workers that kept running
+    pub active_fraction: f64,
+    pub carbon_intensity: f64,
+    /// Energy not drawn, in kWh
+    pub curtailed_energy_kwh: f64,
+    /// Emissions avoided at the grid's intensity, in kg CO2e
+    pub avoided_emissions_kg: f64,
+}
+
+/// Operation applied at the last tick
+#[derive(Debug, Clone)]
+struct AppliedSlot {
+    at: DateTime<Utc>,
+    slot: ScheduleSlot,
+    /// Active share actually achieved with whole workers
+    fraction: f64,
+}
+
+pub struct CarbonAwareScheduler {
+    source: Arc<dyn GridDataSource>,
+    config: SchedulerConfig,
+    plan: Vec<ScheduleSlot>,
+    applied: Option<AppliedSlot>,
+    open_event: Option<CurtailmentEvent>,
+    events: Vec<CurtailmentEvent>,
+    /// Hours spent above the throttle intensity
+    dirty_hours: f64,
+    /// Full-power hours shed during dirty hours
+    dirty_hours_shed: f64,
+}
+
+impl CarbonAwareScheduler {
+    pub fn new(source: Arc<dyn GridDataSource>, config: SchedulerConfig) -> Result<Self, SchedulerError> {
+        config.validate()?;
+        Ok(Self {
+            source,
+            config,
+            plan: Vec::new(),
+            applied: None,
+            open_event: None,
+            events: Vec::new(),
+            dirty_hours: 0.0,
+            dirty_hours_shed: 0.0,
+        })
+    }
+    
+    /// Fetch a new forecast and rebuild the plan
+    pub async fn refresh(&mut self) -> Result<&[ScheduleSlot], SchedulerError> {
+        let forecast = self.source.forecast(&self.config.zone, self.config.forecast_hours).await?;
+        self.plan = forecast.iter()
+            .map(|reading| self.plan_slot(reading.timestamp, reading.carbon_intensity))
+            .collect();
+        self.plan.sort_by_key(|slot| slot.start);
+        Ok(&self.plan)
+    }
+    
+    /// Current plan
+    pub fn plan(&self) -> &[ScheduleSlot] {
+        &self.plan
+    }
+    
+    /// Decide how hard to run for one hour
+    pub fn plan_slot(&self, start: DateTime<Utc>, carbon_intensity: f64) -> ScheduleSlot {
+        let price_per_mwh = self.config.price_at(start);
+        let mut active_fraction = 1.0;
+        let mut reason = None;
+        
+        if carbon_intensity > self.config.throttle_intensity {
+            active_fraction = ((self.config.pause_intensity - carbon_intensity)
+                / (self.config.pause_intensity - self.config.throttle_intensity))
+                .clamp(0.0, 1.0);
+            reason = Some(CurtailmentReason::CarbonIntensity);
+        }
+        if let (Some(cap), Some(price)) = (self.config.max_price_per_mwh, price_per_mwh) {
+            if price > cap && active_fraction > 0.0 {
+                active_fraction = 0.0;
+                reason = Some(CurtailmentReason::Price);
+            }
+        }
+        if let Some(cap) = self.config.max_power_kw {
+            let cap_fraction = (cap / self.config.full_power_kw).clamp(0.0, 1.0);
+            if cap_fraction < active_fraction {
+                active_fraction = cap_fraction;
+                reason = Some(CurtailmentReason::PowerCap);
+            }
+        }
+        
+        ScheduleSlot { start, carbon_intensity, price_per_mwh, active_fraction, reason }
+    }
+    
+    /// Slot of the plan covering `now`
+    pub fn slot_at(&self, now: DateTime<Utc>) -> Option<&ScheduleSlot> {
+        self.plan.iter()
+            .rev()
+            .find(|slot| slot.start <= now && now < slot.start + Duration::hours(1))
+    }
+    
+    /// Apply the plan for `now` to the miner's workers
+    ///
+    /// Refreshes the forecast when the plan does not cover `now`. If the
+    /// refresh fails the workers are left as they are.
+    pub async fn tick(&mut self, miner: &Miner, now: DateTime<Utc>) -> Result<ScheduleSlot, SchedulerError> {
+        if self.slot_at(now).is_none() {
+            self.refresh().await?;
+        }
+        let slot = self.slot_at(now).cloned().ok_or(SchedulerError::NoForecast(now))?;
+        
+        let workers = miner.worker_count();
+        let active = miner.set_active_workers((slot.active_fraction * workers as f64).floor() as usize);
+        let fraction = if workers > 0 { active as f64 / workers as f64 } else { 1.0 };
+        
+        self.account(now);
+        let reason = if fraction < 1.0 {
+            slot.reason.or(Some(CurtailmentReason::PowerCap))
+        } else {
+            None
+        };
+        self.track_event(now, reason, fraction, slot.carbon_intensity);
+        
+        let changed = self.applied.as_ref().map_or(true, |applied| applied.fraction != fraction);
+        if changed {
+            info!(
+                "Carbon-aware scheduler: {} of {} workers active ({:.0} gCO2/kWh)",
+                active, workers, slot.carbon_intensity
+            );
+        }
+        self.applied = Some(AppliedSlot { at: now, slot: slot.clone(), fraction });
+        Ok(slot)
+    }
+    
+    /// Tick every `interval` until the miner is stopped
+    pub async fn run(mut self, miner: Miner, interval: std::time::Duration) -> Self {
+        let mut ticker = tokio::time::interval(interval);
+        while !miner.is_stopped() {
+            ticker.tick().await;
+            if let Err(e) = self.tick(&miner, Utc::now()).await {
+                warn!("Carbon-aware scheduler: {}", e);
+            }
+        }
+        self.finish(Utc::now());
+        self
+    }
+    
+    /// Close the open curtailment event at `now`
+    pub fn finish(&mut self, now: DateTime<Utc>) {
+        self.account(now);
+        if let Some(event) = self.open_event.take() {
+            self.events.push(event);
+        }
+        self.applied = None;
+    }
+    
+    /// Closed curtailment events
+    pub fn events(&self) -> &[CurtailmentEvent] {
+        &self.events
+    }
+    
+    /// Share of dirty-hour load that was shed, 0.0 to 1.0
+    pub fn curtailment_score(&self) -> f64 {
+        if self.dirty_hours > 0.0 {
+            (self.dirty_hours_shed / self.dirty_hours).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+    
+    /// Report the curtailment score into a profile
+    pub fn report(&self, profile: &mut EnvironmentalProfile) {
+        profile.curtailment_score = self.curtailment_score();
+    }
+    
+    /// Charge the time since the last tick to the slot that was applied
+    fn account(&mut self, now: DateTime<Utc>) {
+        let Some(applied) = &self.applied else {
+            return;
+        };
+        let hours = (now - applied.at).num_milliseconds().max(0) as f64 / 3_600_000.0;
+        let shed = 1.0 - applied.fraction;
+        
+        if applied.slot.carbon_intensity > self.config.throttle_intensity {
+            self.dirty_hours += hours;
+            self.dirty_hours_shed += hours * shed;
+        }
+        if let Some(event) = &mut self.open_event {
+            let energy = hours * shed * self.config.full_power_kw;
+            event.end = now;
+            event.curtailed_energy_kwh += energy;
+            event.avoided_emissions_kg += energy * applied.slot.carbon_intensity / 1000.0;
+        }
+    }
+    
+    /// Open, continue or close the curtailment event
+    fn track_event(&mut self, now: DateTime<Utc>, reason: Option<CurtailmentReason>, fraction: f64, carbon_intensity: f64) {
+        let continues = match (&self.open_event, reason) {
+            (Some(event), Some(reason)) => event.reason == reason && event.active_fraction == fraction,
+            _ => false,
+        };
+        if continues {
+            return;
+        }
+        if let Some(event) = self.open_event.take() {
+            self.events.push(event);
+        }
+        if let Some(reason) = reason {
+            self.open_event = Some(CurtailmentEvent {
+                start: now,
+                end: now,
+                reason,
+                active_fraction: fraction,
+                carbon_intensity,
+                curtailed_energy_kwh: 0.0,
+                avoided_emissions_kg: 0.0,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use btclib::environmental::grid_data::GridReading;
+    use btclib::types::transaction::Transaction;
+    use std::collections::HashMap;
+    use std::sync::atomic::Ordering;
+    use crate::mining::reward::calculate_mining_reward;
+    use crate::mining::template::MempoolInterface;
+    
+    struct MockMempool;
+    
+    #[async_trait]
+    impl MempoolInterface for MockMempool {
+        async fn get_transactions(&self, _max_size: usize) -> Vec<Transaction> {
+            Vec::new()
+        }
+    }
+    
+    /// Forecast of fixed hourly intensities starting at `start`
+    struct ForecastSource {
+        start: DateTime<Utc>,
+        intensities: Vec<f64>,
+    }
+    
+    #[async_trait]
+    impl GridDataSource for ForecastSource {
+        fn name(&self) -> &str {
+            "forecast"
+        }
+        
+        async fn fetch(&self, zone: &str) -> Result<GridReading, GridDataError> {
+            Ok(self.forecast(zone, 1).await?.remove(0))
+        }
+        
+        async fn forecast(&self, zone: &str, hours: u32) -> Result<Vec<GridReading>, GridDataError> {
+            Ok(self.intensities.iter().take(hours as usize).enumerate()
+                .map(|(hour, intensity)| GridReading {
+                    zone: zone.to_string(),
+                    timestamp: self.start + Duration::hours(hour as i64),
+                    carbon_intensity: *intensity,
+                    renewable_percentage: None,
+                    energy_mix: HashMap::new(),
+                    demand_mw: None,
+                    generation_mw: None,
+                    source: "forecast".to_string(),
+                })
+                .collect())
+        }
+    }
+    
+    fn paused(miner: &Miner) -> Vec<bool> {
+        miner.workers().iter().map(|w| w.pause_signal.load(Ordering::Relaxed)).collect()
+    }
+    
+    #[tokio::test]
+    async fn test_scheduler_throttles_and_reports() {
+        let start = DateTime::from_timestamp(1_700_000_000 - 1_700_000_000 % 3600, 0).unwrap();
+        let source = Arc::new(ForecastSource { start, intensities: vec![200.0, 400.0, 600.0, 250.0, 250.0] });
+        let mut prices = vec![50.0; 24];
+        prices[((start + Duration::hours(4)).hour()) as usize] = 400.0;
+        let config = SchedulerConfig {
+            zone: "DE".to_string(),
+            full_power_kw: 10.0,
+            max_power_kw: Some(7.5),
+            max_price_per_mwh: Some(200.0),
+            hourly_prices: Some(prices),
+            forecast_hours: 5,
+            ..SchedulerConfig::default()
+        };
+        let (miner, _rx) = Miner::new(4, u32::MAX, Arc::new(MockMempool), vec![1, 2, 3, 4]);
+        let mut scheduler = CarbonAwareScheduler::new(source, config).unwrap();
+        let hour = |h: i64| start + Duration::hours(h);
+        
+        // Clean hour: the power cap limits 4 workers to 3
+        let slot = scheduler.tick(&miner, hour(0)).await.unwrap();
+        assert_eq!(slot.reason, Some(CurtailmentReason::PowerCap));
+        assert_eq!(paused(&miner), vec![false, false, false, true]);
+        
+        // Halfway between the thresholds half the workers run; dirty hours pause all
+        scheduler.tick(&miner, hour(1)).await.unwrap();
+        assert_eq!(paused(&miner), vec![false, false, true, true]);
+        scheduler.tick(&miner, hour(2)).await.unwrap();
+        assert_eq!(paused(&miner), vec![true; 4]);
+        
+        // Clean but expensive hours are skipped for price
+        scheduler.tick(&miner, hour(3)).await.unwrap();
+        let slot = scheduler.tick(&miner, hour(4)).await.unwrap();
+        assert_eq!(slot.reason, Some(CurtailmentReason::Price));
+        scheduler.finish(hour(5));
+        
+        let reasons: Vec<_> = scheduler.events().iter().map(|e| (e.reason, e.active_fraction)).collect();
+        assert_eq!(reasons, vec![
+            (CurtailmentReason::PowerCap, 0.75),
+            (CurtailmentReason::CarbonIntensity, 0.5),
+            (CurtailmentReason::CarbonIntensity, 0.0),
+            (CurtailmentReason::PowerCap, 0.75),
+            (CurtailmentReason::Price, 0.0),
+        ]);
+        // The fully paused dirty hour shed 10 kWh at 600 g/kWh
+        assert_eq!(scheduler.events()[2].curtailed_energy_kwh, 10.0);
+        assert_eq!(scheduler.events()[2].avoided_emissions_kg, 6.0);
+        
+        // Two dirty hours, shedding half of one and all of the other
+        assert_eq!(scheduler.curtailment_score(), 0.75);
+        let mut profile = EnvironmentalProfile {
+            renewable_percentage: 0.0,
+            efficiency_score: 0.0,
+            verified: true,
+            rec_coverage: 0.0,
+            curtailment_score: 0.0,
+        };
+        scheduler.report(&mut profile);
+        let reward = calculate_mining_reward(0, &profile);
+        assert_eq!(reward.environmental_bonus, (reward.base_reward as f64 * 0.05 * 0.75) as u64);
+    }
+    
+    #[test]
+    fn test_config_validation() {
+        let source: Arc<dyn GridDataSource> = Arc::new(ForecastSource { start: Utc::now(), intensities: vec![] });
+        let valid = SchedulerConfig { zone: "DE".to_string(), ..SchedulerConfig::default() };
+        assert!(CarbonAwareScheduler::new(Arc::clone(&source), valid.clone()).is_ok());
+        
+        for invalid in [
+            SchedulerConfig { zone: String::new(), ..valid.clone() },
+            SchedulerConfig { pause_intensity: 100.0, ..valid.clone() },
+            SchedulerConfig { max_price_per_mwh: Some(100.0), ..valid.clone() },
+            SchedulerConfig { hourly_prices: Some(vec![1.0; 12]), ..valid.clone() },
+        ] {
+            assert!(CarbonAwareScheduler::new(Arc::clone(&source), invalid).is_err());
+        }
+    }
+}
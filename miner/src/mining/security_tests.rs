@@ -33,6 +33,7 @@ mod security_tests {
             efficiency_score: 1.0,
             verified: true,
             rec_coverage: 1.0,
+            curtailment_score: 0.0,
         };
         let max_bonus = calculate_environmental_bonus(base_reward, &max_profile);
         let max_expected = (base_reward as f64 * 0.35) as u64; // 35% max bonus
@@ -44,6 +45,7 @@ mod security_tests {
             efficiency_score: 10.0,
             verified: true,
             rec_coverage: 10.0,
+            curtailment_score: 0.0,
         };
         let exploit_bonus = calculate_environmental_bonus(base_reward, &exploit_profile);
         assert!(exploit_bonus <= (base_reward as f64 * ENV_BONUS_MAX_TOTAL) as u64,
@@ -60,6 +62,7 @@ mod security_tests {
             efficiency_score: 1.0,
             verified: false,
             rec_coverage: 1.0,
+            curtailment_score: 0.0,
         };
         let bonus = calculate_environmental_bonus(base_reward, &unverified);
         assert_eq!(bonus, 0, "Unverified profiles should receive no bonus");
@@ -75,6 +78,7 @@ mod security_tests {
             efficiency_score: -1.0,
             verified: true,
             rec_coverage: -1.0,
+            curtailment_score: 0.0,
         };
         let bonus = calculate_environmental_bonus(base_reward, &negative_profile);
         assert_eq!(bonus, 0, "Negative values should result in 0 bonus");
@@ -150,6 +154,7 @@ mod security_tests {
                 efficiency_score: efficiency,
                 verified: true,
                 rec_coverage: rec,
+                curtailment_score: 0.0,
             };
             let bonus = calculate_environmental_bonus(base_reward, &profile);
             // Allow small rounding differences
@@ -169,6 +174,7 @@ mod security_tests {
             efficiency_score: 0.5,
             verified: true,
             rec_coverage: 0.5,
+            curtailment_score: 0.0,
         });
         
         let mut handles = vec![];
@@ -209,6 +215,7 @@ mod security_tests {
             efficiency_score: 1.0,
             verified: true,
             rec_coverage: 1.0,
+            curtailment_score: 0.0,
         };
         let mining_reward = calculate_mining_reward(very_high_block, &profile);
         assert_eq!(mining_reward.total_reward, 0, "No rewards should be given after all halvings");
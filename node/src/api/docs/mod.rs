@@ -55,6 +55,13 @@ use super::types;
         crate::api::routes::environmental::get_resource_utilization,
         crate::api::routes::environmental::get_environmental_settings,
         crate::api::routes::environmental::update_environmental_settings,
+        crate::api::routes::environmental::get_miner_attestations,
+        crate::api::routes::environmental::submit_attestation,
+        crate::api::routes::environmental::get_treasury_status,
+        crate::api::routes::environmental::list_governance_proposals,
+        crate::api::routes::environmental::get_governance_proposal,
+        crate::api::routes::environmental::get_certificate_claim,
+        crate::api::routes::environmental::submit_meter_readings,
         
         // Lightning routes
         crate::api::routes::lightning::get_lightning_info,
@@ -66,6 +73,18 @@ use super::types;
         crate::api::routes::lightning::send_payment,
         crate::api::routes::lightning::get_invoices,
         crate::api::routes::lightning::create_invoice,
+        crate::api::routes::lightning::create_hold_invoice,
+        crate::api::routes::lightning::settle_hold_invoice,
+        crate::api::routes::lightning::cancel_hold_invoice,
+        crate::api::routes::lightning::get_hold_invoice,
+        crate::api::routes::lightning::get_forwards,
+        crate::api::routes::lightning::get_forwarding_report,
+        crate::api::routes::lightning::list_offers,
+        crate::api::routes::lightning::create_offer,
+        crate::api::routes::lightning::request_offer_invoice,
+        crate::api::routes::lightning::handle_offer_invoice_request,
+        crate::api::routes::lightning::pay_offer_invoice,
+        crate::api::routes::lightning::create_payer_proof,
         crate::api::routes::lightning::get_network_nodes,
         crate::api::routes::lightning::get_node_info,
         crate::api::routes::lightning::find_route,
@@ -135,6 +154,17 @@ use super::types;
             types::CarbonFootprint,
             types::EnvironmentalSettings,
             types::ResourceUtilization,
+            types::RecAttestationInfo,
+            types::SubmitAttestationRequest,
+            types::TreasuryStatus,
+            types::TreasurySpendInfo,
+            types::GovernanceProposalInfo,
+            types::ProposalTallyInfo,
+            types::CertificateClaimInfo,
+            types::MeterBatchRequest,
+            types::MeterReadingInput,
+            types::MeterIngestResponse,
+            types::EpochConsumptionInfo,
             
             // Lightning Network
             types::LightningInfo,
@@ -145,9 +175,24 @@ use super::types;
             types::OpenChannelResponse,
             types::CloseChannelRequest,
             types::PaymentRequest,
+            types::RouteCost,
             types::PaymentResponse,
             types::InvoiceRequest,
             types::InvoiceResponse,
+            types::HoldInvoiceRequest,
+            types::SettleHoldInvoiceRequest,
+            types::CancelHoldInvoiceRequest,
+            types::HoldInvoiceResponse,
+            types::ForwardingEvent,
+            types::ChannelEarnings,
+            types::PeerEarnings,
+            types::ForwardingReport,
+            types::CreateOfferRequest,
+            types::OfferResponse,
+            types::OfferInvoiceRequestParams,
+            types::EncodedOfferMessage,
+            types::PayOfferInvoiceRequest,
+            types::PayerProofRequest,
             types::NodeInfo,
             types::Route,
             
@@ -74,6 +74,13 @@ impl Modify for SecurityAddon {
         environmental::get_resource_utilization,
         environmental::get_environmental_settings,
         environmental::update_environmental_settings,
+        environmental::get_miner_attestations,
+        environmental::submit_attestation,
+        environmental::get_treasury_status,
+        environmental::list_governance_proposals,
+        environmental::get_governance_proposal,
+        environmental::get_certificate_claim,
+        environmental::submit_meter_readings,
         
         // Lightning routes
         lightning::get_lightning_info,
@@ -85,6 +92,18 @@ impl Modify for SecurityAddon {
         lightning::send_payment,
         lightning::get_invoices,
         lightning::create_invoice,
+        lightning::create_hold_invoice,
+        lightning::settle_hold_invoice,
+        lightning::cancel_hold_invoice,
+        lightning::get_hold_invoice,
+        lightning::get_forwards,
+        lightning::get_forwarding_report,
+        lightning::list_offers,
+        lightning::create_offer,
+        lightning::request_offer_invoice,
+        lightning::handle_offer_invoice_request,
+        lightning::pay_offer_invoice,
+        lightning::create_payer_proof,
         lightning::get_network_nodes,
         lightning::get_node_info,
         lightning::find_route,
@@ -156,6 +175,17 @@ impl Modify for SecurityAddon {
             types::CarbonFootprint,
             types::EnvironmentalSettings,
             types::ResourceUtilization,
+            types::RecAttestationInfo,
+            types::SubmitAttestationRequest,
+            types::TreasuryStatus,
+            types::TreasurySpendInfo,
+            types::GovernanceProposalInfo,
+            types::ProposalTallyInfo,
+            types::CertificateClaimInfo,
+            types::MeterBatchRequest,
+            types::MeterReadingInput,
+            types::MeterIngestResponse,
+            types::EpochConsumptionInfo,
             
             // Lightning types
             types::LightningInfo,
@@ -166,9 +196,24 @@ impl Modify for SecurityAddon {
             types::OpenChannelResponse,
             types::CloseChannelRequest,
             types::PaymentRequest,
+            types::RouteCost,
             types::PaymentResponse,
             types::InvoiceRequest,
             types::InvoiceResponse,
+            types::HoldInvoiceRequest,
+            types::SettleHoldInvoiceRequest,
+            types::CancelHoldInvoiceRequest,
+            types::HoldInvoiceResponse,
+            types::ForwardingEvent,
+            types::ChannelEarnings,
+            types::PeerEarnings,
+            types::ForwardingReport,
+            types::CreateOfferRequest,
+            types::OfferResponse,
+            types::OfferInvoiceRequestParams,
+            types::EncodedOfferMessage,
+            types::PayOfferInvoiceRequest,
+            types::PayerProofRequest,
             types::NodeInfo,
             types::Route,
             
@@ -44,6 +44,10 @@ pub async fn dispatch(
         "getblocktemplate" => get_block_template(params, node).await,
         "submitblock" => submit_block(params, node).await,
         
+        // Governance methods
+        "listproposals" => list_proposals(params, node).await,
+        "getproposaltally" => get_proposal_tally(params, node).await,
+        
         // Method not found
         _ => Err(JsonRpcError {
             code: ErrorCode::MethodNotFound as i32,
@@ -665,8 +669,108 @@ async fn submit_block(
     }
 }
 
+/// List on-chain governance proposals, optionally filtered by status
+async fn list_proposals(
+    params: Value,
+    node: web::Data<Arc<Node>>,
+) -> Result<Value, JsonRpcError> {
+    // Extract optional status parameter (e.g. "Active", "Approved")
+    let status = match params {
+        Value::Array(arr) if !arr.is_empty() => {
+            match &arr[0] {
+                Value::String(s) => Some(s.clone()),
+                Value::Null => None,
+                _ => return Err(JsonRpcError {
+                    code: ErrorCode::InvalidParams as i32,
+                    message: "Invalid status parameter (must be a string)".to_string(),
+                    data: None,
+                }),
+            }
+        },
+        _ => None,
+    };
+    
+    let ledger = governance_ledger(&node)?;
+    let proposals: Vec<Value> = ledger.proposals()
+        .filter(|proposal| status.as_deref().map_or(true, |s| format!("{:?}", proposal.status).eq_ignore_ascii_case(s)))
+        .map(|proposal| json!({
+            "id": proposal.id,
+            "title": proposal.payload.title,
+            "description": proposal.payload.description,
+            "type": proposal.payload.proposal_type,
+            "proposer": hex::encode(proposal.proposer),
+            "status": format!("{:?}", proposal.status),
+            "snapshotheight": proposal.snapshot_height,
+            "votingendheight": proposal.payload.voting_end_height,
+            "activationheight": proposal.payload.activation_height,
+            "activatedat": proposal.activated_at,
+        }))
+        .collect();
+    
+    Ok(Value::Array(proposals))
+}
+
+/// Get the stake-weighted tally of a governance proposal
+async fn get_proposal_tally(
+    params: Value,
+    node: web::Data<Arc<Node>>,
+) -> Result<Value, JsonRpcError> {
+    // Extract proposal ID parameter
+    let proposal_id = match params {
+        Value::Array(arr) if !arr.is_empty() => {
+            match &arr[0] {
+                Value::String(s) => s.clone(),
+                _ => return Err(JsonRpcError {
+                    code: ErrorCode::InvalidParams as i32,
+                    message: "Invalid proposal ID parameter (must be a string)".to_string(),
+                    data: None,
+                }),
+            }
+        },
+        _ => return Err(JsonRpcError {
+            code: ErrorCode::InvalidParams as i32,
+            message: "Missing proposal ID parameter".to_string(),
+            data: None,
+        }),
+    };
+    
+    let ledger = governance_ledger(&node)?;
+    let (proposal, tally) = match (ledger.proposal(&proposal_id), ledger.tally(&proposal_id)) {
+        (Some(proposal), Some(tally)) => (proposal, tally),
+        _ => return Err(JsonRpcError {
+            code: ErrorCode::InvalidParams as i32,
+            message: format!("Proposal {} not found", proposal_id),
+            data: None,
+        }),
+    };
+    
+    Ok(json!({
+        "id": proposal.id,
+        "status": format!("{:?}", proposal.status),
+        "final": proposal.tally.is_some(),
+        "for": tally.votes_for,
+        "against": tally.votes_against,
+        "eligible": tally.eligible_weight,
+        "voters": tally.voters,
+        "participation": tally.participation(),
+        "approval": tally.approval(),
+        "quorum": ledger.config().quorum_percentage,
+        "threshold": ledger.config().approval_threshold,
+    }))
+}
+
 // Helper functions
 
+/// Get the node's governance ledger
+fn governance_ledger(node: &web::Data<Arc<Node>>) -> Result<btclib::environmental::GovernanceLedger, JsonRpcError> {
+    node.governance_ledger().ok_or_else(|| JsonRpcError {
+        code: ErrorCode::ServerError as i32,
+        message: "On-chain governance is not enabled".to_string(),
+        data: None,
+    })
+}
+
+
 /// Format transaction as JSON
 fn format_transaction(tx: &btclib::types::transaction::Transaction) -> Value {
     // Placeholder implementation - in a real implementation, this would format the transaction
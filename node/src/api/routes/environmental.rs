@@ -1,10 +1,17 @@
 use crate::api::error::{ApiError, ApiResult};
 use crate::api::types::{
     EnvironmentalImpact, EnergyUsage, CarbonFootprint, EnvironmentalSettings,
-    ResourceUtilization,
+    ResourceUtilization, RecAttestationInfo, SubmitAttestationRequest, TreasuryStatus, TreasurySpendInfo,
+    CertificateClaimInfo, MeterBatchRequest, MeterIngestResponse, EpochConsumptionInfo,
+    GovernanceProposalInfo, ProposalTallyInfo,
 };
 use crate::environmental::EnvironmentalMonitor;
+use crate::node::Node;
 use actix_web::{web, HttpResponse};
+use btclib::environmental::attestation::{AttestationError, RecAttestation};
+use btclib::environmental::chain_governance::{ChainProposal, GovernanceLedger};
+use btclib::environmental::meter_telemetry::{MeterBatch, MeterTelemetryError};
+use btclib::environmental::oracle::MeterReading;
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 use std::sync::Arc;
@@ -18,7 +25,14 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .route("/carbon", web::get().to(get_carbon_footprint))
             .route("/resources", web::get().to(get_resource_utilization))
             .route("/settings", web::get().to(get_environmental_settings))
-            .route("/settings", web::put().to(update_environmental_settings)),
+            .route("/settings", web::put().to(update_environmental_settings))
+            .route("/attestations", web::post().to(submit_attestation))
+            .route("/attestations/{miner_id}", web::get().to(get_miner_attestations))
+            .route("/treasury", web::get().to(get_treasury_status))
+            .route("/governance/proposals", web::get().to(list_governance_proposals))
+            .route("/governance/proposals/{proposal_id}", web::get().to(get_governance_proposal))
+            .route("/certificates/{certificate_id}", web::get().to(get_certificate_claim))
+            .route("/meters/readings", web::post().to(submit_meter_readings)),
     );
 }
 
@@ -214,4 +228,349 @@ pub async fn update_environmental_settings(
         Ok(updated_settings) => Ok(HttpResponse::Ok().json(updated_settings)),
         Err(e) => Err(ApiError::internal_error(format!("Failed to update environmental settings: {}", e))),
     }
-} 
\ No newline at end of file
+} 
+/// Get a miner's REC attestations
+///
+/// Returns the oracle-signed renewable energy certificate attestations
+/// verified for a miner, each with its encoded form for offline verification.
+#[utoipa::path(
+    get,
+    path = "/api/v1/environmental/attestations/{miner_id}",
+    params(
+        ("miner_id" = String, Path, description = "Hash of the miner's reward script (hex)")
+    ),
+    responses(
+        (status = 200, description = "Attestations retrieved successfully", body = Vec<RecAttestationInfo>),
+        (status = 400, description = "Invalid miner ID", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_miner_attestations(
+    path: web::Path<String>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    let miner_id: [u8; 32] = hex::decode(path.into_inner())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| ApiError::bad_request("Miner ID must be a 32-byte hex script hash"))?;
+    
+    let attestations = node.attestations().by_miner(&miner_id)
+        .into_iter()
+        .map(attestation_info)
+        .collect::<ApiResult<Vec<_>>>()?;
+    
+    Ok(HttpResponse::Ok().json(attestations))
+}
+
+/// Submit an oracle-signed REC attestation
+///
+/// Verifies the attestation against the oracle set of its epoch, requiring
+/// valid signatures from at least the set's threshold of members, and stores
+/// it for the attested miner.
+#[utoipa::path(
+    post,
+    path = "/api/v1/environmental/attestations",
+    request_body = SubmitAttestationRequest,
+    responses(
+        (status = 200, description = "Attestation verified and stored", body = RecAttestationInfo),
+        (status = 400, description = "Malformed attestation or invalid signatures", body = ApiError),
+        (status = 409, description = "Certificate already attested", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn submit_attestation(
+    request: web::Json<SubmitAttestationRequest>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    let bytes = hex::decode(&request.encoded)
+        .map_err(|e| ApiError::bad_request(format!("Invalid attestation encoding: {}", e)))?;
+    let attestation = RecAttestation::from_bytes(&bytes)
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    
+    node.attestations().insert(attestation.clone())
+        .map_err(|e| match e {
+            AttestationError::DuplicateCertificate(_) => ApiError::conflict(e.to_string()),
+            _ => ApiError::bad_request(e.to_string()),
+        })?;
+    
+    Ok(HttpResponse::Ok().json(attestation_info(attestation)?))
+}
+
+/// API view of an attestation
+fn attestation_info(attestation: RecAttestation) -> ApiResult<RecAttestationInfo> {
+    let encoded = attestation.to_bytes()
+        .map_err(|e| ApiError::internal_error(format!("Failed to encode attestation: {}", e)))?;
+    
+    Ok(RecAttestationInfo {
+        attestation_id: hex::encode(attestation.attestation_id()),
+        kind: format!("{:?}", attestation.claim.kind),
+        certificate_id: attestation.claim.certificate_id,
+        miner_id: hex::encode(attestation.claim.miner_id),
+        energy_mwh: attestation.claim.energy_milli_mwh as f64 / 1_000.0,
+        period_start: attestation.claim.period_start,
+        period_end: attestation.claim.period_end,
+        location: attestation.claim.location,
+        energy_type: format!("{:?}", attestation.claim.energy_type),
+        oracle_epoch: attestation.claim.oracle_epoch,
+        signers: attestation.signatures.iter().map(|signature| signature.signer).collect(),
+        encoded: hex::encode(encoded),
+    })
+}
+
+/// Get the on-chain environmental treasury
+///
+/// Returns the treasury balance and spend history derived from the chain.
+#[utoipa::path(
+    get,
+    path = "/api/v1/environmental/treasury",
+    responses(
+        (status = 200, description = "Treasury state retrieved successfully", body = TreasuryStatus),
+        (status = 404, description = "No treasury committee configured", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_treasury_status(
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    let ledger = node.treasury_ledger()
+        .ok_or_else(|| ApiError::not_found("No treasury committee configured"))?;
+    
+    let spends = ledger.spends().iter()
+        .map(|spend| TreasurySpendInfo {
+            txid: hex::encode(spend.txid),
+            height: spend.height,
+            proposal_id: spend.proposal_id.clone(),
+            amount: spend.paid_out(),
+            change: spend.change,
+        })
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(TreasuryStatus {
+        script: hex::encode(ledger.script()),
+        balance: ledger.balance(),
+        total_allocated: ledger.total_allocated(),
+        unspent_outputs: ledger.unspent_outputs().count(),
+        tip: ledger.tip(),
+        spends,
+    }))
+}
+
+/// List on-chain governance proposals
+///
+/// Returns the proposals and stake-weighted tallies the node has derived
+/// from the chain, optionally filtered by status.
+#[derive(Debug, Deserialize, IntoParams)]
+struct ListGovernanceProposalsParams {
+    /// Only return proposals with this status (e.g., "Active", "Approved")
+    status: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/environmental/governance/proposals",
+    params(
+        ListGovernanceProposalsParams
+    ),
+    responses(
+        (status = 200, description = "Proposals retrieved successfully", body = Vec<GovernanceProposalInfo>),
+        (status = 404, description = "On-chain governance is not enabled", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn list_governance_proposals(
+    params: web::Query<ListGovernanceProposalsParams>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    let ledger = node.governance_ledger()
+        .ok_or_else(|| ApiError::not_found("On-chain governance is not enabled"))?;
+    
+    let proposals: Vec<GovernanceProposalInfo> = ledger.proposals()
+        .filter(|proposal| params.status.as_deref()
+            .map_or(true, |status| format!("{:?}", proposal.status).eq_ignore_ascii_case(status)))
+        .map(|proposal| governance_proposal_info(&ledger, proposal))
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(proposals))
+}
+
+/// Get an on-chain governance proposal
+///
+/// Returns the proposal and its stake-weighted tally, final once voting has ended.
+#[utoipa::path(
+    get,
+    path = "/api/v1/environmental/governance/proposals/{proposal_id}",
+    params(
+        ("proposal_id" = String, Path, description = "Proposal ID")
+    ),
+    responses(
+        (status = 200, description = "Proposal retrieved successfully", body = GovernanceProposalInfo),
+        (status = 404, description = "Proposal not found or governance not enabled", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_governance_proposal(
+    path: web::Path<String>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    let proposal_id = path.into_inner();
+    let ledger = node.governance_ledger()
+        .ok_or_else(|| ApiError::not_found("On-chain governance is not enabled"))?;
+    let proposal = ledger.proposal(&proposal_id)
+        .ok_or_else(|| ApiError::not_found(format!("Proposal {} not found", proposal_id)))?;
+    
+    Ok(HttpResponse::Ok().json(governance_proposal_info(&ledger, proposal)))
+}
+
+/// Convert a tracked proposal and its tally to the API representation
+fn governance_proposal_info(ledger: &GovernanceLedger, proposal: &ChainProposal) -> GovernanceProposalInfo {
+    let tally = ledger.tally(&proposal.id).unwrap_or_default();
+    
+    GovernanceProposalInfo {
+        id: proposal.id.clone(),
+        title: proposal.payload.title.clone(),
+        description: proposal.payload.description.clone(),
+        proposal_type: format!("{:?}", proposal.payload.proposal_type),
+        proposer: hex::encode(proposal.proposer),
+        status: format!("{:?}", proposal.status),
+        snapshot_height: proposal.snapshot_height,
+        voting_end_height: proposal.payload.voting_end_height,
+        activation_height: proposal.payload.activation_height,
+        activated_at: proposal.activated_at,
+        tally: ProposalTallyInfo {
+            is_final: proposal.tally.is_some(),
+            votes_for: tally.votes_for,
+            votes_against: tally.votes_against,
+            eligible_weight: tally.eligible_weight,
+            voters: tally.voters,
+            participation: tally.participation(),
+            approval: tally.approval(),
+            quorum_percentage: ledger.config().quorum_percentage,
+            approval_threshold: ledger.config().approval_threshold,
+        },
+    }
+}
+
+/// Check whether a certificate has been claimed
+///
+/// Looks up a REC or carbon offset ID in the node's retirement registry, so
+/// anyone can check a certificate has not already been counted on Supernova.
+#[utoipa::path(
+    get,
+    path = "/api/v1/environmental/certificates/{certificate_id}",
+    params(
+        ("certificate_id" = String, Path, description = "REC or carbon offset ID")
+    ),
+    responses(
+        (status = 200, description = "Claim status retrieved successfully", body = CertificateClaimInfo),
+        (status = 400, description = "Invalid certificate ID", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_certificate_claim(
+    path: web::Path<String>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    let certificate_id = path.into_inner();
+    if certificate_id.trim().is_empty() {
+        return Err(ApiError::bad_request("Certificate ID is required"));
+    }
+    
+    let record = node.retirements().lookup(&certificate_id)
+        .map_err(|e| ApiError::internal_error(format!("Failed to read retirement registry: {}", e)))?;
+    
+    let info = match record {
+        Some(record) => CertificateClaimInfo {
+            certificate_id,
+            claimed: true,
+            kind: Some(record.kind.as_str().to_string()),
+            issuer: Some(record.issuer),
+            miner_id: Some(record.miner_id),
+            amount: Some(record.amount),
+            period_start: Some(record.period_start.to_rfc3339()),
+            period_end: Some(record.period_end.to_rfc3339()),
+            claimed_at: Some(record.claimed_at.to_rfc3339()),
+        },
+        None => CertificateClaimInfo {
+            certificate_id,
+            claimed: false,
+            kind: None,
+            issuer: None,
+            miner_id: None,
+            amount: None,
+            period_start: None,
+            period_end: None,
+            claimed_at: None,
+        },
+    };
+    
+    Ok(HttpResponse::Ok().json(info))
+}
+
+/// Submit signed smart-meter readings
+///
+/// Accepts a batch of readings signed by a registered meter key. The batch is
+/// rejected as a whole if any reading fails signature, monotonicity or
+/// plausibility checks. Epochs completed by the batch are applied to the
+/// miner's reported energy consumption.
+#[utoipa::path(
+    post,
+    path = "/api/v1/environmental/meters/readings",
+    request_body = MeterBatchRequest,
+    responses(
+        (status = 200, description = "Readings accepted", body = MeterIngestResponse),
+        (status = 400, description = "Invalid or implausible readings", body = ApiError),
+        (status = 404, description = "Meter not registered", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn submit_meter_readings(
+    request: web::Json<MeterBatchRequest>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    let request = request.into_inner();
+    
+    let mut readings = Vec::with_capacity(request.readings.len());
+    for reading in request.readings {
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&reading.timestamp)
+            .map_err(|e| ApiError::bad_request(format!("Invalid timestamp {}: {}", reading.timestamp, e)))?
+            .with_timezone(&chrono::Utc);
+        let attestation = hex::decode(&reading.signature)
+            .map_err(|e| ApiError::bad_request(format!("Invalid signature encoding: {}", e)))?;
+        
+        readings.push(MeterReading {
+            meter_id: request.meter_id.clone(),
+            timestamp,
+            energy_kwh: reading.energy_kwh,
+            power_kw: reading.power_kw,
+            attestation,
+        });
+    }
+    
+    let batch = MeterBatch { meter_id: request.meter_id, readings };
+    let (receipt, completed) = node.ingest_meter_readings(&batch, chrono::Utc::now())
+        .map_err(|e| match e {
+            MeterTelemetryError::UnknownMeter(_) => ApiError::not_found(e.to_string()),
+            _ => ApiError::bad_request(e.to_string()),
+        })?;
+    
+    let completed_epochs = completed.into_iter()
+        .map(|consumption| EpochConsumptionInfo {
+            miner_id: consumption.miner_id,
+            epoch: consumption.epoch,
+            start: consumption.start.to_rfc3339(),
+            end: consumption.end.to_rfc3339(),
+            energy_kwh: consumption.energy_kwh,
+            peak_power_kw: consumption.peak_power_kw,
+            readings: consumption.readings,
+            meters: consumption.meters.into_iter().collect(),
+        })
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(MeterIngestResponse {
+        meter_id: receipt.meter_id,
+        miner_id: receipt.miner_id,
+        accepted: receipt.accepted,
+        energy_kwh: receipt.energy_kwh,
+        completed_epochs,
+    }))
+}
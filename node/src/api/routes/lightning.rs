@@ -2,10 +2,15 @@ use crate::api::error::{ApiError, ApiResult};
 use crate::api::types::{
     LightningInfo, LightningChannel, LightningPayment, LightningInvoice, 
     OpenChannelRequest, OpenChannelResponse, CloseChannelRequest, 
-    PaymentRequest, PaymentResponse, InvoiceRequest, InvoiceResponse,
-    NodeInfo, Route,
+    PaymentRequest, PaymentResponse, RouteCost, InvoiceRequest, InvoiceResponse,
+    NodeInfo, Route, CreateOfferRequest, OfferResponse, OfferInvoiceRequestParams,
+    EncodedOfferMessage, PayOfferInvoiceRequest, PayerProofRequest,
+    HoldInvoiceRequest, SettleHoldInvoiceRequest, CancelHoldInvoiceRequest, HoldInvoiceResponse,
+    ForwardingEvent, ForwardingReport,
 };
 use crate::node::Node;
+use btclib::lightning::green_routing::GreenRoutingParameters;
+use btclib::lightning::router::RouteCostFunction;
 use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
@@ -25,6 +30,18 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .route("/pay", web::post().to(send_payment))
             .route("/invoices", web::get().to(get_invoices))
             .route("/invoice", web::post().to(create_invoice))
+            .route("/holdinvoice", web::post().to(create_hold_invoice))
+            .route("/holdinvoice/settle", web::post().to(settle_hold_invoice))
+            .route("/holdinvoice/cancel", web::post().to(cancel_hold_invoice))
+            .route("/holdinvoice/{payment_hash}", web::get().to(get_hold_invoice))
+            .route("/forwards", web::get().to(get_forwards))
+            .route("/forwards/report", web::get().to(get_forwarding_report))
+            .route("/offers", web::get().to(list_offers))
+            .route("/offer", web::post().to(create_offer))
+            .route("/offer/request", web::post().to(request_offer_invoice))
+            .route("/offer/invoice", web::post().to(handle_offer_invoice_request))
+            .route("/offer/pay", web::post().to(pay_offer_invoice))
+            .route("/offer/proof", web::post().to(create_payer_proof))
             .route("/nodes", web::get().to(get_network_nodes))
             .route("/node/{node_id}", web::get().to(get_node_info))
             .route("/routes", web::get().to(find_route)),
@@ -290,11 +307,30 @@ pub async fn send_payment(
     
     // Send payment
     let mut manager = lightning_manager.write().unwrap();
-    let response = manager.send_payment(
+    let mut preferences = manager.router_preferences();
+    if request.route_cost == Some(RouteCost::CarbonAware) {
+        let mut params = GreenRoutingParameters::default();
+        for (weight, value) in [
+            (&mut params.fee_weight, request.fee_weight),
+            (&mut params.carbon_weight, request.carbon_weight),
+            (&mut params.renewable_weight, request.renewable_weight),
+        ] {
+            if let Some(value) = value {
+                if !value.is_finite() || value < 0.0 {
+                    return Err(ApiError::bad_request("Route cost weights must be non-negative"));
+                }
+                *weight = value;
+            }
+        }
+        preferences.cost_function = RouteCostFunction::CarbonAware(params);
+    }
+    
+    let response = manager.send_payment_with_preferences(
         &request.payment_request,
         request.amount_msat,
         request.timeout_seconds.unwrap_or(60),
         request.fee_limit_msat,
+        &preferences,
     ).await;
     
     let response = response
@@ -384,6 +420,403 @@ pub async fn create_invoice(
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// Create a Lightning Network hold invoice
+///
+/// Creates an invoice for a payment hash only. Incoming HTLCs are held
+/// until the invoice is settled with the preimage or canceled.
+#[utoipa::path(
+    post,
+    path = "/api/v1/lightning/holdinvoice",
+    request_body = HoldInvoiceRequest,
+    responses(
+        (status = 200, description = "Hold invoice created successfully", body = InvoiceResponse),
+        (status = 400, description = "Invalid request parameters", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn create_hold_invoice(
+    request: web::Json<HoldInvoiceRequest>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    // Create hold invoice
+    let manager = lightning_manager.read().unwrap();
+    let response = manager.add_hold_invoice(
+        &request.payment_hash,
+        request.value_msat,
+        request.memo.as_deref().unwrap_or(""),
+        request.expiry.unwrap_or(3600),
+    ).map_err(|e| ApiError::bad_request(format!("Failed to create hold invoice: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Settle a Lightning Network hold invoice
+///
+/// Releases the preimage for an accepted hold invoice, settling its held HTLCs.
+#[utoipa::path(
+    post,
+    path = "/api/v1/lightning/holdinvoice/settle",
+    request_body = SettleHoldInvoiceRequest,
+    responses(
+        (status = 200, description = "Hold invoice settled successfully"),
+        (status = 400, description = "Invalid request parameters", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn settle_hold_invoice(
+    request: web::Json<SettleHoldInvoiceRequest>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    let manager = lightning_manager.read().unwrap();
+    manager.settle_hold_invoice(&request.preimage)
+        .map_err(|e| ApiError::bad_request(format!("Failed to settle hold invoice: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Cancel a Lightning Network hold invoice
+///
+/// Cancels an open or accepted hold invoice, failing back its held HTLCs.
+#[utoipa::path(
+    post,
+    path = "/api/v1/lightning/holdinvoice/cancel",
+    request_body = CancelHoldInvoiceRequest,
+    responses(
+        (status = 200, description = "Hold invoice canceled successfully"),
+        (status = 400, description = "Invalid request parameters", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn cancel_hold_invoice(
+    request: web::Json<CancelHoldInvoiceRequest>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    let manager = lightning_manager.read().unwrap();
+    manager.cancel_hold_invoice(&request.payment_hash)
+        .map_err(|e| ApiError::bad_request(format!("Failed to cancel hold invoice: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Get a Lightning Network hold invoice
+///
+/// Returns the state of a hold invoice and its held HTLCs.
+#[utoipa::path(
+    get,
+    path = "/api/v1/lightning/holdinvoice/{payment_hash}",
+    params(
+        ("payment_hash" = String, Path, description = "Payment hash")
+    ),
+    responses(
+        (status = 200, description = "Hold invoice retrieved successfully", body = HoldInvoiceResponse),
+        (status = 404, description = "Hold invoice not found", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_hold_invoice(
+    path: web::Path<String>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    let payment_hash = path.into_inner();
+    
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    let manager = lightning_manager.read().unwrap();
+    let invoice = manager.lookup_hold_invoice(&payment_hash)
+        .map_err(|_| ApiError::not_found(format!("Hold invoice {} not found", payment_hash)))?;
+    
+    Ok(HttpResponse::Ok().json(invoice))
+}
+
+/// Time range of forwarding queries
+#[derive(Debug, Deserialize, IntoParams)]
+struct ForwardsRangeParams {
+    /// Start of the range in seconds since the epoch (default: 0)
+    from: Option<u64>,
+    
+    /// End of the range in seconds since the epoch (default: now)
+    to: Option<u64>,
+}
+
+impl ForwardsRangeParams {
+    fn range(&self) -> (u64, u64) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        (self.from.unwrap_or(0), self.to.unwrap_or(now))
+    }
+}
+
+/// Get forwarded HTLCs
+///
+/// Returns the HTLCs this node forwarded for others that resolved within
+/// the given time range, oldest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/lightning/forwards",
+    params(
+        ForwardsRangeParams
+    ),
+    responses(
+        (status = 200, description = "Forwarding events retrieved successfully", body = Vec<ForwardingEvent>),
+        (status = 400, description = "Invalid request parameters", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_forwards(
+    params: web::Query<ForwardsRangeParams>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    let (from, to) = params.range();
+    if from > to {
+        return Err(ApiError::bad_request("'from' must not be after 'to'"));
+    }
+    
+    let manager = lightning_manager.read().unwrap();
+    let forwards = manager.get_forwards(from, to)
+        .map_err(|e| ApiError::internal_error(format!("Failed to list forwards: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(forwards))
+}
+
+/// Get routing income
+///
+/// Returns fees earned by forwarding within the given time range,
+/// aggregated per channel and per outgoing peer.
+#[utoipa::path(
+    get,
+    path = "/api/v1/lightning/forwards/report",
+    params(
+        ForwardsRangeParams
+    ),
+    responses(
+        (status = 200, description = "Forwarding report generated successfully", body = ForwardingReport),
+        (status = 400, description = "Invalid request parameters", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_forwarding_report(
+    params: web::Query<ForwardsRangeParams>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    let (from, to) = params.range();
+    if from > to {
+        return Err(ApiError::bad_request("'from' must not be after 'to'"));
+    }
+    
+    let manager = lightning_manager.read().unwrap();
+    let report = manager.get_forwarding_report(from, to)
+        .map_err(|e| ApiError::internal_error(format!("Failed to build forwarding report: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Create a reusable Lightning Network offer
+///
+/// Creates a quantum-signed offer that can be paid many times.
+#[utoipa::path(
+    post,
+    path = "/api/v1/lightning/offer",
+    request_body = CreateOfferRequest,
+    responses(
+        (status = 200, description = "Offer created successfully", body = OfferResponse),
+        (status = 400, description = "Invalid request parameters", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn create_offer(
+    request: web::Json<CreateOfferRequest>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    let recurrence = request.recurrence_period_seconds.map(|seconds| btclib::lightning::Recurrence {
+        period: btclib::lightning::RecurrencePeriod::Seconds(seconds),
+        limit: request.recurrence_limit,
+        paywindow_seconds: None,
+    });
+    
+    // Create offer
+    let manager = lightning_manager.read().unwrap();
+    let response = manager.create_offer(
+        &request.description,
+        request.amount_msat,
+        request.issuer.clone(),
+        request.quantity_max,
+        request.absolute_expiry,
+        recurrence,
+    ).map_err(|e| ApiError::bad_request(format!("Failed to create offer: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Get a list of Lightning Network offers
+///
+/// Returns the offers published by this node.
+#[utoipa::path(
+    get,
+    path = "/api/v1/lightning/offers",
+    responses(
+        (status = 200, description = "Offers retrieved successfully", body = Vec<OfferResponse>),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn list_offers(
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    let manager = lightning_manager.read().unwrap();
+    let offers = manager.list_offers()
+        .map_err(|e| ApiError::internal_error(format!("Failed to list offers: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(offers))
+}
+
+/// Request an invoice for an offer
+///
+/// Builds a signed invoice request to send to the offer's issuer.
+#[utoipa::path(
+    post,
+    path = "/api/v1/lightning/offer/request",
+    request_body = OfferInvoiceRequestParams,
+    responses(
+        (status = 200, description = "Invoice request created successfully", body = EncodedOfferMessage),
+        (status = 400, description = "Invalid request parameters", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn request_offer_invoice(
+    request: web::Json<OfferInvoiceRequestParams>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    let manager = lightning_manager.read().unwrap();
+    let encoded = manager.request_invoice(
+        &request.offer,
+        request.amount_msat,
+        request.quantity,
+        request.recurrence_counter,
+        request.payer_note.clone(),
+    ).map_err(|e| ApiError::bad_request(format!("Failed to request invoice: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(EncodedOfferMessage { encoded }))
+}
+
+/// Answer an invoice request for one of this node's offers
+///
+/// Validates the invoice request and returns a signed invoice.
+#[utoipa::path(
+    post,
+    path = "/api/v1/lightning/offer/invoice",
+    request_body = EncodedOfferMessage,
+    responses(
+        (status = 200, description = "Invoice issued successfully", body = EncodedOfferMessage),
+        (status = 400, description = "Invalid request parameters", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn handle_offer_invoice_request(
+    request: web::Json<EncodedOfferMessage>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    let manager = lightning_manager.read().unwrap();
+    let encoded = manager.handle_invoice_request(&request.encoded)
+        .map_err(|e| ApiError::bad_request(format!("Failed to issue invoice: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(EncodedOfferMessage { encoded }))
+}
+
+/// Pay an offer invoice
+///
+/// Pays an invoice received in response to one of this node's invoice requests.
+#[utoipa::path(
+    post,
+    path = "/api/v1/lightning/offer/pay",
+    request_body = PayOfferInvoiceRequest,
+    responses(
+        (status = 200, description = "Payment sent successfully", body = PaymentResponse),
+        (status = 400, description = "Invalid request parameters", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn pay_offer_invoice(
+    request: web::Json<PayOfferInvoiceRequest>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    let manager = lightning_manager.read().unwrap();
+    let response = manager.pay_offer_invoice(&request.invoice, request.fee_limit_msat).await
+        .map_err(|e| ApiError::internal_error(format!("Failed to pay offer invoice: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Create a payer proof
+///
+/// Proves that this node paid an offer invoice.
+#[utoipa::path(
+    post,
+    path = "/api/v1/lightning/offer/proof",
+    request_body = PayerProofRequest,
+    responses(
+        (status = 200, description = "Payer proof created successfully", body = EncodedOfferMessage),
+        (status = 404, description = "Payment not found", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn create_payer_proof(
+    request: web::Json<PayerProofRequest>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node.lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+    
+    let manager = lightning_manager.read().unwrap();
+    let encoded = manager.create_payer_proof(&request.payment_hash, request.note.clone())
+        .map_err(|e| ApiError::not_found(format!("Failed to create payer proof: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(EncodedOfferMessage { encoded }))
+}
+
 /// Get a list of Lightning Network nodes
 ///
 /// Returns information about nodes in the Lightning Network.
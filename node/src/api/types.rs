@@ -930,6 +930,27 @@ pub struct PaymentRequest {
     pub fee_limit_msat: Option<u64>,
     /// Timeout in seconds
     pub timeout_seconds: Option<u32>,
+    /// Path cost to minimize, fees by default
+    pub route_cost: Option<RouteCost>,
+    /// Weight of fees against the carbon components, for `carbon_aware`
+    #[schema(example = 0.4)]
+    pub fee_weight: Option<f64>,
+    /// Weight of the node's carbon intensity, for `carbon_aware`
+    #[schema(example = 0.3)]
+    pub carbon_weight: Option<f64>,
+    /// Weight of the node's non-renewable share, for `carbon_aware`
+    #[schema(example = 0.2)]
+    pub renewable_weight: Option<f64>,
+}
+
+/// Path cost a payment minimizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteCost {
+    /// Fees plus liquidity penalties
+    Fee,
+    /// Fees plus liquidity penalties plus a carbon penalty per hop
+    CarbonAware,
 }
 
 /// Payment response
@@ -980,6 +1001,208 @@ pub struct InvoiceResponse {
     pub add_index: u64,
 }
 
+/// Hold invoice request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct HoldInvoiceRequest {
+    /// Payment hash (hex); the preimage stays with the caller
+    pub payment_hash: String,
+    /// Amount in millisatoshis
+    pub value_msat: u64,
+    /// Description
+    pub memo: Option<String>,
+    /// Expiry in seconds
+    pub expiry: Option<u32>,
+}
+
+/// Settle hold invoice request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SettleHoldInvoiceRequest {
+    /// Payment preimage (hex)
+    pub preimage: String,
+}
+
+/// Cancel hold invoice request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CancelHoldInvoiceRequest {
+    /// Payment hash (hex)
+    pub payment_hash: String,
+}
+
+/// Hold invoice information
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct HoldInvoiceResponse {
+    /// Payment hash
+    pub payment_hash: String,
+    /// State (OPEN, ACCEPTED, PAID, CANCELED, ...)
+    pub state: String,
+    /// Value in millisatoshis
+    pub value_msat: u64,
+    /// Amount held so far in millisatoshis
+    pub amount_received_msat: u64,
+    /// Number of held HTLCs
+    pub held_htlcs: usize,
+    /// Height at which the invoice is canceled automatically
+    pub cancel_height: Option<u32>,
+}
+
+/// A forwarded HTLC
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ForwardingEvent {
+    /// When the forward resolved (seconds since the epoch)
+    pub timestamp: u64,
+    /// Channel the HTLC came in on
+    pub incoming_channel: String,
+    /// Channel the HTLC went out on
+    pub outgoing_channel: String,
+    /// Peer on the incoming channel
+    pub incoming_peer: String,
+    /// Peer on the outgoing channel
+    pub outgoing_peer: String,
+    /// Payment hash
+    pub payment_hash: String,
+    /// Amount received in millisatoshis
+    pub amount_in_msat: u64,
+    /// Amount forwarded in millisatoshis
+    pub amount_out_msat: u64,
+    /// Fee earned in millisatoshis
+    pub fee_msat: u64,
+    /// SETTLED or FAILED
+    pub status: String,
+    /// Why the forward failed
+    pub failure_reason: Option<String>,
+}
+
+/// Routing income of a channel
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChannelEarnings {
+    /// Channel ID
+    pub channel_id: String,
+    /// Settled forwards received on this channel
+    pub forwards_in: u64,
+    /// Settled forwards sent on this channel
+    pub forwards_out: u64,
+    /// Amount received in millisatoshis
+    pub volume_in_msat: u64,
+    /// Amount sent in millisatoshis
+    pub volume_out_msat: u64,
+    /// Fees earned in millisatoshis
+    pub fees_msat: u64,
+    /// Failed forwards
+    pub failures: u64,
+}
+
+/// Routing income from a peer
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PeerEarnings {
+    /// Peer node ID
+    pub peer: String,
+    /// Settled forwards to this peer
+    pub forwards: u64,
+    /// Amount sent in millisatoshis
+    pub volume_msat: u64,
+    /// Fees earned in millisatoshis
+    pub fees_msat: u64,
+}
+
+/// Routing income over a time range
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ForwardingReport {
+    /// Start of the range (seconds since the epoch)
+    pub from: u64,
+    /// End of the range (seconds since the epoch)
+    pub to: u64,
+    /// Settled forwards
+    pub settled: u64,
+    /// Failed forwards
+    pub failed: u64,
+    /// Amount forwarded in millisatoshis
+    pub total_volume_msat: u64,
+    /// Fees earned in millisatoshis
+    pub total_fees_msat: u64,
+    /// Earnings per channel
+    pub channels: Vec<ChannelEarnings>,
+    /// Earnings per outgoing peer
+    pub peers: Vec<PeerEarnings>,
+}
+
+/// Create offer request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateOfferRequest {
+    /// Description
+    pub description: String,
+    /// Amount per item in millisatoshis (omit to let the payer choose)
+    pub amount_msat: Option<u64>,
+    /// Issuer name
+    pub issuer: Option<String>,
+    /// Maximum quantity per invoice request
+    pub quantity_max: Option<u64>,
+    /// Absolute expiry as a Unix timestamp
+    pub absolute_expiry: Option<u64>,
+    /// Recurrence period in seconds (makes the offer recurring)
+    pub recurrence_period_seconds: Option<u32>,
+    /// Maximum number of recurrence periods
+    pub recurrence_limit: Option<u32>,
+}
+
+/// Offer information
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OfferResponse {
+    /// Encoded offer (lno...)
+    pub offer: String,
+    /// Offer ID
+    pub offer_id: String,
+    /// Description
+    pub description: String,
+    /// Amount per item in millisatoshis
+    pub amount_msat: Option<u64>,
+    /// Whether the offer is recurring
+    pub recurring: bool,
+    /// Creation date
+    pub created_at: u64,
+    /// Whether the offer has expired
+    pub expired: bool,
+}
+
+/// Request for an invoice against an offer
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct OfferInvoiceRequestParams {
+    /// Encoded offer (lno...)
+    pub offer: String,
+    /// Amount in millisatoshis (required if the offer has no amount)
+    pub amount_msat: Option<u64>,
+    /// Quantity
+    pub quantity: Option<u64>,
+    /// Recurrence period counter
+    pub recurrence_counter: Option<u32>,
+    /// Note to the payee
+    pub payer_note: Option<String>,
+}
+
+/// Encoded offer message (invoice request, invoice or payer proof)
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EncodedOfferMessage {
+    /// Bech32-encoded message (lnr..., lni... or lnp...)
+    pub encoded: String,
+}
+
+/// Pay offer invoice request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PayOfferInvoiceRequest {
+    /// Encoded offer invoice (lni...)
+    pub invoice: String,
+    /// Fee limit in millisatoshis
+    pub fee_limit_msat: Option<u64>,
+}
+
+/// Payer proof request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PayerProofRequest {
+    /// Payment hash of the paid offer invoice
+    pub payment_hash: String,
+    /// Optional note included in the proof
+    pub note: Option<String>,
+}
+
 /// Load average information
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LoadAverage {
@@ -177,4 +177,202 @@ pub struct EnvironmentalSettings {
     pub energy_efficiency_target: Option<f64>,
     /// Geographic location code for emissions calculation
     pub location_code: Option<String>,
-} 
\ No newline at end of file
+} 
+/// Oracle-signed REC attestation
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RecAttestationInfo {
+    /// Attestation ID (hex)
+    pub attestation_id: String,
+    /// Kind of claim (e.g., "RenewableCertificate", "CarbonOffset")
+    pub kind: String,
+    /// Registry ID of the certificate
+    pub certificate_id: String,
+    /// Hash of the reward script of the miner the certificate was verified for (hex)
+    pub miner_id: String,
+    /// Energy covered in MWh
+    pub energy_mwh: f64,
+    /// Start of the generation period (Unix seconds)
+    pub period_start: u64,
+    /// End of the generation period (Unix seconds)
+    pub period_end: u64,
+    /// Generation location
+    pub location: String,
+    /// Source of the energy (e.g., "Solar", "Wind")
+    pub energy_type: String,
+    /// Epoch of the signing oracle set
+    pub oracle_epoch: u64,
+    /// Oracle-set indices of the signers
+    pub signers: Vec<u16>,
+    /// Serialized attestation (hex) for offline verification
+    pub encoded: String,
+}
+
+/// Oracle-signed REC attestation to verify and store
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SubmitAttestationRequest {
+    /// Serialized attestation (hex), as returned in `RecAttestationInfo.encoded`
+    pub encoded: String,
+}
+
+/// Payment out of the on-chain environmental treasury
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TreasurySpendInfo {
+    /// Spending transaction ID (hex)
+    pub txid: String,
+    /// Block height
+    pub height: u64,
+    /// Governance proposal that authorized the spend
+    pub proposal_id: String,
+    /// Amount paid out of the treasury
+    pub amount: u64,
+    /// Change returned to the treasury
+    pub change: u64,
+}
+
+/// On-chain environmental treasury state
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TreasuryStatus {
+    /// Treasury locking script (hex)
+    pub script: String,
+    /// Spendable balance
+    pub balance: u64,
+    /// Total allocated by coinbases
+    pub total_allocated: u64,
+    /// Number of unspent treasury outputs
+    pub unspent_outputs: usize,
+    /// Height of the last block applied
+    pub tip: Option<u64>,
+    /// Spends, oldest first
+    pub spends: Vec<TreasurySpendInfo>,
+}
+
+/// Claim status of a REC or carbon offset certificate
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CertificateClaimInfo {
+    /// Certificate ID as queried
+    pub certificate_id: String,
+    /// Whether the certificate has been claimed on Supernova
+    pub claimed: bool,
+    /// Kind of certificate ("REC" or "CARBON_OFFSET"), if claimed
+    pub kind: Option<String>,
+    /// Issuing organization, if claimed
+    pub issuer: Option<String>,
+    /// Miner that claimed the certificate, if claimed
+    pub miner_id: Option<String>,
+    /// MWh for RECs, tonnes CO2e for offsets, if claimed
+    pub amount: Option<f64>,
+    /// Start of the covered period (RFC 3339), if claimed
+    pub period_start: Option<String>,
+    /// End of the covered period (RFC 3339), if claimed
+    pub period_end: Option<String>,
+    /// When the claim was recorded (RFC 3339), if claimed
+    pub claimed_at: Option<String>,
+}
+
+/// Stake-weighted tally of an on-chain governance proposal
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProposalTallyInfo {
+    /// Whether voting has ended and the tally is final
+    pub is_final: bool,
+    /// Weight in favour
+    pub votes_for: u64,
+    /// Weight against
+    pub votes_against: u64,
+    /// Total weight able to vote at the snapshot
+    pub eligible_weight: u64,
+    /// Number of distinct voters counted
+    pub voters: usize,
+    /// Percentage of eligible weight that voted
+    pub participation: f64,
+    /// Percentage of cast weight in favour
+    pub approval: f64,
+    /// Participation required for the proposal to pass
+    pub quorum_percentage: f64,
+    /// Approval required for the proposal to pass
+    pub approval_threshold: f64,
+}
+
+/// On-chain environmental governance proposal
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GovernanceProposalInfo {
+    /// Proposal ID (hex of the proposing transaction's hash)
+    pub id: String,
+    /// Title of the proposal
+    pub title: String,
+    /// Detailed description of the proposal
+    pub description: String,
+    /// What the proposal does
+    pub proposal_type: String,
+    /// Proposer's voter ID (hex)
+    pub proposer: String,
+    /// Status (e.g., "Active", "Approved", "Executed")
+    pub status: String,
+    /// Height voting weight is measured at
+    pub snapshot_height: u64,
+    /// Last height at which votes count
+    pub voting_end_height: u64,
+    /// Height at which an approved proposal takes effect
+    pub activation_height: u64,
+    /// Height the proposal took effect at, once activated
+    pub activated_at: Option<u64>,
+    /// Current tally
+    pub tally: ProposalTallyInfo,
+}
+
+/// Signed reading from a smart meter
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MeterReadingInput {
+    /// Time of the reading (RFC 3339)
+    pub timestamp: String,
+    /// Cumulative energy register in kWh
+    pub energy_kwh: f64,
+    /// Instantaneous power in kW
+    pub power_kw: f64,
+    /// Signature over the reading by the registered meter key (hex)
+    pub signature: String,
+}
+
+/// Batch of readings from one smart meter
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MeterBatchRequest {
+    /// Registered meter ID
+    pub meter_id: String,
+    /// Readings, oldest first
+    pub readings: Vec<MeterReadingInput>,
+}
+
+/// Metered energy of a miner over a completed epoch
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EpochConsumptionInfo {
+    /// Miner ID
+    pub miner_id: String,
+    /// Epoch number
+    pub epoch: i64,
+    /// Start of the epoch (RFC 3339)
+    pub start: String,
+    /// End of the epoch (RFC 3339)
+    pub end: String,
+    /// Energy drawn in kWh
+    pub energy_kwh: f64,
+    /// Highest reported power in kW
+    pub peak_power_kw: f64,
+    /// Number of readings aggregated
+    pub readings: usize,
+    /// Meters that reported
+    pub meters: Vec<String>,
+}
+
+/// Result of ingesting a meter batch
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MeterIngestResponse {
+    /// Meter that sent the batch
+    pub meter_id: String,
+    /// Miner the meter is registered to
+    pub miner_id: String,
+    /// Readings accepted
+    pub accepted: usize,
+    /// Energy attributed by the batch in kWh
+    pub energy_kwh: f64,
+    /// Epochs completed and applied to miner reports
+    pub completed_epochs: Vec<EpochConsumptionInfo>,
+}
@@ -6,6 +6,7 @@ use tracing::{info, warn, error};
 use config::{Config, ConfigError, Environment, File};
 use notify::{self, Watcher, RecommendedWatcher, RecursiveMode};
 use crate::api::ApiConfig;
+use btclib::environmental::chain_governance::ChainGovernanceConfig;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NodeConfig {
@@ -17,6 +18,10 @@ pub struct NodeConfig {
     pub checkpoint: CheckpointConfig,
     pub api: ApiConfig,
     pub testnet: TestnetConfig,
+    #[serde(default)]
+    pub environmental: EnvironmentalConfig,
+    #[serde(default)]
+    pub governance: GovernanceConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -127,6 +132,25 @@ pub struct TestnetConfig {
     pub simulated_packet_loss: f64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EnvironmentalConfig {
+    /// Published oracle-set registry (JSON) that REC attestations are verified against
+    pub oracle_sets_path: Option<PathBuf>,
+    /// Treasury committee (JSON) whose script receives coinbase allocations and authorizes spends
+    pub treasury_committee_path: Option<PathBuf>,
+    /// Smart meters (JSON list) whose signed readings are accepted
+    pub meters_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GovernanceConfig {
+    /// Follow on-chain governance proposals, votes and parameter changes
+    pub enabled: bool,
+    /// Voting rules for on-chain proposals
+    #[serde(default)]
+    pub rules: ChainGovernanceConfig,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PeerDiversityConfig {
     pub enabled: bool,
@@ -186,6 +210,8 @@ impl Default for NodeConfig {
             checkpoint: CheckpointConfig::default(),
             api: ApiConfig::default(),
             testnet: TestnetConfig::default(),
+            environmental: EnvironmentalConfig::default(),
+            governance: GovernanceConfig::default(),
         }
     }
 }
@@ -304,6 +330,25 @@ impl Default for TestnetConfig {
     }
 }
 
+impl Default for EnvironmentalConfig {
+    fn default() -> Self {
+        Self {
+            oracle_sets_path: None,
+            treasury_committee_path: None,
+            meters_path: None,
+        }
+    }
+}
+
+impl Default for GovernanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: ChainGovernanceConfig::default(),
+        }
+    }
+}
+
 impl Default for PeerDiversityConfig {
     fn default() -> Self {
         Self {
@@ -507,6 +552,35 @@ impl NodeConfig {
             return Err("min_rbf_fee_increase must be non-negative".to_string());
         }
 
+        if let Some(path) = &self.environmental.oracle_sets_path {
+            if !path.exists() {
+                return Err(format!("oracle_sets_path {:?} does not exist", path));
+            }
+        }
+        if let Some(path) = &self.environmental.treasury_committee_path {
+            if !path.exists() {
+                return Err(format!("treasury_committee_path {:?} does not exist", path));
+            }
+        }
+        if let Some(path) = &self.environmental.meters_path {
+            if !path.exists() {
+                return Err(format!("meters_path {:?} does not exist", path));
+            }
+        }
+        
+        if self.governance.enabled {
+            let rules = &self.governance.rules;
+            if !(0.0..=100.0).contains(&rules.quorum_percentage) {
+                return Err("governance quorum_percentage must be between 0 and 100".to_string());
+            }
+            if !(0.0..=100.0).contains(&rules.approval_threshold) {
+                return Err("governance approval_threshold must be between 0 and 100".to_string());
+            }
+            if rules.min_voting_blocks == 0 {
+                return Err("governance min_voting_blocks must be greater than 0".to_string());
+            }
+        }
+
         if self.storage.max_open_files < 100 {
             return Err("max_open_files must be at least 100".to_string());
         }
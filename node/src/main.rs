@@ -3,6 +3,7 @@ use node::config::NodeConfig;
 use node::metrics::performance::PerformanceMonitor;
 use tracing::{info, error, warn};
 use clap::Parser;
+use btclib::lightning::tower::{SessionPolicy, TowerServer, TowerServerConfig};
 use std::sync::Arc;
 use tokio::signal;
 
@@ -21,6 +22,18 @@ struct Args {
     /// Enable debug logging
     #[arg(short, long)]
     debug: bool,
+    
+    /// Run a watchtower service listening on this address
+    #[arg(long, value_name = "ADDR")]
+    watchtower: Option<std::net::SocketAddr>,
+    
+    /// Hex script the watchtower's rewards are paid to
+    #[arg(long, value_name = "HEX", requires = "watchtower")]
+    watchtower_reward_script: Option<String>,
+    
+    /// Back up revoked commitments to the watchtower at this address
+    #[arg(long, value_name = "ADDR")]
+    tower_client: Option<std::net::SocketAddr>,
 }
 
 #[tokio::main]
@@ -77,6 +90,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Start the node
     node.start().await?;
     
+    // Start the watchtower service if requested
+    if let Some(addr) = args.watchtower {
+        let reward_script = match &args.watchtower_reward_script {
+            Some(script) => hex::decode(script)?,
+            None => Vec::new(),
+        };
+        let tower = Arc::new(TowerServer::new(TowerServerConfig {
+            reward_script,
+            ..Default::default()
+        }));
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        node.set_watchtower(Arc::clone(&tower));
+        tokio::spawn(async move {
+            if let Err(e) = tower.serve(listener).await {
+                error!("Watchtower stopped: {}", e);
+            }
+        });
+    }
+    
+    // Back up channel state to a remote watchtower if requested
+    if let Some(addr) = args.tower_client {
+        match node.lightning() {
+            Some(manager) => manager.read().unwrap().add_watchtower(addr, SessionPolicy::default()),
+            None => warn!("Lightning is disabled, ignoring --tower-client"),
+        }
+    }
+    
     // Start API server if configured (check if bind_address and port are set)
     let api_server_handle = if !config.api.bind_address.is_empty() && config.api.port > 0 {
         info!("Starting API server on {}:{}", config.api.bind_address, config.api.port);
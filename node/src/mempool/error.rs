@@ -33,6 +33,9 @@ pub enum MempoolError {
     #[error("Invalid transaction: {0}")]
     InvalidTransaction(String),
     
+    #[error("Lock time not satisfied: {0}")]
+    LockTimeNotSatisfied(String),
+    
     #[error("Transaction too large")]
     TransactionTooLarge,
     
@@ -6,7 +6,7 @@ pub mod prioritization;
 pub mod atomic_pool;
 pub mod secure_pool;
 
-pub use pool::{TransactionPool, MempoolConfig};
+pub use pool::{TransactionPool, MempoolConfig, LockTimeCheck};
 pub use error::{MempoolError, MempoolResult};
 pub use priority::TransactionPriority;
 pub use validator::TransactionValidator;
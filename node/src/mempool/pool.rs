@@ -2,6 +2,7 @@ use crate::mempool::error::{MempoolError, MempoolResult};
 use crate::api::types::{MempoolInfo, MempoolTransaction, TransactionValidationResult, TransactionFees};
 use btclib::types::transaction::Transaction;
 use dashmap::DashMap;
+use std::sync::RwLock;
 use std::time::{SystemTime, Duration};
 use crate::config;
 use hex;
@@ -54,12 +55,17 @@ struct MempoolEntry {
     size: usize,      // Size in bytes
 }
 
+/// Check that a transaction's lock times allow it in the next block
+pub type LockTimeCheck = Box<dyn Fn(&Transaction) -> Result<(), String> + Send + Sync>;
+
 /// Thread-safe transaction pool implementation
 pub struct TransactionPool {
     /// Main storage using DashMap for thread-safety
     transactions: DashMap<[u8; 32], MempoolEntry>,
     /// Configuration settings
     config: MempoolConfig,
+    /// Finality and sequence lock check against the chain tip, once set
+    lock_time_check: RwLock<Option<LockTimeCheck>>,
 }
 
 impl TransactionPool {
@@ -68,8 +74,14 @@ impl TransactionPool {
         Self {
             transactions: DashMap::new(),
             config,
+            lock_time_check: RwLock::new(None),
         }
     }
+    
+    /// Reject transactions that `check` finds not final or sequence locked
+    pub fn set_lock_time_check(&self, check: LockTimeCheck) {
+        *self.lock_time_check.write().unwrap() = Some(check);
+    }
 
     /// Add a transaction to the pool
     pub fn add_transaction(&self, transaction: Transaction, fee_rate: u64) -> Result<(), MempoolError> {
@@ -96,6 +108,9 @@ impl TransactionPool {
             });
         }
 
+        // Only transactions that could be mined in the next block
+        self.check_lock_times(&transaction)?;
+
         // Calculate transaction size
         let tx_size = bincode::serialize(&transaction)
             .map_err(|e| MempoolError::SerializationError(e.to_string()))?
@@ -113,6 +128,14 @@ impl TransactionPool {
         Ok(())
     }
 
+    /// Apply the lock time check, if one is set
+    fn check_lock_times(&self, transaction: &Transaction) -> Result<(), MempoolError> {
+        match self.lock_time_check.read().unwrap().as_ref() {
+            Some(check) => check(transaction).map_err(MempoolError::LockTimeNotSatisfied),
+            None => Ok(()),
+        }
+    }
+
     /// Remove a transaction from the pool
     pub fn remove_transaction(&self, tx_hash: &[u8; 32]) -> Option<Transaction> {
         match self.transactions.remove(tx_hash) {
@@ -238,6 +261,8 @@ impl TransactionPool {
             });
         }
         
+        self.check_lock_times(&new_transaction)?;
+        
         // Remove all conflicting transactions
         let mut removed_txs = Vec::new();
         for (hash, entry) in conflicting_txs {
@@ -20,6 +20,19 @@ use btclib::crypto::quantum::QuantumScheme;
 use btclib::lightning::{LightningConfig, LightningNetworkError};
 use btclib::lightning::manager::{LightningManager, ManagerError, LightningEvent};
 use btclib::lightning::wallet::LightningWallet;
+use btclib::lightning::tower::TowerServer;
+use btclib::lightning::forwarding::ForwardingLedger;
+use btclib::lightning::offer_store::OfferStore;
+use btclib::environmental::attestation::{AttestationStore, ConsumedCertificates, OracleSetRegistry};
+use btclib::environmental::retirement::RetirementRegistry;
+use btclib::environmental::meter_telemetry::{EpochConsumption, IngestReceipt, MeterBatch, MeterIngestor, MeterTelemetryError, RegisteredMeter};
+use btclib::environmental::miner_reporting::MinerReportingManager;
+use btclib::environmental::treasury_ledger::{TreasuryCommittee, TreasuryConsensus, TreasuryLedger};
+use btclib::environmental::chain_governance::{ChainGovernanceConfig, GovernanceLedger};
+use btclib::environmental::governance::ProposalStatus;
+use btclib::validation::block::{BlockValidationConfig, BlockValidator, ValidationContext};
+use btclib::validation::finality::{check_lock_times, CoinConfirmation};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex, RwLock, atomic::AtomicBool};
 use std::time::{Instant, Duration};
 use tracing::{info, error, warn, debug};
@@ -33,7 +46,7 @@ use tokio::task::JoinHandle;
 use std::str::FromStr;
 use serde::{Serialize, Deserialize};
 use crate::testnet::NodeTestnetManager;
-use btclib::types::transaction::Transaction;
+use btclib::types::transaction::{Transaction, TransactionOutput};
 use btclib::types::block::Block;
 use hex;
 use uuid;
@@ -93,7 +106,6 @@ impl From<btclib::lightning::wallet::WalletError> for NodeError {
 
 // Placeholder types for missing imports
 type NetworkManager = P2PNetwork;
-type BlockValidator = ();
 type TransactionValidator = ();
 type RpcServer = ();
 type MemPool = TransactionPool;
@@ -134,6 +146,24 @@ pub struct Node {
     testnet_manager: Option<Arc<NodeTestnetManager>>,
     /// Lightning Network manager
     lightning_manager: Option<Arc<RwLock<LightningManager>>>,
+    /// Watchtower service punishing breaches for remote clients
+    watchtower: RwLock<Option<Arc<TowerServer>>>,
+    /// Oracle-signed REC attestations, verified against the published oracle sets
+    attestation_store: Arc<AttestationStore>,
+    /// Certificates behind the environmental bonuses of main-chain blocks
+    consumed_certificates: Arc<RwLock<ConsumedCertificates>>,
+    /// Retired REC and carbon offset IDs, so no certificate is claimed twice
+    retirement_registry: Arc<RetirementRegistry>,
+    /// Verifier and per-epoch aggregator for signed smart-meter readings
+    meter_ingestor: Arc<MeterIngestor>,
+    /// Miner environmental reports, fed with metered consumption
+    miner_reporting: Arc<RwLock<MinerReportingManager>>,
+    /// Committee whose treasury rules blocks must follow, once set
+    treasury_committee: RwLock<Option<TreasuryCommittee>>,
+    /// Environmental treasury state derived from the chain, once a committee is set
+    treasury_ledger: RwLock<Option<TreasuryLedger>>,
+    /// On-chain governance proposals, votes and tallies, once enabled
+    governance_ledger: RwLock<Option<GovernanceLedger>>,
     pub api_config: ApiConfig,
     pub peer_id: PeerId,
     pub start_time: Instant,
@@ -157,6 +187,24 @@ impl Node {
         // Initialize chain state
         let chain_state = Arc::new(RwLock::new(ChainState::new(Arc::clone(&db))?));
         
+        // Keep retired certificate IDs in their own tree
+        let retirement_registry = Arc::new(RetirementRegistry::new(db.open_tree("certificate_retirements")?));
+        let mut miner_reporting = MinerReportingManager::new();
+        miner_reporting.set_retirement_registry(Arc::clone(&retirement_registry));
+        
+        // Verify REC attestations against the published oracle sets
+        let attestation_store = Arc::new(AttestationStore::new(Self::load_oracle_sets(&config)?));
+        let treasury_committee = Self::load_treasury_committee(&config)?;
+        
+        // Accept signed readings from the configured smart meters
+        let meter_ingestor = Arc::new(MeterIngestor::default());
+        for meter in Self::load_meters(&config)? {
+            let meter_id = meter.meter_id.clone();
+            meter_ingestor.register_meter(meter)
+                .map_err(|e| NodeError::ConfigError(format!("Cannot register meter {}: {}", meter_id, e)))?;
+        }
+        let governance = config.governance.clone();
+        
         // Initialize genesis block if needed
         if chain_state.read().unwrap().get_height() == 0 {
             // Create genesis block
@@ -168,6 +216,8 @@ impl Node {
         // Initialize mempool
         let mempool_config = crate::mempool::MempoolConfig::from(config.mempool.clone());
         let mempool = Arc::new(TransactionPool::new(mempool_config));
+        let lock_time_db = Arc::clone(&db);
+        mempool.set_lock_time_check(Box::new(move |tx| Self::check_next_block_lock_times(&lock_time_db, tx)));
         
         // Initialize network
         let keypair = libp2p::identity::Keypair::generate_ed25519();
@@ -242,6 +292,19 @@ impl Node {
                 lightning_wallet,
             ).map_err(|e| NodeError::General(format!("Lightning manager error: {}", e)))?;
             
+            // Keep the forwarding ledger in the node database
+            let forwards_tree = db.open_tree("lightning_forwards")
+                .map_err(|e| NodeError::General(format!("Forwarding ledger error: {}", e)))?;
+            let ledger = ForwardingLedger::new(forwards_tree)
+                .map_err(|e| NodeError::General(format!("Forwarding ledger error: {}", e)))?;
+            lightning_manager.set_forwarding_ledger(ledger);
+            
+            // Keep the offer signing key, offers and invoice requests across restarts
+            let offers_tree = db.open_tree("lightning_offers")
+                .map_err(|e| NodeError::General(format!("Offer store error: {}", e)))?;
+            lightning_manager.set_offer_store(OfferStore::new(offers_tree))
+                .map_err(|e| NodeError::General(format!("Offer store error: {}", e)))?;
+            
             // Create event handler and spawn processing task in the background
             let manager_clone = Arc::new(RwLock::new(lightning_manager));
             let manager_for_task = Arc::clone(&manager_clone);
@@ -250,13 +313,28 @@ impl Node {
             tokio::spawn(async move {
                 Self::process_lightning_events(manager_for_task, event_receiver).await;
             });
-            
+
+            // Fail back held HTLCs before their CLTV deadline
+            let manager_for_holds = Arc::clone(&manager_clone);
+            let chain_for_holds = Arc::clone(&chain_state);
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(30));
+                loop {
+                    interval.tick().await;
+                    let height = chain_for_holds.read().unwrap().get_height() as u32;
+                    let canceled = manager_for_holds.read().unwrap().cancel_expiring_hold_invoices(height);
+                    if canceled > 0 {
+                        warn!("Canceled {} expiring hold invoices at height {}", canceled, height);
+                    }
+                }
+            });
+
             Some(manager_clone)
         } else {
             None
         };
 
-        Ok(Self {
+        let node = Self {
             config: Arc::new(RwLock::new(config)),
             db,
             chain_state: Arc::clone(&chain_state),
@@ -264,6 +342,15 @@ impl Node {
             network,
             testnet_manager,
             lightning_manager,
+            watchtower: RwLock::new(None),
+            attestation_store,
+            consumed_certificates: Arc::new(RwLock::new(ConsumedCertificates::new())),
+            retirement_registry,
+            meter_ingestor,
+            miner_reporting: Arc::new(RwLock::new(miner_reporting)),
+            treasury_committee: RwLock::new(None),
+            treasury_ledger: RwLock::new(None),
+            governance_ledger: RwLock::new(None),
             api_config: ApiConfig::default(),
             peer_id: PeerId::random(),
             start_time: Instant::now(),
@@ -272,7 +359,17 @@ impl Node {
             wallet: Arc::new(()),
             db_shutdown_handler: None,
             wal: None,
-        })
+        };
+        
+        node.rebuild_consumed_certificates()?;
+        if governance.enabled {
+            node.enable_chain_governance(governance.rules)?;
+        }
+        if let Some(committee) = treasury_committee {
+            node.set_treasury_committee(&committee)?;
+        }
+        
+        Ok(node)
     }
 
     /// Start the node
@@ -373,6 +470,10 @@ impl Node {
         if !block.validate() {
             return Err(NodeError::General("Block validation failed".to_string()));
         }
+        self.validate_lock_times(&block)?;
+        let spent_outputs = self.spent_outputs(&block)?;
+        self.validate_treasury(&block, &spent_outputs)?;
+        self.validate_environmental_bonus(&block, &spent_outputs)?;
         
         // Add to chain state
         self.chain_state.write().unwrap().add_block(&block)
@@ -383,16 +484,75 @@ impl Node {
             self.mempool.remove_transaction(&tx.hash());
         }
         
+        // Punish revoked commitments watched on behalf of tower clients
+        let watchtower = self.watchtower.read().unwrap().clone();
+        if let Some(tower) = watchtower {
+            for justice_tx in tower.process_transactions(block.transactions()) {
+                self.broadcast_transaction(&justice_tx);
+            }
+        }
+        
         // Store full block in database
         self.db.insert_block(&block)
             .map_err(|e| NodeError::StorageError(e))?;
         
+        self.update_ledgers(&block)?;
+        
         // Broadcast to network if this is a new block we mined
         self.network.broadcast_block(&block);
         
                 Ok(())
             }
     
+    /// Move chain-derived ledgers along with the main chain after `block` was added
+    ///
+    /// Blocks a reorganization disconnected are undone before the new
+    /// branch is applied; blocks stored on a side branch are not applied.
+    fn update_ledgers(&self, block: &Block) -> Result<(), NodeError> {
+        let (disconnected, connected) = {
+            let mut chain_state = self.chain_state.write().unwrap();
+            match chain_state.take_reorganization() {
+                Some(reorganization) => (reorganization.disconnected, reorganization.connected),
+                None if chain_state.get_best_block_hash() == block.hash() => (Vec::new(), vec![block.clone()]),
+                None => return Ok(()),
+            }
+        };
+        
+        // Release and consume the certificates behind environmental bonuses
+        let validator = self.bonus_validator();
+        if let Some(lowest) = disconnected.iter().map(|block| block.height()).min() {
+            validator.disconnect_blocks_from(lowest);
+        }
+        for block in &connected {
+            validator.record_certificates(block)
+                .map_err(|e| NodeError::General(format!("Consumed certificates: {}", e)))?;
+        }
+        
+        // Track treasury allocations and spends
+        if let Some(ledger) = self.treasury_ledger.write().unwrap().as_mut() {
+            for block in &disconnected {
+                ledger.disconnect_block(block)
+                    .map_err(|e| NodeError::General(format!("Treasury ledger: {}", e)))?;
+            }
+            for block in &connected {
+                ledger.apply_block(block);
+            }
+        }
+        
+        // Record governance votes, tallying and activating proposals that are due
+        if let Some(ledger) = self.governance_ledger.write().unwrap().as_mut() {
+            for block in &disconnected {
+                ledger.disconnect_block(block)
+                    .map_err(|e| NodeError::General(format!("Governance ledger: {}", e)))?;
+            }
+            for block in &connected {
+                ledger.apply_block(block);
+            }
+        }
+        
+        Ok(())
+    }
+    
     /// Get storage (blockchain database)
     pub fn storage(&self) -> Arc<BlockchainDB> {
         Arc::clone(&self.db)
@@ -692,6 +852,338 @@ impl Node {
         self.lightning_manager.as_ref().map(Arc::clone)
     }
     
+    /// Get the REC attestation store
+    pub fn attestations(&self) -> Arc<AttestationStore> {
+        Arc::clone(&self.attestation_store)
+    }
+    
+    /// Load the oracle-set registry named in the environmental config
+    fn load_oracle_sets(config: &NodeConfig) -> Result<OracleSetRegistry, NodeError> {
+        let path = match &config.environmental.oracle_sets_path {
+            Some(path) => path,
+            None => return Ok(OracleSetRegistry::new()),
+        };
+        
+        let json = std::fs::read_to_string(path)?;
+        let registry = OracleSetRegistry::from_json(&json)
+            .map_err(|e| NodeError::ConfigError(format!("Invalid oracle sets in {:?}: {}", path, e)))?;
+        info!("Loaded oracle sets up to epoch {:?} from {:?}", registry.latest_epoch(), path);
+        Ok(registry)
+    }
+    
+    /// Load the treasury committee named in the environmental config, if any
+    fn load_treasury_committee(config: &NodeConfig) -> Result<Option<TreasuryCommittee>, NodeError> {
+        let path = match &config.environmental.treasury_committee_path {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        
+        let json = std::fs::read_to_string(path)?;
+        let committee: TreasuryCommittee = serde_json::from_str(&json)
+            .map_err(|e| NodeError::ConfigError(format!("Invalid treasury committee in {:?}: {}", path, e)))?;
+        info!("Loaded {}-of-{} treasury committee from {:?}", committee.threshold, committee.members.len(), path);
+        Ok(Some(committee))
+    }
+    
+    /// Load the smart meters named in the environmental config, if any
+    fn load_meters(config: &NodeConfig) -> Result<Vec<RegisteredMeter>, NodeError> {
+        let path = match &config.environmental.meters_path {
+            Some(path) => path,
+            None => return Ok(Vec::new()),
+        };
+        
+        let json = std::fs::read_to_string(path)?;
+        let meters: Vec<RegisteredMeter> = serde_json::from_str(&json)
+            .map_err(|e| NodeError::ConfigError(format!("Invalid meters in {:?}: {}", path, e)))?;
+        info!("Loaded {} smart meters from {:?}", meters.len(), path);
+        Ok(meters)
+    }
+    
+    /// Get the certificate retirement registry
+    pub fn retirements(&self) -> Arc<RetirementRegistry> {
+        Arc::clone(&self.retirement_registry)
+    }
+    
+    /// Get the smart-meter telemetry ingestor
+    pub fn meters(&self) -> Arc<MeterIngestor> {
+        Arc::clone(&self.meter_ingestor)
+    }
+    
+    /// Get the miner environmental reporting manager
+    pub fn miner_reporting(&self) -> Arc<RwLock<MinerReportingManager>> {
+        Arc::clone(&self.miner_reporting)
+    }
+    
+    /// Accept signed readings from a meter registered with `register`
+    pub fn register_meter(&self, register: RegisteredMeter) -> Result<(), MeterTelemetryError> {
+        self.meter_ingestor.register_meter(register)
+    }
+    
+    /// Ingest a batch of signed meter readings
+    ///
+    /// Epochs that have ended by `now` are applied to the miners' reported
+    /// energy consumption and returned alongside the receipt.
+    pub fn ingest_meter_readings(
+        &self,
+        batch: &MeterBatch,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(IngestReceipt, Vec<EpochConsumption>), MeterTelemetryError> {
+        let receipt = self.meter_ingestor.ingest(batch, now)?;
+        let completed = self.meter_ingestor.complete_epochs(now);
+        
+        let mut reporting = self.miner_reporting.write().unwrap();
+        for consumption in &completed {
+            if let Err(e) = reporting.apply_metered_consumption(consumption) {
+                warn!("Metered consumption for epoch {} not applied: {}", consumption.epoch, e);
+            }
+        }
+        
+        Ok((receipt, completed))
+    }
+    
+    /// Serve as a watchtower, checking every processed block for breaches
+    pub fn set_watchtower(&self, tower: Arc<TowerServer>) {
+        *self.watchtower.write().unwrap() = Some(tower);
+    }
+    
+    /// Track the environmental treasury locked to `committee`
+    ///
+    /// Replays the stored chain so balance and spend history come from blocks
+    /// rather than process memory; later blocks are applied as they arrive.
+    pub fn set_treasury_committee(&self, committee: &TreasuryCommittee) -> Result<(), NodeError> {
+        committee.validate()
+            .map_err(|e| NodeError::ConfigError(format!("Invalid treasury committee: {}", e)))?;
+        
+        let mut ledger = TreasuryLedger::new(committee.script());
+        self.replay_chain(|block| ledger.apply_block(block))?;
+        
+        *self.treasury_committee.write().unwrap() = Some(committee.clone());
+        *self.treasury_ledger.write().unwrap() = Some(ledger);
+        Ok(())
+    }
+    
+    /// Check a block against the treasury rules, once a committee is set
+    ///
+    /// The coinbase must pay the treasury its allocation, at the rate
+    /// governance has set when chain governance is enabled, and treasury
+    /// outputs may only be spent for proposals governance has approved.
+    fn validate_treasury(
+        &self,
+        block: &Block,
+        spent_outputs: &HashMap<([u8; 32], u32), TransactionOutput>,
+    ) -> Result<(), NodeError> {
+        let committee = match self.treasury_committee.read().unwrap().clone() {
+            Some(committee) => committee,
+            None => return Ok(()),
+        };
+        
+        // Budget left to each approved proposal: its amount less what earlier
+        // blocks already paid out for it
+        let governance = self.governance_ledger.read().unwrap();
+        let treasury_ledger = self.treasury_ledger.read().unwrap();
+        let mut treasury = TreasuryConsensus::new(committee);
+        let mut proposal_budgets = HashMap::new();
+        if let Some(ledger) = governance.as_ref() {
+            treasury.allocation_bps = ledger.parameters().treasury_allocation_bps;
+            for proposal in ledger.proposals() {
+                if !matches!(proposal.status, ProposalStatus::Approved | ProposalStatus::Executed) {
+                    continue;
+                }
+                let amount = proposal.payload.proposal_type.spend_amount().unwrap_or(0);
+                let paid = treasury_ledger.as_ref()
+                    .map(|treasury| treasury.spends_for_proposal(&proposal.id).iter().map(|spend| spend.paid_out()).sum())
+                    .unwrap_or(0);
+                proposal_budgets.insert(proposal.id.clone(), amount.saturating_sub(paid));
+            }
+        }
+        drop(treasury_ledger);
+        drop(governance);
+        
+        let spent_scripts: HashMap<_, _> = spent_outputs.iter()
+            .map(|(outpoint, output)| (*outpoint, output.script_pubkey().to_vec()))
+            .collect();
+        let context = ValidationContext {
+            prev_block_hash: *block.prev_block_hash(),
+            prev_block_height: block.height().saturating_sub(1),
+            prev_block_timestamp: 0,
+            median_time_past: 0,
+            current_difficulty: 0,
+            utxo_provider: Some(Box::new(move |hash: &[u8; 32], index: u32| {
+                spent_scripts.get(&(*hash, index)).cloned()
+            })),
+            proposal_budget_provider: Some(Box::new(move |proposal_id: &str| {
+                proposal_budgets.get(proposal_id).copied()
+            })),
+            confirmation_provider: None,
+        };
+        
+        let validator = BlockValidator::with_config(BlockValidationConfig {
+            treasury: Some(treasury),
+            ..BlockValidationConfig::default()
+        });
+        validator.validate_treasury(block, &context)
+            .map_err(|e| NodeError::General(format!("Block validation failed: {}", e)))
+    }
+    
+    /// Check that every transaction in the block is final and past the
+    /// sequence locks of its inputs
+    ///
+    /// Outputs created earlier in the block count as confirmed at its height.
+    fn validate_lock_times(&self, block: &Block) -> Result<(), NodeError> {
+        let height = block.height();
+        let median_time_past = self.db.median_time_past(block.prev_block_hash())
+            .map_err(NodeError::StorageError)?;
+        let created: HashSet<[u8; 32]> = block.transactions().iter().map(|tx| tx.hash()).collect();
+        let db = Arc::clone(&self.db);
+        let context = ValidationContext {
+            prev_block_hash: *block.prev_block_hash(),
+            prev_block_height: height.saturating_sub(1),
+            prev_block_timestamp: 0,
+            median_time_past,
+            current_difficulty: 0,
+            utxo_provider: None,
+            proposal_budget_provider: None,
+            confirmation_provider: Some(Box::new(move |hash: &[u8; 32], _| {
+                if created.contains(hash) {
+                    return Some(CoinConfirmation { height, median_time_past });
+                }
+                db.transaction_confirmation(hash).ok().flatten()
+            })),
+        };
+        
+        BlockValidator::new().validate_lock_times(block, &context)
+            .map_err(|e| NodeError::General(format!("Block validation failed: {}", e)))
+    }
+    
+    /// Mempool admission check: `tx` must be final and past its sequence
+    /// locks in the block after the tip
+    ///
+    /// Outputs of unconfirmed transactions count as confirmed in that block.
+    fn check_next_block_lock_times(db: &BlockchainDB, tx: &Transaction) -> Result<(), String> {
+        let height = db.get_height().map_err(|e| e.to_string())? + 1;
+        let median_time_past = db.get_best_block_hash()
+            .and_then(|tip| db.median_time_past(&tip))
+            .map_err(|e| e.to_string())?;
+        let unconfirmed = CoinConfirmation { height, median_time_past };
+        
+        check_lock_times(tx, height, median_time_past, |hash: &[u8; 32], _| {
+            Some(db.transaction_confirmation(hash).ok().flatten().unwrap_or(unconfirmed))
+        })
+        .map_err(|e| e.to_string())
+    }
+    
+    /// Outputs the block spends, from earlier transactions in the block or
+    /// the UTXO set
+    ///
+    /// An output found in neither cannot be checked against the treasury
+    /// script or counted towards fees, so the block is rejected.
+    fn spent_outputs(&self, block: &Block) -> Result<HashMap<([u8; 32], u32), TransactionOutput>, NodeError> {
+        let mut created = HashMap::new();
+        let mut spent = HashMap::new();
+        for tx in block.transactions() {
+            if !tx.is_coinbase() {
+                for input in tx.inputs() {
+                    let outpoint = (input.prev_tx_hash(), input.prev_output_index());
+                    let output = match created.get(&outpoint) {
+                        Some(output) => Some(TransactionOutput::clone(output)),
+                        None => self.db.get_transaction_output(&outpoint.0, outpoint.1)
+                            .map_err(NodeError::StorageError)?
+                            .map(|data| bincode::deserialize::<TransactionOutput>(&data))
+                            .transpose()
+                            .map_err(|e| NodeError::General(format!("Corrupt UTXO entry: {}", e)))?,
+                    };
+                    let output = output.ok_or_else(|| NodeError::General(format!(
+                        "Block spends unknown output {}:{}", hex::encode(outpoint.0), outpoint.1
+                    )))?;
+                    spent.insert(outpoint, output);
+                }
+            }
+            
+            let txid = tx.hash();
+            for (index, output) in tx.outputs().iter().enumerate() {
+                created.insert((txid, index as u32), output.clone());
+            }
+        }
+        Ok(spent)
+    }
+    
+    /// Validator for environmental bonuses, sharing the node's consumed certificates
+    fn bonus_validator(&self) -> BlockValidator {
+        BlockValidator::with_config(BlockValidationConfig {
+            attestation_oracles: self.attestation_store.registry(),
+            ..BlockValidationConfig::default()
+        })
+        .with_consumed_certificates(Arc::clone(&self.consumed_certificates))
+    }
+    
+    /// Check the environmental bonus the block's coinbase claims
+    ///
+    /// The coinbase may pay the subsidy and the block's fees; anything more
+    /// must be committed to and earned by oracle-signed attestations that no
+    /// earlier main-chain block used.
+    fn validate_environmental_bonus(
+        &self,
+        block: &Block,
+        spent_outputs: &HashMap<([u8; 32], u32), TransactionOutput>,
+    ) -> Result<(), NodeError> {
+        let mut fees = 0u64;
+        for tx in block.transactions().iter().filter(|tx| !tx.is_coinbase()) {
+            let input_value = tx.inputs().iter()
+                .filter_map(|input| spent_outputs.get(&(input.prev_tx_hash(), input.prev_output_index())))
+                .map(|output| output.value())
+                .fold(0u64, u64::saturating_add);
+            let output_value = tx.outputs().iter().map(|output| output.value()).fold(0u64, u64::saturating_add);
+            fees = fees.saturating_add(input_value.saturating_sub(output_value));
+        }
+        
+        self.bonus_validator().validate_environmental_bonus(block, fees)
+            .map_err(|e| NodeError::General(format!("Block validation failed: {}", e)))
+    }
+    
+    /// Rebuild the consumed certificates from the stored chain
+    fn rebuild_consumed_certificates(&self) -> Result<(), NodeError> {
+        let validator = self.bonus_validator();
+        self.replay_chain(|block| {
+            if let Err(e) = validator.record_certificates(block) {
+                warn!("Certificates of block {} not recorded: {}", hex::encode(block.hash()), e);
+            }
+        })
+    }
+    
+    /// Get the chain-derived treasury ledger, if a committee is set
+    pub fn treasury_ledger(&self) -> Option<TreasuryLedger> {
+        self.treasury_ledger.read().unwrap().clone()
+    }
+    
+    /// Follow on-chain governance under `config`
+    ///
+    /// Replays the stored chain to rebuild stake, proposals and votes; later
+    /// blocks are applied as they arrive.
+    pub fn enable_chain_governance(&self, config: ChainGovernanceConfig) -> Result<(), NodeError> {
+        let mut ledger = GovernanceLedger::new(config);
+        self.replay_chain(|block| ledger.apply_block(block))?;
+        
+        *self.governance_ledger.write().unwrap() = Some(ledger);
+        Ok(())
+    }
+    
+    /// Get the on-chain governance ledger, if enabled
+    pub fn governance_ledger(&self) -> Option<GovernanceLedger> {
+        self.governance_ledger.read().unwrap().clone()
+    }
+    
+    /// Feed every stored block, in height order, to `apply`
+    fn replay_chain(&self, mut apply: impl FnMut(&Block)) -> Result<(), NodeError> {
+        let height = self.db.get_height()
+            .map_err(|e| NodeError::StorageError(e))?;
+        for h in 0..=height {
+            if let Some(block) = self.db.get_block_by_height(h).map_err(|e| NodeError::StorageError(e))? {
+                apply(&block);
+            }
+        }
+        Ok(())
+    }
+    
     /// Process Lightning Network events
     async fn process_lightning_events(
         manager: Arc<RwLock<LightningManager>>,
@@ -4,6 +4,8 @@ use std::sync::Arc;
 use thiserror::Error;
 use btclib::types::block::{Block, BlockHeader};
 use btclib::types::transaction::Transaction;
+use btclib::consensus::timestamp_validation::MEDIAN_TIME_BLOCKS;
+use btclib::validation::finality::CoinConfirmation;
 use std::path::PathBuf;
 use std::time::{SystemTime, Duration, UNIX_EPOCH};
 use serde::{Serialize, Deserialize};
@@ -1173,8 +1175,8 @@ impl BlockchainDB {
     pub fn insert_block(&self, block: &Block) -> Result<(), StorageError> {
         let block_hash = block.hash();
         let block_data = bincode::serialize(block)?;
-        self.blocks.insert(block_hash, block_data)?;
-        Ok(())
+        // Go through store_block so the bloom filter sees the block
+        self.store_block(&block_hash, &block_data)
     }
     
     /// Set metadata in the database
@@ -2309,6 +2311,51 @@ impl BlockchainDB {
         }
     }
 
+    /// Record the main-chain block that confirmed a transaction
+    pub fn index_transaction(&self, tx_hash: &[u8; 32], block_hash: &[u8; 32]) -> Result<(), StorageError> {
+        self.tx_index.insert(tx_hash, block_hash)?;
+        Ok(())
+    }
+    
+    /// Forget the confirming block of a transaction whose block was disconnected
+    pub fn remove_transaction_index(&self, tx_hash: &[u8; 32]) -> Result<(), StorageError> {
+        self.tx_index.remove(tx_hash)?;
+        Ok(())
+    }
+    
+    /// Median timestamp of a block and up to ten of its ancestors
+    pub fn median_time_past(&self, block_hash: &[u8; 32]) -> Result<u64, StorageError> {
+        let mut timestamps = Vec::with_capacity(MEDIAN_TIME_BLOCKS);
+        let mut hash = *block_hash;
+        while timestamps.len() < MEDIAN_TIME_BLOCKS {
+            match self.get_block(&hash)? {
+                Some(block) => {
+                    timestamps.push(block.timestamp());
+                    hash = *block.prev_block_hash();
+                }
+                None => break,
+            }
+        }
+        
+        timestamps.sort_unstable();
+        Ok(timestamps.get(timestamps.len() / 2).copied().unwrap_or(0))
+    }
+    
+    /// Height and prior median time past of the block that confirmed a
+    /// main-chain transaction
+    pub fn transaction_confirmation(&self, tx_hash: &[u8; 32]) -> Result<Option<CoinConfirmation>, StorageError> {
+        let block = match self.get_transaction_block(tx_hash)? {
+            Some(block_hash) => self.get_block(&block_hash)?,
+            None => None,
+        };
+        
+        block.map(|block| Ok(CoinConfirmation {
+            height: block.height(),
+            median_time_past: self.median_time_past(block.prev_block_hash())?,
+        }))
+        .transpose()
+    }
+    
     /// Get a transaction output
     pub fn get_transaction_output(&self, tx_hash: &[u8; 32], vout: u32) -> Result<Option<Vec<u8>>, StorageError> {
         let utxo_key = create_utxo_key(tx_hash, vout);
@@ -21,7 +21,7 @@ pub mod database_shutdown_tests;
 #[cfg(test)]
 mod utxo_attack_tests;
 
-pub use persistence::ChainState;
+pub use persistence::{ChainReorganization, ChainState};
 pub use database::{BlockchainDB, BlockchainDBConfig, StorageError, IntegrityCheckLevel, IntegrityCheckResult};
 pub use backup::{BackupManager, BackupMode, BackupState, BackupError, BackupOperation, RecoveryManager};
 pub use checkpoint::{CheckpointManager, CheckpointType, CheckpointConfig, CheckpointError};
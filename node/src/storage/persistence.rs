@@ -32,6 +32,16 @@ pub struct ChainState {
     active_forks: HashMap<[u8; 32], ForkInfo>,
     last_block_time: SystemTime,
     rejected_reorgs: u64,
+    last_reorganization: Option<ChainReorganization>,
+}
+
+/// Blocks a reorganization moved the main chain across
+#[derive(Debug, Clone)]
+pub struct ChainReorganization {
+    /// Blocks taken off the main chain, old tip first
+    pub disconnected: Vec<Block>,
+    /// Blocks added to the main chain, lowest first, ending with the new tip
+    pub connected: Vec<Block>,
 }
 
 #[derive(Debug)]
@@ -107,6 +117,7 @@ impl ChainState {
             active_forks: HashMap::new(),
             last_block_time: SystemTime::now(),
             rejected_reorgs: 0,
+            last_reorganization: None,
         })
     }
 
@@ -127,6 +138,7 @@ impl ChainState {
         
         // Store the genesis block
         self.store_block(genesis_block.clone())?;
+        self.connect_utxos(&genesis_block)?;
         
         // Set genesis hash in metadata
         self.db.store_metadata(b"genesis_hash", &genesis_block.hash())?;
@@ -210,6 +222,14 @@ impl ChainState {
         self.rejected_reorgs
     }
 
+    /// Take the blocks the last reorganization moved across, if any since the last call
+    ///
+    /// State derived from the main chain uses this to undo the disconnected
+    /// blocks and apply the connected ones.
+    pub fn take_reorganization(&mut self) -> Option<ChainReorganization> {
+        self.last_reorganization.take()
+    }
+
     /// Get information about active forks
     pub fn get_active_forks(&self) -> Vec<ForkInfo> {
         self.active_forks.values().cloned().collect()
@@ -329,6 +349,7 @@ impl ChainState {
             let block_difficulty = calculate_block_work(extract_target_from_block(&block)) as u64;
             
             self.store_block(block.clone())?;
+            self.connect_utxos(&block)?;
             self.chain_work.insert(block_hash, new_chain_work);
             
             // Update chain state
@@ -365,16 +386,24 @@ impl ChainState {
             }
         }
 
+        // Outputs created earlier in the block may be spent by later transactions
+        let mut created = HashSet::new();
         for tx in block.transactions() {
-            if !self.validate_transaction(tx).await? {
+            if !self.validate_transaction(tx, &created).await? {
                 return Ok(false);
             }
+            let tx_hash = tx.hash();
+            created.extend((0..tx.outputs().len() as u32).map(|index| (tx_hash, index)));
         }
 
         Ok(true)
     }
 
-    async fn validate_transaction(&self, tx: &Transaction) -> Result<bool, StorageError> {
+    async fn validate_transaction(&self, tx: &Transaction, created: &HashSet<([u8; 32], u32)>) -> Result<bool, StorageError> {
+        if tx.is_coinbase() {
+            return Ok(true);
+        }
+
         let mut spent_outputs = HashSet::new();
         for input in tx.inputs() {
             let outpoint = (input.prev_tx_hash(), input.prev_output_index());
@@ -382,7 +411,8 @@ impl ChainState {
                 return Ok(false);
             }
 
-            if self.db.get_utxo(&input.prev_tx_hash(), input.prev_output_index())?.is_none() {
+            if !created.contains(&outpoint)
+                && self.db.get_transaction_output(&outpoint.0, outpoint.1)?.is_none() {
                 return Ok(false);
             }
         }
@@ -456,8 +486,8 @@ impl ChainState {
 
         self.db.begin_transaction()?;
 
-        // Disconnect blocks from the current main chain
-        for block in blocks_to_disconnect.iter().rev() {
+        // Disconnect blocks from the current main chain, tip first
+        for block in blocks_to_disconnect.iter() {
             if let Err(e) = self.disconnect_block(block) {
                 error!("Error disconnecting block during reorganization: {:?}", e);
                 self.db.rollback_transaction()?;
@@ -465,9 +495,9 @@ impl ChainState {
             }
         }
 
-        // Connect blocks from the new chain
+        // Connect blocks from the new chain, lowest first
         let mut total_difficulty_adjustment: u64 = 0;
-        for block in blocks_to_apply.iter() {
+        for block in blocks_to_apply.iter().rev() {
             let block_difficulty = calculate_block_work(extract_target_from_block(block)) as u64;
             total_difficulty_adjustment += block_difficulty;
             
@@ -494,6 +524,11 @@ impl ChainState {
             return Err(e);
         }
         
+        self.last_reorganization = Some(ChainReorganization {
+            disconnected: blocks_to_disconnect,
+            connected: blocks_to_apply.into_iter().rev().collect(),
+        });
+
         // Update fork points
         self.prune_fork_points()?;
         
@@ -522,10 +557,12 @@ impl ChainState {
     }
 
     fn disconnect_block(&mut self, block: &Block) -> Result<(), StorageError> {
-        for tx in block.transactions() {
+        // Newest transaction first, so outputs spent within the block are not restored
+        for tx in block.transactions().iter().rev() {
             for (index, _) in tx.outputs().iter().enumerate() {
                 self.db.remove_utxo(&tx.hash(), index as u32)?;
             }
+            self.db.remove_transaction_index(&tx.hash())?;
 
             for input in tx.inputs() {
                 if let Some(prev_tx) = self.db.get_transaction(&input.prev_tx_hash())? {
@@ -556,7 +593,33 @@ impl ChainState {
         Ok(())
     }
 
+    /// Apply a block to the UTXO set: spend its inputs and add its outputs
+    ///
+    /// Transactions are stored alongside so `disconnect_block` can restore
+    /// the outputs they spent.
+    fn connect_utxos(&self, block: &Block) -> Result<(), StorageError> {
+        let block_hash = block.hash();
+        for tx in block.transactions() {
+            let tx_hash = tx.hash();
+            if !tx.is_coinbase() {
+                for input in tx.inputs() {
+                    self.db.remove_utxo(&input.prev_tx_hash(), input.prev_output_index())?;
+                }
+            }
+
+            for (index, output) in tx.outputs().iter().enumerate() {
+                self.db.store_utxo(&tx_hash, index as u32, &bincode::serialize(output)?)?;
+            }
+            self.db.store_transaction(&tx_hash, &bincode::serialize(tx)?)?;
+            self.db.index_transaction(&tx_hash, &block_hash)?;
+        }
+
+        Ok(())
+    }
+
     fn connect_block(&mut self, block: &Block) -> Result<(), StorageError> {
+        self.connect_utxos(block)?;
+
         // Calculate total difficulty and block work
         let block_difficulty = calculate_block_work(extract_target_from_block(block)) as u64;
         
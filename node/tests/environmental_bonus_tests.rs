@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use btclib::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+use btclib::environmental::attestation::{
+    miner_id_for_script, AttestationClaim, AttestationKind, OracleSet, OracleSetMember, OracleSetRegistry,
+    RecAttestation, ATTESTATION_VERSION,
+};
+use btclib::environmental::types::EnergySource;
+use btclib::types::block::Block;
+use btclib::types::coinbase::CoinbaseBuilder;
+use node::{BlockchainDB, Node, NodeConfig};
+
+const MINER_SCRIPT: &[u8] = b"miner";
+const SUBSIDY: u64 = 50_000_000_000;
+const BONUS: u64 = 1_000_000_000;
+
+/// REC attestation for one MWh, signed by the only member of epoch 1
+fn attest(oracle: &QuantumKeyPair, certificate_id: &str, reward_script: &[u8]) -> RecAttestation {
+    let mut attestation = RecAttestation::new(AttestationClaim {
+        version: ATTESTATION_VERSION,
+        kind: AttestationKind::RenewableCertificate,
+        certificate_id: certificate_id.to_string(),
+        miner_id: miner_id_for_script(reward_script),
+        energy_milli_mwh: 1_000,
+        period_start: 1_700_000_000,
+        period_end: 1_702_592_000,
+        location: "IS".to_string(),
+        energy_type: EnergySource::Geothermal,
+        oracle_epoch: 1,
+    });
+    attestation.sign(0, oracle).unwrap();
+    attestation
+}
+
+/// Mine a block at `height` whose coinbase is built by `coinbase`
+///
+/// `0x03ffffff` decodes to a target nearly every hash meets.
+fn mine_block(height: u64, prev_hash: [u8; 32], coinbase: CoinbaseBuilder, reward: u64) -> Block {
+    let mut block = Block::new_with_params(1, prev_hash, vec![coinbase.build(reward, MINER_SCRIPT.to_vec())], 0x03ffffff);
+    block.set_height(height);
+    while !block.verify_proof_of_work() {
+        block.header.increment_nonce();
+    }
+    block
+}
+
+#[tokio::test]
+async fn test_environmental_bonus_is_validated_against_the_stored_chain() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let oracle = QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap();
+    let mut registry = OracleSetRegistry::new();
+    registry.publish(OracleSet {
+        epoch: 1,
+        threshold: 1,
+        security_level: 2,
+        members: vec![OracleSetMember { oracle_id: "oracle-0".to_string(), public_key: oracle.public_key.clone() }],
+    }).unwrap();
+    let oracle_sets_path = db_dir.path().join("oracle_sets.json");
+    std::fs::write(&oracle_sets_path, registry.to_json().unwrap()).unwrap();
+    
+    // A stored main chain whose block 1 used REC-1 for its bonus
+    let used = attest(&oracle, "REC-1", MINER_SCRIPT);
+    let block_1 = mine_block(1, [0u8; 32], CoinbaseBuilder::new(1).with_environmental_commitment(&[used.clone()], BONUS), SUBSIDY + BONUS);
+    {
+        let db = BlockchainDB::new(db_dir.path().join("db")).unwrap();
+        db.insert_block(&block_1).unwrap();
+        db.store_block_height_index(1, &block_1.hash()).unwrap();
+        db.set_height(1).unwrap();
+    }
+    
+    let mut config = NodeConfig::default();
+    config.storage.db_path = db_dir.path().join("db");
+    config.node.enable_lightning = false;
+    config.environmental.oracle_sets_path = Some(oracle_sets_path);
+    let node = Arc::new(Node::new(config).await.unwrap());
+    
+    // The node rebuilt the consumed certificates, so REC-1 cannot back another bonus
+    let reused = mine_block(2, block_1.hash(), CoinbaseBuilder::new(2).with_environmental_commitment(&[used], BONUS), SUBSIDY + BONUS);
+    let error = node.process_block(reused).await.unwrap_err();
+    assert!(error.to_string().contains("already backed"), "{}", error);
+    
+    // A bonus without a commitment is rejected
+    let uncommitted = mine_block(2, block_1.hash(), CoinbaseBuilder::new(2), SUBSIDY + BONUS);
+    let error = node.process_block(uncommitted).await.unwrap_err();
+    assert!(error.to_string().contains("without a commitment"), "{}", error);
+    
+    // So is one backed by a certificate attested to another miner
+    let foreign = attest(&oracle, "REC-2", b"other-miner");
+    let block = mine_block(2, block_1.hash(), CoinbaseBuilder::new(2).with_environmental_commitment(&[foreign], BONUS), SUBSIDY + BONUS);
+    let error = node.process_block(block).await.unwrap_err();
+    assert!(error.to_string().contains("another miner"), "{}", error);
+}
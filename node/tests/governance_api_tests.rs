@@ -0,0 +1,104 @@
+use actix_web::{test, web, App};
+use serde_json::Value;
+use std::sync::Arc;
+
+use btclib::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+use btclib::environmental::chain_governance::{voter_id, GovernanceRecord, ProposalPayload, SignedProposal, SignedVote, StakeLock};
+use btclib::environmental::governance::ProposalType;
+use btclib::types::block::Block;
+use btclib::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+use node::api::routes::environmental;
+use node::{Node, NodeConfig};
+
+/// Start a node on a fresh database, with on-chain governance enabled or not
+async fn start_node(db_dir: &tempfile::TempDir, governance: bool) -> Arc<Node> {
+    let mut config = NodeConfig::default();
+    config.storage.db_path = db_dir.path().join("db");
+    config.node.enable_lightning = false;
+    config.governance.enabled = governance;
+    config.governance.rules.min_voting_blocks = 2;
+    config.governance.rules.activation_delay_blocks = 1;
+    
+    Arc::new(Node::new(config).await.unwrap())
+}
+
+/// Store a block carrying governance records at `height`
+fn store_block(node: &Node, height: u64, seed: u8, records: Vec<(u64, GovernanceRecord)>) -> Transaction {
+    let tx = Transaction::new(
+        1,
+        vec![TransactionInput::new([seed; 32], 0, Vec::new(), 0xffffffff)],
+        records.into_iter().map(|(value, record)| TransactionOutput::new(value, record.to_script())).collect(),
+        0,
+    );
+    let mut block = Block::new_with_params(1, [height as u8; 32], vec![tx.clone()], 0x207fffff);
+    block.set_height(height);
+    
+    let storage = node.storage();
+    storage.insert_block(&block).unwrap();
+    storage.store_block_height_index(height, &block.hash()).unwrap();
+    storage.set_height(height).unwrap();
+    tx
+}
+
+#[actix_rt::test]
+async fn test_governance_proposals_endpoint() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let node = start_node(&db_dir, true).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(Arc::clone(&node)))
+            .configure(environmental::configure)
+    ).await;
+    
+    // The [governance] section enables the ledger at startup
+    let req = test::TestRequest::get().uri("/environmental/governance/proposals").to_request();
+    let proposals: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(proposals, Value::Array(vec![]));
+    
+    // Stake, a proposal and a vote mined on chain
+    let key = QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap();
+    let stake = StakeLock { voter: voter_id(&key.public_key), unlock_height: 1_000 };
+    store_block(&node, 1, 1, vec![(700, GovernanceRecord::Stake(stake))]);
+    let proposal = SignedProposal::sign(ProposalPayload {
+        title: "Raise treasury allocation".to_string(),
+        description: "Allocate 3% of the subsidy".to_string(),
+        proposal_type: ProposalType::ChangeFeeAllocation { new_percentage: 3.0 },
+        voting_end_height: 4,
+        activation_height: 5,
+        url: None,
+    }, &key).unwrap();
+    let proposal_tx = store_block(&node, 2, 2, vec![(0, GovernanceRecord::Proposal(proposal))]);
+    let id = hex::encode(proposal_tx.hash());
+    store_block(&node, 3, 3, vec![(0, GovernanceRecord::Vote(SignedVote::sign(&id, true, &key).unwrap()))]);
+    node.enable_chain_governance(node.config().read().unwrap().governance.rules.clone()).unwrap();
+    
+    let req = test::TestRequest::get().uri("/environmental/governance/proposals?status=active").to_request();
+    let proposals: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(proposals.as_array().unwrap().len(), 1);
+    assert_eq!(proposals[0]["id"], id.as_str());
+    assert_eq!(proposals[0]["snapshot_height"], 2);
+    
+    let req = test::TestRequest::get().uri(&format!("/environmental/governance/proposals/{}", id)).to_request();
+    let proposal: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(proposal["status"], "Active");
+    assert_eq!(proposal["tally"]["votes_for"], 700);
+    assert_eq!(proposal["tally"]["eligible_weight"], 700);
+    assert_eq!(proposal["tally"]["is_final"], false);
+    
+    let req = test::TestRequest::get().uri("/environmental/governance/proposals/unknown").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+}
+
+#[actix_rt::test]
+async fn test_governance_proposals_endpoint_without_governance() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let node = start_node(&db_dir, false).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(node))
+            .configure(environmental::configure)
+    ).await;
+    
+    let req = test::TestRequest::get().uri("/environmental/governance/proposals").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use btclib::types::block::Block;
+use btclib::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+use node::{BlockchainDB, Node, NodeConfig};
+
+/// Mine a block at `height` holding a coinbase and `transactions`
+///
+/// `0x03ffffff` decodes to a target nearly every hash meets.
+fn mine_block(height: u64, prev_hash: [u8; 32], transactions: Vec<Transaction>) -> Block {
+    let coinbase = Transaction::new(
+        1,
+        vec![TransactionInput::new_coinbase(height.to_le_bytes().to_vec())],
+        vec![TransactionOutput::new(50_000_000_000, vec![0xaa])],
+        0,
+    );
+    let mut block = Block::new_with_params(1, prev_hash, std::iter::once(coinbase).chain(transactions).collect(), 0x03ffffff);
+    block.set_height(height);
+    while !block.verify_proof_of_work() {
+        block.header.increment_nonce();
+    }
+    block
+}
+
+/// Swap refund spending `htlc` with the given sequence and lock time
+fn refund(htlc: [u8; 32], sequence: u32, lock_time: u32) -> Transaction {
+    Transaction::new(
+        2,
+        vec![TransactionInput::new(htlc, 0, Vec::new(), sequence)],
+        vec![TransactionOutput::new(49_000, vec![0xbb])],
+        lock_time,
+    )
+}
+
+#[tokio::test]
+async fn test_premature_swap_refunds_are_rejected() {
+    let db_dir = tempfile::tempdir().unwrap();
+    
+    // A stored main chain whose block 1 funds a swap HTLC
+    let funding = Transaction::new(
+        1,
+        vec![TransactionInput::new([7u8; 32], 0, Vec::new(), 0xffffffff)],
+        vec![TransactionOutput::new(50_000, vec![0xcc])],
+        0,
+    );
+    let htlc = funding.hash();
+    let block_1 = mine_block(1, [0u8; 32], vec![funding.clone()]);
+    {
+        let db = BlockchainDB::new(db_dir.path().join("db")).unwrap();
+        db.insert_block(&block_1).unwrap();
+        db.store_block_height_index(1, &block_1.hash()).unwrap();
+        db.set_height(1).unwrap();
+        db.index_transaction(&htlc, &block_1.hash()).unwrap();
+        db.store_utxo(&htlc, 0, &bincode::serialize(&funding.outputs()[0]).unwrap()).unwrap();
+    }
+    
+    let mut config = NodeConfig::default();
+    config.storage.db_path = db_dir.path().join("db");
+    config.node.enable_lightning = false;
+    let node = Arc::new(Node::new(config).await.unwrap());
+    let mempool = node.mempool();
+    
+    // A CLTV refund locked until height 800 is not final in block 2
+    let cltv_refund = refund(htlc, 0xFFFF_FFFE, 800);
+    let error = mempool.add_transaction(cltv_refund.clone(), 1).unwrap_err();
+    assert!(error.to_string().contains("lock time 800 not reached"), "{}", error);
+    let error = node.process_block(mine_block(2, block_1.hash(), vec![cltv_refund])).await.unwrap_err();
+    assert!(error.to_string().contains("lock time 800 not reached"), "{}", error);
+    
+    // A CSV refund 144 blocks after confirmation is still sequence locked
+    let csv_refund = refund(htlc, 144, 0);
+    let error = mempool.add_transaction(csv_refund.clone(), 1).unwrap_err();
+    assert!(error.to_string().contains("relative lock time"), "{}", error);
+    let error = node.process_block(mine_block(2, block_1.hash(), vec![csv_refund])).await.unwrap_err();
+    assert!(error.to_string().contains("relative lock time"), "{}", error);
+    
+    // One block after confirmation, a one-block relative lock has elapsed
+    mempool.add_transaction(refund(htlc, 1, 0), 1).unwrap();
+}
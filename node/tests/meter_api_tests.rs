@@ -0,0 +1,88 @@
+use actix_web::{test, web, App};
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use btclib::environmental::meter_telemetry::{MeterSigner, RegisteredMeter};
+use btclib::environmental::oracle::MeterReading;
+use node::api::routes::environmental;
+use node::{Node, NodeConfig};
+
+#[actix_rt::test]
+async fn test_configured_meter_submits_signed_readings() {
+    let dir = tempfile::tempdir().unwrap();
+    
+    // Register the meter through the [environmental] meters_path file
+    let signer = MeterSigner::generate_ed25519();
+    let meter = RegisteredMeter {
+        meter_id: "meter-1".to_string(),
+        miner_id: "miner-1".to_string(),
+        scheme: signer.scheme(),
+        public_key: signer.public_key(),
+        max_power_kw: 100.0,
+    };
+    let meters_path = dir.path().join("meters.json");
+    std::fs::write(&meters_path, serde_json::to_string(&vec![meter]).unwrap()).unwrap();
+    
+    let mut config = NodeConfig::default();
+    config.storage.db_path = dir.path().join("db");
+    config.node.enable_lightning = false;
+    config.environmental.meters_path = Some(meters_path);
+    let node = Arc::new(Node::new(config).await.unwrap());
+    
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(node))
+            .configure(environmental::configure)
+    ).await;
+    
+    // Two readings ten minutes apart: 10 kWh at 60 kW
+    let now = Utc::now();
+    let readings: Vec<Value> = [(now - Duration::minutes(20), 1_000.0), (now - Duration::minutes(10), 1_010.0)]
+        .into_iter()
+        .map(|(timestamp, energy_kwh)| {
+            let mut reading = MeterReading {
+                meter_id: "meter-1".to_string(),
+                timestamp,
+                energy_kwh,
+                power_kw: 60.0,
+                attestation: Vec::new(),
+            };
+            signer.sign(&mut reading).unwrap();
+            json!({
+                "timestamp": timestamp.to_rfc3339(),
+                "energy_kwh": energy_kwh,
+                "power_kw": 60.0,
+                "signature": hex::encode(reading.attestation),
+            })
+        })
+        .collect();
+    
+    let req = test::TestRequest::post()
+        .uri("/environmental/meters/readings")
+        .set_json(json!({ "meter_id": "meter-1", "readings": readings }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let receipt: Value = test::read_body_json(resp).await;
+    assert_eq!(receipt["miner_id"], "miner-1");
+    assert_eq!(receipt["accepted"], 2);
+    assert!((receipt["energy_kwh"].as_f64().unwrap() - 10.0).abs() < 1e-9);
+    
+    // A reading that does not match its signature is rejected
+    let mut forged = readings[1].clone();
+    forged["timestamp"] = json!((now - Duration::minutes(5)).to_rfc3339());
+    forged["energy_kwh"] = json!(1_020.0);
+    let req = test::TestRequest::post()
+        .uri("/environmental/meters/readings")
+        .set_json(json!({ "meter_id": "meter-1", "readings": [forged] }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+    
+    // Meters missing from the config are unknown
+    let req = test::TestRequest::post()
+        .uri("/environmental/meters/readings")
+        .set_json(json!({ "meter_id": "meter-2", "readings": [readings[0].clone()] }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+}
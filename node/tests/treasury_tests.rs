@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use btclib::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+use btclib::environmental::treasury_ledger::{TreasuryAuthorization, TreasuryCommittee};
+use btclib::types::block::Block;
+use btclib::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+use node::{Node, NodeConfig};
+
+/// Start a node enforcing treasury rules for `committee`
+async fn start_node(db_dir: &tempfile::TempDir, committee: &TreasuryCommittee) -> Arc<Node> {
+    let committee_path = db_dir.path().join("treasury_committee.json");
+    std::fs::write(&committee_path, serde_json::to_string(committee).unwrap()).unwrap();
+    
+    let mut config = NodeConfig::default();
+    config.storage.db_path = db_dir.path().join("db");
+    config.node.enable_lightning = false;
+    config.environmental.treasury_committee_path = Some(committee_path);
+    
+    Arc::new(Node::new(config).await.unwrap())
+}
+
+/// Mine a block at height 1 whose coinbase pays the treasury its allocation
+///
+/// `0x03ffffff` decodes to a target nearly every hash meets.
+fn mine_block(treasury_script: &[u8], spends: Vec<Transaction>) -> Block {
+    let coinbase = Transaction::new(
+        1,
+        vec![TransactionInput::new_coinbase(vec![1])],
+        vec![
+            TransactionOutput::new(49_000_000_000, vec![0xaa]),
+            TransactionOutput::new(1_000_000_000, treasury_script.to_vec()),
+        ],
+        0,
+    );
+    let mut transactions = vec![coinbase];
+    transactions.extend(spends);
+    
+    let mut block = Block::new_with_params(1, [0u8; 32], transactions, 0x03ffffff);
+    block.set_height(1);
+    while !block.verify_proof_of_work() {
+        block.header.increment_nonce();
+    }
+    block
+}
+
+#[tokio::test]
+async fn test_treasury_spends_are_checked_against_the_utxo_set() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let key = QuantumKeyPair::generate(QuantumParameters::with_security_level(QuantumScheme::Dilithium, 2)).unwrap();
+    let committee = TreasuryCommittee {
+        threshold: 1,
+        security_level: 2,
+        members: vec![key.public_key.clone()],
+    };
+    let script = committee.script();
+    let node = start_node(&db_dir, &committee).await;
+    
+    // A treasury output known only to the UTXO set
+    let treasury_outpoint = [5u8; 32];
+    let storage = node.storage();
+    storage.store_utxo(&treasury_outpoint, 0, &bincode::serialize(&TransactionOutput::new(5_000, script.clone())).unwrap()).unwrap();
+    
+    // Spending it without committee signatures is rejected
+    let unsigned = Transaction::new(
+        1,
+        vec![TransactionInput::new(treasury_outpoint, 0, TreasuryAuthorization::new("proposal-1").to_script(), 0xffffffff)],
+        vec![TransactionOutput::new(5_000, vec![0xbb])],
+        0,
+    );
+    let error = node.process_block(mine_block(&script, vec![unsigned])).await.unwrap_err();
+    assert!(error.to_string().contains("Unauthorized treasury spend"), "{}", error);
+    
+    // So is a block spending an output the node cannot resolve
+    let unknown = Transaction::new(
+        1,
+        vec![TransactionInput::new([6u8; 32], 0, Vec::new(), 0xffffffff)],
+        vec![TransactionOutput::new(5_000, vec![0xbb])],
+        0,
+    );
+    let error = node.process_block(mine_block(&script, vec![unknown])).await.unwrap_err();
+    assert!(error.to_string().contains("unknown output"), "{}", error);
+}
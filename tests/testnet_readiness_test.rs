@@ -138,6 +138,7 @@ mod testnet_readiness_tests {
             efficiency_score: 1.0,
             verified: true,
             rec_coverage: 1.0,
+            curtailment_score: 0.0,
         };
         
         let reward_with_bonus = calculate_mining_reward(0, &env_profile);